@@ -0,0 +1,54 @@
+use crate::bn254::{Fq, Fr};
+use ark_ec::{
+    models::short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+    ModelParameters, SWModelParameters,
+};
+use ark_ff::{field_new, Zero};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrumpkinParameters;
+
+impl ModelParameters for GrumpkinParameters {
+    type BaseField = Fr;
+    type ScalarField = Fq;
+}
+
+pub type Grumpkin = GroupAffine<GrumpkinParameters>;
+pub type ProjectiveGrumpkin = GroupProjective<GrumpkinParameters>;
+
+impl SWModelParameters for GrumpkinParameters {
+    /// COEFF_A = 0
+    const COEFF_A: Fr = field_new!(Fr, "0");
+
+    /// COEFF_B = -17
+    const COEFF_B: Fr = field_new!(
+        Fr,
+        "21888242871839275222246405745257275088548364400416034343698204186575808495600"
+    );
+
+    /// COFACTOR = 1
+    const COFACTOR: &'static [u64] = &[0x1];
+
+    /// COFACTOR_INV = 1
+    const COFACTOR_INV: Fq = field_new!(Fq, "1");
+
+    /// AFFINE_GENERATOR_COEFFS = (G_GENERATOR_X, G_GENERATOR_Y)
+    const AFFINE_GENERATOR_COEFFS: (Self::BaseField, Self::BaseField) =
+        (G_GENERATOR_X, G_GENERATOR_Y);
+
+    #[inline(always)]
+    fn mul_by_a(_: &Self::BaseField) -> Self::BaseField {
+        Self::BaseField::zero()
+    }
+}
+
+/// G_GENERATOR_X =
+/// 1
+pub const G_GENERATOR_X: Fr = field_new!(Fr, "1");
+
+/// G_GENERATOR_Y =
+/// 17631683881184975370165255887551781615748388533673675138860
+pub const G_GENERATOR_Y: Fr = field_new!(
+    Fr,
+    "17631683881184975370165255887551781615748388533673675138860"
+);
@@ -0,0 +1,14 @@
+//! The BN254 curve and Grumpkin, which form a cycle: the base field of each
+//! curve is the scalar field of the other one.
+//!
+//! BN254 is the pairing-friendly curve with precompiles on Ethereum, and is
+//! re-exported from [ark_bn254]. Grumpkin, defined in [grumpkin], is the curve
+//! `y^2 = x^3 - 17` over the scalar field of BN254, of prime order the base
+//! field of BN254.
+
+pub mod grumpkin;
+
+pub use ark_bn254::{
+    g1::Parameters as Bn254Parameters, Fq, Fr, G1Affine as Bn254, G1Projective as ProjectiveBn254,
+};
+pub use grumpkin::{Grumpkin, GrumpkinParameters, ProjectiveGrumpkin};
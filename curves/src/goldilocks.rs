@@ -0,0 +1,593 @@
+//! The Goldilocks field, of prime order `p = 2^64 - 2^32 + 1`.
+//!
+//! Its elements fit in a machine word, and the multiplication reduces a 128
+//! bits product with a few additions thanks to the shape of `p`
+//! (`2^64 = 2^32 - 1 mod p` and `2^96 = -1 mod p`), which makes the field much
+//! faster than the 255 bits Pasta fields for the witness generation. It is
+//! meant to experiment with small-field arithmetizations, e.g. for the zkVM:
+//! there is no curve over it, and a field of 64 bits gives no security to a
+//! proof system sampling its challenges in it.
+//!
+//! The field cannot use the Montgomery arithmetic of [ark_ff], which needs a
+//! spare bit in the representation, so [Goldilocks] implements the traits of
+//! [ark_ff] on the canonical representation of its elements. The constants of
+//! [GoldilocksParameters] are given in this canonical form, except the ones
+//! related to the Montgomery form ([FpParameters::R], [FpParameters::R2] and
+//! [FpParameters::INV]) which are only informative.
+
+use ark_ff::{
+    biginteger::BigInteger64 as BigInteger,
+    bytes::{FromBytes, ToBytes},
+    FftField, FftParameters, Field, FpParameters, LegendreSymbol, One, PrimeField, SquareRootField,
+    Zero,
+};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalDeserializeWithFlags, CanonicalSerialize,
+    CanonicalSerializeWithFlags, EmptyFlags, Flags, SerializationError,
+};
+use ark_std::{
+    fmt,
+    io::{Error, ErrorKind, Read, Result as IoResult, Write},
+    iter::{Product, Sum},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    rand::{
+        distributions::{Distribution, Standard},
+        Rng,
+    },
+    str::FromStr,
+};
+use num_bigint::BigUint;
+use zeroize::Zeroize;
+
+/// The modulus `2^64 - 2^32 + 1`
+pub const MODULUS: u64 = 0xffff_ffff_0000_0001;
+
+/// `2^64 mod p = 2^32 - 1`
+const EPSILON: u64 = 0xffff_ffff;
+
+/// A generator of the multiplicative group
+const GENERATOR: u64 = 7;
+
+/// `GENERATOR^T`, a root of unity of order `2^32`
+const TWO_ADIC_ROOT_OF_UNITY: u64 = 0x1856_29dc_da58_878c;
+
+/// An element of the Goldilocks field, in canonical form
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Goldilocks(u64);
+
+pub struct GoldilocksParameters;
+
+impl FftParameters for GoldilocksParameters {
+    type BigInt = BigInteger;
+
+    const TWO_ADICITY: u32 = 32;
+
+    const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([TWO_ADIC_ROOT_OF_UNITY]);
+}
+
+impl FpParameters for GoldilocksParameters {
+    // 18446744069414584321
+    const MODULUS: BigInteger = BigInteger([MODULUS]);
+
+    const R: BigInteger = BigInteger([EPSILON]);
+
+    const R2: BigInteger = BigInteger([0xffff_fffe_0000_0001]);
+
+    const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([0x7fff_ffff_8000_0000]);
+
+    // T and T_MINUS_ONE_DIV_TWO, where MODULUS - 1 = 2^S * T
+    const T: BigInteger = BigInteger([0xffff_ffff]);
+
+    const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([0x7fff_ffff]);
+
+    // GENERATOR = 7
+    const GENERATOR: BigInteger = BigInteger([GENERATOR]);
+
+    const MODULUS_BITS: u32 = 64;
+
+    const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+    const REPR_SHAVE_BITS: u32 = 0;
+
+    // -(MODULUS^{-1} mod 2^64) mod 2^64
+    const INV: u64 = 0xffff_fffe_ffff_ffff;
+}
+
+impl Goldilocks {
+    /// Creates an element from its canonical representation
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `x` is not less than the modulus.
+    pub const fn new(x: u64) -> Self {
+        assert!(x < MODULUS, "the value is not less than the modulus");
+        Goldilocks(x)
+    }
+
+    /// Returns the canonical representation of the element
+    pub const fn to_canonical_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Reduces a 128 bits integer modulo `p`
+    fn reduce128(x: u128) -> Self {
+        let (lo, hi) = (x as u64, (x >> 64) as u64);
+        let (hi_hi, hi_lo) = (hi >> 32, hi & EPSILON);
+        // lo - hi_hi, as 2^96 = -1
+        let (mut t0, borrow) = lo.overflowing_sub(hi_hi);
+        if borrow {
+            t0 -= EPSILON;
+        }
+        // + hi_lo * (2^32 - 1), as 2^64 = 2^32 - 1
+        let (mut t1, carry) = t0.overflowing_add(hi_lo * EPSILON);
+        if carry {
+            t1 += EPSILON;
+        }
+        Self::from(t1)
+    }
+}
+
+impl Zero for Goldilocks {
+    fn zero() -> Self {
+        Goldilocks(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Goldilocks {
+    fn one() -> Self {
+        Goldilocks(1)
+    }
+
+    fn is_one(&self) -> bool {
+        self.0 == 1
+    }
+}
+
+impl Add for Goldilocks {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let (sum, carry) = self.0.overflowing_add(other.0);
+        let (diff, borrow) = sum.overflowing_sub(MODULUS);
+        if carry || !borrow {
+            Goldilocks(diff)
+        } else {
+            Goldilocks(sum)
+        }
+    }
+}
+
+impl Sub for Goldilocks {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        let (diff, borrow) = self.0.overflowing_sub(other.0);
+        if borrow {
+            Goldilocks(diff.wrapping_add(MODULUS))
+        } else {
+            Goldilocks(diff)
+        }
+    }
+}
+
+impl Mul for Goldilocks {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::reduce128(u128::from(self.0) * u128::from(other.0))
+    }
+}
+
+impl Div for Goldilocks {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Self) -> Self {
+        self * other.inverse().expect("division by zero")
+    }
+}
+
+impl Neg for Goldilocks {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::zero() - self
+    }
+}
+
+/// Implements the variants of a binary operation from the one on values
+macro_rules! impl_ops {
+    ($($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident;)*) => {
+        $(
+            impl<'a> $Op<&'a Goldilocks> for Goldilocks {
+                type Output = Goldilocks;
+
+                fn $op(self, other: &Goldilocks) -> Goldilocks {
+                    $Op::$op(self, *other)
+                }
+            }
+
+            impl $OpAssign for Goldilocks {
+                fn $op_assign(&mut self, other: Goldilocks) {
+                    *self = $Op::$op(*self, other);
+                }
+            }
+
+            impl<'a> $OpAssign<&'a Goldilocks> for Goldilocks {
+                fn $op_assign(&mut self, other: &Goldilocks) {
+                    *self = $Op::$op(*self, *other);
+                }
+            }
+        )*
+    };
+}
+
+impl_ops! {
+    Add, add, AddAssign, add_assign;
+    Sub, sub, SubAssign, sub_assign;
+    Mul, mul, MulAssign, mul_assign;
+    Div, div, DivAssign, div_assign;
+}
+
+impl Sum for Goldilocks {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Goldilocks> for Goldilocks {
+    fn sum<I: Iterator<Item = &'a Goldilocks>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl Product for Goldilocks {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), Mul::mul)
+    }
+}
+
+impl<'a> Product<&'a Goldilocks> for Goldilocks {
+    fn product<I: Iterator<Item = &'a Goldilocks>>(iter: I) -> Self {
+        iter.fold(Self::one(), Mul::mul)
+    }
+}
+
+impl From<u128> for Goldilocks {
+    fn from(x: u128) -> Self {
+        Self::reduce128(x)
+    }
+}
+
+impl From<u64> for Goldilocks {
+    fn from(x: u64) -> Self {
+        if x >= MODULUS {
+            Goldilocks(x - MODULUS)
+        } else {
+            Goldilocks(x)
+        }
+    }
+}
+
+impl From<u32> for Goldilocks {
+    fn from(x: u32) -> Self {
+        Goldilocks(x.into())
+    }
+}
+
+impl From<u16> for Goldilocks {
+    fn from(x: u16) -> Self {
+        Goldilocks(x.into())
+    }
+}
+
+impl From<u8> for Goldilocks {
+    fn from(x: u8) -> Self {
+        Goldilocks(x.into())
+    }
+}
+
+impl From<bool> for Goldilocks {
+    fn from(x: bool) -> Self {
+        Goldilocks(x.into())
+    }
+}
+
+impl From<BigInteger> for Goldilocks {
+    fn from(x: BigInteger) -> Self {
+        Self::from(x.0[0])
+    }
+}
+
+impl From<Goldilocks> for BigInteger {
+    fn from(x: Goldilocks) -> Self {
+        BigInteger([x.0])
+    }
+}
+
+impl From<BigUint> for Goldilocks {
+    /// Reduces an arbitrary integer modulo `p`
+    fn from(x: BigUint) -> Self {
+        let reduced = x % MODULUS;
+        Goldilocks(reduced.to_u64_digits().first().copied().unwrap_or(0))
+    }
+}
+
+impl From<Goldilocks> for BigUint {
+    fn from(x: Goldilocks) -> Self {
+        BigUint::from(x.0)
+    }
+}
+
+impl FromStr for Goldilocks {
+    type Err = ();
+
+    /// Parses a decimal integer, reduced modulo `p`. As for the other prime
+    /// fields, leading zeros are rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || (s.len() > 1 && s.starts_with('0')) {
+            return Err(());
+        }
+        s.chars().try_fold(Self::zero(), |x, c| {
+            let digit = c.to_digit(10).ok_or(())?;
+            Ok(x * Self::from(10u8) + Self::from(digit))
+        })
+    }
+}
+
+impl fmt::Display for Goldilocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Zeroize for Goldilocks {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Distribution<Goldilocks> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Goldilocks {
+        loop {
+            let x: u64 = rng.gen();
+            if x < MODULUS {
+                return Goldilocks(x);
+            }
+        }
+    }
+}
+
+impl ToBytes for Goldilocks {
+    fn write<W: Write>(&self, writer: W) -> IoResult<()> {
+        self.0.write(writer)
+    }
+}
+
+impl FromBytes for Goldilocks {
+    fn read<R: Read>(reader: R) -> IoResult<Self> {
+        let x = u64::read(reader)?;
+        if x < MODULUS {
+            Ok(Goldilocks(x))
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "the value is not less than the modulus",
+            ))
+        }
+    }
+}
+
+/// The number of bytes of an element serialized with flags of `flag_bits`
+/// bits: the flags take an additional byte, as the element has no spare bit
+const fn serialized_size(flag_bits: usize) -> usize {
+    (64 + flag_bits + 7) / 8
+}
+
+impl CanonicalSerializeWithFlags for Goldilocks {
+    fn serialize_with_flags<W: Write, F: Flags>(
+        &self,
+        mut writer: W,
+        flags: F,
+    ) -> Result<(), SerializationError> {
+        if F::BIT_SIZE > 8 {
+            return Err(SerializationError::NotEnoughSpace);
+        }
+        let mut bytes = [0u8; 9];
+        bytes[..8].copy_from_slice(&self.0.to_le_bytes());
+        bytes[8] = flags.u8_bitmask();
+        writer.write_all(&bytes[..serialized_size(F::BIT_SIZE)])?;
+        Ok(())
+    }
+
+    fn serialized_size_with_flags<F: Flags>(&self) -> usize {
+        serialized_size(F::BIT_SIZE)
+    }
+}
+
+impl CanonicalSerialize for Goldilocks {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        self.serialize_with_flags(writer, EmptyFlags)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.serialized_size_with_flags::<EmptyFlags>()
+    }
+}
+
+impl CanonicalDeserializeWithFlags for Goldilocks {
+    fn deserialize_with_flags<R: Read, F: Flags>(
+        mut reader: R,
+    ) -> Result<(Self, F), SerializationError> {
+        if F::BIT_SIZE > 8 {
+            return Err(SerializationError::NotEnoughSpace);
+        }
+        let mut bytes = [0u8; 9];
+        reader.read_exact(&mut bytes[..serialized_size(F::BIT_SIZE)])?;
+        let flags =
+            F::from_u8_remove_flags(&mut bytes[8]).ok_or(SerializationError::UnexpectedFlags)?;
+        let mut x = [0u8; 8];
+        x.copy_from_slice(&bytes[..8]);
+        let x = u64::from_le_bytes(x);
+        if bytes[8] != 0 || x >= MODULUS {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok((Goldilocks(x), flags))
+    }
+}
+
+impl CanonicalDeserialize for Goldilocks {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::deserialize_with_flags::<R, EmptyFlags>(reader).map(|(x, _)| x)
+    }
+}
+
+impl Field for Goldilocks {
+    type BasePrimeField = Self;
+
+    fn extension_degree() -> u64 {
+        1
+    }
+
+    fn from_base_prime_field_elems(elems: &[Self::BasePrimeField]) -> Option<Self> {
+        match elems {
+            [x] => Some(*x),
+            _ => None,
+        }
+    }
+
+    fn double(&self) -> Self {
+        *self + *self
+    }
+
+    fn double_in_place(&mut self) -> &mut Self {
+        *self = self.double();
+        self
+    }
+
+    fn characteristic() -> &'static [u64] {
+        &[MODULUS]
+    }
+
+    fn from_random_bytes_with_flags<F: Flags>(bytes: &[u8]) -> Option<(Self, F)> {
+        if F::BIT_SIZE > 8 {
+            return None;
+        }
+        let mut buffer = [0u8; 9];
+        buffer.iter_mut().zip(bytes).for_each(|(b, x)| *b = *x);
+        // the flags are the highest bits of the byte following the element
+        let flags_mask = u8::MAX.checked_shl(8 - F::BIT_SIZE as u32).unwrap_or(0);
+        let flags = F::from_u8(buffer[8] & flags_mask)?;
+        let mut x = [0u8; 8];
+        x.copy_from_slice(&buffer[..8]);
+        let x = u64::from_le_bytes(x);
+        (x < MODULUS).then_some((Goldilocks(x), flags))
+    }
+
+    fn square(&self) -> Self {
+        *self * *self
+    }
+
+    fn square_in_place(&mut self) -> &mut Self {
+        *self = self.square();
+        self
+    }
+
+    fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self.pow([MODULUS - 2]))
+        }
+    }
+
+    fn inverse_in_place(&mut self) -> Option<&mut Self> {
+        *self = self.inverse()?;
+        Some(self)
+    }
+
+    fn frobenius_map(&mut self, _power: usize) {}
+}
+
+impl FftField for Goldilocks {
+    type FftParams = GoldilocksParameters;
+
+    fn two_adic_root_of_unity() -> Self {
+        Goldilocks(TWO_ADIC_ROOT_OF_UNITY)
+    }
+
+    fn large_subgroup_root_of_unity() -> Option<Self> {
+        None
+    }
+
+    fn multiplicative_generator() -> Self {
+        Goldilocks(GENERATOR)
+    }
+}
+
+impl PrimeField for Goldilocks {
+    type Params = GoldilocksParameters;
+    type BigInt = BigInteger;
+
+    fn from_repr(repr: BigInteger) -> Option<Self> {
+        (repr.0[0] < MODULUS).then_some(Goldilocks(repr.0[0]))
+    }
+
+    fn into_repr(&self) -> BigInteger {
+        BigInteger([self.0])
+    }
+}
+
+impl SquareRootField for Goldilocks {
+    fn legendre(&self) -> LegendreSymbol {
+        let s = self.pow(GoldilocksParameters::MODULUS_MINUS_ONE_DIV_TWO);
+        if s.is_zero() {
+            LegendreSymbol::Zero
+        } else if s.is_one() {
+            LegendreSymbol::QuadraticResidue
+        } else {
+            LegendreSymbol::QuadraticNonResidue
+        }
+    }
+
+    /// Tonelli-Shanks, as for the fields of [ark_ff]
+    fn sqrt(&self) -> Option<Self> {
+        if self.is_zero() {
+            return Some(Self::zero());
+        }
+        if self.legendre().is_qnr() {
+            return None;
+        }
+        let mut z = Self::two_adic_root_of_unity();
+        let mut w = self.pow(GoldilocksParameters::T_MINUS_ONE_DIV_TWO);
+        let mut x = w * self;
+        let mut b = x * w;
+        let mut v = GoldilocksParameters::TWO_ADICITY;
+        while !b.is_one() {
+            let mut k = 0;
+            let mut b2k = b;
+            while !b2k.is_one() {
+                b2k.square_in_place();
+                k += 1;
+            }
+            w = z;
+            for _ in 1..(v - k) {
+                w.square_in_place();
+            }
+            z = w.square();
+            b *= z;
+            x *= w;
+            v = k;
+        }
+        Some(x)
+    }
+
+    fn sqrt_in_place(&mut self) -> Option<&mut Self> {
+        *self = self.sqrt()?;
+        Some(self)
+    }
+}
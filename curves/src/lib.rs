@@ -1 +1,3 @@
+pub mod bn254;
+pub mod goldilocks;
 pub mod pasta;
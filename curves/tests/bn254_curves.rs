@@ -0,0 +1,27 @@
+use ark_algebra_test_templates::{curves::*, groups::*};
+use ark_ec::AffineCurve;
+use ark_std::test_rng;
+use mina_curves::bn254::grumpkin;
+use rand::Rng;
+
+#[test]
+fn test_grumpkin_projective_curve() {
+    curve_tests::<grumpkin::ProjectiveGrumpkin>();
+
+    sw_tests::<grumpkin::GrumpkinParameters>();
+}
+
+#[test]
+fn test_grumpkin_projective_group() {
+    let mut rng = test_rng();
+    let a: grumpkin::ProjectiveGrumpkin = rng.gen();
+    let b: grumpkin::ProjectiveGrumpkin = rng.gen();
+    group_test(a, b);
+}
+
+#[test]
+fn test_grumpkin_generator() {
+    let generator = grumpkin::Grumpkin::prime_subgroup_generator();
+    assert!(generator.is_on_curve());
+    assert!(generator.is_in_correct_subgroup_assuming_on_curve());
+}
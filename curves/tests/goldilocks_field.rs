@@ -0,0 +1,58 @@
+use ark_algebra_test_templates::fields::{field_test, primefield_test, sqrt_field_test};
+use ark_ff::{FftField, Field, One, PrimeField, SquareRootField, Zero};
+use ark_std::test_rng;
+use mina_curves::goldilocks::{Goldilocks, MODULUS};
+use num_bigint::BigUint;
+use rand::Rng;
+use std::str::FromStr;
+
+#[test]
+fn test_goldilocks() {
+    let mut rng = test_rng();
+    let a: Goldilocks = rng.gen();
+    let b: Goldilocks = rng.gen();
+    field_test(a, b);
+    sqrt_field_test(a);
+    primefield_test::<Goldilocks>();
+}
+
+#[test]
+fn test_goldilocks_reduction() {
+    let minus_one = Goldilocks::new(MODULUS - 1);
+    assert_eq!(minus_one + Goldilocks::one(), Goldilocks::zero());
+    assert_eq!(minus_one * minus_one, Goldilocks::one());
+    assert_eq!(Goldilocks::zero() - Goldilocks::one(), minus_one);
+    assert_eq!(minus_one.double(), minus_one - Goldilocks::one());
+    // 2^64 = 2^32 - 1 and 2^96 = -1
+    assert_eq!(
+        Goldilocks::from(1u128 << 64),
+        Goldilocks::from(0xffff_ffffu64)
+    );
+    assert_eq!(Goldilocks::from(1u128 << 96), minus_one);
+    assert_eq!(Goldilocks::from(u128::MAX).to_canonical_u64(), {
+        let p = u128::from(MODULUS);
+        (u128::MAX % p) as u64
+    });
+    assert_eq!(
+        Goldilocks::from(u64::MAX).to_canonical_u64(),
+        u64::MAX - MODULUS
+    );
+    assert_eq!(
+        Goldilocks::from_str("18446744069414584322"),
+        Ok(Goldilocks::one())
+    );
+    // BigUint conversions reduce modulo p and round-trip canonical values
+    let big = (BigUint::from(MODULUS) << 70) + BigUint::from(5u8);
+    assert_eq!(Goldilocks::from(big), Goldilocks::from(5u8));
+    assert_eq!(BigUint::from(minus_one), BigUint::from(MODULUS - 1));
+    assert_eq!(Goldilocks::from(BigUint::from(minus_one)), minus_one);
+}
+
+#[test]
+fn test_goldilocks_roots_of_unity() {
+    let root = Goldilocks::two_adic_root_of_unity();
+    assert_eq!(root.pow([1u64 << 32]), Goldilocks::one());
+    assert_eq!(root.pow([1u64 << 31]), -Goldilocks::one());
+    assert!(Goldilocks::multiplicative_generator().legendre().is_qnr());
+    assert_eq!(Goldilocks::size_in_bits(), 64);
+}
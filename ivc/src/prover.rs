@@ -196,10 +196,13 @@ where
                 .collect(),
         );
 
+    // The columns are committed in the Lagrange basis, directly from their
+    // evaluations, the interpolation being only required for the quotient.
     let fixed_selectors_comms: Box<[PolyComm<G>; N_FSEL]> = {
-        let comm = |poly: &DensePolynomial<Fp>| srs.commit_non_hiding(poly, 1);
+        let comm =
+            |evals: &Evaluations<Fp, R2D<Fp>>| srs.commit_evaluations_non_hiding(domain.d1, evals);
         o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_polys
+            fixed_selectors_evals_d1
                 .as_ref()
                 .into_par_iter()
                 .map(comm)
@@ -223,32 +226,34 @@ where
         acc.try_into().unwrap()
     };
 
-    let witness_polys: Witness<N_WIT_QUAD, DensePolynomial<Fp>> = {
-        witness_evals_d1
-            .into_par_iter()
-            .map(|e| e.interpolate())
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
-    };
-
     let witness_comms: Witness<N_WIT_QUAD, PolyComm<G>> = {
         let blinders = PolyComm {
             elems: vec![Fp::one()],
         };
         let comm = {
-            |poly: &DensePolynomial<Fp>| {
+            |evals: &Evaluations<Fp, R2D<Fp>>| {
                 // In case the column polynomial is all zeroes, we want to mask the commitment
-                let comm = srs.commit_custom(poly, 1, &blinders).unwrap();
+                let comm = srs
+                    .commit_evaluations_custom(domain.d1, evals, &blinders)
+                    .unwrap();
                 comm.commitment
             }
         };
-        (&witness_polys)
+        (&witness_evals_d1)
             .into_par_iter()
             .map(comm)
             .collect::<Witness<N_WIT_QUAD, PolyComm<G>>>()
     };
 
+    let witness_polys: Witness<N_WIT_QUAD, DensePolynomial<Fp>> = {
+        witness_evals_d1
+            .into_par_iter()
+            .map(|e| e.interpolate())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    };
+
     // Do not use parallelism
     (&witness_comms)
         .into_iter()
@@ -4,10 +4,7 @@ use crate::{
     prover::Proof,
 };
 use ark_ff::Field;
-use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, Evaluations, Polynomial,
-    Radix2EvaluationDomain as R2D,
-};
+use ark_poly::{EvaluationDomain, Evaluations, Polynomial, Radix2EvaluationDomain as R2D};
 use folding::{
     eval_leaf::EvalLeaf, instance_witness::ExtendedWitness, FoldingCompatibleExpr, FoldingConfig,
 };
@@ -68,19 +65,13 @@ pub fn verify<
 
     let fixed_selectors_evals_d1: Box<[Evaluations<Fp, R2D<Fp>>; N_FSEL]> = fixed_selectors;
 
-    let fixed_selectors_polys: Box<[DensePolynomial<Fp>; N_FSEL]> = {
-        o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_evals_d1
-                .into_par_iter()
-                .map(|evals| evals.interpolate())
-                .collect(),
-        )
-    };
-
+    // The commitments are computed in the Lagrange basis, without
+    // interpolating the columns.
     let fixed_selectors_comms: Box<[PolyComm<G>; N_FSEL]> = {
-        let comm = |poly: &DensePolynomial<Fp>| srs.commit_non_hiding(poly, 1);
+        let comm =
+            |evals: &Evaluations<Fp, R2D<Fp>>| srs.commit_evaluations_non_hiding(domain.d1, evals);
         o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_polys
+            fixed_selectors_evals_d1
                 .as_ref()
                 .into_par_iter()
                 .map(comm)
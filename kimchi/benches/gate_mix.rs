@@ -0,0 +1,62 @@
+use std::array;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode};
+use kimchi::{
+    bench::{BenchmarkCtx, GateMix},
+    circuits::polynomials::keccak::{constants::KECCAK_COLS, witness::extend_keccak_witness_bytes},
+};
+use mina_curves::pasta::Fp;
+
+const SIZES: [u32; 2] = [14, 16];
+
+pub fn bench_gate_mix_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Gate mix proof");
+    group.sample_size(10).sampling_mode(SamplingMode::Flat); // for slow benchmarks
+
+    for mix in GateMix::ALL {
+        for size in SIZES {
+            let ctx = BenchmarkCtx::new_with_mix(size, mix);
+
+            group.bench_function(
+                format!(
+                    "{} proof creation (SRS size 2^{{{}}}, {} gates)",
+                    mix.name(),
+                    ctx.srs_size(),
+                    ctx.num_gates
+                ),
+                |b| b.iter(|| black_box(ctx.create_proof())),
+            );
+
+            let proof_and_public = ctx.create_proof();
+            group.bench_function(
+                format!(
+                    "{} proof verification (SRS size 2^{{{}}}, {} gates)",
+                    mix.name(),
+                    ctx.srs_size(),
+                    ctx.num_gates
+                ),
+                |b| b.iter(|| ctx.batch_verification(black_box(&vec![proof_and_public.clone()]))),
+            );
+        }
+    }
+}
+
+pub fn bench_keccak_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Keccak witness");
+    group.sample_size(10);
+
+    // one and four blocks of the sponge
+    for len in [100, 500] {
+        let message: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        group.bench_function(format!("keccak witness ({len} bytes)"), |b| {
+            b.iter(|| {
+                let mut witness: [Vec<Fp>; KECCAK_COLS] = array::from_fn(|_| vec![]);
+                extend_keccak_witness_bytes(&mut witness, black_box(&message));
+                witness
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_gate_mix_proof, bench_keccak_witness);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Records the proof sizes and the proving and verification times of the gate mixes as
+//! a JSON report, and compares them with the ones of a baseline report.
+//!
+//! The report is written to the path of `BENCH_REPORT` (`bench-report.json` by
+//! default). When `BENCH_BASELINE` is the path of a previous report, the benchmark fails
+//! if a proof got bigger or if a time increased by more than `BENCH_TOLERANCE` (0.1, i.e.
+//! 10%, by default).
+
+use std::{env, fs, process};
+
+use kimchi::bench::{regressions, BenchRecord, BenchmarkCtx, GateMix};
+
+const SIZES: [u32; 2] = [14, 16];
+const ITERATIONS: u32 = 5;
+
+fn main() {
+    let mut records = vec![];
+    for mix in GateMix::ALL {
+        for size in SIZES {
+            let ctx = BenchmarkCtx::new_with_mix(size, mix);
+            let record = ctx.record(format!("{} (SRS size 2^{size})", mix.name()), ITERATIONS);
+            println!(
+                "{}: {} bytes, proved in {:.2} ms, verified in {:.2} ms",
+                record.name, record.proof_size, record.prover_ms, record.verifier_ms
+            );
+            records.push(record);
+        }
+    }
+
+    let report = env::var("BENCH_REPORT").unwrap_or_else(|_| "bench-report.json".to_string());
+    let json = serde_json::to_string_pretty(&records).expect("the records can be serialized");
+    fs::write(&report, json).expect("the report can be written");
+    println!("report written to {report}");
+
+    if let Ok(baseline) = env::var("BENCH_BASELINE") {
+        let baseline = fs::read_to_string(&baseline).expect("the baseline can be read");
+        let baseline: Vec<BenchRecord> =
+            serde_json::from_str(&baseline).expect("the baseline is a report");
+        let tolerance = env::var("BENCH_TOLERANCE")
+            .map(|tolerance| tolerance.parse().expect("the tolerance is a number"))
+            .unwrap_or(0.1);
+
+        let found = regressions(&baseline, &records, tolerance);
+        for regression in &found {
+            eprintln!("regression: {regression}");
+        }
+        if !found.is_empty() {
+            process::exit(1);
+        }
+    }
+}
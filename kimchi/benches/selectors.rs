@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use kimchi::circuits::{
+    constraints::selector_polynomial,
+    domains::EvaluationDomains,
+    gate::{CircuitGate, GateType},
+    wires::Wire,
+};
+use mina_curves::pasta::Fp;
+
+const SIZES: [usize; 2] = [14, 16];
+
+// A circuit of zero gates, except for `gates` Poseidon gates
+fn create_gates(n: usize, gates: usize) -> Vec<CircuitGate<Fp>> {
+    (0..n)
+        .map(|row| {
+            let typ = if row < gates {
+                GateType::Poseidon
+            } else {
+                GateType::Zero
+            };
+            CircuitGate::new(typ, Wire::for_row(row), vec![])
+        })
+        .collect()
+}
+
+/// Compares the evaluations of the sparse selectors, of at most `log2(n)`
+/// gates, with the interpolation of the dense ones
+pub fn bench_selector_polynomial(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Selector polynomial");
+    group.sample_size(10);
+
+    for size in SIZES {
+        let n = 1 << size;
+        let domain = EvaluationDomains::<Fp>::create(n).unwrap();
+        // one gate, the most gates of a sparse selector, and a dense selector
+        for gates in [1, size, size + 1] {
+            let circuit = create_gates(n, gates);
+            group.bench_function(
+                format!("selector of {gates} gates over d8 (domain size 2^{size})"),
+                |b| {
+                    b.iter(|| {
+                        selector_polynomial(
+                            GateType::Poseidon,
+                            black_box(&circuit),
+                            &domain,
+                            &domain.d8,
+                            false,
+                        )
+                    })
+                },
+            );
+        }
+    }
+}
+
+criterion_group!(benches, bench_selector_polynomial);
+criterion_main!(benches);
@@ -323,8 +323,14 @@ mod tests {
     fn get_alphas_for_spec() {
         let gates = vec![CircuitGate::<Fp>::zero(Wire::for_row(0)); 2];
         let index = new_index_for_test::<Vesta>(gates, 0);
-        let (_linearization, powers_of_alpha) =
-            expr_linearization::<Fp>(Some(&index.cs.feature_flags), true);
+        let (_linearization, powers_of_alpha) = expr_linearization::<Fp>(
+            Some(&index.cs.feature_flags),
+            true,
+            &index.cs.base_gates,
+            None,
+            index.cs.lookup_argument(),
+            index.cs.max_lookups_per_row(),
+        );
         // make sure this is present in the specification
         let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
         let spec_path = Path::new(&manifest_dir)
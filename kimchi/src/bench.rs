@@ -1,18 +1,24 @@
-use std::array;
+use std::{array, fmt, time::Instant};
 
 use groupmap::{BWParameters, GroupMap};
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
+    pasta::fp_kimchi,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
 use o1_utils::math;
 use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     circuits::{
         gate::CircuitGate,
-        polynomials::generic::GenericGateSpec,
+        polynomials::{
+            generic::GenericGateSpec,
+            poseidon::{generate_chain_witness, POS_ROWS_PER_HASH},
+            range_check,
+        },
         wires::{Wire, COLUMNS},
     },
     proof::ProverProof,
@@ -25,11 +31,212 @@ type SpongeParams = PlonkSpongeConstantsKimchi;
 type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
 type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
 
+/// Number of permutations of the Poseidon chains of [GateMix::Poseidon]
+const POSEIDON_CHAIN: usize = 8;
+
+/// Number of rows of a multi-range check
+const RANGE_CHECK_ROWS: usize = 4;
+
+/// The gates of the circuit of a benchmark
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateMix {
+    /// Generic gates
+    Generic,
+    /// Chains of Poseidon permutations
+    Poseidon,
+    /// Multi-range checks, using the lookup argument
+    RangeCheck,
+    /// A third of the rows of each of the other mixes
+    Mixed,
+}
+
+impl GateMix {
+    /// All the mixes
+    pub const ALL: [GateMix; 4] = [
+        GateMix::Generic,
+        GateMix::Poseidon,
+        GateMix::RangeCheck,
+        GateMix::Mixed,
+    ];
+
+    /// The name of the mix, used in the names of the benchmarks
+    pub fn name(self) -> &'static str {
+        match self {
+            GateMix::Generic => "generic",
+            GateMix::Poseidon => "poseidon",
+            GateMix::RangeCheck => "range check",
+            GateMix::Mixed => "mixed",
+        }
+    }
+
+    /// The smallest SRS size (in log2) of the mix, the table of the range checks having
+    /// 2^12 entries
+    pub fn min_srs_size(self) -> u32 {
+        match self {
+            GateMix::Generic | GateMix::Poseidon => 4,
+            GateMix::RangeCheck | GateMix::Mixed => 13,
+        }
+    }
+
+    // Appends `num_rows` rows of gates of the mix to the circuit and to its witness
+    fn extend(
+        self,
+        gates: &mut Vec<CircuitGate<Fp>>,
+        witness: &mut [Vec<Fp>; COLUMNS],
+        num_rows: usize,
+    ) {
+        let end = gates.len() + num_rows;
+        match self {
+            GateMix::Generic => {}
+            GateMix::Poseidon => {
+                let rows = POSEIDON_CHAIN * POS_ROWS_PER_HASH + 1;
+                let absorbed: Vec<[Fp; 3]> = (1..POSEIDON_CHAIN)
+                    .map(|i| [Fp::from(i as u64), Fp::from(0u64), Fp::from(0u64)])
+                    .collect();
+                while gates.len() + rows <= end {
+                    let row = gates.len();
+                    let (chain, _) = CircuitGate::create_poseidon_chain(
+                        row,
+                        [Wire::for_row(row), Wire::for_row(row + rows - 1)],
+                        &fp_kimchi::static_params().round_constants,
+                        &absorbed,
+                    );
+                    gates.extend(chain);
+                    for column in witness.iter_mut() {
+                        column.resize(row + rows, Fp::from(0u64));
+                    }
+                    let input = array::from_fn(|i| Fp::from((row + i) as u64));
+                    generate_chain_witness(
+                        row,
+                        fp_kimchi::static_params(),
+                        witness,
+                        input,
+                        &absorbed,
+                    );
+                }
+            }
+            GateMix::RangeCheck => {
+                while gates.len() + RANGE_CHECK_ROWS <= end {
+                    let row = gates.len();
+                    let (_, range_check) = CircuitGate::create_multi_range_check(row);
+                    gates.extend(range_check);
+                    let values = array::from_fn::<_, 3, _>(|i| Fp::from((3 * row + i) as u64));
+                    let rows = range_check::witness::create_multi(values[0], values[1], values[2]);
+                    for (column, rows) in witness.iter_mut().zip(rows) {
+                        column.extend(rows);
+                    }
+                }
+            }
+            GateMix::Mixed => {
+                let third = num_rows / 3;
+                GateMix::Poseidon.extend(gates, witness, third);
+                GateMix::RangeCheck.extend(gates, witness, third);
+            }
+        }
+
+        // the remaining rows are filled with generic gates
+        for row in gates.len()..end {
+            gates.push(CircuitGate::create_generic_gadget(
+                Wire::for_row(row),
+                GenericGateSpec::Const(1u32.into()),
+                None,
+            ));
+            for column in witness.iter_mut() {
+                column.push(1u32.into());
+            }
+        }
+    }
+}
+
+/// The measurements of a benchmark, as recorded in the JSON reports used to track
+/// regressions
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchRecord {
+    /// Name of the benchmark
+    pub name: String,
+    /// Number of gates of the circuit
+    pub num_gates: usize,
+    /// Size of the SRS filled by the circuit, in log2
+    pub srs_size: usize,
+    /// Size of a serialized proof, in bytes
+    pub proof_size: usize,
+    /// Mean proving time, in milliseconds
+    pub prover_ms: f64,
+    /// Mean verification time, in milliseconds
+    pub verifier_ms: f64,
+}
+
+/// A measurement of a benchmark which regressed with respect to a baseline
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    /// Name of the benchmark
+    pub name: String,
+    /// The measurement
+    pub metric: &'static str,
+    /// The measurement in the baseline
+    pub baseline: f64,
+    /// The current measurement
+    pub current: f64,
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} went from {:.2} to {:.2}",
+            self.name, self.metric, self.baseline, self.current
+        )
+    }
+}
+
+/// Compares the records of a run with the ones of a baseline, and returns the times
+/// increased by more than `tolerance` (e.g. 0.1 for 10%) and the increased proof sizes.
+/// The benchmarks missing from the baseline are ignored.
+pub fn regressions(
+    baseline: &[BenchRecord],
+    current: &[BenchRecord],
+    tolerance: f64,
+) -> Vec<Regression> {
+    let mut regressions = vec![];
+    for record in current {
+        if let Some(base) = baseline.iter().find(|base| base.name == record.name) {
+            // the proof size is deterministic
+            let metrics = [
+                (
+                    "proof size",
+                    base.proof_size as f64,
+                    record.proof_size as f64,
+                    0.0,
+                ),
+                ("prover time", base.prover_ms, record.prover_ms, tolerance),
+                (
+                    "verifier time",
+                    base.verifier_ms,
+                    record.verifier_ms,
+                    tolerance,
+                ),
+            ];
+            for (metric, baseline, current, tolerance) in metrics {
+                if current > baseline * (1.0 + tolerance) {
+                    regressions.push(Regression {
+                        name: record.name.clone(),
+                        metric,
+                        baseline,
+                        current,
+                    });
+                }
+            }
+        }
+    }
+    regressions
+}
+
 pub struct BenchmarkCtx {
     pub num_gates: usize,
     group_map: BWParameters<VestaParameters>,
     index: ProverIndex<Vesta, OpeningProof<Vesta>>,
     verifier_index: VerifierIndex<Vesta, OpeningProof<Vesta>>,
+    witness: [Vec<Fp>; COLUMNS],
 }
 
 impl BenchmarkCtx {
@@ -39,22 +246,31 @@ impl BenchmarkCtx {
 
     /// This will create a context that allows for benchmarks of `num_gates` gates (multiplication gates).
     pub fn new(srs_size_log2: u32) -> Self {
+        Self::new_with_mix(srs_size_log2, GateMix::Generic)
+    }
+
+    /// This will create a context that allows for benchmarks of a circuit of the gates of
+    /// `mix`, filling an SRS of size `2^srs_size_log2`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the SRS is smaller than [GateMix::min_srs_size].
+    pub fn new_with_mix(srs_size_log2: u32, mix: GateMix) -> Self {
+        assert!(
+            srs_size_log2 >= mix.min_srs_size(),
+            "the {} gates need an SRS of size at least 2^{}",
+            mix.name(),
+            mix.min_srs_size()
+        );
+
         // there's some overhead that we need to remove (e.g. zk rows)
 
         let num_gates = ((1 << srs_size_log2) - 10) as usize;
 
-        // create the circuit
+        // create the circuit and its witness
         let mut gates = vec![];
-
-        #[allow(clippy::explicit_counter_loop)]
-        for row in 0..num_gates {
-            let wires = Wire::for_row(row);
-            gates.push(CircuitGate::create_generic_gadget(
-                wires,
-                GenericGateSpec::Const(1u32.into()),
-                None,
-            ));
-        }
+        let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+        mix.extend(&mut gates, &mut witness, num_gates);
 
         // group map
         let group_map = <Vesta as CommitmentCurve>::Map::setup();
@@ -73,13 +289,13 @@ impl BenchmarkCtx {
             group_map,
             index,
             verifier_index,
+            witness,
         }
     }
 
     /// Produces a proof
     pub fn create_proof(&self) -> (ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>) {
-        // create witness
-        let witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![1u32.into(); self.num_gates]);
+        let witness = self.witness.clone();
 
         let public_input = witness[0][0..self.index.cs.public].to_vec();
 
@@ -115,6 +331,45 @@ impl BenchmarkCtx {
     }
 }
 
+impl BenchmarkCtx {
+    /// Returns the size of a serialized proof, in bytes
+    pub fn proof_size(proof: &ProverProof<Vesta, OpeningProof<Vesta>>) -> usize {
+        rmp_serde::to_vec(proof)
+            .expect("a proof can be serialized")
+            .len()
+    }
+
+    /// Measures the proof size, and the mean proving and verification times over
+    /// `iterations` runs
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `iterations` is zero.
+    pub fn record(&self, name: String, iterations: u32) -> BenchRecord {
+        assert!(iterations > 0, "a benchmark needs at least one iteration");
+
+        let start = Instant::now();
+        let mut proofs: Vec<_> = (0..iterations).map(|_| self.create_proof()).collect();
+        let prover_ms = start.elapsed().as_secs_f64() * 1000.0 / f64::from(iterations);
+
+        let proof = proofs.swap_remove(0);
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.batch_verification(std::slice::from_ref(&proof));
+        }
+        let verifier_ms = start.elapsed().as_secs_f64() * 1000.0 / f64::from(iterations);
+
+        BenchRecord {
+            name,
+            num_gates: self.num_gates,
+            srs_size: self.index.cs.domain.d1.log_size_of_group as usize,
+            proof_size: Self::proof_size(&proof.0),
+            prover_ms,
+            verifier_ms,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Instant;
@@ -140,4 +395,44 @@ mod tests {
         ctx.batch_verification(&vec![(proof, public_input)]);
         println!("proof verified in {}", start.elapsed().as_secs());
     }
+
+    #[test]
+    fn test_bench_gate_mix() {
+        let ctx = BenchmarkCtx::new_with_mix(8, GateMix::Poseidon);
+        let record = ctx.record("poseidon".to_string(), 1);
+        assert_eq!(record.num_gates, 246);
+        assert_eq!(record.srs_size, 8);
+        assert!(record.proof_size > 0);
+    }
+
+    #[test]
+    fn test_regressions() {
+        let record = |name: &str, proof_size, prover_ms| BenchRecord {
+            name: name.to_string(),
+            num_gates: 1000,
+            srs_size: 10,
+            proof_size,
+            prover_ms,
+            verifier_ms: 10.0,
+        };
+        let baseline = vec![record("a", 1000, 100.0), record("b", 1000, 100.0)];
+
+        // the times within the tolerance, and the new benchmarks, are not regressions
+        let current = vec![
+            record("a", 1000, 109.0),
+            record("b", 900, 50.0),
+            record("c", 2000, 500.0),
+        ];
+        assert!(regressions(&baseline, &current, 0.1).is_empty());
+
+        let current = vec![record("a", 1001, 100.0), record("b", 1000, 111.0)];
+        let found = regressions(&baseline, &current, 0.1);
+        assert_eq!(
+            found
+                .iter()
+                .map(|regression| (regression.name.as_str(), regression.metric))
+                .collect::<Vec<_>>(),
+            vec![("a", "proof size"), ("b", "prover time")]
+        );
+    }
 }
@@ -124,6 +124,9 @@ impl<F: Copy> ColumnEvaluations<F> for ProofEvaluations<PointEvaluations<F>> {
             Index(GateType::Rot64) => self
                 .rot_selector
                 .ok_or(ExprError::MissingIndexEvaluation(col)),
+            Index(GateType::Custom) => self
+                .custom_selector
+                .ok_or(ExprError::MissingIndexEvaluation(col)),
             Permutation(i) => Ok(self.s[i]),
             Coefficient(i) => Ok(self.coefficients[i]),
             LookupKindIndex(LookupPattern::Xor) => self
@@ -138,6 +141,9 @@ impl<F: Copy> ColumnEvaluations<F> for ProofEvaluations<PointEvaluations<F>> {
             LookupKindIndex(LookupPattern::ForeignFieldMul) => self
                 .foreign_field_mul_lookup_selector
                 .ok_or(ExprError::MissingIndexEvaluation(col)),
+            LookupKindIndex(LookupPattern::VectorLookup) => self
+                .vector_lookup_selector
+                .ok_or(ExprError::MissingIndexEvaluation(col)),
             LookupRuntimeSelector => self
                 .runtime_lookup_table_selector
                 .ok_or(ExprError::MissingIndexEvaluation(col)),
@@ -0,0 +1,377 @@
+//! This module implements a "bus", that is an offline memory checking argument.
+//!
+//! A bus is a multiset of accesses `(op, addr, ts, value)`, where `op` is `1`
+//! for a write and `0` for a read, `addr` is the address accessed, `ts` the
+//! timestamp of the access and `value` the value read or written. The accesses
+//! are consistent with a memory if every read of an address returns the value
+//! of the last write to this address.
+//!
+//! The argument is split in two parts, that the user combines with the
+//! machinery of its proof system:
+//!
+//! * the accesses, in the order of the execution, are shown to be a permutation
+//!   of the same accesses sorted by address then timestamp. This is a multiset
+//!   equality, that can be checked with a product argument over
+//!   [BusAccess::fingerprint], with copy constraints when the order of the
+//!   accesses is known when building the circuit, or with a logarithmic
+//!   derivative argument (e.g. the `RAMLookup`s of the zkVM);
+//! * the sorted accesses satisfy [sorted_constraints] on each pair of
+//!   consecutive rows, and the value returned by [sorted_increment] is range
+//!   checked, which ensures that the trace is sorted and that no two accesses
+//!   share the same address and timestamp.
+//!
+//! The constraints are generic over the type of the values, so that they can
+//! be used to build expressions, as in the [BusSorted] custom gate, as well as
+//! to check a witness. The witness of the argument is computed by a [BusTrace].
+
+use crate::circuits::{
+    argument::ArgumentEnv,
+    custom_gate::{CustomGate, WitnessCell},
+    expr::{constraints::ExprOps, Cache},
+    gate::CurrOrNext,
+};
+use ark_ff::{One, PrimeField, Zero};
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul, Sub},
+};
+use thiserror::Error;
+
+/// The number of constraints of [sorted_constraints]
+pub const BUS_SORTED_CONSTRAINTS: usize = 5;
+
+/// The custom gate identifier of [BusSorted]
+pub const BUS_SORTED_GATE_ID: u32 = 0xb05;
+
+/// Errors that can arise when recording or checking the accesses to a bus
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BusError {
+    #[error("the address {0} is read before being written")]
+    UninitializedRead(u64),
+    #[error("the sorted access {0} does not satisfy the constraint {1}")]
+    Constraint(usize, usize),
+    #[error("the increment of the sorted access {0} does not fit in {1} bits")]
+    Increment(usize, u32),
+    #[error("the accesses are not a permutation of the sorted accesses")]
+    Multiset,
+}
+
+/// The arithmetic required by the constraints of the bus
+pub trait BusOps:
+    Clone + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + One + Zero
+{
+}
+
+impl<T> BusOps for T where
+    T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + One + Zero
+{
+}
+
+/// The kind of an access to a bus
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BusOp {
+    Read,
+    Write,
+}
+
+impl BusOp {
+    /// Returns the value encoding the operation in an access
+    pub fn to_value<T: BusOps>(self) -> T {
+        match self {
+            BusOp::Read => T::zero(),
+            BusOp::Write => T::one(),
+        }
+    }
+}
+
+/// An access to a bus
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BusAccess<T> {
+    /// `1` for a write, `0` for a read
+    pub op: T,
+    /// The address accessed
+    pub addr: T,
+    /// The timestamp of the access
+    pub ts: T,
+    /// The value read or written
+    pub value: T,
+}
+
+impl<T: BusOps> BusAccess<T> {
+    /// Combines the fields of the access into a single value,
+    /// `op + beta * addr + beta^2 * ts + beta^3 * value`
+    pub fn combine(&self, beta: &T) -> T {
+        [&self.value, &self.ts, &self.addr]
+            .into_iter()
+            .fold(T::zero(), |acc, x| (acc + x.clone()) * beta.clone())
+            + self.op.clone()
+    }
+
+    /// The factor of the access in a product argument, `gamma - combine(beta)`.
+    /// Two lists of accesses are a permutation of each other (with high probability)
+    /// if the products of their fingerprints are equal for random `beta` and `gamma`.
+    pub fn fingerprint(&self, beta: &T, gamma: &T) -> T {
+        gamma.clone() - self.combine(beta)
+    }
+}
+
+/// The constraints between two consecutive accesses `curr` and `next` of the
+/// sorted trace, where `same_addr` is `1` if both accesses have the same address
+/// and `0` otherwise. They enforce that
+///
+/// * `next.op` and `same_addr` are boolean,
+/// * `same_addr` is `1` only if the addresses are equal,
+/// * the first access to an address is a write,
+/// * a read returns the value of the previous access to its address.
+///
+/// The caller additionally constrains the first access of the trace to be a write.
+pub fn sorted_constraints<T: BusOps>(
+    curr: &BusAccess<T>,
+    next: &BusAccess<T>,
+    same_addr: &T,
+) -> [T; BUS_SORTED_CONSTRAINTS] {
+    let boolean = |x: &T| x.clone() * (T::one() - x.clone());
+    let is_read = T::one() - next.op.clone();
+    [
+        boolean(&next.op),
+        boolean(same_addr),
+        same_addr.clone() * (next.addr.clone() - curr.addr.clone()),
+        (T::one() - same_addr.clone()) * is_read.clone(),
+        same_addr.clone() * is_read * (next.value.clone() - curr.value.clone()),
+    ]
+}
+
+/// The increment between two consecutive accesses `curr` and `next` of the
+/// sorted trace: the increment of the timestamps minus one if the accesses
+/// have the same address, and the increment of the addresses minus one otherwise.
+/// Range checking it ensures that the trace is strictly increasing.
+pub fn sorted_increment<T: BusOps>(curr: &BusAccess<T>, next: &BusAccess<T>, same_addr: &T) -> T {
+    same_addr.clone() * (next.ts.clone() - curr.ts.clone() - T::one())
+        + (T::one() - same_addr.clone()) * (next.addr.clone() - curr.addr.clone() - T::one())
+}
+
+/// An access recorded by a [BusTrace]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BusEvent<F> {
+    pub op: BusOp,
+    pub addr: u64,
+    pub ts: u64,
+    pub value: F,
+}
+
+impl<F: PrimeField> BusEvent<F> {
+    /// Returns the access as field elements
+    pub fn access(&self) -> BusAccess<F> {
+        BusAccess {
+            op: self.op.to_value(),
+            addr: F::from(self.addr),
+            ts: F::from(self.ts),
+            value: self.value,
+        }
+    }
+}
+
+/// A row of the sorted trace: an access, and whether the previous row has the same address
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SortedEvent<F> {
+    pub event: BusEvent<F>,
+    pub same_addr: bool,
+}
+
+/// Records the accesses to a memory, in the order of the execution, and
+/// computes the witness of the bus argument.
+/// The timestamp of an access is its position in the trace.
+#[derive(Clone, Debug)]
+pub struct BusTrace<F> {
+    events: Vec<BusEvent<F>>,
+    memory: HashMap<u64, F>,
+}
+
+impl<F> Default for BusTrace<F> {
+    fn default() -> Self {
+        Self {
+            events: vec![],
+            memory: HashMap::new(),
+        }
+    }
+}
+
+impl<F: PrimeField> BusTrace<F> {
+    /// Creates an empty trace
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a write of `value` at `addr`
+    pub fn write(&mut self, addr: u64, value: F) {
+        self.memory.insert(addr, value);
+        self.push(BusOp::Write, addr, value);
+    }
+
+    /// Records a read at `addr`, and returns the value read
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `addr` was never written.
+    pub fn read(&mut self, addr: u64) -> Result<F, BusError> {
+        let value = *self
+            .memory
+            .get(&addr)
+            .ok_or(BusError::UninitializedRead(addr))?;
+        self.push(BusOp::Read, addr, value);
+        Ok(value)
+    }
+
+    fn push(&mut self, op: BusOp, addr: u64, value: F) {
+        let ts = self.events.len() as u64;
+        self.events.push(BusEvent {
+            op,
+            addr,
+            ts,
+            value,
+        });
+    }
+
+    /// Returns the accesses in the order of the execution
+    pub fn events(&self) -> &[BusEvent<F>] {
+        &self.events
+    }
+
+    /// Returns the accesses sorted by address then timestamp
+    pub fn sorted(&self) -> Vec<SortedEvent<F>> {
+        let mut events = self.events.clone();
+        events.sort_by_key(|event| (event.addr, event.ts));
+        let mut prev_addr = None;
+        events
+            .into_iter()
+            .map(|event| {
+                let same_addr = prev_addr == Some(event.addr);
+                prev_addr = Some(event.addr);
+                SortedEvent { event, same_addr }
+            })
+            .collect()
+    }
+
+    /// Returns the rows of the sorted trace laid out for the [BusSorted] gate
+    pub fn sorted_rows(&self) -> Vec<[F; 6]> {
+        let sorted = self.sorted();
+        let mut rows: Vec<[F; 6]> = Vec::with_capacity(sorted.len());
+        for (i, row) in sorted.iter().enumerate() {
+            let access = row.event.access();
+            let same_addr = F::from(row.same_addr);
+            let increment = match i {
+                0 => F::zero(),
+                _ => sorted_increment(&sorted[i - 1].event.access(), &access, &same_addr),
+            };
+            rows.push([
+                access.op,
+                access.addr,
+                access.ts,
+                access.value,
+                same_addr,
+                increment,
+            ]);
+        }
+        rows
+    }
+
+    /// Checks the bus argument on the witness of the trace (see [check_sorted])
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a constraint of the argument is not satisfied.
+    pub fn check(&self, increment_bits: u32, beta: F, gamma: F) -> Result<(), BusError> {
+        check_sorted(&self.events, &self.sorted(), increment_bits, beta, gamma)
+    }
+}
+
+/// Checks the bus argument on the accesses `events`, in the order of the
+/// execution, and their claimed sorted version `sorted`, with the increments of
+/// the sorted trace range checked on `increment_bits` bits, and the multiset
+/// equality checked with the challenges `beta` and `gamma`.
+///
+/// # Errors
+///
+/// Will give error if a constraint of the argument is not satisfied.
+pub fn check_sorted<F: PrimeField>(
+    events: &[BusEvent<F>],
+    sorted: &[SortedEvent<F>],
+    increment_bits: u32,
+    beta: F,
+    gamma: F,
+) -> Result<(), BusError> {
+    if let Some(first) = sorted.first() {
+        if first.event.op != BusOp::Write {
+            return Err(BusError::Constraint(0, 3));
+        }
+    }
+    let bound = F::from(2u64).pow([u64::from(increment_bits)]);
+    for (i, pair) in sorted.windows(2).enumerate() {
+        let (curr, next) = (pair[0].event.access(), pair[1].event.access());
+        let same_addr = F::from(pair[1].same_addr);
+        let constraints = sorted_constraints(&curr, &next, &same_addr);
+        if let Some(j) = constraints.iter().position(|c| !c.is_zero()) {
+            return Err(BusError::Constraint(i + 1, j));
+        }
+        if sorted_increment(&curr, &next, &same_addr).into_repr() >= bound.into_repr() {
+            return Err(BusError::Increment(i + 1, increment_bits));
+        }
+    }
+    let fingerprint = |event: &BusEvent<F>| event.access().fingerprint(&beta, &gamma);
+    if events.iter().map(fingerprint).product::<F>()
+        != sorted
+            .iter()
+            .map(|row| fingerprint(&row.event))
+            .product::<F>()
+    {
+        return Err(BusError::Multiset);
+    }
+    Ok(())
+}
+
+/// A custom gate enforcing [sorted_constraints] between the access of its row
+/// and the access of the next row. An access is laid out in the columns
+/// `0..4` of a row as `op, addr, ts, value`, followed by `same_addr` in column
+/// `4` and by the [sorted_increment] with the previous row in column `5`, which
+/// can be copied to a range check.
+pub struct BusSorted;
+
+impl<F: PrimeField> CustomGate<F> for BusSorted {
+    const ID: u32 = BUS_SORTED_GATE_ID;
+    const NAME: &'static str = "BusSorted";
+    const CONSTRAINTS: u32 = BUS_SORTED_CONSTRAINTS as u32 + 1;
+
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        let access = |row: CurrOrNext| {
+            let w = |col| match row {
+                CurrOrNext::Curr => env.witness_curr(col),
+                CurrOrNext::Next => env.witness_next(col),
+            };
+            BusAccess {
+                op: w(0),
+                addr: w(1),
+                ts: w(2),
+                value: w(3),
+            }
+        };
+        let (curr, next) = (access(CurrOrNext::Curr), access(CurrOrNext::Next));
+        let same_addr = env.witness_next(4);
+
+        let mut constraints = sorted_constraints(&curr, &next, &same_addr).to_vec();
+        constraints.push(env.witness_next(5) - sorted_increment(&curr, &next, &same_addr));
+        constraints
+    }
+
+    fn witness_layout() -> Vec<WitnessCell> {
+        let names = ["op", "addr", "ts", "value", "same_addr", "increment"];
+        let cells = |row, cols: usize| {
+            names[..cols]
+                .iter()
+                .enumerate()
+                .map(move |(col, name)| WitnessCell { row, col, name })
+        };
+        cells(CurrOrNext::Curr, 4)
+            .chain(cells(CurrOrNext::Next, names.len()))
+            .collect()
+    }
+}
@@ -2,33 +2,49 @@
 use super::lookup::runtime_tables::RuntimeTableCfg;
 use crate::{
     circuits::{
+        custom_gate::{CustomGateError, CustomGateRegistry},
         domain_constant_evaluation::DomainConstantEvaluations,
         domains::EvaluationDomains,
-        gate::{CircuitGate, GateType},
+        gate::{CircuitGate, CurrOrNext, GateType},
         lookup::{
+            constraints::LookupArgument,
             index::LookupConstraintSystem,
-            lookups::LookupFeatures,
-            tables::{GateLookupTables, LookupTable},
+            lookups::{
+                extend_split_lookups_witness, split_lookups, JointLookup, LocalPosition,
+                LookupFeatures,
+            },
+            runtime_tables::RuntimeTable,
+            tables::{
+                fixed::FixedTableRegistry, xor::XorTableBits, GateLookupTable, GateLookupTables,
+                LookupTable,
+            },
         },
         polynomial::{WitnessEvals, WitnessOverDomains, WitnessShifts},
         polynomials::permutation::Shifts,
+        public_input::PublicInputLayout,
         wires::*,
     },
     curve::KimchiCurve,
-    error::{DomainCreationError, SetupError},
+    error::{DomainCreationError, SetupError, WitnessError},
     prover_index::ProverIndex,
+    transcript::TranscriptVersion,
 };
 use ark_ff::{PrimeField, SquareRootField, Zero};
 use ark_poly::{
     univariate::DensePolynomial as DP, EvaluationDomain, Evaluations as E,
     Radix2EvaluationDomain as D,
 };
-use o1_utils::ExtendedEvaluations;
+use o1_utils::{
+    fft::{FftEvaluate as _, FftInterpolate as _},
+    field_helpers::i32_to_field,
+    ExtendedEvaluations,
+};
 use once_cell::sync::OnceCell;
 use poly_commitment::OpenProof;
+use rayon::prelude::*;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
-use std::{array, sync::Arc};
+use std::{array, collections::HashSet, sync::Arc};
 
 //
 // ConstraintSystem
@@ -58,6 +74,92 @@ pub struct FeatureFlags {
     pub lookup_features: LookupFeatures,
 }
 
+/// Flags for the gates whose selectors are always opened by the proofs: the
+/// `Poseidon`, `CompleteAdd`, `VarBaseMul`, `EndoMul` and `EndoMulScalar` gates.
+///
+/// Unlike the gates of the [FeatureFlags], these gates are part of the proof
+/// format even when the circuit does not use them. The selector of an unused
+/// gate is the zero polynomial: when the unused gates are pruned (see
+/// [Builder::prune_unused_gates]), its constraints are left out of the
+/// linearization, and its commitment is left out of the verifier index, the
+/// verifier using the (masked) commitment to zero instead.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct BaseGates {
+    /// Poseidon gate
+    pub poseidon: bool,
+    /// Complete EC addition gate
+    pub complete_add: bool,
+    /// EC variable base scalar multiplication gate
+    pub var_base_mul: bool,
+    /// Endoscaling gate
+    pub endo_mul: bool,
+    /// Endoscalar computation gate
+    pub endo_mul_scalar: bool,
+}
+
+impl Default for BaseGates {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl BaseGates {
+    /// The gates whose selectors are always opened by the proofs
+    pub const GATE_TYPES: [GateType; 5] = [
+        GateType::Poseidon,
+        GateType::CompleteAdd,
+        GateType::VarBaseMul,
+        GateType::EndoMul,
+        GateType::EndoMulScalar,
+    ];
+
+    /// All the base gates are used
+    pub fn all() -> Self {
+        Self {
+            poseidon: true,
+            complete_add: true,
+            var_base_mul: true,
+            endo_mul: true,
+            endo_mul_scalar: true,
+        }
+    }
+
+    /// The base gates used by `gates`
+    pub fn from_gates<F: PrimeField>(gates: &[CircuitGate<F>]) -> Self {
+        let mut base_gates = Self {
+            poseidon: false,
+            complete_add: false,
+            var_base_mul: false,
+            endo_mul: false,
+            endo_mul_scalar: false,
+        };
+        for gate in gates {
+            match gate.typ {
+                GateType::Poseidon => base_gates.poseidon = true,
+                GateType::CompleteAdd => base_gates.complete_add = true,
+                GateType::VarBaseMul => base_gates.var_base_mul = true,
+                GateType::EndoMul => base_gates.endo_mul = true,
+                GateType::EndoMulScalar => base_gates.endo_mul_scalar = true,
+                _ => (),
+            }
+        }
+        base_gates
+    }
+
+    /// Returns `false` if `typ` is a base gate that is not used.
+    /// The other gate types are not covered by these flags, and return `true`.
+    pub fn is_used(&self, typ: GateType) -> bool {
+        match typ {
+            GateType::Poseidon => self.poseidon,
+            GateType::CompleteAdd => self.complete_add,
+            GateType::VarBaseMul => self.var_base_mul,
+            GateType::EndoMul => self.endo_mul,
+            GateType::EndoMulScalar => self.endo_mul_scalar,
+            _ => true,
+        }
+    }
+}
+
 /// The polynomials representing evaluated columns, in coefficient form.
 #[serde_as]
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -139,6 +241,10 @@ pub struct ColumnEvaluations<F: PrimeField> {
     /// Rot gate selector over domain d8
     #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
     pub rot_selector8: Option<E<F, D<F>>>,
+
+    /// Custom gates selector over domain d8
+    #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
+    pub custom_selector8: Option<E<F, D<F>>>,
 }
 
 #[serde_as]
@@ -162,6 +268,10 @@ pub struct ConstraintSystem<F: PrimeField> {
     /// flags for optional features
     pub feature_flags: FeatureFlags,
 
+    /// flags for the base gates used by the circuit
+    #[serde(default)]
+    pub base_gates: BaseGates,
+
     /// SID polynomial
     #[serde_as(as = "Vec<o1_utils::serialization::SerdeAs>")]
     pub sid: Vec<F>,
@@ -181,6 +291,22 @@ pub struct ConstraintSystem<F: PrimeField> {
 
     /// Disable gates checks (for testing; only enables with development builds)
     pub disable_gates_checks: bool,
+
+    /// Custom gates used by the circuit, if any
+    #[serde(skip)]
+    pub custom_gates: Option<Arc<CustomGateRegistry<F>>>,
+
+    /// The names of the public inputs, if any
+    #[serde(default)]
+    pub public_input_layout: Option<PublicInputLayout>,
+
+    /// The size of the words of the XOR lookup table
+    #[serde(default)]
+    pub xor_table_bits: XorTableBits,
+
+    /// The version of the Fiat-Shamir transcript of the proofs
+    #[serde(default)]
+    pub transcript_version: TranscriptVersion,
 }
 
 /// Represents an error found when verifying a witness with a gate
@@ -203,6 +329,87 @@ pub struct Builder<F: PrimeField> {
     precomputations: Option<Arc<DomainConstantEvaluations<F>>>,
     disable_gates_checks: bool,
     max_poly_size: Option<usize>,
+    custom_gates: Option<CustomGateRegistry<F>>,
+    fixed_tables: Option<FixedTableRegistry<F>>,
+    lookup_argument: LookupArgument,
+    max_lookups_per_row: Option<usize>,
+    zk_rows: Option<u64>,
+    public_input_layout: Option<PublicInputLayout>,
+    prune_unused_gates: bool,
+    xor_table_bits: Option<XorTableBits>,
+    transcript_version: TranscriptVersion,
+}
+
+/// Returns the rows of the gates of type `gate_type` if the selector of this
+/// gate is sparse, that is if there are at most `log2(n)` such gates in a
+/// domain of size `n`. The selector polynomial of a sparse gate is evaluated
+/// and committed from its few nonzero evaluations instead of the full domain.
+pub fn sparse_selector_rows<F: PrimeField>(
+    gate_type: GateType,
+    gates: &[CircuitGate<F>],
+    domain: &D<F>,
+    disable_gates_checks: bool,
+) -> Option<Vec<usize>> {
+    if cfg!(debug_assertions) && disable_gates_checks {
+        return Some(vec![]);
+    }
+    let threshold = domain.log_size_of_group as usize;
+    let mut rows = vec![];
+    for (row, gate) in gates.iter().enumerate() {
+        if gate.typ == gate_type {
+            if rows.len() == threshold {
+                return None;
+            }
+            rows.push(row);
+        }
+    }
+    Some(rows)
+}
+
+/// Evaluates over `target_domain` the sum of the Lagrange polynomials of
+/// `domain` at the given rows. The Lagrange polynomials are rotations of the
+/// first one, `L_i(x) = L_0(x w^{-i})`, so that their evaluations are the ones
+/// of `L_0(x) = (x^n - 1) / (n (x - 1))` shifted by `i` rows of `domain`, and
+/// only the evaluations of `L_0` need an inversion.
+fn sparse_selector_evaluations<F: PrimeField>(
+    rows: &[usize],
+    domain: &D<F>,
+    target_domain: &D<F>,
+) -> E<F, D<F>> {
+    let n = domain.size();
+    let size = target_domain.size();
+    let ratio = size / n;
+    // x^n - 1 only depends on the index of x modulo the ratio of the domains
+    let vanishing: Vec<F> = (0..ratio)
+        .map(|j| (target_domain.group_gen.pow([(j * n) as u64]) - F::one()) * domain.size_inv)
+        .collect();
+
+    // the denominator is zero on x = 1 only, which is left as is by the batch inversion
+    let mut first: Vec<F> = target_domain.elements().map(|x| x - F::one()).collect();
+    ark_ff::batch_inversion(&mut first);
+    first.par_iter_mut().enumerate().for_each(|(j, eval)| {
+        if j % ratio != 0 {
+            *eval *= vanishing[j % ratio];
+        } else {
+            *eval = if j == 0 { F::one() } else { F::zero() };
+        }
+    });
+
+    let mut evals = vec![F::zero(); size];
+    for &row in rows {
+        // the evaluations of L_i are the ones of L_0 rotated by `i * ratio`
+        let offset = row * ratio;
+        let (before, after) = evals.split_at_mut(offset);
+        before
+            .par_iter_mut()
+            .zip(first[size - offset..].par_iter())
+            .for_each(|(eval, l)| *eval += l);
+        after
+            .par_iter_mut()
+            .zip(first[..size - offset].par_iter())
+            .for_each(|(eval, l)| *eval += l);
+    }
+    E::<F, D<F>>::from_vec_and_domain(evals, *target_domain)
 }
 
 /// Create selector polynomial for a circuit gate
@@ -215,6 +422,8 @@ pub fn selector_polynomial<F: PrimeField>(
 ) -> E<F, D<F>> {
     if cfg!(debug_assertions) && disable_gates_checks {
         DP::<F>::zero().evaluate_over_domain_by_ref(*target_domain)
+    } else if let Some(rows) = sparse_selector_rows(gate_type, gates, &domain.d1, false) {
+        sparse_selector_evaluations(&rows, &domain.d1, target_domain)
     } else {
         // Coefficient form
         let coeff = E::<F, D<F>>::from_vec_and_domain(
@@ -230,9 +439,9 @@ pub fn selector_polynomial<F: PrimeField>(
                 .collect(),
             domain.d1,
         )
-        .interpolate();
+        .fft_interpolate();
 
-        coeff.evaluate_over_domain_by_ref(*target_domain)
+        coeff.fft_evaluate_over_domain_by_ref(*target_domain)
     }
 }
 
@@ -246,6 +455,13 @@ impl<F: PrimeField> ConstraintSystem<F> {
     /// - `runtime_tables: None`,
     /// - `precomputations: None`,
     /// - `disable_gates_checks: false`,
+    /// - `custom_gates: None`,
+    /// - `fixed_tables: None`,
+    /// - `lookup_argument: LookupArgument::Plookup`,
+    /// - `max_lookups_per_row: None`,
+    /// - `zk_rows: None`,
+    /// - `public_input_layout: None`,
+    /// - `transcript_version: TranscriptVersion::Legacy`,
     ///
     /// How to use it:
     /// 1. Create your instance of your builder for the constraint system using `crate(gates, sponge params)`
@@ -261,6 +477,38 @@ impl<F: PrimeField> ConstraintSystem<F> {
             precomputations: None,
             disable_gates_checks: false,
             max_poly_size: None,
+            custom_gates: None,
+            fixed_tables: None,
+            lookup_argument: LookupArgument::Plookup,
+            max_lookups_per_row: None,
+            zk_rows: None,
+            public_input_layout: None,
+            prune_unused_gates: false,
+            xor_table_bits: None,
+            transcript_version: TranscriptVersion::Legacy,
+        }
+    }
+
+    /// Returns the argument used to prove the lookups of the circuit
+    pub fn lookup_argument(&self) -> LookupArgument {
+        self.lookup_constraint_system
+            .as_ref()
+            .map_or(LookupArgument::default(), |lcs| lcs.configuration.argument)
+    }
+
+    /// Returns the maximum number of lookups per row of the circuit, or `None`
+    /// if it does not use lookups
+    pub fn max_lookups_per_row(&self) -> Option<usize> {
+        self.lookup_constraint_system
+            .as_ref()
+            .map(|lcs| lcs.configuration.lookup_info.max_per_row)
+    }
+
+    /// Extends the witness of the circuit given to the [Builder] with the rows
+    /// added to split its lookup gates (see [Builder::max_lookups_per_row]).
+    pub fn extend_split_lookups_witness(&self, witness: &mut [Vec<F>; COLUMNS]) {
+        if let Some(max_per_row) = self.max_lookups_per_row() {
+            extend_split_lookups_witness(&self.gates, witness, max_per_row);
         }
     }
 
@@ -336,12 +584,185 @@ impl<
 }
 
 impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
+    /// Checks a witness against the constraint system without creating a proof:
+    /// the public inputs, the constraints of every gate, the copy constraints,
+    /// and the lookups into the fixed tables.
+    /// The lookups into runtime tables are not checked, see
+    /// [ConstraintSystem::check_witness_with_runtime_tables] to check them too.
+    ///
+    /// # Errors
+    ///
+    /// Will give the first [WitnessError] found in the witness.
+    pub fn check_witness<G: KimchiCurve<ScalarField = F>>(
+        &self,
+        witness: &[Vec<F>; COLUMNS],
+        public: &[F],
+    ) -> Result<(), WitnessError> {
+        self.check_witness_with_runtime_tables::<G>(witness, public, None)
+    }
+
+    /// Same as [ConstraintSystem::check_witness], also checking the lookups
+    /// into the runtime tables if they are given.
+    ///
+    /// # Errors
+    ///
+    /// Will give the first [WitnessError] found in the witness.
+    pub fn check_witness_with_runtime_tables<G: KimchiCurve<ScalarField = F>>(
+        &self,
+        witness: &[Vec<F>; COLUMNS],
+        public: &[F],
+        runtime_tables: Option<&[RuntimeTable<F>]>,
+    ) -> Result<(), WitnessError> {
+        self.check_witness_with_mds(&G::sponge_params().mds, witness, public, runtime_tables)
+    }
+
+    /// Same as [ConstraintSystem::check_witness_with_runtime_tables], with the
+    /// MDS matrix of the Poseidon gates instead of the curve, so that circuits
+    /// over a field without curve (e.g. a small field) can be checked. The
+    /// matrix can be empty if the circuit has no Poseidon gate.
+    ///
+    /// # Errors
+    ///
+    /// Will give the first [WitnessError] found in the witness.
+    pub fn check_witness_with_mds(
+        &self,
+        mds: &'static Vec<Vec<F>>,
+        witness: &[Vec<F>; COLUMNS],
+        public: &[F],
+        runtime_tables: Option<&[RuntimeTable<F>]>,
+    ) -> Result<(), WitnessError> {
+        let length = witness[0].len();
+        if witness.iter().any(|col| col.len() != length) {
+            return Err(WitnessError::WitnessCsInconsistent);
+        }
+        let n = self.domain.d1.size();
+        let rows = n - self.zk_rows as usize;
+        if length > rows {
+            return Err(WitnessError::TooManyRows(length, rows));
+        }
+        if public.len() != self.public {
+            return Err(WitnessError::PublicInputLength(self.public, public.len()));
+        }
+
+        // pad the witness
+        let witness: [Vec<F>; COLUMNS] = array::from_fn(|i| {
+            let mut w = witness[i].clone();
+            w.resize(n, F::zero());
+            w
+        });
+
+        for (row, gate) in self.gates.iter().enumerate() {
+            // for public gates, only the left wire is toggled
+            if row < self.public && gate.coeffs.first() != Some(&F::one()) {
+                return Err(WitnessError::IncorrectPublic(row));
+            }
+
+            // the constraints of the gate and its copy constraints
+            gate.verify_witness_with_mds(row, &witness, self, mds, public)
+                .map_err(|err| WitnessError::Gate(row, err))?;
+            if gate.typ == GateType::Generic {
+                gate.verify_generic(row, &witness, public)
+                    .map_err(|err| WitnessError::Generic(row, err))?;
+            }
+        }
+
+        self.check_lookups(&witness, runtime_tables)
+    }
+
+    /// Checks that the lookups of the padded witness are in the lookup tables
+    fn check_lookups(
+        &self,
+        witness: &[Vec<F>; COLUMNS],
+        runtime_tables: Option<&[RuntimeTable<F>]>,
+    ) -> Result<(), WitnessError> {
+        let lcs = match &self.lookup_constraint_system {
+            Some(lcs) => lcs,
+            None => return Ok(()),
+        };
+        let n = self.domain.d1.size();
+        let lookup_rows = n - self.zk_rows as usize - 1;
+
+        // the runtime tables are added to the second column of the lookup table
+        let mut runtime_column = vec![F::zero(); n];
+        let mut unchecked_ids = HashSet::<F>::new();
+        if let Some(cfg_runtime_tables) = &lcs.runtime_tables {
+            match runtime_tables {
+                None => unchecked_ids
+                    .extend(cfg_runtime_tables.iter().map(|rt| i32_to_field::<F>(rt.id))),
+                Some(runtime_tables) => {
+                    let expected: Vec<_> = cfg_runtime_tables
+                        .iter()
+                        .map(|rt| (rt.id, rt.len))
+                        .collect();
+                    let given: Vec<_> = runtime_tables
+                        .iter()
+                        .map(|rt| (rt.id, rt.data.len()))
+                        .collect();
+                    if expected != given {
+                        return Err(WitnessError::RuntimeTablesInconsistent);
+                    }
+                    let mut offset = lcs
+                        .runtime_table_offset
+                        .expect("runtime configuration missing offset");
+                    for rt in runtime_tables {
+                        runtime_column[offset..(offset + rt.data.len())].copy_from_slice(&rt.data);
+                        offset += rt.data.len();
+                    }
+                }
+            }
+        }
+
+        let width = lcs.lookup_table8.len();
+        let table: HashSet<(F, Vec<F>)> = (0..lookup_rows)
+            .map(|row| {
+                let table_id = lcs
+                    .table_ids8
+                    .as_ref()
+                    .map_or(F::zero(), |table_ids8| table_ids8.evals[8 * row]);
+                let mut entry: Vec<F> = lcs
+                    .lookup_table8
+                    .iter()
+                    .map(|col| col.evals[8 * row])
+                    .collect();
+                if let Some(second) = entry.get_mut(1) {
+                    *second += runtime_column[row];
+                }
+                (table_id, entry)
+            })
+            .collect();
+
+        let by_row = lcs.configuration.lookup_info.by_row(&self.gates);
+        for (row, lookups) in by_row.iter().enumerate().take(lookup_rows) {
+            let eval = |pos: LocalPosition| -> F {
+                match pos.row {
+                    CurrOrNext::Curr => witness[pos.column][row],
+                    CurrOrNext::Next => witness[pos.column][row + 1],
+                }
+            };
+            for (index, lookup) in lookups.iter().enumerate() {
+                let JointLookup {
+                    table_id,
+                    mut entry,
+                } = lookup.reduce(&eval);
+                if unchecked_ids.contains(&table_id) {
+                    continue;
+                }
+                entry.resize(width, F::zero());
+                if !table.contains(&(table_id, entry)) {
+                    return Err(WitnessError::ValueNotInTable { row, index });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// evaluate witness polynomials over domains
     pub fn evaluate(&self, w: &[DP<F>; COLUMNS], z: &DP<F>) -> WitnessOverDomains<F> {
         // compute shifted witness polynomials
         let w8: [E<F, D<F>>; COLUMNS] =
-            array::from_fn(|i| w[i].evaluate_over_domain_by_ref(self.domain.d8));
-        let z8 = z.evaluate_over_domain_by_ref(self.domain.d8);
+            array::from_fn(|i| w[i].fft_evaluate_over_domain_by_ref(self.domain.d8));
+        let z8 = z.fft_evaluate_over_domain_by_ref(self.domain.d8);
 
         let w4: [E<F, D<F>>; COLUMNS] = array::from_fn(|i| {
             E::<F, D<F>>::from_vec_and_domain(
@@ -375,6 +796,36 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
         }
     }
 
+    /// Evaluates the witness polynomials over the domain d8 only, like
+    /// [ConstraintSystem::evaluate] but without the evaluations which are not
+    /// used by the prover: the evaluations over d4 and the shifted witness
+    /// evaluations over d8 are dummy evaluations over a domain of size 1.
+    pub fn evaluate_d8(&self, w: &[DP<F>; COLUMNS], z: &DP<F>) -> WitnessOverDomains<F> {
+        let w8: [E<F, D<F>>; COLUMNS] =
+            array::from_fn(|i| w[i].fft_evaluate_over_domain_by_ref(self.domain.d8));
+        let z8 = z.fft_evaluate_over_domain_by_ref(self.domain.d8);
+
+        let dummy = || DP::<F>::zero().evaluate_over_domain_by_ref(D::<F>::new(1).unwrap());
+        let dummy_evals = || WitnessEvals {
+            w: array::from_fn(|_| dummy()),
+            z: dummy(),
+        };
+
+        WitnessOverDomains {
+            d4: WitnessShifts {
+                next: dummy_evals(),
+                this: dummy_evals(),
+            },
+            d8: WitnessShifts {
+                next: WitnessEvals {
+                    w: array::from_fn(|_| dummy()),
+                    z: z8.shift(8),
+                },
+                this: WitnessEvals { w: w8, z: z8 },
+            },
+        }
+    }
+
     pub(crate) fn evaluated_column_coefficients(&self) -> EvaluatedColumnCoefficients<F> {
         // compute permutation polynomials
         let shifts = Shifts::new(&self.domain.d1);
@@ -411,14 +862,14 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
         };
 
         let permutation_coefficients: [DP<F>; PERMUTS] =
-            array::from_fn(|i| sigmal1[i].clone().interpolate());
+            array::from_fn(|i| sigmal1[i].clone().fft_interpolate());
 
         // poseidon gate
         let poseidon_selector = E::<F, D<F>>::from_vec_and_domain(
             self.gates.iter().map(|gate| gate.ps()).collect(),
             self.domain.d1,
         )
-        .interpolate();
+        .fft_interpolate();
 
         // double generic gate
         let generic_selector = E::<F, D<F>>::from_vec_and_domain(
@@ -434,7 +885,7 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
                 .collect(),
             self.domain.d1,
         )
-        .interpolate();
+        .fft_interpolate();
 
         // coefficient polynomial
         let coefficients: [_; COLUMNS] = array::from_fn(|i| {
@@ -444,7 +895,7 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
                 .map(|gate| gate.coeffs.get(i).cloned().unwrap_or_else(F::zero))
                 .collect();
             let eval = E::from_vec_and_domain(padded, self.domain.d1);
-            eval.interpolate()
+            eval.fft_interpolate()
         });
 
         EvaluatedColumnCoefficients {
@@ -461,12 +912,12 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
     ) -> ColumnEvaluations<F> {
         let permutation_coefficients8 = array::from_fn(|i| {
             evaluated_column_coefficients.permutation_coefficients[i]
-                .evaluate_over_domain_by_ref(self.domain.d8)
+                .fft_evaluate_over_domain_by_ref(self.domain.d8)
         });
 
         let poseidon_selector8 = evaluated_column_coefficients
             .poseidon_selector
-            .evaluate_over_domain_by_ref(self.domain.d8);
+            .fft_evaluate_over_domain_by_ref(self.domain.d8);
 
         // ECC gates
         let complete_add_selector4 = selector_polynomial(
@@ -503,7 +954,7 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
 
         let generic_selector4 = evaluated_column_coefficients
             .generic_selector
-            .evaluate_over_domain_by_ref(self.domain.d4);
+            .fft_evaluate_over_domain_by_ref(self.domain.d4);
 
         // RangeCheck0 constraint selector polynomials
         let range_check0_selector8 = {
@@ -593,10 +1044,20 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
             }
         };
 
+        let custom_selector8 = self.custom_gates.as_ref().map(|_| {
+            selector_polynomial(
+                GateType::Custom,
+                &self.gates,
+                &self.domain,
+                &self.domain.d8,
+                self.disable_gates_checks,
+            )
+        });
+
         // TODO: This doesn't need to be degree 8 but that would require some changes in expr
         let coefficients8 = array::from_fn(|i| {
             evaluated_column_coefficients.coefficients[i]
-                .evaluate_over_domain_by_ref(self.domain.d8)
+                .fft_evaluate_over_domain_by_ref(self.domain.d8)
         });
 
         ColumnEvaluations {
@@ -614,14 +1075,26 @@ impl<F: PrimeField + SquareRootField> ConstraintSystem<F> {
             foreign_field_mul_selector8,
             xor_selector8,
             rot_selector8,
+            custom_selector8,
         }
     }
 }
 
+/// The minimum number of zero-knowledge rows required by the permutation argument
+pub const MIN_ZK_ROWS: u64 = 3;
+
 pub fn zk_rows_strict_lower_bound(num_chunks: usize) -> usize {
     (2 * (PERMUTS + 1) * num_chunks - 2) / PERMUTS
 }
 
+/// Returns the number of chunks of the polynomials over a domain of size
+/// `domain_size`, when committed with an SRS of size `max_poly_size`.
+/// The SRS size does not have to divide the domain size, the last chunk being
+/// then smaller than the others.
+pub fn num_chunks(domain_size: usize, max_poly_size: usize) -> usize {
+    std::cmp::max(1, (domain_size + max_poly_size - 1) / max_poly_size)
+}
+
 impl FeatureFlags {
     pub fn from_gates_and_lookup_features<F: PrimeField>(
         gates: &[CircuitGate<F>],
@@ -724,17 +1197,175 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
         self
     }
 
+    /// Set up the custom gates that the circuit can use.
+    /// If not invoked, it is `None` by default.
+    ///
+    /// (see [crate::circuits::custom_gate]).
+    pub fn custom_gates(mut self, custom_gates: CustomGateRegistry<F>) -> Self {
+        self.custom_gates = Some(custom_gates);
+        self
+    }
+
+    /// Set up the application-specific fixed lookup tables.
+    /// The tables are added to the ones given to [Self::lookup].
+    /// If not invoked, it is `None` by default.
+    ///
+    /// (see [crate::circuits::lookup::tables::fixed]).
+    pub fn fixed_tables(mut self, fixed_tables: FixedTableRegistry<F>) -> Self {
+        self.fixed_tables = Some(fixed_tables);
+        self
+    }
+
+    /// Set up the argument used to prove the lookups.
+    /// If not invoked, it is [LookupArgument::Plookup] by default.
+    ///
+    /// (see [crate::circuits::lookup::constraints::LookupArgument]).
+    pub fn lookup_argument(mut self, lookup_argument: LookupArgument) -> Self {
+        self.lookup_argument = lookup_argument;
+        self
+    }
+
+    /// Set up the maximum number of lookups per row.
+    /// If not invoked, it is the largest number of lookups per row of the
+    /// lookup patterns used by the circuit.
+    /// The [GateType::Lookup] gates performing more lookups are split over
+    /// additional rows appended to the circuit, whose witness is computed by
+    /// [ConstraintSystem::extend_split_lookups_witness].
+    ///
+    /// (see [crate::circuits::lookup::lookups::LookupInfo::with_max_per_row]).
+    pub fn max_lookups_per_row(mut self, max_lookups_per_row: usize) -> Self {
+        self.max_lookups_per_row = Some(max_lookups_per_row);
+        self
+    }
+
+    /// Set up the number of zero-knowledge rows, randomized by the prover.
+    /// If not invoked, it is the smallest number of rows achieving zero-knowledge
+    /// with the number of chunks induced by [Self::max_poly_size].
+    /// A larger number adds blinding at the cost of rows of the domain, while a
+    /// smaller one is rejected by [Self::build].
+    pub fn zk_rows(mut self, zk_rows: u64) -> Self {
+        self.zk_rows = Some(zk_rows);
+        self
+    }
+
+    /// Set up the names of the public inputs, whose number is set to the
+    /// length of the layout.
+    /// If not invoked, it is `None` by default.
+    ///
+    /// (see [crate::circuits::public_input]).
+    pub fn public_input_layout(mut self, layout: PublicInputLayout) -> Self {
+        self.public = layout.len();
+        self.public_input_layout = Some(layout);
+        self
+    }
+
+    /// Set up whether the base gates that are not used by the circuit are
+    /// left out of the linearization and of the verifier index.
+    /// If not invoked, it is `false` by default, so that the verifier indexes
+    /// keep all the selector commitments, like the verification keys of Mina.
+    /// Enabling it changes the linearization and the digest of the verifier
+    /// index of the circuit.
+    ///
+    /// (see [BaseGates]).
+    pub fn prune_unused_gates(mut self, prune_unused_gates: bool) -> Self {
+        self.prune_unused_gates = prune_unused_gates;
+        self
+    }
+
+    /// Set up the size of the words of the XOR lookup table, which must be the
+    /// one of the coefficients of the [GateType::Xor16] gates.
+    /// If not invoked, it is the size of the first `Xor16` gate, or
+    /// [XorTableBits::Four] if there is none.
+    ///
+    /// (see [XorTableBits]).
+    pub fn xor_table_bits(mut self, xor_table_bits: XorTableBits) -> Self {
+        self.xor_table_bits = Some(xor_table_bits);
+        self
+    }
+
+    /// Set up the version of the Fiat-Shamir transcript of the proofs.
+    /// If not invoked, it is [TranscriptVersion::Legacy] by default, the
+    /// transcript of the proofs verified by Mina.
+    ///
+    /// (see [crate::transcript]).
+    pub fn transcript_version(mut self, transcript_version: TranscriptVersion) -> Self {
+        self.transcript_version = transcript_version;
+        self
+    }
+
     /// Build the [ConstraintSystem] from a [Builder].
     pub fn build(self) -> Result<ConstraintSystem<F>, SetupError> {
         let mut gates = self.gates;
-        let lookup_tables = self.lookup_tables;
+        let mut lookup_tables = self.lookup_tables;
+        if let Some(fixed_tables) = self.fixed_tables.as_ref() {
+            lookup_tables.extend(fixed_tables.tables().cloned());
+        }
         let runtime_tables = self.runtime_tables;
 
+        //~ 1. If a public input layout is set, check that it describes all the public inputs.
+        if let Some(layout) = &self.public_input_layout {
+            if layout.len() != self.public {
+                return Err(SetupError::ConstraintSystem(format!(
+                    "the public input layout has {} inputs, but the circuit has {}",
+                    layout.len(),
+                    self.public
+                )));
+            }
+        }
+
         //~ 1. If the circuit is less than 2 gates, abort.
         // for some reason we need more than 1 gate for the circuit to work, see TODO below
         assert!(gates.len() > 1);
 
+        //~ 1. If a maximum number of lookups per row is set, split the lookup gates exceeding it.
+        if let Some(max_lookups_per_row) = self.max_lookups_per_row {
+            split_lookups(&mut gates, max_lookups_per_row);
+        }
+
+        //~ 1. Check that the `Xor16` gates all decompose their words with the size of the
+        //~    XOR lookup table.
+        let xor_table_bits = {
+            let mut xor_gates = gates
+                .iter()
+                .enumerate()
+                .filter(|(_, gate)| gate.typ == GateType::Xor16);
+            let xor_table_bits = self
+                .xor_table_bits
+                .or_else(|| {
+                    let (_, gate) = xor_gates.clone().next()?;
+                    XorTableBits::from_coeffs(&gate.coeffs)
+                })
+                .unwrap_or_default();
+            let coeffs = xor_table_bits.coeffs::<F>();
+            if let Some((row, _)) = xor_gates.find(|(_, gate)| gate.coeffs != coeffs) {
+                return Err(SetupError::XorTable(row, xor_table_bits));
+            }
+            xor_table_bits
+        };
+
         let feature_flags = FeatureFlags::from_gates(&gates, runtime_tables.is_some());
+        let base_gates = if self.prune_unused_gates {
+            BaseGates::from_gates(&gates)
+        } else {
+            BaseGates::all()
+        };
+
+        //~ 1. If the circuit uses custom gates, check that they are all registered.
+        let custom_gates = if gates.iter().any(|gate| gate.typ == GateType::Custom) {
+            let custom_gates = self
+                .custom_gates
+                .ok_or(SetupError::CustomGate(CustomGateError::MissingRegistry))?;
+            for (row, gate) in gates.iter().enumerate() {
+                if gate.typ == GateType::Custom {
+                    custom_gates
+                        .resolve(row, gate)
+                        .map_err(SetupError::CustomGate)?;
+                }
+            }
+            Some(Arc::new(custom_gates))
+        } else {
+            None
+        };
 
         let lookup_domain_size = {
             // First we sum over the lookup table size
@@ -773,7 +1404,10 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
                 }
             }
             for gate_table in gate_lookup_tables.into_iter() {
-                lookup_domain_size += gate_table.table_size();
+                lookup_domain_size += match gate_table {
+                    GateLookupTable::Xor => xor_table_bits.table_size(),
+                    _ => gate_table.table_size(),
+                };
             }
 
             // A dummy zero entry will be added if there is no table with ID
@@ -808,6 +1442,10 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
         //~    domain_size = circuit_size + zk_rows
         //~    ```
         //~
+        //~    If the number of zero-knowledge rows is set by the user, it is used in place of
+        //~    `zk_rows` in the fixpoint, and it is an error if it does not satisfy the above
+        //~    constraints (and in any case if it is smaller than 3).
+        //~
         let (zk_rows, domain_size_lower_bound) = {
             // We add 1 to the lookup domain size because there is one element
             // used to close the permutation argument (the polynomial Z is of
@@ -815,7 +1453,10 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
             let circuit_lower_bound = std::cmp::max(gates.len(), lookup_domain_size + 1);
             let get_domain_size_lower_bound = |zk_rows: u64| circuit_lower_bound + zk_rows as usize;
 
-            let mut zk_rows = 3;
+            let mut zk_rows = self.zk_rows.unwrap_or(MIN_ZK_ROWS);
+            if zk_rows < MIN_ZK_ROWS {
+                return Err(SetupError::NotEnoughZkRows(MIN_ZK_ROWS, zk_rows));
+            }
             let mut domain_size_lower_bound = get_domain_size_lower_bound(zk_rows);
             if let Some(max_poly_size) = self.max_poly_size {
                 // Iterate to find a fixed-point where zk_rows is sufficient for the number of
@@ -828,12 +1469,16 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
                         .ok_or(SetupError::DomainCreation(
                             DomainCreationError::DomainSizeFailed(domain_size_lower_bound),
                         ))?;
-                    let num_chunks = if domain_size < max_poly_size {
-                        1
-                    } else {
-                        domain_size / max_poly_size
-                    };
-                    zk_rows = (zk_rows_strict_lower_bound(num_chunks) + 1) as u64;
+                    let num_chunks = num_chunks(domain_size, max_poly_size);
+                    let required_zk_rows = (zk_rows_strict_lower_bound(num_chunks) + 1) as u64;
+                    match self.zk_rows {
+                        Some(zk_rows) if zk_rows < required_zk_rows => {
+                            return Err(SetupError::NotEnoughZkRows(required_zk_rows, zk_rows));
+                        }
+                        // the domain size cannot grow with a fixed number of rows
+                        Some(_) => (),
+                        None => zk_rows = required_zk_rows,
+                    }
                     domain_size_lower_bound = get_domain_size_lower_bound(zk_rows);
                     domain_size < domain_size_lower_bound
                 } {}
@@ -849,6 +1494,13 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
 
         assert!(domain.d1.size > zk_rows);
 
+        //~ 1. If fixed tables were registered with a domain budget, check that the domain fits in it.
+        if let Some(fixed_tables) = self.fixed_tables.as_ref() {
+            fixed_tables
+                .check_budget(domain.d1.size())
+                .map_err(SetupError::FixedTable)?;
+        }
+
         //~ 1. Pad the circuit: add zero gates to reach the domain size.
         let d1_size = domain.d1.size();
         let mut padding = (gates.len()..d1_size)
@@ -873,6 +1525,9 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
             runtime_tables,
             &domain,
             zk_rows as usize,
+            self.lookup_argument,
+            self.max_lookups_per_row,
+            xor_table_bits,
         )
         .map_err(SetupError::LookupCreation)?;
 
@@ -895,8 +1550,13 @@ impl<F: PrimeField + SquareRootField> Builder<F> {
             //fr_sponge_params: self.sponge_params,
             lookup_constraint_system,
             feature_flags,
+            base_gates,
             precomputations: domain_constant_evaluation,
             disable_gates_checks: self.disable_gates_checks,
+            custom_gates,
+            public_input_layout: self.public_input_layout,
+            xor_table_bits,
+            transcript_version: self.transcript_version,
         };
 
         match self.precomputations {
@@ -955,6 +1615,7 @@ pub mod tests {
                     RuntimeTableCfg {
                         id: table_id,
                         first_column,
+                        unique_keys: false,
                     }
                 })
                 .collect();
@@ -0,0 +1,536 @@
+//! This module implements custom gates: gates that are defined outside of
+//! kimchi and registered with the constraint system builder, instead of being
+//! added as a new variant of [`GateType`].
+//!
+//! All the custom gates of a circuit share the [`GateType::Custom`] selector.
+//! The rows of a custom gate carry the identifier of the gate in their first
+//! coefficient (see [`CUSTOM_GATE_ID_COEFF`]), and the coefficients of the gate
+//! itself start right after it. When several custom gates are registered, the
+//! constraints of each gate are additionally multiplied by the Lagrange
+//! polynomial (over the registered identifiers) that selects its identifier, so
+//! that each gate is only enforced on its own rows.
+//!
+//! Note that this additional selector increases the degree of the constraints
+//! by the number of registered gates minus one, and that the combined degree
+//! must stay within the degree supported by the prover
+//! ([`MAX_CUSTOM_GATE_DEGREE`]), which is checked when registering a gate.
+//!
+//! Custom gates cannot declare lookups: the lookup patterns are selected by
+//! gate type, and all the custom gates share [`GateType::Custom`]. A custom
+//! gate whose cells must be looked up is laid out next to built-in lookup gates
+//! ([`GateType::Lookup`] or [`GateType::VectorLookup`]) wired to these cells by
+//! copy constraints.
+//!
+//! A [`CustomGateRegistry`] is serialized with the constraints of its gates, so
+//! that the verifier index of a circuit carries them. A deserialized registry
+//! holds the constraints as expressions, and only keeps the identifiers and
+//! names of the gates besides them: the witness layouts are not serialized.
+
+use crate::{
+    alphas::Alphas,
+    circuits::{
+        argument::{ArgumentEnv, ArgumentType},
+        berkeley_columns::{coeff, index, Column, E},
+        expr::{constraints::ExprOps, Cache, CacheId, ExprInner, Operations, Variable},
+        gate::{CircuitGate, CurrOrNext, GateType},
+        wires::{GateWires, COLUMNS},
+    },
+};
+use ark_ff::{One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::{Blake2b512, Digest};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    marker::PhantomData,
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// The coefficient in which the rows of a custom gate store its identifier
+pub const CUSTOM_GATE_ID_COEFF: usize = 0;
+
+/// The maximum number of constraints of a custom gate. The powers of alpha are
+/// shared by all the gates, and only as many as the constraints of the largest
+/// built-in gate ([`crate::circuits::polynomials::varbasemul::VarbaseMul`]) are
+/// reserved for them.
+pub const MAX_CUSTOM_GATE_CONSTRAINTS: u32 = 21;
+
+/// The maximum degree of the combined constraints of a custom gate, in number of
+/// polynomial factors, including the gate and identifier selectors. The prover
+/// evaluates the constraints over the domain `d8`, and cannot handle higher degrees.
+pub const MAX_CUSTOM_GATE_DEGREE: u64 = 8;
+
+/// Errors that can arise when registering or resolving custom gates
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CustomGateError {
+    #[error("the custom gate identifier {0} is registered twice")]
+    DuplicateId(u32),
+    #[error(
+        "the custom gate {name} has {constraints} constraints, but at most {max} are supported"
+    )]
+    TooManyConstraints {
+        name: String,
+        constraints: u32,
+        max: u32,
+    },
+    #[error(
+        "the constraints of the custom gate {name} have degree {degree} once selected, but at most {max} is supported"
+    )]
+    DegreeTooHigh { name: String, degree: u64, max: u64 },
+    #[error(
+        "the custom gate {name} lays out a witness cell in column {col}, which does not exist"
+    )]
+    InvalidLayout { name: String, col: usize },
+    #[error(
+        "the constraints of the custom gate {name} use a column or an expression that custom gates cannot use"
+    )]
+    InvalidConstraint { name: String },
+    #[error("the circuit uses custom gates, but no custom gates were registered")]
+    MissingRegistry,
+    #[error("the custom gate on row {0} has no identifier coefficient")]
+    MissingId(usize),
+    #[error("the custom gate on row {0} was not registered")]
+    UnknownGate(usize),
+}
+
+/// A named cell of the witness used by a custom gate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WitnessCell {
+    /// The row of the cell, relative to the row of the gate
+    pub row: CurrOrNext,
+    /// The column of the cell
+    pub col: usize,
+    /// A human-readable name for the value stored in the cell
+    pub name: &'static str,
+}
+
+/// A gate defined outside of kimchi.
+///
+/// This mirrors [`crate::circuits::argument::Argument`]: the constraints are
+/// written once against an [`ArgumentEnv`], and are used both to build the
+/// linearization and to check a witness.
+pub trait CustomGate<F: PrimeField>: 'static {
+    /// The identifier of the gate, stored in the [`CUSTOM_GATE_ID_COEFF`] coefficient of its rows
+    const ID: u32;
+
+    /// A human-readable name for the gate
+    const NAME: &'static str;
+
+    /// The number of constraints created by the gate
+    const CONSTRAINTS: u32;
+
+    /// The constraints of the gate. The coefficients of the gate start at
+    /// `CUSTOM_GATE_ID_COEFF + 1`.
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, cache: &mut Cache) -> Vec<T>;
+
+    /// The cells of the witness used by the gate
+    fn witness_layout() -> Vec<WitnessCell> {
+        vec![]
+    }
+}
+
+/// An object-safe version of [`CustomGate`], used to store gates in a [`CustomGateRegistry`]
+pub trait DynCustomGate<F: PrimeField>: Send + Sync {
+    fn id(&self) -> u32;
+    fn name(&self) -> &str;
+    fn num_constraints(&self) -> u32;
+    fn witness_layout(&self) -> Vec<WitnessCell>;
+    fn constraints(&self, cache: &mut Cache) -> Vec<E<F>>;
+    fn check_witness(&self, env: &ArgumentEnv<F, F>, cache: &mut Cache) -> Vec<F>;
+}
+
+struct RegisteredGate<G>(PhantomData<fn() -> G>);
+
+impl<F: PrimeField, G: CustomGate<F>> DynCustomGate<F> for RegisteredGate<G> {
+    fn id(&self) -> u32 {
+        G::ID
+    }
+
+    fn name(&self) -> &str {
+        G::NAME
+    }
+
+    fn num_constraints(&self) -> u32 {
+        G::CONSTRAINTS
+    }
+
+    fn witness_layout(&self) -> Vec<WitnessCell> {
+        G::witness_layout()
+    }
+
+    fn constraints(&self, cache: &mut Cache) -> Vec<E<F>> {
+        G::constraint_checks(&ArgumentEnv::default(), cache)
+    }
+
+    fn check_witness(&self, env: &ArgumentEnv<F, F>, cache: &mut Cache) -> Vec<F> {
+        G::constraint_checks(env, cache)
+    }
+}
+
+/// A custom gate read from a serialized [`CustomGateRegistry`], whose constraints
+/// are stored as expressions
+struct ExprGate<F> {
+    id: u32,
+    name: String,
+    constraints: Vec<E<F>>,
+}
+
+impl<F: PrimeField> DynCustomGate<F> for ExprGate<F> {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn num_constraints(&self) -> u32 {
+        self.constraints.len() as u32
+    }
+
+    fn witness_layout(&self) -> Vec<WitnessCell> {
+        vec![]
+    }
+
+    fn constraints(&self, cache: &mut Cache) -> Vec<E<F>> {
+        renumber_caches(&self.constraints, cache)
+    }
+
+    fn check_witness(&self, env: &ArgumentEnv<F, F>, _cache: &mut Cache) -> Vec<F> {
+        self.constraints
+            .iter()
+            .map(|constraint| evaluate_constraint(constraint, env))
+            .collect()
+    }
+}
+
+/// Gives the cached subexpressions of `constraints` new identifiers taken from
+/// `cache`, in the order in which they appear, so that they do not collide with
+/// the cached subexpressions of the other gates
+fn renumber_caches<F: PrimeField>(constraints: &[E<F>], cache: &mut Cache) -> Vec<E<F>> {
+    fn renumber<F: PrimeField>(
+        expr: &E<F>,
+        cache: &mut Cache,
+        renumbered: &mut HashMap<CacheId, E<F>>,
+    ) -> E<F> {
+        if let Operations::Cache(id, x) = expr {
+            if let Some(cached) = renumbered.get(id) {
+                return cached.clone();
+            }
+            let x = renumber(x, cache, renumbered);
+            let cached = cache.cache::<F, E<F>>(x);
+            renumbered.insert(*id, cached.clone());
+            return cached;
+        }
+        let mut go = |e: &E<F>| Box::new(renumber(e, cache, renumbered));
+        match expr {
+            Operations::Atom(_) | Operations::Cache(..) => expr.clone(),
+            Operations::Pow(x, p) => Operations::Pow(go(x), *p),
+            Operations::Add(x, y) => Operations::Add(go(x), go(y)),
+            Operations::Mul(x, y) => Operations::Mul(go(x), go(y)),
+            Operations::Sub(x, y) => Operations::Sub(go(x), go(y)),
+            Operations::Double(x) => Operations::Double(go(x)),
+            Operations::Square(x) => Operations::Square(go(x)),
+            Operations::IfFeature(feature, x, y) => Operations::IfFeature(*feature, go(x), go(y)),
+        }
+    }
+
+    let mut renumbered = HashMap::new();
+    constraints
+        .iter()
+        .map(|constraint| renumber(constraint, cache, &mut renumbered))
+        .collect()
+}
+
+/// Checks that `expr` only uses the cells of the witness and the coefficients
+/// of the gate, and constants, as the constraints created by a [`CustomGate`]
+fn is_valid_constraint<F>(expr: &E<F>) -> bool {
+    match expr {
+        Operations::Atom(ExprInner::Constant(_)) => true,
+        Operations::Atom(ExprInner::Cell(Variable { col, .. })) => {
+            matches!(col, Column::Witness(i) | Column::Coefficient(i) if *i < COLUMNS)
+        }
+        Operations::Atom(_) | Operations::IfFeature(..) => false,
+        Operations::Pow(x, _)
+        | Operations::Double(x)
+        | Operations::Square(x)
+        | Operations::Cache(_, x) => is_valid_constraint(x),
+        Operations::Add(x, y) | Operations::Mul(x, y) | Operations::Sub(x, y) => {
+            is_valid_constraint(x) && is_valid_constraint(y)
+        }
+    }
+}
+
+/// Evaluates a constraint checked by [`is_valid_constraint`] over the rows of a gate
+fn evaluate_constraint<F: PrimeField>(expr: &E<F>, env: &ArgumentEnv<F, F>) -> F {
+    match expr {
+        Operations::Atom(ExprInner::Constant(c)) => env.constant(c.clone()),
+        Operations::Atom(ExprInner::Cell(Variable { col, row })) => match col {
+            Column::Witness(i) => env.witness(*row, *i),
+            Column::Coefficient(i) => env.coeff(*i),
+            _ => unreachable!("the columns of custom gates are checked when deserializing"),
+        },
+        Operations::Atom(_) | Operations::IfFeature(..) => {
+            unreachable!("the constraints of custom gates are checked when deserializing")
+        }
+        Operations::Pow(x, p) => evaluate_constraint(x, env).pow([*p]),
+        Operations::Add(x, y) => evaluate_constraint(x, env) + evaluate_constraint(y, env),
+        Operations::Mul(x, y) => evaluate_constraint(x, env) * evaluate_constraint(y, env),
+        Operations::Sub(x, y) => evaluate_constraint(x, env) - evaluate_constraint(y, env),
+        Operations::Double(x) => evaluate_constraint(x, env).double(),
+        Operations::Square(x) => evaluate_constraint(x, env).square(),
+        Operations::Cache(_, x) => evaluate_constraint(x, env),
+    }
+}
+
+/// The serialized form of a custom gate
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "F: CanonicalSerialize + CanonicalDeserialize")]
+struct SerializedGate<F> {
+    id: u32,
+    name: String,
+    constraints: Vec<E<F>>,
+}
+
+/// The set of custom gates that can be used by a circuit
+#[derive(Clone)]
+pub struct CustomGateRegistry<F: PrimeField> {
+    gates: BTreeMap<u32, Arc<dyn DynCustomGate<F>>>,
+}
+
+impl<F: PrimeField> Default for CustomGateRegistry<F> {
+    fn default() -> Self {
+        Self {
+            gates: BTreeMap::new(),
+        }
+    }
+}
+
+impl<F: PrimeField> fmt::Debug for CustomGateRegistry<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.gates.iter().map(|(id, gate)| (id, gate.name())))
+            .finish()
+    }
+}
+
+impl<F: PrimeField> CustomGateRegistry<F> {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the custom gate `G`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the identifier of `G` is already registered, if `G`
+    /// has more constraints than a gate can have, if its witness layout refers
+    /// to columns that do not exist, or if the constraints of one of the
+    /// registered gates (whose identifier selectors grow with each
+    /// registration) exceed [`MAX_CUSTOM_GATE_DEGREE`].
+    pub fn register<G: CustomGate<F>>(&mut self) -> Result<(), CustomGateError> {
+        self.insert(Arc::new(RegisteredGate::<G>(PhantomData)))
+    }
+
+    /// Adds `gate` to the registry, see [`Self::register`]
+    fn insert(&mut self, gate: Arc<dyn DynCustomGate<F>>) -> Result<(), CustomGateError> {
+        if gate.num_constraints() > MAX_CUSTOM_GATE_CONSTRAINTS {
+            return Err(CustomGateError::TooManyConstraints {
+                name: gate.name().to_string(),
+                constraints: gate.num_constraints(),
+                max: MAX_CUSTOM_GATE_CONSTRAINTS,
+            });
+        }
+        if let Some(cell) = gate
+            .witness_layout()
+            .iter()
+            .find(|cell| cell.col >= COLUMNS)
+        {
+            return Err(CustomGateError::InvalidLayout {
+                name: gate.name().to_string(),
+                col: cell.col,
+            });
+        }
+        let id = gate.id();
+        if self.gates.contains_key(&id) {
+            return Err(CustomGateError::DuplicateId(id));
+        }
+        self.gates.insert(id, gate);
+        if let Err(err) = self.check_degrees() {
+            self.gates.remove(&id);
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Checks that the selected constraints of every registered gate can be
+    /// evaluated by the prover
+    fn check_degrees(&self) -> Result<(), CustomGateError> {
+        // the gate selector, and one factor per other identifier
+        let selectors_degree = self.gates.len() as u64;
+        for gate in self.iter() {
+            let constraints_degree = gate
+                .constraints(&mut Cache::default())
+                .iter()
+                .map(|constraint| constraint.degree(1, 0))
+                .max()
+                .unwrap_or(0);
+            let degree = selectors_degree + constraints_degree;
+            if degree > MAX_CUSTOM_GATE_DEGREE {
+                return Err(CustomGateError::DegreeTooHigh {
+                    name: gate.name().to_string(),
+                    degree,
+                    max: MAX_CUSTOM_GATE_DEGREE,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of registered gates
+    pub fn len(&self) -> usize {
+        self.gates.len()
+    }
+
+    /// Returns true if no gate is registered
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty()
+    }
+
+    /// Returns the gate registered under `id`
+    pub fn get(&self, id: u32) -> Option<&dyn DynCustomGate<F>> {
+        self.gates.get(&id).map(|gate| gate.as_ref())
+    }
+
+    /// Iterates over the registered gates, by increasing identifier
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DynCustomGate<F>> {
+        self.gates.values().map(|gate| gate.as_ref())
+    }
+
+    /// Returns the Blake2b digest of the identifiers and constraints of the
+    /// registered gates. A registry and its deserialized copy have the same
+    /// digest.
+    pub fn digest(&self) -> [u8; 64] {
+        let mut hasher = Blake2b512::new();
+        hasher.update((self.len() as u64).to_be_bytes());
+        for gate in self.iter() {
+            let constraints = renumber_caches(
+                &gate.constraints(&mut Cache::default()),
+                &mut Cache::default(),
+            );
+            hasher.update(gate.id().to_be_bytes());
+            hasher.update((constraints.len() as u64).to_be_bytes());
+            for constraint in constraints {
+                hasher.update(constraint.digest());
+            }
+        }
+        hasher.finalize().into()
+    }
+
+    /// Returns the gate used by `gate`, which lives on row `row` of the circuit.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `gate` has no identifier, or if its identifier is not registered.
+    pub fn resolve(
+        &self,
+        row: usize,
+        gate: &CircuitGate<F>,
+    ) -> Result<&dyn DynCustomGate<F>, CustomGateError> {
+        let id = gate
+            .coeffs
+            .get(CUSTOM_GATE_ID_COEFF)
+            .ok_or(CustomGateError::MissingId(row))?;
+        self.iter()
+            .find(|custom| F::from(custom.id()) == *id)
+            .ok_or(CustomGateError::UnknownGate(row))
+    }
+
+    /// The expression that is one on the rows of the gate `id`, and zero on the
+    /// rows of the other registered gates
+    fn id_selector(&self, id: u32) -> E<F> {
+        let id_field = F::from(id);
+        self.gates
+            .keys()
+            .filter(|other| **other != id)
+            .fold(E::one(), |acc, other| {
+                let other = F::from(*other);
+                let denominator = (id_field - other)
+                    .inverse()
+                    .expect("custom gate identifiers are distinct");
+                acc * ((coeff(CUSTOM_GATE_ID_COEFF) - E::literal(other)) * denominator)
+            })
+    }
+
+    /// The constraints of all the registered gates, combined with powers of alpha and selectors
+    pub fn combined_constraints(&self, alphas: &Alphas<F>, cache: &mut Cache) -> E<F> {
+        self.iter().fold(E::zero(), |acc, gate| {
+            let constraints = gate.constraints(cache);
+            assert_eq!(constraints.len(), gate.num_constraints() as usize);
+            let alphas =
+                alphas.get_exponents(ArgumentType::Gate(GateType::Custom), gate.num_constraints());
+            let combined_constraints = E::combine_constraints(alphas, constraints);
+            acc + index(GateType::Custom) * self.id_selector(gate.id()) * combined_constraints
+        })
+    }
+}
+
+impl<F: PrimeField> Serialize for CustomGateRegistry<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.iter()
+            .map(|gate| SerializedGate {
+                id: gate.id(),
+                name: gate.name().to_string(),
+                constraints: renumber_caches(
+                    &gate.constraints(&mut Cache::default()),
+                    &mut Cache::default(),
+                ),
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+}
+
+impl<'de, F: PrimeField> Deserialize<'de> for CustomGateRegistry<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut registry = Self::new();
+        for gate in Vec::<SerializedGate<F>>::deserialize(deserializer)? {
+            if !gate.constraints.iter().all(is_valid_constraint) {
+                return Err(D::Error::custom(CustomGateError::InvalidConstraint {
+                    name: gate.name,
+                }));
+            }
+            registry
+                .insert(Arc::new(ExprGate {
+                    id: gate.id,
+                    name: gate.name,
+                    constraints: gate.constraints,
+                }))
+                .map_err(D::Error::custom)?;
+        }
+        Ok(registry)
+    }
+}
+
+impl<F: PrimeField> CircuitGate<F> {
+    /// Creates a row of the custom gate `id`, with the coefficients `coeffs`
+    /// stored after its identifier.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are more coefficients than columns to store them.
+    pub fn create_custom(id: u32, wires: GateWires, coeffs: &[F]) -> Self {
+        assert!(
+            coeffs.len() < COLUMNS,
+            "a custom gate can have at most {} coefficients",
+            COLUMNS - 1
+        );
+        let mut all_coeffs = Vec::with_capacity(coeffs.len() + 1);
+        all_coeffs.push(F::from(id));
+        all_coeffs.extend_from_slice(coeffs);
+        CircuitGate::new(GateType::Custom, wires, all_coeffs)
+    }
+}
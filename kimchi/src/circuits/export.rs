@@ -0,0 +1,257 @@
+//! This module implements a file format for circuits, so that a circuit can be
+//! built by one party, audited, and loaded by provers and verifiers elsewhere.
+//!
+//! An [ExportedCircuit] holds the gates of a circuit, with their wiring and
+//! coefficients, along with the configuration of its lookups: the fixed lookup
+//! tables, the runtime tables and the lookup argument. It can be encoded as
+//! JSON, to be read by humans, or in a compact binary format
+//! ([MessagePack](https://msgpack.org)):
+//!
+//! ```
+//! use kimchi::circuits::{
+//!     export::ExportedCircuit,
+//!     gate::CircuitGate,
+//!     polynomials::generic::GenericGateSpec,
+//!     wires::Wire,
+//! };
+//! use mina_curves::pasta::Fp;
+//!
+//! let gates: Vec<_> = (0..4)
+//!     .map(|row| {
+//!         CircuitGate::<Fp>::create_generic_gadget(
+//!             Wire::for_row(row),
+//!             GenericGateSpec::Const((row as u64).into()),
+//!             None,
+//!         )
+//!     })
+//!     .collect();
+//! let circuit = ExportedCircuit::new(0, gates);
+//! let hash = circuit.hash();
+//!
+//! let json = circuit.to_json().unwrap();
+//! let loaded = ExportedCircuit::<Fp>::from_json(&json).unwrap();
+//! assert_eq!(loaded.verify_hash(&hash), Ok(()));
+//! let cs = loaded.constraint_system().build().unwrap();
+//! assert_eq!(cs.public, 0);
+//! ```
+//!
+//! The [hash](ExportedCircuit::hash) of a circuit is computed from its content
+//! and not from one of its encodings, so that the same circuit has the same
+//! hash in both formats. It covers the gates, the number of public inputs and
+//! the lookup configuration, including the order of the tables.
+
+use crate::circuits::{
+    constraints::{Builder, ConstraintSystem},
+    gate::{Circuit, CircuitGate},
+    lookup::{constraints::LookupArgument, runtime_tables::RuntimeTableCfg, tables::LookupTable},
+};
+use ark_ff::{PrimeField, SquareRootField};
+use o1_utils::hasher::CryptoDigest;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
+use thiserror::Error;
+
+/// The version of the circuit format, which is part of the exported circuits
+/// and of their hash
+pub const CIRCUIT_FORMAT_VERSION: u32 = 1;
+
+/// Errors that can arise when exporting or importing a circuit
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CircuitExportError {
+    #[error("the circuit could not be encoded: {0}")]
+    Encoding(String),
+    #[error("the circuit could not be decoded: {0}")]
+    Decoding(String),
+    #[error("the circuit format version {0} is not supported, expected {CIRCUIT_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("the circuit hash {found} does not match the expected hash {expected}")]
+    HashMismatch { expected: String, found: String },
+}
+
+/// A fixed lookup table of an exported circuit
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedLookupTable<F: PrimeField> {
+    /// The table ID
+    pub id: i32,
+    /// The columns of the table
+    #[serde_as(as = "Vec<Vec<o1_utils::serialization::SerdeAs>>")]
+    pub data: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> From<&LookupTable<F>> for ExportedLookupTable<F> {
+    fn from(table: &LookupTable<F>) -> Self {
+        Self {
+            id: table.id,
+            data: table.data.clone(),
+        }
+    }
+}
+
+impl<F: PrimeField> From<&ExportedLookupTable<F>> for LookupTable<F> {
+    fn from(table: &ExportedLookupTable<F>) -> Self {
+        Self {
+            id: table.id,
+            data: table.data.clone(),
+        }
+    }
+}
+
+/// A runtime table of an exported circuit
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedRuntimeTable<F: PrimeField> {
+    /// The table ID
+    pub id: i32,
+    /// The content of the first column of the table
+    #[serde_as(as = "Vec<o1_utils::serialization::SerdeAs>")]
+    pub first_column: Vec<F>,
+    /// Whether the entries of the first column must be distinct
+    pub unique_keys: bool,
+}
+
+impl<F: PrimeField> From<&RuntimeTableCfg<F>> for ExportedRuntimeTable<F> {
+    fn from(table: &RuntimeTableCfg<F>) -> Self {
+        Self {
+            id: table.id,
+            first_column: table.first_column.clone(),
+            unique_keys: table.unique_keys,
+        }
+    }
+}
+
+impl<F: PrimeField> From<&ExportedRuntimeTable<F>> for RuntimeTableCfg<F> {
+    fn from(table: &ExportedRuntimeTable<F>) -> Self {
+        Self {
+            id: table.id,
+            first_column: table.first_column.clone(),
+            unique_keys: table.unique_keys,
+        }
+    }
+}
+
+/// A circuit with its lookup configuration, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "CircuitGate<F>: Serialize + DeserializeOwned")]
+pub struct ExportedCircuit<F: PrimeField> {
+    /// The version of the format, see [CIRCUIT_FORMAT_VERSION]
+    pub version: u32,
+    /// The number of public inputs
+    pub public_input_size: usize,
+    /// The gates of the circuit, with their wiring
+    pub gates: Vec<CircuitGate<F>>,
+    /// The fixed lookup tables, in addition to the ones of the gates
+    pub lookup_tables: Vec<ExportedLookupTable<F>>,
+    /// The runtime tables
+    pub runtime_tables: Option<Vec<ExportedRuntimeTable<F>>>,
+    /// The argument used to prove the lookups
+    pub lookup_argument: LookupArgument,
+}
+
+impl<F: PrimeField> CryptoDigest for ExportedCircuit<F> {
+    const PREFIX: &'static [u8; 15] = b"kimchi-circfile";
+}
+
+impl<F: PrimeField> ExportedCircuit<F> {
+    /// Creates an exported circuit without lookup tables
+    pub fn new(public_input_size: usize, gates: Vec<CircuitGate<F>>) -> Self {
+        Self {
+            version: CIRCUIT_FORMAT_VERSION,
+            public_input_size,
+            gates,
+            lookup_tables: vec![],
+            runtime_tables: None,
+            lookup_argument: LookupArgument::default(),
+        }
+    }
+
+    /// Sets the fixed lookup tables of the circuit
+    pub fn lookup(mut self, lookup_tables: &[LookupTable<F>]) -> Self {
+        self.lookup_tables = lookup_tables.iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the runtime tables of the circuit
+    pub fn runtime(mut self, runtime_tables: Option<&[RuntimeTableCfg<F>]>) -> Self {
+        self.runtime_tables = runtime_tables.map(|tables| tables.iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the argument used to prove the lookups of the circuit
+    pub fn lookup_argument(mut self, lookup_argument: LookupArgument) -> Self {
+        self.lookup_argument = lookup_argument;
+        self
+    }
+
+    /// Returns the canonical hash of the circuit, which does not depend on its
+    /// encoding
+    pub fn hash(&self) -> [u8; 32] {
+        self.digest()
+    }
+
+    /// Checks that the hash of the circuit is `expected`, e.g. the hash of an
+    /// audited circuit
+    pub fn verify_hash(&self, expected: &[u8; 32]) -> Result<(), CircuitExportError> {
+        let found = self.hash();
+        if &found != expected {
+            return Err(CircuitExportError::HashMismatch {
+                expected: hex::encode(expected),
+                found: hex::encode(found),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the gates and the number of public inputs of the circuit
+    pub fn circuit(&self) -> Circuit<'_, F> {
+        Circuit::new(self.public_input_size, &self.gates)
+    }
+
+    /// Encodes the circuit as JSON
+    pub fn to_json(&self) -> Result<String, CircuitExportError> {
+        serde_json::to_string(self).map_err(|e| CircuitExportError::Encoding(e.to_string()))
+    }
+
+    /// Decodes a circuit from JSON, see [Self::to_json]
+    pub fn from_json(json: &str) -> Result<Self, CircuitExportError> {
+        let circuit: Self =
+            serde_json::from_str(json).map_err(|e| CircuitExportError::Decoding(e.to_string()))?;
+        circuit.check_version()
+    }
+
+    /// Encodes the circuit in the binary format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CircuitExportError> {
+        rmp_serde::to_vec(self).map_err(|e| CircuitExportError::Encoding(e.to_string()))
+    }
+
+    /// Decodes a circuit from the binary format, see [Self::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CircuitExportError> {
+        let circuit: Self = rmp_serde::from_slice(bytes)
+            .map_err(|e| CircuitExportError::Decoding(e.to_string()))?;
+        circuit.check_version()
+    }
+
+    fn check_version(self) -> Result<Self, CircuitExportError> {
+        if self.version != CIRCUIT_FORMAT_VERSION {
+            return Err(CircuitExportError::UnsupportedVersion(self.version));
+        }
+        Ok(self)
+    }
+}
+
+impl<F: PrimeField + SquareRootField> ExportedCircuit<F> {
+    /// Returns a builder of the constraint system of the circuit, which can be
+    /// further configured before being built
+    pub fn constraint_system(&self) -> Builder<F> {
+        ConstraintSystem::create(self.gates.clone())
+            .public(self.public_input_size)
+            .lookup(self.lookup_tables.iter().map(Into::into).collect())
+            .runtime(
+                self.runtime_tables
+                    .as_ref()
+                    .map(|tables| tables.iter().map(Into::into).collect()),
+            )
+            .lookup_argument(self.lookup_argument)
+    }
+}
@@ -14,10 +14,13 @@ use ark_ff::{FftField, Field, One, PrimeField, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::{Blake2b512, Digest};
 use itertools::Itertools;
 use o1_utils::{foreign_field::ForeignFieldHelpers, FieldHelpers};
 use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
@@ -170,11 +173,13 @@ pub enum ChallengeTerm {
 /// semantic in the expression framework.
 /// TODO: we should generalize the expression type over challenges and constants.
 /// See <https://github.com/MinaProtocol/mina/issues/15287>
+#[serde_as]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "F: CanonicalSerialize + CanonicalDeserialize")]
 pub enum ConstantTerm<F> {
     EndoCoefficient,
     Mds { row: usize, col: usize },
-    Literal(F),
+    Literal(#[serde_as(as = "o1_utils::serialization::SerdeAs")] F),
 }
 
 pub trait Literal: Sized + Clone {
@@ -234,7 +239,8 @@ impl<F: Clone> Literal for ConstantTerm<F> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "F: CanonicalSerialize + CanonicalDeserialize")]
 pub enum ConstantExprInner<F> {
     Challenge(ChallengeTerm),
     Constant(ConstantTerm<F>),
@@ -280,7 +286,7 @@ impl<F> From<ConstantTerm<F>> for ConstantExprInner<F> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Operations<T> {
     Atom(T),
     Pow(Box<Self>, u64),
@@ -508,7 +514,7 @@ impl<F: Field> ConstantExpr<F> {
 }
 
 /// A key for a cached value
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct CacheId(usize);
 
 /// A cache
@@ -600,7 +606,7 @@ pub struct RowOffset {
     pub offset: i32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ExprInner<C, Column> {
     Constant(C),
     Cell(Variable<Column>),
@@ -806,6 +812,9 @@ where
 /// [reverse Polish notation](https://en.wikipedia.org/wiki/Reverse_Polish_notation)
 /// expressions, which are vectors of the below tokens.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    bound = "F: CanonicalSerialize + CanonicalDeserialize, Column: Serialize + DeserializeOwned"
+)]
 pub enum PolishToken<F, Column> {
     Constant(ConstantTerm<F>),
     Challenge(ChallengeTerm),
@@ -1663,6 +1672,14 @@ impl<F: Field, Column: PartialEq + Copy> Expr<ConstantExpr<F>, Column> {
     }
 }
 
+impl<F: PrimeField, Column: Copy + Debug> Expr<ConstantExpr<F>, Column> {
+    /// Returns the hexadecimal Blake2b digest of the expression compiled to
+    /// reverse Polish notation, see [polish_digest].
+    pub fn digest(&self) -> String {
+        polish_digest(&self.to_polish())
+    }
+}
+
 impl<F: FftField, Column: Copy> Expr<ConstantExpr<F>, Column> {
     /// Compile an expression to an RPN expression.
     pub fn to_polish(&self) -> Vec<PolishToken<F, Column>> {
@@ -1768,6 +1785,217 @@ impl<F: FftField, Column: Copy> Expr<ConstantExpr<F>, Column> {
     }
 }
 
+/// The key identifying a node of an expression up to its value, the children
+/// being given by their number in [ValueNumbering]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum NodeKey {
+    Atom(usize),
+    Pow(usize, u64),
+    Add(usize, usize),
+    Mul(usize, usize),
+    Sub(usize, usize),
+    Double(usize),
+    Square(usize),
+    Cache(CacheId),
+    /// A node which is never shared, see [Expr::optimize]
+    Opaque(usize),
+}
+
+/// An expression whose nodes are numbered by value, see [ValueNumbering]
+struct NumberedExpr {
+    number: usize,
+    children: Vec<NumberedExpr>,
+}
+
+/// The numbering of the nodes of an expression, two nodes having the same
+/// number if and only if they have the same key, so that they have the same
+/// value
+struct ValueNumbering<'a, C, Column> {
+    atoms: Vec<&'a ExprInner<C, Column>>,
+    numbers: HashMap<NodeKey, usize>,
+    /// Whether the nodes with the number can be cached
+    cacheable: Vec<bool>,
+}
+
+impl<'a, C: PartialEq, Column: PartialEq> ValueNumbering<'a, C, Column> {
+    fn number(&mut self, key: NodeKey, cacheable: bool) -> usize {
+        let next = self.numbers.len();
+        let number = *self.numbers.entry(key).or_insert(next);
+        if number == next {
+            self.cacheable.push(cacheable);
+        }
+        number
+    }
+
+    fn numbered(&mut self, expr: &'a Expr<C, Column>) -> NumberedExpr {
+        use Operations::*;
+        let children = match expr {
+            Atom(_) | IfFeature(..) => vec![],
+            Pow(x, _) | Double(x) | Square(x) | Cache(_, x) => vec![self.numbered(x)],
+            Add(x, y) | Mul(x, y) | Sub(x, y) => vec![self.numbered(x), self.numbered(y)],
+        };
+        let child = |i: usize| children[i].number;
+        // the operands of the commutative operations are ordered
+        let (key, cacheable) = match expr {
+            Atom(atom) => {
+                let i = match self.atoms.iter().position(|a| *a == atom) {
+                    Some(i) => i,
+                    None => {
+                        self.atoms.push(atom);
+                        self.atoms.len() - 1
+                    }
+                };
+                (NodeKey::Atom(i), false)
+            }
+            Pow(_, n) => (NodeKey::Pow(child(0), *n), true),
+            Add(..) => (
+                NodeKey::Add(child(0).min(child(1)), child(0).max(child(1))),
+                true,
+            ),
+            Mul(..) => (
+                NodeKey::Mul(child(0).min(child(1)), child(0).max(child(1))),
+                true,
+            ),
+            Sub(..) => (NodeKey::Sub(child(0), child(1)), true),
+            Double(_) => (NodeKey::Double(child(0)), true),
+            Square(_) => (NodeKey::Square(child(0)), true),
+            Cache(id, _) => (NodeKey::Cache(*id), false),
+            // The caches of the branches of a feature flag are not visible
+            // outside of them, see `to_polish`
+            IfFeature(..) => (NodeKey::Opaque(self.numbers.len()), false),
+        };
+        let number = self.number(key, cacheable);
+        NumberedExpr { number, children }
+    }
+}
+
+/// Counts the occurrences of each number in the expression, the children of a
+/// node being only counted at its first occurrence, as the next ones are loaded
+/// from the cache
+fn count_occurrences(expr: &NumberedExpr, counts: &mut [usize]) {
+    counts[expr.number] += 1;
+    if counts[expr.number] == 1 {
+        for child in &expr.children {
+            count_occurrences(child, counts);
+        }
+    }
+}
+
+impl<F: Field, Column: PartialEq + Clone> Expr<ConstantExpr<F>, Column> {
+    /// Optimizes the expression for its evaluation, with a constant folding
+    /// pass followed by a common subexpression elimination pass:
+    ///
+    /// - the operations on literals are computed, and the operations with the
+    ///   neutral and absorbing elements are removed.
+    /// - the subexpressions occurring more than once are cached, so that they
+    ///   are computed only once by the evaluation of the expression (see
+    ///   [Expr::to_polish]). Two subexpressions are the same if they only
+    ///   differ by the order of the operands of additions and
+    ///   multiplications.
+    ///
+    /// The caches already present in the expression are kept, and the new
+    /// caches get fresh IDs. The subexpressions under an
+    /// [Operations::IfFeature] are not shared with the rest of the
+    /// expression, as their caches are local to the branches.
+    pub fn optimize(&self) -> Self {
+        self.fold_constants().eliminate_common_subexpressions()
+    }
+
+    fn fold_constants(&self) -> Self {
+        use Operations::*;
+        let literal = |e: &Self| e.to_literal_ref().copied();
+        match self {
+            Atom(_) => self.clone(),
+            Add(x, y) => x.fold_constants() + y.fold_constants(),
+            Sub(x, y) => x.fold_constants() - y.fold_constants(),
+            Mul(x, y) => x.fold_constants() * y.fold_constants(),
+            Pow(x, 1) => x.fold_constants(),
+            Pow(x, n) => x.fold_constants().pow(*n),
+            Double(x) => {
+                let x = x.fold_constants();
+                match literal(&x) {
+                    Some(x) => Self::literal(x.double()),
+                    None => Double(Box::new(x)),
+                }
+            }
+            Square(x) => {
+                let x = x.fold_constants();
+                match literal(&x) {
+                    Some(x) => Self::literal(x.square()),
+                    None => Square(Box::new(x)),
+                }
+            }
+            Cache(id, x) => {
+                let x = x.fold_constants();
+                if literal(&x).is_some() {
+                    x
+                } else {
+                    Cache(*id, Box::new(x))
+                }
+            }
+            IfFeature(feature, x, y) => IfFeature(
+                *feature,
+                Box::new(x.fold_constants()),
+                Box::new(y.fold_constants()),
+            ),
+        }
+    }
+
+    fn eliminate_common_subexpressions(&self) -> Self {
+        let mut numbering = ValueNumbering {
+            atoms: vec![],
+            numbers: HashMap::new(),
+            cacheable: vec![],
+        };
+        let numbered = numbering.numbered(self);
+        let mut counts = vec![0; numbering.cacheable.len()];
+        count_occurrences(&numbered, &mut counts);
+
+        let mut next_id = self.max_cache_id().map_or(0, |CacheId(id)| id + 1);
+        let mut ids = HashMap::new();
+        for (number, &count) in counts.iter().enumerate() {
+            if count > 1 && numbering.cacheable[number] {
+                ids.insert(number, CacheId(next_id));
+                next_id += 1;
+            }
+        }
+        self.with_caches(&numbered, &ids)
+    }
+
+    /// Returns the largest cache ID of the expression
+    fn max_cache_id(&self) -> Option<CacheId> {
+        use Operations::*;
+        match self {
+            Atom(_) => None,
+            Pow(x, _) | Double(x) | Square(x) => x.max_cache_id(),
+            Add(x, y) | Mul(x, y) | Sub(x, y) | IfFeature(_, x, y) => {
+                x.max_cache_id().max(y.max_cache_id())
+            }
+            Cache(id, x) => Some(*id).max(x.max_cache_id()),
+        }
+    }
+
+    /// Wraps the nodes whose number has an ID in `ids` in a cache
+    fn with_caches(&self, numbered: &NumberedExpr, ids: &HashMap<usize, CacheId>) -> Self {
+        use Operations::*;
+        let child = |x: &Self, i: usize| Box::new(x.with_caches(&numbered.children[i], ids));
+        let expr = match self {
+            Atom(_) | IfFeature(..) => self.clone(),
+            Pow(x, n) => Pow(child(x, 0), *n),
+            Add(x, y) => Add(child(x, 0), child(y, 1)),
+            Mul(x, y) => Mul(child(x, 0), child(y, 1)),
+            Sub(x, y) => Sub(child(x, 0), child(y, 1)),
+            Double(x) => Double(child(x, 0)),
+            Square(x) => Square(child(x, 0)),
+            Cache(id, x) => Cache(*id, child(x, 0)),
+        };
+        match ids.get(&numbered.number) {
+            Some(id) => Cache(*id, Box::new(expr)),
+            None => expr,
+        }
+    }
+}
+
 impl<F: FftField, Column: PartialEq + Copy> Expr<ConstantExpr<F>, Column> {
     fn evaluate_constants_(&self, c: &Constants<F>, chals: &Challenges<F>) -> Expr<F, Column> {
         use ExprInner::*;
@@ -1884,6 +2112,16 @@ impl<F: FftField, Column: PartialEq + Copy> Expr<ConstantExpr<F>, Column> {
     ) -> Evaluations<F, D<F>> {
         self.evaluate_constants(env).evaluations(env)
     }
+
+    /// Same as [Expr::evaluations], but evaluating the polynomial coset by
+    /// coset, see [Expr::evaluations_by_coset].
+    pub fn evaluations_by_coset<'a, Environment>(&self, env: &Environment) -> Evaluations<F, D<F>>
+    where
+        Environment: ColumnEnvironment<'a, F, Column = Column> + Sync,
+        Column: Sync + Send,
+    {
+        self.evaluate_constants(env).evaluations_by_coset(env)
+    }
 }
 
 /// Use as a result of the expression evaluations routine.
@@ -1948,14 +2186,15 @@ impl<F: FftField, Column: Copy> Expr<F, Column> {
         }
     }
 
-    /// Compute the polynomial corresponding to this expression, in evaluation form.
-    pub fn evaluations<'a, Environment: ColumnEnvironment<'a, F, Column = Column>>(
+    // The smallest domain over which the polynomial of this expression can be
+    // evaluated
+    fn evaluation_domain<'a, Environment: ColumnEnvironment<'a, F, Column = Column>>(
         &self,
         env: &Environment,
-    ) -> Evaluations<F, D<F>> {
+    ) -> Domain {
         let d1_size = env.get_domain(Domain::D1).size;
         let deg = self.degree(d1_size, env.get_constants().zk_rows);
-        let d = if deg <= d1_size {
+        if deg <= d1_size {
             Domain::D1
         } else if deg <= 4 * d1_size {
             Domain::D4
@@ -1963,7 +2202,15 @@ impl<F: FftField, Column: Copy> Expr<F, Column> {
             Domain::D8
         } else {
             panic!("constraint had degree {deg} > d8 ({})", 8 * d1_size);
-        };
+        }
+    }
+
+    /// Compute the polynomial corresponding to this expression, in evaluation form.
+    pub fn evaluations<'a, Environment: ColumnEnvironment<'a, F, Column = Column>>(
+        &self,
+        env: &Environment,
+    ) -> Evaluations<F, D<F>> {
+        let d = self.evaluation_domain(env);
 
         let mut cache = HashMap::new();
 
@@ -2163,6 +2410,358 @@ impl<F: FftField, Column: Copy> Expr<F, Column> {
         };
         Either::Left(res)
     }
+
+    /// Compute the polynomial corresponding to this expression, in evaluation
+    /// form, like [Expr::evaluations].
+    ///
+    /// The evaluation domain of size `k * n` is split into the `k` cosets of
+    /// the domain `d1` of size `n`, which are evaluated in parallel. The
+    /// evaluation of a coset only allocates buffers of size `n`, which are
+    /// reused across the sub-expressions, instead of a new evaluation over the
+    /// whole domain for each of them.
+    pub fn evaluations_by_coset<'a, Environment>(&self, env: &Environment) -> Evaluations<F, D<F>>
+    where
+        Environment: ColumnEnvironment<'a, F, Column = Column> + Sync,
+        Column: Sync + Send,
+    {
+        let d = self.evaluation_domain(env);
+        let k = d as usize;
+        let n = env.get_domain(Domain::D1).size();
+
+        let cosets: Vec<Vec<F>> = (0..k)
+            .into_par_iter()
+            .map(|j| {
+                let coset = Coset { domain: d, k, j, n };
+                let mut scratch = Scratch::new(n);
+                let mut cache = HashMap::new();
+                let res = match self.coset_helper(&mut cache, coset, env, &mut scratch) {
+                    Either::Left(x) => x,
+                    Either::Right(id) => cache.remove(&id).unwrap(),
+                };
+                res.into_vec(n)
+            })
+            .collect();
+
+        // the point `k * q + j` of the domain is the point `q` of the coset `j`
+        let mut evals = vec![F::zero(); k * n];
+        evals.par_chunks_mut(k).enumerate().for_each(|(q, points)| {
+            for (j, point) in points.iter_mut().enumerate() {
+                *point = cosets[j][q];
+            }
+        });
+        Evaluations::from_vec_and_domain(evals, env.get_domain(d))
+    }
+
+    fn coset_helper<'a, 'b, Environment: ColumnEnvironment<'a, F, Column = Column>>(
+        &self,
+        cache: &'b mut HashMap<CacheId, CosetResult<'a, F>>,
+        coset: Coset,
+        env: &Environment,
+        scratch: &mut Scratch<F>,
+    ) -> Either<CosetResult<'a, F>, CacheId>
+    where
+        'a: 'b,
+    {
+        // the result of a sub-expression, borrowed from the cache if needed
+        macro_rules! get {
+            ($x:expr) => {
+                match $x {
+                    Either::Left(x) => x,
+                    Either::Right(id) => id.get_coset(cache).unwrap(),
+                }
+            };
+        }
+
+        let res: CosetResult<'a, F> = match self {
+            Expr::Atom(ExprInner::Constant(x)) => CosetResult::Constant(*x),
+            Expr::Atom(ExprInner::Cell(Variable { col, row })) => match env.get_column(col) {
+                None => CosetResult::Constant(F::zero()),
+                Some(evals) => coset.column(evals, env.column_domain(col), row.shift()),
+            },
+            Expr::Atom(ExprInner::VanishesOnZeroKnowledgeAndPreviousRows) => coset.column(
+                env.vanishes_on_zero_knowledge_and_previous_rows(),
+                Domain::D8,
+                0,
+            ),
+            Expr::Atom(ExprInner::UnnormalizedLagrangeBasis(i)) => {
+                let offset = if i.zk_rows {
+                    -(env.get_constants().zk_rows as i32) + i.offset
+                } else {
+                    i.offset
+                };
+                let mut evals = scratch.take();
+                coset.unnormalized_lagrange_evals(env.l0_1(), offset, env, &mut evals);
+                CosetResult::Evals(evals)
+            }
+            Expr::Cache(id, e) => {
+                if !cache.contains_key(id) {
+                    if let Either::Left(es) = e.coset_helper(cache, coset, env, scratch) {
+                        cache.insert(*id, es);
+                    }
+                }
+                return Either::Right(*id);
+            }
+            Expr::IfFeature(feature, e1, e2) => {
+                /* Clone the cache, to make sure we don't try to access cached statements later
+                when the feature flag is off. */
+                let mut cache = cache.clone();
+                let e = if feature.is_enabled() { e1 } else { e2 };
+                match e.coset_helper(&mut cache, coset, env, scratch) {
+                    Either::Left(x) => x,
+                    Either::Right(id) => cache.remove(&id).unwrap(),
+                }
+            }
+            Expr::Double(x) => {
+                let x = x.coset_helper(cache, coset, env, scratch);
+                get!(x).map(scratch, |x| x.double())
+            }
+            Expr::Square(x) => {
+                let x = x.coset_helper(cache, coset, env, scratch);
+                get!(x).map(scratch, |x| x.square())
+            }
+            Expr::Pow(x, p) => {
+                let x = x.coset_helper(cache, coset, env, scratch);
+                get!(x).map(scratch, |x| x.pow([*p]))
+            }
+            Expr::Add(e1, e2) => {
+                let e1 = e1.coset_helper(cache, coset, env, scratch);
+                let e2 = e2.coset_helper(cache, coset, env, scratch);
+                get!(e1).zip_with(get!(e2), scratch, |x, y| x + y)
+            }
+            Expr::Sub(e1, e2) => {
+                let e1 = e1.coset_helper(cache, coset, env, scratch);
+                let e2 = e2.coset_helper(cache, coset, env, scratch);
+                get!(e1).zip_with(get!(e2), scratch, |x, y| x - y)
+            }
+            Expr::Mul(e1, e2) => {
+                let e1 = e1.coset_helper(cache, coset, env, scratch);
+                let e2 = e2.coset_helper(cache, coset, env, scratch);
+                get!(e1).zip_with(get!(e2), scratch, |x, y| x * y)
+            }
+        };
+        Either::Left(res)
+    }
+}
+
+/// A coset `j` of the domain `d1` of size `n` in the evaluation domain of size
+/// `k * n`, made of the points `k * q + j` of the evaluation domain
+#[derive(Copy, Clone)]
+struct Coset {
+    domain: Domain,
+    k: usize,
+    j: usize,
+    n: usize,
+}
+
+impl Coset {
+    /// The evaluations over the coset of a column evaluated over `column_domain`,
+    /// at the row shifted by `shift`
+    fn column<'a, F: FftField>(
+        &self,
+        evals: &'a Evaluations<F, D<F>>,
+        column_domain: Domain,
+        shift: usize,
+    ) -> CosetResult<'a, F> {
+        let d_sub = column_domain as usize;
+        let scale = d_sub / self.k;
+        assert!(
+            scale != 0,
+            "Check that the implementation of
+                column_domain and the evaluation domain of the
+                witnesses are the same"
+        );
+        CosetResult::Column {
+            evals: &evals.evals,
+            start: scale * self.j + d_sub * shift,
+            stride: d_sub,
+        }
+    }
+
+    /// Writes the evaluations over the coset of the unnormalized lagrange
+    /// polynomial `l_i`, see [unnormalized_lagrange_evals]
+    fn unnormalized_lagrange_evals<'a, F: FftField, Environment: ColumnEnvironment<'a, F>>(
+        &self,
+        l0_1: F,
+        i: i32,
+        env: &Environment,
+        evals: &mut [F],
+    ) {
+        let d1 = env.get_domain(Domain::D1);
+        let n = self.n as u64;
+        let i = if i < 0 {
+            ((i as isize) + (n as isize)) as usize
+        } else {
+            i as usize
+        };
+        let ii = i as u64;
+        assert!(ii < n);
+        let omega = d1.group_gen;
+
+        if self.j == 0 {
+            // l_i vanishes on d1, except at omega^i
+            evals.iter_mut().for_each(|e| *e = F::zero());
+            evals[i] = omega.pow([n - ii]) * l0_1;
+            return;
+        }
+
+        // The points of the coset are omega^q omega_k^j, where
+        // (omega^q omega_k^j)^n = omega_k^{j n} is constant
+        let omega_i = omega.pow([ii]);
+        let omega_k_j = env.get_domain(self.domain).group_gen.pow([self.j as u64]);
+        let mut x = omega_k_j;
+        for e in evals.iter_mut() {
+            *e = x - omega_i;
+            x *= omega;
+        }
+        ark_ff::fields::batch_inversion::<F>(evals);
+        let numerator = omega_k_j.pow([n]) - F::one();
+        evals.par_iter_mut().for_each(|e| *e *= numerator);
+    }
+}
+
+/// The evaluations of a sub-expression over a [Coset]
+#[derive(Clone)]
+enum CosetResult<'a, F> {
+    Constant(F),
+    /// The evaluations, in a buffer of the [Scratch]
+    Evals(Vec<F>),
+    /// The evaluations borrowed from a column, the point `q` of the coset being
+    /// at the index `(start + stride * q) % evals.len()`
+    Column {
+        evals: &'a [F],
+        start: usize,
+        stride: usize,
+    },
+}
+
+impl<'a, F: FftField> CosetResult<'a, F> {
+    fn get(&self, q: usize) -> F {
+        match self {
+            CosetResult::Constant(x) => *x,
+            CosetResult::Evals(evals) => evals[q],
+            CosetResult::Column {
+                evals,
+                start,
+                stride,
+            } => evals[(start + stride * q) % evals.len()],
+        }
+    }
+
+    fn into_vec(self, n: usize) -> Vec<F> {
+        match self {
+            CosetResult::Evals(evals) => evals,
+            x => (0..n).into_par_iter().map(|q| x.get(q)).collect(),
+        }
+    }
+
+    /// Applies `f` to the evaluations, in place if they are owned
+    fn map<'c>(self, scratch: &mut Scratch<F>, f: impl Fn(F) -> F + Sync) -> CosetResult<'c, F> {
+        match self {
+            CosetResult::Constant(x) => CosetResult::Constant(f(x)),
+            CosetResult::Evals(mut evals) => {
+                evals.par_iter_mut().for_each(|x| *x = f(*x));
+                CosetResult::Evals(evals)
+            }
+            x => {
+                let mut evals = scratch.take();
+                evals
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(q, e)| *e = f(x.get(q)));
+                CosetResult::Evals(evals)
+            }
+        }
+    }
+
+    /// Combines the evaluations with `f`, in the buffer of one of them if they
+    /// are owned, and recycles the other buffer
+    fn zip_with<'c>(
+        self,
+        other: CosetResult<'_, F>,
+        scratch: &mut Scratch<F>,
+        f: impl Fn(F, F) -> F + Sync,
+    ) -> CosetResult<'c, F> {
+        use CosetResult::*;
+        match (self, other) {
+            (Constant(x), Constant(y)) => Constant(f(x, y)),
+            (Evals(mut evals), y) => {
+                evals
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(q, e)| *e = f(*e, y.get(q)));
+                scratch.recycle(y);
+                Evals(evals)
+            }
+            (x, Evals(mut evals)) => {
+                evals
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(q, e)| *e = f(x.get(q), *e));
+                Evals(evals)
+            }
+            (x, y) => {
+                let mut evals = scratch.take();
+                evals
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(q, e)| *e = f(x.get(q), y.get(q)));
+                Evals(evals)
+            }
+        }
+    }
+}
+
+/// The buffers of the size of a [Coset] freed by the evaluation of the
+/// sub-expressions, to be reused by the next ones
+struct Scratch<F> {
+    size: usize,
+    buffers: Vec<Vec<F>>,
+}
+
+impl<F: Field> Scratch<F> {
+    fn new(size: usize) -> Self {
+        Scratch {
+            size,
+            buffers: vec![],
+        }
+    }
+
+    fn take(&mut self) -> Vec<F> {
+        self.buffers
+            .pop()
+            .unwrap_or_else(|| vec![F::zero(); self.size])
+    }
+
+    fn recycle(&mut self, x: CosetResult<'_, F>) {
+        if let CosetResult::Evals(evals) = x {
+            self.buffers.push(evals)
+        }
+    }
+}
+
+impl CacheId {
+    fn get_coset<'b, F: FftField>(
+        &self,
+        cache: &'b HashMap<CacheId, CosetResult<'_, F>>,
+    ) -> Option<CosetResult<'b, F>> {
+        cache.get(self).map(|e| match e {
+            CosetResult::Constant(x) => CosetResult::Constant(*x),
+            CosetResult::Evals(evals) => CosetResult::Column {
+                evals,
+                start: 0,
+                stride: 1,
+            },
+            CosetResult::Column {
+                evals,
+                start,
+                stride,
+            } => CosetResult::Column {
+                evals,
+                start: *start,
+                stride: *stride,
+            },
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -2192,6 +2791,56 @@ impl<A, Column: Copy> Linearization<A, Column> {
     }
 }
 
+/// Updates the hasher with an encoding of the tokens that does not depend on
+/// the representation of the field elements
+fn update_with_polish<F: PrimeField, Column: Debug>(
+    hasher: &mut Blake2b512,
+    tokens: &[PolishToken<F, Column>],
+) {
+    hasher.update((tokens.len() as u64).to_be_bytes());
+    for token in tokens {
+        let encoded = match token {
+            PolishToken::Constant(ConstantTerm::Literal(x)) => {
+                format!("Literal({})", x.to_biguint())
+            }
+            token => format!("{token:?}"),
+        };
+        hasher.update((encoded.len() as u64).to_be_bytes());
+        hasher.update(encoded);
+    }
+}
+
+/// Returns the hexadecimal Blake2b digest of an expression in reverse Polish
+/// notation.
+///
+/// The digest only depends on the structure of the expression, so that two
+/// builds of this crate agree on it if and only if they produce the same
+/// expression.
+pub fn polish_digest<F: PrimeField, Column: Debug>(tokens: &[PolishToken<F, Column>]) -> String {
+    let mut hasher = Blake2b512::new();
+    update_with_polish(&mut hasher, tokens);
+    hex::encode(hasher.finalize())
+}
+
+impl<F: PrimeField, Column: Debug> Linearization<Vec<PolishToken<F, Column>>, Column> {
+    /// Returns the hexadecimal Blake2b digest of the linearization, see
+    /// [polish_digest].
+    /// A verifier can compare it to the digest of the linearization of the
+    /// prover to detect a change in the constraints of the circuit.
+    pub fn digest(&self) -> String {
+        let mut hasher = Blake2b512::new();
+        update_with_polish(&mut hasher, &self.constant_term);
+        hasher.update((self.index_terms.len() as u64).to_be_bytes());
+        for (col, tokens) in &self.index_terms {
+            let col = format!("{col:?}");
+            hasher.update((col.len() as u64).to_be_bytes());
+            hasher.update(col);
+            update_with_polish(&mut hasher, tokens);
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
 impl<F: FftField, Column: PartialEq + Copy> Linearization<Expr<ConstantExpr<F>, Column>, Column> {
     /// Evaluate the constants in a linearization with `ConstantExpr<F>` coefficients down
     /// to literal field elements.
@@ -3406,7 +4055,7 @@ pub mod test {
     use super::*;
     use crate::{
         circuits::{
-            berkeley_columns::{index, witness_curr, Environment, E},
+            berkeley_columns::{coeff, index, witness_curr, witness_next, Environment, E},
             constraints::ConstraintSystem,
             domains::EvaluationDomains,
             expr::constraints::ExprOps,
@@ -3415,9 +4064,11 @@ pub mod test {
             wires::{Wire, COLUMNS},
         },
         curve::KimchiCurve,
+        proof::ProofEvaluations,
         prover_index::ProverIndex,
     };
     use ark_ff::UniformRand;
+    use ark_poly::UVPolynomial;
     use mina_curves::pasta::{Fp, Pallas, Vesta};
     use poly_commitment::{
         evaluation_proof::OpeningProof,
@@ -3507,6 +4158,87 @@ pub mod test {
         expr.evaluations(&env);
     }
 
+    #[test]
+    fn test_evaluations_by_coset() {
+        let rng = &mut StdRng::from_seed([42u8; 32]);
+        let gates: Vec<_> = (0..20)
+            .map(|row| {
+                CircuitGate::create_generic_gadget(
+                    Wire::for_row(row),
+                    GenericGateSpec::Const(Fp::from(row as u64)),
+                    None,
+                )
+            })
+            .collect();
+        let prover_index = {
+            let constraint_system = ConstraintSystem::fp_for_testing(gates);
+            let mut srs = SRS::<Vesta>::create(constraint_system.domain.d1.size());
+            srs.add_lagrange_basis(constraint_system.domain.d1);
+            let srs = Arc::new(srs);
+
+            let (endo_q, _endo_r) = endos::<Pallas>();
+            ProverIndex::<Vesta, OpeningProof<Vesta>>::create(constraint_system, endo_q, srs)
+        };
+
+        let n = prover_index.cs.domain.d1.size();
+        let witness_cols: [_; COLUMNS] = array::from_fn(|_| DensePolynomial::rand(n - 1, rng));
+        let z = DensePolynomial::rand(n - 1, rng);
+        let domain_evals = prover_index.cs.evaluate(&witness_cols, &z);
+
+        let mut index_evals = HashMap::new();
+        index_evals.insert(
+            GateType::Generic,
+            &prover_index.column_evaluations.generic_selector4,
+        );
+        let env = Environment {
+            constants: Constants {
+                endo_coefficient: Fp::rand(rng),
+                mds: &Vesta::sponge_params().mds,
+                zk_rows: prover_index.cs.zk_rows,
+            },
+            challenges: Challenges {
+                alpha: Fp::rand(rng),
+                beta: Fp::rand(rng),
+                gamma: Fp::rand(rng),
+                joint_combiner: None,
+            },
+            witness: &domain_evals.d8.this.w,
+            coefficient: &prover_index.column_evaluations.coefficients8,
+            vanishes_on_zero_knowledge_and_previous_rows: &prover_index
+                .cs
+                .precomputations()
+                .vanishes_on_zero_knowledge_and_previous_rows,
+            z: &domain_evals.d8.this.z,
+            l0_1: l0_1(prover_index.cs.domain.d1),
+            domain: prover_index.cs.domain,
+            index: index_evals,
+            lookup: None,
+        };
+
+        let mut cache = Cache::default();
+        let x = cache.cache(witness_curr::<Fp>(0) + witness_next(3).double());
+        let lagrange = |offset| {
+            E::Atom(ExprInner::UnnormalizedLagrangeBasis(RowOffset {
+                zk_rows: false,
+                offset,
+            }))
+        };
+        let vanishes = E::Atom(ExprInner::VanishesOnZeroKnowledgeAndPreviousRows);
+        let exprs: Vec<E<Fp>> = vec![
+            // over d1
+            witness_curr(2) - coeff(1),
+            // over d4
+            index(GateType::Generic) * x.clone() * witness_next(5),
+            x.clone().square() - lagrange(0) * x.clone() + lagrange(-1),
+            // over d8
+            x.clone().pow(5) * vanishes - witness_curr(7) * lagrange(3),
+        ];
+        for expr in exprs {
+            let evals = expr.evaluations(&env);
+            assert_eq!(expr.evaluations_by_coset(&env).evals, evals.evals);
+        }
+    }
+
     #[test]
     fn test_unnormalized_lagrange_basis() {
         let zk_rows = 3;
@@ -3557,4 +4289,118 @@ pub mod test {
         );
         assert_eq!(test_4::<Fp, Fp>(Fp::from(5u64)), Fp::from(160u64));
     }
+
+    #[test]
+    fn test_optimize_folds_constants() {
+        // (2 * 3) + (w0 * 1) - 0
+        let two = Box::new(E::<Fp>::from(2u64));
+        let three = Box::new(E::from(3u64));
+        let w0_times_one = Expr::Mul(Box::new(witness_curr(0)), Box::new(E::one()));
+        let expr = Expr::Sub(
+            Box::new(Expr::Add(
+                Box::new(Expr::Mul(two, three)),
+                Box::new(w0_times_one),
+            )),
+            Box::new(E::zero()),
+        );
+        assert_eq!(expr.optimize(), E::from(6u64) + witness_curr(0));
+
+        // (w1^1)^2 with a cached literal
+        let cached = Expr::Cache(CacheId(0), Box::new(E::<Fp>::from(4u64)));
+        let expr = Expr::Square(Box::new(Expr::Pow(Box::new(witness_curr(1)), 1))) * cached;
+        assert_eq!(
+            expr.optimize(),
+            Expr::Square(Box::new(witness_curr(1))) * E::from(4u64)
+        );
+    }
+
+    #[test]
+    fn test_optimize_eliminates_common_subexpressions() {
+        // w0 * w1' occurs three times, once with the operands commuted
+        let x = || witness_curr::<Fp>(0) * witness_next(1);
+        let y = witness_next::<Fp>(1) * witness_curr(0);
+        let mut cache = Cache::default();
+        let z = cache.cache(witness_curr(2) + coeff(0));
+        let expr = x().square() + y * z.clone() - x() * coeff(1) + z.square() + E::from(6u64);
+
+        let optimized = expr.optimize();
+        let polish = optimized.to_polish();
+        let stores = polish
+            .iter()
+            .filter(|tok| matches!(tok, PolishToken::Store))
+            .count();
+        let loads = polish
+            .iter()
+            .filter(|tok| matches!(tok, PolishToken::Load(_)))
+            .count();
+        // the existing cache of z is kept, and x gets a new one
+        assert_eq!(stores, 2);
+        assert_eq!(loads, 3);
+
+        let rng = &mut StdRng::from_seed([3u8; 32]);
+        let domain = EvaluationDomains::<Fp>::create(8).unwrap().d1;
+        let evals = ProofEvaluations::dummy_with_witness_evaluations(
+            array::from_fn(|_| Fp::rand(rng)),
+            array::from_fn(|_| Fp::rand(rng)),
+        );
+        let constants = Constants {
+            endo_coefficient: Fp::rand(rng),
+            mds: &Vesta::sponge_params().mds,
+            zk_rows: 3,
+        };
+        let challenges = Challenges {
+            alpha: Fp::rand(rng),
+            beta: Fp::rand(rng),
+            gamma: Fp::rand(rng),
+            joint_combiner: None,
+        };
+        let pt = Fp::rand(rng);
+        let evaluate = |expr: &E<Fp>| {
+            PolishToken::evaluate(
+                &expr.to_polish(),
+                domain,
+                pt,
+                &evals,
+                &constants,
+                &challenges,
+            )
+            .unwrap()
+        };
+        assert_eq!(evaluate(&optimized), evaluate(&expr));
+    }
+
+    #[test]
+    fn test_optimize_keeps_feature_flags() {
+        let x = || witness_curr::<Fp>(0) * witness_curr(1);
+        let expr =
+            x() + Expr::IfFeature(FeatureFlag::RangeCheck0, Box::new(x()), Box::new(E::zero()));
+        // the subexpression under the feature flag is not shared
+        assert_eq!(expr.optimize(), expr);
+    }
+
+    #[test]
+    fn test_serialization_and_digest() {
+        let expr: E<Fp> = witness_curr(0) * witness_curr(1) + E::from(7u64).square();
+        let bytes = rmp_serde::to_vec(&expr).unwrap();
+        let deserialized: E<Fp> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(deserialized, expr);
+        assert_eq!(deserialized.digest(), expr.digest());
+
+        // the digest catches a change in the constraint
+        let changed: E<Fp> = witness_curr(0) * witness_curr(2) + E::from(7u64).square();
+        assert_ne!(changed.digest(), expr.digest());
+
+        let (linearization, _) = crate::linearization::expr_linearization::<Fp>(
+            None,
+            true,
+            &Default::default(),
+            None,
+            Default::default(),
+            None,
+        );
+        let bytes = rmp_serde::to_vec(&linearization).unwrap();
+        let deserialized: Linearization<Vec<PolishToken<Fp, berkeley_columns::Column>>, _> =
+            rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(deserialized.digest(), linearization.digest());
+    }
 }
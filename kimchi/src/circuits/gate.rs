@@ -111,6 +111,10 @@ pub enum GateType {
     Rot64,
     KeccakRound,
     KeccakSponge,
+    /// Gate defined outside of kimchi (see [crate::circuits::custom_gate])
+    Custom,
+    /// Lookup of a tuple of cells in a multi-column table
+    VectorLookup,
 }
 
 /// Gate error
@@ -134,6 +138,9 @@ pub enum CircuitGateError {
     /// Failed to get witness for row
     #[error("Failed to get {0:?} witness for row {1}")]
     FailedToGetWitnessForRow(GateType, usize),
+    /// Custom gate that was not registered
+    #[error("Unknown custom gate on row {0}")]
+    UnknownCustomGate(usize),
 }
 
 /// Gate result
@@ -210,7 +217,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
             EndoMulScalar => self.verify_endomul_scalar::<G>(row, witness, &index.cs),
             // TODO: implement the verification for the lookup gate
             // See https://github.com/MinaProtocol/mina/issues/14011
-            Lookup => Ok(()),
+            Lookup | VectorLookup => Ok(()),
             CairoClaim | CairoInstruction | CairoFlags | CairoTransition => {
                 self.verify_cairo_gate::<G>(row, witness, &index.cs)
             }
@@ -235,6 +242,9 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
             KeccakSponge => self
                 .verify_witness::<G>(row, witness, &index.cs, public)
                 .map_err(|e| e.to_string()),
+            Custom => self
+                .verify_witness::<G>(row, witness, &index.cs, public)
+                .map_err(|e| e.to_string()),
         }
     }
 
@@ -244,6 +254,21 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
         row: usize,
         witness: &[Vec<F>; COLUMNS],
         cs: &ConstraintSystem<F>,
+        public: &[F],
+    ) -> CircuitGateResult<()> {
+        self.verify_witness_with_mds(row, witness, cs, &G::sponge_params().mds, public)
+    }
+
+    /// Same as [CircuitGate::verify_witness], with the MDS matrix of the
+    /// Poseidon gates instead of the curve, so that the witness of a field
+    /// without curve can be verified. The matrix can be empty if the gate is
+    /// not a Poseidon gate.
+    pub fn verify_witness_with_mds(
+        &self,
+        row: usize,
+        witness: &[Vec<F>; COLUMNS],
+        cs: &ConstraintSystem<F>,
+        mds: &'static Vec<Vec<F>>,
         _public: &[F],
     ) -> CircuitGateResult<()> {
         // Grab the relevant part of the witness
@@ -252,7 +277,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
         // are one because this function is not running the prover.
         let constants = expr::Constants {
             endo_coefficient: cs.endo,
-            mds: &G::sponge_params().mds,
+            mds,
             zk_rows: cs.zk_rows,
         };
         let challenges = expr::Challenges {
@@ -309,7 +334,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
             GateType::EndoMulScalar => {
                 endomul_scalar::EndomulScalar::constraint_checks(&env, &mut cache)
             }
-            GateType::Lookup => {
+            GateType::Lookup | GateType::VectorLookup => {
                 // TODO: implement the verification for the lookup gate
                 // See https://github.com/MinaProtocol/mina/issues/14011
                 vec![]
@@ -342,6 +367,12 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
             GateType::KeccakSponge => {
                 keccak::circuitgates::KeccakSponge::constraint_checks(&env, &mut cache)
             }
+            GateType::Custom => cs
+                .custom_gates
+                .as_ref()
+                .and_then(|custom_gates| custom_gates.resolve(row, self).ok())
+                .ok_or(CircuitGateError::UnknownCustomGate(row))?
+                .check_witness(&env, &mut cache),
         };
 
         // Check for failed constraints
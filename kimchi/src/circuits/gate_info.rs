@@ -0,0 +1,263 @@
+//! This module implements a registry describing the gates of kimchi.
+//!
+//! For each [GateType], [GateType::spec] returns a [GateSpec] with the number
+//! of rows it spans, its number of constraints and their degrees, the witness
+//! and coefficient columns they use, the lookups the gate performs, and a
+//! short description of what the gate enforces. The numbers are computed from
+//! the constraints of the gate and from its lookup patterns, i.e. from the
+//! code building the constraint system, so that they are always in sync with
+//! the polynomial modules. [gate_registry] returns the description of all the
+//! gates, and can be displayed as a table or serialized for external tooling
+//! (e.g. circuit visualizers).
+
+use crate::circuits::{
+    argument::Argument,
+    berkeley_columns::{Column, E},
+    expr::{Cache, PolishToken},
+    gate::{CurrOrNext, GateType},
+    lookup::{lookups::LookupPattern, tables::GateLookupTable},
+    polynomials::{
+        complete_add, endomul_scalar, endosclmul, foreign_field_add, foreign_field_mul, generic,
+        keccak, poseidon, range_check, rot, turshi, varbasemul, xor,
+    },
+};
+use ark_ff::PrimeField;
+use serde::Serialize;
+use std::{collections::BTreeSet, fmt};
+
+/// All the gate types, in the order of their declaration
+pub const GATE_TYPES: [GateType; 22] = [
+    GateType::Zero,
+    GateType::Generic,
+    GateType::Poseidon,
+    GateType::CompleteAdd,
+    GateType::VarBaseMul,
+    GateType::EndoMul,
+    GateType::EndoMulScalar,
+    GateType::Lookup,
+    GateType::CairoClaim,
+    GateType::CairoInstruction,
+    GateType::CairoFlags,
+    GateType::CairoTransition,
+    GateType::RangeCheck0,
+    GateType::RangeCheck1,
+    GateType::ForeignFieldAdd,
+    GateType::ForeignFieldMul,
+    GateType::Xor16,
+    GateType::Rot64,
+    GateType::KeccakRound,
+    GateType::KeccakSponge,
+    GateType::Custom,
+    GateType::VectorLookup,
+];
+
+/// The description of a gate, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GateSpec {
+    /// The type of the gate
+    pub typ: GateType,
+    /// The number of rows read by the constraints and the lookups of the
+    /// gate, i.e. 2 if it reads the next row and 1 otherwise
+    pub rows: usize,
+    /// The number of constraints of the gate
+    pub constraints: usize,
+    /// The degree of each constraint, in the witness and coefficient columns
+    pub degrees: Vec<u64>,
+    /// The maximum degree of the constraints
+    pub degree: u64,
+    /// The witness columns read in the row of the gate
+    pub curr_witness: Vec<usize>,
+    /// The witness columns read in the next row
+    pub next_witness: Vec<usize>,
+    /// The coefficient columns read in the row of the gate
+    pub coefficients: Vec<usize>,
+    /// The lookups performed by the gate
+    pub lookups: Vec<GateLookup>,
+    /// What the gate enforces
+    pub description: &'static str,
+}
+
+/// The lookups performed by a gate in one of its rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct GateLookup {
+    /// The row of the lookups, relative to the row of the gate
+    pub row: CurrOrNext,
+    /// The lookup pattern
+    pub pattern: LookupPattern,
+    /// The maximum number of lookups in the row
+    pub count: usize,
+    /// The built-in table looked up, if fixed by the pattern
+    pub table: Option<GateLookupTable>,
+}
+
+impl GateType {
+    /// Returns the description of the gate, see [GateSpec]
+    pub fn spec<F: PrimeField>(self) -> GateSpec {
+        let constraints = gate_constraints::<F>(self);
+
+        let mut curr_witness = BTreeSet::new();
+        let mut next_witness = BTreeSet::new();
+        let mut coefficients = BTreeSet::new();
+        for constraint in &constraints {
+            for token in constraint.to_polish() {
+                if let PolishToken::Cell(variable) = token {
+                    match (variable.col, variable.row) {
+                        (Column::Witness(i), CurrOrNext::Curr) => {
+                            curr_witness.insert(i);
+                        }
+                        (Column::Witness(i), CurrOrNext::Next) => {
+                            next_witness.insert(i);
+                        }
+                        (Column::Coefficient(i), _) => {
+                            coefficients.insert(i);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        let lookups: Vec<GateLookup> = [CurrOrNext::Curr, CurrOrNext::Next]
+            .into_iter()
+            .filter_map(|row| {
+                LookupPattern::from_gate(self, row).map(|pattern| GateLookup {
+                    row,
+                    pattern,
+                    count: pattern.max_lookups_per_row(),
+                    table: pattern.table(),
+                })
+            })
+            .collect();
+
+        let degrees: Vec<u64> = constraints
+            .iter()
+            .map(|constraint| constraint.degree(1, 0))
+            .collect();
+        let reads_next =
+            !next_witness.is_empty() || lookups.iter().any(|lookup| lookup.row == CurrOrNext::Next);
+
+        GateSpec {
+            typ: self,
+            rows: if reads_next { 2 } else { 1 },
+            constraints: constraints.len(),
+            degree: degrees.iter().copied().max().unwrap_or(0),
+            degrees,
+            curr_witness: curr_witness.into_iter().collect(),
+            next_witness: next_witness.into_iter().collect(),
+            coefficients: coefficients.into_iter().collect(),
+            lookups,
+            description: description(self),
+        }
+    }
+}
+
+/// Returns the description of all the gates, see [GateSpec]
+pub fn gate_registry<F: PrimeField>() -> GateRegistry {
+    GateRegistry(GATE_TYPES.iter().map(|typ| typ.spec::<F>()).collect())
+}
+
+/// The description of all the gates, displayed as a table
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GateRegistry(pub Vec<GateSpec>);
+
+impl GateRegistry {
+    /// Returns the description of the gate of type `typ`
+    pub fn get(&self, typ: GateType) -> Option<&GateSpec> {
+        self.0.iter().find(|info| info.typ == typ)
+    }
+}
+
+impl fmt::Display for GateRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<18} {:>4} {:>11} {:>6} {:>8} {:>8} {:>6} {:>7}  description",
+            "gate", "rows", "constraints", "degree", "curr", "next", "coeffs", "lookups"
+        )?;
+        for spec in &self.0 {
+            writeln!(
+                f,
+                "{:<18} {:>4} {:>11} {:>6} {:>8} {:>8} {:>6} {:>7}  {}",
+                format!("{:?}", spec.typ),
+                spec.rows,
+                spec.constraints,
+                spec.degree,
+                spec.curr_witness.len(),
+                spec.next_witness.len(),
+                spec.coefficients.len(),
+                spec.lookups
+                    .iter()
+                    .map(|lookup| lookup.count)
+                    .sum::<usize>(),
+                spec.description
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The constraints of a gate, without its selector
+fn gate_constraints<F: PrimeField>(typ: GateType) -> Vec<E<F>> {
+    let mut cache = Cache::default();
+    match typ {
+        // the lookups are constrained by the lookup argument, and the custom
+        // gates are only known at runtime
+        GateType::Zero | GateType::Lookup | GateType::VectorLookup | GateType::Custom => vec![],
+        GateType::Generic => generic::Generic::constraints(&mut cache),
+        GateType::Poseidon => poseidon::Poseidon::constraints(&mut cache),
+        GateType::CompleteAdd => complete_add::CompleteAdd::constraints(&mut cache),
+        GateType::VarBaseMul => varbasemul::VarbaseMul::constraints(&mut cache),
+        GateType::EndoMul => endosclmul::EndosclMul::constraints(&mut cache),
+        GateType::EndoMulScalar => endomul_scalar::EndomulScalar::constraints(&mut cache),
+        GateType::CairoClaim => turshi::Claim::constraints(&mut cache),
+        GateType::CairoInstruction => turshi::Instruction::constraints(&mut cache),
+        GateType::CairoFlags => turshi::Flags::constraints(&mut cache),
+        GateType::CairoTransition => turshi::Transition::constraints(&mut cache),
+        GateType::RangeCheck0 => range_check::circuitgates::RangeCheck0::constraints(&mut cache),
+        GateType::RangeCheck1 => range_check::circuitgates::RangeCheck1::constraints(&mut cache),
+        GateType::ForeignFieldAdd => {
+            foreign_field_add::circuitgates::ForeignFieldAdd::constraints(&mut cache)
+        }
+        GateType::ForeignFieldMul => {
+            foreign_field_mul::circuitgates::ForeignFieldMul::constraints(&mut cache)
+        }
+        GateType::Xor16 => xor::Xor16::constraints(&mut cache),
+        GateType::Rot64 => rot::Rot64::constraints(&mut cache),
+        GateType::KeccakRound => keccak::circuitgates::KeccakRound::constraints(&mut cache),
+        GateType::KeccakSponge => keccak::circuitgates::KeccakSponge::constraints(&mut cache),
+    }
+}
+
+/// What a gate enforces
+fn description(typ: GateType) -> &'static str {
+    match typ {
+        GateType::Zero => "no constraint, used for rows only read by the previous gate",
+        GateType::Generic => {
+            "two gates c0 l + c1 r + c2 o + c3 l r + c4 = 0, with coefficients as selectors"
+        }
+        GateType::Poseidon => "5 rounds of the Poseidon permutation, over the next row",
+        GateType::CompleteAdd => "complete addition of two affine points, handling doubling",
+        GateType::VarBaseMul => "5 bits of a variable-base scalar multiplication",
+        GateType::EndoMul => "4 bits of a scalar multiplication with the endomorphism",
+        GateType::EndoMulScalar => {
+            "8 crumbs of the scalar of an endomorphism scalar multiplication"
+        }
+        GateType::Lookup => "3 lookups in the lookup tables, constrained by the lookup argument",
+        GateType::CairoClaim => "the initial and final states of a Cairo program",
+        GateType::CairoInstruction => "the decomposition of a Cairo instruction",
+        GateType::CairoFlags => "the flags of a Cairo instruction",
+        GateType::CairoTransition => "the transition between two Cairo states",
+        GateType::RangeCheck0 => "a 88-bit range check of a limb, with 4 12-bit lookups",
+        GateType::RangeCheck1 => "the 88-bit range check of a limb and of a compact limb",
+        GateType::ForeignFieldAdd => "addition of foreign field elements, with overflow",
+        GateType::ForeignFieldMul => "multiplication of foreign field elements, with quotient",
+        GateType::Xor16 => "16 bits of the XOR of two words, with 4-bit lookups",
+        GateType::Rot64 => "a rotation of a 64-bit word",
+        GateType::KeccakRound => "a round of the Keccak permutation, over the next row",
+        GateType::KeccakSponge => "an absorb or squeeze of the Keccak sponge, with padding",
+        GateType::Custom => "a gate defined at runtime, see the custom gate registry",
+        GateType::VectorLookup => {
+            "a lookup of a tuple of cells in a multi-column table, by the lookup argument"
+        }
+    }
+}
@@ -333,6 +333,36 @@ where
     Ok(res)
 }
 
+/// The argument used to prove the lookups of a constraint system
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LookupArgument {
+    /// The plookup argument: the lookups and the table are sorted together,
+    /// using `max_per_row + 1` sorted polynomials, and checked with a
+    /// product-based aggregation polynomial.
+    #[default]
+    Plookup,
+    /// The logUp argument: the prover commits to the multiplicity of each
+    /// table entry, and the aggregation polynomial accumulates the fractional
+    /// sum `sum_j 1 / (gamma + f_j) - m / (gamma + t)`, which must vanish over
+    /// the lookup rows.
+    ///
+    /// Only two polynomials are committed, independently of the number of
+    /// lookups per row: the multiplicities, stored in
+    /// [`Column::LookupSorted`]`(0)`, and the aggregation, stored in
+    /// [`Column::LookupAggreg`].
+    LogUp,
+}
+
+impl LookupArgument {
+    /// Returns the number of polynomials stored in the [`Column::LookupSorted`] columns
+    pub fn num_sorted(&self, lookup_info: &LookupInfo) -> usize {
+        match self {
+            LookupArgument::Plookup => lookup_info.max_per_row + 1,
+            LookupArgument::LogUp => 1,
+        }
+    }
+}
+
 /// Configuration for the lookup constraint.
 /// These values are independent of the choice of lookup values.
 // TODO: move to lookup::index
@@ -343,6 +373,10 @@ pub struct LookupConfiguration<F> {
     /// Information about the specific lookups used
     pub lookup_info: LookupInfo,
 
+    /// The argument used to prove the lookups
+    #[serde(default)]
+    pub argument: LookupArgument,
+
     /// A placeholder value that is known to appear in the lookup table.
     /// This is used to pad the lookups to `max_lookups_per_row` when fewer lookups are used in a
     /// particular row, so that we can treat each row uniformly as having the same number of
@@ -361,9 +395,16 @@ impl<F: Zero> LookupConfiguration<F> {
 
         LookupConfiguration {
             lookup_info,
+            argument: LookupArgument::default(),
             dummy_lookup,
         }
     }
+
+    /// Sets the argument used to prove the lookups
+    pub fn argument(mut self, argument: LookupArgument) -> Self {
+        self.argument = argument;
+        self
+    }
 }
 
 /// Specifies the lookup constraints as expressions.
@@ -388,6 +429,10 @@ pub fn constraints<F: FftField>(
     // values) and thus
     //
     // num_lookup_rows = n - 3
+    if configuration.argument == LookupArgument::LogUp {
+        return logup_constraints(configuration);
+    }
+
     let lookup_info = &configuration.lookup_info;
 
     let column = |col: Column| E::cell(col, Curr);
@@ -525,8 +570,8 @@ pub fn constraints<F: FftField>(
                 .patterns
                 .into_iter()
                 .map(|spec| {
-                    let mut term =
-                        column(Column::LookupKindIndex(spec)) * f_term(&spec.lookups::<F>());
+                    let mut term = column(Column::LookupKindIndex(spec))
+                        * f_term(&lookup_info.pattern_lookups::<F>(spec));
                     if generate_feature_flags {
                         term = E::IfFeature(
                             FeatureFlag::LookupPattern(spec),
@@ -677,6 +722,274 @@ pub fn constraints<F: FftField>(
     res
 }
 
+/// Computes the multiplicities used by the logUp argument: the `i`th entry is
+/// the number of times the `i`th entry of the table is looked up, including
+/// the dummy lookups used for padding. When an entry appears several times in
+/// the table, all its lookups are counted at its first occurrence.
+///
+/// # Errors
+///
+/// Will give error if a looked up value is missing from the table.
+#[allow(clippy::too_many_arguments)]
+pub fn multiplicities<F: PrimeField>(
+    dummy_lookup_value: F,
+    joint_lookup_table_d8: &Evaluations<F, D<F>>,
+    d1: D<F>,
+    gates: &[CircuitGate<F>],
+    witness: &[Vec<F>; COLUMNS],
+    joint_combiner: F,
+    table_id_combiner: F,
+    lookup_info: &LookupInfo,
+    zk_rows: usize,
+) -> Result<Vec<F>, ProverError> {
+    let n = d1.size();
+    let lookup_rows = n - zk_rows - 1;
+    let by_row = lookup_info.by_row(gates);
+    let max_lookups_per_row = lookup_info.max_per_row;
+
+    let mut first_index: HashMap<&F, usize> = HashMap::new();
+    for (i, t) in joint_lookup_table_d8
+        .evals
+        .iter()
+        .step_by(8)
+        .take(lookup_rows)
+        .enumerate()
+    {
+        first_index.entry(t).or_insert(i);
+    }
+
+    let mut multiplicities = vec![0u64; lookup_rows];
+    for i in 0..lookup_rows {
+        let spec = by_row.get(i).map(Vec::as_slice).unwrap_or_default();
+        let eval = |pos: LocalPosition| -> F {
+            let row = match pos.row {
+                Curr => i,
+                Next => i + 1,
+            };
+            witness[pos.column][row]
+        };
+        for joint_lookup in spec.iter() {
+            let joint_lookup_evaluation =
+                joint_lookup.evaluate(&joint_combiner, &table_id_combiner, &eval);
+            match first_index.get(&joint_lookup_evaluation) {
+                None => return Err(ProverError::ValueNotInTable(i)),
+                Some(index) => multiplicities[*index] += 1,
+            }
+        }
+        let padding = max_lookups_per_row - spec.len();
+        if padding > 0 {
+            match first_index.get(&dummy_lookup_value) {
+                None => return Err(ProverError::ValueNotInTable(i)),
+                Some(index) => multiplicities[*index] += padding as u64,
+            }
+        }
+    }
+
+    Ok(multiplicities.into_iter().map(F::from).collect())
+}
+
+/// Computes the aggregation polynomial of the logUp argument, whose `k`th entry is
+///
+/// \sum_{i < k} ( \sum_{0 <= j < n} 1 / (gamma + f_{i,j}) - m_i / (gamma + t_i) )
+///
+/// where `t_i` is the `i`th entry of the table, `m_i` its multiplicity, and
+/// `f_{i,j}` the `j`th lookup of the `i`th row of the witness, padded with the
+/// dummy lookup up to `n` lookups per row.
+///
+/// If all the lookups are in the table and the multiplicities are correct, the
+/// final entry is 0.
+///
+/// # Panics
+///
+/// Will panic if final evaluation is not 0.
+#[allow(clippy::too_many_arguments)]
+pub fn logup_aggregation<R, F>(
+    dummy_lookup_value: F,
+    joint_lookup_table_d8: &Evaluations<F, D<F>>,
+    d1: D<F>,
+    gates: &[CircuitGate<F>],
+    witness: &[Vec<F>; COLUMNS],
+    joint_combiner: &F,
+    table_id_combiner: &F,
+    gamma: F,
+    multiplicities: &Evaluations<F, D<F>>,
+    rng: &mut R,
+    lookup_info: &LookupInfo,
+    zk_rows: usize,
+) -> Result<Evaluations<F, D<F>>, ProverError>
+where
+    R: Rng + ?Sized,
+    F: PrimeField,
+{
+    let n = d1.size();
+    let lookup_rows = n - zk_rows - 1;
+    let by_row = lookup_info.by_row(gates);
+    let max_lookups_per_row = lookup_info.max_per_row;
+
+    // The denominators of each row are stored contiguously: first the table
+    // entry, then the lookups of the row.
+    let mut denominators = Vec::with_capacity(lookup_rows * (max_lookups_per_row + 1) + 1);
+    denominators.push(gamma + dummy_lookup_value);
+    let mut row_lengths = Vec::with_capacity(lookup_rows);
+    for (i, t) in joint_lookup_table_d8
+        .evals
+        .iter()
+        .step_by(8)
+        .take(lookup_rows)
+        .enumerate()
+    {
+        let spec = by_row.get(i).map(Vec::as_slice).unwrap_or_default();
+        let eval = |pos: LocalPosition| -> F {
+            let row = match pos.row {
+                Curr => i,
+                Next => i + 1,
+            };
+            witness[pos.column][row]
+        };
+        denominators.push(gamma + t);
+        for joint_lookup in spec.iter() {
+            denominators
+                .push(gamma + joint_lookup.evaluate(joint_combiner, table_id_combiner, &eval));
+        }
+        row_lengths.push(spec.len());
+    }
+    ark_ff::fields::batch_inversion::<F>(&mut denominators);
+
+    let dummy_inv = denominators[0];
+    let mut inverses = denominators[1..].iter();
+    let mut lookup_aggreg = Vec::with_capacity(lookup_rows + 1);
+    lookup_aggreg.push(F::zero());
+    for (i, len) in row_lengths.into_iter().enumerate() {
+        let t_inv = *inverses.next().unwrap();
+        let f_sum = inverses
+            .by_ref()
+            .take(len)
+            .fold(F::zero(), |acc, x| acc + x);
+        let padding = F::from((max_lookups_per_row - len) as u64) * dummy_inv;
+        let prev = lookup_aggreg[i];
+        lookup_aggreg.push(prev + f_sum + padding - multiplicities[i] * t_inv);
+    }
+
+    let res = zk_patch(lookup_aggreg, d1, zk_rows, rng);
+
+    // check that the final evaluation is equal to 0
+    if cfg!(debug_assertions) {
+        let final_val = res.evals[d1.size() - (zk_rows + 1)];
+        if !final_val.is_zero() {
+            panic!("logUp aggregation incorrect: {final_val}");
+        }
+    }
+
+    Ok(res)
+}
+
+/// Specifies the constraints of the logUp argument as expressions.
+///
+/// Feature flags are not supported by this argument, so the expressions are
+/// always specialized to the lookup configuration.
+fn logup_constraints<F: FftField>(configuration: &LookupConfiguration<F>) -> Vec<E<F>> {
+    let lookup_info = &configuration.lookup_info;
+    let gamma = E::<F>::from(ChallengeTerm::Gamma);
+
+    let joint_combiner = E::from(ChallengeTerm::JointCombiner);
+    let table_id_combiner = (1..lookup_info.max_joint_size)
+        .fold(joint_combiner.clone(), |acc, _| {
+            acc * joint_combiner.clone()
+        });
+
+    let dummy_lookup = {
+        let expr_dummy: JointLookupValue<E<F>> = JointLookup {
+            entry: configuration
+                .dummy_lookup
+                .entry
+                .iter()
+                .map(|x| ConstantTerm::Literal(*x).into())
+                .collect(),
+            table_id: ConstantTerm::Literal(configuration.dummy_lookup.table_id).into(),
+        };
+        expr_dummy.evaluate(&joint_combiner, &table_id_combiner)
+    };
+
+    // Returns the numerator and the denominator of
+    // sum_j 1 / (gamma + f_j)
+    // for the lookups `spec`, padded with the dummy lookup
+    let fractions = |spec: &Vec<JointLookupSpec<F>>| -> (E<F>, E<F>) {
+        assert!(spec.len() <= lookup_info.max_per_row);
+        let eval = |pos: LocalPosition| witness(pos.column, pos.row);
+        spec.iter()
+            .map(|j| gamma.clone() + j.evaluate(&joint_combiner, &table_id_combiner, &eval))
+            .chain(
+                (spec.len()..lookup_info.max_per_row).map(|_| gamma.clone() + dummy_lookup.clone()),
+            )
+            .fold((E::zero(), E::one()), |(num, den), x| {
+                (num * x.clone() + den.clone(), den * x)
+            })
+    };
+
+    // Combine the fractions of each pattern with its selector. The rows
+    // without lookup only contain dummy lookups.
+    let (numerator, denominator) = {
+        let lookup_indicator = lookup_info
+            .features
+            .patterns
+            .into_iter()
+            .map(|spec| E::cell(Column::LookupKindIndex(spec), Curr))
+            .fold(E::zero(), |acc: E<F>, x| acc + x);
+        let non_lookup_indicator = E::one() - lookup_indicator;
+        let (dummy_num, dummy_den) = fractions(&vec![]);
+
+        lookup_info.features.patterns.into_iter().fold(
+            (
+                non_lookup_indicator.clone() * dummy_num,
+                non_lookup_indicator * dummy_den,
+            ),
+            |(num, den), spec| {
+                let selector = E::cell(Column::LookupKindIndex(spec), Curr);
+                let (spec_num, spec_den) = fractions(&lookup_info.pattern_lookups::<F>(spec));
+                (num + selector.clone() * spec_num, den + selector * spec_den)
+            },
+        )
+    };
+
+    let table = gamma + E::cell(Column::LookupTable, Curr);
+    let multiplicity = E::cell(Column::LookupSorted(0), Curr);
+
+    // (phi(x w) - phi(x)) = numerator / denominator - m(x) / (gamma + t(x))
+    let aggreg_equation = (E::cell(Column::LookupAggreg, Next)
+        - E::cell(Column::LookupAggreg, Curr))
+        * denominator.clone()
+        * table.clone()
+        - numerator * table
+        + multiplicity * denominator;
+
+    let final_lookup_row = RowOffset {
+        zk_rows: true,
+        offset: -1,
+    };
+
+    let mut res = vec![
+        // the accumulator except for the last zk_rows+1 rows
+        // (contains the zk-rows and the last value of the accumulator)
+        E::Atom(ExprInner::VanishesOnZeroKnowledgeAndPreviousRows) * aggreg_equation,
+        // the initial value of the accumulator
+        E::Atom(ExprInner::UnnormalizedLagrangeBasis(RowOffset {
+            zk_rows: false,
+            offset: 0,
+        })) * E::cell(Column::LookupAggreg, Curr),
+        // Check that the final value of the accumulator is 0
+        E::Atom(ExprInner::UnnormalizedLagrangeBasis(final_lookup_row))
+            * E::cell(Column::LookupAggreg, Curr),
+    ];
+
+    // if we are using runtime tables, we add:
+    // $RT(x) (1 - \text{selector}_{RT}(x)) = 0$
+    if lookup_info.features.uses_runtime_tables {
+        res.extend(runtime_tables::constraints());
+    }
+
+    res
+}
+
 /// Checks that all the lookup constraints are satisfied.
 ///
 /// # Panics
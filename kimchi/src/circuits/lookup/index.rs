@@ -3,9 +3,10 @@ use crate::circuits::{
     domains::EvaluationDomains,
     gate::CircuitGate,
     lookup::{
-        constraints::LookupConfiguration,
+        constraints::{LookupArgument, LookupConfiguration},
         lookups::{LookupInfo, LookupPattern},
-        tables::LookupTable,
+        registry::{LookupTableKind, LookupTableRegistry},
+        tables::{xor::XorTableBits, LookupTable},
     },
 };
 use ark_ff::{FftField, PrimeField, SquareRootField};
@@ -14,7 +15,10 @@ use ark_poly::{
     Radix2EvaluationDomain as D,
 };
 use itertools::repeat_n;
-use o1_utils::field_helpers::i32_to_field;
+use o1_utils::{
+    fft::{FftEvaluate as _, FftInterpolate as _},
+    field_helpers::i32_to_field,
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
 use std::iter;
@@ -32,8 +36,22 @@ pub enum LookupError {
     },
     #[error("The table with id 0 must have an entry of all zeros")]
     TableIDZeroMustHaveZeroEntry,
-    #[error("Cannot create a combined table since ids for sub-tables are colliding. The collision type is: {collision_type}")]
-    LookupTableIdCollision { collision_type: String },
+    #[error("The lookup table ID {id} is used by both {first} and {second}")]
+    LookupTableIdCollision {
+        id: i32,
+        first: LookupTableKind,
+        second: LookupTableKind,
+    },
+    #[error("The maximum number of lookups per row must be between {minimum} and {maximum}, got {max_per_row}")]
+    MaxLookupsPerRowOutOfRange {
+        max_per_row: usize,
+        minimum: usize,
+        maximum: usize,
+    },
+    #[error(
+        "The runtime table with id {id} requires unique keys, but its first column has duplicates"
+    )]
+    RuntimeTableDuplicateKeys { id: i32 },
 }
 
 /// Lookup selectors
@@ -47,6 +65,8 @@ pub struct LookupSelectors<T> {
     pub range_check: Option<T>,
     /// Foreign field multiplication pattern lookup selector
     pub ffmul: Option<T>,
+    /// Vector lookup pattern lookup selector
+    pub vector_lookup: Option<T>,
 }
 
 #[serde_as]
@@ -60,6 +80,8 @@ struct LookupSelectorsSerdeAs<F: FftField> {
     pub range_check: Option<E<F, D<F>>>,
     #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
     pub ffmul: Option<E<F, D<F>>>,
+    #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
+    pub vector_lookup: Option<E<F, D<F>>>,
 }
 
 impl<F: FftField> serde_with::SerializeAs<LookupSelectors<E<F, D<F>>>>
@@ -74,6 +96,7 @@ impl<F: FftField> serde_with::SerializeAs<LookupSelectors<E<F, D<F>>>>
             lookup: val.lookup.clone(),
             range_check: val.range_check.clone(),
             ffmul: val.ffmul.clone(),
+            vector_lookup: val.vector_lookup.clone(),
         };
         repr.serialize(serializer)
     }
@@ -91,12 +114,14 @@ impl<'de, F: FftField> serde_with::DeserializeAs<'de, LookupSelectors<E<F, D<F>>
             lookup,
             range_check,
             ffmul,
+            vector_lookup,
         } = LookupSelectorsSerdeAs::deserialize(deserializer)?;
         Ok(LookupSelectors {
             xor,
             lookup,
             range_check,
             ffmul,
+            vector_lookup,
         })
     }
 }
@@ -110,6 +135,7 @@ impl<T> std::ops::Index<LookupPattern> for LookupSelectors<T> {
             LookupPattern::Lookup => &self.lookup,
             LookupPattern::RangeCheck => &self.range_check,
             LookupPattern::ForeignFieldMul => &self.ffmul,
+            LookupPattern::VectorLookup => &self.vector_lookup,
         }
     }
 }
@@ -121,6 +147,7 @@ impl<T> std::ops::IndexMut<LookupPattern> for LookupSelectors<T> {
             LookupPattern::Lookup => &mut self.lookup,
             LookupPattern::RangeCheck => &mut self.range_check,
             LookupPattern::ForeignFieldMul => &mut self.ffmul,
+            LookupPattern::VectorLookup => &mut self.vector_lookup,
         }
     }
 }
@@ -132,6 +159,7 @@ impl<T> LookupSelectors<T> {
             lookup,
             range_check,
             ffmul,
+            vector_lookup,
         } = self;
         // This closure isn't really redundant -- it shields the parameter from a copy -- but
         // clippy isn't smart enough to figure that out..
@@ -142,6 +170,7 @@ impl<T> LookupSelectors<T> {
             lookup: lookup.map(f),
             range_check: range_check.map(f),
             ffmul: ffmul.map(f),
+            vector_lookup: vector_lookup.map(f),
         }
     }
 
@@ -151,6 +180,7 @@ impl<T> LookupSelectors<T> {
             lookup: self.lookup.as_ref(),
             range_check: self.range_check.as_ref(),
             ffmul: self.ffmul.as_ref(),
+            vector_lookup: self.vector_lookup.as_ref(),
         }
     }
 }
@@ -189,6 +219,10 @@ pub struct LookupConstraintSystem<F: FftField> {
     /// The offset of the runtime table within the concatenated table
     pub runtime_table_offset: Option<usize>,
 
+    /// The IDs and lengths of the tables, in the order of the concatenated table
+    #[serde(default)]
+    pub table_lengths: Vec<(i32, usize)>,
+
     /// Configuration for the lookup constraint.
     #[serde(bound = "LookupConfiguration<F>: Serialize + DeserializeOwned")]
     pub configuration: LookupConfiguration<F>,
@@ -200,17 +234,28 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
     /// # Errors
     ///
     /// Will give error if inputs validation do not match.
+    #[allow(clippy::too_many_arguments)]
     pub fn create(
         gates: &[CircuitGate<F>],
         fixed_lookup_tables: Vec<LookupTable<F>>,
         runtime_tables: Option<Vec<RuntimeTableCfg<F>>>,
         domain: &EvaluationDomains<F>,
         zk_rows: usize,
+        argument: LookupArgument,
+        max_per_row: Option<usize>,
+        xor_table_bits: XorTableBits,
     ) -> Result<Option<Self>, LookupError> {
         //~ 1. If no lookup is used in the circuit, do not create a lookup index
         match LookupInfo::create_from_gates(gates, runtime_tables.is_some()) {
             None => Ok(None),
             Some(lookup_info) => {
+                // If a maximum number of lookups per row is given, check that the
+                // lookup patterns used by the circuit can be split to fit in it.
+                let lookup_info = match max_per_row {
+                    Some(max_per_row) => lookup_info.with_max_per_row(max_per_row)?,
+                    None => lookup_info,
+                };
+
                 let d1_size = domain.d1.size();
 
                 // The maximum number of entries that can be provided across all tables.
@@ -222,33 +267,16 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 //~ 2. Get the lookup selectors and lookup tables that are specified implicitly
                 // by the lookup gates.
                 let (lookup_selectors, gate_lookup_tables) =
-                    lookup_info.selector_polynomials_and_tables(domain, gates);
-
-                // Checks whether an iterator contains any duplicates, and if yes, raises
-                // a corresponding LookupTableIdCollision error.
-                fn check_id_duplicates<'a, I: Iterator<Item = &'a i32>>(
-                    iter: I,
-                    msg: &str,
-                ) -> Result<(), LookupError> {
-                    use itertools::Itertools;
-                    match iter.duplicates().collect::<Vec<_>>() {
-                        dups if !dups.is_empty() => Err(LookupError::LookupTableIdCollision {
-                            collision_type: format!("{}: {:?}", msg, dups).to_string(),
-                        }),
-                        _ => Ok(()),
-                    }
-                }
-
-                // If there is a gate using a lookup table, this table must not be added
-                // explicitly to the constraint system.
-                let fixed_gate_joint_ids: Vec<i32> = fixed_lookup_tables
-                    .iter()
-                    .map(|lt| lt.id)
-                    .chain(gate_lookup_tables.iter().map(|lt| lt.id))
-                    .collect();
-                check_id_duplicates(
-                    fixed_gate_joint_ids.iter(),
-                    "duplicates between fixed given and fixed from-gate tables",
+                    lookup_info.selector_polynomials_and_tables(domain, gates, xor_table_bits);
+
+                // The tables must not share their IDs, except the runtime tables
+                // extending fixed tables. In particular, if there is a gate using a
+                // lookup table, this table must not be added explicitly to the
+                // constraint system.
+                LookupTableRegistry::create(
+                    gates,
+                    &fixed_lookup_tables,
+                    runtime_tables.as_deref().unwrap_or_default(),
                 )?;
 
                 //~ 3. Concatenate explicit runtime lookup tables with the ones (implicitly) used by gates.
@@ -262,12 +290,16 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 // if we are using runtime tables
                 let (runtime_table_offset, runtime_selector) =
                     if let Some(runtime_tables) = &runtime_tables {
-                        // Check duplicates in runtime table ids
-                        let runtime_tables_ids: Vec<i32> =
-                            runtime_tables.iter().map(|rt| rt.id).collect();
-                        check_id_duplicates(runtime_tables_ids.iter(), "runtime table duplicates")?;
-                        // Runtime table IDs /may/ collide with lookup
-                        // table IDs, so we intentionally do not perform another potential check.
+                        // The keys of the runtime tables with unique keys are distinct
+                        for runtime_table in runtime_tables.iter().filter(|rt| rt.unique_keys) {
+                            let mut keys = runtime_table.first_column.clone();
+                            keys.sort_unstable();
+                            if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+                                return Err(LookupError::RuntimeTableDuplicateKeys {
+                                    id: runtime_table.id,
+                                });
+                            }
+                        }
 
                         // save the offset of the end of the table
                         let mut runtime_table_offset = 0;
@@ -384,6 +416,11 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 let mut non_zero_table_id = false;
                 let mut has_table_id_0_with_zero_entry = false;
 
+                let table_lengths: Vec<(i32, usize)> = lookup_tables
+                    .iter()
+                    .map(|table| (table.id, table.len()))
+                    .collect();
+
                 for table in &lookup_tables {
                     let table_len = table.len();
 
@@ -451,8 +488,8 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 let mut lookup_table_polys: Vec<DP<F>> = vec![];
                 let mut lookup_table8: Vec<E<F, D<F>>> = vec![];
                 for col in lookup_table {
-                    let poly = E::<F, D<F>>::from_vec_and_domain(col, domain.d1).interpolate();
-                    let eval = poly.evaluate_over_domain_by_ref(domain.d8);
+                    let poly = E::<F, D<F>>::from_vec_and_domain(col, domain.d1).fft_interpolate();
+                    let eval = poly.fft_evaluate_over_domain_by_ref(domain.d8);
                     lookup_table_polys.push(poly);
                     lookup_table8.push(eval);
                 }
@@ -461,8 +498,9 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 //~    only if a table with an ID different from zero was used.
                 let (table_ids, table_ids8) = if non_zero_table_id {
                     let table_ids: DP<F> =
-                        E::<F, D<F>>::from_vec_and_domain(table_ids, domain.d1).interpolate();
-                    let table_ids8: E<F, D<F>> = table_ids.evaluate_over_domain_by_ref(domain.d8);
+                        E::<F, D<F>>::from_vec_and_domain(table_ids, domain.d1).fft_interpolate();
+                    let table_ids8: E<F, D<F>> =
+                        table_ids.fft_evaluate_over_domain_by_ref(domain.d8);
                     (Some(table_ids), Some(table_ids8))
                 } else {
                     (None, None)
@@ -472,7 +510,7 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                 let runtime_tables =
                     runtime_tables.map(|rt| rt.into_iter().map(Into::into).collect());
 
-                let configuration = LookupConfiguration::new(lookup_info);
+                let configuration = LookupConfiguration::new(lookup_info).argument(argument);
 
                 Ok(Some(Self {
                     lookup_selectors,
@@ -483,6 +521,7 @@ impl<F: PrimeField + SquareRootField> LookupConstraintSystem<F> {
                     runtime_selector,
                     runtime_tables,
                     runtime_table_offset,
+                    table_lengths,
                     configuration,
                 }))
             }
@@ -496,7 +535,12 @@ mod tests {
     use super::{LookupError, LookupTable, RuntimeTableCfg};
     use crate::{
         circuits::{
-            constraints::ConstraintSystem, gate::CircuitGate, lookup::tables::xor,
+            constraints::ConstraintSystem,
+            gate::CircuitGate,
+            lookup::{
+                registry::{LookupTableKind, LookupTableRegistry},
+                tables::{xor, GateLookupTable, RANGE_CHECK_TABLE_ID},
+            },
             polynomials::range_check,
         },
         error::SetupError,
@@ -559,10 +603,12 @@ mod tests {
                 RuntimeTableCfg {
                     id: collision_id,
                     first_column: vec![From::from(0); 16],
+                    unique_keys: false,
                 },
                 RuntimeTableCfg {
                     id: collision_id,
                     first_column: vec![From::from(1); 16],
+                    unique_keys: false,
                 },
             ]))
             .build();
@@ -585,6 +631,7 @@ mod tests {
             .runtime(Some(vec![RuntimeTableCfg {
                 id: collision_id,
                 first_column: vec![From::from(1); 16],
+                unique_keys: false,
             }]))
             .build();
 
@@ -593,4 +640,66 @@ mod tests {
             "LookupConstraintSystem::create(...) must not fail when there is a collision between runtime and lookup ids"
         );
     }
+
+    #[test]
+    fn test_runtime_table_colliding_with_gate_table() {
+        let (_, gates) = CircuitGate::<Fp>::create_multi_range_check(0);
+
+        // the entries of the runtime table would pass the range checks
+        let cs = ConstraintSystem::<Fp>::create(gates)
+            .runtime(Some(vec![RuntimeTableCfg {
+                id: RANGE_CHECK_TABLE_ID,
+                first_column: vec![From::from(1u64 << 20); 16],
+                unique_keys: false,
+            }]))
+            .build();
+        match cs {
+            Err(SetupError::LookupCreation(error)) => assert_eq!(
+                error.to_string(),
+                "The lookup table ID 1 is used by both the range check table of the gates and a runtime table"
+            ),
+            _ => panic!("LookupConstraintSystem::create(...) must fail, collision with a gate table"),
+        }
+    }
+
+    #[test]
+    fn test_lookup_table_registry() {
+        let (_, gates) = CircuitGate::<Fp>::create_multi_range_check(0);
+        let fixed = LookupTable {
+            id: 2,
+            data: vec![vec![From::from(0); 16]],
+        };
+        let runtime = RuntimeTableCfg {
+            id: 2,
+            first_column: vec![From::from(1); 16],
+            unique_keys: false,
+        };
+        let mut registry =
+            LookupTableRegistry::create(&gates, &[fixed], &[runtime.clone()]).unwrap();
+        assert_eq!(registry.ids().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(
+            registry.kinds(RANGE_CHECK_TABLE_ID),
+            &[LookupTableKind::Gate(GateLookupTable::RangeCheck)]
+        );
+        assert_eq!(
+            registry.kinds(2),
+            &[
+                LookupTableKind::Fixed,
+                LookupTableKind::Runtime { unique_keys: false }
+            ]
+        );
+
+        // the assigned IDs are not used
+        assert_eq!(registry.assign(LookupTableKind::Fixed), 3);
+        assert_eq!(registry.assign(LookupTableKind::Fixed), 4);
+        assert!(matches!(
+            registry.register(4, LookupTableKind::Fixed),
+            Err(LookupError::LookupTableIdCollision { id: 4, .. })
+        ));
+        assert!(matches!(
+            registry.register(2, LookupTableKind::Runtime { unique_keys: false }),
+            Err(LookupError::LookupTableIdCollision { id: 2, .. })
+        ));
+        assert!(registry.kinds(5).is_empty());
+    }
 }
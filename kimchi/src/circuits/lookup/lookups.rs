@@ -1,13 +1,15 @@
 use crate::circuits::{
     domains::EvaluationDomains,
-    gate::{CircuitGate, CurrOrNext, GateType},
+    gate::{CircuitGate, Connect, CurrOrNext, GateType},
     lookup::{
-        index::LookupSelectors,
+        index::{LookupError, LookupSelectors},
         tables::{
-            combine_table_entry, get_table, GateLookupTable, LookupTable, RANGE_CHECK_TABLE_ID,
-            XOR_TABLE_ID,
+            combine_table_entry, get_table, xor, GateLookupTable, LookupTable,
+            RANGE_CHECK_TABLE_ID, XOR_TABLE_ID,
         },
     },
+    polynomial::COLUMNS,
+    wires::{Wire, PERMUTS},
 };
 use ark_ff::{Field, One, PrimeField, Zero};
 use ark_poly::{EvaluationDomain, Evaluations as E, Radix2EvaluationDomain as D};
@@ -34,6 +36,16 @@ type Evaluations<Field> = E<Field, D<Field>>;
 //~    `JointLookup { SingleLookup { value: vec![(scale1, col1), ..., (scale2, col2)] } }`
 //~   * Any combination of these
 
+/// The maximum number of lookups per row supported by the proofs, which
+/// contain the evaluations of at most `MAX_LOOKUPS_PER_ROW + 1` sorted polynomials.
+pub const MAX_LOOKUPS_PER_ROW: usize = 4;
+
+/// The number of cells looked up at once by the [LookupPattern::VectorLookup]
+/// pattern, in the columns following the table ID. All of them can be wired to
+/// other gates. Tables of smaller width are looked up by setting the cells in
+/// excess to zero.
+pub const VECTOR_LOOKUP_WIDTH: usize = PERMUTS - 1;
+
 fn max_lookups_per_row(kinds: LookupPatterns) -> usize {
     kinds
         .into_iter()
@@ -52,6 +64,7 @@ pub struct LookupPatterns {
     pub lookup: bool,
     pub range_check: bool,
     pub foreign_field_mul: bool,
+    pub vector_lookup: bool,
 }
 
 impl IntoIterator for LookupPatterns {
@@ -65,6 +78,7 @@ impl IntoIterator for LookupPatterns {
             lookup,
             range_check,
             foreign_field_mul,
+            vector_lookup,
         } = self;
 
         let mut patterns = Vec::with_capacity(5);
@@ -81,6 +95,9 @@ impl IntoIterator for LookupPatterns {
         if foreign_field_mul {
             patterns.push(LookupPattern::ForeignFieldMul)
         }
+        if vector_lookup {
+            patterns.push(LookupPattern::VectorLookup)
+        }
         patterns.into_iter()
     }
 }
@@ -94,6 +111,7 @@ impl std::ops::Index<LookupPattern> for LookupPatterns {
             LookupPattern::Lookup => &self.lookup,
             LookupPattern::RangeCheck => &self.range_check,
             LookupPattern::ForeignFieldMul => &self.foreign_field_mul,
+            LookupPattern::VectorLookup => &self.vector_lookup,
         }
     }
 }
@@ -105,6 +123,7 @@ impl std::ops::IndexMut<LookupPattern> for LookupPatterns {
             LookupPattern::Lookup => &mut self.lookup,
             LookupPattern::RangeCheck => &mut self.range_check,
             LookupPattern::ForeignFieldMul => &mut self.foreign_field_mul,
+            LookupPattern::VectorLookup => &mut self.vector_lookup,
         }
     }
 }
@@ -189,6 +208,33 @@ impl LookupInfo {
         }
     }
 
+    /// Sets the maximum number of lookups per row, which must be at least the
+    /// minimum number of lookups per row of the patterns, and at most [MAX_LOOKUPS_PER_ROW].
+    /// A smaller maximum needs fewer sorted polynomials, at the cost of splitting the
+    /// lookups of the [LookupPattern::Lookup] pattern over more rows (see [split_lookups]).
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the maximum is out of range.
+    pub fn with_max_per_row(mut self, max_per_row: usize) -> Result<Self, LookupError> {
+        let minimum = self
+            .features
+            .patterns
+            .into_iter()
+            .map(|pattern| pattern.min_lookups_per_row())
+            .max()
+            .unwrap_or(1);
+        if max_per_row < minimum || max_per_row > MAX_LOOKUPS_PER_ROW {
+            return Err(LookupError::MaxLookupsPerRowOutOfRange {
+                max_per_row,
+                minimum,
+                maximum: MAX_LOOKUPS_PER_ROW,
+            });
+        }
+        self.max_per_row = max_per_row;
+        Ok(self)
+    }
+
     pub fn create_from_gates<F: PrimeField>(
         gates: &[CircuitGate<F>],
         uses_runtime_tables: bool,
@@ -204,10 +250,12 @@ impl LookupInfo {
 
     /// Each entry in `kinds` has a corresponding selector polynomial that controls whether that
     /// lookup kind should be enforced at a given row. This computes those selector polynomials.
+    /// The XOR table used by the gates has words of `xor_table_bits` bits.
     pub fn selector_polynomials_and_tables<F: PrimeField>(
         &self,
         domain: &EvaluationDomains<F>,
         gates: &[CircuitGate<F>],
+        xor_table_bits: xor::XorTableBits,
     ) -> (LookupSelectors<Evaluations<F>>, Vec<LookupTable<F>>) {
         let n = domain.d1.size();
 
@@ -250,7 +298,13 @@ impl LookupInfo {
                 .interpolate()
                 .evaluate_over_domain(domain.d8)
         });
-        let res_tables: Vec<_> = gate_tables.into_iter().map(get_table).collect();
+        let res_tables: Vec<_> = gate_tables
+            .into_iter()
+            .map(|table| match table {
+                GateLookupTable::Xor => xor::xor_table_with(xor_table_bits),
+                _ => get_table(table),
+            })
+            .collect();
         (selector_values8, res_tables)
     }
 
@@ -261,14 +315,91 @@ impl LookupInfo {
             let typ = gates[i].typ;
 
             if let Some(lookup_pattern) = LookupPattern::from_gate(typ, CurrOrNext::Curr) {
-                kinds[i] = lookup_pattern.lookups();
+                kinds[i] = self.pattern_lookups(lookup_pattern);
             }
             if let Some(lookup_pattern) = LookupPattern::from_gate(typ, CurrOrNext::Next) {
-                kinds[i + 1] = lookup_pattern.lookups();
+                kinds[i + 1] = self.pattern_lookups(lookup_pattern);
             }
         }
         kinds
     }
+
+    /// The lookups of a pattern that are enforced on a row, that is its first `max_per_row` lookups.
+    pub fn pattern_lookups<F: Field>(&self, pattern: LookupPattern) -> Vec<JointLookupSpec<F>> {
+        let mut lookups = pattern.lookups();
+        lookups.truncate(self.max_per_row);
+        lookups
+    }
+}
+
+// For each row added to split the lookups of the first `rows` gates, the row
+// of the original lookup gate and the indices of the lookups it takes over
+fn split_lookups_layout<F: PrimeField>(
+    gates: &[CircuitGate<F>],
+    rows: usize,
+    max_per_row: usize,
+) -> Vec<(usize, Vec<usize>)> {
+    if max_per_row == 0 {
+        return vec![];
+    }
+    let exceeding: Vec<_> = (max_per_row..LookupPattern::Lookup.max_lookups_per_row()).collect();
+    gates
+        .iter()
+        .take(rows)
+        .enumerate()
+        .filter(|(_, gate)| gate.typ == GateType::Lookup)
+        .flat_map(|(row, _)| {
+            exceeding
+                .chunks(max_per_row)
+                .map(move |lookups| (row, lookups.to_vec()))
+        })
+        .collect()
+}
+
+/// Splits the [GateType::Lookup] gates that perform more than `max_per_row` lookups.
+/// The exceeding lookups of each gate are moved to new lookup gates appended to
+/// the circuit, whose table ID and lookup cells are copy-constrained to the
+/// ones of the original gate, which then only needs its first `max_per_row`
+/// lookups to be enforced (see [LookupInfo::pattern_lookups]).
+/// The witness of the new gates is computed by [extend_split_lookups_witness].
+pub fn split_lookups<F: PrimeField>(gates: &mut Vec<CircuitGate<F>>, max_per_row: usize) {
+    for (row, lookups) in split_lookups_layout(gates, gates.len(), max_per_row) {
+        let new_row = gates.len();
+        gates.push(CircuitGate::new(
+            GateType::Lookup,
+            Wire::for_row(new_row),
+            vec![],
+        ));
+        gates.connect_cell_pair((row, 0), (new_row, 0));
+        for (slot, lookup) in lookups.iter().enumerate() {
+            gates.connect_cell_pair((row, 2 * lookup + 1), (new_row, 2 * slot + 1));
+            gates.connect_cell_pair((row, 2 * lookup + 2), (new_row, 2 * slot + 2));
+        }
+    }
+}
+
+/// Extends the witness of a circuit with the rows added by [split_lookups].
+/// The `gates` are the ones of the split circuit, and the `witness` the one of
+/// the circuit before splitting.
+pub fn extend_split_lookups_witness<F: PrimeField>(
+    gates: &[CircuitGate<F>],
+    witness: &mut [Vec<F>; COLUMNS],
+    max_per_row: usize,
+) {
+    let rows = witness[0].len();
+    for (row, lookups) in split_lookups_layout(gates, rows, max_per_row) {
+        let mut new_row = [F::zero(); COLUMNS];
+        new_row[0] = witness[0][row];
+        // the enforced lookups left unused repeat the first one
+        for slot in 0..max_per_row {
+            let lookup = lookups.get(slot).unwrap_or(&lookups[0]);
+            new_row[2 * slot + 1] = witness[2 * lookup + 1][row];
+            new_row[2 * slot + 2] = witness[2 * lookup + 2][row];
+        }
+        for col in 0..COLUMNS {
+            witness[col].push(new_row[col]);
+        }
+    }
 }
 
 /// A position in the circuit relative to a given row.
@@ -392,6 +523,7 @@ pub enum LookupPattern {
     Lookup,
     RangeCheck,
     ForeignFieldMul,
+    VectorLookup,
 }
 
 impl LookupPattern {
@@ -400,6 +532,20 @@ impl LookupPattern {
         match self {
             LookupPattern::Xor | LookupPattern::RangeCheck | LookupPattern::ForeignFieldMul => 4,
             LookupPattern::Lookup => 3,
+            LookupPattern::VectorLookup => 1,
+        }
+    }
+
+    /// Returns the minimum number of lookups per row that the pattern can be split into.
+    /// The lookups of [LookupPattern::Lookup] are independent of each other and can be
+    /// spread over several rows (see [split_lookups]), while the other patterns look up
+    /// cells at fixed positions of their gates.
+    pub fn min_lookups_per_row(&self) -> usize {
+        match self {
+            LookupPattern::Xor | LookupPattern::RangeCheck | LookupPattern::ForeignFieldMul => {
+                self.max_lookups_per_row()
+            }
+            LookupPattern::Lookup | LookupPattern::VectorLookup => 1,
         }
     }
 
@@ -409,6 +555,7 @@ impl LookupPattern {
             LookupPattern::Xor => 3,
             LookupPattern::Lookup => 2,
             LookupPattern::ForeignFieldMul | LookupPattern::RangeCheck => 1,
+            LookupPattern::VectorLookup => VECTOR_LOOKUP_WIDTH as u32,
         }
     }
 
@@ -496,6 +643,18 @@ impl LookupPattern {
                     })
                     .collect()
             }
+            LookupPattern::VectorLookup => {
+                // 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14
+                // i v v v v v v - - - -  -  -  -  -
+                vec![JointLookup {
+                    table_id: LookupTableID::WitnessColumn(0),
+                    entry: (1..=VECTOR_LOOKUP_WIDTH)
+                        .map(|column| SingleLookup {
+                            value: vec![(F::one(), curr_row(column))],
+                        })
+                        .collect(),
+                }]
+            }
         }
     }
 
@@ -503,7 +662,7 @@ impl LookupPattern {
     pub fn table(&self) -> Option<GateLookupTable> {
         match self {
             LookupPattern::Xor => Some(GateLookupTable::Xor),
-            LookupPattern::Lookup => None,
+            LookupPattern::Lookup | LookupPattern::VectorLookup => None,
             LookupPattern::RangeCheck => Some(GateLookupTable::RangeCheck),
             LookupPattern::ForeignFieldMul => Some(GateLookupTable::RangeCheck),
         }
@@ -520,6 +679,7 @@ impl LookupPattern {
             }
             (ForeignFieldMul, Curr | Next) => Some(LookupPattern::ForeignFieldMul),
             (Xor16, Curr) => Some(LookupPattern::Xor),
+            (VectorLookup, Curr) => Some(LookupPattern::VectorLookup),
             _ => None,
         }
     }
@@ -533,6 +693,7 @@ impl GateType {
             LookupPattern::Lookup,
             LookupPattern::RangeCheck,
             LookupPattern::ForeignFieldMul,
+            LookupPattern::VectorLookup,
         ]
     }
 }
@@ -566,12 +727,14 @@ pub mod wasm {
             lookup: bool,
             range_check: bool,
             foreign_field_mul: bool,
+            vector_lookup: bool,
         ) -> LookupPatterns {
             LookupPatterns {
                 xor,
                 lookup,
                 range_check,
                 foreign_field_mul,
+                vector_lookup,
             }
         }
     }
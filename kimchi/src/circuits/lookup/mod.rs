@@ -4,5 +4,7 @@
 pub mod constraints;
 pub mod index;
 pub mod lookups;
+pub mod registry;
 pub mod runtime_tables;
+pub mod shared_tables;
 pub mod tables;
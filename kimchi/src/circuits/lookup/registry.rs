@@ -0,0 +1,160 @@
+//! This module implements the registry of the IDs of the lookup tables of a
+//! circuit.
+//!
+//! The lookups of a table are combined with its ID, so that two tables sharing
+//! an ID behave as a single table: a lookup meant for one of them also succeeds
+//! with the entries of the other. The registry records the origin of each ID,
+//! and rejects at `ConstraintSystem` build time the IDs shared by
+//!
+//! - two tables used by the gates, two fixed tables or two runtime tables,
+//! - a table used by the gates and any other table, as the entries of the
+//!   other table would pass the lookups of the gates,
+//! - a runtime table with unique keys and any other table.
+//!
+//! A runtime table may share the ID of a fixed table to extend it with
+//! runtime entries, as long as its keys are not required to be unique.
+//!
+//! A circuit adding its own tables can get unused IDs from
+//! [LookupTableRegistry::assign].
+
+use super::{
+    index::LookupError,
+    lookups::LookupPatterns,
+    runtime_tables::RuntimeTableCfg,
+    tables::{GateLookupTable, LookupTable},
+};
+use crate::circuits::gate::CircuitGate;
+use ark_ff::PrimeField;
+use std::{collections::BTreeMap, fmt};
+
+/// The origin of a lookup table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookupTableKind {
+    /// A table used by the gates of the circuit
+    Gate(GateLookupTable),
+    /// A fixed table given to the constraint system
+    Fixed,
+    /// A runtime table
+    Runtime {
+        /// Whether the keys of the table are unique
+        unique_keys: bool,
+    },
+}
+
+impl fmt::Display for LookupTableKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupTableKind::Gate(GateLookupTable::Xor) => write!(f, "the XOR table of the gates"),
+            LookupTableKind::Gate(GateLookupTable::RangeCheck) => {
+                write!(f, "the range check table of the gates")
+            }
+            LookupTableKind::Fixed => write!(f, "a fixed table"),
+            LookupTableKind::Runtime { unique_keys: false } => write!(f, "a runtime table"),
+            LookupTableKind::Runtime { unique_keys: true } => {
+                write!(f, "a runtime table with unique keys")
+            }
+        }
+    }
+}
+
+/// The IDs of the lookup tables of a circuit, with their origins
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LookupTableRegistry {
+    tables: BTreeMap<i32, Vec<LookupTableKind>>,
+}
+
+impl LookupTableRegistry {
+    /// Creates the registry of the tables used by `gates`, of the fixed tables
+    /// `fixed_tables` and of the runtime tables `runtime_tables`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if two tables share an ID, see the module documentation.
+    pub fn create<F: PrimeField>(
+        gates: &[CircuitGate<F>],
+        fixed_tables: &[LookupTable<F>],
+        runtime_tables: &[RuntimeTableCfg<F>],
+    ) -> Result<Self, LookupError> {
+        let mut registry = LookupTableRegistry::default();
+        for table in LookupPatterns::from_gates(gates)
+            .into_iter()
+            .filter_map(|pattern| pattern.table())
+        {
+            // several patterns use the range check table
+            if !registry
+                .kinds(table.id())
+                .contains(&LookupTableKind::Gate(table))
+            {
+                registry.register(table.id(), LookupTableKind::Gate(table))?;
+            }
+        }
+        for table in fixed_tables {
+            registry.register(table.id, LookupTableKind::Fixed)?;
+        }
+        for table in runtime_tables {
+            registry.register(
+                table.id,
+                LookupTableKind::Runtime {
+                    unique_keys: table.unique_keys,
+                },
+            )?;
+        }
+        Ok(registry)
+    }
+
+    /// Registers a table of kind `kind` with ID `id`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the ID is already used by a table it cannot be
+    /// shared with, see the module documentation.
+    pub fn register(&mut self, id: i32, kind: LookupTableKind) -> Result<(), LookupError> {
+        if let Some(&first) = self
+            .kinds(id)
+            .iter()
+            .find(|&&first| !Self::can_share(first, kind))
+        {
+            return Err(LookupError::LookupTableIdCollision {
+                id,
+                first,
+                second: kind,
+            });
+        }
+        self.tables.entry(id).or_default().push(kind);
+        Ok(())
+    }
+
+    /// Registers a table of kind `kind` with the smallest positive ID which is
+    /// not used, and returns this ID
+    pub fn assign(&mut self, kind: LookupTableKind) -> i32 {
+        let id = (1..)
+            .find(|id| !self.tables.contains_key(id))
+            .expect("there is an unused ID");
+        self.tables.insert(id, vec![kind]);
+        id
+    }
+
+    /// Returns the kinds of the tables with ID `id`
+    pub fn kinds(&self, id: i32) -> &[LookupTableKind] {
+        self.tables.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the used IDs, in increasing order
+    pub fn ids(&self) -> impl Iterator<Item = i32> + '_ {
+        self.tables.keys().copied()
+    }
+
+    /// Whether tables of kinds `first` and `second` can share an ID
+    fn can_share(first: LookupTableKind, second: LookupTableKind) -> bool {
+        matches!(
+            (first, second),
+            (
+                LookupTableKind::Fixed,
+                LookupTableKind::Runtime { unique_keys: false }
+            ) | (
+                LookupTableKind::Runtime { unique_keys: false },
+                LookupTableKind::Fixed
+            )
+        )
+    }
+}
@@ -1,10 +1,13 @@
 //! Runtime tables are tables (or arrays) that can be produced during proof creation.
 //! The setup has to prepare for their presence using [`RuntimeTableCfg`].
-//! At proving time, the prover can use [`RuntimeTable`] to specify the actual tables.
+//! At proving time, the prover can use [`RuntimeTable`] to specify the actual tables,
+//! or [`LazyRuntimeTable`] to produce them during proof creation.
 
 // TODO: write cargo specifications
 
-use crate::circuits::{berkeley_columns::Column, expr::prologue::*, gate::CurrOrNext};
+use crate::circuits::{
+    berkeley_columns::Column, expr::prologue::*, gate::CurrOrNext, polynomial::COLUMNS,
+};
 
 use ark_ff::Field;
 use serde::{Deserialize, Serialize};
@@ -27,6 +30,16 @@ pub struct RuntimeTableCfg<F> {
     pub id: i32,
     /// The content of the first column of the runtime table.
     pub first_column: Vec<F>,
+    /// Whether the entries of the first column must be distinct.
+    ///
+    /// When set, the setup checks that no key is repeated in the first column,
+    /// and that no other lookup table uses the ID of the runtime table. Each
+    /// key is then associated to a single value, the one provided by the
+    /// prover in the second column, so that the runtime table can be used as
+    /// a key-value map. Otherwise, the prover can use several values for a
+    /// repeated key.
+    #[serde(default)]
+    pub unique_keys: bool,
 }
 
 impl<F> RuntimeTableCfg<F> {
@@ -65,6 +78,60 @@ pub struct RuntimeTable<F> {
     pub data: Vec<F>,
 }
 
+/// The data available to the producer of a [`LazyRuntimeTable`].
+///
+/// The runtime tables are produced after the witness is committed, but before
+/// any challenge of the lookup argument is sampled, so that the data cannot
+/// depend on them.
+pub struct RuntimeTableContext<'a, F> {
+    /// The ID of the produced table.
+    pub id: i32,
+    /// The witness, padded to the size of the domain.
+    /// Its last rows are the random zero-knowledge rows.
+    pub witness: &'a [Vec<F>; COLUMNS],
+}
+
+/// A function writing the data of a runtime table in the slice it is given,
+/// whose length is the one of the table.
+pub type RuntimeTableProducer<'a, F> = Box<dyn FnOnce(&RuntimeTableContext<F>, &mut [F]) + 'a>;
+
+/// A runtime table whose data is produced by a closure during proof creation,
+/// directly in the runtime table column of the prover.
+/// This avoids materializing very large runtime tables before calling the prover.
+/// Lazy runtime tables must match the configuration that was specified in
+/// [`RuntimeTableCfg`].
+pub struct LazyRuntimeTable<'a, F> {
+    /// The table id.
+    pub id: i32,
+    /// The number of entries of the table.
+    pub len: usize,
+    /// The producer of the data of the table.
+    pub data: RuntimeTableProducer<'a, F>,
+}
+
+impl<'a, F> LazyRuntimeTable<'a, F> {
+    /// Creates a runtime table of `len` entries, whose data is produced by `data`.
+    pub fn new(
+        id: i32,
+        len: usize,
+        data: impl FnOnce(&RuntimeTableContext<F>, &mut [F]) + 'a,
+    ) -> Self {
+        Self {
+            id,
+            len,
+            data: Box::new(data),
+        }
+    }
+}
+
+impl<'a, F: Copy> From<&'a RuntimeTable<F>> for LazyRuntimeTable<'a, F> {
+    fn from(rt: &'a RuntimeTable<F>) -> Self {
+        Self::new(rt.id, rt.data.len(), |_, data| {
+            data.copy_from_slice(&rt.data)
+        })
+    }
+}
+
 /// Returns the constraints related to the runtime tables.
 pub fn constraints<F>() -> Vec<E<F>>
 where
@@ -182,6 +249,7 @@ pub mod caml {
                     .into_iter()
                     .map(Into::into)
                     .collect(),
+                unique_keys: false,
             }
         }
     }
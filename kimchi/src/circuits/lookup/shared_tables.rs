@@ -0,0 +1,191 @@
+//! This module implements commitments to fixed lookup tables that are shared
+//! between several verifier indexes.
+//!
+//! The commitments to the fixed tables of a circuit only depend on the tables
+//! and on the size of the domain, so that circuits using the same set of tables
+//! (e.g. the sparse tables of Keccak) over domains of the same size have the
+//! same commitments. A verifier index can detach them with
+//! [VerifierIndex::share_lookup_tables], keeping only their hash, so that a
+//! node verifying many circuits stores and loads each set of tables only once,
+//! in a [SharedLookupTablesStore]. The commitments are attached back with
+//! [VerifierIndex::attach_lookup_tables] before verifying proofs.
+
+use crate::{curve::KimchiCurve, verifier_index::VerifierIndex};
+use blake2::{Blake2b512, Digest};
+use poly_commitment::{
+    commitment::{CommitmentCurve, PolyComm},
+    OpenProof,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+
+/// Errors that can arise when attaching shared lookup tables to an index
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SharedTablesError {
+    #[error("the verifier index does not use shared lookup tables")]
+    NotShared,
+    #[error("the shared lookup tables {0} are missing")]
+    Missing(String),
+    #[error("the verifier index expects the shared lookup tables {expected}, found {found}")]
+    Mismatch { expected: String, found: String },
+}
+
+/// Commitments to a set of fixed lookup tables, identified by their hash
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SharedLookupTables<G: CommitmentCurve> {
+    /// The hash of the commitments, see [SharedLookupTables::hash]
+    pub id: String,
+    /// The commitments to the columns of the tables
+    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
+    pub lookup_table: Vec<PolyComm<G>>,
+    /// The commitment to the table IDs, if any
+    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
+    pub table_ids: Option<PolyComm<G>>,
+}
+
+impl<G: CommitmentCurve> SharedLookupTables<G> {
+    /// Creates the shared set of tables with the given commitments
+    pub fn new(lookup_table: Vec<PolyComm<G>>, table_ids: Option<PolyComm<G>>) -> Self {
+        SharedLookupTables {
+            id: Self::hash(&lookup_table, table_ids.as_ref()),
+            lookup_table,
+            table_ids,
+        }
+    }
+
+    /// Returns the hexadecimal Blake2b digest of the commitments to the tables
+    pub fn hash(lookup_table: &[PolyComm<G>], table_ids: Option<&PolyComm<G>>) -> String {
+        let mut hasher = Blake2b512::new();
+        hasher.update((lookup_table.len() as u64).to_be_bytes());
+        hasher.update([table_ids.is_some() as u8]);
+        for comm in lookup_table.iter().chain(table_ids) {
+            hasher.update((comm.elems.len() as u64).to_be_bytes());
+            for point in &comm.elems {
+                let mut bytes = vec![];
+                point
+                    .serialize(&mut bytes)
+                    .expect("serialization of a point into a vector cannot fail");
+                hasher.update(bytes);
+            }
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns whether the identifier of the tables is the hash of their
+    /// commitments, which should be checked for tables from untrusted sources
+    pub fn is_consistent(&self) -> bool {
+        self.id == Self::hash(&self.lookup_table, self.table_ids.as_ref())
+    }
+}
+
+/// A store of shared lookup tables, indexed by their hash
+#[derive(Debug, Clone)]
+pub struct SharedLookupTablesStore<G: CommitmentCurve> {
+    tables: HashMap<String, Arc<SharedLookupTables<G>>>,
+}
+
+impl<G: CommitmentCurve> Default for SharedLookupTablesStore<G> {
+    fn default() -> Self {
+        SharedLookupTablesStore {
+            tables: HashMap::new(),
+        }
+    }
+}
+
+impl<G: CommitmentCurve> SharedLookupTablesStore<G> {
+    /// Adds a set of tables to the store, if not already present, and returns
+    /// its identifier
+    pub fn insert(&mut self, tables: SharedLookupTables<G>) -> String {
+        let id = tables.id.clone();
+        self.tables
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(tables));
+        id
+    }
+
+    /// Returns the set of tables with the given identifier
+    pub fn get(&self, id: &str) -> Option<Arc<SharedLookupTables<G>>> {
+        self.tables.get(id).cloned()
+    }
+
+    /// Returns the number of sets of tables in the store
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof> {
+    /// Detaches the commitments to the fixed lookup tables from the index,
+    /// keeping only their hash, and returns them.
+    /// Returns `None` if the index does not use lookups, or if its tables are
+    /// already detached.
+    pub fn share_lookup_tables(&mut self) -> Option<SharedLookupTables<G>> {
+        let lookup_index = self.lookup_index.as_mut()?;
+        if lookup_index.shared_tables.is_some() {
+            return None;
+        }
+        let tables = SharedLookupTables::new(
+            std::mem::take(&mut lookup_index.lookup_table),
+            lookup_index.table_ids.take(),
+        );
+        lookup_index.shared_tables = Some(tables.id.clone());
+        Some(tables)
+    }
+
+    /// Attaches the commitments to the shared lookup tables the index was
+    /// detached from.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the index does not use shared tables, or if the
+    /// tables are not the ones the index was detached from.
+    pub fn attach_lookup_tables(
+        &mut self,
+        tables: &SharedLookupTables<G>,
+    ) -> Result<(), SharedTablesError> {
+        let lookup_index = self
+            .lookup_index
+            .as_mut()
+            .ok_or(SharedTablesError::NotShared)?;
+        let expected = lookup_index
+            .shared_tables
+            .as_ref()
+            .ok_or(SharedTablesError::NotShared)?;
+        let found = SharedLookupTables::hash(&tables.lookup_table, tables.table_ids.as_ref());
+        if expected != &found {
+            return Err(SharedTablesError::Mismatch {
+                expected: expected.clone(),
+                found,
+            });
+        }
+        lookup_index.lookup_table = tables.lookup_table.clone();
+        lookup_index.table_ids = tables.table_ids.clone();
+        Ok(())
+    }
+
+    /// Attaches the commitments to the shared lookup tables from a store, see
+    /// [VerifierIndex::attach_lookup_tables].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the index does not use shared tables, or if the
+    /// tables are not in the store.
+    pub fn attach_lookup_tables_from(
+        &mut self,
+        store: &SharedLookupTablesStore<G>,
+    ) -> Result<(), SharedTablesError> {
+        let id = self
+            .lookup_index
+            .as_ref()
+            .and_then(|lookup_index| lookup_index.shared_tables.clone())
+            .ok_or(SharedTablesError::NotShared)?;
+        let tables = store.get(&id).ok_or(SharedTablesError::Missing(id))?;
+        self.attach_lookup_tables(&tables)
+    }
+}
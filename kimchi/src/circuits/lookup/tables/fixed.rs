@@ -0,0 +1,182 @@
+//! This module implements application-specific fixed lookup tables: tables
+//! whose content is known at setup time (precomputed curve points, S-boxes,
+//! ...), that are defined outside of kimchi and registered with the
+//! constraint system builder, instead of being added to this module.
+//!
+//! The registered tables are appended to the tables passed to
+//! [`crate::circuits::constraints::Builder::lookup`], and are therefore
+//! accounted for when computing the size of the domain. A registry can
+//! additionally be given a domain budget (see
+//! [`FixedTableRegistry::max_domain_size`]), in which case the setup fails
+//! instead of silently growing the domain when the tables do not fit.
+
+use super::{LookupTable, RANGE_CHECK_TABLE_ID, XOR_TABLE_ID};
+use ark_ff::FftField;
+use std::{collections::BTreeMap, fmt};
+use thiserror::Error;
+
+/// The table IDs used by the built-in tables, which cannot be registered
+pub const RESERVED_TABLE_IDS: [i32; 2] = [XOR_TABLE_ID, RANGE_CHECK_TABLE_ID];
+
+/// Errors that can arise when registering fixed tables, or when fitting them in the domain
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FixedTableError {
+    #[error("the table id {0} is reserved for a built-in table")]
+    ReservedId(i32),
+    #[error("the table id {0} is registered twice")]
+    DuplicateId(i32),
+    #[error("the table {0} has no column")]
+    NoColumn(&'static str),
+    #[error("the columns of the table {0} have different lengths")]
+    InconsistentLength(&'static str),
+    #[error("the table {0} is empty")]
+    Empty(&'static str),
+    #[error("the domain has size {domain_size}, but the budget is {max_domain_size} (the fixed tables use {fixed_table_rows} rows)")]
+    DomainBudgetExceeded {
+        domain_size: usize,
+        max_domain_size: usize,
+        fixed_table_rows: usize,
+    },
+}
+
+/// A lookup table whose content is fixed at setup time.
+///
+/// Tables that only depend on constants can be unit structs, while tables that
+/// depend on parameters (e.g. the multiples of a given generator) carry them.
+pub trait FixedTable<F: FftField> {
+    /// The identifier of the table, used in the lookups
+    const ID: i32;
+
+    /// A human-readable name for the table
+    const NAME: &'static str;
+
+    /// The columns of the table. All the columns must have the same length.
+    fn columns(&self) -> Vec<Vec<F>>;
+}
+
+/// The set of fixed tables that can be used by a circuit
+#[derive(Clone)]
+pub struct FixedTableRegistry<F> {
+    tables: BTreeMap<i32, (&'static str, LookupTable<F>)>,
+    max_domain_size: Option<usize>,
+}
+
+impl<F> Default for FixedTableRegistry<F> {
+    fn default() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            max_domain_size: None,
+        }
+    }
+}
+
+impl<F> fmt::Debug for FixedTableRegistry<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixedTableRegistry")
+            .field(
+                "tables",
+                &self
+                    .tables
+                    .iter()
+                    .map(|(id, (name, table))| (id, name, table.data.first().map_or(0, Vec::len)))
+                    .collect::<Vec<_>>(),
+            )
+            .field("max_domain_size", &self.max_domain_size)
+            .finish()
+    }
+}
+
+impl<F: FftField> FixedTableRegistry<F> {
+    /// Creates an empty registry, without domain budget
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum size of the domain that the circuit can use.
+    /// The setup of a circuit using this registry fails if its domain is larger.
+    pub fn max_domain_size(mut self, max_domain_size: usize) -> Self {
+        self.max_domain_size = Some(max_domain_size);
+        self
+    }
+
+    /// Registers the fixed table `table`.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the identifier of the table is reserved or already
+    /// registered, or if its columns are empty or of different lengths.
+    pub fn register<T: FixedTable<F>>(&mut self, table: &T) -> Result<(), FixedTableError> {
+        if RESERVED_TABLE_IDS.contains(&T::ID) {
+            return Err(FixedTableError::ReservedId(T::ID));
+        }
+        if self.tables.contains_key(&T::ID) {
+            return Err(FixedTableError::DuplicateId(T::ID));
+        }
+        let data = table.columns();
+        let Some(first) = data.first() else {
+            return Err(FixedTableError::NoColumn(T::NAME));
+        };
+        if first.is_empty() {
+            return Err(FixedTableError::Empty(T::NAME));
+        }
+        if data.iter().any(|col| col.len() != first.len()) {
+            return Err(FixedTableError::InconsistentLength(T::NAME));
+        }
+        self.tables
+            .insert(T::ID, (T::NAME, LookupTable { id: T::ID, data }));
+        Ok(())
+    }
+
+    /// Returns the number of registered tables
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Returns true if no table is registered
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Returns the table registered under `id`
+    pub fn get(&self, id: i32) -> Option<&LookupTable<F>> {
+        self.tables.get(&id).map(|(_, table)| table)
+    }
+
+    /// Returns the name of the table registered under `id`
+    pub fn name(&self, id: i32) -> Option<&'static str> {
+        self.tables.get(&id).map(|(name, _)| *name)
+    }
+
+    /// Returns the number of rows of the combined lookup table used by the registered tables
+    pub fn num_rows(&self) -> usize {
+        self.tables.values().map(|(_, table)| table.len()).sum()
+    }
+
+    /// Returns the maximum size of the domain, if a budget was set
+    pub fn domain_budget(&self) -> Option<usize> {
+        self.max_domain_size
+    }
+
+    /// Checks that a domain of size `domain_size` fits in the budget of the registry.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the domain is larger than the budget.
+    pub fn check_budget(&self, domain_size: usize) -> Result<(), FixedTableError> {
+        match self.max_domain_size {
+            Some(max_domain_size) if domain_size > max_domain_size => {
+                Err(FixedTableError::DomainBudgetExceeded {
+                    domain_size,
+                    max_domain_size,
+                    fixed_table_rows: self.num_rows(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the registered tables, by increasing identifier
+    pub fn tables(&self) -> impl Iterator<Item = &LookupTable<F>> {
+        self.tables.values().map(|(_, table)| table)
+    }
+}
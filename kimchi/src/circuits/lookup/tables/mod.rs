@@ -2,6 +2,7 @@ use ark_ff::{FftField, One, Zero};
 use poly_commitment::PolyComm;
 use serde::{Deserialize, Serialize};
 
+pub mod fixed;
 pub mod range_check;
 pub mod xor;
 
@@ -127,6 +128,14 @@ impl GateLookupTable {
             GateLookupTable::RangeCheck => range_check::TABLE_SIZE,
         }
     }
+
+    /// Returns the ID of the lookup table associated to a [`GateLookupTable`].
+    pub fn id(&self) -> i32 {
+        match self {
+            GateLookupTable::Xor => XOR_TABLE_ID,
+            GateLookupTable::RangeCheck => RANGE_CHECK_TABLE_ID,
+        }
+    }
 }
 
 /// Let's say we want to do a lookup in a "vector-valued" table `T: Vec<[F; n]>` (here I
@@ -1,5 +1,6 @@
 use crate::circuits::lookup::tables::{LookupTable, XOR_TABLE_ID};
 use ark_ff::Field;
+use serde::{Deserialize, Serialize};
 
 //~ The lookup table for 4-bit xor.
 //~ Note that it is constructed so that `(0, 0, 0)` is the last position in the table.
@@ -13,19 +14,81 @@ use ark_ff::Field;
 //~
 //~ will translate into a scalar multiplication by 0, which is free.
 
+/// The size of the words of the XOR lookup table, which is a parameter of the
+/// constraint system. The `Xor16` gates decompose their words in 4 words of
+/// this size, given by their coefficients (see [XorTableBits::coeffs]).
+/// Larger words halve the rows of the XOR gadgets, at the cost of a table of
+/// `2^16` entries instead of `2^8`, which is worth it for hash-heavy circuits.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum XorTableBits {
+    /// XOR of 4-bit words, with a table of 256 entries
+    #[default]
+    Four,
+    /// XOR of 8-bit words, with a table of 65536 entries
+    Eight,
+}
+
+impl XorTableBits {
+    /// All the sizes of the XOR table
+    pub const ALL: [XorTableBits; 2] = [XorTableBits::Four, XorTableBits::Eight];
+
+    /// Returns the number of bits of the words of the table
+    pub fn bits(self) -> usize {
+        match self {
+            XorTableBits::Four => 4,
+            XorTableBits::Eight => 8,
+        }
+    }
+
+    /// Returns the number of bits of the words handled by one `Xor16` row
+    pub fn row_bits(self) -> usize {
+        4 * self.bits()
+    }
+
+    /// Returns the number of entries of the table
+    pub fn table_size(self) -> usize {
+        1 << (2 * self.bits())
+    }
+
+    /// Returns the coefficients of the `Xor16` gates, which are the shifts
+    /// `2^b`, `2^(2b)`, `2^(3b)` and `2^(4b)` of the words of `b` bits
+    pub fn coeffs<F: Field>(self) -> Vec<F> {
+        (1..=4)
+            .map(|i| F::from(2u64).pow([(i * self.bits()) as u64]))
+            .collect()
+    }
+
+    /// Returns the size of the table used by a `Xor16` gate with the
+    /// coefficients `coeffs`, if any
+    pub fn from_coeffs<F: Field>(coeffs: &[F]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|bits| bits.coeffs::<F>() == coeffs)
+    }
+}
+
 /// Returns the XOR lookup table
 ///
 /// # Panics
 ///
 /// Will panic if `data` is invalid.
 pub fn xor_table<F: Field>() -> LookupTable<F> {
+    xor_table_with(XorTableBits::Four)
+}
+
+/// Returns the XOR lookup table of words of the given size
+///
+/// # Panics
+///
+/// Will panic if `data` is invalid.
+pub fn xor_table_with<F: Field>(bits: XorTableBits) -> LookupTable<F> {
     let mut data = vec![vec![]; 3];
 
-    // XOR for all possible four-bit arguments.
-    // I suppose this could be computed a bit faster using symmetry but it's quite
-    // small (16*16 = 256 entries) so let's just keep it simple.
-    for i in 0u32..=0b1111 {
-        for j in 0u32..=0b1111 {
+    // XOR for all possible arguments. The 4-bit table is quite small
+    // (16*16 = 256 entries) so let's just keep it simple.
+    let max = (1u32 << bits.bits()) - 1;
+    for i in 0u32..=max {
+        for j in 0u32..=max {
             data[0].push(F::from(i));
             data[1].push(F::from(j));
             data[2].push(F::from(i ^ j));
@@ -43,4 +106,5 @@ pub fn xor_table<F: Field>() -> LookupTable<F> {
     }
 }
 
+/// The number of entries of the 4-bit XOR table
 pub const TABLE_SIZE: usize = 256;
@@ -3,15 +3,22 @@ pub mod macros;
 
 pub mod argument;
 pub mod berkeley_columns;
+pub mod bus;
 pub mod constraints;
+pub mod custom_gate;
 pub mod domain_constant_evaluation;
 pub mod domains;
+pub mod export;
 pub mod expr;
 pub mod gate;
+pub mod gate_info;
 pub mod lookup;
 pub mod polynomial;
 pub mod polynomials;
+pub mod public_input;
+pub mod report;
 pub mod scalars;
 mod serialization_helper;
+pub mod split;
 pub mod wires;
 pub mod witness;
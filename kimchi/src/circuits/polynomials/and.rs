@@ -10,7 +10,7 @@ use crate::circuits::{
     gate::{CircuitGate, Connect},
     lookup::{
         self,
-        tables::{GateLookupTable, LookupTable},
+        tables::{xor::XorTableBits, GateLookupTable, LookupTable},
     },
     polynomial::COLUMNS,
     wires::Wire,
@@ -152,7 +152,13 @@ pub fn create_and_witness<F: PrimeField>(input1: F, input2: F, bytes: usize) ->
     let and_row = num_xors(bytes * 8) + 1;
     let mut and_witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![F::zero(); and_row + 1]);
 
-    init_xor(&mut and_witness, 0, bytes * 8, (input1, input2, xor));
+    init_xor(
+        &mut and_witness,
+        0,
+        bytes * 8,
+        XorTableBits::Four,
+        (input1, input2, xor),
+    );
     // Fill in double generic witness
     and_witness[0][and_row] = input1;
     and_witness[1][and_row] = input2;
@@ -0,0 +1,400 @@
+//! This module implements the ChaCha20 block function of
+//! [RFC 8439](https://www.rfc-editor.org/rfc/rfc8439) as a gadget, together
+//! with its witness generation.
+//!
+//! The gadget does not need new gate types, it reuses existing gadgets on
+//! 32-bit words:
+//!
+//! - `x + y mod 2^32`: a double generic gate checks `x + y = s` and
+//!   `s = z + 2^32 * c`, and a generic gate checks that the carry `c` is
+//!   boolean. The output `z` is range checked by the XOR that follows it in the
+//!   quarter round, or explicitly for the output of the block.
+//! - `x ^ y`: the XOR gadget for 32 bits, which also range checks its inputs and
+//!   output.
+//! - `x <<< r`: a double generic gate checks `2^r * x = lo + 2^32 * hi` and
+//!   `z = lo + hi`, and a XOR gadget range checks `lo` and `hi` as its inputs.
+//!   As `x` is the output of a XOR, `x < 2^32` and the decomposition is unique.
+//!
+//! The layout of the gadget is the following:
+//!
+//! | Rows  | Purpose                                                         |
+//! | ----- | --------------------------------------------------------------- |
+//! | 4     | `Generic` rows with the constants of the initial state          |
+//! | 18    | XOR gadgets range checking the key, counter and nonce by pairs  |
+//! | 2880  | 10 double rounds of 8 quarter rounds of 36 rows each            |
+//! | 32    | additions of the initial state to the output of the rounds      |
+//! | 24    | XOR gadgets range checking the output words by pairs            |
+//!
+//! The cells of the key, counter and nonce, and of the output words, are
+//! returned by [CircuitGate::extend_chacha20] to be wired by the caller.
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect},
+    polynomial::COLUMNS,
+    polynomials::{generic::GenericGateSpec, xor},
+    wires::Wire,
+};
+use ark_ff::{PrimeField, SquareRootField};
+use o1_utils::Two;
+use std::array;
+
+/// The constant words of the initial state, "expand 32-byte k"
+pub const CHACHA_CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// Number of rounds of ChaCha20, performed as double rounds
+pub const CHACHA_ROUNDS: usize = 20;
+
+/// Number of bits of the words of the state
+const WORD_BITS: usize = 32;
+
+/// The quarter rounds of a double round, as the indices of their words: the
+/// column rounds followed by the diagonal rounds
+const QUARTER_ROUNDS: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/// Returns the initial state of the block function
+pub fn chacha20_initial_state(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter;
+    state[13..].copy_from_slice(nonce);
+    state
+}
+
+/// Computes the ChaCha20 block function, whose output serialized in little
+/// endian is the keystream block number `counter`
+pub fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u32; 16] {
+    block(&mut Native, chacha20_initial_state(key, counter, nonce))
+}
+
+/// Encrypts (or decrypts) `data` with the keystream starting at block number
+/// `counter`
+pub fn chacha20_encrypt(key: &[u32; 8], counter: u32, nonce: &[u32; 3], data: &[u8]) -> Vec<u8> {
+    data.chunks(64)
+        .zip(counter..)
+        .flat_map(|(chunk, counter)| {
+            let stream = chacha20_block(key, counter, nonce)
+                .iter()
+                .flat_map(|word| word.to_le_bytes())
+                .collect::<Vec<_>>();
+            chunk
+                .iter()
+                .zip(stream)
+                .map(|(byte, stream)| byte ^ stream)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The cells of a ChaCha20 gadget, as (row, column) pairs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChaChaCells {
+    /// The words of the key
+    pub key: [(usize, usize); 8],
+    /// The block counter
+    pub counter: (usize, usize),
+    /// The words of the nonce
+    pub nonce: [(usize, usize); 3],
+    /// The words of the output of the block function
+    pub output: [(usize, usize); 16],
+}
+
+impl<F: PrimeField + SquareRootField> CircuitGate<F> {
+    /// Extends a ChaCha20 block function gadget
+    /// Includes:
+    /// - 4 Generic gates with the constants of the initial state
+    /// - the operations of the block function (see the module documentation)
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs and outputs of the gadget
+    /// Warning:
+    /// - don't forget to wire the key, counter and nonce cells to their values
+    pub fn extend_chacha20(gates: &mut Vec<Self>) -> (usize, ChaChaCells) {
+        let mut layout = Layout { gates };
+        let constants = CHACHA_CONSTANTS.map(|constant| layout.constant(constant));
+        let mut inputs = Vec::with_capacity(12);
+        for _ in 0..6 {
+            let row = layout.gates.len();
+            CircuitGate::extend_xor_gadget(layout.gates, WORD_BITS);
+            inputs.extend([(row, 0), (row, 1)]);
+        }
+        let state = array::from_fn(|i| if i < 4 { constants[i] } else { inputs[i - 4] });
+        let output = block(&mut layout, state);
+        let next_row = layout.gates.len();
+
+        let cells = ChaChaCells {
+            key: array::from_fn(|i| inputs[i]),
+            counter: inputs[8],
+            nonce: array::from_fn(|i| inputs[9 + i]),
+            output,
+        };
+        (next_row, cells)
+    }
+}
+
+/// Extends the rows of a ChaCha20 gadget (see `CircuitGate::extend_chacha20`)
+/// to the full witness, and returns the output of the block function
+pub fn extend_chacha20_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    key: &[u32; 8],
+    counter: u32,
+    nonce: &[u32; 3],
+) -> [u32; 16] {
+    let state = chacha20_initial_state(key, counter, nonce);
+    let mut rows = Rows { witness };
+    for constant in CHACHA_CONSTANTS {
+        rows.constant(constant);
+    }
+    for pair in state[4..].chunks(2) {
+        rows.range_check(pair[0], pair[1]);
+    }
+    block(&mut rows, state)
+}
+
+/// Creates the witness of a ChaCha20 gadget starting at row 0, and returns it
+/// together with the output of the block function
+pub fn create_chacha20_witness<F: PrimeField>(
+    key: &[u32; 8],
+    counter: u32,
+    nonce: &[u32; 3],
+) -> ([Vec<F>; COLUMNS], [u32; 16]) {
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    let output = extend_chacha20_witness(&mut witness, key, counter, nonce);
+    (witness, output)
+}
+
+/// The operations of the block function on 32-bit words. They are implemented
+/// natively, by the gates of the gadget, and by the rows of its witness, so
+/// that the layout of the gates and of the witness always agree.
+trait WordOps {
+    type Word: Copy;
+
+    /// Returns `x + y mod 2^32`
+    fn add(&mut self, x: Self::Word, y: Self::Word) -> Self::Word;
+
+    /// Returns `x ^ y`
+    fn xor(&mut self, x: Self::Word, y: Self::Word) -> Self::Word;
+
+    /// Returns `x` rotated to the left by `rot` bits
+    fn rotl(&mut self, x: Self::Word, rot: u32) -> Self::Word;
+
+    /// Checks that `x` and `y` are 32-bit words
+    fn range_check(&mut self, x: Self::Word, y: Self::Word);
+}
+
+/// Runs the block function from the initial state `state`
+fn block<O: WordOps>(ops: &mut O, state: [O::Word; 16]) -> [O::Word; 16] {
+    let mut words = state;
+    for _ in 0..CHACHA_ROUNDS / 2 {
+        for [a, b, c, d] in QUARTER_ROUNDS {
+            words[a] = ops.add(words[a], words[b]);
+            words[d] = ops.xor(words[d], words[a]);
+            words[d] = ops.rotl(words[d], 16);
+            words[c] = ops.add(words[c], words[d]);
+            words[b] = ops.xor(words[b], words[c]);
+            words[b] = ops.rotl(words[b], 12);
+            words[a] = ops.add(words[a], words[b]);
+            words[d] = ops.xor(words[d], words[a]);
+            words[d] = ops.rotl(words[d], 8);
+            words[c] = ops.add(words[c], words[d]);
+            words[b] = ops.xor(words[b], words[c]);
+            words[b] = ops.rotl(words[b], 7);
+        }
+    }
+    let output: [O::Word; 16] = array::from_fn(|i| ops.add(words[i], state[i]));
+    for pair in output.chunks(2) {
+        ops.range_check(pair[0], pair[1]);
+    }
+    output
+}
+
+/// The native implementation of the block function
+struct Native;
+
+impl WordOps for Native {
+    type Word = u32;
+
+    fn add(&mut self, x: u32, y: u32) -> u32 {
+        x.wrapping_add(y)
+    }
+
+    fn xor(&mut self, x: u32, y: u32) -> u32 {
+        x ^ y
+    }
+
+    fn rotl(&mut self, x: u32, rot: u32) -> u32 {
+        x.rotate_left(rot)
+    }
+
+    fn range_check(&mut self, _x: u32, _y: u32) {}
+}
+
+/// The gates of the gadget, whose words are the cells holding them
+struct Layout<'a, F: PrimeField> {
+    gates: &'a mut Vec<CircuitGate<F>>,
+}
+
+impl<'a, F: PrimeField + SquareRootField> Layout<'a, F> {
+    /// Returns the cell of a new row constraining it to `value`
+    fn constant(&mut self, value: u32) -> (usize, usize) {
+        let row = self.gates.len();
+        self.gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(row),
+            GenericGateSpec::Const(F::from(value)),
+            None,
+        ));
+        (row, 0)
+    }
+}
+
+impl<'a, F: PrimeField + SquareRootField> WordOps for Layout<'a, F> {
+    type Word = (usize, usize);
+
+    fn add(&mut self, x: Self::Word, y: Self::Word) -> Self::Word {
+        let row = self.gates.len();
+        // x + y = s
+        let sum = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: None,
+            output_coeff: None,
+        };
+        // s - 2^32 * c = z
+        let carry = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: Some(-F::two_pow(WORD_BITS as u64)),
+            output_coeff: None,
+        };
+        self.gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(row),
+            sum,
+            Some(carry),
+        ));
+        // c * c = c
+        let boolean = GenericGateSpec::Mul {
+            output_coeff: None,
+            mul_coeff: None,
+        };
+        self.gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(row + 1),
+            boolean,
+            None,
+        ));
+
+        self.gates.connect_cell_pair(x, (row, 0));
+        self.gates.connect_cell_pair(y, (row, 1));
+        self.gates.connect_cell_pair((row, 2), (row, 3));
+        for col in 0..3 {
+            self.gates.connect_cell_pair((row, 4), (row + 1, col));
+        }
+        (row, 5)
+    }
+
+    fn xor(&mut self, x: Self::Word, y: Self::Word) -> Self::Word {
+        let row = self.gates.len();
+        CircuitGate::extend_xor_gadget(self.gates, WORD_BITS);
+        self.gates.connect_cell_pair(x, (row, 0));
+        self.gates.connect_cell_pair(y, (row, 1));
+        (row, 2)
+    }
+
+    fn rotl(&mut self, x: Self::Word, rot: u32) -> Self::Word {
+        let row = self.gates.len();
+        // 2^rot * x - 2^32 * hi = lo
+        let shift = GenericGateSpec::Add {
+            left_coeff: Some(F::two_pow(rot as u64)),
+            right_coeff: Some(-F::two_pow(WORD_BITS as u64)),
+            output_coeff: None,
+        };
+        // lo + hi = z
+        let rotation = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: None,
+            output_coeff: None,
+        };
+        self.gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(row),
+            shift,
+            Some(rotation),
+        ));
+        self.gates.connect_cell_pair(x, (row, 0));
+        self.gates.connect_cell_pair((row, 2), (row, 3));
+        self.gates.connect_cell_pair((row, 1), (row, 4));
+        self.range_check((row, 2), (row, 1));
+        (row, 5)
+    }
+
+    fn range_check(&mut self, x: Self::Word, y: Self::Word) {
+        let row = self.gates.len();
+        CircuitGate::extend_xor_gadget(self.gates, WORD_BITS);
+        self.gates.connect_cell_pair(x, (row, 0));
+        self.gates.connect_cell_pair(y, (row, 1));
+    }
+}
+
+/// The rows of the witness of the gadget, whose words are their values
+struct Rows<'a, F: PrimeField> {
+    witness: &'a mut [Vec<F>; COLUMNS],
+}
+
+impl<'a, F: PrimeField> Rows<'a, F> {
+    /// Appends a row to the witness with the given first cells
+    fn push(&mut self, cells: &[u64]) {
+        for (col, column) in self.witness.iter_mut().enumerate() {
+            column.push(cells.get(col).map_or(F::zero(), |&cell| F::from(cell)));
+        }
+    }
+
+    /// Appends the row of a constant
+    fn constant(&mut self, value: u32) {
+        self.push(&[value.into()]);
+    }
+}
+
+impl<'a, F: PrimeField> WordOps for Rows<'a, F> {
+    type Word = u32;
+
+    fn add(&mut self, x: u32, y: u32) -> u32 {
+        let sum = u64::from(x) + u64::from(y);
+        let carry = sum >> WORD_BITS;
+        let z = x.wrapping_add(y);
+        self.push(&[x.into(), y.into(), sum, sum, carry, z.into()]);
+        self.push(&[carry, carry, carry]);
+        z
+    }
+
+    fn xor(&mut self, x: u32, y: u32) -> u32 {
+        xor::extend_xor_witness(self.witness, F::from(x), F::from(y), WORD_BITS);
+        x ^ y
+    }
+
+    fn rotl(&mut self, x: u32, rot: u32) -> u32 {
+        let shifted = u64::from(x) << rot;
+        let (lo, hi) = (shifted as u32, (shifted >> WORD_BITS) as u32);
+        let z = x.rotate_left(rot);
+        self.push(&[
+            x.into(),
+            hi.into(),
+            lo.into(),
+            lo.into(),
+            hi.into(),
+            z.into(),
+        ]);
+        self.range_check(lo, hi);
+        z
+    }
+
+    fn range_check(&mut self, x: u32, y: u32) {
+        xor::extend_xor_witness(self.witness, F::from(x), F::from(y), WORD_BITS);
+    }
+}
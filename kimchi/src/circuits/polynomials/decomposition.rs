@@ -0,0 +1,282 @@
+//! This module implements the decomposition of a field element in limbs of
+//! `k` bits, together with its witness generation. With `k = 1` the limbs are
+//! the bits of the element.
+//!
+//! The gadget does not need new gate types: the element `x` is packed from its
+//! `n` limbs `l_0, ..., l_{n-1}` with generic gates (see [super::select]), and
+//! each limb is constrained to be less than `2^k` by a lookup in the table
+//! [limb_table] of the pairs `(j, j)` for `0 <= j < 2^k`. For `k = 1` this is
+//! the booleanity of the bits, checked with a lookup instead of a generic gate.
+//!
+//! The order of the limbs is given by an [Endianness]:
+//!
+//! - little-endian: `x = sum_i l_i * 2^(k * i)`, the first limb is the least
+//!   significant one
+//! - big-endian: `x = sum_i l_i * 2^(k * (n - 1 - i))`, the first limb is the
+//!   most significant one, as in the byte order of most hash functions
+//!
+//! The decomposition is unique only if `n * k` is less than the number of bits
+//! of the field, which is required by the gadget. The gadget takes
+//! `ceil((n - 1) / 2)` rows of generic gates (one row if `n = 1`), followed by
+//! one `Generic` gate with the ID of the table and `ceil(n / 3)` `Lookup`
+//! gates.
+//!
+//! [pack] and [decompose] are the corresponding computations outside of the
+//! circuit, to be shared by the hashing and bitwise gadgets.
+
+use super::{
+    generic::GenericGateSpec,
+    select::{Generic, SELECTIONS_PER_ROW},
+};
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    lookup::tables::LookupTable,
+    polynomial::COLUMNS,
+    wires::Wire,
+};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::{field_helpers::i32_to_field, FieldHelpers, Two};
+
+/// The largest number of bits of a limb, which bounds the size of the table
+pub const MAX_LIMB_BITS: usize = 16;
+
+/// The order of the limbs of a decomposition
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// The first limb is the least significant one
+    #[default]
+    Little,
+    /// The first limb is the most significant one
+    Big,
+}
+
+impl Endianness {
+    /// The position of the `i`-th of `num_limbs` limbs, from the least
+    /// significant one
+    fn position(self, i: usize, num_limbs: usize) -> usize {
+        match self {
+            Endianness::Little => i,
+            Endianness::Big => num_limbs - 1 - i,
+        }
+    }
+}
+
+/// The cells of a decomposition
+pub struct DecompositionCells {
+    /// The decomposed value
+    pub value: (usize, usize),
+    /// The limbs, in the order of the endianness
+    pub limbs: Vec<(usize, usize)>,
+}
+
+/// The variables of a decomposition
+struct DecompositionVars {
+    value: usize,
+    limbs: Vec<usize>,
+}
+
+/// Checks the parameters of a decomposition of an element of `F`
+fn check_params<F: PrimeField>(limb_bits: usize, num_limbs: usize) {
+    assert!(
+        (1..=MAX_LIMB_BITS).contains(&limb_bits),
+        "the limbs must have between 1 and {MAX_LIMB_BITS} bits"
+    );
+    assert!(num_limbs > 0, "a decomposition needs at least one limb");
+    assert!(
+        limb_bits * num_limbs < F::size_in_bits(),
+        "the decomposition of {} bits is not unique in the field",
+        limb_bits * num_limbs
+    );
+}
+
+/// The lookup table with ID `id` of the limbs of `limb_bits` bits, containing
+/// the pairs `(j, j)` for `0 <= j < 2^limb_bits`
+///
+/// # Panics
+///
+/// Will panic if `limb_bits` is zero or larger than [MAX_LIMB_BITS].
+pub fn limb_table<F: PrimeField>(id: i32, limb_bits: usize) -> LookupTable<F> {
+    assert!(
+        (1..=MAX_LIMB_BITS).contains(&limb_bits),
+        "the limbs must have between 1 and {MAX_LIMB_BITS} bits"
+    );
+    let limbs: Vec<F> = (0..1u64 << limb_bits).map(F::from).collect();
+    LookupTable {
+        id,
+        data: vec![limbs.clone(), limbs],
+    }
+}
+
+/// Returns the element packed from `limbs` of `limb_bits` bits in the order of
+/// `endianness`, the inverse of [decompose]
+pub fn pack<F: PrimeField>(limbs: &[F], limb_bits: usize, endianness: Endianness) -> F {
+    limbs.iter().enumerate().fold(F::zero(), |x, (i, limb)| {
+        let position = endianness.position(i, limbs.len());
+        x + *limb * F::two_pow((limb_bits * position) as u64)
+    })
+}
+
+/// Returns the `num_limbs` limbs of `limb_bits` bits of `x` in the order of
+/// `endianness`
+///
+/// # Panics
+///
+/// Will panic if `x` does not fit in `num_limbs * limb_bits` bits.
+pub fn decompose<F: PrimeField>(
+    x: F,
+    limb_bits: usize,
+    num_limbs: usize,
+    endianness: Endianness,
+) -> Vec<F> {
+    let x = x.to_biguint();
+    assert!(
+        x.bits() as usize <= limb_bits * num_limbs,
+        "the value does not fit in {} bits",
+        limb_bits * num_limbs
+    );
+    let mask = (BigUint::from(1u32) << limb_bits) - 1u32;
+    (0..num_limbs)
+        .map(|i| {
+            let position = endianness.position(i, num_limbs);
+            let limb = (&x >> (limb_bits * position)) & &mask;
+            F::from_biguint(&limb).expect("a limb fits in the field")
+        })
+        .collect()
+}
+
+/// The operations packing the value from its limbs
+fn decomposition_gadget<F: PrimeField>(
+    limb_bits: usize,
+    num_limbs: usize,
+    endianness: Endianness,
+) -> (Generic<F>, DecompositionVars) {
+    let mut generic = Generic::default();
+    let limbs: Vec<usize> = (0..num_limbs).map(|_| generic.var()).collect();
+    let weight = |i| F::two_pow((limb_bits * endianness.position(i, num_limbs)) as u64);
+    let value = match limbs[..] {
+        [limb] => generic.affine(limb, F::one(), F::zero()),
+        _ => {
+            let mut value = generic.add(limbs[0], weight(0), limbs[1], weight(1));
+            for (i, &limb) in limbs.iter().enumerate().skip(2) {
+                value = generic.add(value, F::one(), limb, weight(i));
+            }
+            value
+        }
+    };
+    (generic, DecompositionVars { value, limbs })
+}
+
+impl<F: PrimeField> CircuitGate<F> {
+    /// Extends a decomposition of a value in `num_limbs` limbs of `limb_bits`
+    /// bits, see the module documentation
+    /// Includes:
+    /// - the double Generic gates packing the value
+    /// - 1 Generic gate with the ID of the table
+    /// - ceil(num_limbs / 3) Lookup gates
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - table_id  : ID of the table created with [limb_table]
+    /// - limb_bits : number of bits of a limb, 1 for a decomposition in bits
+    /// - num_limbs : number of limbs
+    /// - endianness: order of the limbs
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the value and of the limbs
+    /// Warning:
+    /// - the table [limb_table] must be added to the constraint system
+    /// - don't forget to wire the value
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `limb_bits` is zero or larger than [MAX_LIMB_BITS], if
+    /// `num_limbs` is zero, or if the decomposition is not unique in the field.
+    pub fn extend_decomposition(
+        gates: &mut Vec<Self>,
+        table_id: i32,
+        limb_bits: usize,
+        num_limbs: usize,
+        endianness: Endianness,
+    ) -> (usize, DecompositionCells) {
+        check_params::<F>(limb_bits, num_limbs);
+        let (generic, vars) = decomposition_gadget(limb_bits, num_limbs, endianness);
+        let cells = generic.layout_partial(gates);
+        let cells: Vec<(usize, usize)> = cells
+            .into_iter()
+            .map(|cell| cell.expect("every variable is used by an operation"))
+            .collect();
+
+        // the lookups (l_i, l_i) check the limbs, as in the lookup multiplexer
+        let start = gates.len();
+        gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(start),
+            GenericGateSpec::Const(i32_to_field(table_id)),
+            None,
+        ));
+        for (i, &limb) in vars.limbs.iter().enumerate() {
+            let row = start + 1 + i / SELECTIONS_PER_ROW;
+            if i % SELECTIONS_PER_ROW == 0 {
+                gates.push(CircuitGate::new(
+                    GateType::Lookup,
+                    Wire::for_row(row),
+                    vec![],
+                ));
+                gates.connect_cell_pair((start, 0), (row, 0));
+            }
+            let col = 2 * (i % SELECTIONS_PER_ROW) + 1;
+            gates.connect_cell_pair(cells[limb], (row, col));
+            gates.connect_cell_pair(cells[limb], (row, col + 1));
+        }
+
+        let decomposition = DecompositionCells {
+            value: cells[vars.value],
+            limbs: vars.limbs.iter().map(|&var| cells[var]).collect(),
+        };
+        (gates.len(), decomposition)
+    }
+}
+
+/// Extends the rows of a decomposition (see
+/// `CircuitGate::extend_decomposition`) to the full witness, and returns the
+/// limbs of `x` in the order of `endianness`
+///
+/// # Panics
+///
+/// Will panic if the parameters are not valid, or if `x` does not fit in
+/// `num_limbs * limb_bits` bits.
+pub fn extend_decomposition_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    table_id: i32,
+    limb_bits: usize,
+    num_limbs: usize,
+    endianness: Endianness,
+    x: F,
+) -> Vec<F> {
+    check_params::<F>(limb_bits, num_limbs);
+    let limbs = decompose(x, limb_bits, num_limbs, endianness);
+    let (generic, vars) = decomposition_gadget(limb_bits, num_limbs, endianness);
+    let mut values = vec![None; generic.num_vars];
+    for (&var, &limb) in vars.limbs.iter().zip(&limbs) {
+        values[var] = Some(limb);
+    }
+    let values = generic.witness(witness, values);
+    debug_assert_eq!(values[vars.value], x);
+
+    let table_id: F = i32_to_field(table_id);
+    let mut push = |cells: &[F]| {
+        for (col, column) in witness.iter_mut().enumerate() {
+            column.push(cells.get(col).copied().unwrap_or_else(F::zero));
+        }
+    };
+    push(&[table_id]);
+    for limbs in limbs.chunks(SELECTIONS_PER_ROW) {
+        // the unused lookups of the last row look up the zero limb
+        let mut cells = vec![table_id];
+        for i in 0..SELECTIONS_PER_ROW {
+            let limb = limbs.get(i).copied().unwrap_or_else(F::zero);
+            cells.extend([limb, limb]);
+        }
+        push(&cells);
+    }
+    limbs
+}
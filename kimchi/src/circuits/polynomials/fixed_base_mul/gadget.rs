@@ -0,0 +1,297 @@
+//! This module obtains the gates of a fixed-base scalar multiplication circuit.
+//!
+//! The gadget computes `k * G` for a scalar `k` of `num_bits` bits and a base point `G`
+//! fixed when the circuit is created (e.g. a generator of a Pedersen commitment), on the
+//! curve whose base field is the native field of the circuit. It does not need new gate
+//! types: it reuses the `Lookup`, `Generic` and `CompleteAdd` gates.
+//!
+//! The scalar is split into windows of `w` bits `k = sum_i d_i * 2^{w * i}`, and each
+//! window is replaced by its key `K_i = d_i * 2^{w * i}`, so that `k = sum_i K_i`.
+//! Two lookup tables map every possible key `K` of every window to the coordinates
+//! `x` (table `table_id`) and `y` (table `table_id + 1`) of the point `(K + 1) * G`.
+//! The offset of the points keeps them away from the point at infinity, and is corrected
+//! by adding the constant point `-n * G` to their sum, for `n` windows.
+//!
+//! As the keys are added to obtain the scalar, a key looked up in the "wrong" window
+//! leads to a different scalar, but not to a wrong result: the result is always
+//! `(sum_i K_i) * G` for the keys `K_i` of the table. The digits therefore do not need to
+//! be range checked, as long as the sum of any `n` keys cannot wrap around the native
+//! field or the scalar field, which is checked when the parameters are created.
+//!
+//! With windows of 8 bits, a scalar of 248 bits takes 70 rows, instead of 100 rows with
+//! the `VarBaseMul` gate, at the cost of 2 lookup tables of about 8000 entries each.
+//!
+//! The layout of the gadget is
+//!
+//! | Rows                  | Gates        | Content                                        |
+//! |-----------------------|--------------|------------------------------------------------|
+//! | 1                     | `Generic`    | the IDs of the two tables                      |
+//! | 1                     | `Generic`    | the coordinates of the correction `-n * G`     |
+//! | `ceil(n / 3)`         | `Lookup`     | the keys and the x coordinates, 3 per row      |
+//! | `ceil(n / 3)`         | `Lookup`     | the keys and the y coordinates, 3 per row      |
+//! | `ceil((n - 1) / 2)`   | `Generic`    | the sum of the keys, 2 per row                 |
+//! | `n`                   | `CompleteAdd`| the sum of the points and of the correction    |
+
+use ark_ec::ProjectiveCurve;
+use ark_ff::{One, PrimeField, Zero};
+use o1_utils::{field_helpers::i32_to_field, math::ceil_log2, Two};
+use poly_commitment::commitment::CommitmentCurve;
+use std::iter;
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    lookup::tables::LookupTable,
+    polynomials::generic::GenericGateSpec,
+    wires::Wire,
+};
+
+/// Number of lookups of a `Lookup` gate
+pub const LOOKUPS_PER_ROW: usize = 3;
+
+/// Parameters of a fixed-base scalar multiplication
+#[derive(Clone, Debug)]
+pub struct FixedBaseMulParams<G> {
+    /// The fixed base point
+    pub base: G,
+    /// Number of bits of the scalars
+    pub num_bits: usize,
+    /// Number of bits of a window
+    pub window: usize,
+    /// ID of the lookup table of the x coordinates, the table of the y coordinates
+    /// has ID `table_id + 1`
+    pub table_id: i32,
+}
+
+/// Cells of the input and outputs of a fixed-base scalar multiplication
+#[derive(Clone, Copy, Debug)]
+pub struct FixedBaseMulCells {
+    /// The scalar
+    pub scalar: (usize, usize),
+    /// The coordinates of the result
+    pub result: [(usize, usize); 2],
+    /// The boolean that is true iff the result is the point at infinity, i.e. iff the
+    /// scalar is zero. The coordinates of the result are meaningless in that case.
+    pub infinity: (usize, usize),
+}
+
+impl<G: CommitmentCurve> FixedBaseMulParams<G>
+where
+    G::BaseField: PrimeField,
+{
+    /// Creates the parameters of a fixed-base scalar multiplication
+    /// Panics if the base point is the point at infinity, if the window is empty, or if
+    /// the sum of the keys of the windows can wrap around the native field or the scalar
+    /// field (i.e. if `num_bits + ceil(log2(num_windows)) + 2` exceeds their size)
+    pub fn new(base: G, num_bits: usize, window: usize, table_id: i32) -> Self {
+        assert!(!base.is_zero(), "base must not be the point at infinity");
+        assert!(window > 0, "window must have at least 1 bit");
+        assert!(num_bits > 0, "scalars must have at least 1 bit");
+        let params = FixedBaseMulParams {
+            base,
+            num_bits,
+            window,
+            table_id,
+        };
+        let field_bits = std::cmp::min(
+            <G::BaseField as PrimeField>::size_in_bits(),
+            <G::ScalarField as PrimeField>::size_in_bits(),
+        );
+        assert!(
+            num_bits + ceil_log2(params.num_windows()) + 2 <= field_bits,
+            "scalars of {num_bits} bits are too large for windows of {window} bits"
+        );
+        params
+    }
+
+    /// Number of windows of the scalar, the last one can be smaller than the others
+    pub fn num_windows(&self) -> usize {
+        (self.num_bits + self.window - 1) / self.window
+    }
+
+    /// Number of bits of the window `i`
+    pub fn window_bits(&self, i: usize) -> usize {
+        std::cmp::min(self.window, self.num_bits - self.window * i)
+    }
+
+    /// Number of rows of the gadget
+    pub fn num_rows(&self) -> usize {
+        let num_windows = self.num_windows();
+        let lookup_rows = (num_windows + LOOKUPS_PER_ROW - 1) / LOOKUPS_PER_ROW;
+        2 + 2 * lookup_rows + num_windows / 2 + num_windows
+    }
+
+    /// The constant point `-n * G` added to the sum of the looked up points
+    pub fn correction(&self) -> G {
+        let num_windows = G::ScalarField::from(self.num_windows() as u64);
+        -self.base.mul(num_windows.into_repr()).into_affine()
+    }
+
+    /// Precomputes the entries of the tables: every key `K` of every window, with the
+    /// point `(K + 1) * G`. The key 0, shared by all the windows, appears once.
+    pub fn table_points(&self) -> Vec<(G::BaseField, G)> {
+        let base = self.base.into_projective();
+        let mut keys = vec![G::BaseField::zero()];
+        let mut points = vec![base];
+        // 2^{w * i} * G
+        let mut power = base;
+        let mut shift = G::BaseField::one();
+        for i in 0..self.num_windows() {
+            let mut point = base;
+            let mut key = G::BaseField::zero();
+            for _ in 1..(1usize << self.window_bits(i)) {
+                point += &power;
+                key += shift;
+                keys.push(key);
+                points.push(point);
+            }
+            for _ in 0..self.window {
+                power.double_in_place();
+            }
+            shift *= G::BaseField::two_pow(self.window as u64);
+        }
+        let points = G::Projective::batch_normalization_into_affine(&points);
+        keys.into_iter().zip(points).collect()
+    }
+
+    /// Lookup tables of the precomputed points, to be added to the constraint system
+    pub fn lookup_tables(&self) -> Vec<LookupTable<G::BaseField>> {
+        let (keys, (xs, ys)): (Vec<_>, (Vec<_>, Vec<_>)) = self
+            .table_points()
+            .into_iter()
+            .map(|(key, point)| {
+                let (x, y) = point
+                    .to_coordinates()
+                    .expect("order of the base point is too small");
+                (key, (x, y))
+            })
+            .unzip();
+        vec![
+            LookupTable {
+                id: self.table_id,
+                data: vec![keys.clone(), xs],
+            },
+            LookupTable {
+                id: self.table_id + 1,
+                data: vec![keys, ys],
+            },
+        ]
+    }
+}
+
+impl<F: PrimeField> CircuitGate<F> {
+    /// Extends a circuit with a fixed-base scalar multiplication, see the documentation
+    /// of this module for the details
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - curr_row  : mutable reference to the current row
+    /// - params    : parameters of the scalar multiplication
+    /// Output:
+    /// - cells of the scalar and of the result
+    /// Warning:
+    /// - the lookup tables [FixedBaseMulParams::lookup_tables] must be added to the
+    ///   constraint system
+    /// - don't forget to wire the scalar and the result
+    pub fn extend_fixed_base_mul<G: CommitmentCurve<BaseField = F>>(
+        gates: &mut Vec<Self>,
+        curr_row: &mut usize,
+        params: &FixedBaseMulParams<G>,
+    ) -> FixedBaseMulCells {
+        let start = *curr_row;
+        let num_windows = params.num_windows();
+
+        // the table IDs and the correction point
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(start),
+            GenericGateSpec::Const(i32_to_field(params.table_id)),
+            Some(GenericGateSpec::Const(i32_to_field(params.table_id + 1))),
+        );
+        let (x, y) = params
+            .correction()
+            .to_coordinates()
+            .expect("order of the base point is too small");
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(start + 1),
+            GenericGateSpec::Const(x),
+            Some(GenericGateSpec::Const(y)),
+        );
+        let correction = [(start + 1, 0), (start + 1, 3)];
+
+        // lookups of the coordinates of the points
+        let lookup_rows = (num_windows + LOOKUPS_PER_ROW - 1) / LOOKUPS_PER_ROW;
+        let x_rows = *curr_row;
+        let y_rows = x_rows + lookup_rows;
+        for row in x_rows..(y_rows + lookup_rows) {
+            gates.push(CircuitGate::new(
+                GateType::Lookup,
+                Wire::for_row(row),
+                vec![],
+            ));
+        }
+        *curr_row += 2 * lookup_rows;
+        for row in x_rows..y_rows {
+            gates.connect_cell_pair((start, 0), (row, 0));
+            gates.connect_cell_pair((start, 3), (row + lookup_rows, 0));
+        }
+        let mut keys = vec![];
+        let mut points = vec![];
+        for i in 0..num_windows {
+            let (row, col) = (x_rows + i / LOOKUPS_PER_ROW, 2 * (i % LOOKUPS_PER_ROW) + 1);
+            // the same key is used for both coordinates
+            gates.connect_cell_pair((row, col), (row + lookup_rows, col));
+            keys.push((row, col));
+            points.push([(row, col + 1), (row + lookup_rows, col + 1)]);
+        }
+
+        // sum of the keys
+        let sum = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: None,
+            output_coeff: None,
+        };
+        let sum_rows = *curr_row;
+        let mut scalar = keys[0];
+        for (i, key) in keys.iter().enumerate().skip(1) {
+            let (row, col) = (sum_rows + (i - 1) / 2, 3 * ((i - 1) % 2));
+            if col == 0 {
+                Self::extend_generic(
+                    gates,
+                    curr_row,
+                    Wire::for_row(row),
+                    sum.clone(),
+                    Some(sum.clone()),
+                );
+            }
+            gates.connect_cell_pair(scalar, (row, col));
+            gates.connect_cell_pair(*key, (row, col + 1));
+            scalar = (row, col + 2);
+        }
+
+        // sum of the points and of the correction
+        let mut result = points[0];
+        for right in points.iter().skip(1).chain(iter::once(&correction)) {
+            let row = *curr_row;
+            gates.push(CircuitGate::new(
+                GateType::CompleteAdd,
+                Wire::for_row(row),
+                vec![],
+            ));
+            *curr_row += 1;
+            gates.connect_cell_pair(result[0], (row, 0));
+            gates.connect_cell_pair(result[1], (row, 1));
+            gates.connect_cell_pair(right[0], (row, 2));
+            gates.connect_cell_pair(right[1], (row, 3));
+            result = [(row, 4), (row, 5)];
+        }
+        assert_eq!(*curr_row, start + params.num_rows());
+
+        FixedBaseMulCells {
+            scalar,
+            result,
+            infinity: (*curr_row - 1, 6),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Fixed-base scalar multiplication module
+
+pub mod gadget;
+pub mod witness;
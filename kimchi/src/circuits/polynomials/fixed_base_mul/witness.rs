@@ -0,0 +1,185 @@
+//! This module computes the witness of a fixed-base scalar multiplication circuit.
+
+use crate::circuits::polynomial::COLUMNS;
+use ark_ec::ProjectiveCurve;
+use ark_ff::{PrimeField, Zero};
+use num_bigint::BigUint;
+use num_traits::One;
+use o1_utils::{field_helpers::i32_to_field, FieldHelpers};
+use poly_commitment::commitment::CommitmentCurve;
+use std::array;
+
+use super::gadget::{FixedBaseMulParams, LOOKUPS_PER_ROW};
+
+fn extend_rows<F: PrimeField>(witness: &mut [Vec<F>; COLUMNS], rows: &[[F; COLUMNS]]) {
+    for row in rows {
+        for col in 0..COLUMNS {
+            witness[col].push(row[col]);
+        }
+    }
+}
+
+/// Computes the row of a `CompleteAdd` gate adding the points `(x1, y1)` and `(x2, y2)`,
+/// which must not be the point at infinity, and returns it together with the
+/// coordinates of the sum (meaningless if the sum is the point at infinity)
+pub fn complete_add_row<F: PrimeField>(
+    (x1, y1): (F, F),
+    (x2, y2): (F, F),
+) -> ([F; COLUMNS], (F, F)) {
+    let same_x = x1 == x2;
+    let s = if same_x {
+        // 2 * s * y1 = 3 * x1^2
+        let x1_squared = x1.square();
+        (x1_squared.double() + x1_squared) / y1.double()
+    } else {
+        (y2 - y1) / (x2 - x1)
+    };
+    let x3 = s.square() - x1 - x2;
+    let y3 = s * (x1 - x3) - y1;
+    let inf = same_x && y1 != y2;
+    let inf_z = if inf {
+        (y2 - y1).inverse().unwrap()
+    } else {
+        F::zero()
+    };
+    let x21_inv = if same_x {
+        F::zero()
+    } else {
+        (x2 - x1).inverse().unwrap()
+    };
+
+    let mut row = [F::zero(); COLUMNS];
+    row[0..11].copy_from_slice(&[
+        x1,
+        y1,
+        x2,
+        y2,
+        x3,
+        y3,
+        F::from(inf),
+        F::from(same_x),
+        s,
+        inf_z,
+        x21_inv,
+    ]);
+    (row, (x3, y3))
+}
+
+/// Extends the witness with a fixed-base scalar multiplication `scalar * base`,
+/// and returns the product
+/// Panics if the scalar has more than `num_bits` bits
+pub fn extend<G: CommitmentCurve>(
+    witness: &mut [Vec<G::BaseField>; COLUMNS],
+    scalar: &BigUint,
+    params: &FixedBaseMulParams<G>,
+) -> G
+where
+    G::BaseField: PrimeField,
+{
+    assert!(
+        scalar.bits() as usize <= params.num_bits,
+        "the scalar has more than {} bits",
+        params.num_bits
+    );
+    let num_windows = params.num_windows();
+    let coordinates = |point: G| {
+        point
+            .to_coordinates()
+            .expect("order of the base point is too small")
+    };
+
+    // The table IDs and the correction point
+    let mut rows = [[G::BaseField::zero(); COLUMNS]; 2];
+    rows[0][0] = i32_to_field(params.table_id);
+    rows[0][3] = i32_to_field(params.table_id + 1);
+    (rows[1][0], rows[1][3]) = coordinates(params.correction());
+    extend_rows(witness, &rows);
+
+    // The keys of the windows, and their points (K + 1) * G
+    let keys: Vec<BigUint> = (0..num_windows)
+        .map(|i| {
+            let shift = params.window * i;
+            let mask = (BigUint::one() << params.window_bits(i)) - BigUint::one();
+            ((scalar >> shift) & mask) << shift
+        })
+        .collect();
+    let points: Vec<(G::BaseField, G::BaseField)> = keys
+        .iter()
+        .map(|key| {
+            let multiple = G::ScalarField::from_biguint(&(key + BigUint::one())).unwrap();
+            coordinates(params.base.mul(multiple.into_repr()).into_affine())
+        })
+        .collect();
+    let keys: Vec<G::BaseField> = keys
+        .iter()
+        .map(|key| G::BaseField::from_biguint(key).unwrap())
+        .collect();
+
+    // Lookups of the points, the unused lookups of the last rows repeat the first one
+    // of their row
+    for coordinate in 0..2 {
+        for (keys, points) in keys
+            .chunks(LOOKUPS_PER_ROW)
+            .zip(points.chunks(LOOKUPS_PER_ROW))
+        {
+            let mut row = [G::BaseField::zero(); COLUMNS];
+            row[0] = rows[0][3 * coordinate];
+            for slot in 0..LOOKUPS_PER_ROW {
+                let i = if slot < keys.len() { slot } else { 0 };
+                row[2 * slot + 1] = keys[i];
+                row[2 * slot + 2] = if coordinate == 0 {
+                    points[i].0
+                } else {
+                    points[i].1
+                };
+            }
+            extend_rows(witness, &[row]);
+        }
+    }
+
+    // Sum of the keys
+    let mut halves = vec![];
+    let mut sum = keys[0];
+    for key in keys.iter().skip(1) {
+        halves.push([sum, *key, sum + key]);
+        sum += key;
+    }
+    for pair in halves.chunks(2) {
+        let mut row = [G::BaseField::zero(); COLUMNS];
+        for (i, half) in pair.iter().enumerate() {
+            row[(3 * i)..(3 * i + 3)].copy_from_slice(half);
+        }
+        extend_rows(witness, &[row]);
+    }
+
+    // Sum of the points and of the correction
+    let correction = (rows[1][0], rows[1][3]);
+    let mut infinity = false;
+    let mut result = points[0];
+    for right in points.iter().skip(1).chain(std::iter::once(&correction)) {
+        let (row, sum) = complete_add_row(result, *right);
+        infinity = row[6] == G::BaseField::one();
+        extend_rows(witness, &[row]);
+        result = sum;
+    }
+
+    if infinity {
+        G::zero()
+    } else {
+        G::of_coordinates(result.0, result.1)
+    }
+}
+
+/// Creates the witness of a fixed-base scalar multiplication `scalar * base`,
+/// and returns it together with the product
+pub fn create<G: CommitmentCurve>(
+    scalar: &BigUint,
+    params: &FixedBaseMulParams<G>,
+) -> ([Vec<G::BaseField>; COLUMNS], G)
+where
+    G::BaseField: PrimeField,
+{
+    let mut witness = array::from_fn(|_| vec![]);
+    let result = extend(&mut witness, scalar, params);
+    (witness, result)
+}
@@ -3,6 +3,7 @@
 use crate::{
     circuits::{
         expr::constraints::compact_limb,
+        gate::GateType,
         polynomial::COLUMNS,
         polynomials::foreign_field_common::{
             BigUintForeignFieldHelpers, KimchiForeignElement, HI, LIMB_BITS, LO, MI,
@@ -177,6 +178,38 @@ pub fn create_chain<F: PrimeField>(
     witness
 }
 
+/// Extends a witness with a single foreign field addition or subtraction, that is a
+/// `ForeignFieldAdd` row followed by a `Zero` row holding the result, following the
+/// layout of `CircuitGate::create_single_ffadd` (without the bound check of the result)
+pub fn extend_witness_single_ffadd<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    left_input: &BigUint,
+    right_input: &BigUint,
+    opcode: FFOps,
+    foreign_field_modulus: &BigUint,
+) {
+    let foreign_modulus = ForeignElement::from_biguint(foreign_field_modulus.clone());
+    let left = ForeignElement::from_biguint(left_input.clone());
+    let right = ForeignElement::from_biguint(right_input.clone());
+    let (result, _sign, ovf, carry) = compute_ffadd_values(&left, &right, opcode, &foreign_modulus);
+
+    let offset = witness[0].len();
+    for col in witness.iter_mut().take(COLUMNS) {
+        col.extend(std::iter::repeat(F::zero()).take(2))
+    }
+    init_ffadd_row(
+        witness,
+        offset,
+        left.limbs,
+        [right[LO], right[MI], right[HI]],
+        ovf,
+        carry,
+    );
+    for (col, limb) in result.limbs.iter().enumerate() {
+        witness[col][offset + 1] = *limb;
+    }
+}
+
 fn init_ffadd_row<F: PrimeField>(
     witness: &mut [Vec<F>; COLUMNS],
     offset: usize,
@@ -206,7 +239,8 @@ fn init_ffadd_row<F: PrimeField>(
         ],
     ];
 
-    witness::init(
+    witness::init_traced(
+        GateType::ForeignFieldAdd,
         witness,
         offset,
         &layout,
@@ -260,7 +294,8 @@ fn init_bound_rows<F: PrimeField>(
         ],
     ];
 
-    witness::init(
+    witness::init_traced(
+        GateType::ForeignFieldAdd,
         witness,
         offset,
         &layout,
@@ -9,6 +9,7 @@ use ark_ff::{Field, PrimeField};
 use num_bigint::BigUint;
 use num_traits::{One, Zero};
 use std::array;
+use thiserror::Error;
 
 /// Index of low limb (in 3-limb foreign elements)
 pub const LO: usize = 0;
@@ -124,6 +125,47 @@ impl BigUintForeignFieldHelpers for BigUint {
     }
 }
 
+/// Errors of the validation of a foreign field modulus
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ForeignFieldModulusError {
+    #[error("the foreign field modulus {0} is smaller than 2")]
+    TooSmall(BigUint),
+    #[error("the foreign field modulus {0} exceeds the maximum 2^259 - 1")]
+    TooLarge(BigUint),
+    #[error(
+        "the native field is too small for the foreign field modulus {0} (f^2 + f >= 2^t * n)"
+    )]
+    NativeFieldTooSmall(BigUint),
+}
+
+/// Checks that `foreign_field_modulus` can be used as the modulus of the foreign field
+/// gates over the native field `F`, i.e. that
+/// - it fits in the 3 limbs of the gates, with the bound `2^259 - 1` of the foreign
+///   field multiplication,
+/// - the products of the foreign field multiplication do not wrap around the native
+///   field, i.e. `f^2 + f < 2^t * n` where `n` is the native modulus (see the foreign
+///   field multiplication RFC). This is implied by the first bound for the Pasta fields,
+///   but not for smaller native fields.
+///
+/// Moduli of any shape satisfying these bounds are supported, including the sparse
+/// generalized Mersenne primes of the NIST curves (e.g. the P-256 prime, whose low limb is
+/// `2^88 - 1`).
+pub fn check_foreign_field_modulus<F: PrimeField>(
+    foreign_field_modulus: &BigUint,
+) -> Result<(), ForeignFieldModulusError> {
+    let f = foreign_field_modulus;
+    if *f < BigUint::two() {
+        return Err(ForeignFieldModulusError::TooSmall(f.clone()));
+    }
+    if *f > BigUint::max_foreign_field_modulus::<F>() {
+        return Err(ForeignFieldModulusError::TooLarge(f.clone()));
+    }
+    if f * f + f >= BigUint::binary_modulus() * F::modulus_biguint() {
+        return Err(ForeignFieldModulusError::NativeFieldTooSmall(f.clone()));
+    }
+    Ok(())
+}
+
 /// PrimeField array BigUint helpers
 pub trait FieldArrayBigUintHelpers<F: PrimeField, const N: usize> {
     /// Convert limbs from field elements to BigUint
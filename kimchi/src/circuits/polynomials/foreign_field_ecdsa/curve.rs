@@ -0,0 +1,67 @@
+//! This module defines the parameters of the short Weierstrass curves over foreign fields
+//! whose signatures are verified by the ECDSA gadget.
+
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+
+use crate::circuits::polynomials::{
+    foreign_field_common::{check_foreign_field_modulus, ForeignFieldModulusError},
+    foreign_field_scalar_mul::witness::ForeignAffine,
+};
+
+/// A short Weierstrass curve `y^2 = x^3 + a * x + b` over a foreign field, with a
+/// generator of prime order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignCurve {
+    /// Modulus `p` of the base field
+    pub modulus: BigUint,
+    /// Order `n` of the generator, i.e. modulus of the scalar field
+    pub order: BigUint,
+    /// Coefficient `a` of the curve equation
+    pub a: BigUint,
+    /// Coefficient `b` of the curve equation
+    pub b: BigUint,
+    /// The generator
+    pub generator: ForeignAffine,
+}
+
+// Parses a hexadecimal constant
+fn hex(digits: &str) -> BigUint {
+    BigUint::parse_bytes(digits.as_bytes(), 16).expect("invalid hexadecimal constant")
+}
+
+impl ForeignCurve {
+    /// The NIST curve secp256r1 (P-256), used among others by WebAuthn and passkeys.
+    /// Its base field modulus is `p = 2^256 - 2^224 + 2^192 + 2^96 - 1` and `a = -3`.
+    pub fn secp256r1() -> Self {
+        let modulus = hex("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        ForeignCurve {
+            order: hex("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"),
+            a: &modulus - BigUint::from(3u32),
+            b: hex("5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b"),
+            generator: ForeignAffine::new(
+                hex("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296"),
+                hex("4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+            ),
+            modulus,
+        }
+    }
+
+    /// Checks whether `point` is on the curve
+    pub fn is_on_curve(&self, point: &ForeignAffine) -> bool {
+        let p = &self.modulus;
+        if point.x >= *p || point.y >= *p {
+            return false;
+        }
+        let lhs = &point.y * &point.y % p;
+        let rhs = (&point.x * &point.x % p * &point.x + &self.a * &point.x + &self.b) % p;
+        lhs == rhs
+    }
+
+    /// Checks that both the base field modulus and the order can be used as moduli of
+    /// the foreign field gates over the native field `F`
+    pub fn check<F: PrimeField>(&self) -> Result<(), ForeignFieldModulusError> {
+        check_foreign_field_modulus::<F>(&self.modulus)?;
+        check_foreign_field_modulus::<F>(&self.order)
+    }
+}
@@ -0,0 +1,335 @@
+//! This module obtains the gates of an ECDSA signature verification circuit.
+//!
+//! The gadget verifies a signature `(r, s)` of the hash `z` of a message for a public key
+//! `Q` fixed when the circuit is created, on a short Weierstrass curve defined over a
+//! foreign field (e.g. P-256, see [ForeignCurve::secp256r1]). Fixing the public key
+//! allows to compute both scalar multiplications with the fixed-base gadget of
+//! [crate::circuits::polynomials::foreign_field_scalar_mul], which suits the attestations
+//! of a WebAuthn credential (a passkey), whose public key is registered once.
+//!
+//! With `n` the order of the generator `G` and `p` the base field modulus, the gadget
+//! constrains
+//! - `s * w = 1 mod n`, `u1 = z * w mod n` and `u2 = r * w mod n`,
+//! - `R = u1 * G + u2 * Q` with the scalar multiplication and incomplete addition gadgets,
+//! - `R.x < p` with a bound addition, so that the x coordinate of `R` is canonical,
+//! - `R.x = r mod n`, i.e. `R.x` is `r` or `r + n` since `p < 2 * n`,
+//! - `r < n` and `s < n` with bound additions, so that a signature is accepted in only
+//!   one encoding.
+//!
+//! The hash `z` must be reduced modulo `n` by the caller, as only `z mod n` is used by
+//! the verification. The witness generation fails for the negligible fraction of valid
+//! signatures for which `u1 * G` and `u2 * Q` have the same x coordinate (including
+//! `R = 0`, which is rejected by the verification anyway).
+
+use ark_ff::{PrimeField, SquareRootField};
+use num_bigint::BigUint;
+use o1_utils::Two;
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect},
+    lookup::tables::LookupTable,
+    polynomials::{
+        foreign_field_add::witness::FFOps,
+        foreign_field_common::{BigUintForeignFieldHelpers, LIMB_BITS},
+        foreign_field_scalar_mul::{
+            gadget::{ForeignCells, ScalarMulParams, EC_ADD_ROWS},
+            witness::ForeignAffine,
+        },
+        generic::GenericGateSpec,
+    },
+    wires::Wire,
+};
+
+use super::curve::ForeignCurve;
+
+/// Number of rows of the verification gadget, without the scalar multiplications and
+/// the final addition of points
+pub const ECDSA_ROWS: usize = 87;
+
+/// Parameters of an ECDSA signature verification for a fixed public key
+#[derive(Clone, Debug)]
+pub struct EcdsaParams {
+    /// The curve of the signatures
+    pub curve: ForeignCurve,
+    /// The public key
+    pub public_key: ForeignAffine,
+    /// Number of bits of a window of the scalar multiplications
+    pub window: usize,
+    /// ID of the first lookup table of the scalar multiplications
+    pub table_id: i32,
+}
+
+/// Cells of the inputs of an ECDSA signature verification
+#[derive(Clone, Debug)]
+pub struct EcdsaCells {
+    /// The hash of the message, reduced modulo the order
+    pub hash: ForeignCells,
+    /// The component `r` of the signature
+    pub r: ForeignCells,
+    /// The component `s` of the signature
+    pub s: ForeignCells,
+}
+
+impl EcdsaParams {
+    /// Creates the parameters of the verification of the signatures of `public_key`
+    /// Panics if the public key is not on the curve, if the moduli of the curve are not
+    /// supported by the foreign field gates (see [ForeignCurve::check]), if `p >= 2 * n`,
+    /// or if the window is not supported by the scalar multiplications
+    pub fn new<F: PrimeField>(
+        curve: ForeignCurve,
+        public_key: ForeignAffine,
+        window: usize,
+        table_id: i32,
+    ) -> Self {
+        if let Err(err) = curve.check::<F>() {
+            panic!("unsupported curve: {err}");
+        }
+        assert!(
+            curve.modulus < BigUint::two() * &curve.order,
+            "the base field modulus must be smaller than twice the order"
+        );
+        assert!(
+            curve.is_on_curve(&public_key),
+            "the public key is not on the curve"
+        );
+        let params = EcdsaParams {
+            curve,
+            public_key,
+            window,
+            table_id,
+        };
+        // checks the window
+        params.generator_mul();
+        params
+    }
+
+    /// Number of bits of the scalars
+    pub fn num_bits(&self) -> usize {
+        self.curve.order.bits() as usize
+    }
+
+    /// Parameters of the scalar multiplication of the generator
+    pub fn generator_mul(&self) -> ScalarMulParams {
+        ScalarMulParams::new(
+            self.curve.modulus.clone(),
+            self.curve.a.clone(),
+            self.curve.generator.clone(),
+            self.num_bits(),
+            self.window,
+            self.table_id,
+        )
+    }
+
+    /// Parameters of the scalar multiplication of the public key, whose tables follow
+    /// the tables of the generator
+    pub fn public_key_mul(&self) -> ScalarMulParams {
+        let generator_tables = self.num_bits() / self.window + 1;
+        ScalarMulParams::new(
+            self.curve.modulus.clone(),
+            self.curve.a.clone(),
+            self.public_key.clone(),
+            self.num_bits(),
+            self.window,
+            self.table_id + generator_tables as i32,
+        )
+    }
+
+    /// Number of rows of the gadget
+    pub fn num_rows(&self) -> usize {
+        ECDSA_ROWS
+            + self.generator_mul().num_rows()
+            + self.public_key_mul().num_rows()
+            + EC_ADD_ROWS
+    }
+
+    /// Lookup tables of the scalar multiplications, to be added to the constraint system
+    pub fn lookup_tables<F: PrimeField>(&self) -> Vec<LookupTable<F>> {
+        let mut tables = self.generator_mul().lookup_tables();
+        tables.extend(self.public_key_mul().lookup_tables());
+        tables
+    }
+}
+
+// Connects the limbs of a foreign field element to consecutive cells of a row
+fn connect_limbs<F: PrimeField>(
+    gates: &mut Vec<CircuitGate<F>>,
+    cells: &ForeignCells,
+    row: usize,
+    col: usize,
+) {
+    for (i, cell) in cells.iter().enumerate() {
+        gates.connect_cell_pair(*cell, (row, col + i));
+    }
+}
+
+impl<F: PrimeField + SquareRootField> CircuitGate<F> {
+    /// Extends a circuit with the verification of an ECDSA signature for a fixed public
+    /// key, see the documentation of this module for the details
+    /// Includes:
+    /// - 3 ForeignFieldMul gates (with their Zero rows) for the products modulo `n`
+    /// - 3 ForeignFieldAdd gates (with their Zero rows) for the bound checks of `r`, `s`
+    ///   and `R.x`
+    /// - 1 ForeignFieldAdd gate (with its Zero row) to compare `R.x` and `r` modulo `n`
+    /// - 3 Generic gates for the constants and the remainders of the products
+    /// - 2 Generic gates to compute the high bounds of the inputs of the products
+    /// - 17 multi-range-checks for the values, the bounds, the quotients and the high bounds
+    /// - the scalar multiplications `u1 * G` and `u2 * Q`, and their incomplete addition
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - curr_row  : mutable reference to the current row
+    /// - params    : parameters of the verification
+    /// Output:
+    /// - cells of the hash of the message and of the signature
+    /// Warning:
+    /// - the lookup tables [EcdsaParams::lookup_tables] must be added to the constraint system
+    /// - don't forget to wire the hash and the signature
+    pub fn extend_foreign_field_ecdsa(
+        gates: &mut Vec<Self>,
+        curr_row: &mut usize,
+        params: &EcdsaParams,
+    ) -> EcdsaCells {
+        let start = *curr_row;
+        let p = &params.curve.modulus;
+        let n = &params.curve.order;
+
+        // 0-5: multiplications modulo n
+        for _ in 0..3 {
+            Self::extend_foreign_field_mul(gates, curr_row, n);
+        }
+        // 6-11: bound checks
+        for modulus in [n, n, p] {
+            Self::extend_single_ffadd(gates, curr_row, FFOps::Add, modulus);
+        }
+        // 12-13: comparison of R.x and r modulo n
+        Self::extend_single_ffadd(gates, curr_row, FFOps::Sub, n);
+        // 14-16: constants and remainders of the multiplications
+        let row = *curr_row;
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(row),
+            GenericGateSpec::Const(F::one()),
+            Some(GenericGateSpec::Const(F::zero())),
+        );
+        let row = *curr_row;
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(row),
+            GenericGateSpec::Const(F::two_pow(LIMB_BITS as u64)),
+            None,
+        );
+        let compact = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: Some(F::two_pow(LIMB_BITS as u64)),
+            output_coeff: None,
+        };
+        let row = *curr_row;
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(row),
+            compact.clone(),
+            Some(compact),
+        );
+        // 17-18: high bounds
+        for _ in 0..2 {
+            Self::extend_high_bounds(gates, curr_row, n);
+        }
+        // 19-86: multi-range-checks
+        for _ in 0..17 {
+            Self::extend_multi_range_check(gates, curr_row);
+        }
+        assert_eq!(*curr_row, start + ECDSA_ROWS);
+
+        // u1 * G + u2 * Q
+        let generator_mul =
+            Self::extend_foreign_field_scalar_mul(gates, curr_row, &params.generator_mul());
+        let public_key_mul =
+            Self::extend_foreign_field_scalar_mul(gates, curr_row, &params.public_key_mul());
+        let [x, _] = Self::extend_foreign_field_ec_add(
+            gates,
+            curr_row,
+            &generator_mul.result,
+            &public_key_mul.result,
+            p,
+        );
+        assert_eq!(*curr_row, start + params.num_rows());
+
+        // the values live in the cells of their multi-range-checks
+        let range_checked = |i: usize| -> ForeignCells {
+            let row = start + 19 + 4 * i;
+            [(row, 0), (row + 1, 0), (row + 2, 0)]
+        };
+        let w = range_checked(0);
+        let z = range_checked(1);
+        let r = range_checked(2);
+        let s = range_checked(3);
+        let u1 = range_checked(4);
+        let u2 = range_checked(5);
+        let one = (start + 14, 0);
+        let zero = (start + 14, 3);
+        let two_to_limb = (start + 15, 0);
+
+        // multiplications and their quotients
+        for (i, left_input) in [s, z, r].iter().enumerate() {
+            let row = start + 2 * i;
+            connect_limbs(gates, left_input, row, 0);
+            connect_limbs(gates, &w, row, 3);
+            let quotient_row = start + 55 + 8 * i;
+            for col in 0..3 {
+                gates.connect_cell_pair((row + 1, 2 + col), (quotient_row + col, 0));
+            }
+            gates.connect_cell_pair((row + 1, 5), (quotient_row + 4, 0)); // quotient_hi_bound
+            gates.connect_cell_pair((row, 6), (quotient_row + 5, 0)); // product1_lo
+            gates.connect_cell_pair((row + 1, 6), (quotient_row + 6, 0)); // product1_hi_0
+        }
+
+        // remainder of s * w is 1
+        gates.connect_cell_pair((start + 1, 0), one);
+        gates.connect_cell_pair((start + 1, 1), zero);
+        // remainders u1 and u2 of the other multiplications
+        for (i, value) in [u1, u2].iter().enumerate() {
+            let (row, col) = (start + 16, 3 * i);
+            gates.connect_cell_pair(value[0], (row, col));
+            gates.connect_cell_pair(value[1], (row, col + 1));
+            gates.connect_cell_pair((start + 3 + 2 * i, 0), (row, col + 2));
+            gates.connect_cell_pair((start + 3 + 2 * i, 1), value[2]);
+        }
+
+        // bound checks, adding 2^t with an overflow of 1
+        for (i, value) in [r, s, x].iter().enumerate() {
+            let row = start + 6 + 2 * i;
+            connect_limbs(gates, value, row, 0);
+            gates.connect_cell_pair((row, 3), zero);
+            gates.connect_cell_pair((row, 4), zero);
+            gates.connect_cell_pair((row, 5), two_to_limb);
+            gates.connect_cell_pair((row, 6), one);
+            connect_limbs(gates, &range_checked(6 + i), row + 1, 0);
+        }
+
+        // r - R.x = 0 mod n
+        connect_limbs(gates, &r, start + 12, 0);
+        connect_limbs(gates, &x, start + 12, 3);
+        for col in 0..3 {
+            gates.connect_cell_pair((start + 13, col), zero);
+        }
+
+        // high bounds
+        for (i, value) in [w, z, u1, u2].iter().enumerate() {
+            let (row, col) = (start + 17 + i / 2, 3 * (i % 2));
+            gates.connect_cell_pair(value[2], (row, col));
+            gates.connect_cell_pair((row, col + 2), (start + 79 + 4 * (i / 3) + i % 3, 0));
+        }
+
+        // scalars of the multiplications of points
+        for (scalar, cells) in [(u1, generator_mul.scalar), (u2, public_key_mul.scalar)] {
+            assert_eq!(cells.len(), 3);
+            for (limb, cell) in scalar.iter().zip(cells) {
+                gates.connect_cell_pair(*limb, cell);
+            }
+        }
+
+        EcdsaCells { hash: z, r, s }
+    }
+}
@@ -0,0 +1,5 @@
+//! Foreign field ECDSA signature verification module
+
+pub mod curve;
+pub mod gadget;
+pub mod witness;
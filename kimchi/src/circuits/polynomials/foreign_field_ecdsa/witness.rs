@@ -0,0 +1,181 @@
+//! This module computes the witness of an ECDSA signature verification circuit.
+
+use crate::circuits::{
+    polynomial::COLUMNS,
+    polynomials::{
+        foreign_field_add::{self, witness::FFOps},
+        foreign_field_common::{BigUintForeignFieldHelpers, LIMB_BITS},
+        foreign_field_mul::{self, witness::ExternalChecks},
+        foreign_field_scalar_mul::{self, witness::ForeignAffine},
+        range_check,
+    },
+};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::Two;
+use std::array;
+
+use super::gadget::EcdsaParams;
+
+/// A signature `(r, s)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// The x coordinate of the nonce point, reduced modulo the order
+    pub r: BigUint,
+    /// The proof of knowledge of the secret key
+    pub s: BigUint,
+}
+
+impl Signature {
+    /// Signs the hash `z` with the secret key `d` and the nonce `k`
+    /// Returns `None` if the nonce leads to an invalid signature
+    pub fn sign(params: &EcdsaParams, z: &BigUint, d: &BigUint, k: &BigUint) -> Option<Self> {
+        let (curve, n) = (&params.curve, &params.curve.order);
+        let nonce = curve.generator.mul(k, &curve.a, &curve.modulus)?;
+        let r = nonce.x % n;
+        let s = inverse(k, n) * ((z + &r * d) % n) % n;
+        if r == BigUint::from(0u32) || s == BigUint::from(0u32) {
+            return None;
+        }
+        Some(Signature { r, s })
+    }
+
+    /// Verifies the signature of the hash `z` for the public key of `params`
+    pub fn verify(&self, params: &EcdsaParams, z: &BigUint) -> bool {
+        let (curve, n) = (&params.curve, &params.curve.order);
+        let zero = BigUint::from(0u32);
+        if self.r == zero || self.r >= *n || self.s == zero || self.s >= *n {
+            return false;
+        }
+        let w = inverse(&self.s, n);
+        let u1 = z * &w % n;
+        let u2 = &self.r * &w % n;
+        let point = curve.generator.mul(&u1, &curve.a, &curve.modulus);
+        let point = point.zip(params.public_key.mul(&u2, &curve.a, &curve.modulus));
+        match point {
+            Some((p1, p2)) if p1.x != p2.x => p1.add(&p2, &curve.modulus).x % n == self.r,
+            _ => false,
+        }
+    }
+}
+
+// Inverse modulo a prime modulus
+fn inverse(x: &BigUint, modulus: &BigUint) -> BigUint {
+    x.modpow(&(modulus - BigUint::two()), modulus)
+}
+
+// Appends rows to the witness
+fn extend_rows<F: PrimeField>(witness: &mut [Vec<F>; COLUMNS], rows: &[[F; COLUMNS]]) {
+    for row in rows {
+        for col in 0..COLUMNS {
+            witness[col].push(row[col]);
+        }
+    }
+}
+
+/// Extends a witness with the verification of the signature of the hash `z`, following
+/// the layout of `CircuitGate::extend_foreign_field_ecdsa`, and returns the point `R`
+/// whose x coordinate is compared to `r`
+/// The witness of an invalid signature is still generated, and does not satisfy the
+/// constraints.
+/// Panics if `z`, `r` or `s` is not reduced modulo the order, if `s` is zero, or for the
+/// negligible fraction of signatures whose point addition is degenerate
+pub fn extend<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    z: &BigUint,
+    signature: &Signature,
+    params: &EcdsaParams,
+) -> ForeignAffine {
+    let (p, n) = (&params.curve.modulus, &params.curve.order);
+    let (r, s) = (&signature.r, &signature.s);
+    assert!(
+        z < n && r < n && s < n,
+        "the hash and the signature must be reduced modulo the order"
+    );
+    assert!(*s != BigUint::from(0u32), "s must not be zero");
+
+    // All the values of the gadget
+    let w = inverse(s, n);
+    let u1 = z * &w % n;
+    let u2 = r * &w % n;
+    let (generator_mul, public_key_mul) = (params.generator_mul(), params.public_key_mul());
+    let point = |scalar: &BigUint, base: &ForeignAffine| {
+        base.mul(scalar, &params.curve.a, p)
+            .expect("degenerate scalar multiplication")
+    };
+    let sum = point(&u1, &generator_mul.base).add(&point(&u2, &public_key_mul.base), p);
+    let x = &sum.x;
+
+    // Foreign field multiplications, tracking their quotient range checks
+    let mut quotient_checks = ExternalChecks::<F>::default();
+    for left_input in [s, z, r] {
+        let (mul_witness, external_checks) = foreign_field_mul::witness::create(left_input, &w, n);
+        for col in 0..COLUMNS {
+            witness[col].extend(mul_witness[col].iter());
+        }
+        quotient_checks
+            .multi_ranges
+            .extend(external_checks.multi_ranges);
+    }
+
+    // Bound checks
+    for (value, modulus) in [(r, n), (s, n), (x, p)] {
+        foreign_field_add::witness::extend_witness_bound_addition(
+            witness,
+            &value.to_field_limbs(),
+            &modulus.to_field_limbs(),
+        );
+    }
+
+    // Comparison of R.x and r modulo n
+    foreign_field_add::witness::extend_witness_single_ffadd(witness, r, x, FFOps::Sub, n);
+
+    // Constants and remainders of the multiplications
+    let compact = |value: &BigUint| -> [F; 3] {
+        let limbs = value.to_field_limbs::<F>();
+        [limbs[0], limbs[1], value.to_compact_field_limbs::<F>()[0]]
+    };
+    let [u1_0, u1_1, u1_01] = compact(&u1);
+    let [u2_0, u2_1, u2_01] = compact(&u2);
+    let mut rows = [[F::zero(); COLUMNS]; 3];
+    rows[0][0] = F::one();
+    rows[1][0] = F::two_pow(LIMB_BITS as u64);
+    rows[2][0..6].copy_from_slice(&[u1_0, u1_1, u1_01, u2_0, u2_1, u2_01]);
+    extend_rows(witness, &rows);
+
+    // High bounds of the inputs and remainders of the multiplications
+    let mut bound_checks = ExternalChecks::<F>::default();
+    for value in [&w, z, &u1, &u2] {
+        bound_checks.add_high_bound_computation(&value.to_field_limbs()[2]);
+        bound_checks
+            .add_limb_check(&foreign_field_mul::witness::compute_high_bound(value, n).into());
+    }
+    bound_checks.extend_witness_high_bounds_computation(witness, n);
+
+    // Range checks of the values, the bounds, the quotients and the high bounds
+    for value in [&w, z, r, s, &u1, &u2] {
+        range_check::witness::extend_multi_limbs(witness, &value.to_field_limbs());
+    }
+    for (value, modulus) in [(r, n), (s, n), (x, p)] {
+        let bound = foreign_field_mul::witness::compute_bound(value, &modulus.negate());
+        range_check::witness::extend_multi_limbs(witness, &bound.to_field_limbs());
+    }
+    quotient_checks.extend_witness_multi_range_checks(witness);
+    bound_checks.extend_witness_limb_checks(witness);
+
+    // u1 * G + u2 * Q
+    let left = foreign_field_scalar_mul::witness::extend(witness, &u1, &generator_mul);
+    let right = foreign_field_scalar_mul::witness::extend(witness, &u2, &public_key_mul);
+    foreign_field_scalar_mul::witness::extend_ec_add(witness, &left, &right, p)
+}
+
+/// Creates the witness of the verification of the signature of the hash `z`
+pub fn create<F: PrimeField>(
+    z: &BigUint,
+    signature: &Signature,
+    params: &EcdsaParams,
+) -> [Vec<F>; COLUMNS] {
+    let mut witness = array::from_fn(|_| vec![]);
+    extend(&mut witness, z, signature, params);
+    witness
+}
@@ -3,6 +3,7 @@
 use crate::{
     auto_clone_array,
     circuits::{
+        gate::GateType,
         polynomial::COLUMNS,
         polynomials::{
             foreign_field_add,
@@ -208,7 +209,8 @@ pub fn create<F: PrimeField>(
     let right_input = right_input.to_field_limbs();
     let remainder = remainder.to_compact_field_limbs();
     let quotient = quotient.to_field_limbs();
-    witness::init(
+    witness::init_traced(
+        GateType::ForeignFieldMul,
         &mut witness,
         0,
         &create_layout(),
@@ -0,0 +1,298 @@
+//! This module implements a registry of the parameters of the foreign field gates for
+//! common moduli, so that their coefficients do not have to be computed by hand.
+//!
+//! The [ForeignFieldParams] of a modulus `f` over the native field `F` hold the
+//! limbs of `f` and of its negation `f' = 2^t - f`, the offset of the bound checks of
+//! the high limbs, and the constants of the Chinese remainder theorem (CRT) check of the
+//! foreign field multiplication, which works modulo `2^t` and modulo the native modulus
+//! `n` (see the foreign field multiplication RFC).
+//!
+//! ```
+//! use kimchi::circuits::polynomials::foreign_field_params::{ForeignFieldParams, StandardModulus};
+//! use mina_curves::pasta::Fp;
+//!
+//! let params = ForeignFieldParams::<Fp>::standard(StandardModulus::Secp256k1Base).unwrap();
+//! assert_eq!(params.modulus, StandardModulus::Secp256k1Base.modulus());
+//! let coeffs = params.ffmul_coeffs();
+//! assert_eq!(coeffs[1..], params.neg_modulus_limbs);
+//! ```
+
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use num_traits::One;
+use o1_utils::field_helpers::FieldHelpers;
+
+use super::{
+    foreign_field_add::witness::FFOps,
+    foreign_field_common::{
+        check_foreign_field_modulus, BigUintForeignFieldHelpers, ForeignFieldModulusError, HI,
+    },
+};
+
+/// The moduli of the registry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StandardModulus {
+    /// The base field of secp256k1 (Bitcoin, Ethereum)
+    Secp256k1Base,
+    /// The scalar field of secp256k1
+    Secp256k1Scalar,
+    /// The base field of the NIST curve P-256 (secp256r1)
+    P256Base,
+    /// The scalar field of P-256
+    P256Scalar,
+    /// The base field `2^255 - 19` of Curve25519 and Ed25519
+    Curve25519Base,
+    /// The order of the prime subgroup of Curve25519
+    Curve25519Scalar,
+    /// The scalar field of BLS12-381. Its 381-bit base field exceeds the bound of the
+    /// foreign field gates, and is not part of the registry.
+    Bls12_381Scalar,
+    /// The base field of BN254 (alt_bn128)
+    Bn254Base,
+    /// The scalar field of BN254
+    Bn254Scalar,
+}
+
+// Parses a hexadecimal constant
+fn hex(digits: &str) -> BigUint {
+    BigUint::parse_bytes(digits.as_bytes(), 16).expect("invalid hexadecimal constant")
+}
+
+impl StandardModulus {
+    /// All the moduli of the registry
+    pub const ALL: [StandardModulus; 9] = [
+        StandardModulus::Secp256k1Base,
+        StandardModulus::Secp256k1Scalar,
+        StandardModulus::P256Base,
+        StandardModulus::P256Scalar,
+        StandardModulus::Curve25519Base,
+        StandardModulus::Curve25519Scalar,
+        StandardModulus::Bls12_381Scalar,
+        StandardModulus::Bn254Base,
+        StandardModulus::Bn254Scalar,
+    ];
+
+    /// Returns the modulus
+    pub fn modulus(self) -> BigUint {
+        hex(match self {
+            StandardModulus::Secp256k1Base => {
+                "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"
+            }
+            StandardModulus::Secp256k1Scalar => {
+                "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+            }
+            StandardModulus::P256Base => {
+                "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff"
+            }
+            StandardModulus::P256Scalar => {
+                "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551"
+            }
+            StandardModulus::Curve25519Base => {
+                "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed"
+            }
+            StandardModulus::Curve25519Scalar => {
+                "1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed"
+            }
+            StandardModulus::Bls12_381Scalar => {
+                "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+            }
+            StandardModulus::Bn254Base => {
+                "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47"
+            }
+            StandardModulus::Bn254Scalar => {
+                "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001"
+            }
+        })
+    }
+}
+
+/// The parameters of the foreign field gates for a foreign field modulus `f` over the
+/// native field `F`, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignFieldParams<F: PrimeField> {
+    /// The foreign field modulus `f`
+    pub modulus: BigUint,
+    /// The limbs of `f`
+    pub modulus_limbs: [F; 3],
+    /// The negated modulus `f' = 2^t - f`
+    pub neg_modulus: BigUint,
+    /// The limbs of `f'`
+    pub neg_modulus_limbs: [F; 3],
+    /// The offset `2^l - f2 - 1` added to the high limb `x2` of a value `x` to check that
+    /// `x2 <= f2`, with the range check of `x2 + 2^l - f2 - 1`
+    pub high_bound_offset: F,
+    /// The modulus `f`, reduced modulo the native modulus `n`
+    pub native_modulus: F,
+    /// The negated modulus `f'`, reduced modulo the native modulus `n`
+    pub native_neg_modulus: F,
+    /// The binary modulus `2^t`, reduced modulo the native modulus `n`
+    pub native_binary_modulus: F,
+    /// The modulus `2^t * n` of the CRT check, which must exceed `f^2 + f`
+    pub crt_modulus: BigUint,
+}
+
+impl<F: PrimeField> ForeignFieldParams<F> {
+    /// Computes the parameters of the foreign field modulus `modulus`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `modulus` cannot be used by the foreign field gates over `F`,
+    /// see [check_foreign_field_modulus].
+    pub fn new(modulus: &BigUint) -> Result<Self, ForeignFieldModulusError> {
+        check_foreign_field_modulus::<F>(modulus)?;
+
+        let native = |x: &BigUint| {
+            F::from_biguint(&(x % F::modulus_biguint())).expect("the value is reduced")
+        };
+        let neg_modulus = modulus.negate();
+        let modulus_limbs = modulus.to_field_limbs::<F>();
+        Ok(Self {
+            modulus: modulus.clone(),
+            modulus_limbs,
+            neg_modulus_limbs: neg_modulus.to_field_limbs(),
+            high_bound_offset: native(
+                &(BigUint::two_to_limb() - modulus.to_limbs()[HI].clone() - BigUint::one()),
+            ),
+            native_modulus: native(modulus),
+            native_neg_modulus: native(&neg_modulus),
+            native_binary_modulus: native(&BigUint::binary_modulus()),
+            crt_modulus: BigUint::binary_modulus() * F::modulus_biguint(),
+            neg_modulus,
+        })
+    }
+
+    /// Returns the parameters of a modulus of the registry
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the native field `F` is too small for the modulus, see
+    /// [check_foreign_field_modulus].
+    pub fn standard(modulus: StandardModulus) -> Result<Self, ForeignFieldModulusError> {
+        Self::new(&modulus.modulus())
+    }
+
+    /// Returns the coefficients of a [crate::circuits::gate::GateType::ForeignFieldMul]
+    /// gate: the high limb of `f` and the limbs of `f'`
+    pub fn ffmul_coeffs(&self) -> [F; 4] {
+        [
+            self.modulus_limbs[HI],
+            self.neg_modulus_limbs[0],
+            self.neg_modulus_limbs[1],
+            self.neg_modulus_limbs[2],
+        ]
+    }
+
+    /// Returns the coefficients of a [crate::circuits::gate::GateType::ForeignFieldAdd]
+    /// gate performing `operation`: the limbs of `f` and the sign of the operation
+    pub fn ffadd_coeffs(&self, operation: FFOps) -> [F; 4] {
+        [
+            self.modulus_limbs[0],
+            self.modulus_limbs[1],
+            self.modulus_limbs[2],
+            operation.sign(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::circuits::{
+        gate::CircuitGate,
+        polynomials::{
+            foreign_field_common::BigUintArrayCompose, foreign_field_ecdsa::curve::ForeignCurve,
+        },
+    };
+    use mina_curves::pasta::{Fp, Fq};
+    use num_bigint::RandBigInt;
+    use o1_utils::tests::make_test_rng;
+
+    #[test]
+    fn test_standard_moduli() {
+        assert_eq!(
+            StandardModulus::Secp256k1Base.modulus(),
+            BigUint::from_bytes_be(&secp256k1::constants::FIELD_SIZE)
+        );
+        assert_eq!(
+            StandardModulus::Secp256k1Scalar.modulus(),
+            BigUint::from_bytes_be(&secp256k1::constants::CURVE_ORDER)
+        );
+        let p256 = ForeignCurve::secp256r1();
+        assert_eq!(StandardModulus::P256Base.modulus(), p256.modulus);
+        assert_eq!(StandardModulus::P256Scalar.modulus(), p256.order);
+        assert_eq!(
+            StandardModulus::Curve25519Base.modulus(),
+            BigUint::from(2u32).pow(255) - BigUint::from(19u32)
+        );
+        assert_eq!(
+            StandardModulus::Curve25519Scalar.modulus(),
+            BigUint::from(2u32).pow(252)
+                + BigUint::parse_bytes(b"27742317777372353535851937790883648493", 10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_standard_params() {
+        for modulus in StandardModulus::ALL {
+            let params = ForeignFieldParams::<Fp>::standard(modulus).unwrap();
+            let f = modulus.modulus();
+
+            // the limbs compose to the moduli, and f + f' = 2^t
+            assert_eq!(params.modulus_limbs.map(|x| x.to_biguint()).compose(), f);
+            assert_eq!(
+                params.neg_modulus_limbs.map(|x| x.to_biguint()).compose(),
+                params.neg_modulus
+            );
+            assert_eq!(&f + &params.neg_modulus, BigUint::binary_modulus());
+
+            // the CRT constants are consistent
+            assert_eq!(
+                params.native_modulus + params.native_neg_modulus,
+                params.native_binary_modulus
+            );
+            assert!(&f * &f + &f < params.crt_modulus);
+
+            // the coefficients are the ones of the gadgets
+            let (_, gates) = CircuitGate::<Fp>::create_foreign_field_mul(0, &f);
+            assert_eq!(gates[0].coeffs, params.ffmul_coeffs());
+            for operation in [FFOps::Add, FFOps::Sub] {
+                let (_, gates) = CircuitGate::<Fp>::create_single_ffadd(0, operation, &f);
+                assert_eq!(gates[0].coeffs, params.ffadd_coeffs(operation));
+            }
+            let mut gates = vec![];
+            CircuitGate::<Fp>::extend_high_bounds(&mut gates, &mut 0, &f);
+            assert_eq!(gates[0].coeffs[4], params.high_bound_offset);
+        }
+    }
+
+    #[test]
+    fn test_high_bound_offset() {
+        let rng = &mut make_test_rng(None);
+        let params = ForeignFieldParams::<Fq>::standard(StandardModulus::Bn254Base).unwrap();
+        let f_hi = params.modulus.to_limbs()[HI].clone();
+        for _ in 0..10 {
+            // the offset high limb fits in a limb iff the high limb is at most f2
+            let x_hi = rng.gen_biguint_below(&(&f_hi * 2u32));
+            let bound = x_hi.clone() + params.high_bound_offset.to_biguint();
+            assert_eq!(bound < BigUint::two_to_limb(), x_hi <= f_hi);
+        }
+        let bound = f_hi + params.high_bound_offset.to_biguint();
+        assert_eq!(bound, BigUint::two_to_limb() - BigUint::one());
+    }
+
+    #[test]
+    fn test_invalid_modulus() {
+        let bls12_381_base = hex(
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab",
+        );
+        assert!(matches!(
+            ForeignFieldParams::<Fp>::new(&bls12_381_base),
+            Err(ForeignFieldModulusError::TooLarge(_))
+        ));
+        assert!(matches!(
+            ForeignFieldParams::<Fp>::new(&BigUint::one()),
+            Err(ForeignFieldModulusError::TooSmall(_))
+        ));
+    }
+}
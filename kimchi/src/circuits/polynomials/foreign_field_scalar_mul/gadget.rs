@@ -0,0 +1,488 @@
+//! This module obtains the gates of a foreign field scalar multiplication circuit.
+//!
+//! The gadget computes `k * G` for a scalar `k` of `num_bits` bits and a base point `G`
+//! fixed when the circuit is created (e.g. the generator of secp256k1 for ECDSA), on a
+//! short Weierstrass curve defined over a foreign field. It does not need new gate types,
+//! it reuses the foreign field addition and multiplication gates, the multi-range-check
+//! gadget and the `Lookup` gate.
+//!
+//! The scalar is split into windows of `w` bits `k = sum_i d_i * 2^{w * i}`, and for each
+//! window `i` a lookup table of precomputed points contains
+//! `T_i[d] = (d + 2^w) * 2^{w * i} * G` for every digit `d < 2^w`. A final table contains the
+//! single point `-C * G` with `C = sum_i 2^w * 2^{w * i}`, so that `k * G = sum_i T_i[d_i] - C * G`.
+//! The offset `2^w` of the digits makes every partial sum a multiple of `G` smaller than the
+//! next point of the sum, so that the incomplete additions are never degenerate as long as
+//! `2^{num_bits + 3}` is smaller than the order of `G`. Larger scalars are supported too,
+//! but the witness generation fails for the negligible fraction of them leading to a
+//! degenerate addition. In any case the additions check that the x coordinates of their
+//! inputs are distinct, so the gadget is always sound.
+//!
+//! The points of the table of window `i` are stored in the lookup table with ID
+//! `table_id + i`, where the limb `l` of the coordinates (`x0, x1, x2, y0, y1, y2`) of the
+//! point `T_i[d]` has index `d + l * 2^w`. The lookup of the index `d` guarantees `d < 2^w`.
+//!
+//! The coordinates of the result are range checked as limbs of 88 bits, and are congruent
+//! to the canonical coordinates modulo the foreign field modulus. If the canonical values
+//! are needed, the caller must add a final bound check.
+
+use ark_ff::{PrimeField, SquareRootField};
+use num_bigint::BigUint;
+use num_traits::One;
+use o1_utils::{field_helpers::i32_to_field, Two};
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    lookup::tables::LookupTable,
+    polynomials::{
+        foreign_field_add::witness::FFOps,
+        foreign_field_common::{BigUintForeignFieldHelpers, LIMB_BITS},
+        generic::GenericGateSpec,
+    },
+    wires::Wire,
+};
+
+use super::witness::ForeignAffine;
+
+/// Number of rows of the lookup of a point of a table
+pub const WINDOW_ROWS: usize = 5;
+
+/// Number of rows of an incomplete point addition
+pub const EC_ADD_ROWS: usize = 111;
+
+/// Positions `(row, col)` of the limbs of a foreign field element
+pub type ForeignCells = [(usize, usize); 3];
+
+/// Positions of the coordinates `[x, y]` of a point
+pub type ForeignPointCells = [ForeignCells; 2];
+
+/// Parameters of a fixed-base foreign field scalar multiplication
+#[derive(Clone, Debug)]
+pub struct ScalarMulParams {
+    /// Modulus of the foreign field
+    pub foreign_field_modulus: BigUint,
+    /// Coefficient `a` of the curve equation `y^2 = x^3 + a * x + b`
+    pub a: BigUint,
+    /// The fixed base point
+    pub base: ForeignAffine,
+    /// Number of bits of the scalars
+    pub num_bits: usize,
+    /// Number of bits of a window
+    pub window: usize,
+    /// ID of the lookup table of the first window
+    pub table_id: i32,
+}
+
+/// Cells of the inputs and outputs of a foreign field scalar multiplication
+#[derive(Clone, Debug)]
+pub struct ScalarMulCells {
+    /// The limbs of 88 bits of the scalar, starting from the least significant one
+    pub scalar: Vec<(usize, usize)>,
+    /// The resulting point
+    pub result: ForeignPointCells,
+}
+
+impl ScalarMulParams {
+    /// Creates the parameters of a fixed-base scalar multiplication
+    /// Panics if the window is smaller than 2 bits or does not divide both the
+    /// number of bits of the scalars and the number of bits of a limb
+    pub fn new(
+        foreign_field_modulus: BigUint,
+        a: BigUint,
+        base: ForeignAffine,
+        num_bits: usize,
+        window: usize,
+        table_id: i32,
+    ) -> Self {
+        assert!(window >= 2, "window must have at least 2 bits");
+        assert_eq!(LIMB_BITS % window, 0, "window must divide {LIMB_BITS}");
+        assert!(
+            num_bits > 0 && num_bits % window == 0,
+            "window must divide the number of bits"
+        );
+        ScalarMulParams {
+            foreign_field_modulus,
+            a,
+            base,
+            num_bits,
+            window,
+            table_id,
+        }
+    }
+
+    /// Number of windows of the scalar
+    pub fn num_windows(&self) -> usize {
+        self.num_bits / self.window
+    }
+
+    /// Number of limbs of 88 bits of the scalar
+    pub fn num_limbs(&self) -> usize {
+        (self.num_bits + LIMB_BITS - 1) / LIMB_BITS
+    }
+
+    /// Number of rows of the gadget
+    pub fn num_rows(&self) -> usize {
+        let num_windows = self.num_windows();
+        let compositions = num_windows - self.num_limbs();
+        (num_windows + 1) * WINDOW_ROWS + (compositions + 1) / 2 + num_windows * EC_ADD_ROWS
+    }
+
+    /// Precomputes the points of the tables, one table per window and a last table
+    /// with the single point correcting the offsets of the digits
+    pub fn table_points(&self) -> Vec<Vec<ForeignAffine>> {
+        let modulus = &self.foreign_field_modulus;
+        let offset = BigUint::one() << self.window;
+        let mut tables = vec![];
+        let mut correction = BigUint::from(0u32);
+        // 2^{w * i} * G
+        let mut power = self.base.clone();
+        for i in 0..self.num_windows() {
+            if i > 0 {
+                for _ in 0..self.window {
+                    power = power.double(&self.a, modulus);
+                }
+            }
+            // (d + 2^w) * 2^{w * i} * G
+            let mut point = power
+                .mul(&offset, &self.a, modulus)
+                .expect("order of the base point is too small");
+            let mut table = vec![];
+            for _ in 0..(1usize << self.window) {
+                table.push(point.clone());
+                point = point.add(&power, modulus);
+            }
+            tables.push(table);
+            correction += &offset << (self.window * i);
+        }
+        let correction = self
+            .base
+            .mul(&correction, &self.a, modulus)
+            .expect("order of the base point is too small");
+        tables.push(vec![correction.neg(modulus)]);
+        tables
+    }
+
+    /// Lookup tables of the precomputed points, to be added to the constraint system
+    pub fn lookup_tables<F: PrimeField>(&self) -> Vec<LookupTable<F>> {
+        let limb_offset = 1usize << self.window;
+        self.table_points()
+            .iter()
+            .enumerate()
+            .map(|(i, points)| {
+                let mut indices = vec![];
+                let mut values = vec![];
+                for (d, point) in points.iter().enumerate() {
+                    let limbs = point
+                        .x
+                        .to_field_limbs::<F>()
+                        .into_iter()
+                        .chain(point.y.to_field_limbs::<F>());
+                    for (l, limb) in limbs.enumerate() {
+                        indices.push(F::from((d + l * limb_offset) as u64));
+                        values.push(limb);
+                    }
+                }
+                LookupTable {
+                    id: self.table_id + i as i32,
+                    data: vec![indices, values],
+                }
+            })
+            .collect()
+    }
+}
+
+// Connects the limbs of a foreign field element to consecutive cells of a row
+fn connect_limbs<F: PrimeField>(
+    gates: &mut Vec<CircuitGate<F>>,
+    cells: &ForeignCells,
+    row: usize,
+    col: usize,
+) {
+    for (i, cell) in cells.iter().enumerate() {
+        gates.connect_cell_pair(*cell, (row, col + i));
+    }
+}
+
+impl<F: PrimeField + SquareRootField> CircuitGate<F> {
+    /// Extends a circuit with the incomplete addition of two points `left + right`
+    /// over a foreign field, checking that their x coordinates are distinct.
+    /// The gadget constrains the slope `lambda` of the line and the sum `(x3, y3)` with
+    /// - `(x2 - x1) * inv = 1`
+    /// - `lambda * (x2 - x1) = y2 - y1`
+    /// - `lambda * lambda = x1 + x2 + x3`
+    /// - `lambda * (x1 - x3) = y1 + y3`
+    /// Includes:
+    /// - 4 ForeignFieldMul gates (with their Zero rows) for the products
+    /// - 6 ForeignFieldAdd gates (with their Zero rows) for the sums and differences
+    /// - 3 Generic gates to compare the remainders of the products to the sums
+    /// - 4 Generic gates to compute the high bounds of the inputs of the products
+    /// - 21 multi-range-checks for the values, the quotients and the high bounds
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - curr_row  : mutable reference to the current row
+    /// - left      : cells of the coordinates of the left point
+    /// - right     : cells of the coordinates of the right point
+    /// - foreign_field_modulus : modulus of the foreign field
+    /// Output:
+    /// - cells of the coordinates of the sum
+    /// Warning:
+    /// - the limbs of the inputs must be range checked by the caller
+    pub fn extend_foreign_field_ec_add(
+        gates: &mut Vec<Self>,
+        curr_row: &mut usize,
+        left: &ForeignPointCells,
+        right: &ForeignPointCells,
+        foreign_field_modulus: &BigUint,
+    ) -> ForeignPointCells {
+        let start = *curr_row;
+
+        // 0-7: multiplications
+        for _ in 0..4 {
+            Self::extend_foreign_field_mul(gates, curr_row, foreign_field_modulus);
+        }
+        // 8-19: additions
+        for opcode in [
+            FFOps::Sub,
+            FFOps::Sub,
+            FFOps::Add,
+            FFOps::Add,
+            FFOps::Sub,
+            FFOps::Add,
+        ] {
+            Self::extend_single_ffadd(gates, curr_row, opcode, foreign_field_modulus);
+        }
+        // 20-22: remainders of the multiplications
+        let compact = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: Some(F::two_pow(LIMB_BITS as u64)),
+            output_coeff: None,
+        };
+        let row = *curr_row;
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(row),
+            GenericGateSpec::Const(F::one()),
+            Some(GenericGateSpec::Const(F::zero())),
+        );
+        let row = *curr_row;
+        Self::extend_generic(
+            gates,
+            curr_row,
+            Wire::for_row(row),
+            compact.clone(),
+            Some(compact.clone()),
+        );
+        let row = *curr_row;
+        Self::extend_generic(gates, curr_row, Wire::for_row(row), compact, None);
+        // 23-26: high bounds
+        for _ in 0..4 {
+            Self::extend_high_bounds(gates, curr_row, foreign_field_modulus);
+        }
+        // 27-110: multi-range-checks
+        for _ in 0..21 {
+            Self::extend_multi_range_check(gates, curr_row);
+        }
+        assert_eq!(*curr_row, start + EC_ADD_ROWS);
+
+        // the values live in the cells of their multi-range-checks
+        let range_checked = |i: usize| -> ForeignCells {
+            let row = start + 27 + 4 * i;
+            [(row, 0), (row + 1, 0), (row + 2, 0)]
+        };
+        let lambda = range_checked(0);
+        let x3 = range_checked(1);
+        let y3 = range_checked(2);
+        let inv = range_checked(3);
+        let d1 = range_checked(4);
+        let n1 = range_checked(5);
+        let s1 = range_checked(6);
+        let s2 = range_checked(7);
+        let d2 = range_checked(8);
+        let n2 = range_checked(9);
+        let [x1, y1] = *left;
+        let [x2, y2] = *right;
+
+        // multiplications and their quotients
+        for (i, (left_input, right_input)) in
+            [(d1, inv), (lambda, d1), (lambda, lambda), (lambda, d2)]
+                .iter()
+                .enumerate()
+        {
+            let row = start + 2 * i;
+            connect_limbs(gates, left_input, row, 0);
+            connect_limbs(gates, right_input, row, 3);
+            let quotient_row = start + 67 + 8 * i;
+            for col in 0..3 {
+                gates.connect_cell_pair((row + 1, 2 + col), (quotient_row + col, 0));
+            }
+            gates.connect_cell_pair((row + 1, 5), (quotient_row + 4, 0)); // quotient_hi_bound
+            gates.connect_cell_pair((row, 6), (quotient_row + 5, 0)); // product1_lo
+            gates.connect_cell_pair((row + 1, 6), (quotient_row + 6, 0)); // product1_hi_0
+        }
+
+        // additions
+        for (i, (left_input, right_input, result)) in [
+            (x2, x1, d1),
+            (y2, y1, n1),
+            (x1, x2, s1),
+            (s1, x3, s2),
+            (x1, x3, d2),
+            (y1, y3, n2),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let row = start + 8 + 2 * i;
+            connect_limbs(gates, left_input, row, 0);
+            connect_limbs(gates, right_input, row, 3);
+            connect_limbs(gates, result, row + 1, 0);
+        }
+
+        // remainder of (x2 - x1) * inv is 1
+        gates.connect_cell_pair((start + 1, 0), (start + 20, 0));
+        gates.connect_cell_pair((start + 1, 1), (start + 20, 3));
+        // remainders of the other multiplications
+        for (i, value) in [n1, s2, n2].iter().enumerate() {
+            let (row, col) = (start + 21 + i / 2, 3 * (i % 2));
+            gates.connect_cell_pair(value[0], (row, col));
+            gates.connect_cell_pair(value[1], (row, col + 1));
+            gates.connect_cell_pair((start + 3 + 2 * i, 0), (row, col + 2));
+            gates.connect_cell_pair((start + 3 + 2 * i, 1), value[2]);
+        }
+
+        // high bounds
+        for (i, value) in [lambda, inv, d1, n1, s2, d2, n2].iter().enumerate() {
+            let (row, col) = (start + 23 + i / 2, 3 * (i % 2));
+            gates.connect_cell_pair(value[2], (row, col));
+            gates.connect_cell_pair((row, col + 2), (start + 99 + 4 * (i / 3) + i % 3, 0));
+        }
+
+        [x3, y3]
+    }
+
+    /// Extends a circuit with a fixed-base scalar multiplication over a foreign field,
+    /// see the documentation of this module for the details
+    /// Includes:
+    /// - for each window and the correction point, 2 Lookup gates for the coordinates
+    ///   of the point and 3 Generic gates for the table ID and the indices of the lookups
+    /// - Generic gates to compose the digits into the limbs of the scalar
+    /// - an incomplete point addition for each window
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - curr_row  : mutable reference to the current row
+    /// - params    : parameters of the scalar multiplication
+    /// Output:
+    /// - cells of the limbs of the scalar and of the coordinates of the result
+    /// Warning:
+    /// - the lookup tables [ScalarMulParams::lookup_tables] must be added to the constraint system
+    /// - don't forget to wire the limbs of the scalar and the result
+    pub fn extend_foreign_field_scalar_mul(
+        gates: &mut Vec<Self>,
+        curr_row: &mut usize,
+        params: &ScalarMulParams,
+    ) -> ScalarMulCells {
+        let start = *curr_row;
+        let limb_offset = F::two_pow(params.window as u64);
+        let num_windows = params.num_windows();
+
+        // lookups of the points of the tables
+        let mut digits = vec![];
+        let mut points = vec![];
+        for i in 0..=num_windows {
+            let row = *curr_row;
+            let table_id: F = i32_to_field(params.table_id + i as i32);
+            for lookup_row in row..(row + 2) {
+                gates.push(CircuitGate::new(
+                    GateType::Lookup,
+                    Wire::for_row(lookup_row),
+                    vec![],
+                ));
+            }
+            let plus = |l: u64| GenericGateSpec::Plus(F::from(l) * limb_offset);
+            gates.push(Self::create_generic_gadget(
+                Wire::for_row(row + 2),
+                GenericGateSpec::Const(table_id),
+                Some(plus(1)),
+            ));
+            gates.push(Self::create_generic_gadget(
+                Wire::for_row(row + 3),
+                plus(2),
+                Some(plus(3)),
+            ));
+            gates.push(Self::create_generic_gadget(
+                Wire::for_row(row + 4),
+                plus(4),
+                Some(plus(5)),
+            ));
+            *curr_row += WINDOW_ROWS;
+
+            // the table ID
+            gates.connect_cell_pair((row + 2, 0), (row, 0));
+            gates.connect_cell_pair((row + 2, 0), (row + 1, 0));
+            // the indices of the lookups, the digit is the first one
+            let digit = (row, 1);
+            let indices = [(row, 3), (row, 5), (row + 1, 1), (row + 1, 3), (row + 1, 5)];
+            for (l, index) in indices.iter().enumerate() {
+                let (generic_row, col) = (row + 2 + (l + 1) / 2, 3 * ((l + 1) % 2));
+                gates.connect_cell_pair(digit, (generic_row, col));
+                gates.connect_cell_pair(*index, (generic_row, col + 2));
+            }
+
+            digits.push(digit);
+            points.push([
+                [(row, 2), (row, 4), (row, 6)],
+                [(row + 1, 2), (row + 1, 4), (row + 1, 6)],
+            ]);
+        }
+
+        // composition of the digits into the limbs of the scalar
+        let digits_per_limb = LIMB_BITS / params.window;
+        let composition = GenericGateSpec::Add {
+            left_coeff: Some(limb_offset),
+            right_coeff: None,
+            output_coeff: None,
+        };
+        let composition_row = *curr_row;
+        let mut halves = 0;
+        let mut scalar = vec![];
+        for limb in 0..params.num_limbs() {
+            let limb_digits =
+                &digits[(limb * digits_per_limb)..num_windows.min((limb + 1) * digits_per_limb)];
+            let mut acc = *limb_digits.last().unwrap();
+            for digit in limb_digits.iter().rev().skip(1) {
+                let (row, col) = (composition_row + halves / 2, 3 * (halves % 2));
+                if col == 0 {
+                    gates.push(Self::create_generic_gadget(
+                        Wire::for_row(row),
+                        composition.clone(),
+                        Some(composition.clone()),
+                    ));
+                    *curr_row += 1;
+                }
+                gates.connect_cell_pair(acc, (row, col));
+                gates.connect_cell_pair(*digit, (row, col + 1));
+                acc = (row, col + 2);
+                halves += 1;
+            }
+            scalar.push(acc);
+        }
+
+        // sum of the points
+        let mut acc = points[0];
+        for point in points.iter().skip(1) {
+            acc = Self::extend_foreign_field_ec_add(
+                gates,
+                curr_row,
+                &acc,
+                point,
+                &params.foreign_field_modulus,
+            );
+        }
+        assert_eq!(*curr_row, start + params.num_rows());
+
+        ScalarMulCells {
+            scalar,
+            result: acc,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Foreign field scalar multiplication module
+
+pub mod gadget;
+pub mod witness;
@@ -0,0 +1,308 @@
+//! This module computes the witness of a foreign field scalar multiplication circuit.
+
+use crate::circuits::{
+    polynomial::COLUMNS,
+    polynomials::{
+        foreign_field_add::{self, witness::FFOps},
+        foreign_field_common::{BigUintForeignFieldHelpers, LIMB_BITS},
+        foreign_field_mul::{self, witness::ExternalChecks},
+        range_check,
+    },
+};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use o1_utils::{field_helpers::i32_to_field, Two};
+use std::array;
+
+use super::gadget::ScalarMulParams;
+
+/// An affine point of a short Weierstrass curve `y^2 = x^3 + a * x + b`
+/// defined over a foreign field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignAffine {
+    /// x coordinate
+    pub x: BigUint,
+    /// y coordinate
+    pub y: BigUint,
+}
+
+impl ForeignAffine {
+    /// Creates a point from its coordinates
+    pub fn new(x: BigUint, y: BigUint) -> Self {
+        ForeignAffine { x, y }
+    }
+
+    /// Negation of the point
+    pub fn neg(&self, modulus: &BigUint) -> Self {
+        ForeignAffine::new(self.x.clone(), (modulus - &self.y) % modulus)
+    }
+
+    /// Addition of two points with distinct x coordinates
+    /// Panics if the x coordinates are equal
+    pub fn add(&self, other: &Self, modulus: &BigUint) -> Self {
+        assert_ne!(
+            self.x, other.x,
+            "incomplete addition of points with equal x"
+        );
+        let lambda = sub_mod(&other.y, &self.y, modulus)
+            * inverse(&sub_mod(&other.x, &self.x, modulus), modulus)
+            % modulus;
+        self.third_point(other, &lambda, modulus)
+    }
+
+    /// Doubling of a point, for a curve with coefficient `a`
+    pub fn double(&self, a: &BigUint, modulus: &BigUint) -> Self {
+        let numerator = (BigUint::from(3u32) * &self.x * &self.x + a) % modulus;
+        let lambda = numerator * inverse(&(BigUint::two() * &self.y % modulus), modulus) % modulus;
+        self.third_point(self, &lambda, modulus)
+    }
+
+    /// Multiplication of a point by a scalar, for a curve with coefficient `a`
+    /// Returns `None` for the point at infinity
+    pub fn mul(&self, scalar: &BigUint, a: &BigUint, modulus: &BigUint) -> Option<Self> {
+        let mut acc = None;
+        for i in (0..scalar.bits()).rev() {
+            acc = acc.map(|p: Self| p.double(a, modulus));
+            if scalar.bit(i) {
+                acc = add_complete(acc, Some(self.clone()), a, modulus);
+            }
+        }
+        acc
+    }
+
+    // Third point of the line of slope lambda going through self and other
+    fn third_point(&self, other: &Self, lambda: &BigUint, modulus: &BigUint) -> Self {
+        let x = sub_mod(
+            &(lambda * lambda % modulus),
+            &((&self.x + &other.x) % modulus),
+            modulus,
+        );
+        let y = sub_mod(
+            &(lambda * sub_mod(&self.x, &x, modulus) % modulus),
+            &self.y,
+            modulus,
+        );
+        ForeignAffine::new(x, y)
+    }
+}
+
+// Addition of two points, any of them possibly being the point at infinity
+fn add_complete(
+    p: Option<ForeignAffine>,
+    q: Option<ForeignAffine>,
+    a: &BigUint,
+    modulus: &BigUint,
+) -> Option<ForeignAffine> {
+    match (p, q) {
+        (None, q) => q,
+        (p, None) => p,
+        (Some(p), Some(q)) if p.x != q.x => Some(p.add(&q, modulus)),
+        (Some(p), Some(q)) if p.y == q.y && !p.y.is_zero() => Some(p.double(a, modulus)),
+        _ => None,
+    }
+}
+
+// Subtraction modulo the modulus of two reduced values
+fn sub_mod(x: &BigUint, y: &BigUint, modulus: &BigUint) -> BigUint {
+    (x + modulus - y) % modulus
+}
+
+// Inverse modulo a prime modulus
+fn inverse(x: &BigUint, modulus: &BigUint) -> BigUint {
+    assert!(!x.is_zero(), "inverse of zero");
+    x.modpow(&(modulus - BigUint::two()), modulus)
+}
+
+// Appends rows to the witness
+fn extend_rows<F: PrimeField>(witness: &mut [Vec<F>; COLUMNS], rows: &[[F; COLUMNS]]) {
+    for row in rows {
+        for col in 0..COLUMNS {
+            witness[col].push(row[col]);
+        }
+    }
+}
+
+/// Extends a witness with an incomplete addition `left + right` of two points
+/// with distinct x coordinates, following the layout of
+/// `CircuitGate::extend_foreign_field_ec_add`, and returns the sum
+/// Panics if the x coordinates of the points are equal
+pub fn extend_ec_add<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    left: &ForeignAffine,
+    right: &ForeignAffine,
+    foreign_field_modulus: &BigUint,
+) -> ForeignAffine {
+    let f = foreign_field_modulus;
+    let (x1, y1) = (&left.x, &left.y);
+    let (x2, y2) = (&right.x, &right.y);
+
+    // All the values of the gadget
+    let d1 = sub_mod(x2, x1, f);
+    let inv = inverse(&d1, f);
+    let n1 = sub_mod(y2, y1, f);
+    let lambda = &n1 * &inv % f;
+    let s1 = (x1 + x2) % f;
+    let x3 = sub_mod(&(&lambda * &lambda % f), &s1, f);
+    let s2 = (&s1 + &x3) % f;
+    let d2 = sub_mod(x1, &x3, f);
+    let y3 = sub_mod(&(&lambda * &d2 % f), y1, f);
+    let n2 = (y1 + &y3) % f;
+
+    // Foreign field multiplications, tracking their quotient range checks
+    let mut quotient_checks = ExternalChecks::<F>::default();
+    for (left_input, right_input) in [
+        (&d1, &inv),
+        (&lambda, &d1),
+        (&lambda, &lambda),
+        (&lambda, &d2),
+    ] {
+        let (mul_witness, external_checks) =
+            foreign_field_mul::witness::create(left_input, right_input, f);
+        for col in 0..COLUMNS {
+            witness[col].extend(mul_witness[col].iter());
+        }
+        quotient_checks
+            .multi_ranges
+            .extend(external_checks.multi_ranges);
+    }
+
+    // Foreign field additions
+    for (left_input, right_input, opcode) in [
+        (x2, x1, FFOps::Sub),
+        (y2, y1, FFOps::Sub),
+        (x1, x2, FFOps::Add),
+        (&s1, &x3, FFOps::Add),
+        (x1, &x3, FFOps::Sub),
+        (y1, &y3, FFOps::Add),
+    ] {
+        foreign_field_add::witness::extend_witness_single_ffadd(
+            witness,
+            left_input,
+            right_input,
+            opcode,
+            f,
+        );
+    }
+
+    // Remainders of the multiplications
+    let compact = |x: &BigUint| -> [F; 3] {
+        let limbs = x.to_field_limbs::<F>();
+        [limbs[0], limbs[1], x.to_compact_field_limbs::<F>()[0]]
+    };
+    let [n1_0, n1_1, n1_01] = compact(&n1);
+    let [s2_0, s2_1, s2_01] = compact(&s2);
+    let [n2_0, n2_1, n2_01] = compact(&n2);
+    let mut rows = [[F::zero(); COLUMNS]; 3];
+    rows[0][0] = F::one();
+    rows[1][0..6].copy_from_slice(&[n1_0, n1_1, n1_01, s2_0, s2_1, s2_01]);
+    rows[2][0..3].copy_from_slice(&[n2_0, n2_1, n2_01]);
+    extend_rows(witness, &rows);
+
+    // High bounds of the inputs and remainders of the multiplications
+    let bounded = [&lambda, &inv, &d1, &n1, &s2, &d2, &n2];
+    let mut bound_checks = ExternalChecks::<F>::default();
+    for value in bounded {
+        bound_checks.add_high_bound_computation(&value.to_field_limbs()[2]);
+        bound_checks
+            .add_limb_check(&foreign_field_mul::witness::compute_high_bound(value, f).into());
+    }
+    bound_checks.extend_witness_high_bounds_computation(witness, f);
+
+    // Range checks of the values, the quotients and the high bounds
+    for value in [&lambda, &x3, &y3, &inv, &d1, &n1, &s1, &s2, &d2, &n2] {
+        range_check::witness::extend_multi_limbs(witness, &value.to_field_limbs());
+    }
+    quotient_checks.extend_witness_multi_range_checks(witness);
+    bound_checks.extend_witness_limb_checks(witness);
+
+    ForeignAffine::new(x3, y3)
+}
+
+/// Extends a witness with a fixed-base scalar multiplication `scalar * base`,
+/// following the layout of `CircuitGate::extend_foreign_field_scalar_mul`,
+/// and returns the product
+/// Panics if the scalar has more than `num_bits` bits, or if the scalar is one of the
+/// few values for which an incomplete addition of the gadget is degenerate (e.g. zero)
+pub fn extend<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    scalar: &BigUint,
+    params: &ScalarMulParams,
+) -> ForeignAffine {
+    assert!(
+        scalar.bits() as usize <= params.num_bits,
+        "scalar exceeds {} bits",
+        params.num_bits
+    );
+    let window = params.window;
+    let num_windows = params.num_windows();
+    let mask = (BigUint::one() << window) - BigUint::one();
+    let digits: Vec<usize> = (0..num_windows)
+        .map(|i| {
+            let digit = (scalar >> (window * i)) & &mask;
+            digit.to_u64_digits().first().copied().unwrap_or(0) as usize
+        })
+        .chain(std::iter::once(0))
+        .collect();
+
+    // Lookups of the points of the tables
+    let tables = params.table_points();
+    let points: Vec<ForeignAffine> = digits
+        .iter()
+        .zip(tables.iter())
+        .map(|(digit, table)| table[*digit].clone())
+        .collect();
+    let limb_offset = F::two_pow(window as u64);
+    for (i, (digit, point)) in digits.iter().zip(points.iter()).enumerate() {
+        let table_id: F = i32_to_field(params.table_id + i as i32);
+        let d = F::from(*digit as u64);
+        let index = |k: u64| d + F::from(k) * limb_offset;
+        let x = point.x.to_field_limbs::<F>();
+        let y = point.y.to_field_limbs::<F>();
+        let mut rows = [[F::zero(); COLUMNS]; 5];
+        rows[0][0..7].copy_from_slice(&[table_id, d, x[0], index(1), x[1], index(2), x[2]]);
+        rows[1][0..7].copy_from_slice(&[table_id, index(3), y[0], index(4), y[1], index(5), y[2]]);
+        rows[2][0..6].copy_from_slice(&[table_id, F::zero(), F::zero(), d, F::zero(), index(1)]);
+        rows[3][0..6].copy_from_slice(&[d, F::zero(), index(2), d, F::zero(), index(3)]);
+        rows[4][0..6].copy_from_slice(&[d, F::zero(), index(4), d, F::zero(), index(5)]);
+        extend_rows(witness, &rows);
+    }
+
+    // Composition of the digits into the limbs of the scalar
+    let digits_per_limb = LIMB_BITS / window;
+    let mut halves = vec![];
+    for limb in 0..params.num_limbs() {
+        let limb_digits =
+            &digits[(limb * digits_per_limb)..num_windows.min((limb + 1) * digits_per_limb)];
+        let mut acc = F::from(*limb_digits.last().unwrap() as u64);
+        for digit in limb_digits.iter().rev().skip(1) {
+            let d = F::from(*digit as u64);
+            let next = acc * limb_offset + d;
+            halves.push([acc, d, next]);
+            acc = next;
+        }
+    }
+    for pair in halves.chunks(2) {
+        let mut row = [F::zero(); COLUMNS];
+        for (i, half) in pair.iter().enumerate() {
+            row[(3 * i)..(3 * i + 3)].copy_from_slice(half);
+        }
+        extend_rows(witness, &[row]);
+    }
+
+    // Sum of the points
+    points.iter().skip(1).fold(points[0].clone(), |acc, point| {
+        extend_ec_add(witness, &acc, point, &params.foreign_field_modulus)
+    })
+}
+
+/// Creates the witness of a fixed-base scalar multiplication `scalar * base`,
+/// and returns it together with the product
+pub fn create<F: PrimeField>(
+    scalar: &BigUint,
+    params: &ScalarMulParams,
+) -> ([Vec<F>; COLUMNS], ForeignAffine) {
+    let mut witness = array::from_fn(|_| vec![]);
+    let result = extend(&mut witness, scalar, params);
+    (witness, result)
+}
@@ -159,7 +159,7 @@ where
         );
         let shifts_sum = grid!(
             400,
-            env.witness_curr_chunk(CHI_SHIFTS_SUM_OFF, IOTA_STATE_G_OFF)
+            env.witness_curr_chunk(CHI_SHIFTS_SUM_OFF, CHI_SHIFTS_SUM_OFF + CHI_SHIFTS_SUM_LEN)
         );
         // IOTA
         let state_g = grid!(100, env.witness_next_chunk(0, IOTA_STATE_G_LEN));
@@ -75,6 +75,28 @@ pub const RC: [u64; ROUNDS] = [
     0x8000000080008008,
 ];
 
+/// The sparse representation of the 16-bit quarter `0xFFFF`, whose nibbles are all `1`
+pub const SPARSE_ONES: u64 = 0x1111111111111111;
+
+// The shifts and masks spreading the bits of a 16-bit quarter to the least
+// significant bits of the nibbles of a u64, halving the distance between the groups
+// of bits at each step
+const SPREAD_MASKS: [(u32, u64); 4] = [
+    (24, 0x000000FF000000FF),
+    (12, 0x000F000F000F000F),
+    (6, 0x0303030303030303),
+    (3, SPARSE_ONES),
+];
+
+// The shifts and masks gathering the least significant bits of the nibbles of a u64
+// into a 16-bit quarter, undoing the steps of [SPREAD_MASKS]
+const GATHER_MASKS: [(u32, u64); 4] = [
+    (3, 0x0303030303030303),
+    (6, 0x000F000F000F000F),
+    (12, 0x000000FF000000FF),
+    (24, 0xFFFF),
+];
+
 /// Naive Keccak structure
 pub struct Keccak {}
 
@@ -97,7 +119,15 @@ impl Keccak {
 
     /// Expands a quarter of a word into the sparse representation as a u64
     pub fn expand(quarter: u64) -> u64 {
-        u64::from_str_radix(&format!("{:b}", quarter), 16).unwrap()
+        debug_assert!(quarter < 1 << 16, "a quarter has 16 bits");
+        SPREAD_MASKS
+            .iter()
+            .fold(quarter, |x, &(shift, mask)| (x | (x << shift)) & mask)
+    }
+
+    /// Expands many quarters at once, see [Self::expand]
+    pub fn expand_many(quarters: &[u64]) -> Vec<u64> {
+        quarters.iter().map(|&q| Self::expand(q)).collect()
     }
 
     /// Expands a u64 word into a vector of 4 sparse u64 quarters
@@ -122,12 +152,12 @@ impl Keccak {
     pub fn shift(state: &[u64]) -> Vec<u64> {
         let n = state.len();
         let mut shifts = vec![0; QUARTERS * n];
-        let aux = Self::expand(0xFFFF);
-        for (i, term) in state.iter().enumerate() {
-            shifts[i] = aux & term; // shift0 = reset0
-            shifts[n + i] = ((aux << 1) & term) / 2; // shift1 = reset1/2
-            shifts[2 * n + i] = ((aux << 2) & term) / 4; // shift2 = reset2/4
-            shifts[3 * n + i] = ((aux << 3) & term) / 8; // shift3 = reset3/8
+        // shift_i = reset_i / 2^i, computed one shift at a time over the whole state so
+        // that each pass is a single vectorizable loop
+        for (i, shift) in shifts.chunks_exact_mut(n.max(1)).enumerate() {
+            for (s, term) in shift.iter_mut().zip(state) {
+                *s = (term >> i) & SPARSE_ONES;
+            }
         }
         shifts
     }
@@ -142,39 +172,31 @@ impl Keccak {
     pub fn collapse(state: &[u64]) -> Vec<u64> {
         state
             .iter()
-            .map(|&reset| u64::from_str_radix(&format!("{:x}", reset), 2).unwrap())
+            .map(|&reset| {
+                debug_assert_eq!(reset & !SPARSE_ONES, 0, "the state is not canonical");
+                GATHER_MASKS
+                    .iter()
+                    .fold(reset, |x, &(shift, mask)| (x | (x >> shift)) & mask)
+            })
             .collect::<Vec<u64>>()
     }
 
     /// Outputs the state into dense quarters of 16-bits each in little endian order
     pub fn quarters(state: &[u8]) -> Vec<u64> {
-        let mut quarters = vec![];
-        for pair in state.chunks(2) {
-            quarters.push(u16::from_le_bytes([pair[0], pair[1]]) as u64);
-        }
-        quarters
+        state
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]) as u64)
+            .collect()
     }
 
     /// On input a vector of 16-bit dense quarters, outputs a vector of 8-bit bytes in the right order for Keccak
     pub fn bytestring(dense: &[u64]) -> Vec<u64> {
-        dense
-            .iter()
-            .map(|x| vec![x % 256, x / 256])
-            .collect::<Vec<Vec<u64>>>()
-            .iter()
-            .flatten()
-            .copied()
-            .collect()
+        dense.iter().flat_map(|x| [x & 0xFF, x >> 8]).collect()
     }
 
     /// On input a 200-byte vector, generates a vector of 100 expanded quarters representing the 1600-bit state
     pub fn expand_state(state: &[u8]) -> Vec<u64> {
-        let mut expanded = vec![];
-        for pair in state.chunks(2) {
-            let quarter = u16::from_le_bytes([pair[0], pair[1]]);
-            expanded.push(Self::expand(quarter as u64));
-        }
-        expanded
+        Self::expand_many(&Self::quarters(state))
     }
 
     /// On input a length, returns the smallest multiple of RATE_IN_BYTES that is greater than the bytelength.
@@ -210,7 +232,7 @@ mod tests {
     use rand::{rngs::StdRng, thread_rng, Rng};
     use rand_core::SeedableRng;
 
-    use super::*;
+    use super::{constants::STATE_LEN, *};
 
     #[test]
     // Shows that the expansion of the 16-bit dense quarters into 64-bit sparse quarters
@@ -221,6 +243,39 @@ mod tests {
         assert_eq!(Keccak::expand(0x1234), 0x0001001000110100)
     }
 
+    #[test]
+    // Checks the expansion and the collapse of all the quarters against their
+    // definition: the binary digits of a quarter are the hexadecimal digits of its
+    // expansion
+    fn test_expand_collapse_all_quarters() {
+        let quarters: Vec<u64> = (0..1 << 16).collect();
+        let expanded = Keccak::expand_many(&quarters);
+        for (&quarter, &sparse) in quarters.iter().zip(&expanded) {
+            assert_eq!(
+                sparse,
+                u64::from_str_radix(&format!("{:b}", quarter), 16).unwrap()
+            );
+        }
+        assert_eq!(Keccak::collapse(&expanded), quarters);
+    }
+
+    #[test]
+    // Checks the shifts of sparse quarters with carries against their definition
+    fn test_shifts_with_carries() {
+        let seed: [u8; 32] = thread_rng().gen();
+        eprintln!("Seed: {:?}", seed);
+        let mut rng = StdRng::from_seed(seed);
+        let state: Vec<u64> = (0..STATE_LEN).map(|_| rng.gen()).collect();
+        let shifts = Keccak::shift(&state);
+        assert_eq!(shifts.len(), QUARTERS * STATE_LEN);
+        let aux = Keccak::expand(0xFFFF);
+        for (i, term) in state.iter().enumerate() {
+            for j in 0..QUARTERS {
+                assert_eq!(shifts[j * STATE_LEN + i], ((aux << j) & term) >> j);
+            }
+        }
+    }
+
     #[test]
     // Tests that composing and decomposition are the inverse of each other,
     // and the order of the quarters is the desired one.
@@ -7,7 +7,7 @@ use crate::{
             constants::{
                 CAPACITY_IN_BYTES, DIM, KECCAK_COLS, QUARTERS, RATE_IN_BYTES, ROUNDS, STATE_LEN,
             },
-            Keccak, OFF,
+            Keccak, OFF, SPARSE_ONES,
         },
         witness::{self, IndexCell, Variables, WitnessCell},
     },
@@ -213,19 +213,16 @@ impl Rotation {
     // On input the dense quarters of a word, rotate the word offset bits to the left
     fn new(dense: &[u64], offset: u32) -> Self {
         let word = Keccak::compose(dense);
-        let rem = word as u128 * 2u128.pow(offset) % 2u128.pow(64);
-        let quo = (word as u128) / 2u128.pow(64 - offset);
+        let rem = word << offset;
+        let quo = word.checked_shr(64 - offset).unwrap_or(0);
         let rot = rem + quo;
-        assert!(rot as u64 == word.rotate_left(offset));
+        debug_assert_eq!(rot, word.rotate_left(offset));
 
         Self {
-            quotient: Keccak::decompose(quo as u64),
-            remainder: Keccak::decompose(rem as u64),
-            dense_rot: Keccak::decompose(rot as u64),
-            expand_rot: Keccak::decompose(rot as u64)
-                .iter()
-                .map(|x| Keccak::expand(*x))
-                .collect(),
+            quotient: Keccak::decompose(quo),
+            remainder: Keccak::decompose(rem),
+            dense_rot: Keccak::decompose(rot),
+            expand_rot: Keccak::sparse(rot),
         }
     }
 
@@ -461,7 +458,7 @@ impl Chi {
         for y in 0..DIM {
             for x in 0..DIM {
                 for q in 0..QUARTERS {
-                    let not = 0x1111111111111111u64 - shiftsb(0, y, (x + 1) % DIM, q);
+                    let not = SPARSE_ONES - shiftsb(0, y, (x + 1) % DIM, q);
                     sum.push(not + shiftsb(0, y, (x + 2) % DIM, q));
                 }
             }
@@ -535,7 +532,21 @@ impl Iota {
 /// Requires at least one more row after the keccak gadget so that
 /// constraints can access the next row in the squeeze
 pub fn extend_keccak_witness<F: PrimeField>(witness: &mut [Vec<F>; KECCAK_COLS], message: BigUint) {
-    let padded = Keccak::pad(&message.to_bytes_be());
+    extend_keccak_witness_bytes(witness, &message.to_bytes_be())
+}
+
+/// Creates a witness for the Keccak hash function of a bytestring, which
+/// unlike [extend_keccak_witness] can be empty or start with zero bytes
+/// Input:
+/// - message: the bytes to be hashed
+/// Note:
+/// Requires at least one more row after the keccak gadget so that
+/// constraints can access the next row in the squeeze
+pub fn extend_keccak_witness_bytes<F: PrimeField>(
+    witness: &mut [Vec<F>; KECCAK_COLS],
+    message: &[u8],
+) {
+    let padded = Keccak::pad(message);
     let chunks = padded.chunks(RATE_IN_BYTES);
 
     // The number of rows that need to be added to the witness correspond to
@@ -1,10 +1,16 @@
 pub mod and;
+pub mod chacha;
 pub mod complete_add;
+pub mod decomposition;
 pub mod endomul_scalar;
 pub mod endosclmul;
+pub mod fixed_base_mul;
 pub mod foreign_field_add;
 pub mod foreign_field_common;
+pub mod foreign_field_ecdsa;
 pub mod foreign_field_mul;
+pub mod foreign_field_params;
+pub mod foreign_field_scalar_mul;
 pub mod generic;
 pub mod keccak;
 pub mod not;
@@ -12,6 +18,9 @@ pub mod permutation;
 pub mod poseidon;
 pub mod range_check;
 pub mod rot;
+pub mod schnorr;
+pub mod select;
 pub mod turshi;
+pub mod uint;
 pub mod varbasemul;
 pub mod xor;
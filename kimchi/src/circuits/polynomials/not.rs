@@ -3,6 +3,7 @@
 //! Note that this module does not include a `Not` gate type.
 use crate::circuits::{
     gate::{CircuitGate, Connect, GateType},
+    lookup::tables::xor::XorTableBits,
     polynomial::COLUMNS,
     wires::Wire,
 };
@@ -13,7 +14,7 @@ use std::{array, cmp::max};
 
 use super::{
     generic::GenericGateSpec,
-    xor::{init_xor, num_xors},
+    xor::{check_exact_length, init_xor, num_xors, padding_nybbles},
 };
 
 //~ We implement NOT, i.e. bitwise negation, as a gadget in two different ways, needing no new gate type for it. Instead, it reuses the XOR gadget and the Generic gate.
@@ -156,7 +157,7 @@ impl<F: PrimeField> CircuitGate<F> {
             .map(|i| CircuitGate {
                 typ: GateType::Xor16,
                 wires: Wire::for_row(new_row + i),
-                coeffs: vec![],
+                coeffs: XorTableBits::Four.coeffs(),
             })
             .collect::<Vec<_>>();
         let zero_row = new_row + n;
@@ -174,6 +175,34 @@ impl<F: PrimeField> CircuitGate<F> {
 
         gates.len()
     }
+
+    /// Extends a NOT gadget for words of exactly `bits` length using Xor gates, where
+    /// `bits` can be any multiple of [XOR_TABLE_BITS](super::xor::XOR_TABLE_BITS)
+    /// (e.g. 8, 16, 32, 64 or 128).
+    /// On top of `extend_not_gadget_checked_length`, it checks that the most significant
+    /// nybbles of the input in the last `Xor16` row are zero when `bits` is not a multiple
+    /// of 16. The second input being the all-one word, those of the output are zero too.
+    /// The witness is the one of `extend_not_witness_checked_length` with `Some(bits)`.
+    /// Input:
+    /// - gates        : full circuit
+    /// - all_ones_row : row containing the public input with the all-one word of the given length
+    /// - bits         : number of bits of the input
+    /// Output:
+    /// - new row index
+    /// Panics if `bits` is not a positive multiple of [XOR_TABLE_BITS](super::xor::XOR_TABLE_BITS)
+    pub fn extend_not_gadget_exact_length(
+        gates: &mut Vec<Self>,
+        all_ones_row: usize,
+        bits: usize,
+    ) -> usize {
+        check_exact_length(bits);
+        let new_row = Self::extend_not_gadget_checked_length(gates, all_ones_row, bits);
+        let zero_cell = (new_row - 1, 0);
+        for nybble in (4 - padding_nybbles(bits))..4 {
+            gates.connect_cell_pair(zero_cell, (new_row - 2, 3 + nybble));
+        }
+        new_row
+    }
 }
 
 /// Extend a NOT witness for less than 255 bits (native field)
@@ -196,6 +225,7 @@ pub fn extend_not_witness_checked_length<F: PrimeField>(
         &mut not_witness,
         0,
         bits,
+        XorTableBits::Four,
         (
             F::from_biguint(&input).unwrap(),
             F::from(2u8).pow([bits as u64]) - F::one(),
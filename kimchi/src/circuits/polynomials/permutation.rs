@@ -55,7 +55,7 @@ use ark_poly::{
     EvaluationDomain, Evaluations, Polynomial, Radix2EvaluationDomain as D, UVPolynomial,
 };
 use blake2::{Blake2b512, Digest};
-use o1_utils::{ExtendedDensePolynomial, ExtendedEvaluations};
+use o1_utils::{fft::FftInterpolate as _, ExtendedDensePolynomial, ExtendedEvaluations};
 use poly_commitment::OpenProof;
 use rand::{CryptoRng, RngCore};
 use rayon::prelude::*;
@@ -169,20 +169,24 @@ where
 
     /// sample coordinate shifts deterministically
     fn sample(domain: &D<F>, input: &mut u32) -> F {
+        // the shifts are sampled from at most 31 bytes, fewer for the small
+        // fields so that the bytes are always below the modulus
+        let num_bytes = std::cmp::min(31, (F::BasePrimeField::size_in_bits() - 1) / 8);
+
         let mut h = Blake2b512::new();
 
         *input += 1;
         h.update(input.to_be_bytes());
 
-        let mut shift = F::from_random_bytes(&h.finalize()[..31])
-            .expect("our field elements fit in more than 31 bytes");
+        let mut shift = F::from_random_bytes(&h.finalize()[..num_bytes])
+            .expect("the bytes are below the modulus");
 
         while !shift.legendre().is_qnr() || domain.evaluate_vanishing_polynomial(shift).is_zero() {
             let mut h = Blake2b512::new();
             *input += 1;
             h.update(input.to_be_bytes());
-            shift = F::from_random_bytes(&h.finalize()[..31])
-                .expect("our field elements fit in more than 31 bytes");
+            shift = F::from_random_bytes(&h.finalize()[..num_bytes])
+                .expect("the bytes are below the modulus");
         }
         shift
     }
@@ -500,7 +504,8 @@ impl<F: PrimeField, G: KimchiCurve<ScalarField = F>, OpeningProof: OpenProof<G>>
             return Err(ProverError::Permutation("final value"));
         };
 
-        let res = Evaluations::<F, D<F>>::from_vec_and_domain(z, self.cs.domain.d1).interpolate();
+        let res =
+            Evaluations::<F, D<F>>::from_vec_and_domain(z, self.cs.domain.d1).fft_interpolate();
         Ok(res)
     }
 }
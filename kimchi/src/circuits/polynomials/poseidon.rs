@@ -15,6 +15,8 @@
 //~ The last state is stored on the next row. This last state is either used:
 //~
 //~ * with another Poseidon gate on that next row, representing the next 5 rounds.
+//~   The next 5 rounds can be those of the next permutation of a chain, the last
+//~   round constants then also adding the constants absorbed between the permutations.
 //~ * or with a Zero gate, and a permutation to use the output elsewhere in the circuit.
 //~ * or with another gate expecting an input of 3 field elements in its first registers.
 //~
@@ -38,8 +40,10 @@ use crate::{
 use ark_ff::{Field, PrimeField, SquareRootField};
 use mina_poseidon::{
     constants::{PlonkSpongeConstantsKimchi, SpongeConstants},
+    permutation::{full_round_columns, states_to_columns, BATCH_CHUNK_SIZE},
     poseidon::{sbox, ArithmeticSponge, ArithmeticSpongeParams, Sponge},
 };
+use rayon::prelude::*;
 use std::{marker::PhantomData, ops::Range};
 use CurrOrNext::{Curr, Next};
 
@@ -99,12 +103,38 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
         // first and last row of the poseidon circuit (because they are used in the permutation)
         first_and_last_row: [GateWires; 2],
         round_constants: &[Vec<F>],
+    ) -> (Vec<Self>, usize) {
+        Self::create_poseidon_chain(row, first_and_last_row, round_constants, &[])
+    }
+
+    /// `create_poseidon_chain(row, first_and_last_row, round_constants, absorbed)` creates the
+    /// constraints of a chain of `absorbed.len() + 1` Poseidon permutations, where the state given
+    /// to each permutation but the first one is the state returned by the previous permutation,
+    /// plus the constants of `absorbed`.
+    /// The permutations share their boundary rows: the state after the last round of a
+    /// permutation is stored on the first row of the next one, and the constants are added by
+    /// the round constants of that last round. A chain of `n` permutations has
+    /// `POS_ROWS_PER_HASH * n + 1` rows, instead of the `(POS_ROWS_PER_HASH + 1) * n` rows of `n`
+    /// calls to [Self::create_poseidon_gadget], plus the generic gates adding the constants.
+    /// The state given to the `k`-th permutation is in the first 3 columns of the row
+    /// `row + k * POS_ROWS_PER_HASH`, and the output of the chain in the first 3 columns of the
+    /// last row.
+    /// The function returns a set of gates, as well as the last row, containing the output
+    pub fn create_poseidon_chain(
+        // the absolute row in the circuit
+        row: usize,
+        // first and last row of the chain (because they are used in the permutation)
+        first_and_last_row: [GateWires; 2],
+        round_constants: &[Vec<F>],
+        // the constants added to the state between two permutations
+        absorbed: &[[F; SPONGE_WIDTH]],
     ) -> (Vec<Self>, usize) {
         let mut gates = vec![];
 
         // create the gates
-        let relative_rows = 0..POS_ROWS_PER_HASH;
-        let last_row = row + POS_ROWS_PER_HASH;
+        let num_rows = POS_ROWS_PER_HASH * (absorbed.len() + 1);
+        let relative_rows = 0..num_rows;
+        let last_row = row + num_rows;
         let absolute_rows = row..last_row;
 
         for (abs_row, rel_row) in absolute_rows.zip(relative_rows) {
@@ -115,10 +145,20 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
                 std::array::from_fn(|col| Wire { col, row: abs_row })
             };
 
+            // the permutation of this row, and the constants absorbed after it
+            let permutation_row = rel_row % POS_ROWS_PER_HASH;
+            let absorbed = absorbed.get(rel_row / POS_ROWS_PER_HASH);
+
             // round constant for this row
             let coeffs = std::array::from_fn(|offset| {
-                let round = rel_row * ROUNDS_PER_ROW + offset;
-                std::array::from_fn(|field_el| round_constants[round][field_el])
+                let round = permutation_row * ROUNDS_PER_ROW + offset;
+                std::array::from_fn(|field_el| match absorbed {
+                    // the last round also adds the constants absorbed by the next permutation
+                    Some(absorbed) if round == ROUNDS_PER_HASH - 1 => {
+                        round_constants[round][field_el] + absorbed[field_el]
+                    }
+                    _ => round_constants[round][field_el],
+                })
             });
 
             // create poseidon gate for this row
@@ -270,6 +310,98 @@ pub fn generate_witness<F: Field>(
     }
 }
 
+/// `generate_chain_witness(row, params, witness_cols, input, absorbed)` generates the witness
+/// of a chain of permutations (see [CircuitGate::create_poseidon_chain]) starting at row `row`
+/// in `witness_cols`, with input `input` and the constants `absorbed` added between the
+/// permutations, and returns the output of the chain.
+///
+/// # Panics
+///
+/// Will panic if the `circuit` has `INITIAL_ARK`.
+pub fn generate_chain_witness<F: Field>(
+    row: usize,
+    params: &'static ArithmeticSpongeParams<F>,
+    witness_cols: &mut [Vec<F>; COLUMNS],
+    input: [F; SPONGE_WIDTH],
+    absorbed: &[[F; SPONGE_WIDTH]],
+) -> [F; SPONGE_WIDTH] {
+    let mut state = input;
+    for permutation in 0..=absorbed.len() {
+        let first_row = row + permutation * POS_ROWS_PER_HASH;
+        generate_witness(first_row, params, witness_cols, state);
+
+        // the output is stored on the first row of the next permutation, with the constants
+        let next_row = first_row + POS_ROWS_PER_HASH;
+        state = std::array::from_fn(|i| witness_cols[i][next_row]);
+        if let Some(absorbed) = absorbed.get(permutation) {
+            for (s, a) in state.iter_mut().zip(absorbed) {
+                *s += a;
+            }
+        }
+    }
+    state
+}
+
+/// `generate_witness_batch(params, witness_cols, hashes)` generates the
+/// witness of several Poseidon hashes at once. `hashes` contains, for each
+/// hash, the row at which its witness starts and its input, as given to
+/// [generate_witness].
+///
+/// The permutations of all the hashes are computed together, round by round,
+/// in parallel chunks (see [mina_poseidon::permutation::full_round_columns]),
+/// which is faster than calling [generate_witness] for each hash in circuits
+/// containing many of them.
+///
+/// # Panics
+///
+/// Will panic if the `circuit` has `INITIAL_ARK`.
+#[allow(clippy::assertions_on_constants)]
+pub fn generate_witness_batch<F: Field>(
+    params: &'static ArithmeticSpongeParams<F>,
+    witness_cols: &mut [Vec<F>; COLUMNS],
+    hashes: &[(usize, [F; SPONGE_WIDTH])],
+) {
+    assert!(
+        !PlonkSpongeConstantsKimchi::PERM_INITIAL_ARK,
+        "this won't work if the circuit has an INITIAL_ARK"
+    );
+
+    // the states of each hash, after each round (the first one is the input)
+    let traces: Vec<Vec<[F; SPONGE_WIDTH]>> = hashes
+        .par_chunks(BATCH_CHUNK_SIZE)
+        .flat_map_iter(|chunk| {
+            let inputs: Vec<Vec<F>> = chunk.iter().map(|(_, input)| input.to_vec()).collect();
+            let mut columns = states_to_columns(&inputs, SPONGE_WIDTH);
+            let mut traces: Vec<Vec<[F; SPONGE_WIDTH]>> = chunk
+                .iter()
+                .map(|(_, input)| {
+                    let mut trace = Vec::with_capacity(ROUNDS_PER_HASH + 1);
+                    trace.push(*input);
+                    trace
+                })
+                .collect();
+            for round in 0..ROUNDS_PER_HASH {
+                full_round_columns::<F, PlonkSpongeConstantsKimchi>(params, &mut columns, round);
+                for (k, trace) in traces.iter_mut().enumerate() {
+                    trace.push(std::array::from_fn(|i| columns[i][k]));
+                }
+            }
+            traces
+        })
+        .collect();
+
+    // the state after the round `k` is stored in the row `k / ROUNDS_PER_ROW`
+    for ((row, _), trace) in hashes.iter().zip(traces) {
+        for (k, state) in trace.into_iter().enumerate() {
+            let row = row + k / ROUNDS_PER_ROW;
+            witness_cols[round_to_cols(k % ROUNDS_PER_ROW)]
+                .iter_mut()
+                .zip(state)
+                .for_each(|(w, s)| w[row] = s);
+        }
+    }
+}
+
 /// An equation of the form `(curr | next)[i] = round(curr[j])`
 struct RoundEquation {
     pub source: usize,
@@ -8,9 +8,12 @@ use std::array;
 
 use crate::{
     circuits::{
+        gate::GateType,
         polynomial::COLUMNS,
         polynomials::foreign_field_common::{BigUintForeignFieldHelpers, LIMB_BITS},
-        witness::{init_row, CopyBitsCell, CopyCell, VariableCell, Variables, WitnessCell},
+        witness::{
+            init_row, init_row_traced, CopyBitsCell, CopyCell, VariableCell, Variables, WitnessCell,
+        },
     },
     variable_map, variables,
 };
@@ -115,9 +118,24 @@ pub fn create_multi<F: PrimeField>(v0: F, v1: F, v2: F) -> [Vec<F>; COLUMNS] {
     let layout = layout();
     let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![F::zero(); 4]);
 
-    init_row(&mut witness, 0, 0, &layout, &variables!(v0));
-    init_row(&mut witness, 0, 1, &layout, &variables!(v1));
-    init_row(
+    init_row_traced(
+        GateType::RangeCheck0,
+        &mut witness,
+        0,
+        0,
+        &layout,
+        &variables!(v0),
+    );
+    init_row_traced(
+        GateType::RangeCheck0,
+        &mut witness,
+        0,
+        1,
+        &layout,
+        &variables!(v1),
+    );
+    init_row_traced(
+        GateType::RangeCheck1,
         &mut witness,
         0,
         2,
@@ -139,10 +157,25 @@ pub fn create_multi_compact<F: PrimeField>(v01: F, v2: F) -> [Vec<F>; COLUMNS] {
     let v0: F = v0.to_field().expect("failed to convert to field element");
     let v1: F = v1.to_field().expect("failed to convert to field element");
 
-    init_row(&mut witness, 0, 0, &layout, &variable_map!("v0" => v2));
-    init_row(&mut witness, 0, 1, &layout, &variable_map!("v1" => v0));
+    init_row_traced(
+        GateType::RangeCheck0,
+        &mut witness,
+        0,
+        0,
+        &layout,
+        &variable_map!("v0" => v2),
+    );
+    init_row_traced(
+        GateType::RangeCheck0,
+        &mut witness,
+        0,
+        1,
+        &layout,
+        &variable_map!("v1" => v0),
+    );
 
-    init_row(
+    init_row_traced(
+        GateType::RangeCheck1,
         &mut witness,
         0,
         2,
@@ -170,7 +203,14 @@ pub fn create<F: PrimeField>(v0: F) -> [Vec<F>; COLUMNS] {
     let layout = vec![range_check_0_row("v0", 0)];
     let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![F::zero()]);
 
-    init_row(&mut witness, 0, 0, &layout, &variables!(v0));
+    init_row_traced(
+        GateType::RangeCheck0,
+        &mut witness,
+        0,
+        0,
+        &layout,
+        &variables!(v0),
+    );
 
     witness
 }
@@ -306,7 +306,8 @@ fn init_rot64<F: PrimeField>(
     bound: F,
 ) {
     let rot_rows = layout_rot64(curr_row);
-    witness::init(
+    witness::init_traced(
+        GateType::Rot64,
         witness,
         curr_row,
         &rot_rows,
@@ -0,0 +1,415 @@
+//! This module obtains the gates of a Schnorr signature verification circuit.
+//!
+//! The gadget verifies the signatures of Mina's signer (the `mina-signer` crate), on
+//! the Pallas curve whose base field is the native field of the circuit, for messages
+//! made of a fixed number of field elements. With `G` the generator, a signature
+//! `(rx, s)` of the message `m` by the public key `P` is valid iff the point
+//! `R = s * G - e * P` has the x coordinate `rx` and an even y coordinate, where
+//! `e = H(m, P.x, P.y, rx)` is the hash of Mina's Poseidon hasher, whose initial state
+//! is fixed by a domain string.
+//!
+//! The component `s` is an element of the scalar field of Pallas, which is larger than
+//! the native field, so it is not a cell of the circuit: the gadget proves the knowledge
+//! of a valid `s`. The message, the public key and `rx` are cells, to be wired by the
+//! caller (e.g. to the public input).
+//!
+//! From the `n` bits of a scalar `k`, a base `T` and an initial accumulator `A`, the
+//! `VarBaseMul` gates compute `2^n * A + (2 * k + 1 - 2^n) * T`. The gadget does not
+//! need new gate types, and constrains
+//! - the hash `e`, with generic gates absorbing the inputs and `Poseidon` gates,
+//! - `A_e = (2^255 + 1 + 2 * e) * P`, from the bits of `e` with `A = 2 * P`. The most
+//!   significant bit is zero, so that the bits of `e` are canonical,
+//! - `A_s = 2^255 * P + 2 * s * G`, from the bits of the `k` such that
+//!   `2 * k + 1 - 2^255 = 2 * s` modulo the order of Pallas, with `A = P`,
+//! - `A_e + 2 * R = A_s + P` with `CompleteAdd` gates, which holds iff
+//!   `s * G = R + e * P`,
+//! - `R = (rx, 2 * h)` with `h < 2^253` decomposed in limbs (see
+//!   [crate::circuits::polynomials::decomposition]), so that `2 * h` is the canonical
+//!   even y coordinate,
+//! - `R` and `P` on the curve.
+//!
+//! The witness generation fails for the negligible fractions of messages and signatures
+//! for which `e` or the y coordinate of `R` have more than 254 bits, and of public keys
+//! for which an addition of the `VarBaseMul` gates is exceptional.
+//!
+//! The layout of the gadget, for messages of `l` field elements, is
+//!
+//! | Rows                   | Gates                   | Content                            |
+//! |------------------------|-------------------------|------------------------------------|
+//! | `8 + ceil(3 * c / 2)`  | `Generic`               | constants, curve equations, sponge |
+//! | `12 * c`               | `Poseidon`, `Zero`      | the `c = ceil((l + 3) / 2)` permutations of the sponge |
+//! | 1                      | `CompleteAdd`           | `2 * P`                            |
+//! | 102                    | `VarBaseMul`, `Zero`    | `A_e`                              |
+//! | 102                    | `VarBaseMul`, `Zero`    | `A_s`                              |
+//! | 3                      | `CompleteAdd`           | `2 * R`, `A_e + 2 * R`, `A_s + P`  |
+//! | 20                     | `Generic`, `Lookup`     | the decomposition of `h`           |
+
+use ark_ec::{models::SWModelParameters, AffineCurve};
+use ark_ff::{One, Zero};
+use mina_curves::pasta::{Fp, Pallas, PallasParameters};
+use mina_hasher::domain_prefix_to_field;
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    pasta::fp_kimchi,
+    poseidon::{ArithmeticSponge, Sponge},
+};
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    lookup::tables::LookupTable,
+    polynomials::{
+        decomposition::{self, Endianness},
+        poseidon::POS_ROWS_PER_HASH,
+        select::Generic,
+    },
+    wires::Wire,
+};
+
+/// Number of bits of the scalars of the `VarBaseMul` gates
+pub const SCALAR_BITS: usize = 255;
+
+/// Number of bits of the limbs of the decomposition of `h`
+pub const PARITY_LIMB_BITS: usize = 11;
+
+/// Number of limbs of the decomposition of `h`, which has 253 bits
+pub const PARITY_LIMBS: usize = 23;
+
+/// Largest length of a domain string of Mina's hasher
+pub const MAX_DOMAIN_LEN: usize = 20;
+
+/// Number of bits of a `VarBaseMul` gate
+const BITS_PER_GATE: usize = 5;
+
+/// Number of rows of a scalar multiplication
+pub(crate) const VAR_BASE_MUL_ROWS: usize = 2 * SCALAR_BITS / BITS_PER_GATE;
+
+/// Number of rows of a permutation of the sponge
+pub(crate) const PERMUTATION_ROWS: usize = POS_ROWS_PER_HASH + 1;
+
+/// Number of rows of the decomposition of `h`
+const DECOMPOSITION_ROWS: usize = PARITY_LIMBS / 2 + 1 + (PARITY_LIMBS + 2) / 3;
+
+/// Parameters of a Schnorr signature verification
+#[derive(Clone, Debug)]
+pub struct SchnorrParams {
+    /// Domain string of the hash of the messages, e.g. `CodaSignature` for the
+    /// signatures of Mina's testnets
+    pub domain: String,
+    /// Number of field elements of the messages
+    pub message_len: usize,
+    /// ID of the lookup table of the limbs of the decomposition of `h`
+    pub table_id: i32,
+}
+
+/// Cells of the inputs and of the hash of a Schnorr signature verification
+#[derive(Clone, Debug)]
+pub struct SchnorrCells {
+    /// The message
+    pub message: Vec<(usize, usize)>,
+    /// The coordinates of the public key
+    pub public_key: [(usize, usize); 2],
+    /// The x coordinate of the point `R` of the signature
+    pub rx: (usize, usize),
+    /// The hash `e` of the message
+    pub hash: (usize, usize),
+}
+
+/// The variables of the generic gates of the gadget
+pub(crate) struct SchnorrVars {
+    pub(crate) message: Vec<usize>,
+    pub(crate) public_key: [usize; 2],
+    pub(crate) rx: usize,
+    pub(crate) ry: usize,
+    /// Half the y coordinate of `R`
+    pub(crate) half_ry: usize,
+    pub(crate) zero: usize,
+    pub(crate) generator: [usize; 2],
+    /// The states given to the permutations of the sponge
+    pub(crate) permutation_inputs: Vec<[usize; 3]>,
+    /// The states returned by the permutations of the sponge, but the last one
+    pub(crate) permutation_outputs: Vec<[usize; 3]>,
+}
+
+impl SchnorrParams {
+    /// Creates the parameters of the verification of the signatures of messages of
+    /// `message_len` field elements, hashed with the domain string `domain`
+    /// Panics if the domain string is longer than [MAX_DOMAIN_LEN]
+    pub fn new(domain: &str, message_len: usize, table_id: i32) -> Self {
+        assert!(
+            domain.len() <= MAX_DOMAIN_LEN,
+            "the domain string has more than {MAX_DOMAIN_LEN} bytes"
+        );
+        SchnorrParams {
+            domain: domain.to_string(),
+            message_len,
+            table_id,
+        }
+    }
+
+    /// The state of the sponge after the absorption of the domain string, from which
+    /// the messages are hashed
+    pub fn initial_state(&self) -> [Fp; 3] {
+        let mut sponge =
+            ArithmeticSponge::<Fp, PlonkSpongeConstantsKimchi>::new(fp_kimchi::static_params());
+        sponge.absorb(&[domain_prefix_to_field::<Fp>(self.domain.clone())]);
+        sponge.squeeze();
+        [sponge.state[0], sponge.state[1], sponge.state[2]]
+    }
+
+    /// Number of permutations of the sponge, absorbing 2 of the `message_len + 3`
+    /// inputs of the hash at a time
+    pub fn num_permutations(&self) -> usize {
+        (self.message_len + 4) / 2
+    }
+
+    /// Number of rows of the gadget
+    pub fn num_rows(&self) -> usize {
+        self.gadget().0.num_rows()
+            + self.num_permutations() * PERMUTATION_ROWS
+            + 2 * VAR_BASE_MUL_ROWS
+            + 4
+            + DECOMPOSITION_ROWS
+    }
+
+    /// Lookup tables of the gadget, to be added to the constraint system
+    pub fn lookup_tables(&self) -> Vec<LookupTable<Fp>> {
+        vec![decomposition::limb_table(self.table_id, PARITY_LIMB_BITS)]
+    }
+
+    /// The operations of the generic gates, shared by the layout and the witness
+    pub(crate) fn gadget(&self) -> (Generic<Fp>, SchnorrVars) {
+        let mut generic = Generic::default();
+        let message: Vec<usize> = (0..self.message_len).map(|_| generic.var()).collect();
+        let public_key = [generic.var(), generic.var()];
+        let rx = generic.var();
+        let half_ry = generic.var();
+        let [px, py] = public_key;
+
+        let zero = constant(&mut generic, px, Fp::zero());
+        let g = Pallas::prime_subgroup_generator();
+        let generator = [
+            constant(&mut generic, px, g.x),
+            constant(&mut generic, px, g.y),
+        ];
+
+        on_curve(&mut generic, px, py);
+        let ry = generic.affine(half_ry, Fp::from(2u64), Fp::zero());
+        on_curve(&mut generic, rx, ry);
+
+        // the sponge absorbs the inputs in its 2 first elements, before each permutation
+        let initial = self.initial_state();
+        let inputs: Vec<usize> = message.iter().copied().chain([px, py, rx]).collect();
+        let mut permutation_inputs = vec![];
+        let mut permutation_outputs = vec![];
+        for (i, chunk) in inputs.chunks(2).enumerate() {
+            let input = if i == 0 {
+                [
+                    generic.affine(chunk[0], Fp::one(), initial[0]),
+                    match chunk.get(1) {
+                        Some(&right) => generic.affine(right, Fp::one(), initial[1]),
+                        None => constant(&mut generic, px, initial[1]),
+                    },
+                    constant(&mut generic, px, initial[2]),
+                ]
+            } else {
+                let state = [generic.var(), generic.var(), generic.var()];
+                permutation_outputs.push(state);
+                [
+                    generic.add(state[0], Fp::one(), chunk[0], Fp::one()),
+                    match chunk.get(1) {
+                        Some(&right) => generic.add(state[1], Fp::one(), right, Fp::one()),
+                        None => generic.affine(state[1], Fp::one(), Fp::zero()),
+                    },
+                    generic.affine(state[2], Fp::one(), Fp::zero()),
+                ]
+            };
+            permutation_inputs.push(input);
+        }
+
+        let vars = SchnorrVars {
+            message,
+            public_key,
+            rx,
+            ry,
+            half_ry,
+            zero,
+            generator,
+            permutation_inputs,
+            permutation_outputs,
+        };
+        (generic, vars)
+    }
+}
+
+// Returns a variable equal to `c`, computed from the variable `anchor`
+fn constant(generic: &mut Generic<Fp>, anchor: usize, c: Fp) -> usize {
+    generic.affine(anchor, Fp::zero(), c)
+}
+
+// Constrains `(x, y)` to be on the curve: y^2 = x^3 + b
+fn on_curve(generic: &mut Generic<Fp>, x: usize, y: usize) {
+    let x_squared = generic.mul(x, x);
+    let x_cubed = generic.mul(x_squared, x);
+    let y_squared = generic.mul(y, y);
+    let difference = generic.add(y_squared, Fp::one(), x_cubed, -Fp::one());
+    let z = generic.affine(difference, Fp::one(), -PallasParameters::COEFF_B);
+    generic.zero(z);
+}
+
+// The cells of a scalar multiplication with `VarBaseMul` gates
+struct VarBaseMulCells {
+    first_bit: (usize, usize),
+    scalar: (usize, usize),
+    result: [(usize, usize); 2],
+}
+
+impl CircuitGate<Fp> {
+    /// Extends a circuit with the verification of a Schnorr signature, see the
+    /// documentation of this module for the details
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - curr_row  : mutable reference to the current row, the number of gates
+    /// - params    : parameters of the verification
+    /// Output:
+    /// - cells of the message, the public key, `rx` and the hash
+    /// Warning:
+    /// - the lookup tables [SchnorrParams::lookup_tables] must be added to the
+    ///   constraint system
+    /// - don't forget to wire the message, the public key and `rx`
+    pub fn extend_schnorr_verification(
+        gates: &mut Vec<Self>,
+        curr_row: &mut usize,
+        params: &SchnorrParams,
+    ) -> SchnorrCells {
+        assert_eq!(
+            *curr_row,
+            gates.len(),
+            "the current row must follow the gates"
+        );
+        let start = *curr_row;
+        let (generic, vars) = params.gadget();
+        let cells = generic.layout_partial(gates);
+        let cell = |var: usize| cells[var].expect("every variable is used by an operation");
+        let mut row = gates.len();
+
+        // the permutations of the sponge, the hash is the first element of the last state
+        let round_constants = &fp_kimchi::static_params().round_constants;
+        for (i, input) in vars.permutation_inputs.iter().enumerate() {
+            let last_row = row + POS_ROWS_PER_HASH;
+            let (permutation, _) = CircuitGate::create_poseidon_gadget(
+                row,
+                [Wire::for_row(row), Wire::for_row(last_row)],
+                round_constants,
+            );
+            gates.extend(permutation);
+            for (col, &var) in input.iter().enumerate() {
+                gates.connect_cell_pair(cell(var), (row, col));
+            }
+            if let Some(output) = vars.permutation_outputs.get(i) {
+                for (col, &var) in output.iter().enumerate() {
+                    gates.connect_cell_pair(cell(var), (last_row, col));
+                }
+            }
+            row = last_row + 1;
+        }
+        let hash = (row - 1, 0);
+
+        let zero = cell(vars.zero);
+        let public_key = vars.public_key.map(cell);
+        let r = [cell(vars.rx), cell(vars.ry)];
+
+        // A_e = (2^255 + 1 + 2 * e) * P, with a zero most significant bit
+        let double_public_key = complete_add(gates, &mut row, public_key, public_key, zero);
+        let e_mul = var_base_mul(gates, &mut row, public_key, double_public_key, zero);
+        gates.connect_cell_pair(zero, e_mul.first_bit);
+        gates.connect_cell_pair(hash, e_mul.scalar);
+
+        // A_s = 2^255 * P + 2 * s * G
+        let s_mul = var_base_mul(gates, &mut row, vars.generator.map(cell), public_key, zero);
+
+        // A_e + 2 * R = A_s + P
+        let double_r = complete_add(gates, &mut row, r, r, zero);
+        let left = complete_add(gates, &mut row, e_mul.result, double_r, zero);
+        let right = complete_add(gates, &mut row, s_mul.result, public_key, zero);
+        gates.connect_cell_pair(left[0], right[0]);
+        gates.connect_cell_pair(left[1], right[1]);
+
+        // the y coordinate of R is even
+        let (next_row, decomposition) = CircuitGate::extend_decomposition(
+            gates,
+            params.table_id,
+            PARITY_LIMB_BITS,
+            PARITY_LIMBS,
+            Endianness::Little,
+        );
+        gates.connect_cell_pair(cell(vars.half_ry), decomposition.value);
+        *curr_row = next_row;
+        assert_eq!(*curr_row, start + params.num_rows());
+
+        SchnorrCells {
+            message: vars.message.iter().map(|&var| cell(var)).collect(),
+            public_key,
+            rx: r[0],
+            hash,
+        }
+    }
+}
+
+// Appends a `CompleteAdd` gate adding the points `left` and `right`, whose sum must not
+// be the point at infinity, and returns the cells of the sum
+fn complete_add(
+    gates: &mut Vec<CircuitGate<Fp>>,
+    row: &mut usize,
+    left: [(usize, usize); 2],
+    right: [(usize, usize); 2],
+    zero: (usize, usize),
+) -> [(usize, usize); 2] {
+    gates.push(CircuitGate::new(
+        GateType::CompleteAdd,
+        Wire::for_row(*row),
+        vec![],
+    ));
+    for (col, cell) in left.into_iter().chain(right).enumerate() {
+        gates.connect_cell_pair(cell, (*row, col));
+    }
+    // the sum is not the point at infinity
+    gates.connect_cell_pair(zero, (*row, 6));
+    *row += 1;
+    [(*row - 1, 4), (*row - 1, 5)]
+}
+
+// Appends the `VarBaseMul` gates of a scalar multiplication of `base` with the initial
+// accumulator `acc`, chaining the accumulators and the scalar from one gate to the next
+fn var_base_mul(
+    gates: &mut Vec<CircuitGate<Fp>>,
+    row: &mut usize,
+    base: [(usize, usize); 2],
+    acc: [(usize, usize); 2],
+    zero: (usize, usize),
+) -> VarBaseMulCells {
+    let start = *row;
+    for i in 0..SCALAR_BITS / BITS_PER_GATE {
+        let gate_row = start + 2 * i;
+        gates.extend(CircuitGate::create_vbmul(&[
+            Wire::for_row(gate_row),
+            Wire::for_row(gate_row + 1),
+        ]));
+        gates.connect_cell_pair(base[0], (gate_row, 0));
+        gates.connect_cell_pair(base[1], (gate_row, 1));
+        if i == 0 {
+            gates.connect_cell_pair(acc[0], (gate_row, 2));
+            gates.connect_cell_pair(acc[1], (gate_row, 3));
+            gates.connect_cell_pair(zero, (gate_row, 4));
+        } else {
+            gates.connect_cell_pair((gate_row - 1, 0), (gate_row, 2));
+            gates.connect_cell_pair((gate_row - 1, 1), (gate_row, 3));
+            gates.connect_cell_pair((gate_row - 2, 5), (gate_row, 4));
+        }
+    }
+    *row += VAR_BASE_MUL_ROWS;
+    VarBaseMulCells {
+        first_bit: (start + 1, 2),
+        scalar: (*row - 2, 5),
+        result: [(*row - 1, 0), (*row - 1, 1)],
+    }
+}
@@ -0,0 +1,4 @@
+//! Native Schnorr signature verification module
+
+pub mod gadget;
+pub mod witness;
@@ -0,0 +1,266 @@
+//! This module computes the witness of a Schnorr signature verification circuit, and
+//! signs messages out of the circuit.
+
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use blake2::{Blake2b512, Digest};
+use mina_curves::pasta::{Fp, Fq, Pallas};
+use mina_hasher::ROInput;
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi, pasta::fp_kimchi, permutation::poseidon_block_cipher,
+};
+use std::array;
+
+use super::gadget::{
+    SchnorrParams, PARITY_LIMBS, PARITY_LIMB_BITS, PERMUTATION_ROWS, SCALAR_BITS, VAR_BASE_MUL_ROWS,
+};
+use crate::circuits::{
+    polynomial::COLUMNS,
+    polynomials::{
+        decomposition::{extend_decomposition_witness, Endianness},
+        fixed_base_mul::witness::complete_add_row,
+        poseidon, varbasemul,
+    },
+};
+
+/// A Schnorr signature `(rx, s)`, as produced by Mina's signer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature {
+    /// The x coordinate of the point `R`
+    pub rx: Fp,
+    /// The scalar `s`
+    pub s: Fq,
+}
+
+impl Signature {
+    /// Signs `message` with the secret key `secret`, deterministically
+    /// Panics if the message does not have `params.message_len` field elements
+    pub fn sign(params: &SchnorrParams, secret: Fq, message: &[Fp]) -> Self {
+        let public_key = public_key(secret);
+        let k = nonce(params, public_key, secret, message);
+        let r = Pallas::prime_subgroup_generator().mul(k).into_affine();
+        // R = k * G has an even y coordinate
+        let k = if r.y.into_repr().is_even() { k } else { -k };
+        let e = to_scalar(message_hash(params, public_key, r.x, message));
+        Signature {
+            rx: r.x,
+            s: k + e * secret,
+        }
+    }
+
+    /// Checks the signature of `message` by `public_key`, out of the circuit
+    /// Panics if the message does not have `params.message_len` field elements
+    pub fn verify(&self, params: &SchnorrParams, public_key: Pallas, message: &[Fp]) -> bool {
+        let e = to_scalar(message_hash(params, public_key, self.rx, message));
+        let r = Pallas::prime_subgroup_generator().mul(self.s) - public_key.mul(e);
+        if r.is_zero() {
+            return false;
+        }
+        let r = r.into_affine();
+        r.x == self.rx && r.y.into_repr().is_even()
+    }
+}
+
+/// Returns the public key of the secret key `secret`
+pub fn public_key(secret: Fq) -> Pallas {
+    Pallas::prime_subgroup_generator().mul(secret).into_affine()
+}
+
+/// Returns the hash `e` of the signature of `message` by `public_key` whose point `R`
+/// has the x coordinate `rx`
+/// Panics if the message does not have `params.message_len` field elements
+pub fn message_hash(params: &SchnorrParams, public_key: Pallas, rx: Fp, message: &[Fp]) -> Fp {
+    let permutations = permutations(params, public_key, rx, message);
+    let (_, output) = permutations.last().expect("the sponge is permuted");
+    output[0]
+}
+
+// The inputs and the outputs of the permutations of the sponge computing the hash
+fn permutations(
+    params: &SchnorrParams,
+    public_key: Pallas,
+    rx: Fp,
+    message: &[Fp],
+) -> Vec<([Fp; 3], [Fp; 3])> {
+    assert_eq!(
+        message.len(),
+        params.message_len,
+        "the message must have {} field elements",
+        params.message_len
+    );
+    let inputs: Vec<Fp> = message
+        .iter()
+        .copied()
+        .chain([public_key.x, public_key.y, rx])
+        .collect();
+    let mut state = params.initial_state();
+    let mut permutations = vec![];
+    for chunk in inputs.chunks(2) {
+        for (s, x) in state.iter_mut().zip(chunk) {
+            *s += x;
+        }
+        let mut output = state.to_vec();
+        poseidon_block_cipher::<Fp, PlonkSpongeConstantsKimchi>(
+            fp_kimchi::static_params(),
+            &mut output,
+        );
+        let output = [output[0], output[1], output[2]];
+        permutations.push((state, output));
+        state = output;
+    }
+    permutations
+}
+
+// The nonce of a signature, derived as in Mina's signer from the message, the keys and
+// the domain string
+fn nonce(params: &SchnorrParams, public_key: Pallas, secret: Fq, message: &[Fp]) -> Fq {
+    let input = message
+        .iter()
+        .fold(ROInput::new(), |input, &x| input.append_field(x))
+        .append_field(public_key.x)
+        .append_field(public_key.y)
+        .append_scalar(secret)
+        .append_bytes(params.domain.as_bytes());
+    let mut hasher = Blake2b512::new();
+    hasher.update(input.to_bytes());
+    let mut bytes: [u8; 32] = hasher.finalize()[..32]
+        .try_into()
+        .expect("the digest has 64 bytes");
+    // the two top bits are dropped, so that the bytes are smaller than the order
+    bytes[31] &= 0b0011_1111;
+    Fq::from_random_bytes(&bytes).expect("the bytes are a scalar")
+}
+
+// Converts a hash to a scalar, the base field is smaller than the scalar field
+fn to_scalar(e: Fp) -> Fq {
+    Fq::from_repr(e.into_repr()).expect("the base field is smaller than the scalar field")
+}
+
+// The most significant bits of `x`, first
+fn scalar_bits<F: PrimeField>(x: F) -> Vec<bool> {
+    let bits = x.into_repr().to_bits_be();
+    bits[bits.len() - SCALAR_BITS..].to_vec()
+}
+
+fn push_row(witness: &mut [Vec<Fp>; COLUMNS], row: [Fp; COLUMNS]) {
+    for (column, x) in witness.iter_mut().zip(row) {
+        column.push(x);
+    }
+}
+
+fn extend_rows(witness: &mut [Vec<Fp>; COLUMNS], num_rows: usize) -> usize {
+    let row = witness[0].len();
+    for column in witness.iter_mut() {
+        column.resize(row + num_rows, Fp::zero());
+    }
+    row
+}
+
+/// Extends the witness with the verification of the signature `signature` of `message`
+/// by `public_key`
+/// Panics if the signature is not valid, or in the negligible cases where the witness
+/// cannot be generated (see the documentation of the module)
+pub fn extend(
+    witness: &mut [Vec<Fp>; COLUMNS],
+    params: &SchnorrParams,
+    public_key: Pallas,
+    message: &[Fp],
+    signature: &Signature,
+) {
+    assert!(
+        signature.verify(params, public_key, message),
+        "the signature is not valid"
+    );
+    let permutations = permutations(params, public_key, signature.rx, message);
+    let (_, last) = permutations.last().expect("the sponge is permuted");
+    let e = last[0];
+    assert!(
+        e.into_repr().num_bits() < SCALAR_BITS as u32,
+        "the hash has more than {} bits",
+        SCALAR_BITS - 1
+    );
+
+    // R = (rx, 2 * h) with the even y coordinate
+    let r = Pallas::get_point_from_x(signature.rx, false).expect("rx is on the curve");
+    let ry = if r.y.into_repr().is_even() { r.y } else { -r.y };
+    let half_ry = ry / Fp::from(2u64);
+    assert!(
+        half_ry.into_repr().num_bits() as usize <= PARITY_LIMBS * PARITY_LIMB_BITS,
+        "the y coordinate of R has more than {} bits",
+        PARITY_LIMBS * PARITY_LIMB_BITS + 1
+    );
+
+    // The generic gates
+    let (generic, vars) = params.gadget();
+    let mut values = vec![None; generic.num_vars];
+    for (&var, &x) in vars.message.iter().zip(message) {
+        values[var] = Some(x);
+    }
+    values[vars.public_key[0]] = Some(public_key.x);
+    values[vars.public_key[1]] = Some(public_key.y);
+    values[vars.rx] = Some(signature.rx);
+    values[vars.half_ry] = Some(half_ry);
+    for (output_vars, (_, output)) in vars.permutation_outputs.iter().zip(&permutations) {
+        for (&var, &x) in output_vars.iter().zip(output) {
+            values[var] = Some(x);
+        }
+    }
+    let values = generic.witness(witness, values);
+
+    // The permutations of the sponge
+    for (input_vars, (input, _)) in vars.permutation_inputs.iter().zip(&permutations) {
+        debug_assert_eq!(input_vars.map(|var| values[var]), *input);
+        let row = extend_rows(witness, PERMUTATION_ROWS);
+        poseidon::generate_witness(row, fp_kimchi::static_params(), witness, *input);
+    }
+
+    // A_e = (2^255 + 1 + 2 * e) * P
+    let p = (public_key.x, public_key.y);
+    let (row, double_p) = complete_add_row(p, p);
+    push_row(witness, row);
+    let row = extend_rows(witness, VAR_BASE_MUL_ROWS);
+    let a_e = varbasemul::witness(witness, row, p, &scalar_bits(e), double_p);
+    debug_assert_eq!(a_e.n, e);
+
+    // A_s = 2^255 * P + 2 * s * G, with 2 * k + 1 - 2^255 = 2 * s
+    let two = Fq::from(2u64);
+    let k = (two * signature.s - Fq::one() + two.pow([SCALAR_BITS as u64])) / two;
+    let g = Pallas::prime_subgroup_generator();
+    let row = extend_rows(witness, VAR_BASE_MUL_ROWS);
+    let a_s = varbasemul::witness(witness, row, (g.x, g.y), &scalar_bits(k), p);
+
+    // A_e + 2 * R = A_s + P
+    let r = (signature.rx, ry);
+    let (row, double_r) = complete_add_row(r, r);
+    push_row(witness, row);
+    let (row, left) = complete_add_row(a_e.acc, double_r);
+    push_row(witness, row);
+    let (row, right) = complete_add_row(a_s.acc, p);
+    push_row(witness, row);
+    debug_assert_eq!(left, right);
+
+    // The y coordinate of R is even
+    extend_decomposition_witness(
+        witness,
+        params.table_id,
+        PARITY_LIMB_BITS,
+        PARITY_LIMBS,
+        Endianness::Little,
+        half_ry,
+    );
+}
+
+/// Creates the witness of the verification of the signature `signature` of `message`
+/// by `public_key`
+/// Panics if the signature is not valid, or in the negligible cases where the witness
+/// cannot be generated (see the documentation of the module)
+pub fn create(
+    params: &SchnorrParams,
+    public_key: Pallas,
+    message: &[Fp],
+    signature: &Signature,
+) -> [Vec<Fp>; COLUMNS] {
+    let mut witness = array::from_fn(|_| vec![]);
+    extend(&mut witness, params, public_key, message, signature);
+    witness
+}
@@ -0,0 +1,474 @@
+//! This module implements conditional selection and multiplexer gadgets,
+//! together with their witness generation.
+//!
+//! The gadgets do not need new gate types:
+//!
+//! - `cond_select(b, x, y) = b * (x - y) + y` checks that `b` is boolean and
+//!   computes the selection with 4 generic gates, in 2 rows.
+//! - the multiplexer of `n` cells `x_0, ..., x_{n-1}` decomposes the index `i`
+//!   in `k = ceil(log2(n))` boolean bits with `i = sum_j 2^j b_j`, and selects
+//!   `x_i` with a tree of `n - 1` conditional selections, the bit `b_j`
+//!   selecting at the `j`-th level of the tree. It takes
+//!   `ceil((2 * k - 1 + 3 * (n - 1)) / 2)` rows of generic gates. An index
+//!   `i >= n` selects `x_{n-1}`, the index must be range checked by the caller
+//!   if this is not wanted.
+//! - the lookup multiplexer selects among `n` values fixed when the circuit is
+//!   created, with a lookup table of the pairs `(j, x_j)`. A `Lookup` gate
+//!   performs 3 selections, whatever `n`, and the index is constrained to be
+//!   less than `n` by the table, so that it is cheaper than the multiplexer of
+//!   cells for large `n`.
+//!
+//! The generic gates of [CircuitGate::extend_cond_select] and
+//! [CircuitGate::extend_multiplexer] are laid out and filled by the same list of
+//! operations, so that the gates and the witness always agree.
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    lookup::tables::LookupTable,
+    polynomial::COLUMNS,
+    polynomials::generic::{GenericGateSpec, GENERIC_COEFFS},
+    wires::Wire,
+};
+use ark_ff::PrimeField;
+use o1_utils::{field_helpers::i32_to_field, math::ceil_log2, Two};
+use std::array;
+
+/// Number of selections of a `Lookup` gate of the lookup multiplexer
+pub const SELECTIONS_PER_ROW: usize = 3;
+
+/// The cells of a conditional selection
+pub struct SelectCells {
+    /// The boolean condition
+    pub b: (usize, usize),
+    /// The value selected if the condition is true
+    pub x: (usize, usize),
+    /// The value selected if the condition is false
+    pub y: (usize, usize),
+    /// The selected value
+    pub output: (usize, usize),
+}
+
+/// The cells of a multiplexer
+pub struct MultiplexerCells {
+    /// The index of the selected input
+    pub index: (usize, usize),
+    /// The inputs
+    pub inputs: Vec<(usize, usize)>,
+    /// The selected input
+    pub output: (usize, usize),
+}
+
+/// The cells of a selection of the lookup multiplexer
+pub struct LookupSelectionCells {
+    /// The index of the selected value
+    pub index: (usize, usize),
+    /// The selected value
+    pub output: (usize, usize),
+}
+
+/// Returns `x` if `b` is true, and `y` otherwise
+pub fn cond_select<F: PrimeField>(b: bool, x: F, y: F) -> F {
+    if b {
+        x
+    } else {
+        y
+    }
+}
+
+/// The lookup table of the lookup multiplexer with ID `id`, mapping an index
+/// `j` to `values[j]`
+/// Warning:
+/// - a table with ID 0 must contain the entry `(0, 0)`, that is `values[0]` must
+///   be zero
+pub fn multiplexer_table<F: PrimeField>(id: i32, values: &[F]) -> LookupTable<F> {
+    LookupTable {
+        id,
+        data: vec![
+            (0..values.len() as u64).map(F::from).collect(),
+            values.to_vec(),
+        ],
+    }
+}
+
+impl<F: PrimeField> CircuitGate<F> {
+    /// Extends a conditional selection gadget, see the module documentation
+    /// Includes:
+    /// - 2 double Generic gates
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs and of the output of the gadget
+    /// Warning:
+    /// - don't forget to wire the condition and the inputs to their values
+    pub fn extend_cond_select(gates: &mut Vec<Self>) -> (usize, SelectCells) {
+        let (generic, vars) = Generic::multiplexer(2);
+        let cells = generic.layout(gates);
+        let select = SelectCells {
+            b: cells[vars.index],
+            x: cells[vars.inputs[1]],
+            y: cells[vars.inputs[0]],
+            output: cells[vars.output],
+        };
+        (gates.len(), select)
+    }
+
+    /// Extends a multiplexer gadget of `n` inputs, see the module documentation
+    /// Includes:
+    /// - the double Generic gates of the decomposition of the index and of the
+    ///   selections
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - n         : number of inputs, at least 2
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the index, of the inputs and of the output
+    /// Warning:
+    /// - don't forget to wire the index and the inputs to their values
+    pub fn extend_multiplexer(gates: &mut Vec<Self>, n: usize) -> (usize, MultiplexerCells) {
+        let (generic, vars) = Generic::multiplexer(n);
+        let cells = generic.layout(gates);
+        let multiplexer = MultiplexerCells {
+            index: cells[vars.index],
+            inputs: vars.inputs.iter().map(|&var| cells[var]).collect(),
+            output: cells[vars.output],
+        };
+        (gates.len(), multiplexer)
+    }
+
+    /// Extends a lookup multiplexer gadget performing `num_selections`
+    /// selections among the values of the table with ID `table_id`, see the
+    /// module documentation
+    /// Includes:
+    /// - 1 Generic gate with the ID of the table
+    /// - ceil(num_selections / 3) Lookup gates
+    /// Input:
+    /// - gates             : vector of circuit gates comprising the full circuit
+    /// - table_id          : ID of the table created with [multiplexer_table]
+    /// - num_selections    : number of selections
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the index and of the output of each selection
+    /// Warning:
+    /// - the table [multiplexer_table] must be added to the constraint system
+    /// - don't forget to wire the indices
+    pub fn extend_lookup_multiplexer(
+        gates: &mut Vec<Self>,
+        table_id: i32,
+        num_selections: usize,
+    ) -> (usize, Vec<LookupSelectionCells>) {
+        let start = gates.len();
+        gates.push(CircuitGate::create_generic_gadget(
+            Wire::for_row(start),
+            GenericGateSpec::Const(i32_to_field(table_id)),
+            None,
+        ));
+        let num_rows = (num_selections + SELECTIONS_PER_ROW - 1) / SELECTIONS_PER_ROW;
+        for row in start + 1..=start + num_rows {
+            gates.push(CircuitGate::new(
+                GateType::Lookup,
+                Wire::for_row(row),
+                vec![],
+            ));
+            gates.connect_cell_pair((start, 0), (row, 0));
+        }
+        let cells = (0..num_selections)
+            .map(|i| {
+                let row = start + 1 + i / SELECTIONS_PER_ROW;
+                let col = 2 * (i % SELECTIONS_PER_ROW) + 1;
+                LookupSelectionCells {
+                    index: (row, col),
+                    output: (row, col + 1),
+                }
+            })
+            .collect();
+        (gates.len(), cells)
+    }
+}
+
+/// Extends the rows of a conditional selection gadget (see
+/// `CircuitGate::extend_cond_select`) to the full witness, and returns the
+/// selected value
+pub fn extend_cond_select_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    b: bool,
+    x: F,
+    y: F,
+) -> F {
+    extend_multiplexer_witness(witness, b as usize, &[y, x])
+}
+
+/// Extends the rows of a multiplexer gadget of `inputs.len()` inputs (see
+/// `CircuitGate::extend_multiplexer`) to the full witness, and returns the
+/// selected input
+///
+/// # Panics
+///
+/// Will panic if `index` is not less than the number of inputs.
+pub fn extend_multiplexer_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    index: usize,
+    inputs: &[F],
+) -> F {
+    assert!(index < inputs.len(), "the index must select an input");
+    let (generic, vars) = Generic::multiplexer(inputs.len());
+    let mut values = vec![None; generic.num_vars];
+    for (&var, &input) in vars.inputs.iter().zip(inputs) {
+        values[var] = Some(input);
+    }
+    for (j, &var) in vars.bits.iter().enumerate() {
+        values[var] = Some(F::from((index >> j) as u64 & 1));
+    }
+    let values = generic.witness(witness, values);
+    values[vars.output]
+}
+
+/// Creates the witness of a multiplexer gadget starting at row 0, and returns
+/// it together with the selected input
+pub fn create_multiplexer_witness<F: PrimeField>(
+    index: usize,
+    inputs: &[F],
+) -> ([Vec<F>; COLUMNS], F) {
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    let output = extend_multiplexer_witness(&mut witness, index, inputs);
+    (witness, output)
+}
+
+/// Extends the rows of a lookup multiplexer gadget (see
+/// `CircuitGate::extend_lookup_multiplexer`) to the full witness, and returns
+/// the selected values
+///
+/// # Panics
+///
+/// Will panic if an index is not less than the number of values.
+pub fn extend_lookup_multiplexer_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    table_id: i32,
+    values: &[F],
+    indices: &[usize],
+) -> Vec<F> {
+    let table_id: F = i32_to_field(table_id);
+    let mut push = |cells: &[F]| {
+        for (col, column) in witness.iter_mut().enumerate() {
+            column.push(cells.get(col).copied().unwrap_or_else(F::zero));
+        }
+    };
+    push(&[table_id]);
+    for indices in indices.chunks(SELECTIONS_PER_ROW) {
+        // the unused lookups of the last row select the first value
+        let mut cells = vec![table_id];
+        for i in 0..SELECTIONS_PER_ROW {
+            let index = indices.get(i).copied().unwrap_or(0);
+            cells.extend([F::from(index as u64), values[index]]);
+        }
+        push(&cells);
+    }
+    indices.iter().map(|&index| values[index]).collect()
+}
+
+/// A generic gate `cl * l + cr * r + co * o + cm * l * r + cc = 0` on the
+/// variables `l`, `r` and `o`, whose output `o` is solved from `l` and `r` by
+/// the witness generation
+struct Operation<F> {
+    coeffs: [F; GENERIC_COEFFS],
+    vars: [usize; 3],
+}
+
+/// The variables of a multiplexer
+struct MultiplexerVars {
+    index: usize,
+    bits: Vec<usize>,
+    inputs: Vec<usize>,
+    output: usize,
+}
+
+/// The variables and the operations of a gadget of generic gates, placed two
+/// per row
+#[derive(Default)]
+pub(crate) struct Generic<F> {
+    pub(crate) num_vars: usize,
+    ops: Vec<Operation<F>>,
+}
+
+impl<F: PrimeField> Generic<F> {
+    /// Returns a new variable
+    pub(crate) fn var(&mut self) -> usize {
+        self.num_vars += 1;
+        self.num_vars - 1
+    }
+
+    /// Constrains `b` to be boolean: `b * b - b = 0`
+    pub(crate) fn boolean(&mut self, b: usize) {
+        self.ops.push(Operation {
+            coeffs: [F::zero(), F::zero(), -F::one(), F::one(), F::zero()],
+            vars: [b, b, b],
+        });
+    }
+
+    /// Returns `cx * x + cy * y`
+    pub(crate) fn add(&mut self, x: usize, cx: F, y: usize, cy: F) -> usize {
+        let o = self.var();
+        self.ops.push(Operation {
+            coeffs: [cx, cy, -F::one(), F::zero(), F::zero()],
+            vars: [x, y, o],
+        });
+        o
+    }
+
+    /// Returns `x * y`
+    pub(crate) fn mul(&mut self, x: usize, y: usize) -> usize {
+        let o = self.var();
+        self.ops.push(Operation {
+            coeffs: [F::zero(), F::zero(), -F::one(), F::one(), F::zero()],
+            vars: [x, y, o],
+        });
+        o
+    }
+
+    /// Constrains `z` to be zero
+    pub(crate) fn zero(&mut self, z: usize) {
+        self.ops.push(Operation {
+            coeffs: [F::one(), F::zero(), F::zero(), F::zero(), F::zero()],
+            vars: [z, z, z],
+        });
+    }
+
+    /// Returns `cx * x + c`
+    pub(crate) fn affine(&mut self, x: usize, cx: F, c: F) -> usize {
+        let o = self.var();
+        self.ops.push(Operation {
+            coeffs: [cx, F::zero(), -F::one(), F::zero(), c],
+            vars: [x, x, o],
+        });
+        o
+    }
+
+    /// Returns `b * (x - y) + y`
+    fn select(&mut self, b: usize, x: usize, y: usize) -> usize {
+        let d = self.add(x, F::one(), y, -F::one());
+        let e = self.mul(b, d);
+        self.add(e, F::one(), y, F::one())
+    }
+
+    /// The operations of a multiplexer of `n` inputs
+    fn multiplexer(n: usize) -> (Self, MultiplexerVars) {
+        assert!(n >= 2, "a multiplexer needs at least 2 inputs");
+        let mut generic = Generic::default();
+        let inputs: Vec<usize> = (0..n).map(|_| generic.var()).collect();
+        let bits: Vec<usize> = (0..ceil_log2(n)).map(|_| generic.var()).collect();
+
+        // the index is the sum of its boolean bits
+        for &bit in &bits {
+            generic.boolean(bit);
+        }
+        let mut index = bits[0];
+        for (j, &bit) in bits.iter().enumerate().skip(1) {
+            index = generic.add(index, F::one(), bit, F::two_pow(j as u64));
+        }
+
+        // the tree of selections, an odd input is carried to the next level
+        let mut level = inputs.clone();
+        for &bit in &bits {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [y, x] => generic.select(bit, *x, *y),
+                    _ => pair[0],
+                })
+                .collect();
+        }
+        let vars = MultiplexerVars {
+            index,
+            bits,
+            inputs,
+            output: level[0],
+        };
+        (generic, vars)
+    }
+
+    /// Returns the number of rows of the operations
+    pub(crate) fn num_rows(&self) -> usize {
+        (self.ops.len() + 1) / 2
+    }
+
+    /// Returns the cells of the operations, two per row starting at `row`
+    fn cells(&self, row: usize) -> impl Iterator<Item = [(usize, usize); 3]> + '_ {
+        (0..self.ops.len()).map(move |i| {
+            let (row, col) = (row + i / 2, 3 * (i % 2));
+            [(row, col), (row, col + 1), (row, col + 2)]
+        })
+    }
+
+    /// Extends the gates with the operations, and returns the first cell of
+    /// each variable
+    fn layout(&self, gates: &mut Vec<CircuitGate<F>>) -> Vec<(usize, usize)> {
+        self.layout_partial(gates)
+            .into_iter()
+            .map(|cell| cell.expect("every variable is used by an operation"))
+            .collect()
+    }
+
+    /// Extends the gates with the operations, and returns the first cell of
+    /// each variable, or `None` for the variables used by no operation
+    pub(crate) fn layout_partial(
+        &self,
+        gates: &mut Vec<CircuitGate<F>>,
+    ) -> Vec<Option<(usize, usize)>> {
+        let start = gates.len();
+        for (row, ops) in self.ops.chunks(2).enumerate() {
+            let mut coeffs = [F::zero(); 2 * GENERIC_COEFFS];
+            for (i, op) in ops.iter().enumerate() {
+                coeffs[i * GENERIC_COEFFS..(i + 1) * GENERIC_COEFFS].copy_from_slice(&op.coeffs);
+            }
+            gates.push(CircuitGate::create_generic(
+                Wire::for_row(start + row),
+                coeffs,
+            ));
+        }
+
+        // the cells of a variable are wired together
+        let mut first: Vec<Option<(usize, usize)>> = vec![None; self.num_vars];
+        for (op, cells) in self.ops.iter().zip(self.cells(start)) {
+            for (&var, cell) in op.vars.iter().zip(cells) {
+                match first[var] {
+                    Some(first) => gates.connect_cell_pair(first, cell),
+                    None => first[var] = Some(cell),
+                }
+            }
+        }
+        first
+    }
+
+    /// Extends the witness with the operations, solving the variables which
+    /// are not given in `values`, and returns the values of the variables
+    pub(crate) fn witness(
+        &self,
+        witness: &mut [Vec<F>; COLUMNS],
+        mut values: Vec<Option<F>>,
+    ) -> Vec<F> {
+        for op in &self.ops {
+            let [l, r, o] = op.vars;
+            if values[o].is_none() {
+                let (l, r) = (values[l].unwrap(), values[r].unwrap());
+                let [cl, cr, co, cm, cc] = op.coeffs;
+                let inv = co.inverse().expect("the output coefficient is not zero");
+                values[o] = Some(-(cl * l + cr * r + cm * l * r + cc) * inv);
+            }
+        }
+        let values: Vec<F> = values
+            .into_iter()
+            .map(|value| value.expect("every variable is solved"))
+            .collect();
+
+        let start = witness[0].len();
+        for column in witness.iter_mut() {
+            column.resize(start + (self.ops.len() + 1) / 2, F::zero());
+        }
+        for (op, cells) in self.ops.iter().zip(self.cells(start)) {
+            for (&var, (row, col)) in op.vars.iter().zip(cells) {
+                witness[col][row] = values[var];
+            }
+        }
+        values
+    }
+}
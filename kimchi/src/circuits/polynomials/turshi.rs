@@ -100,6 +100,8 @@ use turshi::{
     word::{FlagBits, Offsets},
 };
 
+pub mod builtins;
+
 const NUM_FLAGS: usize = 16;
 pub const CIRCUIT_GATE_COUNT: usize = 4;
 
@@ -223,7 +225,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
         let constants = expr::Constants {
             endo_coefficient: cs.endo,
             mds: &G::sponge_params().mds,
-            zk_rows: 3,
+            zk_rows: cs.zk_rows,
         };
         let challenges = expr::Challenges {
             alpha: F::rand(rng),
@@ -256,6 +258,7 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
 
 pub mod witness {
     use super::*;
+    use o1_utils::transpose::transpose;
 
     /// Returns the witness of an execution of a Cairo program in `CircuitGate` format
     pub fn cairo_witness<F: Field>(prog: &CairoProgram<F>) -> [Vec<F>; COLUMNS] {
@@ -289,15 +292,7 @@ pub mod witness {
             }
         }
 
-        let mut witness: [Vec<F>; COLUMNS] = Default::default();
-        for col in 0..COLUMNS {
-            // initialize column with zeroes
-            witness[col].resize(table.len(), F::zero());
-            for (row, wit) in table.iter().enumerate() {
-                witness[col][row] = wit[col];
-            }
-        }
-        witness
+        transpose(&table)
     }
 
     fn claim_witness<F: Field>(prog: &CairoProgram<F>) -> [F; COLUMNS] {
@@ -0,0 +1,179 @@
+//! This module implements the circuits of the Cairo builtins.
+//!
+//! The builtins do not need new gate types, they reuse existing gadgets:
+//!
+//! - `range_check`: a value `x < 2^128` is split into `v0 = x mod 2^88` and
+//!   `v1 = x >> 88`, which are constrained by a multi-range-check together
+//!   with `v2 = 2^48 * v1`, so that `v1 < 2^40`. A final double generic gate
+//!   checks `x = v0 + 2^88 * v1` and `v2 = 2^48 * v1`.
+//! - `bitwise`: the AND gadget computes `x AND y` and `x XOR y`, and a final
+//!   generic gate computes `x OR y = (x AND y) + (x XOR y)`. The gadget is sound
+//!   for operands of up to 256 bits, the tighter bound of 251 bits is checked by
+//!   the runner.
+//! - `pedersen`: the hash is defined over the STARK curve, whose base field is
+//!   not the native field of the circuit, so its instances are only checked by
+//!   the runner and no gates are created for them.
+//!
+//! The value cells of the gadgets (column 2 of the last generic gate for the
+//! range check, columns 0 and 1 of the first XOR row and columns 0 to 2 of the
+//! last generic gate for the bitwise) must be wired to the Cairo memory by the caller.
+
+use crate::circuits::{
+    gate::{CircuitGate, Connect},
+    polynomial::COLUMNS,
+    polynomials::{
+        and, foreign_field_common::LIMB_BITS, generic::GenericGateSpec, range_check, xor,
+    },
+    wires::Wire,
+};
+use ark_ff::{PrimeField, SquareRootField};
+use o1_utils::{FieldHelpers, Two};
+use turshi::{
+    builtins::{BuiltinError, CairoBuiltin, BITWISE_BITS, RANGE_CHECK_BITS},
+    CairoMemory,
+};
+
+/// Number of bytes of the AND gadget used by the bitwise builtin
+const BITWISE_BYTES: usize = (BITWISE_BITS + 7) / 8;
+
+impl<F: PrimeField + SquareRootField> CircuitGate<F> {
+    /// Extends a circuit with a range check builtin instance
+    /// Includes:
+    /// - 4 multi-range-check gates for `v0`, `v1` and `v2 = 2^48 * v1`
+    /// - 1 double Generic gate to check `x = v0 + 2^88 * v1` and `v2 = 2^48 * v1`
+    /// Output:
+    /// - next_row  : next row after this gadget
+    /// Warning:
+    /// - don't forget to wire the value `x` in column 2 of the last row
+    pub fn extend_cairo_range_check(gates: &mut Vec<Self>) -> usize {
+        let rc_row = gates.len();
+        let mut generic_row = rc_row;
+        Self::extend_multi_range_check(gates, &mut generic_row);
+
+        // v0 + 2^88 * v1 = x
+        let value = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: Some(F::two_pow(LIMB_BITS as u64)),
+            output_coeff: None,
+        };
+        // 2^48 * v1 - v2 = 0
+        let shift = GenericGateSpec::Add {
+            left_coeff: Some(F::two_pow((2 * LIMB_BITS - RANGE_CHECK_BITS) as u64)),
+            right_coeff: Some(-F::one()),
+            output_coeff: Some(F::zero()),
+        };
+        gates.push(Self::create_generic_gadget(
+            Wire::for_row(generic_row),
+            value,
+            Some(shift),
+        ));
+
+        // connect v0, v1 and v2 of the multi-range-check to the generic gate
+        gates.connect_cell_pair((rc_row, 0), (generic_row, 0));
+        gates.connect_cell_pair((rc_row + 1, 0), (generic_row, 1));
+        gates.connect_cell_pair((rc_row + 1, 0), (generic_row, 3));
+        gates.connect_cell_pair((rc_row + 2, 0), (generic_row, 4));
+
+        gates.len()
+    }
+
+    /// Extends a circuit with a bitwise builtin instance
+    /// Includes:
+    /// - the AND gadget for 32 bytes, computing `x AND y` and `x XOR y`
+    /// - 1 Generic gate to compute `x OR y = (x AND y) + (x XOR y)`
+    /// Output:
+    /// - next_row  : next row after this gadget
+    /// Warning:
+    /// - don't forget to wire the inputs and the outputs to the Cairo memory
+    pub fn extend_cairo_bitwise(gates: &mut Vec<Self>) -> usize {
+        let xor_row = gates.len();
+        let or_row = Self::extend_and(gates, BITWISE_BYTES);
+        let and_row = or_row - 1;
+
+        // and + xor = or
+        let or = GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: None,
+            output_coeff: None,
+        };
+        gates.push(Self::create_generic_gadget(Wire::for_row(or_row), or, None));
+
+        // connect the AND output and the XOR output to the inputs of the generic gate
+        gates.connect_cell_pair((and_row, 5), (or_row, 0));
+        gates.connect_cell_pair((xor_row, 2), (or_row, 1));
+
+        gates.len()
+    }
+
+    /// Extends a circuit with the instances of the builtin segments of a Cairo memory,
+    /// in the order of the segments. The instances of the `pedersen` builtin are skipped.
+    /// Output:
+    /// - next_row  : next row after the builtins
+    pub fn extend_cairo_builtins(gates: &mut Vec<Self>, mem: &CairoMemory<F>) -> usize {
+        for (i, seg) in mem.builtins().iter().enumerate() {
+            for _ in 0..mem.builtin_size(i) {
+                match seg.builtin {
+                    CairoBuiltin::Pedersen { .. } => (),
+                    CairoBuiltin::RangeCheck => {
+                        Self::extend_cairo_range_check(gates);
+                    }
+                    CairoBuiltin::Bitwise => {
+                        Self::extend_cairo_bitwise(gates);
+                    }
+                }
+            }
+        }
+        gates.len()
+    }
+}
+
+/// Extends a witness with a range check builtin instance for value `x`
+pub fn extend_cairo_range_check_witness<F: PrimeField>(witness: &mut [Vec<F>; COLUMNS], x: F) {
+    let v0 = x.bits_to_field(0, LIMB_BITS).unwrap();
+    let v1 = x.bits_to_field(LIMB_BITS, F::size_in_bits()).unwrap();
+    let v2 = v1 * F::two_pow((2 * LIMB_BITS - RANGE_CHECK_BITS) as u64);
+    range_check::witness::extend_multi(witness, v0, v1, v2);
+
+    let mut row = [F::zero(); COLUMNS];
+    row[0..5].copy_from_slice(&[v0, v1, x, v1, v2]);
+    for col in 0..COLUMNS {
+        witness[col].push(row[col]);
+    }
+}
+
+/// Extends a witness with a bitwise builtin instance for operands `x` and `y`
+/// Panics if the operands are larger than 256 bits
+pub fn extend_cairo_bitwise_witness<F: PrimeField>(witness: &mut [Vec<F>; COLUMNS], x: F, y: F) {
+    let and_witness = and::create_and_witness(x, y, BITWISE_BYTES);
+    let and_row = xor::num_xors(BITWISE_BYTES * 8) + 1;
+    let xor = and_witness[4][and_row];
+    let and = and_witness[5][and_row];
+    for col in 0..COLUMNS {
+        witness[col].extend(and_witness[col].iter());
+    }
+
+    let mut row = [F::zero(); COLUMNS];
+    row[0..3].copy_from_slice(&[and, xor, and + xor]);
+    for col in 0..COLUMNS {
+        witness[col].push(row[col]);
+    }
+}
+
+/// Extends a witness with the instances of the builtin segments of a Cairo memory,
+/// following the layout of [CircuitGate::extend_cairo_builtins]
+/// Fails if the builtin segments of the memory are not valid
+pub fn extend_cairo_builtins_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    mem: &CairoMemory<F>,
+) -> Result<(), BuiltinError> {
+    for (i, seg) in mem.builtins().iter().enumerate() {
+        for cells in mem.builtin_instances(i)? {
+            match seg.builtin {
+                CairoBuiltin::Pedersen { .. } => (),
+                CairoBuiltin::RangeCheck => extend_cairo_range_check_witness(witness, cells[0]),
+                CairoBuiltin::Bitwise => extend_cairo_bitwise_witness(witness, cells[0], cells[1]),
+            }
+        }
+    }
+    Ok(())
+}
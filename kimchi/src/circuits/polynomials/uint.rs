@@ -0,0 +1,521 @@
+//! This module implements the arithmetic gadgets of fixed-width unsigned
+//! integers of 32 or 64 bits, together with their witness generation.
+//!
+//! The gadgets do not need new gate types: the operations are computed with
+//! generic gates (see [super::select]) and the results are constrained to the
+//! width with `RangeCheck0` gates, whose two most significant 12-bit limbs are
+//! wired to a zero cell so that they check 64 bits. A 32-bit value `x` is
+//! checked with two such gates, on `x` and on `x + 2^64 - 2^32`.
+//!
+//! The inputs of the gadgets are assumed to fit in the width, they must come
+//! from the outputs of other gadgets or be checked with
+//! [CircuitGate::extend_uint_range_check]. For the width `w`:
+//!
+//! - `add(a, b) = (s, c)` with `a + b = s + 2^w * c`, the carry `c` is
+//!   boolean and the sum `s` is range checked.
+//! - `sub(a, b) = (d, c)` with `a - b = d - 2^w * c`, the borrow `c` is
+//!   boolean and the difference `d` is range checked.
+//! - `mul(a, b) = (l, h)` with `a * b = l + 2^w * h`, the low half `l` and the
+//!   high half `h` are range checked, which makes the decomposition unique
+//!   since `a * b < 2^(2 * w)` is much smaller than the field.
+//! - `lt(a, b)` is the borrow of `sub(a, b)`, and `le(a, b) = 1 - lt(b, a)`.
+//!   The difference is still range checked, as it is what makes the borrow
+//!   correct.
+//!
+//! Each gadget takes `ceil(n / 2)` rows of generic gates for its `n` generic
+//! operations, followed by one `RangeCheck0` row per checked 64-bit value, or
+//! two per checked 32-bit value:
+//!
+//! | Gadget        | Generic rows (u64 / u32) | `RangeCheck0` rows (u64 / u32) |
+//! | ------------- | ------------------------ | ------------------------------ |
+//! | range check   | 1 / 1                    | 1 / 2                          |
+//! | `add`, `sub`  | 2 / 3                    | 1 / 2                          |
+//! | `mul`         | 2 / 3                    | 2 / 4                          |
+//! | `lt`          | 2 / 3                    | 1 / 2                          |
+//! | `le`          | 3 / 3                    | 1 / 2                          |
+
+use super::{range_check, select::Generic};
+use crate::circuits::{
+    gate::{CircuitGate, Connect, GateType},
+    polynomial::COLUMNS,
+    wires::Wire,
+};
+use ark_ff::PrimeField;
+use o1_utils::Two;
+
+/// The width of the integers of a gadget
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UintWidth {
+    /// 32-bit integers
+    U32,
+    /// 64-bit integers
+    U64,
+}
+
+impl UintWidth {
+    /// The number of bits of the integers
+    pub fn bits(self) -> u32 {
+        match self {
+            UintWidth::U32 => 32,
+            UintWidth::U64 => 64,
+        }
+    }
+
+    /// The largest integer
+    pub fn max(self) -> u64 {
+        u64::MAX >> (64 - self.bits())
+    }
+
+    fn modulus<F: PrimeField>(self) -> F {
+        F::two_pow(self.bits() as u64)
+    }
+
+    fn check(self, x: u64) {
+        assert!(
+            x <= self.max(),
+            "the value does not fit in {} bits",
+            self.bits()
+        );
+    }
+}
+
+/// The cells of an addition, a subtraction or a multiplication
+pub struct UintCells {
+    /// The left input
+    pub left: (usize, usize),
+    /// The right input
+    pub right: (usize, usize),
+    /// The result, modulo `2^w`
+    pub output: (usize, usize),
+    /// The carry of an addition, the borrow of a subtraction, or the high half
+    /// of a multiplication
+    pub carry: (usize, usize),
+}
+
+/// The cells of a comparison
+pub struct ComparisonCells {
+    /// The left input
+    pub left: (usize, usize),
+    /// The right input
+    pub right: (usize, usize),
+    /// The boolean result of the comparison
+    pub output: (usize, usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UintOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+}
+
+/// The variables of a gadget
+struct UintVars {
+    zero: usize,
+    left: usize,
+    right: usize,
+    /// The carry, borrow or high half given by the witness generation
+    hint: usize,
+    output: usize,
+    /// The values to range check
+    checked: Vec<usize>,
+}
+
+/// Range checks `x` to `width` bits, adding the values to check to `checked`
+fn check<F: PrimeField>(
+    generic: &mut Generic<F>,
+    width: UintWidth,
+    x: usize,
+    checked: &mut Vec<usize>,
+) {
+    checked.push(x);
+    if width == UintWidth::U32 {
+        let two_to_64: F = UintWidth::U64.modulus();
+        checked.push(generic.affine(x, F::one(), two_to_64 - width.modulus::<F>()));
+    }
+}
+
+/// The operations of the range check of one value
+fn range_check_gadget<F: PrimeField>(width: UintWidth) -> (Generic<F>, usize, usize, Vec<usize>) {
+    let mut generic = Generic::default();
+    let zero = generic.var();
+    let x = generic.var();
+    generic.zero(zero);
+    let mut checked = vec![];
+    check(&mut generic, width, x, &mut checked);
+    (generic, zero, x, checked)
+}
+
+/// The operations of the gadget of `op`
+fn uint_gadget<F: PrimeField>(width: UintWidth, op: UintOp) -> (Generic<F>, UintVars) {
+    let mut generic = Generic::default();
+    let zero = generic.var();
+    let left = generic.var();
+    let right = generic.var();
+    let hint = generic.var();
+    generic.zero(zero);
+    let modulus = width.modulus::<F>();
+    let mut checked = vec![];
+
+    let output = match op {
+        UintOp::Add => {
+            generic.boolean(hint);
+            let sum = generic.add(left, F::one(), right, F::one());
+            let output = generic.add(sum, F::one(), hint, -modulus);
+            check(&mut generic, width, output, &mut checked);
+            output
+        }
+        UintOp::Sub | UintOp::Lt | UintOp::Le => {
+            // le(a, b) = 1 - lt(b, a)
+            let (left, right) = if op == UintOp::Le {
+                (right, left)
+            } else {
+                (left, right)
+            };
+            generic.boolean(hint);
+            let difference = generic.add(left, F::one(), right, -F::one());
+            let difference = generic.add(difference, F::one(), hint, modulus);
+            check(&mut generic, width, difference, &mut checked);
+            match op {
+                UintOp::Sub => difference,
+                UintOp::Lt => hint,
+                _ => generic.affine(hint, -F::one(), F::one()),
+            }
+        }
+        UintOp::Mul => {
+            let product = generic.mul(left, right);
+            let output = generic.add(product, F::one(), hint, -modulus);
+            check(&mut generic, width, output, &mut checked);
+            check(&mut generic, width, hint, &mut checked);
+            output
+        }
+    };
+    let vars = UintVars {
+        zero,
+        left,
+        right,
+        hint,
+        output,
+        checked,
+    };
+    (generic, vars)
+}
+
+/// Extends the gates with the operations of `generic` followed by the range
+/// checks of `checked`, and returns the first cell of each variable
+fn layout<F: PrimeField>(
+    gates: &mut Vec<CircuitGate<F>>,
+    generic: &Generic<F>,
+    zero: usize,
+    checked: &[usize],
+) -> Vec<(usize, usize)> {
+    let mut cells = generic.layout_partial(gates);
+    let zero = cells[zero].expect("the zero variable is constrained");
+    for &var in checked {
+        let row = gates.len();
+        gates.push(CircuitGate::new(
+            GateType::RangeCheck0,
+            Wire::for_row(row),
+            vec![F::zero()],
+        ));
+        // the two most significant 12-bit limbs are zero
+        gates.connect_cell_pair(zero, (row, 1));
+        gates.connect_cell_pair(zero, (row, 2));
+        match cells[var] {
+            Some(cell) => gates.connect_cell_pair(cell, (row, 0)),
+            None => cells[var] = Some((row, 0)),
+        }
+    }
+    cells
+        .into_iter()
+        .map(|cell| cell.expect("every variable is used by a gate"))
+        .collect()
+}
+
+/// Extends the witness with the operations of `generic` followed by the range
+/// checks of `checked`, and returns the values of the variables
+fn extend_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    generic: &Generic<F>,
+    checked: &[usize],
+    values: Vec<Option<F>>,
+) -> Vec<F> {
+    let values = generic.witness(witness, values);
+    for &var in checked {
+        range_check::witness::extend(witness, values[var]);
+    }
+    values
+}
+
+impl<F: PrimeField> CircuitGate<F> {
+    /// Extends a range check of a value to `width` bits, see the module
+    /// documentation
+    /// Includes:
+    /// - 1 double Generic gate
+    /// - 1 RangeCheck0 gate for 64 bits, 2 for 32 bits
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the value
+    /// Outputs tuple (next_row, cell) where
+    /// - next_row  : next row after this gadget
+    /// - cell      : the cell of the value
+    /// Warning:
+    /// - don't forget to wire the value
+    pub fn extend_uint_range_check(
+        gates: &mut Vec<Self>,
+        width: UintWidth,
+    ) -> (usize, (usize, usize)) {
+        let (generic, zero, x, checked) = range_check_gadget(width);
+        let cells = layout(gates, &generic, zero, &checked);
+        (gates.len(), cells[x])
+    }
+
+    /// Extends an addition of `width`-bit integers with carry, see the module
+    /// documentation
+    /// Includes:
+    /// - the double Generic gates of the addition
+    /// - the RangeCheck0 gates of the sum
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the integers
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs, of the sum and of the carry
+    /// Warning:
+    /// - don't forget to wire the inputs, which must fit in `width` bits
+    pub fn extend_uint_add(gates: &mut Vec<Self>, width: UintWidth) -> (usize, UintCells) {
+        Self::extend_uint(gates, width, UintOp::Add)
+    }
+
+    /// Extends a subtraction of `width`-bit integers with borrow, see the
+    /// module documentation
+    /// Includes:
+    /// - the double Generic gates of the subtraction
+    /// - the RangeCheck0 gates of the difference
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the integers
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs, of the difference and of the
+    ///   borrow
+    /// Warning:
+    /// - don't forget to wire the inputs, which must fit in `width` bits
+    pub fn extend_uint_sub(gates: &mut Vec<Self>, width: UintWidth) -> (usize, UintCells) {
+        Self::extend_uint(gates, width, UintOp::Sub)
+    }
+
+    /// Extends a multiplication of `width`-bit integers, see the module
+    /// documentation
+    /// Includes:
+    /// - the double Generic gates of the multiplication
+    /// - the RangeCheck0 gates of the low and high halves of the product
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the integers
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs, of the low half and of the high
+    ///   half of the product
+    /// Warning:
+    /// - don't forget to wire the inputs, which must fit in `width` bits
+    pub fn extend_uint_mul(gates: &mut Vec<Self>, width: UintWidth) -> (usize, UintCells) {
+        Self::extend_uint(gates, width, UintOp::Mul)
+    }
+
+    /// Extends a comparison `left < right` of `width`-bit integers, see the
+    /// module documentation
+    /// Includes:
+    /// - the double Generic gates of the subtraction
+    /// - the RangeCheck0 gates of the difference
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the integers
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs and of the boolean result
+    /// Warning:
+    /// - don't forget to wire the inputs, which must fit in `width` bits
+    pub fn extend_uint_lt(gates: &mut Vec<Self>, width: UintWidth) -> (usize, ComparisonCells) {
+        Self::extend_uint_comparison(gates, width, UintOp::Lt)
+    }
+
+    /// Extends a comparison `left <= right` of `width`-bit integers, see the
+    /// module documentation
+    /// Includes:
+    /// - the double Generic gates of the subtraction
+    /// - the RangeCheck0 gates of the difference
+    /// Input:
+    /// - gates     : vector of circuit gates comprising the full circuit
+    /// - width     : width of the integers
+    /// Outputs tuple (next_row, cells) where
+    /// - next_row  : next row after this gadget
+    /// - cells     : the cells of the inputs and of the boolean result
+    /// Warning:
+    /// - don't forget to wire the inputs, which must fit in `width` bits
+    pub fn extend_uint_le(gates: &mut Vec<Self>, width: UintWidth) -> (usize, ComparisonCells) {
+        Self::extend_uint_comparison(gates, width, UintOp::Le)
+    }
+
+    fn extend_uint(gates: &mut Vec<Self>, width: UintWidth, op: UintOp) -> (usize, UintCells) {
+        let (generic, vars) = uint_gadget(width, op);
+        let cells = layout(gates, &generic, vars.zero, &vars.checked);
+        let uint = UintCells {
+            left: cells[vars.left],
+            right: cells[vars.right],
+            output: cells[vars.output],
+            carry: cells[vars.hint],
+        };
+        (gates.len(), uint)
+    }
+
+    fn extend_uint_comparison(
+        gates: &mut Vec<Self>,
+        width: UintWidth,
+        op: UintOp,
+    ) -> (usize, ComparisonCells) {
+        let (generic, vars) = uint_gadget(width, op);
+        let cells = layout(gates, &generic, vars.zero, &vars.checked);
+        let comparison = ComparisonCells {
+            left: cells[vars.left],
+            right: cells[vars.right],
+            output: cells[vars.output],
+        };
+        (gates.len(), comparison)
+    }
+}
+
+/// Extends the rows of a range check of `x` to `width` bits (see
+/// `CircuitGate::extend_uint_range_check`) to the full witness
+///
+/// # Panics
+///
+/// Will panic if `x` does not fit in `width` bits.
+pub fn extend_uint_range_check_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    x: u64,
+) {
+    width.check(x);
+    let (generic, zero, var, checked) = range_check_gadget(width);
+    let mut values = vec![None; generic.num_vars];
+    values[zero] = Some(F::zero());
+    values[var] = Some(F::from(x));
+    extend_witness(witness, &generic, &checked, values);
+}
+
+/// Extends the rows of an addition (see `CircuitGate::extend_uint_add`) to the
+/// full witness, and returns the sum modulo `2^w` with the carry
+///
+/// # Panics
+///
+/// Will panic if an input does not fit in `width` bits.
+pub fn extend_uint_add_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    left: u64,
+    right: u64,
+) -> (u64, bool) {
+    let (sum, carry) = extend_uint_witness(witness, width, UintOp::Add, left, right);
+    (sum, carry == 1)
+}
+
+/// Extends the rows of a subtraction (see `CircuitGate::extend_uint_sub`) to
+/// the full witness, and returns the difference modulo `2^w` with the borrow
+///
+/// # Panics
+///
+/// Will panic if an input does not fit in `width` bits.
+pub fn extend_uint_sub_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    left: u64,
+    right: u64,
+) -> (u64, bool) {
+    let (difference, borrow) = extend_uint_witness(witness, width, UintOp::Sub, left, right);
+    (difference, borrow == 1)
+}
+
+/// Extends the rows of a multiplication (see `CircuitGate::extend_uint_mul`)
+/// to the full witness, and returns the low and the high halves of the product
+///
+/// # Panics
+///
+/// Will panic if an input does not fit in `width` bits.
+pub fn extend_uint_mul_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    left: u64,
+    right: u64,
+) -> (u64, u64) {
+    extend_uint_witness(witness, width, UintOp::Mul, left, right)
+}
+
+/// Extends the rows of a comparison `left < right` (see
+/// `CircuitGate::extend_uint_lt`) to the full witness, and returns its result
+///
+/// # Panics
+///
+/// Will panic if an input does not fit in `width` bits.
+pub fn extend_uint_lt_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    left: u64,
+    right: u64,
+) -> bool {
+    extend_uint_witness(witness, width, UintOp::Lt, left, right).0 == 1
+}
+
+/// Extends the rows of a comparison `left <= right` (see
+/// `CircuitGate::extend_uint_le`) to the full witness, and returns its result
+///
+/// # Panics
+///
+/// Will panic if an input does not fit in `width` bits.
+pub fn extend_uint_le_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    left: u64,
+    right: u64,
+) -> bool {
+    extend_uint_witness(witness, width, UintOp::Le, left, right).0 == 1
+}
+
+/// Extends the rows of the gadget of `op` to the full witness, and returns its
+/// output with its carry, borrow or high half
+fn extend_uint_witness<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    width: UintWidth,
+    op: UintOp,
+    left: u64,
+    right: u64,
+) -> (u64, u64) {
+    width.check(left);
+    width.check(right);
+    let bits = width.bits();
+    let modulus = 1u128 << bits;
+    let (a, b) = (left as u128, right as u128);
+    let (output, hint) = match op {
+        UintOp::Add => (((a + b) % modulus) as u64, ((a + b) >> bits) as u64),
+        UintOp::Sub => (((a + modulus - b) % modulus) as u64, (a < b) as u64),
+        UintOp::Mul => (((a * b) % modulus) as u64, ((a * b) >> bits) as u64),
+        UintOp::Lt => ((a < b) as u64, (a < b) as u64),
+        UintOp::Le => ((a <= b) as u64, (b < a) as u64),
+    };
+
+    let (generic, vars) = uint_gadget(width, op);
+    let mut values = vec![None; generic.num_vars];
+    values[vars.zero] = Some(F::zero());
+    values[vars.left] = Some(F::from(left));
+    values[vars.right] = Some(F::from(right));
+    values[vars.hint] = Some(F::from(hint));
+    let values = extend_witness(witness, &generic, &vars.checked, values);
+    debug_assert_eq!(values[vars.output], F::from(output));
+
+    match op {
+        UintOp::Lt | UintOp::Le => (output, 0),
+        _ => (output, hint),
+    }
+}
@@ -1,4 +1,5 @@
-//! This module includes the definition of the XOR gadget for 64, 32, and 16 bits,
+//! This module includes the definition of the XOR gadget for 64, 32, and 16 bits
+//! (or any other length with the exact length variants),
 //! the definition of the constraints of the `Xor16` circuit gate,
 //! and the code for witness generation for the XOR gadget.
 use crate::{
@@ -8,7 +9,7 @@ use crate::{
         gate::{CircuitGate, Connect, GateType},
         lookup::{
             self,
-            tables::{GateLookupTable, LookupTable},
+            tables::{xor::XorTableBits, GateLookupTable, LookupTable},
         },
         polynomial::COLUMNS,
         wires::Wire,
@@ -34,8 +35,25 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
     /// Output:
     /// - new row index
     pub fn extend_xor_gadget(gates: &mut Vec<Self>, bits: usize) -> usize {
+        Self::extend_xor_gadget_with(gates, bits, XorTableBits::Four)
+    }
+
+    /// Extends a XOR gadget for `bits` length to a circuit, like `extend_xor_gadget`,
+    /// with lookups in the XOR table of words of `table_bits` bits, so that each Xor16
+    /// gate handles `table_bits.row_bits()` bits of the words
+    /// Input:
+    /// - gates      : vector of circuit gates
+    /// - bits       : length of the XOR gadget
+    /// - table_bits : size of the words of the XOR table of the constraint system
+    /// Output:
+    /// - new row index
+    pub fn extend_xor_gadget_with(
+        gates: &mut Vec<Self>,
+        bits: usize,
+        table_bits: XorTableBits,
+    ) -> usize {
         let new_row = gates.len();
-        let (_, mut xor_gates) = Self::create_xor_gadget(new_row, bits);
+        let (_, mut xor_gates) = Self::create_xor_gadget_with(new_row, bits, table_bits);
         // extend the whole circuit with the xor gadget
         gates.append(&mut xor_gates);
 
@@ -60,12 +78,29 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
     /// Warning:
     /// - don't forget to check that the final row is all zeros as in `extend_xor_gadget`
     pub fn create_xor_gadget(new_row: usize, bits: usize) -> (usize, Vec<Self>) {
-        let num_xors = num_xors(bits);
+        Self::create_xor_gadget_with(new_row, bits, XorTableBits::Four)
+    }
+
+    /// Creates a XOR gadget for `bits` length, like `create_xor_gadget`, with lookups
+    /// in the XOR table of words of `table_bits` bits
+    /// Input:
+    /// - new_row    : row to start the XOR gadget
+    /// - bits       : number of bits in the XOR
+    /// - table_bits : size of the words of the XOR table of the constraint system
+    /// Outputs tuple (next_row, circuit_gates) where
+    /// - next_row  : next row after this gate
+    /// - gates     : vector of circuit gates comprising this gate
+    pub fn create_xor_gadget_with(
+        new_row: usize,
+        bits: usize,
+        table_bits: XorTableBits,
+    ) -> (usize, Vec<Self>) {
+        let num_xors = num_xors_with(bits, table_bits);
         let mut xor_gates = (0..num_xors)
             .map(|i| CircuitGate {
                 typ: GateType::Xor16,
                 wires: Wire::for_row(new_row + i),
-                coeffs: vec![],
+                coeffs: table_bits.coeffs(),
             })
             .collect::<Vec<_>>();
         let zero_row = new_row + num_xors;
@@ -77,6 +112,68 @@ impl<F: PrimeField + SquareRootField> CircuitGate<F> {
 
         (new_row + xor_gates.len(), xor_gates)
     }
+
+    /// Extends a XOR gadget for words of exactly `bits` length to a circuit, where
+    /// `bits` can be any multiple of [XOR_TABLE_BITS] (e.g. 8, 16, 32, 64 or 128).
+    /// Contrary to `extend_xor_gadget`, which only constrains the words to fit in the
+    /// `16 * num_xors` bits of its rows, this checks that the most significant nybbles of
+    /// the last `Xor16` row are zero when `bits` is not a multiple of 16.
+    /// Includes:
+    /// - num_xors Xor16 gates and 1 Generic gate, as in `extend_xor_gadget`
+    /// - if `bits` is not a multiple of 16, 1 more Xor16 gate of the most significant
+    ///   16 bits with swapped inputs (so that the nybbles of `in2` are copiable as well)
+    ///   and 1 more Generic gate with zeros
+    /// Input:
+    /// - gates     : vector of circuit gates
+    /// - bits      : length of the words
+    /// Output:
+    /// - new row index
+    /// Panics if `bits` is not a positive multiple of [XOR_TABLE_BITS]
+    pub fn extend_xor_gadget_exact_length(gates: &mut Vec<Self>, bits: usize) -> usize {
+        check_exact_length(bits);
+        let last_row = gates.len() + num_xors(bits) - 1;
+        Self::extend_xor_gadget(gates, bits);
+        let padding = padding_nybbles(bits);
+        if padding > 0 {
+            let zero_cell = (last_row + 1, 0);
+            let swap_row = gates.len();
+            Self::extend_xor_gadget(gates, 16);
+            gates.connect_cell_pair((last_row, 0), (swap_row, 1));
+            gates.connect_cell_pair((last_row, 1), (swap_row, 0));
+            gates.connect_cell_pair((last_row, 2), (swap_row, 2));
+            // the copiable nybbles are those of the first input
+            for row in [last_row, swap_row] {
+                for nybble in (4 - padding)..4 {
+                    gates.connect_cell_pair(zero_cell, (row, 3 + nybble));
+                }
+            }
+        }
+        gates.len()
+    }
+}
+
+/// Number of bits of the words of the default XOR lookup table, which is the one
+/// of the gadgets of exact length (see [XorTableBits])
+pub const XOR_TABLE_BITS: usize = 4;
+
+// Panics if the gadgets of exact length do not support words of `bits` length
+pub(crate) fn check_exact_length(bits: usize) {
+    assert!(
+        bits > 0 && bits % XOR_TABLE_BITS == 0,
+        "the length of the words must be a positive multiple of {XOR_TABLE_BITS} bits: {bits}"
+    );
+}
+
+// Number of most significant nybbles of the last Xor16 row that are not part of words of
+// `bits` length
+pub(crate) fn padding_nybbles(bits: usize) -> usize {
+    (16 * num_xors(bits) - bits) / XOR_TABLE_BITS
+}
+
+/// Returns the number of rows of the XOR gadget of exact length for words of `bits` length
+pub fn num_rows_exact_length(bits: usize) -> usize {
+    let swap_rows = if padding_nybbles(bits) > 0 { 2 } else { 0 };
+    num_xors(bits) + 1 + swap_rows
 }
 
 /// Get the xor lookup table
@@ -89,6 +186,10 @@ pub fn lookup_table<F: PrimeField>() -> LookupTable<F> {
 //~ * This circuit gate is used to constrain that `in1` xored with `in2` equals `out`
 //~ * The length of `in1`, `in2` and `out` must be the same and a multiple of 16bits.
 //~ * This gate operates on the `Curr` and `Next` rows.
+//~ * The coefficients of the gate are the shifts $2^4$, $2^8$, $2^{12}$ and $2^{16}$ of
+//~   the nybbles. With the 8-bit XOR table of the constraint system, they are the
+//~   shifts $2^8$, $2^{16}$, $2^{24}$ and $2^{32}$ of bytes, and each row handles 32 bits
+//~   of the words (with the same layout, where nybbles are replaced by bytes).
 //~
 //~ It uses three different types of constraints:
 //~
@@ -130,8 +231,8 @@ pub fn lookup_table<F: PrimeField>() -> LookupTable<F> {
 //~ |   4 | `Generic`     | Zero values, can be reused as generic gate |
 //~
 //~ ```admonish info
-//~ We could halve the number of rows of the 64-bit XOR gadget by having lookups
-//~ for 8 bits at a time, but for now we will use the 4-bit XOR table that we have.
+//~ The number of rows of the 64-bit XOR gadget is halved by having lookups for 8 bits
+//~ at a time, with the 8-bit XOR table of $2^{16}$ entries instead of the 4-bit one.
 //~ Rough computations show that if we run 8 or more Keccaks in one circuit we should
 //~ use the 8-bit XOR table.
 //~ ```
@@ -148,20 +249,22 @@ where
 
     // Constraints for Xor16
     //   * Operates on Curr and Next rows
-    //   * Constrain the decomposition of `in1`, `in2` and `out` of multiples of 16 bits
-    //   * The actual XOR is performed thanks to the plookups of 4-bit XORs.
+    //   * Constrain the decomposition of `in1`, `in2` and `out` in words of `b` bits, the
+    //     size of the words of the XOR table, given by the shifts in the coefficients
+    //   * The actual XOR is performed thanks to the plookups of b-bit XORs.
     fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
-        let two = T::from(2u64);
-        // in1 = in1_0 + in1_1 * 2^4 + in1_2 * 2^8 + in1_3 * 2^12 + next_in1 * 2^16
-        // in2 = in2_0 + in2_1 * 2^4 + in2_2 * 2^8 + in2_3 * 2^12 + next_in2 * 2^16
-        // out = out_0 + out_1 * 2^4 + out_2 * 2^8 + out_3 * 2^12 + next_out * 2^16
+        // the shifts 2^b, 2^(2b), 2^(3b) and 2^(4b)
+        let shifts = env.coeff_chunk(0, 4);
+        // in1 = in1_0 + in1_1 * 2^b + in1_2 * 2^(2b) + in1_3 * 2^(3b) + next_in1 * 2^(4b)
+        // in2 = in2_0 + in2_1 * 2^b + in2_2 * 2^(2b) + in2_3 * 2^(3b) + next_in2 * 2^(4b)
+        // out = out_0 + out_1 * 2^b + out_2 * 2^(2b) + out_3 * 2^(3b) + next_out * 2^(4b)
         (0..3)
             .map(|i| {
                 env.witness_curr(3 + 4 * i)
-                    + env.witness_curr(4 + 4 * i) * two.clone().pow(4)
-                    + env.witness_curr(5 + 4 * i) * two.clone().pow(8)
-                    + env.witness_curr(6 + 4 * i) * two.clone().pow(12)
-                    + two.clone().pow(16) * env.witness_next(i)
+                    + env.witness_curr(4 + 4 * i) * shifts[0].clone()
+                    + env.witness_curr(5 + 4 * i) * shifts[1].clone()
+                    + env.witness_curr(6 + 4 * i) * shifts[2].clone()
+                    + shifts[3].clone() * env.witness_next(i)
                     - env.witness_curr(i)
             })
             .collect::<Vec<T>>()
@@ -169,15 +272,44 @@ where
 }
 
 // Witness layout
-fn layout<F: PrimeField>(curr_row: usize, bits: usize) -> Vec<Vec<Box<dyn WitnessCell<F>>>> {
-    let num_xor = num_xors(bits);
+fn layout<F: PrimeField>(
+    curr_row: usize,
+    bits: usize,
+    table_bits: XorTableBits,
+) -> Vec<Vec<Box<dyn WitnessCell<F>>>> {
+    let num_xor = num_xors_with(bits, table_bits);
     let mut layout = (0..num_xor)
-        .map(|i| xor_row(i, curr_row + i))
+        .map(|i| match table_bits {
+            XorTableBits::Four => xor_row(i, curr_row + i),
+            XorTableBits::Eight => xor_row_bytes(i, curr_row + i),
+        })
         .collect::<Vec<_>>();
     layout.push(zero_row());
     layout
 }
 
+// A row of the XOR of 8-bit words, handling 32 bits of the words
+fn xor_row_bytes<F: PrimeField>(index: usize, curr_row: usize) -> Vec<Box<dyn WitnessCell<F>>> {
+    let start = index * 32;
+    vec![
+        VariableBitsCell::create("in1", start, None),
+        VariableBitsCell::create("in2", start, None),
+        VariableBitsCell::create("out", start, None),
+        CopyBitsCell::create(curr_row, 0, 0, 8), // First byte of in1
+        CopyBitsCell::create(curr_row, 0, 8, 16), // Second byte of in1
+        CopyBitsCell::create(curr_row, 0, 16, 24), // Third byte of in1
+        CopyBitsCell::create(curr_row, 0, 24, 32), // Fourth byte of in1
+        CopyBitsCell::create(curr_row, 1, 0, 8), // First byte of in2
+        CopyBitsCell::create(curr_row, 1, 8, 16), // Second byte of in2
+        CopyBitsCell::create(curr_row, 1, 16, 24), // Third byte of in2
+        CopyBitsCell::create(curr_row, 1, 24, 32), // Fourth byte of in2
+        CopyBitsCell::create(curr_row, 2, 0, 8), // First byte of out
+        CopyBitsCell::create(curr_row, 2, 8, 16), // Second byte of out
+        CopyBitsCell::create(curr_row, 2, 16, 24), // Third byte of out
+        CopyBitsCell::create(curr_row, 2, 24, 32), // Fourth byte of out
+    ]
+}
+
 fn xor_row<F: PrimeField>(nybble: usize, curr_row: usize) -> Vec<Box<dyn WitnessCell<F>>> {
     let start = nybble * 16;
     vec![
@@ -223,11 +355,13 @@ pub(crate) fn init_xor<F: PrimeField>(
     witness: &mut [Vec<F>; COLUMNS],
     curr_row: usize,
     bits: usize,
+    table_bits: XorTableBits,
     words: (F, F, F),
 ) {
-    let xor_rows = layout(curr_row, bits);
+    let xor_rows = layout(curr_row, bits, table_bits);
 
-    witness::init(
+    witness::init_traced(
+        GateType::Xor16,
         witness,
         curr_row,
         &xor_rows,
@@ -243,16 +377,62 @@ pub fn extend_xor_witness<F: PrimeField>(
     input2: F,
     bits: usize,
 ) {
-    let xor_witness = create_xor_witness(input1, input2, bits);
+    extend_xor_witness_with(witness, input1, input2, bits, XorTableBits::Four)
+}
+
+/// Extends the Xor rows of a gadget with lookups in the XOR table of words of
+/// `table_bits` bits (see `CircuitGate::extend_xor_gadget_with`) to the full witness
+/// Panics if the words are larger than the desired bits
+pub fn extend_xor_witness_with<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    input1: F,
+    input2: F,
+    bits: usize,
+    table_bits: XorTableBits,
+) {
+    let xor_witness = create_xor_witness_with(input1, input2, bits, table_bits);
     for col in 0..COLUMNS {
         witness[col].extend(xor_witness[col].iter());
     }
 }
 
+/// Extends the rows of a XOR gadget of exact length (see
+/// `CircuitGate::extend_xor_gadget_exact_length`) to the full witness
+/// Panics if the words are larger than `bits`, or if `bits` is not a positive multiple
+/// of [XOR_TABLE_BITS]
+pub fn extend_xor_witness_exact_length<F: PrimeField>(
+    witness: &mut [Vec<F>; COLUMNS],
+    input1: F,
+    input2: F,
+    bits: usize,
+) {
+    check_exact_length(bits);
+    extend_xor_witness(witness, input1, input2, bits);
+    if padding_nybbles(bits) > 0 {
+        // the most significant 16 bits, with swapped inputs
+        let shift = 16 * (num_xors(bits) - 1);
+        let top = |word: F| F::from_biguint(&(word.to_biguint() >> shift)).unwrap();
+        extend_xor_witness(witness, top(input2), top(input1), 16);
+    }
+}
+
 /// Create a Xor for up to the native length starting at row 0
 /// Input: first input and second input, bits length, current row
 /// Panics if the desired bits is smaller than the inputs length
 pub fn create_xor_witness<F: PrimeField>(input1: F, input2: F, bits: usize) -> [Vec<F>; COLUMNS] {
+    create_xor_witness_with(input1, input2, bits, XorTableBits::Four)
+}
+
+/// Create a Xor for up to the native length starting at row 0, with lookups in the
+/// XOR table of words of `table_bits` bits
+/// Input: first input and second input, bits length, size of the words of the table
+/// Panics if the desired bits is smaller than the inputs length
+pub fn create_xor_witness_with<F: PrimeField>(
+    input1: F,
+    input2: F,
+    bits: usize,
+    table_bits: XorTableBits,
+) -> [Vec<F>; COLUMNS] {
     let input1_big = input1.to_biguint();
     let input2_big = input2.to_biguint();
     if bits < input1_big.bitlen() || bits < input2_big.bitlen() {
@@ -261,12 +441,13 @@ pub fn create_xor_witness<F: PrimeField>(input1: F, input2: F, bits: usize) -> [
     let output = BigUint::bitwise_xor(&input1_big, &input2_big);
 
     let mut xor_witness: [Vec<F>; COLUMNS] =
-        array::from_fn(|_| vec![F::zero(); 1 + num_xors(bits)]);
+        array::from_fn(|_| vec![F::zero(); 1 + num_xors_with(bits, table_bits)]);
 
     init_xor(
         &mut xor_witness,
         0,
         bits,
+        table_bits,
         (input1, input2, output.to_field().unwrap()),
     );
 
@@ -277,3 +458,10 @@ pub fn create_xor_witness<F: PrimeField>(input1: F, input2: F, bits: usize) -> [
 pub fn num_xors(bits: usize) -> usize {
     (bits as f64 / 16.0).ceil() as usize
 }
+
+/// Returns the number of XOR rows needed for inputs of usize bits, with lookups in the
+/// XOR table of words of `table_bits` bits
+pub fn num_xors_with(bits: usize, table_bits: XorTableBits) -> usize {
+    let row_bits = table_bits.row_bits();
+    (bits + row_bits - 1) / row_bits
+}
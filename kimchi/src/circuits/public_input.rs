@@ -0,0 +1,464 @@
+//! This module implements named layouts of the public inputs of a circuit.
+//!
+//! The public inputs of a circuit are a vector of field elements, stored in
+//! the first rows of the first witness column, whose positions are a
+//! convention between the circuit, the prover and the verifier. A
+//! [PublicInputLayout] names the fields of this vector, with their offsets and
+//! encodings, so that the public input vector can be built and read by name:
+//!
+//! ```
+//! use kimchi::circuits::public_input::{PublicInputEncoding, PublicInputLayout, PublicInputValue};
+//! use mina_curves::pasta::Fp;
+//!
+//! let layout = PublicInputLayout::new()
+//!     .field("root", PublicInputEncoding::Field)
+//!     .array("amounts", PublicInputEncoding::U64, 2)
+//!     .field("is_final", PublicInputEncoding::Bool);
+//! let public: Vec<Fp> = layout
+//!     .encoder()
+//!     .set("root", PublicInputValue::Field(Fp::from(42u8)))
+//!     .set_array("amounts", &[PublicInputValue::U64(3), PublicInputValue::U64(4)])
+//!     .set("is_final", PublicInputValue::Bool(true))
+//!     .encode()
+//!     .unwrap();
+//! assert_eq!(layout.offset("is_final"), Some(3));
+//! assert_eq!(layout.get(&public, "amounts").unwrap()[1], PublicInputValue::U64(4));
+//! ```
+//!
+//! Foreign field elements span [LIMB_COUNT] public inputs, one per limb of
+//! [LIMB_BITS] bits in little-endian order, as in the foreign field gates.
+//!
+//! A layout can be attached to a constraint system with
+//! [crate::circuits::constraints::Builder::public_input_layout], in which case
+//! it is also part of the verifier index, and the public inputs are checked
+//! against it when creating and verifying proofs.
+
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        polynomials::foreign_field_common::{
+            KimchiForeignElement, BINARY_MODULUS_EXP, LIMB_BITS, LIMB_COUNT,
+        },
+    },
+    curve::KimchiCurve,
+    verifier_index::VerifierIndex,
+};
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::FieldHelpers;
+use poly_commitment::OpenProof;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Errors that can arise when encoding or decoding public inputs
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PublicInputError {
+    #[error("the circuit has no public input layout")]
+    NoLayout,
+    #[error("the public input field {0} does not exist")]
+    UnknownField(String),
+    #[error("the public input field {0} is set twice")]
+    DuplicateField(String),
+    #[error("the public input field {0} is not set")]
+    MissingField(String),
+    #[error("the public input field {name} has {expected} values, got {found}")]
+    FieldLength {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("expected {expected} public inputs, got {found}")]
+    Length { expected: usize, found: usize },
+    #[error("the value of the public input field {0} does not match its encoding")]
+    InvalidValue(String),
+}
+
+/// The encoding of a public input value as a field element
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PublicInputEncoding {
+    /// A field element
+    Field,
+    /// A boolean, encoded as zero or one
+    Bool,
+    /// An unsigned 64-bit integer
+    U64,
+    /// A foreign field element, encoded as [LIMB_COUNT] limbs of [LIMB_BITS]
+    /// bits
+    ForeignField,
+}
+
+impl PublicInputEncoding {
+    /// The number of public inputs used by a value of this encoding
+    pub fn size(&self) -> usize {
+        match self {
+            PublicInputEncoding::Field | PublicInputEncoding::Bool | PublicInputEncoding::U64 => 1,
+            PublicInputEncoding::ForeignField => LIMB_COUNT,
+        }
+    }
+}
+
+/// A public input value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicInputValue<F> {
+    /// A field element
+    Field(F),
+    /// A boolean
+    Bool(bool),
+    /// An unsigned 64-bit integer
+    U64(u64),
+    /// A foreign field element, of at most [BINARY_MODULUS_EXP] bits
+    ForeignField(BigUint),
+}
+
+impl<F: PrimeField> PublicInputValue<F> {
+    fn encoding(&self) -> PublicInputEncoding {
+        match self {
+            PublicInputValue::Field(_) => PublicInputEncoding::Field,
+            PublicInputValue::Bool(_) => PublicInputEncoding::Bool,
+            PublicInputValue::U64(_) => PublicInputEncoding::U64,
+            PublicInputValue::ForeignField(_) => PublicInputEncoding::ForeignField,
+        }
+    }
+
+    /// Whether the value can be encoded
+    fn is_valid(&self) -> bool {
+        match self {
+            PublicInputValue::ForeignField(x) => x.bits() <= BINARY_MODULUS_EXP as u64,
+            _ => true,
+        }
+    }
+
+    fn to_fields(&self) -> Vec<F> {
+        match self {
+            PublicInputValue::Field(x) => vec![*x],
+            PublicInputValue::Bool(b) => vec![F::from(*b)],
+            PublicInputValue::U64(n) => vec![F::from(*n)],
+            PublicInputValue::ForeignField(x) => KimchiForeignElement::<F>::from_biguint(x.clone())
+                .limbs
+                .to_vec(),
+        }
+    }
+
+    fn from_fields(xs: &[F], encoding: PublicInputEncoding) -> Option<Self> {
+        let x = xs[0];
+        match encoding {
+            PublicInputEncoding::Field => Some(PublicInputValue::Field(x)),
+            PublicInputEncoding::Bool => {
+                if x.is_zero() {
+                    Some(PublicInputValue::Bool(false))
+                } else if x.is_one() {
+                    Some(PublicInputValue::Bool(true))
+                } else {
+                    None
+                }
+            }
+            PublicInputEncoding::U64 => {
+                let digits = x.to_biguint().to_u64_digits();
+                match digits.len() {
+                    0 => Some(PublicInputValue::U64(0)),
+                    1 => Some(PublicInputValue::U64(digits[0])),
+                    _ => None,
+                }
+            }
+            PublicInputEncoding::ForeignField => {
+                if xs
+                    .iter()
+                    .any(|limb| limb.to_biguint().bits() > LIMB_BITS as u64)
+                {
+                    return None;
+                }
+                let limbs = std::array::from_fn(|i| xs[i]);
+                Some(PublicInputValue::ForeignField(
+                    KimchiForeignElement::<F>::new(limbs).to_biguint(),
+                ))
+            }
+        }
+    }
+}
+
+/// A named field of a [PublicInputLayout]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputField {
+    /// The name of the field
+    pub name: String,
+    /// The position of the first value of the field in the public inputs
+    pub offset: usize,
+    /// The number of values of the field
+    pub len: usize,
+    /// The encoding of the values of the field
+    pub encoding: PublicInputEncoding,
+}
+
+impl PublicInputField {
+    /// The number of public inputs used by the field
+    pub fn size(&self) -> usize {
+        self.len * self.encoding.size()
+    }
+}
+
+/// The named fields of the public inputs of a circuit, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputLayout {
+    fields: Vec<PublicInputField>,
+}
+
+impl PublicInputLayout {
+    /// Creates an empty layout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field of one value after the previous fields
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a field of the same name already exists.
+    pub fn field(self, name: &str, encoding: PublicInputEncoding) -> Self {
+        self.array(name, encoding, 1)
+    }
+
+    /// Adds a field of `len` values after the previous fields
+    ///
+    /// # Panics
+    ///
+    /// Will panic if a field of the same name already exists.
+    pub fn array(mut self, name: &str, encoding: PublicInputEncoding, len: usize) -> Self {
+        assert!(
+            self.get_field(name).is_none(),
+            "the public input field {name} already exists"
+        );
+        let offset = self.len();
+        self.fields.push(PublicInputField {
+            name: name.to_string(),
+            offset,
+            len,
+            encoding,
+        });
+        self
+    }
+
+    /// The fields of the layout, in increasing offsets
+    pub fn fields(&self) -> &[PublicInputField] {
+        &self.fields
+    }
+
+    /// The number of public inputs described by the layout
+    pub fn len(&self) -> usize {
+        self.fields
+            .last()
+            .map_or(0, |field| field.offset + field.size())
+    }
+
+    /// Whether the layout describes no public input
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the field with the given name
+    pub fn get_field(&self, name: &str) -> Option<&PublicInputField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the offset of the field with the given name
+    pub fn offset(&self, name: &str) -> Option<usize> {
+        self.get_field(name).map(|field| field.offset)
+    }
+
+    /// Returns an encoder of public inputs with this layout
+    pub fn encoder<F: PrimeField>(&self) -> PublicInputEncoder<F> {
+        PublicInputEncoder {
+            layout: self,
+            values: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    /// Checks that the public inputs have the length of the layout
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the lengths differ.
+    pub fn check_len<F>(&self, public: &[F]) -> Result<(), PublicInputError> {
+        if public.len() != self.len() {
+            return Err(PublicInputError::Length {
+                expected: self.len(),
+                found: public.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the values of the field with the given name
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the field does not exist, if the public inputs do
+    /// not have the length of the layout, or if a value does not match the
+    /// encoding of the field.
+    pub fn get<F: PrimeField>(
+        &self,
+        public: &[F],
+        name: &str,
+    ) -> Result<Vec<PublicInputValue<F>>, PublicInputError> {
+        self.check_len(public)?;
+        let field = self
+            .get_field(name)
+            .ok_or_else(|| PublicInputError::UnknownField(name.to_string()))?;
+        Self::decode_field(public, field)
+    }
+
+    /// Returns the values of all the fields, by name
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the public inputs do not have the length of the
+    /// layout, or if a value does not match the encoding of its field.
+    pub fn decode<F: PrimeField>(
+        &self,
+        public: &[F],
+    ) -> Result<BTreeMap<String, Vec<PublicInputValue<F>>>, PublicInputError> {
+        self.check_len(public)?;
+        self.fields
+            .iter()
+            .map(|field| Ok((field.name.clone(), Self::decode_field(public, field)?)))
+            .collect()
+    }
+
+    /// Checks that the public inputs have the length of the layout, and that
+    /// their values match the encodings of their fields
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the lengths differ, or if a value does not match the
+    /// encoding of its field.
+    pub fn check<F: PrimeField>(&self, public: &[F]) -> Result<(), PublicInputError> {
+        self.decode(public).map(|_| ())
+    }
+
+    /// Returns the position of the first public input that does not match
+    /// the encoding of its field, if any
+    pub(crate) fn first_invalid<F: PrimeField>(&self, public: &[F]) -> Option<usize> {
+        self.fields.iter().find_map(|field| {
+            public[field.offset..field.offset + field.size()]
+                .chunks(field.encoding.size())
+                .position(|xs| PublicInputValue::from_fields(xs, field.encoding).is_none())
+                .map(|i| field.offset + i * field.encoding.size())
+        })
+    }
+
+    fn decode_field<F: PrimeField>(
+        public: &[F],
+        field: &PublicInputField,
+    ) -> Result<Vec<PublicInputValue<F>>, PublicInputError> {
+        public[field.offset..field.offset + field.size()]
+            .chunks(field.encoding.size())
+            .map(|xs| {
+                PublicInputValue::from_fields(xs, field.encoding)
+                    .ok_or_else(|| PublicInputError::InvalidValue(field.name.clone()))
+            })
+            .collect()
+    }
+}
+
+/// An encoder of public inputs, filled by name, see
+/// [PublicInputLayout::encoder]
+pub struct PublicInputEncoder<'a, F> {
+    layout: &'a PublicInputLayout,
+    values: BTreeMap<String, Vec<PublicInputValue<F>>>,
+    error: Option<PublicInputError>,
+}
+
+impl<'a, F: PrimeField> PublicInputEncoder<'a, F> {
+    /// Sets the value of a field of one value
+    pub fn set(self, name: &str, value: PublicInputValue<F>) -> Self {
+        self.set_array(name, &[value])
+    }
+
+    /// Sets the values of a field.
+    /// Errors are reported by [PublicInputEncoder::encode].
+    pub fn set_array(mut self, name: &str, values: &[PublicInputValue<F>]) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        self.error = match self.layout.get_field(name) {
+            None => Some(PublicInputError::UnknownField(name.to_string())),
+            Some(_) if self.values.contains_key(name) => {
+                Some(PublicInputError::DuplicateField(name.to_string()))
+            }
+            Some(field) if field.len != values.len() => Some(PublicInputError::FieldLength {
+                name: name.to_string(),
+                expected: field.len,
+                found: values.len(),
+            }),
+            Some(field)
+                if values
+                    .iter()
+                    .any(|value| value.encoding() != field.encoding || !value.is_valid()) =>
+            {
+                Some(PublicInputError::InvalidValue(name.to_string()))
+            }
+            Some(_) => {
+                self.values.insert(name.to_string(), values.to_vec());
+                None
+            }
+        };
+        self
+    }
+
+    /// Returns the public input vector
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a field was set incorrectly, or was not set.
+    pub fn encode(self) -> Result<Vec<F>, PublicInputError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut public = Vec::with_capacity(self.layout.len());
+        for field in self.layout.fields() {
+            let values = self
+                .values
+                .get(&field.name)
+                .ok_or_else(|| PublicInputError::MissingField(field.name.clone()))?;
+            public.extend(values.iter().flat_map(|value| value.to_fields()));
+        }
+        Ok(public)
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    /// Returns an encoder of the public inputs of the circuit, see
+    /// [PublicInputLayout::encoder]
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the circuit has no public input layout.
+    pub fn public_input_encoder(&self) -> Result<PublicInputEncoder<F>, PublicInputError> {
+        self.public_input_layout
+            .as_ref()
+            .map(PublicInputLayout::encoder)
+            .ok_or(PublicInputError::NoLayout)
+    }
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof> {
+    /// Returns the values of the public input field with the given name, see
+    /// [PublicInputLayout::get]
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the circuit has no public input layout, or if the
+    /// field cannot be decoded.
+    pub fn public_input(
+        &self,
+        public: &[G::ScalarField],
+        name: &str,
+    ) -> Result<Vec<PublicInputValue<G::ScalarField>>, PublicInputError> {
+        self.public_input_layout
+            .as_ref()
+            .ok_or(PublicInputError::NoLayout)?
+            .get(public, name)
+    }
+}
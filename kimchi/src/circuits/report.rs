@@ -0,0 +1,240 @@
+//! This module implements a report of the size of a constraint system.
+//!
+//! [ConstraintSystem::report] returns a [ConstraintSystemReport] with the rows
+//! used by each type of gate, the sizes of the lookup tables, the maximum
+//! degree of the constraints of the gates used, and an estimate of the size of
+//! a proof and of the memory used by the prover. The estimates assume an SRS of
+//! the size of the domain and the inner product argument as opening proof, see
+//! [crate::dry_run] for the proofs of a given [crate::prover_index::ProverIndex].
+
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::GateType,
+        gate_info::GATE_TYPES,
+        wires::{COLUMNS, PERMUTS},
+    },
+    curve::KimchiCurve,
+    dry_run::{ipa_opening_shape, ProofShape},
+};
+use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use serde::Serialize;
+use std::fmt;
+
+/// The number of rows used by a type of gate
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GateCount {
+    /// The type of the gate
+    pub typ: GateType,
+    /// The number of rows of this type
+    pub rows: usize,
+}
+
+/// The size of a lookup table of the concatenated table
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TableSize {
+    /// The ID of the table
+    pub id: i32,
+    /// The number of entries of the table
+    pub length: usize,
+    /// Whether the table is a runtime table
+    pub runtime: bool,
+}
+
+/// The size of a constraint system, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ConstraintSystemReport {
+    /// The number of rows of the circuit, including the public inputs and
+    /// without the padding up to the domain size
+    pub rows: usize,
+    /// The number of public inputs
+    pub public: usize,
+    /// The size of the domain
+    pub domain_size: usize,
+    /// The number of rows reserved for zero-knowledge
+    pub zk_rows: u64,
+    /// The rows used by each type of gate, in the order of [GATE_TYPES],
+    /// without the types which are not used
+    pub gates: Vec<GateCount>,
+    /// The lookup tables, in the order of the concatenated table
+    pub lookup_tables: Vec<TableSize>,
+    /// The number of columns of the concatenated table
+    pub lookup_table_width: usize,
+    /// The maximum degree of the constraints of the gates used
+    pub max_degree: u64,
+    /// The structure of the proofs
+    pub proof: ProofShape,
+    /// The estimated memory used by the prover, in bytes
+    pub prover_memory: usize,
+}
+
+impl ConstraintSystemReport {
+    /// The number of rows used by gates of type `typ`
+    pub fn rows_of(&self, typ: GateType) -> usize {
+        self.gates
+            .iter()
+            .find(|count| count.typ == typ)
+            .map_or(0, |count| count.rows)
+    }
+
+    /// The total number of entries of the lookup tables
+    pub fn lookup_entries(&self) -> usize {
+        self.lookup_tables.iter().map(|table| table.length).sum()
+    }
+}
+
+impl fmt::Display for ConstraintSystemReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "rows: {}, public: {}, domain size: {}, zk rows: {}, max degree: {}",
+            self.rows, self.public, self.domain_size, self.zk_rows, self.max_degree
+        )?;
+        writeln!(f, "{:<18} {:>8}", "gate", "rows")?;
+        for count in &self.gates {
+            writeln!(f, "{:<18} {:>8}", format!("{:?}", count.typ), count.rows)?;
+        }
+        if !self.lookup_tables.is_empty() {
+            writeln!(
+                f,
+                "{:<18} {:>8}  (width {})",
+                "lookup table", "entries", self.lookup_table_width
+            )?;
+            for table in &self.lookup_tables {
+                writeln!(
+                    f,
+                    "{:<18} {:>8}",
+                    format!(
+                        "{}{}",
+                        table.id,
+                        if table.runtime { " (runtime)" } else { "" }
+                    ),
+                    table.length
+                )?;
+            }
+        }
+        write!(
+            f,
+            "proof: {} bytes, prover memory: {} bytes",
+            self.proof.size_in_bytes(),
+            self.prover_memory
+        )
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    /// Returns the size of the constraint system, see the
+    /// [module documentation](self)
+    pub fn report<G: KimchiCurve<ScalarField = F>>(&self) -> ConstraintSystemReport {
+        let domain_size = self.domain.d1.size();
+
+        // The circuit is padded up to the domain size with zero gates which
+        // are not wired to any other cell, see [ConstraintSystem::create]
+        let padding = self
+            .gates
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(row, gate)| {
+                gate.typ == GateType::Zero
+                    && gate.coeffs.is_empty()
+                    && gate
+                        .wires
+                        .iter()
+                        .enumerate()
+                        .all(|(col, wire)| wire.row == *row && wire.col == col)
+            })
+            .count();
+        let circuit = &self.gates[..self.gates.len() - padding];
+
+        let gates: Vec<GateCount> = GATE_TYPES
+            .iter()
+            .map(|&typ| GateCount {
+                typ,
+                rows: circuit.iter().filter(|gate| gate.typ == typ).count(),
+            })
+            .filter(|count| count.rows > 0)
+            .collect();
+
+        let max_degree = gates
+            .iter()
+            .map(|count| count.typ.spec::<F>().degree)
+            .max()
+            .unwrap_or(0);
+
+        let lcs = self.lookup_constraint_system.as_ref();
+        let lookup_tables = lcs.map_or(vec![], |lcs| {
+            // the runtime tables are at the end of the concatenated table
+            let num_runtime = lcs.runtime_tables.as_ref().map_or(0, Vec::len);
+            let num_fixed = lcs.table_lengths.len() - num_runtime;
+            lcs.table_lengths
+                .iter()
+                .enumerate()
+                .map(|(i, &(id, length))| TableSize {
+                    id,
+                    length,
+                    runtime: i >= num_fixed,
+                })
+                .collect()
+        });
+        let lookup_table_width = lcs.map_or(0, |lcs| lcs.lookup_table.len());
+
+        let proof = self.proof_shape::<G>(domain_size, ipa_opening_shape(domain_size));
+
+        // The memory of the prover is dominated by the evaluations of the
+        // polynomials over the domains d4 and d8: the ones of the index, and
+        // the ones of the witness, the aggregations and the quotient computed
+        // while proving.
+        let feature_flags = &self.feature_flags;
+        let optional_selectors = [
+            feature_flags.range_check0,
+            feature_flags.range_check1,
+            feature_flags.foreign_field_add,
+            feature_flags.foreign_field_mul,
+            feature_flags.xor,
+            feature_flags.rot,
+            self.custom_gates.is_some(),
+        ];
+        // the permutation, the coefficients, and the selectors
+        let mut evaluations8 = PERMUTS + COLUMNS + 4;
+        evaluations8 += optional_selectors.iter().filter(|x| **x).count();
+        // the witness, the permutation aggregation and the quotient
+        evaluations8 += COLUMNS + 2;
+        if let Some(lcs) = lcs {
+            let lookup_selectors = [
+                lcs.lookup_selectors.xor.is_some(),
+                lcs.lookup_selectors.lookup.is_some(),
+                lcs.lookup_selectors.range_check.is_some(),
+                lcs.lookup_selectors.ffmul.is_some(),
+                lcs.lookup_selectors.vector_lookup.is_some(),
+            ];
+            let num_sorted = lcs
+                .configuration
+                .argument
+                .num_sorted(&lcs.configuration.lookup_info);
+            // the table, the selectors, the runtime selector and table, the
+            // sorted polynomials and the aggregation
+            evaluations8 += lookup_table_width + lcs.table_ids8.is_some() as usize;
+            evaluations8 += lookup_selectors.iter().filter(|x| **x).count();
+            evaluations8 += 2 * lcs.runtime_selector.is_some() as usize;
+            evaluations8 += num_sorted + 1;
+        }
+        // the generic and complete addition selectors are over d4
+        let evaluations = 8 * evaluations8 + 4 * 2;
+        let prover_memory = evaluations * domain_size * proof.scalar_size;
+
+        ConstraintSystemReport {
+            rows: circuit.len(),
+            public: self.public,
+            domain_size,
+            zk_rows: self.zk_rows,
+            gates,
+            lookup_tables,
+            lookup_table_width,
+            max_degree,
+            proof,
+            prover_memory,
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! This module splits a circuit that is too large for the maximum supported
+//! domain into smaller sub-circuits.
+//!
+//! The rows of the circuit are partitioned into contiguous chunks, and each
+//! chunk becomes a sub-circuit that is proven on its own. A chunk is only cut
+//! after a gate that does not constrain the next row, so that every gate of a
+//! sub-circuit is fully constrained by it.
+//!
+//! The copy constraints that connect cells of different chunks are realized
+//! with public inputs: for each permutation cycle crossing the boundary of a
+//! chunk, its sub-circuit gets an interface public input wired to the cells of
+//! the cycle that it contains. The sub-circuits sharing a cycle expose the same
+//! value as public input, which [verify_split] checks before batch verifying
+//! the proofs of the sub-circuits, aggregating their opening proofs into a
+//! single check.
+//!
+//! The interface public inputs of a sub-circuit are placed after the public
+//! inputs of the original circuit (for the first sub-circuit), and before its
+//! gates.
+
+use crate::{
+    circuits::{
+        gate::{CircuitGate, GateType},
+        polynomials::generic::GenericGateSpec,
+        wires::{Wire, COLUMNS, PERMUTS},
+    },
+    curve::KimchiCurve,
+    error::VerifyError,
+    plonk_sponge::FrSponge,
+    verifier::{batch_verify, Context, Result},
+};
+use ark_ff::PrimeField;
+use mina_poseidon::FqSponge;
+use poly_commitment::OpenProof;
+use std::{collections::HashMap, ops::Range};
+use thiserror::Error;
+
+/// Errors that can arise when splitting a circuit
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SplitError {
+    #[error("the circuit cannot be cut in sub-circuits of at most {0} rows around row {1}")]
+    NoCutPoint(usize, usize),
+    #[error("the witness has {0} rows, but the circuit has {1} rows")]
+    WitnessLength(usize, usize),
+}
+
+/// Returns the maximum number of gates of a circuit over a domain of size
+/// `2^log2_size` with `zk_rows` zero-knowledge rows
+pub fn max_rows(log2_size: usize, zk_rows: u64) -> usize {
+    (1usize << log2_size).saturating_sub(zk_rows as usize)
+}
+
+/// Returns whether the constraints of the gate only involve the row of the
+/// gate, in which case the circuit can be cut after it.
+/// Custom gates are conservatively assumed to constrain the next row.
+fn is_row_local(typ: GateType) -> bool {
+    matches!(
+        typ,
+        GateType::Zero
+            | GateType::Generic
+            | GateType::CompleteAdd
+            | GateType::EndoMulScalar
+            | GateType::Lookup
+            | GateType::VectorLookup
+    )
+}
+
+/// A part of a split circuit
+#[derive(Clone, Debug)]
+pub struct SubCircuit<F: PrimeField> {
+    /// The gates of the sub-circuit, starting with its public inputs
+    pub gates: Vec<CircuitGate<F>>,
+    /// The number of public inputs of the sub-circuit
+    pub public: usize,
+    /// The rows of the original circuit that are part of the sub-circuit,
+    /// not including the public inputs of the original circuit
+    pub rows: Range<usize>,
+    /// For each interface public input, a cell of the original circuit whose
+    /// value it exposes
+    pub interface: Vec<Wire>,
+}
+
+impl<F: PrimeField> SubCircuit<F> {
+    /// The number of public inputs copied from the original circuit
+    fn prefix(&self) -> usize {
+        self.public - self.interface.len()
+    }
+}
+
+/// A circuit split into sub-circuits, see the [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct SplitCircuit<F: PrimeField> {
+    /// The sub-circuits, in the order of the rows of the original circuit
+    pub sub_circuits: Vec<SubCircuit<F>>,
+    /// For each permutation cycle crossing sub-circuits, the sub-circuits
+    /// sharing it, and the index of the public input exposing it in each of them
+    pub links: Vec<Vec<(usize, usize)>>,
+}
+
+/// Computes the permutation cycles of the circuit, as the list of the cells
+/// of each cycle, and the cycle of each cell.
+fn cycles<F: PrimeField>(gates: &[CircuitGate<F>]) -> (Vec<Vec<Wire>>, Vec<[usize; PERMUTS]>) {
+    let mut cycle_of = vec![[usize::MAX; PERMUTS]; gates.len()];
+    let mut cycles = vec![];
+    for row in 0..gates.len() {
+        for col in 0..PERMUTS {
+            if cycle_of[row][col] != usize::MAX {
+                continue;
+            }
+            let id = cycles.len();
+            let mut cycle = vec![];
+            let mut cell = Wire::new(row, col);
+            while cycle_of[cell.row][cell.col] == usize::MAX {
+                cycle_of[cell.row][cell.col] = id;
+                cycle.push(cell);
+                cell = gates[cell.row].wires[cell.col];
+            }
+            cycles.push(cycle);
+        }
+    }
+    (cycles, cycle_of)
+}
+
+/// Tracks the number of cycles crossing the boundary of a chunk of rows
+struct Crossings<'a> {
+    cycles: &'a [Vec<Wire>],
+    cycle_of: &'a [[usize; PERMUTS]],
+    in_chunk: HashMap<usize, usize>,
+    crossing: usize,
+}
+
+impl<'a> Crossings<'a> {
+    fn new(cycles: &'a [Vec<Wire>], cycle_of: &'a [[usize; PERMUTS]]) -> Self {
+        Crossings {
+            cycles,
+            cycle_of,
+            in_chunk: HashMap::new(),
+            crossing: 0,
+        }
+    }
+
+    fn add_row(&mut self, row: usize) {
+        for col in 0..PERMUTS {
+            let id = self.cycle_of[row][col];
+            let total = self.cycles[id].len();
+            let count = self.in_chunk.entry(id).or_insert(0);
+            if *count == 0 && total > 1 {
+                self.crossing += 1;
+            }
+            *count += 1;
+            if *count == total && total > 1 {
+                self.crossing -= 1;
+            }
+        }
+    }
+}
+
+/// Splits a circuit whose first `public` rows are its public inputs into
+/// sub-circuits of at most `max_rows` rows each, see [max_rows].
+/// If the circuit fits in `max_rows`, it is returned as a single sub-circuit.
+///
+/// # Errors
+///
+/// Will give error if the circuit contains a sequence of gates constraining
+/// their next row that does not fit in a sub-circuit.
+pub fn split_circuit<F: PrimeField>(
+    gates: &[CircuitGate<F>],
+    public: usize,
+    max_rows: usize,
+) -> std::result::Result<SplitCircuit<F>, SplitError> {
+    let (cycles, cycle_of) = cycles(gates);
+
+    // choose the chunks greedily, cutting at the last possible row
+    let mut chunks = vec![];
+    let mut start = public;
+    while start < gates.len() || chunks.is_empty() {
+        let mut crossings = Crossings::new(&cycles, &cycle_of);
+        let prefix = if chunks.is_empty() { public } else { 0 };
+        (0..prefix).for_each(|row| crossings.add_row(row));
+        let mut cut = None;
+        let mut end = start;
+        while end < gates.len() {
+            crossings.add_row(end);
+            if prefix + crossings.crossing + end + 1 - start > max_rows {
+                break;
+            }
+            end += 1;
+            if end == gates.len() || is_row_local(gates[end - 1].typ) {
+                cut = Some(end);
+            }
+        }
+        let end = match cut {
+            Some(end) => end,
+            None if start == gates.len() => start,
+            None => return Err(SplitError::NoCutPoint(max_rows, start)),
+        };
+        chunks.push(start..end);
+        start = end;
+    }
+
+    let mut sub_circuits = vec![];
+    let mut links: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let prefix = if i == 0 { public } else { 0 };
+        let local_rows = || (0..prefix).chain(chunk.clone());
+        let in_chunk = |cell: &Wire| local_rows().any(|row| row == cell.row);
+
+        // the cycles of the chunk that cross its boundary
+        let mut interface_cycles = vec![];
+        for row in local_rows() {
+            for &id in &cycle_of[row] {
+                if !interface_cycles.contains(&id) && !cycles[id].iter().all(in_chunk) {
+                    interface_cycles.push(id);
+                }
+            }
+        }
+        let offset = prefix + interface_cycles.len();
+        let new_row = |row: usize| {
+            if row < prefix {
+                row
+            } else {
+                row - chunk.start + offset
+            }
+        };
+
+        let mut sub_gates: Vec<_> = local_rows()
+            .map(|row| {
+                CircuitGate::new(
+                    gates[row].typ,
+                    Wire::for_row(new_row(row)),
+                    gates[row].coeffs.clone(),
+                )
+            })
+            .collect();
+        let interface_gates = (0..interface_cycles.len()).map(|j| {
+            CircuitGate::create_generic_gadget(
+                Wire::for_row(prefix + j),
+                GenericGateSpec::Pub,
+                None,
+            )
+        });
+        sub_gates.splice(prefix..prefix, interface_gates);
+
+        // rewire the cycles restricted to the chunk, through the interface
+        // public input when the cycle crosses the boundary of the chunk
+        let mut wired = vec![false; cycles.len()];
+        for row in local_rows() {
+            for &id in &cycle_of[row] {
+                if wired[id] {
+                    continue;
+                }
+                wired[id] = true;
+                let mut cells: Vec<_> = cycles[id]
+                    .iter()
+                    .filter(|cell| in_chunk(cell))
+                    .map(|cell| Wire::new(new_row(cell.row), cell.col))
+                    .collect();
+                if let Some(j) = interface_cycles.iter().position(|&c| c == id) {
+                    cells.push(Wire::new(prefix + j, 0));
+                    links.entry(id).or_default().push((i, prefix + j));
+                }
+                for (k, cell) in cells.iter().enumerate() {
+                    sub_gates[cell.row].wires[cell.col] = cells[(k + 1) % cells.len()];
+                }
+            }
+        }
+
+        sub_circuits.push(SubCircuit {
+            gates: sub_gates,
+            public: offset,
+            rows: chunk.clone(),
+            interface: interface_cycles.iter().map(|&id| cycles[id][0]).collect(),
+        });
+    }
+
+    let mut links: Vec<_> = links.into_iter().collect();
+    links.sort();
+    Ok(SplitCircuit {
+        sub_circuits,
+        links: links.into_iter().map(|(_, link)| link).collect(),
+    })
+}
+
+/// The witness of a sub-circuit, with its public inputs
+pub type SubWitness<F> = ([Vec<F>; COLUMNS], Vec<F>);
+
+impl<F: PrimeField> SplitCircuit<F> {
+    /// Splits the witness of the original circuit into the witnesses of the
+    /// sub-circuits, returned with their public inputs.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the witness does not have as many rows as the circuit.
+    pub fn split_witness(
+        &self,
+        witness: &[Vec<F>; COLUMNS],
+    ) -> std::result::Result<Vec<SubWitness<F>>, SplitError> {
+        let rows = self
+            .sub_circuits
+            .last()
+            .map(|sub| sub.rows.end)
+            .unwrap_or(0);
+        if witness[0].len() != rows {
+            return Err(SplitError::WitnessLength(witness[0].len(), rows));
+        }
+
+        Ok(self
+            .sub_circuits
+            .iter()
+            .map(|sub| {
+                let prefix = sub.prefix();
+                let sub_witness: [Vec<F>; COLUMNS] = std::array::from_fn(|col| {
+                    let mut column: Vec<F> = witness[col][0..prefix].to_vec();
+                    column.extend(sub.interface.iter().map(|cell| {
+                        if col == 0 {
+                            witness[cell.col][cell.row]
+                        } else {
+                            F::zero()
+                        }
+                    }));
+                    column.extend_from_slice(&witness[col][sub.rows.clone()]);
+                    column
+                });
+                let public = sub_witness[0][0..sub.public].to_vec();
+                (sub_witness, public)
+            })
+            .collect())
+    }
+}
+
+/// Verifies the proofs of the sub-circuits of a split circuit, given in the
+/// order of [SplitCircuit::sub_circuits], after checking that the public
+/// inputs exposing the same permutation cycle are equal.
+///
+/// # Errors
+///
+/// Will give error if a public input linking two sub-circuits differs between
+/// them, or if a proof does not verify.
+pub fn verify_split<G, EFqSponge, EFrSponge, OpeningProof: OpenProof<G>>(
+    group_map: &G::Map,
+    split: &SplitCircuit<G::ScalarField>,
+    proofs: &[Context<G, OpeningProof>],
+) -> Result<()>
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    EFrSponge: FrSponge<G::ScalarField>,
+{
+    if proofs.len() != split.sub_circuits.len() {
+        return Err(VerifyError::IncorrectSubProofsLength(
+            split.sub_circuits.len(),
+            proofs.len(),
+        ));
+    }
+
+    for (i, link) in split.links.iter().enumerate() {
+        let value = |&(sub, idx): &(usize, usize)| proofs[sub].public_input.get(idx).copied();
+        let first = value(&link[0]);
+        if first.is_none() || link.iter().any(|cell| value(cell) != first) {
+            return Err(VerifyError::SplitLink(i));
+        }
+    }
+
+    batch_verify::<G, EFqSponge, EFrSponge, OpeningProof>(group_map, proofs)
+}
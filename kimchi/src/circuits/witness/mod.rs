@@ -5,6 +5,7 @@ mod copy_bits_cell;
 mod copy_cell;
 mod copy_shift_cell;
 mod index_cell;
+mod tracer;
 mod variable_bits_cell;
 mod variable_cell;
 mod variables;
@@ -15,12 +16,13 @@ pub use self::{
     copy_cell::CopyCell,
     copy_shift_cell::CopyShiftCell,
     index_cell::IndexCell,
+    tracer::{trace, trace_witness, WitnessEvent, WitnessRecorder, WitnessTracer},
     variable_bits_cell::VariableBitsCell,
     variable_cell::VariableCell,
     variables::{variable_map, variables, Variables},
 };
 
-use super::polynomial::COLUMNS;
+use super::{gate::GateType, polynomial::COLUMNS};
 
 /// Witness cell interface. By default, the witness cell is a single element of type F.
 pub trait WitnessCell<F: Field, T = F, const W: usize = COLUMNS> {
@@ -86,6 +88,41 @@ pub fn init<F: PrimeField, T, const W: usize>(
     }
 }
 
+/// Same as [init_row], reporting the variables to the installed
+/// [WitnessTracer] as the values of the gate of type `gate`
+pub fn init_row_traced<F: PrimeField, const W: usize>(
+    gate: GateType,
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    row: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, F, W>>>],
+    variables: &Variables<F>,
+) {
+    trace_variables(gate, offset + row, variables);
+    init_row(witness, offset, row, layout, variables);
+}
+
+/// Same as [init], reporting the variables to the installed [WitnessTracer]
+/// as the values of the gate of type `gate` at the first row of the layout
+pub fn init_traced<F: PrimeField, const W: usize>(
+    gate: GateType,
+    witness: &mut [Vec<F>; W],
+    offset: usize,
+    layout: &[Vec<Box<dyn WitnessCell<F, F, W>>>],
+    variables: &Variables<F>,
+) {
+    trace_variables(gate, offset, variables);
+    init(witness, offset, layout, variables);
+}
+
+fn trace_variables<F: PrimeField>(gate: GateType, row: usize, variables: &Variables<F>) {
+    let values: Vec<(&str, F)> = variables
+        .iter()
+        .map(|(name, value)| (name, *value))
+        .collect();
+    trace(gate, row, &values);
+}
+
 #[cfg(test)]
 mod tests {
     use std::array;
@@ -0,0 +1,149 @@
+//! Tracing hooks of the witness generators.
+//!
+//! The witness generators of the gadgets report the named intermediate values
+//! they compute for each gate to the [WitnessTracer] installed with
+//! [trace_witness], so that the values leading to a witness violating a
+//! constraint can be inspected without modifying the generators.
+//! When no tracer is installed, reporting the values does nothing.
+//!
+//! The rows are the ones of the witness being generated: the `create_*`
+//! generators start at row 0 and the `extend_*` generators report the rows of
+//! the witness they append.
+//!
+//! ```ignore
+//! let (witness, recorder) = trace_witness(WitnessRecorder::default(), || {
+//!     xor::create_xor_witness(input1, input2, 64)
+//! });
+//! for event in recorder.events_at(0) {
+//!     println!("{:?}: {:?}", event.gate, event.values);
+//! }
+//! ```
+
+use crate::circuits::gate::GateType;
+use ark_ff::Field;
+use std::{any::Any, cell::RefCell};
+
+/// Receives the intermediate values of the witness generators
+pub trait WitnessTracer<F> {
+    /// Called with the intermediate values `values` computed by a witness
+    /// generator for the gate of type `gate` at row `row`
+    fn trace(&mut self, gate: GateType, row: usize, values: &[(&str, F)]);
+}
+
+/// The intermediate values of a gate reported by a witness generator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessEvent<F> {
+    /// The type of the gate
+    pub gate: GateType,
+    /// The row of the gate
+    pub row: usize,
+    /// The named intermediate values, sorted by name
+    pub values: Vec<(String, F)>,
+}
+
+/// A [WitnessTracer] recording all the reported values
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessRecorder<F> {
+    /// The reported values, in the order of the reports
+    pub events: Vec<WitnessEvent<F>>,
+}
+
+impl<F> Default for WitnessRecorder<F> {
+    fn default() -> Self {
+        Self { events: vec![] }
+    }
+}
+
+impl<F: Clone> WitnessTracer<F> for WitnessRecorder<F> {
+    fn trace(&mut self, gate: GateType, row: usize, values: &[(&str, F)]) {
+        let mut values: Vec<(String, F)> = values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        values.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.events.push(WitnessEvent { gate, row, values });
+    }
+}
+
+impl<F: Clone> WitnessRecorder<F> {
+    /// Returns the events of the row `row`
+    pub fn events_at(&self, row: usize) -> impl Iterator<Item = &WitnessEvent<F>> {
+        self.events.iter().filter(move |event| event.row == row)
+    }
+
+    /// Returns the last value named `name` reported for the row `row`
+    pub fn value(&self, row: usize, name: &str) -> Option<F> {
+        self.events_at(row)
+            .flat_map(|event| event.values.iter())
+            .filter(|(value_name, _)| value_name == name)
+            .last()
+            .map(|(_, value)| value.clone())
+    }
+}
+
+/// A function calling an erased tracer with the values reported for a row
+type TraceFn<F> = fn(&mut dyn Any, GateType, usize, &[(&str, F)]);
+
+/// The installed tracer, with the function calling it, erased to be stored
+/// independently of the field
+struct ActiveTracer<F> {
+    tracer: Box<dyn Any>,
+    trace: TraceFn<F>,
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+}
+
+fn call_tracer<F, T: WitnessTracer<F> + 'static>(
+    tracer: &mut dyn Any,
+    gate: GateType,
+    row: usize,
+    values: &[(&str, F)],
+) {
+    tracer
+        .downcast_mut::<T>()
+        .expect("the tracer has the type it was installed with")
+        .trace(gate, row, values);
+}
+
+/// Runs `f` with `tracer` receiving the values reported by the witness
+/// generators of the current thread over the field `F`, and returns the
+/// result of `f` with the tracer.
+/// A tracer installed by an outer call is restored when `f` returns.
+pub fn trace_witness<F: Field, T: WitnessTracer<F> + 'static, R>(
+    tracer: T,
+    f: impl FnOnce() -> R,
+) -> (R, T) {
+    let active: Box<dyn Any> = Box::new(ActiveTracer::<F> {
+        tracer: Box::new(tracer),
+        trace: call_tracer::<F, T>,
+    });
+    let outer = TRACER.with(|slot| slot.replace(Some(active)));
+    let result = f();
+    let active = TRACER
+        .with(|slot| slot.replace(outer))
+        .expect("the tracer is installed until f returns");
+    let active = active
+        .downcast::<ActiveTracer<F>>()
+        .expect("the tracer has the field it was installed with");
+    let tracer = active
+        .tracer
+        .downcast::<T>()
+        .expect("the tracer has the type it was installed with");
+    (result, *tracer)
+}
+
+/// Reports the intermediate values `values` of the gate of type `gate` at
+/// row `row` to the installed tracer, if any
+pub fn trace<F: Field>(gate: GateType, row: usize, values: &[(&str, F)]) {
+    TRACER.with(|slot| {
+        if let Some(active) = slot
+            .borrow_mut()
+            .as_mut()
+            .and_then(|active| active.downcast_mut::<ActiveTracer<F>>())
+        {
+            (active.trace)(active.tracer.as_mut(), gate, row, values);
+        }
+    });
+}
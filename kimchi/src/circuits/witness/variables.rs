@@ -61,6 +61,11 @@ impl<'a, T> Variables<'a, T> {
     pub fn insert(&mut self, name: &'a str, value: T) {
         self.0.insert(name, value);
     }
+
+    /// Returns the variables and their values, in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &T)> {
+        self.0.iter().map(|(name, value)| (*name, value))
+    }
 }
 
 impl<'a, T> Index<&'a str> for Variables<'a, T> {
@@ -0,0 +1,183 @@
+//! This module implements the committed columns: witness columns whose
+//! commitment is public, but whose contents are private and can be reused by
+//! several proofs, for instance a database column used in lookups.
+//!
+//! A [CommittedColumn] is created once, with the random values of its
+//! zero-knowledge rows and the blinders of its commitment. The proofs created
+//! with [ProverProof::create_with_committed_columns] use the committed values
+//! and blinders for the column, so that the commitment of the column in the
+//! proof is the one of the [CommittedColumn]. The verifier accepts such a proof
+//! with [verify_with_committed_columns], given the commitments of the columns
+//! from an external source.
+//!
+//! Each proof reveals the evaluations of the column at two points, per chunk
+//! of the column, which are hidden by the random rows of the column. A
+//! [CommittedColumn] is therefore created for a declared maximum number of
+//! proofs, which the zero-knowledge rows of the circuit must hide (see
+//! [zk_rows_for_uses] to size them with
+//! [crate::circuits::constraints::Builder::zk_rows]), and the proofs past this
+//! number are refused by the prover.
+
+use crate::{
+    circuits::{berkeley_columns::Column, constraints::num_chunks, wires::COLUMNS},
+    curve::KimchiCurve,
+    error::{ProverError, VerifyError},
+    plonk_sponge::FrSponge,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::{verify, Result},
+    verifier_index::VerifierIndex,
+};
+use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_poly::{EvaluationDomain, Evaluations, Radix2EvaluationDomain as D};
+use mina_poseidon::FqSponge;
+use poly_commitment::{commitment::BlindedCommitment, OpenProof, PolyComm, SRS as _};
+use rand_core::{CryptoRng, RngCore};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// The number of zero-knowledge rows hiding the evaluations of a column
+/// committed with `num_chunks` chunks and shared by up to `max_uses` proofs
+pub fn zk_rows_for_uses(max_uses: usize, num_chunks: usize) -> u64 {
+    (2 * max_uses * num_chunks + 1) as u64
+}
+
+/// A witness column with a public commitment, see the
+/// [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct CommittedColumn<G: KimchiCurve> {
+    /// The index of the witness column
+    pub column: usize,
+    /// The values of the column over the whole domain, including the padding
+    /// and the zero-knowledge rows
+    pub evals: Vec<G::ScalarField>,
+    /// The commitment of the column, with its blinders
+    pub blinded_comm: BlindedCommitment<G>,
+    /// The maximum number of proofs using the column
+    max_uses: usize,
+    /// The number of proofs which used the column, shared by its clones
+    uses: Arc<AtomicUsize>,
+}
+
+impl<G: KimchiCurve> CommittedColumn<G> {
+    /// Commits to the witness column `column` of the circuit of `index` with
+    /// the values `values` of its rows, to be used by up to `max_uses`
+    /// proofs, randomizing its zero-knowledge rows and its blinders with `rng`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if there is no room for the zero-knowledge rows after
+    /// the values, or if the zero-knowledge rows of the circuit are too few to
+    /// hide `max_uses` proofs.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `column` is not a witness column.
+    pub fn create<OpeningProof: OpenProof<G>>(
+        index: &ProverIndex<G, OpeningProof>,
+        column: usize,
+        values: &[G::ScalarField],
+        max_uses: usize,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> std::result::Result<Self, ProverError> {
+        assert!(
+            column < COLUMNS,
+            "the committed column is not a witness column"
+        );
+        let domain: D<G::ScalarField> = index.cs.domain.d1;
+        let zk_rows = index.cs.zk_rows as usize;
+        if values.len() + zk_rows > domain.size() {
+            return Err(ProverError::NoRoomForZkInWitness);
+        }
+        let required = zk_rows_for_uses(max_uses, num_chunks(domain.size(), index.max_poly_size));
+        if index.cs.zk_rows < required {
+            return Err(ProverError::CommittedColumnNotZeroKnowledge {
+                column,
+                expected: required as usize,
+                got: zk_rows,
+            });
+        }
+
+        let mut evals = values.to_vec();
+        evals.resize(domain.size(), G::ScalarField::zero());
+        for row in evals.iter_mut().rev().take(zk_rows) {
+            *row = G::ScalarField::rand(rng);
+        }
+
+        let evaluations = Evaluations::from_vec_and_domain(evals.clone(), domain);
+        let blinded_comm = index.srs.mask(
+            index
+                .srs
+                .commit_evaluations_non_hiding(domain, &evaluations),
+            rng,
+        );
+        Ok(CommittedColumn {
+            column,
+            evals,
+            blinded_comm,
+            max_uses,
+            uses: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// The public commitment of the column
+    pub fn commitment(&self) -> &PolyComm<G> {
+        &self.blinded_comm.commitment
+    }
+
+    /// The number of proofs which can still use the column
+    pub fn remaining_uses(&self) -> usize {
+        self.max_uses - self.uses.load(Ordering::SeqCst)
+    }
+
+    /// Counts a proof using the column, shared with the clones of the column.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the column was already used by its maximum number of
+    /// proofs.
+    pub(crate) fn reserve_use(&self) -> std::result::Result<(), ProverError> {
+        self.uses
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |uses| {
+                (uses < self.max_uses).then_some(uses + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| ProverError::CommittedColumnExhausted {
+                column: self.column,
+                max_uses: self.max_uses,
+            })
+    }
+}
+
+/// Verifies a proof [`ProverProof`] using a [`VerifierIndex`] and a
+/// `group_map`, whose witness columns `columns` have the commitments given
+/// with them, see [verify].
+///
+/// # Errors
+///
+/// Will give error if the commitment of a committed column differs, or if the
+/// proof is not verified as valid.
+pub fn verify_with_committed_columns<G, EFqSponge, EFrSponge, OpeningProof: OpenProof<G>>(
+    group_map: &G::Map,
+    verifier_index: &VerifierIndex<G, OpeningProof>,
+    proof: &ProverProof<G, OpeningProof>,
+    public_input: &[G::ScalarField],
+    columns: &[(usize, PolyComm<G>)],
+) -> Result<()>
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    EFrSponge: FrSponge<G::ScalarField>,
+{
+    for (column, commitment) in columns {
+        if proof.commitments.w_comm.get(*column) != Some(commitment) {
+            return Err(VerifyError::CommittedColumnMismatch(Column::Witness(
+                *column,
+            )));
+        }
+    }
+    verify::<G, EFqSponge, EFrSponge, OpeningProof>(group_map, verifier_index, proof, public_input)
+}
@@ -0,0 +1,315 @@
+//! This module fingerprints the public parameters used by prover and verifier
+//! indexes.
+//!
+//! A proof is only accepted by a verifier whose index was created from the
+//! same circuit with the same public parameters: the curve and its fields, the
+//! SRS, the sponge parameters, the lookup configuration, the constraints of the
+//! circuit and the layouts of the indexes and proofs. When the prover and the verifier run in different
+//! services, a mismatch in any of them results in a rejected proof without
+//! further explanation.
+//!
+//! The [CompatibilityDescriptor] of an index gathers these parameters, and can
+//! be computed for both [ProverIndex] and [VerifierIndex]. Comparing the
+//! descriptors of two indexes with [CompatibilityDescriptor::check] lists the
+//! parameters that differ.
+
+use crate::{
+    circuits::lookup::{constraints::LookupArgument, lookups::LookupInfo},
+    curve::KimchiCurve,
+    opening_order::OPENING_ORDER_VERSION,
+    prover_index::ProverIndex,
+    verifier_index::VerifierIndex,
+};
+use ark_ff::PrimeField;
+use ark_poly::{univariate::DensePolynomial, UVPolynomial};
+use blake2::{Blake2b512, Digest};
+use mina_poseidon::poseidon::ArithmeticSpongeParams;
+use o1_utils::FieldHelpers;
+use poly_commitment::{OpenProof, SRS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The number of generators of the SRS that are fingerprinted by [srs_hash]
+pub const SRS_FINGERPRINT_SIZE: usize = 16;
+
+/// The lookup configuration of an index
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupDescriptor {
+    /// The argument used to prove the lookups
+    pub argument: LookupArgument,
+    /// The maximum number of lookups per row
+    pub max_per_row: usize,
+    /// The maximum size of a joint lookup
+    pub max_joint_size: u32,
+    /// Whether runtime tables are used
+    pub uses_runtime_tables: bool,
+}
+
+impl LookupDescriptor {
+    fn new(lookup_info: &LookupInfo, argument: LookupArgument) -> Self {
+        LookupDescriptor {
+            argument,
+            max_per_row: lookup_info.max_per_row,
+            max_joint_size: lookup_info.max_joint_size,
+            uses_runtime_tables: lookup_info.features.uses_runtime_tables,
+        }
+    }
+}
+
+/// The public parameters of a prover or verifier index
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatibilityDescriptor {
+    /// The name of the curve
+    pub curve: String,
+    /// The modulus of the scalar field
+    pub scalar_field: String,
+    /// The modulus of the base field
+    pub base_field: String,
+    /// The hash of the SRS, see [srs_hash]
+    pub srs_hash: String,
+    /// The maximum size of a polynomial committed with the SRS
+    pub max_poly_size: usize,
+    /// The hash of the sponge parameters of both fields, see [sponge_params_hash]
+    pub sponge_params_hash: String,
+    /// The size of the domain of the circuit
+    pub domain_size: u64,
+    /// The number of zero-knowledge rows
+    pub zk_rows: u64,
+    /// The number of public inputs
+    pub public: usize,
+    /// The number of previous challenges, for recursion
+    pub prev_challenges: usize,
+    /// The lookup configuration, if lookups are used
+    pub lookup: Option<LookupDescriptor>,
+    /// The digest of the linearization of the constraints, see
+    /// [crate::circuits::expr::Linearization::digest]
+    pub linearization_hash: String,
+    /// The version of the opening order, see [crate::opening_order]
+    pub opening_order_version: u32,
+}
+
+/// A parameter that differs between two [CompatibilityDescriptor]s
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The name of the parameter
+    pub parameter: &'static str,
+    /// The value of the parameter in the expected descriptor
+    pub expected: String,
+    /// The value of the parameter in the descriptor being checked
+    pub found: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            self.parameter, self.expected, self.found
+        )
+    }
+}
+
+impl CompatibilityDescriptor {
+    fn new<G: KimchiCurve, OpeningProof: OpenProof<G>>(
+        srs: &OpeningProof::SRS,
+        domain_size: u64,
+        zk_rows: u64,
+        public: usize,
+        prev_challenges: usize,
+        lookup: Option<LookupDescriptor>,
+        linearization_hash: String,
+    ) -> Self
+    where
+        G::BaseField: PrimeField,
+    {
+        CompatibilityDescriptor {
+            curve: G::NAME.to_string(),
+            scalar_field: G::ScalarField::modulus_biguint().to_string(),
+            base_field: G::BaseField::modulus_biguint().to_string(),
+            srs_hash: srs_hash::<G, OpeningProof::SRS>(srs),
+            max_poly_size: srs.max_poly_size(),
+            sponge_params_hash: sponge_params_hash(
+                G::sponge_params(),
+                G::other_curve_sponge_params(),
+            ),
+            domain_size,
+            zk_rows,
+            public,
+            prev_challenges,
+            lookup,
+            linearization_hash,
+            opening_order_version: OPENING_ORDER_VERSION,
+        }
+    }
+
+    /// Lists the parameters of `self` that differ from the ones of `expected`
+    pub fn mismatches(&self, expected: &Self) -> Vec<Mismatch> {
+        let fields: [(&'static str, String, String); 13] = [
+            ("curve", expected.curve.clone(), self.curve.clone()),
+            (
+                "scalar field",
+                expected.scalar_field.clone(),
+                self.scalar_field.clone(),
+            ),
+            (
+                "base field",
+                expected.base_field.clone(),
+                self.base_field.clone(),
+            ),
+            ("SRS hash", expected.srs_hash.clone(), self.srs_hash.clone()),
+            (
+                "SRS size",
+                expected.max_poly_size.to_string(),
+                self.max_poly_size.to_string(),
+            ),
+            (
+                "sponge parameters hash",
+                expected.sponge_params_hash.clone(),
+                self.sponge_params_hash.clone(),
+            ),
+            (
+                "domain size",
+                expected.domain_size.to_string(),
+                self.domain_size.to_string(),
+            ),
+            (
+                "zero-knowledge rows",
+                expected.zk_rows.to_string(),
+                self.zk_rows.to_string(),
+            ),
+            (
+                "public inputs",
+                expected.public.to_string(),
+                self.public.to_string(),
+            ),
+            (
+                "previous challenges",
+                expected.prev_challenges.to_string(),
+                self.prev_challenges.to_string(),
+            ),
+            (
+                "lookup configuration",
+                format!("{:?}", expected.lookup),
+                format!("{:?}", self.lookup),
+            ),
+            (
+                "linearization hash",
+                expected.linearization_hash.clone(),
+                self.linearization_hash.clone(),
+            ),
+            (
+                "opening order version",
+                expected.opening_order_version.to_string(),
+                self.opening_order_version.to_string(),
+            ),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, expected, found)| expected != found)
+            .map(|(parameter, expected, found)| Mismatch {
+                parameter,
+                expected,
+                found,
+            })
+            .collect()
+    }
+
+    /// Checks that `self` is compatible with `expected`
+    ///
+    /// # Errors
+    ///
+    /// Will give error with the list of the parameters that differ, if any.
+    pub fn check(&self, expected: &Self) -> Result<(), Vec<Mismatch>> {
+        let mismatches = self.mismatches(expected);
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+}
+
+/// Returns the hexadecimal Blake2b digest of the SRS size, of its blinding
+/// generator, and of the commitment to the polynomial `1 + 2 X + 3 X^2 + ...`
+/// of degree [SRS_FINGERPRINT_SIZE] (or the SRS size if smaller) minus one,
+/// which is a combination of its first generators.
+pub fn srs_hash<G: KimchiCurve, S: SRS<G>>(srs: &S) -> String {
+    let size = std::cmp::min(SRS_FINGERPRINT_SIZE, srs.max_poly_size());
+    let polynomial = DensePolynomial::from_coefficients_vec(
+        (1..=size as u64).map(G::ScalarField::from).collect(),
+    );
+    let commitment = srs.commit_non_hiding(&polynomial, 1);
+
+    let mut hasher = Blake2b512::new();
+    hasher.update((srs.max_poly_size() as u64).to_be_bytes());
+    let mut points = vec![srs.blinding_commitment()];
+    points.extend(commitment.elems);
+    for point in points {
+        let mut bytes = vec![];
+        point
+            .serialize(&mut bytes)
+            .expect("serialization of a point into a vector cannot fail");
+        hasher.update(bytes);
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn update_with_params<F: PrimeField>(hasher: &mut Blake2b512, params: &ArithmeticSpongeParams<F>) {
+    for row in params.mds.iter().chain(params.round_constants.iter()) {
+        for x in row {
+            hasher.update(x.to_bytes());
+        }
+    }
+}
+
+/// Returns the hexadecimal Blake2b digest of the sponge parameters of the
+/// scalar field followed by the sponge parameters of the base field
+pub fn sponge_params_hash<F: PrimeField, Q: PrimeField>(
+    params: &ArithmeticSpongeParams<F>,
+    other_params: &ArithmeticSpongeParams<Q>,
+) -> String {
+    let mut hasher = Blake2b512::new();
+    update_with_params(&mut hasher, params);
+    update_with_params(&mut hasher, other_params);
+    hex::encode(hasher.finalize())
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> ProverIndex<G, OpeningProof>
+where
+    G::BaseField: PrimeField,
+{
+    /// Returns the public parameters of this index
+    pub fn compatibility(&self) -> CompatibilityDescriptor {
+        CompatibilityDescriptor::new::<G, OpeningProof>(
+            &self.srs,
+            self.cs.domain.d1.size,
+            self.cs.zk_rows,
+            self.cs.public,
+            self.cs.prev_challenges,
+            self.cs.lookup_constraint_system.as_ref().map(|lcs| {
+                LookupDescriptor::new(&lcs.configuration.lookup_info, lcs.configuration.argument)
+            }),
+            self.linearization.digest(),
+        )
+    }
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
+where
+    G::BaseField: PrimeField,
+{
+    /// Returns the public parameters of this index
+    pub fn compatibility(&self) -> CompatibilityDescriptor {
+        CompatibilityDescriptor::new::<G, OpeningProof>(
+            &self.srs,
+            self.domain.size,
+            self.zk_rows,
+            self.public,
+            self.prev_challenges,
+            self.lookup_index
+                .as_ref()
+                .map(|li| LookupDescriptor::new(&li.lookup_info, li.argument)),
+            self.linearization.digest(),
+        )
+    }
+}
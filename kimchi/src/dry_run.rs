@@ -0,0 +1,318 @@
+//! This module implements a "dry run" of the prover, which computes the
+//! structure of the proofs of a circuit and an estimate of their size without
+//! computing them.
+//!
+//! The dry run follows the rounds of the prover (see [crate::prover]) and
+//! counts, for each of them, the group elements and field elements added to the
+//! proof and the challenges drawn, without computing any polynomial or
+//! commitment. It only depends on the [ProverIndex], or on the
+//! [ConstraintSystem] and the size of the SRS with
+//! [ConstraintSystem::proof_shape], so that protocol designers can budget the
+//! bandwidth of a circuit in milliseconds, before its witness generation is
+//! written.
+//! The size estimate is the size of the compressed elements of the proof, the
+//! serialization of a proof adding a few bytes of framing.
+
+use crate::{
+    circuits::{
+        constraints::{num_chunks, ConstraintSystem},
+        wires::{COLUMNS, PERMUTS},
+    },
+    curve::KimchiCurve,
+    prover_index::ProverIndex,
+};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{PrimeField, Zero};
+use ark_poly::EvaluationDomain;
+use ark_serialize::CanonicalSerialize;
+use o1_utils::math;
+use poly_commitment::{
+    commitment::{CommitmentCurve, EndoCurve},
+    evaluation_proof::OpeningProof,
+    pairing_proof::PairingProof,
+    OpenProof, SRS,
+};
+use serde::Serialize;
+use std::fmt;
+
+/// The number of group elements and field elements of a part of a proof
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ElementCount {
+    /// The number of group elements
+    pub points: usize,
+    /// The number of elements of the scalar field
+    pub scalars: usize,
+}
+
+impl ElementCount {
+    /// The size of the elements in bytes, with compressed group elements
+    pub fn size_in_bytes<G: AffineCurve>(&self) -> usize {
+        self.points * G::zero().serialized_size()
+            + self.scalars * G::ScalarField::zero().serialized_size()
+    }
+}
+
+impl std::ops::Add for ElementCount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        ElementCount {
+            points: self.points + other.points,
+            scalars: self.scalars + other.scalars,
+        }
+    }
+}
+
+/// The number of elements of an inner product argument opening proof with an
+/// SRS of `max_poly_size` elements
+pub fn ipa_opening_shape(max_poly_size: usize) -> ElementCount {
+    ElementCount {
+        points: 2 * math::ceil_log2(max_poly_size) + 2,
+        scalars: 2,
+    }
+}
+
+/// An opening proof whose size only depends on the SRS
+pub trait OpeningProofShape<G: CommitmentCurve>: OpenProof<G> {
+    /// The number of elements of an opening proof with the SRS
+    fn shape(srs: &Self::SRS) -> ElementCount;
+}
+
+impl<
+        BaseField: PrimeField,
+        G: AffineCurve<BaseField = BaseField> + CommitmentCurve + EndoCurve,
+    > OpeningProofShape<G> for OpeningProof<G>
+{
+    /// A pair of points per round of the inner product argument, `delta` and
+    /// `sg`, and the scalars `z1` and `z2`
+    fn shape(srs: &Self::SRS) -> ElementCount {
+        ipa_opening_shape(srs.max_poly_size())
+    }
+}
+
+impl<
+        F: PrimeField,
+        G: CommitmentCurve<ScalarField = F>,
+        G2: CommitmentCurve<ScalarField = F>,
+        Pair: PairingEngine<G1Affine = G, G2Affine = G2>,
+    > OpeningProofShape<G> for PairingProof<Pair>
+{
+    /// The quotient and the blinding factor
+    fn shape(_srs: &Self::SRS) -> ElementCount {
+        ElementCount {
+            points: 1,
+            scalars: 1,
+        }
+    }
+}
+
+/// A round of the prover
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Round {
+    /// The name of the round
+    pub name: &'static str,
+    /// The elements added to the proof in this round
+    pub elements: ElementCount,
+    /// The challenges drawn at the end of the round
+    pub challenges: Vec<&'static str>,
+}
+
+/// The structure of the proofs of a circuit, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProofShape {
+    /// The size of the domain of the circuit
+    pub domain_size: usize,
+    /// The number of chunks of each commitment
+    pub num_chunks: usize,
+    /// The number of polynomials evaluated at `zeta` and `zeta * omega`
+    pub num_evaluations: usize,
+    /// The rounds of the prover, in order
+    pub rounds: Vec<Round>,
+    /// The size of a compressed group element, in bytes
+    pub point_size: usize,
+    /// The size of a scalar field element, in bytes
+    pub scalar_size: usize,
+}
+
+impl ProofShape {
+    /// The number of elements of a proof
+    pub fn elements(&self) -> ElementCount {
+        self.rounds
+            .iter()
+            .fold(ElementCount::default(), |acc, round| acc + round.elements)
+    }
+
+    /// The estimated size of a proof, in bytes
+    pub fn size_in_bytes(&self) -> usize {
+        let elements = self.elements();
+        elements.points * self.point_size + elements.scalars * self.scalar_size
+    }
+}
+
+impl fmt::Display for ProofShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "domain size: {}, chunks: {}, evaluations: {}",
+            self.domain_size, self.num_chunks, self.num_evaluations
+        )?;
+        for round in &self.rounds {
+            writeln!(
+                f,
+                "{}: {} points, {} scalars, challenges [{}]",
+                round.name,
+                round.elements.points,
+                round.elements.scalars,
+                round.challenges.join(", ")
+            )?;
+        }
+        let elements = self.elements();
+        write!(
+            f,
+            "total: {} points, {} scalars, {} bytes",
+            elements.points,
+            elements.scalars,
+            self.size_in_bytes()
+        )
+    }
+}
+
+impl<G: KimchiCurve, OpeningProof: OpeningProofShape<G>> ProverIndex<G, OpeningProof> {
+    /// Runs the prover without computing the proof, and returns the structure
+    /// of the proofs created with this index, see the
+    /// [module documentation](self)
+    pub fn dry_run(&self) -> ProofShape {
+        self.cs
+            .proof_shape::<G>(self.max_poly_size, OpeningProof::shape(&self.srs))
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    /// Returns the structure of the proofs of the circuit, with commitments
+    /// split in chunks of `max_poly_size` and an opening proof of `opening`
+    /// elements, see the [module documentation](self)
+    pub fn proof_shape<G: KimchiCurve<ScalarField = F>>(
+        &self,
+        max_poly_size: usize,
+        opening: ElementCount,
+    ) -> ProofShape {
+        let d1_size = self.domain.d1.size();
+        let num_chunks = num_chunks(d1_size, max_poly_size);
+        let commitments = |n: usize| ElementCount {
+            points: n * num_chunks,
+            scalars: 0,
+        };
+        let lcs = self.lookup_constraint_system.as_ref();
+        let mut rounds = vec![];
+
+        // the accumulators of the previous proofs
+        rounds.push(Round {
+            name: "previous challenges",
+            elements: ElementCount {
+                points: self.prev_challenges,
+                scalars: self.prev_challenges * math::ceil_log2(max_poly_size),
+            },
+            challenges: vec![],
+        });
+
+        // the witness columns, and the runtime table
+        let runtime_tables = lcs.map_or(false, |lcs| lcs.runtime_tables.is_some());
+        let mut challenges = vec![];
+        if let Some(lcs) = lcs {
+            if lcs.configuration.lookup_info.features.joint_lookup_used {
+                challenges.push("joint combiner");
+            }
+        }
+        rounds.push(Round {
+            name: "witness",
+            elements: commitments(COLUMNS + runtime_tables as usize),
+            challenges,
+        });
+
+        // the sorted polynomials, or the multiplicities with logUp
+        let num_sorted = lcs.map_or(0, |lcs| {
+            lcs.configuration
+                .argument
+                .num_sorted(&lcs.configuration.lookup_info)
+        });
+        if lcs.is_some() {
+            rounds.push(Round {
+                name: "lookup sorted",
+                elements: commitments(num_sorted),
+                challenges: vec![],
+            });
+        }
+        rounds.push(Round {
+            name: "beta and gamma",
+            elements: ElementCount::default(),
+            challenges: vec!["beta", "gamma"],
+        });
+
+        // the aggregations of the permutation and of the lookups
+        rounds.push(Round {
+            name: "aggregation",
+            elements: commitments(1 + lcs.is_some() as usize),
+            challenges: vec!["alpha"],
+        });
+
+        // the quotient, of degree 7 n
+        rounds.push(Round {
+            name: "quotient",
+            elements: commitments(7),
+            challenges: vec!["zeta"],
+        });
+
+        // the evaluations, in the order of the prover
+        let feature_flags = &self.feature_flags;
+        let optional_selectors = [
+            feature_flags.range_check0,
+            feature_flags.range_check1,
+            feature_flags.foreign_field_add,
+            feature_flags.foreign_field_mul,
+            feature_flags.xor,
+            feature_flags.rot,
+            self.custom_gates.is_some(),
+        ];
+        // the public input, the witness, the permutation and the selectors
+        let mut num_evaluations = 1 + COLUMNS + 1 + (PERMUTS - 1) + COLUMNS + 6;
+        num_evaluations += optional_selectors.iter().filter(|x| **x).count();
+        if let Some(lcs) = lcs {
+            let lookup_selectors = [
+                lcs.lookup_selectors.xor.is_some(),
+                lcs.lookup_selectors.lookup.is_some(),
+                lcs.lookup_selectors.range_check.is_some(),
+                lcs.lookup_selectors.ffmul.is_some(),
+                lcs.lookup_selectors.vector_lookup.is_some(),
+            ];
+            // the aggregation, the table, and the sorted polynomials
+            num_evaluations += 2 + num_sorted;
+            num_evaluations += 2 * runtime_tables as usize;
+            num_evaluations += lookup_selectors.iter().filter(|x| **x).count();
+        }
+        rounds.push(Round {
+            name: "evaluations",
+            elements: ElementCount {
+                points: 0,
+                // the chunks at zeta and zeta * omega, and ft_eval1
+                scalars: 2 * num_chunks * num_evaluations + 1,
+            },
+            challenges: vec!["v", "u"],
+        });
+
+        rounds.push(Round {
+            name: "opening",
+            elements: opening,
+            challenges: vec![],
+        });
+
+        ProofShape {
+            domain_size: d1_size,
+            num_chunks,
+            num_evaluations,
+            rounds,
+            point_size: G::zero().serialized_size(),
+            scalar_size: G::ScalarField::zero().serialized_size(),
+        }
+    }
+}
@@ -1,6 +1,23 @@
 //! This module implements the [`ProverError`] type.
+//!
+//! Each error has a stable code, returned by its method `code`, for the
+//! services embedding the prover and the verifier to handle the errors without
+//! parsing their messages. The errors of a witness expose the offending row,
+//! returned by their method `row`, and the errors wrapping another one expose
+//! it as their [std::error::Error::source].
 
-use crate::circuits::lookup::index::LookupError; // not sure about hierarchy
+use crate::circuits::{
+    argument::ArgumentType,
+    berkeley_columns::Column,
+    custom_gate::CustomGateError,
+    gate::CircuitGateError,
+    lookup::{
+        index::LookupError,
+        tables::{fixed::FixedTableError, xor::XorTableBits},
+    },
+}; // not sure about hierarchy
+use mina_poseidon::transcript::TranscriptError;
+use o1_utils::cancellation::Cancelled;
 use poly_commitment::error::CommitmentError;
 use thiserror::Error;
 
@@ -32,7 +49,70 @@ pub enum ProverError {
     RuntimeTablesInconsistent,
 
     #[error("wrong number of custom blinders given: {0}")]
-    WrongBlinders(CommitmentError),
+    WrongBlinders(#[source] CommitmentError),
+
+    #[error("the public input at position {0} does not match the encoding of its layout")]
+    InvalidPublicInput(usize),
+
+    #[error("the proof creation was cancelled")]
+    Cancelled(#[from] Cancelled),
+
+    #[error("the witness column {0} differs from the values of its committed column")]
+    CommittedColumnMismatch(usize),
+
+    #[error("there are not enough random rows to hide the uses of the committed column {column} (expected: {expected}, got: {got})")]
+    CommittedColumnNotZeroKnowledge {
+        column: usize,
+        expected: usize,
+        got: usize,
+    },
+
+    #[error("the committed column {column} was already used by its maximum of {max_uses} proofs")]
+    CommittedColumnExhausted { column: usize, max_uses: usize },
+
+    #[error("the Fiat-Shamir transcript is invalid: {0}")]
+    Transcript(#[from] TranscriptError),
+
+    /// The constraints of the argument do not vanish at the row, reported
+    /// instead of the remainder of the division by the vanishing polynomial
+    /// with the `quotient_diagnostics` feature
+    #[error("the constraints of the {0:?} argument are not satisfied at row {1}")]
+    UnsatisfiedConstraints(ArgumentType, usize),
+}
+
+impl ProverError {
+    /// Stable code of the error, which does not change with its message
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProverError::NoRoomForZkInWitness => "prover.no_room_for_zk_in_witness",
+            ProverError::NotZeroKnowledge(_, _) => "prover.not_zero_knowledge",
+            ProverError::WitnessCsInconsistent => "prover.witness_cs_inconsistent",
+            ProverError::Prover(_) => "prover.prover",
+            ProverError::Permutation(_) => "prover.permutation",
+            ProverError::ValueNotInTable(_) => "prover.value_not_in_table",
+            ProverError::RuntimeTablesInconsistent => "prover.runtime_tables_inconsistent",
+            ProverError::WrongBlinders(_) => "prover.wrong_blinders",
+            ProverError::InvalidPublicInput(_) => "prover.invalid_public_input",
+            ProverError::Cancelled(_) => "prover.cancelled",
+            ProverError::CommittedColumnMismatch(_) => "prover.committed_column_mismatch",
+            ProverError::CommittedColumnNotZeroKnowledge { .. } => {
+                "prover.committed_column_not_zero_knowledge"
+            }
+            ProverError::CommittedColumnExhausted { .. } => "prover.committed_column_exhausted",
+            ProverError::Transcript(_) => "prover.transcript",
+            ProverError::UnsatisfiedConstraints(_, _) => "prover.unsatisfied_constraints",
+        }
+    }
+
+    /// The row of the witness at which the error occurred, if any
+    pub fn row(&self) -> Option<usize> {
+        match self {
+            ProverError::ValueNotInTable(row)
+            | ProverError::InvalidPublicInput(row)
+            | ProverError::UnsatisfiedConstraints(_, row) => Some(*row),
+            _ => None,
+        }
+    }
 }
 
 /// Errors that can arise when verifying a proof
@@ -81,6 +161,65 @@ pub enum VerifyError {
 
     #[error("the commitment for {0:?} is missing")]
     MissingCommitment(crate::circuits::berkeley_columns::Column),
+
+    #[error("a split circuit has {0} sub-circuits, but {1} proofs were given")]
+    IncorrectSubProofsLength(usize, usize),
+
+    #[error("the public inputs of the link {0} between sub-circuits differ")]
+    SplitLink(usize),
+
+    #[error("the shared lookup tables are not attached to the verifier index")]
+    MissingSharedLookupTables,
+
+    #[error("the public input at position {0} does not match the encoding of its layout")]
+    InvalidPublicInput(usize),
+
+    #[error("the proof was created with an index of another circuit (the index digests differ)")]
+    IndexDigestMismatch,
+
+    #[error("the proof does not carry the digest of its index, required by the verifier index")]
+    MissingIndexDigest,
+
+    #[error("the commitment of the committed column {0:?} differs from the one given")]
+    CommittedColumnMismatch(Column),
+
+    #[error("the Fiat-Shamir transcript is invalid: {0}")]
+    Transcript(#[from] TranscriptError),
+}
+
+impl VerifyError {
+    /// Stable code of the error, which does not change with its message
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyError::IncorrectCommitmentLength(_, _, _) => "verify.incorrect_commitment_length",
+            VerifyError::IncorrectPubicInputLength(_) => "verify.incorrect_public_input_length",
+            VerifyError::IncorrectPrevChallengesLength(_, _) => {
+                "verify.incorrect_prev_challenges_length"
+            }
+            VerifyError::IncorrectEvaluationsLength(_, _, _) => {
+                "verify.incorrect_evaluations_length"
+            }
+            VerifyError::OpenProof => "verify.open_proof",
+            VerifyError::LookupCommitmentMissing => "verify.lookup_commitment_missing",
+            VerifyError::LookupEvalsMissing => "verify.lookup_evals_missing",
+            VerifyError::ProofInconsistentLookup => "verify.proof_inconsistent_lookup",
+            VerifyError::DifferentSRS => "verify.different_srs",
+            VerifyError::SRSTooSmall => "verify.srs_too_small",
+            VerifyError::IncorrectRuntimeProof => "verify.incorrect_runtime_proof",
+            VerifyError::MissingEvaluation(_) => "verify.missing_evaluation",
+            VerifyError::MissingPublicInputEvaluation => "verify.missing_public_input_evaluation",
+            VerifyError::MissingCommitment(_) => "verify.missing_commitment",
+            VerifyError::IncorrectSubProofsLength(_, _) => "verify.incorrect_sub_proofs_length",
+            VerifyError::SplitLink(_) => "verify.split_link",
+            VerifyError::MissingSharedLookupTables => "verify.missing_shared_lookup_tables",
+            VerifyError::InvalidPublicInput(_) => "verify.invalid_public_input",
+            VerifyError::IndexDigestMismatch => "verify.index_digest_mismatch",
+            VerifyError::MissingIndexDigest => "verify.missing_index_digest",
+            VerifyError::CommittedColumnMismatch(_) => "verify.committed_column_mismatch",
+            VerifyError::Transcript(_) => "verify.transcript",
+        }
+    }
+
 }
 
 /// Errors that can arise when preparing the setup
@@ -100,10 +239,92 @@ pub enum SetupError {
     ConstraintSystem(String),
 
     #[error("the domain could not be constructed: {0}")]
-    DomainCreation(DomainCreationError),
+    DomainCreation(#[source] DomainCreationError),
 
     #[error("the lookup constraint system cannot not be constructed: {0}")]
-    LookupCreation(LookupError),
+    LookupCreation(#[source] LookupError),
+
+    #[error("the custom gates cannot be set up: {0}")]
+    CustomGate(#[source] CustomGateError),
+
+    #[error("the fixed lookup tables cannot be set up: {0}")]
+    FixedTable(#[source] FixedTableError),
+
+    #[error("there are not enough zero-knowledge rows (expected at least: {0}, got: {1})")]
+    NotEnoughZkRows(u64, u64),
+
+    #[error("the Xor16 gate of row {0} does not have the coefficients of the {1:?} XOR table")]
+    XorTable(usize, XorTableBits),
+}
+
+impl SetupError {
+    /// Stable code of the error, which does not change with its message
+    pub fn code(&self) -> &'static str {
+        match self {
+            SetupError::ConstraintSystem(_) => "setup.constraint_system",
+            SetupError::DomainCreation(_) => "setup.domain_creation",
+            SetupError::LookupCreation(_) => "setup.lookup_creation",
+            SetupError::CustomGate(_) => "setup.custom_gate",
+            SetupError::FixedTable(_) => "setup.fixed_table",
+            SetupError::NotEnoughZkRows(_, _) => "setup.not_enough_zk_rows",
+            SetupError::XorTable(_, _) => "setup.xor_table",
+        }
+    }
+}
+
+/// Errors that can arise when checking a witness against a constraint system
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WitnessError {
+    #[error("the witness columns are not all the same size")]
+    WitnessCsInconsistent,
+
+    #[error("the witness has {0} rows, but at most {1} rows are available")]
+    TooManyRows(usize, usize),
+
+    #[error("expected {0} public inputs, got {1}")]
+    PublicInputLength(usize, usize),
+
+    #[error("the gate at row {0} is not a public input gate")]
+    IncorrectPublic(usize),
+
+    #[error("the gate at row {0} is not satisfied: {1}")]
+    Gate(usize, #[source] CircuitGateError),
+
+    #[error("the generic gate at row {0} is not satisfied: {1}")]
+    Generic(usize, String),
+
+    #[error("the lookup failed to find a match in the table: row={row}, lookup={index}")]
+    ValueNotInTable { row: usize, index: usize },
+
+    #[error("the runtime tables provided did not match the index's configuration")]
+    RuntimeTablesInconsistent,
+}
+
+impl WitnessError {
+    /// Stable code of the error, which does not change with its message
+    pub fn code(&self) -> &'static str {
+        match self {
+            WitnessError::WitnessCsInconsistent => "witness.witness_cs_inconsistent",
+            WitnessError::TooManyRows(_, _) => "witness.too_many_rows",
+            WitnessError::PublicInputLength(_, _) => "witness.public_input_length",
+            WitnessError::IncorrectPublic(_) => "witness.incorrect_public",
+            WitnessError::Gate(_, _) => "witness.gate",
+            WitnessError::Generic(_, _) => "witness.generic",
+            WitnessError::ValueNotInTable { .. } => "witness.value_not_in_table",
+            WitnessError::RuntimeTablesInconsistent => "witness.runtime_tables_inconsistent",
+        }
+    }
+
+    /// The row of the witness at which the error occurred, if any
+    pub fn row(&self) -> Option<usize> {
+        match self {
+            WitnessError::IncorrectPublic(row)
+            | WitnessError::Gate(row, _)
+            | WitnessError::Generic(row, _)
+            | WitnessError::ValueNotInTable { row, .. } => Some(*row),
+            _ => None,
+        }
+    }
 }
 
 /// Errors that can arise when creating a verifier index
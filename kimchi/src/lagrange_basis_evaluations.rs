@@ -1,3 +1,4 @@
+use crate::circuits::constraints::num_chunks;
 use ark_ff::{batch_inversion_and_mul, FftField};
 use ark_poly::{EvaluationDomain, Evaluations, Radix2EvaluationDomain as D};
 use rayon::prelude::*;
@@ -120,13 +121,18 @@ impl<F: FftField> LagrangeBasisEvaluations<F> {
         x: F,
     ) -> LagrangeBasisEvaluations<F> {
         let n = domain.size();
-        let num_chunks = n / max_poly_size;
+        let num_chunks = num_chunks(n, max_poly_size);
         let mut evals = Vec::with_capacity(num_chunks);
         for i in 0..num_chunks {
             let mut x_pow = F::one();
             let mut chunked_evals = vec![F::zero(); n];
-            for j in 0..max_poly_size {
-                chunked_evals[i * max_poly_size + j] = x_pow;
+            // the last chunk is smaller if `max_poly_size` does not divide `n`
+            for eval in chunked_evals
+                .iter_mut()
+                .skip(i * max_poly_size)
+                .take(max_poly_size)
+            {
+                *eval = x_pow;
                 x_pow *= x;
             }
             // This uses the same trick as `poly_commitment::srs::SRS::add_lagrange_basis`, but
@@ -226,6 +232,32 @@ mod tests {
         assert_eq!(y, expected)
     }
 
+    #[test]
+    fn test_evaluation_chunked_with_uneven_chunks() {
+        use o1_utils::ExtendedDensePolynomial;
+
+        let rng = &mut StdRng::from_seed([0u8; 32]);
+        let n = 1 << 4;
+        let max_poly_size = 5;
+        let domain = Radix2EvaluationDomain::new(n).unwrap();
+
+        let evals =
+            Evaluations::from_vec_and_domain((0..n).map(|_| Fp::rand(rng)).collect(), domain);
+        let x = Fp::rand(rng);
+
+        let evaluator = LagrangeBasisEvaluations::new(max_poly_size, domain, x);
+
+        let y = evaluator.evaluate(&evals);
+        let expected: Vec<_> = evals
+            .interpolate()
+            .to_chunked_polynomial(4, max_poly_size)
+            .polys
+            .iter()
+            .map(|chunk| chunk.evaluate(&x))
+            .collect();
+        assert_eq!(y, expected)
+    }
+
     #[test]
     fn test_evaluation_boolean() {
         let rng = &mut StdRng::from_seed([0u8; 32]);
@@ -13,10 +13,14 @@ pub use turshi;
 pub mod alphas;
 pub mod bench;
 pub mod circuits;
+pub mod committed_column;
+pub mod compatibility;
 pub mod curve;
+pub mod dry_run;
 pub mod error;
 pub mod lagrange_basis_evaluations;
 pub mod linearization;
+pub mod opening_order;
 pub mod oracles;
 pub mod plonk_sponge;
 pub mod precomputed_srs;
@@ -24,6 +28,9 @@ pub mod proof;
 pub mod prover;
 pub mod prover_index;
 pub mod snarky;
+#[cfg(feature = "bn254")]
+pub mod solidity;
+pub mod transcript;
 pub mod verifier;
 pub mod verifier_index;
 
@@ -4,9 +4,10 @@ use crate::{
     alphas::Alphas,
     circuits::{
         argument::{Argument, ArgumentType},
+        custom_gate::CustomGateRegistry,
         expr, lookup,
         lookup::{
-            constraints::LookupConfiguration,
+            constraints::{LookupArgument, LookupConfiguration},
             lookups::{LookupFeatures, LookupInfo, LookupPattern, LookupPatterns},
         },
         polynomials::{
@@ -27,21 +28,43 @@ use crate::{
 
 use crate::circuits::{
     berkeley_columns::Column,
-    constraints::FeatureFlags,
+    constraints::{BaseGates, FeatureFlags},
     expr::{ConstantExpr, Expr, FeatureFlag, Linearization, PolishToken},
     gate::GateType,
     wires::COLUMNS,
 };
 use ark_ff::{FftField, PrimeField, SquareRootField, Zero};
 
+// The lookup configuration of the features, with the given maximum number of
+// lookups per row if any
+fn lookup_info(features: LookupFeatures, max_lookups_per_row: Option<usize>) -> LookupInfo {
+    let lookup_info = LookupInfo::create(features);
+    match max_lookups_per_row {
+        Some(max_per_row) => lookup_info
+            .with_max_per_row(max_per_row)
+            .expect("the maximum number of lookups per row is checked by the constraint system"),
+        None => lookup_info,
+    }
+}
+
 /// Get the expresion of constraints.
 ///
+/// The constraints of the base gates are only included if they are used, see
+/// [BaseGates], and the constraints of the `custom_gates` are included if they
+/// are provided.
+/// The lookup constraints use `max_lookups_per_row` lookups per row if it is
+/// provided, and the largest number of lookups per row of the patterns otherwise.
+///
 /// # Panics
 ///
 /// Will panic if `generic_gate` is not associate with `alpha^0`.
 pub fn constraints_expr<F: PrimeField + SquareRootField>(
     feature_flags: Option<&FeatureFlags>,
     generic: bool,
+    base_gates: &BaseGates,
+    custom_gates: Option<&CustomGateRegistry<F>>,
+    lookup_argument: LookupArgument,
+    max_lookups_per_row: Option<usize>,
 ) -> (Expr<ConstantExpr<F>, Column>, Alphas<F>) {
     // register powers of alpha so that we don't reuse them across mutually inclusive constraints
     let mut powers_of_alpha = Alphas::<F>::default();
@@ -55,11 +78,22 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
 
     let mut cache = expr::Cache::default();
 
-    let mut expr = Poseidon::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += VarbaseMul::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += CompleteAdd::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += EndosclMul::combined_constraints(&powers_of_alpha, &mut cache);
-    expr += EndomulScalar::combined_constraints(&powers_of_alpha, &mut cache);
+    let mut expr = Expr::zero();
+    if base_gates.poseidon {
+        expr += Poseidon::combined_constraints(&powers_of_alpha, &mut cache);
+    }
+    if base_gates.var_base_mul {
+        expr += VarbaseMul::combined_constraints(&powers_of_alpha, &mut cache);
+    }
+    if base_gates.complete_add {
+        expr += CompleteAdd::combined_constraints(&powers_of_alpha, &mut cache);
+    }
+    if base_gates.endo_mul {
+        expr += EndosclMul::combined_constraints(&powers_of_alpha, &mut cache);
+    }
+    if base_gates.endo_mul_scalar {
+        expr += EndomulScalar::combined_constraints(&powers_of_alpha, &mut cache);
+    }
 
     {
         let mut range_check0_expr =
@@ -157,6 +191,10 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
         }
     }
 
+    if let Some(custom_gates) = custom_gates {
+        expr += custom_gates.combined_constraints(&powers_of_alpha, &mut cache);
+    }
+
     if generic {
         expr += generic::Generic::combined_constraints(&powers_of_alpha, &mut cache);
     }
@@ -167,8 +205,11 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
     // lookup
     if let Some(feature_flags) = feature_flags {
         if feature_flags.lookup_features.patterns != LookupPatterns::default() {
-            let lookup_configuration =
-                LookupConfiguration::new(LookupInfo::create(feature_flags.lookup_features));
+            let lookup_configuration = LookupConfiguration::new(lookup_info(
+                feature_flags.lookup_features,
+                max_lookups_per_row,
+            ))
+            .argument(lookup_argument);
             let constraints = lookup::constraints::constraints(&lookup_configuration, false);
 
             // note: the number of constraints depends on the lookup configuration,
@@ -190,6 +231,7 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
                 lookup: true,
                 range_check: true,
                 foreign_field_mul: true,
+                vector_lookup: true,
             },
             uses_runtime_tables: true,
             joint_lookup_used: true,
@@ -223,10 +265,22 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
     }
 
     // Check that the feature flags correctly turn on or off the constraints generated by the given
-    // flags.
-    if cfg!(feature = "check_feature_flags") {
-        if let Some(feature_flags) = feature_flags {
-            let (feature_flagged_expr, _) = constraints_expr(None, generic);
+    // flags. The expression generated without flags only supports the plookup argument, with
+    // the default number of lookups per row.
+    if cfg!(feature = "check_feature_flags") && lookup_argument == LookupArgument::Plookup {
+        if let Some(feature_flags) = feature_flags.filter(|feature_flags| {
+            max_lookups_per_row.map_or(true, |max_per_row| {
+                max_per_row == LookupInfo::create(feature_flags.lookup_features).max_per_row
+            })
+        }) {
+            let (feature_flagged_expr, _) = constraints_expr(
+                None,
+                generic,
+                base_gates,
+                custom_gates,
+                LookupArgument::Plookup,
+                None,
+            );
             let feature_flagged_expr = feature_flagged_expr.apply_feature_flags(feature_flags);
             assert_eq!(expr, feature_flagged_expr);
         }
@@ -240,6 +294,8 @@ pub fn constraints_expr<F: PrimeField + SquareRootField>(
 /// for the linearization to work.
 pub fn linearization_columns<F: FftField + SquareRootField>(
     feature_flags: Option<&FeatureFlags>,
+    lookup_argument: LookupArgument,
+    max_lookups_per_row: Option<usize>,
 ) -> std::collections::HashSet<Column> {
     let mut h = std::collections::HashSet::new();
     use Column::*;
@@ -262,6 +318,7 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
                         lookup: true,
                         range_check: true,
                         foreign_field_mul: true,
+                        vector_lookup: true,
                     },
                     joint_lookup_used: true,
                     uses_runtime_tables: true,
@@ -283,12 +340,15 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
     let lookup_info = if feature_flags.lookup_features.patterns == LookupPatterns::default() {
         None
     } else {
-        Some(LookupInfo::create(feature_flags.lookup_features))
+        Some(lookup_info(
+            feature_flags.lookup_features,
+            max_lookups_per_row,
+        ))
     };
 
     // the lookup polynomials
     if let Some(lookup_info) = lookup_info {
-        for i in 0..=lookup_info.max_per_row {
+        for i in 0..lookup_argument.num_sorted(&lookup_info) {
             h.insert(LookupSorted(i));
         }
         h.insert(LookupAggreg);
@@ -321,6 +381,7 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
     h.insert(Index(GateType::ForeignFieldMul));
     h.insert(Index(GateType::Xor16));
     h.insert(Index(GateType::Rot64));
+    h.insert(Index(GateType::Custom));
 
     // lookup selectors
     h.insert(LookupRuntimeSelector);
@@ -328,6 +389,7 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
     h.insert(LookupKindIndex(LookupPattern::Lookup));
     h.insert(LookupKindIndex(LookupPattern::RangeCheck));
     h.insert(LookupKindIndex(LookupPattern::ForeignFieldMul));
+    h.insert(LookupKindIndex(LookupPattern::VectorLookup));
 
     h
 }
@@ -336,6 +398,8 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
 ///
 /// If the `feature_flags` argument is `None`, this will generate an expression using the
 /// `Expr::IfFeature` variant for each of the flags.
+/// The constraints of the unused base gates are left out, see [BaseGates].
+/// The constraints of the `custom_gates` are included if they are provided.
 ///
 /// # Panics
 ///
@@ -344,15 +408,30 @@ pub fn linearization_columns<F: FftField + SquareRootField>(
 pub fn expr_linearization<F: PrimeField + SquareRootField>(
     feature_flags: Option<&FeatureFlags>,
     generic: bool,
+    base_gates: &BaseGates,
+    custom_gates: Option<&CustomGateRegistry<F>>,
+    lookup_argument: LookupArgument,
+    max_lookups_per_row: Option<usize>,
 ) -> (
     Linearization<Vec<PolishToken<F, Column>>, Column>,
     Alphas<F>,
 ) {
-    let evaluated_cols = linearization_columns::<F>(feature_flags);
-
-    let (expr, powers_of_alpha) = constraints_expr(feature_flags, generic);
+    let evaluated_cols =
+        linearization_columns::<F>(feature_flags, lookup_argument, max_lookups_per_row);
+
+    let (expr, powers_of_alpha) = constraints_expr(
+        feature_flags,
+        generic,
+        base_gates,
+        custom_gates,
+        lookup_argument,
+        max_lookups_per_row,
+    );
 
+    // fold the constants and share the common subexpressions before
+    // linearizing, to reduce the work of the evaluation of the linearization
     let linearization = expr
+        .optimize()
         .linearize(evaluated_cols)
         .unwrap()
         .map(|e| e.to_polish());
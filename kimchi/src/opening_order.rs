@@ -0,0 +1,253 @@
+//! This module defines the canonical order of the polynomials opened by a
+//! kimchi proof.
+//!
+//! The batched opening proof combines the evaluations of all the committed
+//! polynomials with powers of the challenges `polyscale` and `evalscale`, so a
+//! verifier must list the commitments in exactly the same order as the prover.
+//! External verifiers (e.g. written in Solidity, or o1js) hard-code this order,
+//! which therefore cannot change silently when the Rust code is refactored.
+//!
+//! The order is defined once, and used by both the prover (through
+//! [ProverIndex::opening_order]) and the verifier (through [opening_order]).
+//! It is versioned by [OPENING_ORDER_VERSION], and pinned by the tests through
+//! [opening_order_digest]: an external verifier can opt in to the stability
+//! guarantee by checking the digest of the order it implements against the
+//! digest of the verifier index it is given.
+//!
+//! The order is:
+//!
+//! 1. the commitments to the challenge polynomials of the previous proofs
+//!    (recursion), in the order of the proof,
+//! 2. the public input,
+//! 3. the (chunked) `ft` polynomial,
+//! 4. the permutation aggregation [Column::Z],
+//! 5. the selectors of the `Generic`, `Poseidon`, `CompleteAdd`,
+//!    `VarBaseMul`, `EndoMul` and `EndoMulScalar` gates,
+//! 6. the witness columns, then the coefficient columns, then the first
+//!    `PERMUTS - 1` permutation columns,
+//! 7. the selectors of the optional gates, if present: `RangeCheck0`,
+//!    `RangeCheck1`, `ForeignFieldAdd`, `ForeignFieldMul`, `Xor16`, `Rot64`
+//!    and `Custom`,
+//! 8. if lookups are used, the sorted polynomials (or the multiplicities with
+//!    the logUp argument), the aggregation, the combined table, the runtime
+//!    table if any, the runtime selector if any, and the lookup selectors of
+//!    the `Xor`, `Lookup`, `RangeCheck` and `ForeignFieldMul` patterns that are
+//!    present.
+
+use crate::{
+    circuits::{
+        berkeley_columns::Column,
+        gate::GateType,
+        lookup::{index::LookupSelectors, lookups::LookupPattern},
+        wires::{COLUMNS, PERMUTS},
+    },
+    curve::KimchiCurve,
+    prover_index::ProverIndex,
+    verifier_index::VerifierIndex,
+};
+use blake2::{Blake2b512, Digest};
+use poly_commitment::OpenProof;
+use std::fmt;
+
+/// The version of the canonical order. It is incremented whenever the order
+/// defined by [opening_order] changes.
+pub const OPENING_ORDER_VERSION: u32 = 1;
+
+/// A polynomial opened by the batched opening proof
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OpenedPolynomial {
+    /// The challenge polynomial of the `i`-th previous proof
+    PreviousChallenge(usize),
+    /// The public input polynomial
+    Public,
+    /// The `ft` polynomial, see [Maller's optimization](https://o1-labs.github.io/proof-systems/kimchi/maller_15.html)
+    Ft,
+    /// A polynomial of the circuit or of the proof
+    Column(Column),
+}
+
+impl fmt::Display for OpenedPolynomial {
+    /// Writes the canonical name of the polynomial, which is hashed by [opening_order_digest]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenedPolynomial::PreviousChallenge(i) => write!(f, "PreviousChallenge({i})"),
+            OpenedPolynomial::Public => write!(f, "Public"),
+            OpenedPolynomial::Ft => write!(f, "Ft"),
+            OpenedPolynomial::Column(col) => write!(f, "{col:?}"),
+        }
+    }
+}
+
+/// The lookup selectors of an index, with whether they are present
+type LookupSelectorsLayout = [(bool, LookupPattern); 5];
+
+/// The presence of the optional polynomials of an index, from which the
+/// canonical order is derived. It is described identically by the prover and
+/// the verifier indexes.
+struct Layout {
+    prev_challenges: usize,
+    /// The optional gates, with whether their selector is present
+    optional_gates: [(bool, GateType); 7],
+    /// The number of sorted polynomials (or multiplicities), whether a runtime
+    /// table is used, and the lookup selectors that are present
+    lookup: Option<(usize, bool, LookupSelectorsLayout)>,
+}
+
+impl Layout {
+    fn lookup_selectors<T>(selectors: &LookupSelectors<T>) -> LookupSelectorsLayout {
+        [
+            (selectors.xor.is_some(), LookupPattern::Xor),
+            (selectors.lookup.is_some(), LookupPattern::Lookup),
+            (selectors.range_check.is_some(), LookupPattern::RangeCheck),
+            (selectors.ffmul.is_some(), LookupPattern::ForeignFieldMul),
+            (
+                selectors.vector_lookup.is_some(),
+                LookupPattern::VectorLookup,
+            ),
+        ]
+    }
+
+    fn order(self) -> Vec<OpenedPolynomial> {
+        let mut order: Vec<_> = (0..self.prev_challenges)
+            .map(OpenedPolynomial::PreviousChallenge)
+            .collect();
+        order.push(OpenedPolynomial::Public);
+        order.push(OpenedPolynomial::Ft);
+
+        let mut columns = vec![
+            Column::Z,
+            Column::Index(GateType::Generic),
+            Column::Index(GateType::Poseidon),
+            Column::Index(GateType::CompleteAdd),
+            Column::Index(GateType::VarBaseMul),
+            Column::Index(GateType::EndoMul),
+            Column::Index(GateType::EndoMulScalar),
+        ];
+        columns.extend((0..COLUMNS).map(Column::Witness));
+        columns.extend((0..COLUMNS).map(Column::Coefficient));
+        columns.extend((0..PERMUTS - 1).map(Column::Permutation));
+
+        columns.extend(
+            self.optional_gates
+                .into_iter()
+                .filter_map(|(present, typ)| present.then_some(Column::Index(typ))),
+        );
+
+        if let Some((num_sorted, runtime, selectors)) = self.lookup {
+            columns.extend((0..num_sorted).map(Column::LookupSorted));
+            columns.push(Column::LookupAggreg);
+            columns.push(Column::LookupTable);
+
+            if runtime {
+                columns.push(Column::LookupRuntimeTable);
+                columns.push(Column::LookupRuntimeSelector);
+            }
+
+            columns.extend(selectors.into_iter().filter_map(|(present, pattern)| {
+                present.then_some(Column::LookupKindIndex(pattern))
+            }));
+        }
+
+        order.extend(columns.into_iter().map(OpenedPolynomial::Column));
+        order
+    }
+}
+
+/// Returns the polynomials opened by the proofs of `index`, in the order in
+/// which they are combined by the batched opening proof.
+pub fn opening_order<G: KimchiCurve, OpeningProof: OpenProof<G>>(
+    index: &VerifierIndex<G, OpeningProof>,
+) -> Vec<OpenedPolynomial> {
+    Layout {
+        prev_challenges: index.prev_challenges,
+        optional_gates: [
+            (index.range_check0_comm.is_some(), GateType::RangeCheck0),
+            (index.range_check1_comm.is_some(), GateType::RangeCheck1),
+            (
+                index.foreign_field_add_comm.is_some(),
+                GateType::ForeignFieldAdd,
+            ),
+            (
+                index.foreign_field_mul_comm.is_some(),
+                GateType::ForeignFieldMul,
+            ),
+            (index.xor_comm.is_some(), GateType::Xor16),
+            (index.rot_comm.is_some(), GateType::Rot64),
+            (index.custom_comm.is_some(), GateType::Custom),
+        ],
+        lookup: index.lookup_index.as_ref().map(|li| {
+            (
+                li.argument.num_sorted(&li.lookup_info),
+                li.runtime_tables_selector.is_some(),
+                Layout::lookup_selectors(&li.lookup_selectors),
+            )
+        }),
+    }
+    .order()
+}
+
+/// Returns the hexadecimal Blake2b digest of the canonical names of `order`,
+/// separated by newlines and prefixed by [OPENING_ORDER_VERSION].
+pub fn opening_order_digest(order: &[OpenedPolynomial]) -> String {
+    let mut hasher = Blake2b512::new();
+    hasher.update(format!("v{OPENING_ORDER_VERSION}"));
+    for polynomial in order {
+        hasher.update(format!("\n{polynomial}"));
+    }
+    hex::encode(hasher.finalize())
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> ProverIndex<G, OpeningProof> {
+    /// Returns the polynomials opened by the proofs of this index, in the
+    /// order in which the prover combines them. This is the same order as
+    /// [opening_order] on the verifier index of `self`.
+    pub fn opening_order(&self) -> Vec<OpenedPolynomial> {
+        let evals = &self.column_evaluations;
+        Layout {
+            prev_challenges: self.cs.prev_challenges,
+            optional_gates: [
+                (
+                    evals.range_check0_selector8.is_some(),
+                    GateType::RangeCheck0,
+                ),
+                (
+                    evals.range_check1_selector8.is_some(),
+                    GateType::RangeCheck1,
+                ),
+                (
+                    evals.foreign_field_add_selector8.is_some(),
+                    GateType::ForeignFieldAdd,
+                ),
+                (
+                    evals.foreign_field_mul_selector8.is_some(),
+                    GateType::ForeignFieldMul,
+                ),
+                (evals.xor_selector8.is_some(), GateType::Xor16),
+                (evals.rot_selector8.is_some(), GateType::Rot64),
+                (evals.custom_selector8.is_some(), GateType::Custom),
+            ],
+            lookup: self.cs.lookup_constraint_system.as_ref().map(|lcs| {
+                (
+                    lcs.configuration
+                        .argument
+                        .num_sorted(&lcs.configuration.lookup_info),
+                    lcs.runtime_selector.is_some(),
+                    Layout::lookup_selectors(&lcs.lookup_selectors),
+                )
+            }),
+        }
+        .order()
+    }
+}
+
+impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof> {
+    /// Returns the polynomials opened by the proofs of this index, see [opening_order]
+    pub fn opening_order(&self) -> Vec<OpenedPolynomial> {
+        opening_order(self)
+    }
+
+    /// Returns the digest of the opening order of this index, see [opening_order_digest]
+    pub fn opening_order_digest(&self) -> String {
+        opening_order_digest(&self.opening_order())
+    }
+}
@@ -81,6 +81,7 @@ impl<Fr: PrimeField> FrSponge<Fr> for DefaultFrSponge<Fr, SC> {
             foreign_field_mul_selector,
             xor_selector,
             rot_selector,
+            custom_selector,
             lookup_aggregation,
             lookup_table,
             lookup_sorted,
@@ -90,6 +91,7 @@ impl<Fr: PrimeField> FrSponge<Fr> for DefaultFrSponge<Fr, SC> {
             lookup_gate_lookup_selector,
             range_check_lookup_selector,
             foreign_field_mul_lookup_selector,
+            vector_lookup_selector,
         } = e;
 
         let mut points = vec![
@@ -125,6 +127,9 @@ impl<Fr: PrimeField> FrSponge<Fr> for DefaultFrSponge<Fr, SC> {
         if let Some(rot_selector) = rot_selector.as_ref() {
             points.push(rot_selector)
         }
+        if let Some(custom_selector) = custom_selector.as_ref() {
+            points.push(custom_selector)
+        }
         if let Some(lookup_aggregation) = lookup_aggregation.as_ref() {
             points.push(lookup_aggregation)
         }
@@ -155,6 +160,9 @@ impl<Fr: PrimeField> FrSponge<Fr> for DefaultFrSponge<Fr, SC> {
         {
             points.push(foreign_field_mul_lookup_selector)
         }
+        if let Some(vector_lookup_selector) = vector_lookup_selector.as_ref() {
+            points.push(vector_lookup_selector)
+        }
 
         points.into_iter().for_each(|p| {
             self.sponge.absorb(&p.zeta);
@@ -10,7 +10,10 @@ use ark_ec::AffineCurve;
 use ark_ff::{FftField, One, Zero};
 use ark_poly::univariate::DensePolynomial;
 use o1_utils::ExtendedDensePolynomial;
-use poly_commitment::commitment::{b_poly, b_poly_coefficients, PolyComm};
+use poly_commitment::{
+    aggregation::Accumulator,
+    commitment::{b_poly, b_poly_coefficients, CommitmentCurve, PolyComm},
+};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use std::array;
@@ -102,6 +105,16 @@ pub struct ProofEvaluations<Evals> {
     pub range_check_lookup_selector: Option<Evals>,
     /// evaluation of the ForeignFieldMul range check pattern selector polynomial
     pub foreign_field_mul_lookup_selector: Option<Evals>,
+
+    // Evaluations added after the first release: they are kept last and
+    // default to `None`, so that the proofs serialized before them (as arrays
+    // of fields by rmp_serde) still deserialize
+    /// evaluation of the VectorLookup pattern selector polynomial
+    #[serde(default)]
+    pub vector_lookup_selector: Option<Evals>,
+    /// evaluation of the custom gates selector polynomial
+    #[serde(default)]
+    pub custom_selector: Option<Evals>,
 }
 
 /// Commitments linked to the lookup feature
@@ -156,6 +169,13 @@ pub struct ProverProof<G: AffineCurve, OpeningProof> {
 
     /// The challenges underlying the optional polynomials folded into the proof
     pub prev_challenges: Vec<RecursionChallenge<G>>,
+
+    /// The integrity digest of the index used to create the proof (see
+    /// [`VerifierIndex::index_digest`](crate::verifier_index::VerifierIndex::index_digest)).
+    /// When present, the verifier checks that it is the digest of its own index.
+    #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
+    #[serde(default)]
+    pub index_digest: Option<G::BaseField>,
 }
 
 /// A struct to store the challenges inside a `ProverProof`
@@ -213,6 +233,7 @@ impl<Eval> ProofEvaluations<Eval> {
             foreign_field_mul_selector,
             xor_selector,
             rot_selector,
+            custom_selector,
             lookup_aggregation,
             lookup_table,
             lookup_sorted,
@@ -222,6 +243,7 @@ impl<Eval> ProofEvaluations<Eval> {
             lookup_gate_lookup_selector,
             range_check_lookup_selector,
             foreign_field_mul_lookup_selector,
+            vector_lookup_selector,
         } = self;
         ProofEvaluations {
             public: public.map(f),
@@ -241,6 +263,7 @@ impl<Eval> ProofEvaluations<Eval> {
             foreign_field_mul_selector: foreign_field_mul_selector.map(f),
             xor_selector: xor_selector.map(f),
             rot_selector: rot_selector.map(f),
+            custom_selector: custom_selector.map(f),
             lookup_aggregation: lookup_aggregation.map(f),
             lookup_table: lookup_table.map(f),
             lookup_sorted: lookup_sorted.map(|x| x.map(f)),
@@ -250,6 +273,7 @@ impl<Eval> ProofEvaluations<Eval> {
             lookup_gate_lookup_selector: lookup_gate_lookup_selector.map(f),
             range_check_lookup_selector: range_check_lookup_selector.map(f),
             foreign_field_mul_lookup_selector: foreign_field_mul_lookup_selector.map(f),
+            vector_lookup_selector: vector_lookup_selector.map(f),
         }
     }
 
@@ -272,6 +296,7 @@ impl<Eval> ProofEvaluations<Eval> {
             foreign_field_mul_selector,
             xor_selector,
             rot_selector,
+            custom_selector,
             lookup_aggregation,
             lookup_table,
             lookup_sorted,
@@ -281,6 +306,7 @@ impl<Eval> ProofEvaluations<Eval> {
             lookup_gate_lookup_selector,
             range_check_lookup_selector,
             foreign_field_mul_lookup_selector,
+            vector_lookup_selector,
         } = self;
         ProofEvaluations {
             public: public.as_ref().map(f),
@@ -332,6 +358,7 @@ impl<Eval> ProofEvaluations<Eval> {
             foreign_field_mul_selector: foreign_field_mul_selector.as_ref().map(f),
             xor_selector: xor_selector.as_ref().map(f),
             rot_selector: rot_selector.as_ref().map(f),
+            custom_selector: custom_selector.as_ref().map(f),
             lookup_aggregation: lookup_aggregation.as_ref().map(f),
             lookup_table: lookup_table.as_ref().map(f),
             lookup_sorted: array::from_fn(|i| lookup_sorted[i].as_ref().map(f)),
@@ -341,6 +368,7 @@ impl<Eval> ProofEvaluations<Eval> {
             lookup_gate_lookup_selector: lookup_gate_lookup_selector.as_ref().map(f),
             range_check_lookup_selector: range_check_lookup_selector.as_ref().map(f),
             foreign_field_mul_lookup_selector: foreign_field_mul_lookup_selector.as_ref().map(f),
+            vector_lookup_selector: vector_lookup_selector.as_ref().map(f),
         }
     }
 }
@@ -392,6 +420,24 @@ impl<G: AffineCurve> RecursionChallenge<G> {
     }
 }
 
+impl<G: CommitmentCurve> RecursionChallenge<G> {
+    /// The accumulator of the previous opening proof, or `None` if its
+    /// commitment is chunked
+    pub fn accumulator(&self) -> Option<Accumulator<G>> {
+        match self.comm.elems[..] {
+            [comm] => Some(Accumulator::new(self.chals.clone(), comm)),
+            _ => None,
+        }
+    }
+}
+
+impl<G: CommitmentCurve> From<Accumulator<G>> for RecursionChallenge<G> {
+    fn from(accumulator: Accumulator<G>) -> Self {
+        let Accumulator { chals, comm } = accumulator;
+        RecursionChallenge::new(chals, PolyComm { elems: vec![comm] })
+    }
+}
+
 impl<F: Zero + Copy> ProofEvaluations<PointEvaluations<F>> {
     pub fn dummy_with_witness_evaluations(
         curr: [F; COLUMNS],
@@ -419,6 +465,7 @@ impl<F: Zero + Copy> ProofEvaluations<PointEvaluations<F>> {
             foreign_field_mul_selector: None,
             xor_selector: None,
             rot_selector: None,
+            custom_selector: None,
             lookup_aggregation: None,
             lookup_table: None,
             lookup_sorted: array::from_fn(|_| None),
@@ -428,6 +475,7 @@ impl<F: Zero + Copy> ProofEvaluations<PointEvaluations<F>> {
             lookup_gate_lookup_selector: None,
             range_check_lookup_selector: None,
             foreign_field_mul_lookup_selector: None,
+            vector_lookup_selector: None,
         }
     }
 }
@@ -459,6 +507,9 @@ impl<F> ProofEvaluations<F> {
             Column::LookupKindIndex(LookupPattern::ForeignFieldMul) => {
                 self.foreign_field_mul_lookup_selector.as_ref()
             }
+            Column::LookupKindIndex(LookupPattern::VectorLookup) => {
+                self.vector_lookup_selector.as_ref()
+            }
             Column::LookupRuntimeSelector => self.runtime_lookup_table_selector.as_ref(),
             Column::LookupRuntimeTable => self.runtime_lookup_table.as_ref(),
             Column::Index(GateType::Generic) => Some(&self.generic_selector),
@@ -473,6 +524,7 @@ impl<F> ProofEvaluations<F> {
             Column::Index(GateType::ForeignFieldMul) => self.foreign_field_mul_selector.as_ref(),
             Column::Index(GateType::Xor16) => self.xor_selector.as_ref(),
             Column::Index(GateType::Rot64) => self.rot_selector.as_ref(),
+            Column::Index(GateType::Custom) => self.custom_selector.as_ref(),
             Column::Index(_) => None,
             Column::Coefficient(i) => Some(&self.coefficients[i]),
             Column::Permutation(i) => Some(&self.s[i]),
@@ -606,6 +658,18 @@ pub mod caml {
         pub foreign_field_mul_lookup_selector: Option<PointEvaluations<Vec<CamlF>>>,
     }
 
+    //
+    // CamlProofEvaluationsExt<CamlF>
+    //
+
+    /// The evaluations added after the first release, which the OCaml record
+    /// of [CamlProofEvaluations] does not have.
+    #[derive(Clone, ocaml::IntoValue, ocaml::FromValue, ocaml_gen::Struct)]
+    pub struct CamlProofEvaluationsExt<CamlF> {
+        pub vector_lookup_selector: Option<PointEvaluations<Vec<CamlF>>>,
+        pub custom_selector: Option<PointEvaluations<Vec<CamlF>>>,
+    }
+
     //
     // ProofEvaluations<Vec<F>> <-> CamlProofEvaluations<CamlF>
     //
@@ -953,6 +1017,7 @@ pub mod caml {
                 rot_selector: cpe
                     .rot_selector
                     .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect())),
+                custom_selector: None,
                 lookup_aggregation: cpe
                     .lookup_aggregation
                     .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect())),
@@ -985,7 +1050,67 @@ pub mod caml {
                 foreign_field_mul_lookup_selector: cpe
                     .foreign_field_mul_lookup_selector
                     .map(|x| x.map(&|x| x.iter().map(|x| x.clone().into()).collect())),
+                vector_lookup_selector: None,
             }
         }
     }
+
+    //
+    // ProofEvaluations<Vec<F>> <-> (CamlProofEvaluations<CamlF>, CamlProofEvaluationsExt<CamlF>)
+    //
+
+    impl<F, CamlF> From<ProofEvaluations<PointEvaluations<Vec<F>>>>
+        for (
+            Option<PointEvaluations<Vec<CamlF>>>,
+            CamlProofEvaluations<CamlF>,
+            CamlProofEvaluationsExt<CamlF>,
+        )
+    where
+        F: Clone,
+        CamlF: From<F>,
+    {
+        fn from(pe: ProofEvaluations<PointEvaluations<Vec<F>>>) -> Self {
+            let ext = CamlProofEvaluationsExt {
+                vector_lookup_selector: pe
+                    .vector_lookup_selector
+                    .clone()
+                    .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect())),
+                custom_selector: pe
+                    .custom_selector
+                    .clone()
+                    .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect())),
+            };
+            let (public, cpe) = pe.into();
+            (public, cpe, ext)
+        }
+    }
+
+    impl<F, CamlF>
+        From<(
+            Option<PointEvaluations<Vec<CamlF>>>,
+            CamlProofEvaluations<CamlF>,
+            CamlProofEvaluationsExt<CamlF>,
+        )> for ProofEvaluations<PointEvaluations<Vec<F>>>
+    where
+        F: Clone,
+        CamlF: Clone,
+        F: From<CamlF>,
+    {
+        fn from(
+            (public, cpe, ext): (
+                Option<PointEvaluations<Vec<CamlF>>>,
+                CamlProofEvaluations<CamlF>,
+                CamlProofEvaluationsExt<CamlF>,
+            ),
+        ) -> Self {
+            let mut pe: Self = (public, cpe).into();
+            pe.vector_lookup_selector = ext
+                .vector_lookup_selector
+                .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect()));
+            pe.custom_selector = ext
+                .custom_selector
+                .map(|x| x.map(&|x| x.into_iter().map(Into::into).collect()));
+            pe
+        }
+    }
 }
@@ -3,11 +3,17 @@
 use crate::{
     circuits::{
         argument::{Argument, ArgumentType},
-        berkeley_columns::{Environment, LookupEnvironment},
-        constraints::zk_rows_strict_lower_bound,
+        berkeley_columns::{Column, Environment, LookupEnvironment},
+        constraints::{num_chunks, zk_rows_strict_lower_bound},
         expr::{self, l0_1, Challenges, Constants},
         gate::GateType,
-        lookup::{self, runtime_tables::RuntimeTable, tables::combine_table_entry},
+        lookup::{
+            self,
+            constraints::LookupArgument,
+            lookups::LookupPattern,
+            runtime_tables::{LazyRuntimeTable, RuntimeTable, RuntimeTableContext},
+            tables::combine_table_entry,
+        },
         polynomials::{
             complete_add::CompleteAdd,
             endomul_scalar::EndomulScalar,
@@ -21,17 +27,19 @@ use crate::{
             varbasemul::VarbaseMul,
             xor::Xor16,
         },
-        wires::{COLUMNS, PERMUTS},
+        wires::COLUMNS,
     },
+    committed_column::CommittedColumn,
     curve::KimchiCurve,
     error::ProverError,
     lagrange_basis_evaluations::LagrangeBasisEvaluations,
+    opening_order::OpenedPolynomial,
     plonk_sponge::FrSponge,
     proof::{
         LookupCommitments, PointEvaluations, ProofEvaluations, ProverCommitments, ProverProof,
         RecursionChallenge,
     },
-    prover_index::ProverIndex,
+    prover_index::{MemoryMode, ProverIndex},
     verifier_index::VerifierIndex,
 };
 use ark_ff::{FftField, Field, One, PrimeField, UniformRand, Zero};
@@ -40,15 +48,18 @@ use ark_poly::{
     Radix2EvaluationDomain as D, UVPolynomial,
 };
 use itertools::Itertools;
-use mina_poseidon::{sponge::ScalarChallenge, FqSponge};
-use o1_utils::ExtendedDensePolynomial as _;
+use mina_poseidon::{sponge::ScalarChallenge, transcript::Transcript, FqSponge};
+use o1_utils::{
+    cancellation::CancellationToken,
+    fft::{FftEvaluate as _, FftInterpolate as _},
+    ExtendedDensePolynomial as _,
+};
 use poly_commitment::{
-    commitment::{
-        absorb_commitment, b_poly_coefficients, BlindedCommitment, CommitmentCurve, PolyComm,
-    },
+    commitment::{b_poly_coefficients, blinding_rng, BlindedCommitment, CommitmentCurve, PolyComm},
     evaluation_proof::DensePolynomialOrEvaluations,
     OpenProof, SRS as _,
 };
+use rand_core::{CryptoRng, RngCore};
 use rayon::prelude::*;
 use std::{array, collections::HashMap};
 
@@ -61,7 +72,9 @@ macro_rules! check_constraint {
         check_constraint!($index, stringify!($evaluation), $evaluation);
     }};
     ($index:expr, $label:expr, $evaluation:expr) => {{
-        if cfg!(debug_assertions) {
+        // the unsatisfied constraints are reported as errors with the
+        // diagnostics of the quotient
+        if cfg!(debug_assertions) && !cfg!(feature = "quotient_diagnostics") {
             let (_, res) = $evaluation
                 .interpolate_by_ref()
                 .divide_by_vanishing_poly($index.cs.domain.d1)
@@ -73,6 +86,51 @@ macro_rules! check_constraint {
     }};
 }
 
+/// Records the first group of constraints that does not vanish on the domain,
+/// to report it when the quotient cannot be computed. The constraints are only
+/// checked with the `quotient_diagnostics` feature.
+struct QuotientDiagnostics<F: FftField> {
+    domain: D<F>,
+    unsatisfied: Option<(ArgumentType, usize)>,
+}
+
+impl<F: FftField> QuotientDiagnostics<F> {
+    fn new(domain: D<F>) -> Self {
+        Self {
+            domain,
+            unsatisfied: None,
+        }
+    }
+
+    /// Checks the evaluations of the constraints of `argument`, over a domain
+    /// containing the domain of the circuit
+    fn check(&mut self, argument: ArgumentType, evaluations: &Evaluations<F, D<F>>) {
+        if !cfg!(feature = "quotient_diagnostics") || self.unsatisfied.is_some() {
+            return;
+        }
+        // the evaluations of the skipped lookup constraints are over a smaller
+        // domain, and are all zero
+        let step = (evaluations.domain().size() / self.domain.size()).max(1);
+        if let Some(row) = evaluations
+            .evals
+            .iter()
+            .step_by(step)
+            .position(|x| !x.is_zero())
+        {
+            self.unsatisfied = Some((argument, row));
+        }
+    }
+
+    /// The error of a division by the vanishing polynomial with a non-zero
+    /// remainder
+    fn error(&self) -> ProverError {
+        match self.unsatisfied {
+            Some((argument, row)) => ProverError::UnsatisfiedConstraints(argument, row),
+            None => ProverError::Prover("rest of division by vanishing polynomial"),
+        }
+    }
+}
+
 /// Contains variables needed for lookup in the prover algorithm.
 #[derive(Default)]
 struct LookupContext<G, F>
@@ -167,11 +225,202 @@ where
         EFrSponge: FrSponge<G::ScalarField>,
     >(
         group_map: &G::Map,
-        mut witness: [Vec<G::ScalarField>; COLUMNS],
+        witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: &[RuntimeTable<G::ScalarField>],
+        index: &ProverIndex<G, OpeningProof>,
+        prev_challenges: Vec<RecursionChallenge<G>>,
+        blinders: Option<[Option<PolyComm<G::ScalarField>>; COLUMNS]>,
+    ) -> Result<Self>
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        Self::create_recursive_cancellable::<EFqSponge, EFrSponge>(
+            group_map,
+            witness,
+            runtime_tables,
+            index,
+            prev_challenges,
+            blinders,
+            &CancellationToken::new(),
+        )
+    }
+
+    /// Same as [ProverProof::create_recursive], but checks `cancel` between
+    /// the rounds of the prover, so that the creation of the proof can be
+    /// aborted by another thread.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `cancel` is cancelled, or if `create_recursive` process fails.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `lookup_context.joint_lookup_table_d8` is None.
+    pub fn create_recursive_cancellable<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        group_map: &G::Map,
+        witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: &[RuntimeTable<G::ScalarField>],
+        index: &ProverIndex<G, OpeningProof>,
+        prev_challenges: Vec<RecursionChallenge<G>>,
+        blinders: Option<[Option<PolyComm<G::ScalarField>>; COLUMNS]>,
+        cancel: &CancellationToken,
+    ) -> Result<Self>
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        Self::create_recursive_with_rng::<EFqSponge, EFrSponge, _>(
+            group_map,
+            witness,
+            runtime_tables.iter().map(LazyRuntimeTable::from).collect(),
+            index,
+            prev_challenges,
+            blinders,
+            &[],
+            cancel,
+            &mut rand::rngs::OsRng,
+        )
+    }
+
+    /// Same as [ProverProof::create_recursive], but the witness columns of
+    /// `committed_columns` are given by their committed values and blinders,
+    /// so that their commitments in the proof are the ones of the committed
+    /// columns, see [crate::committed_column].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the witness differs from the values of a committed
+    /// column, or if `create_recursive` process fails.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `lookup_context.joint_lookup_table_d8` is None.
+    pub fn create_with_committed_columns<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        group_map: &G::Map,
+        witness: [Vec<G::ScalarField>; COLUMNS],
         runtime_tables: &[RuntimeTable<G::ScalarField>],
         index: &ProverIndex<G, OpeningProof>,
         prev_challenges: Vec<RecursionChallenge<G>>,
+        committed_columns: &[CommittedColumn<G>],
+    ) -> Result<Self>
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        Self::create_recursive_with_rng::<EFqSponge, EFrSponge, _>(
+            group_map,
+            witness,
+            runtime_tables.iter().map(LazyRuntimeTable::from).collect(),
+            index,
+            prev_challenges,
+            None,
+            committed_columns,
+            &CancellationToken::new(),
+            &mut rand::rngs::OsRng,
+        )
+    }
+
+    /// Same as [ProverProof::create_recursive], but the data of the runtime
+    /// tables is produced during proof creation by the closures of
+    /// `runtime_tables`, see [LazyRuntimeTable].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `create_recursive` process fails.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `lookup_context.joint_lookup_table_d8` is None.
+    pub fn create_with_lazy_runtime_tables<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        group_map: &G::Map,
+        witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: Vec<LazyRuntimeTable<'_, G::ScalarField>>,
+        index: &ProverIndex<G, OpeningProof>,
+        prev_challenges: Vec<RecursionChallenge<G>>,
+    ) -> Result<Self>
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        Self::create_recursive_with_rng::<EFqSponge, EFrSponge, _>(
+            group_map,
+            witness,
+            runtime_tables,
+            index,
+            prev_challenges,
+            None,
+            &[],
+            &CancellationToken::new(),
+            &mut rand::rngs::OsRng,
+        )
+    }
+
+    /// Same as [ProverProof::create_recursive], but all the randomness of the
+    /// prover (the blinders of the commitments and the values of the
+    /// zero-knowledge rows) is derived from `seed` instead of the operating
+    /// system, see [blinding_rng]. Two proofs created from the same witness and
+    /// seed are identical, which is useful to reproduce a proof when testing or
+    /// auditing.
+    ///
+    /// The seed must be kept secret and never be reused for different
+    /// witnesses, otherwise the proofs are not zero-knowledge anymore.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `create_recursive` process fails.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `lookup_context.joint_lookup_table_d8` is None.
+    pub fn prove_with_rng_seed<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+    >(
+        group_map: &G::Map,
+        witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: &[RuntimeTable<G::ScalarField>],
+        index: &ProverIndex<G, OpeningProof>,
+        prev_challenges: Vec<RecursionChallenge<G>>,
+        blinders: Option<[Option<PolyComm<G::ScalarField>>; COLUMNS]>,
+        seed: [u8; 32],
+    ) -> Result<Self>
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        Self::create_recursive_with_rng::<EFqSponge, EFrSponge, _>(
+            group_map,
+            witness,
+            runtime_tables.iter().map(LazyRuntimeTable::from).collect(),
+            index,
+            prev_challenges,
+            blinders,
+            &[],
+            &CancellationToken::new(),
+            &mut blinding_rng(seed),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_recursive_with_rng<
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        EFrSponge: FrSponge<G::ScalarField>,
+        RNG: RngCore + CryptoRng,
+    >(
+        group_map: &G::Map,
+        mut witness: [Vec<G::ScalarField>; COLUMNS],
+        runtime_tables: Vec<LazyRuntimeTable<'_, G::ScalarField>>,
+        index: &ProverIndex<G, OpeningProof>,
+        prev_challenges: Vec<RecursionChallenge<G>>,
         blinders: Option<[Option<PolyComm<G::ScalarField>>; COLUMNS]>,
+        committed_columns: &[CommittedColumn<G>],
+        cancel: &CancellationToken,
+        rng: &mut RNG,
     ) -> Result<Self>
     where
         VerifierIndex<G, OpeningProof>: Clone,
@@ -182,14 +431,7 @@ where
 
         let (_, endo_r) = G::endos();
 
-        let num_chunks = if d1_size < index.max_poly_size {
-            1
-        } else {
-            d1_size / index.max_poly_size
-        };
-
-        // TODO: rng should be passed as arg
-        let rng = &mut rand::rngs::OsRng;
+        let num_chunks = num_chunks(d1_size, index.max_poly_size);
 
         // Verify the circuit satisfiability by the computed witness (baring plookup constraints)
         // Catch mistakes before proof generation.
@@ -198,6 +440,8 @@ where
             index.verify(&witness, &public).expect("incorrect witness");
         }
 
+        cancel.check()?;
+
         //~ 1. Ensure we have room in the witness for the zero-knowledge rows.
         //~    We currently expect the witness not to be of the same length as the domain,
         //~    but instead be of the length of the (smaller) circuit.
@@ -237,31 +481,65 @@ where
             }
         }
 
+        //~ 1. Replace the committed columns, if any, by their committed values,
+        //~    which include their own zero-knowledge rows,
+        //~    and count this proof among the uses of each committed column.
+        for committed in committed_columns {
+            let w = &mut witness[committed.column];
+            if committed.evals.len() != w.len()
+                || w[..length_witness] != committed.evals[..length_witness]
+            {
+                return Err(ProverError::CommittedColumnMismatch(committed.column));
+            }
+            w.clone_from(&committed.evals);
+        }
+        for committed in committed_columns {
+            committed.reserve_use()?;
+        }
+
         //~ 1. Setup the Fq-Sponge.
+        //~    With the domain-separated transcript, absorb the domain separator `kimchi-v1`,
+        //~    and the label of each element before the element in the following steps.
         internal_tracing::checkpoint!(internal_traces; set_up_fq_sponge);
-        let mut fq_sponge = EFqSponge::new(G::other_curve_sponge_params());
+        let mut fq_sponge = index
+            .cs
+            .transcript_version
+            .transcript(EFqSponge::new(G::other_curve_sponge_params()));
 
         //~ 1. Absorb the digest of the VerifierIndex.
         let verifier_index_digest = index.verifier_index_digest::<EFqSponge>();
-        fq_sponge.absorb_fq(&[verifier_index_digest]);
+        fq_sponge.absorb_fq("verifier_index_digest", &[verifier_index_digest]);
 
         //~ 1. Absorb the commitments of the previous challenges with the Fq-sponge.
         for RecursionChallenge { comm, .. } in &prev_challenges {
-            absorb_commitment(&mut fq_sponge, comm)
+            fq_sponge.absorb_g("prev_challenge_comm", &comm.elems)
         }
 
         //~ 1. Compute the negated public input polynomial as
         //~    the polynomial that evaluates to $-p_i$ for the first `public_input_size` values of the domain,
         //~    and $0$ for the rest.
         let public = witness[0][0..index.cs.public].to_vec();
-        let public_poly = -Evaluations::<G::ScalarField, D<G::ScalarField>>::from_vec_and_domain(
-            public,
+        if let Some(layout) = &index.cs.public_input_layout {
+            if let Some(position) = layout.first_invalid(&public) {
+                return Err(ProverError::InvalidPublicInput(position));
+            }
+        }
+        let public_evals = Evaluations::<G::ScalarField, D<G::ScalarField>>::from_vec_and_domain(
+            public
+                .iter()
+                .map(|x| -*x)
+                .chain(std::iter::repeat(G::ScalarField::zero()))
+                .take(index.cs.domain.d1.size())
+                .collect(),
             index.cs.domain.d1,
-        )
-        .interpolate();
+        );
 
         //~ 1. Commit (non-hiding) to the negated public input polynomial.
-        let public_comm = index.srs.commit_non_hiding(&public_poly, num_chunks);
+        // The commitment is computed in the Lagrange basis, from the evaluations.
+        let public_comm = index
+            .srs
+            .commit_evaluations_non_hiding(index.cs.domain.d1, &public_evals);
+        let public_poly = public_evals.fft_interpolate();
         let public_comm = {
             index
                 .srs
@@ -278,7 +556,7 @@ where
         //~    Note: unlike the original PLONK protocol,
         //~    the prover also provides evaluations of the public polynomial to help the verifier circuit.
         //~    This is why we need to absorb the commitment to the public polynomial at this point.
-        absorb_commitment(&mut fq_sponge, &public_comm);
+        fq_sponge.absorb_g("public_comm", &public_comm.elems);
 
         //~ 1. Commit to the witness columns by creating `COLUMNS` hidding commitments.
         //~
@@ -294,13 +572,20 @@ where
                     index.cs.domain.d1,
                 );
 
-            let com = match blinders.as_ref().and_then(|b| b[col].as_ref()) {
+            let committed = committed_columns
+                .iter()
+                .find(|committed| committed.column == col);
+            let com = match (committed, blinders.as_ref().and_then(|b| b[col].as_ref())) {
+                // committed column: reuse its commitment
+                (Some(committed), _) => committed.blinded_comm.clone(),
                 // no blinders: blind the witness
-                None => index
-                    .srs
-                    .commit_evaluations(index.cs.domain.d1, &witness_eval, rng),
+                (None, None) => {
+                    index
+                        .srs
+                        .commit_evaluations(index.cs.domain.d1, &witness_eval, rng)
+                }
                 // blinders: blind the witness with them
-                Some(blinder) => {
+                (None, Some(blinder)) => {
                     // TODO: make this a function rather no? mask_with_custom()
                     let witness_com = index
                         .srs
@@ -322,7 +607,7 @@ where
         //~ 1. Absorb the witness commitments with the Fq-Sponge.
         w_comm
             .iter()
-            .for_each(|c| absorb_commitment(&mut fq_sponge, &c.commitment));
+            .for_each(|c| fq_sponge.absorb_g("w_comm", &c.commitment.elems));
 
         //~ 1. Compute the witness polynomials by interpolating each `COLUMNS` of the witness.
         //~    As mentioned above, we commit using the evaluations form rather than the coefficients
@@ -333,7 +618,7 @@ where
                 witness[i].clone(),
                 index.cs.domain.d1,
             )
-            .interpolate()
+            .fft_interpolate()
         });
 
         let mut lookup_context = LookupContext::default();
@@ -352,10 +637,7 @@ where
                     .iter()
                     .map(|rt| (rt.id, rt.len))
                     .collect();
-                let runtime: Vec<_> = runtime_tables
-                    .iter()
-                    .map(|rt| (rt.id, rt.data.len()))
-                    .collect();
+                let runtime: Vec<_> = runtime_tables.iter().map(|rt| (rt.id, rt.len)).collect();
                 if expected_runtime != runtime {
                     return Err(ProverError::RuntimeTablesInconsistent);
                 }
@@ -369,9 +651,13 @@ where
 
                     let mut evals = vec![G::ScalarField::zero(); d1_size];
                     for rt in runtime_tables {
-                        let range = offset..(offset + rt.data.len());
-                        evals[range].copy_from_slice(&rt.data);
-                        offset += rt.data.len();
+                        let range = offset..(offset + rt.len);
+                        let context = RuntimeTableContext {
+                            id: rt.id,
+                            witness: &witness,
+                        };
+                        (rt.data)(&context, &mut evals[range]);
+                        offset += rt.len;
                     }
 
                     // zero-knowledge
@@ -381,10 +667,11 @@ where
 
                     // get coeff and evaluation form
                     let runtime_table_contribution =
-                        Evaluations::from_vec_and_domain(evals, index.cs.domain.d1).interpolate();
+                        Evaluations::from_vec_and_domain(evals, index.cs.domain.d1)
+                            .fft_interpolate();
 
-                    let runtime_table_contribution_d8 =
-                        runtime_table_contribution.evaluate_over_domain_by_ref(index.cs.domain.d8);
+                    let runtime_table_contribution_d8 = runtime_table_contribution
+                        .fft_evaluate_over_domain_by_ref(index.cs.domain.d8);
 
                     (runtime_table_contribution, runtime_table_contribution_d8)
                 };
@@ -397,7 +684,7 @@ where
                         .commit(&runtime_table_contribution, num_chunks, rng);
 
                 // absorb the commitment
-                absorb_commitment(&mut fq_sponge, &runtime_table_comm.commitment);
+                fq_sponge.absorb_g("runtime_table_comm", &runtime_table_comm.commitment.elems);
 
                 // pre-compute the updated second column of the lookup table
                 let mut second_column_d8 = runtime_table_contribution_d8.clone();
@@ -419,7 +706,7 @@ where
             //~~   then squeeze the Fq-Sponge to obtain the joint combiner challenge $j'$,
             //~~   otherwise set the joint combiner challenge $j'$ to $0$.
             let joint_combiner = if lcs.configuration.lookup_info.features.joint_lookup_used {
-                fq_sponge.challenge()
+                fq_sponge.challenge("joint_combiner")?
             } else {
                 G::ScalarField::zero()
             };
@@ -497,23 +784,38 @@ where
             };
 
             // TODO: This interpolation is avoidable.
-            let joint_lookup_table = joint_lookup_table_d8.interpolate_by_ref();
+            let joint_lookup_table = joint_lookup_table_d8.fft_interpolate_by_ref();
 
             //~~ * Compute the sorted evaluations.
+            //~~   When using the logUp argument, compute instead the multiplicities
+            //~~   of the table entries, which take the place of the sorted evaluations.
             // TODO: Once we switch to committing using lagrange commitments,
             // `witness` will be consumed when we interpolate, so interpolation will
             // have to moved below this.
-            let sorted: Vec<_> = lookup::constraints::sorted(
-                dummy_lookup_value,
-                &joint_lookup_table_d8,
-                index.cs.domain.d1,
-                &index.cs.gates,
-                &witness,
-                joint_combiner,
-                table_id_combiner,
-                &lcs.configuration.lookup_info,
-                index.cs.zk_rows as usize,
-            )?;
+            let sorted: Vec<_> = match lcs.configuration.argument {
+                LookupArgument::Plookup => lookup::constraints::sorted(
+                    dummy_lookup_value,
+                    &joint_lookup_table_d8,
+                    index.cs.domain.d1,
+                    &index.cs.gates,
+                    &witness,
+                    joint_combiner,
+                    table_id_combiner,
+                    &lcs.configuration.lookup_info,
+                    index.cs.zk_rows as usize,
+                )?,
+                LookupArgument::LogUp => vec![lookup::constraints::multiplicities(
+                    dummy_lookup_value,
+                    &joint_lookup_table_d8,
+                    index.cs.domain.d1,
+                    &index.cs.gates,
+                    &witness,
+                    joint_combiner,
+                    table_id_combiner,
+                    &lcs.configuration.lookup_info,
+                    index.cs.zk_rows as usize,
+                )?],
+            };
 
             //~~ * Randomize the last `EVALS` rows in each of the sorted polynomials
             //~~   in order to add zero-knowledge to the protocol.
@@ -538,14 +840,15 @@ where
             //~~ * Absorb each commitments to the sorted polynomials.
             sorted_comms
                 .iter()
-                .for_each(|c| absorb_commitment(&mut fq_sponge, &c.commitment));
+                .for_each(|c| fq_sponge.absorb_g("lookup_sorted_comm", &c.commitment.elems));
 
             // precompute different forms of the sorted polynomials for later
             // TODO: We can avoid storing these coefficients.
-            let sorted_coeffs: Vec<_> = sorted.iter().map(|e| e.clone().interpolate()).collect();
+            let sorted_coeffs: Vec<_> =
+                sorted.iter().map(|e| e.clone().fft_interpolate()).collect();
             let sorted8: Vec<_> = sorted_coeffs
                 .iter()
-                .map(|v| v.evaluate_over_domain_by_ref(index.cs.domain.d8))
+                .map(|v| v.fft_evaluate_over_domain_by_ref(index.cs.domain.d8))
                 .collect();
 
             lookup_context.joint_combiner = Some(joint_combiner);
@@ -557,32 +860,52 @@ where
             lookup_context.joint_lookup_table = Some(joint_lookup_table);
         }
 
+        cancel.check()?;
+
         //~ 1. Sample $\beta$ with the Fq-Sponge.
-        let beta = fq_sponge.challenge();
+        let beta = fq_sponge.challenge("beta")?;
 
         //~ 1. Sample $\gamma$ with the Fq-Sponge.
-        let gamma = fq_sponge.challenge();
+        let gamma = fq_sponge.challenge("gamma")?;
 
         //~ 1. If using lookup:
         if let Some(lcs) = &index.cs.lookup_constraint_system {
             //~~ * Compute the lookup aggregation polynomial.
             let joint_lookup_table_d8 = lookup_context.joint_lookup_table_d8.as_ref().unwrap();
 
-            let aggreg = lookup::constraints::aggregation::<_, G::ScalarField>(
-                lookup_context.dummy_lookup_value.unwrap(),
-                joint_lookup_table_d8,
-                index.cs.domain.d1,
-                &index.cs.gates,
-                &witness,
-                &lookup_context.joint_combiner.unwrap(),
-                &lookup_context.table_id_combiner.unwrap(),
-                beta,
-                gamma,
-                lookup_context.sorted.as_ref().unwrap(),
-                rng,
-                &lcs.configuration.lookup_info,
-                index.cs.zk_rows as usize,
-            )?;
+            let aggreg = match lcs.configuration.argument {
+                LookupArgument::Plookup => lookup::constraints::aggregation::<_, G::ScalarField>(
+                    lookup_context.dummy_lookup_value.unwrap(),
+                    joint_lookup_table_d8,
+                    index.cs.domain.d1,
+                    &index.cs.gates,
+                    &witness,
+                    &lookup_context.joint_combiner.unwrap(),
+                    &lookup_context.table_id_combiner.unwrap(),
+                    beta,
+                    gamma,
+                    lookup_context.sorted.as_ref().unwrap(),
+                    rng,
+                    &lcs.configuration.lookup_info,
+                    index.cs.zk_rows as usize,
+                )?,
+                LookupArgument::LogUp => {
+                    lookup::constraints::logup_aggregation::<_, G::ScalarField>(
+                        lookup_context.dummy_lookup_value.unwrap(),
+                        joint_lookup_table_d8,
+                        index.cs.domain.d1,
+                        &index.cs.gates,
+                        &witness,
+                        &lookup_context.joint_combiner.unwrap(),
+                        &lookup_context.table_id_combiner.unwrap(),
+                        gamma,
+                        &lookup_context.sorted.as_ref().unwrap()[0],
+                        rng,
+                        &lcs.configuration.lookup_info,
+                        index.cs.zk_rows as usize,
+                    )?
+                }
+            };
 
             //~~ * Commit to the aggregation polynomial.
             let aggreg_comm = index
@@ -590,31 +913,44 @@ where
                 .commit_evaluations(index.cs.domain.d1, &aggreg, rng);
 
             //~~ * Absorb the commitment to the aggregation polynomial with the Fq-Sponge.
-            absorb_commitment(&mut fq_sponge, &aggreg_comm.commitment);
+            fq_sponge.absorb_g("lookup_aggreg_comm", &aggreg_comm.commitment.elems);
 
             // precompute different forms of the aggregation polynomial for later
-            let aggreg_coeffs = aggreg.interpolate();
+            let aggreg_coeffs = aggreg.fft_interpolate();
             // TODO: There's probably a clever way to expand the domain without
             // interpolating
-            let aggreg8 = aggreg_coeffs.evaluate_over_domain_by_ref(index.cs.domain.d8);
+            let aggreg8 = aggreg_coeffs.fft_evaluate_over_domain_by_ref(index.cs.domain.d8);
 
             lookup_context.aggreg_comm = Some(aggreg_comm);
             lookup_context.aggreg_coeffs = Some(aggreg_coeffs);
             lookup_context.aggreg8 = Some(aggreg8);
         }
 
+        cancel.check()?;
+
         //~ 1. Compute the permutation aggregation polynomial $z$.
         internal_tracing::checkpoint!(internal_traces; z_permutation_aggregation_polynomial);
         let z_poly = index.perm_aggreg(&witness, &beta, &gamma, rng)?;
 
+        // The evaluations over d1 are not used anymore: the permutation
+        // aggregation above was their last reader, and the remaining steps only
+        // use the coefficients of the witness (`witness_poly`) and its
+        // evaluations over d4 and d8
+        if index.memory_mode == MemoryMode::Budget {
+            for column in &mut witness {
+                *column = vec![];
+            }
+            lookup_context.sorted = None;
+        }
+
         //~ 1. Commit (hidding) to the permutation aggregation polynomial $z$.
         let z_comm = index.srs.commit(&z_poly, num_chunks, rng);
 
         //~ 1. Absorb the permutation aggregation polynomial $z$ with the Fq-Sponge.
-        absorb_commitment(&mut fq_sponge, &z_comm.commitment);
+        fq_sponge.absorb_g("z_comm", &z_comm.commitment.elems);
 
         //~ 1. Sample $\alpha'$ with the Fq-Sponge.
-        let alpha_chal = ScalarChallenge(fq_sponge.challenge());
+        let alpha_chal = ScalarChallenge(fq_sponge.challenge("alpha")?);
 
         //~ 1. Derive $\alpha$ from $\alpha'$ using the endomorphism (TODO: details)
         let alpha: G::ScalarField = alpha_chal.to_field(endo_r);
@@ -623,6 +959,8 @@ where
         let mut all_alphas = index.powers_of_alpha.clone();
         all_alphas.instantiate(alpha);
 
+        cancel.check()?;
+
         //~ 1. Compute the quotient polynomial (the $t$ in $f = Z_H \cdot t$).
         //~    The quotient polynomial is computed by adding all these polynomials together:
         //~~ * the combined constraints for all the gates
@@ -647,7 +985,10 @@ where
         };
 
         internal_tracing::checkpoint!(internal_traces; eval_witness_polynomials_over_domains);
-        let lagrange = index.cs.evaluate(&witness_poly, &z_poly);
+        let lagrange = match index.memory_mode {
+            MemoryMode::Speed => index.cs.evaluate(&witness_poly, &z_poly),
+            MemoryMode::Budget => index.cs.evaluate_d8(&witness_poly, &z_poly),
+        };
         internal_tracing::checkpoint!(internal_traces; compute_index_evals);
         let env = {
             let mut index_evals = HashMap::new();
@@ -702,6 +1043,10 @@ where
                 index_evals.insert(GateType::Rot64, selector);
             }
 
+            if let Some(selector) = index.column_evaluations.custom_selector8.as_ref() {
+                index_evals.insert(GateType::Custom, selector);
+            }
+
             let mds = &G::sponge_params().mds;
             Environment {
                 constants: Constants {
@@ -733,18 +1078,21 @@ where
 
         internal_tracing::checkpoint!(internal_traces; compute_quotient_poly);
 
+        let mut diagnostics = QuotientDiagnostics::new(index.cs.domain.d1);
+
         let quotient_poly = {
             // generic
             let mut t4 = {
                 let generic_constraint =
                     generic::Generic::combined_constraints(&all_alphas, &mut cache);
-                let generic4 = generic_constraint.evaluations(&env);
+                let generic4 = generic_constraint.evaluations_by_coset(&env);
 
-                if cfg!(debug_assertions) {
+                if cfg!(debug_assertions) || cfg!(feature = "quotient_diagnostics") {
                     let p4 = public_poly.evaluate_over_domain_by_ref(index.cs.domain.d4);
                     let gen_minus_pub = &generic4 + &p4;
 
                     check_constraint!(index, gen_minus_pub);
+                    diagnostics.check(ArgumentType::Gate(GateType::Generic), &gen_minus_pub);
                 }
 
                 generic4
@@ -756,6 +1104,7 @@ where
                 let (perm, bnd) = index.perm_quot(&lagrange, beta, gamma, &z_poly, alphas)?;
 
                 check_constraint!(index, perm);
+                diagnostics.check(ArgumentType::Permutation, &perm);
 
                 (perm, bnd)
             };
@@ -777,16 +1126,18 @@ where
                     .is_some();
                 let xor_enabled = index.column_evaluations.xor_selector8.is_some();
                 let rot_enabled = index.column_evaluations.rot_selector8.is_some();
+                // the selectors of the unused base gates are zero
+                let base_gates = index.cs.base_gates;
 
                 for gate in [
                     (
                         (&CompleteAdd::default() as &dyn DynArgument<G::ScalarField>),
-                        true,
+                        base_gates.complete_add,
                     ),
-                    (&VarbaseMul::default(), true),
-                    (&EndosclMul::default(), true),
-                    (&EndomulScalar::default(), true),
-                    (&Poseidon::default(), true),
+                    (&VarbaseMul::default(), base_gates.var_base_mul),
+                    (&EndosclMul::default(), base_gates.endo_mul),
+                    (&EndomulScalar::default(), base_gates.endo_mul_scalar),
+                    (&Poseidon::default(), base_gates.poseidon),
                     // Range check gates
                     (&RangeCheck0::default(), range_check0_enabled),
                     (&RangeCheck1::default(), range_check1_enabled),
@@ -806,7 +1157,7 @@ where
                 .filter_map(|(gate, is_enabled)| if is_enabled { Some(gate) } else { None })
                 {
                     let constraint = gate.combined_constraints(&all_alphas, &mut cache);
-                    let eval = constraint.evaluations(&env);
+                    let eval = constraint.evaluations_by_coset(&env);
                     if eval.domain().size == t4.domain().size {
                         t4 += &eval;
                     } else if eval.domain().size == t8.domain().size {
@@ -815,9 +1166,25 @@ where
                         panic!("Bad evaluation")
                     }
                     check_constraint!(index, format!("{:?}", gate.argument_type()), eval);
+                    diagnostics.check(gate.argument_type(), &eval);
                 }
             };
 
+            // custom gates
+            if let Some(custom_gates) = index.cs.custom_gates.as_ref() {
+                let constraint = custom_gates.combined_constraints(&all_alphas, &mut cache);
+                let eval = constraint.evaluations_by_coset(&env);
+                if eval.domain().size == t4.domain().size {
+                    t4 += &eval;
+                } else if eval.domain().size == t8.domain().size {
+                    t8 += &eval;
+                } else {
+                    panic!("Bad evaluation")
+                }
+                check_constraint!(index, "custom gates", eval);
+                diagnostics.check(ArgumentType::Gate(GateType::Custom), &eval);
+            }
+
             // lookup
             {
                 if let Some(lcs) = index.cs.lookup_constraint_system.as_ref() {
@@ -832,7 +1199,7 @@ where
                     for (ii, (constraint, alpha_pow)) in
                         constraints.into_iter().zip_eq(lookup_alphas).enumerate()
                     {
-                        let mut eval = constraint.evaluations(&env);
+                        let mut eval = constraint.evaluations_by_coset(&env);
                         eval.evals.par_iter_mut().for_each(|x| *x *= alpha_pow);
 
                         if eval.domain().size == t4.domain().size {
@@ -846,12 +1213,14 @@ where
                         }
 
                         check_constraint!(index, format!("lookup constraint #{ii}"), eval);
+                        diagnostics.check(ArgumentType::Lookup, &eval);
                     }
                 }
             }
 
             // public polynomial
-            let mut f = t4.interpolate() + t8.interpolate();
+            let mut f = t8.fft_interpolate();
+            f += &t4.fft_interpolate();
             f += &public_poly;
 
             // divide contributions with vanishing polynomial
@@ -859,23 +1228,23 @@ where
                 .divide_by_vanishing_poly(index.cs.domain.d1)
                 .ok_or(ProverError::Prover("division by vanishing polynomial"))?;
             if !res.is_zero() {
-                return Err(ProverError::Prover(
-                    "rest of division by vanishing polynomial",
-                ));
+                return Err(diagnostics.error());
             }
 
             quotient += &bnd; // already divided by Z_H
             quotient
         };
 
+        cancel.check()?;
+
         //~ 1. commit (hiding) to the quotient polynomial $t$
         let t_comm = { index.srs.commit(&quotient_poly, 7 * num_chunks, rng) };
 
         //~ 1. Absorb the commitment of the quotient polynomial with the Fq-Sponge.
-        absorb_commitment(&mut fq_sponge, &t_comm.commitment);
+        fq_sponge.absorb_g("t_comm", &t_comm.commitment.elems);
 
         //~ 1. Sample $\zeta'$ with the Fq-Sponge.
-        let zeta_chal = ScalarChallenge(fq_sponge.challenge());
+        let zeta_chal = ScalarChallenge(fq_sponge.challenge("zeta")?);
 
         //~ 1. Derive $\zeta$ from $\zeta'$ using the endomorphism (TODO: specify)
         let zeta = zeta_chal.to_field(endo_r);
@@ -1056,6 +1425,11 @@ where
                 .rot_selector8
                 .as_ref()
                 .map(chunked_evals_for_selector),
+            custom_selector: index
+                .column_evaluations
+                .custom_selector8
+                .as_ref()
+                .map(chunked_evals_for_selector),
 
             runtime_lookup_table_selector: index.cs.lookup_constraint_system.as_ref().and_then(
                 |lcs| {
@@ -1094,6 +1468,12 @@ where
                         .map(chunked_evals_for_selector)
                 },
             ),
+            vector_lookup_selector: index.cs.lookup_constraint_system.as_ref().and_then(|lcs| {
+                lcs.lookup_selectors
+                    .vector_lookup
+                    .as_ref()
+                    .map(chunked_evals_for_selector)
+            }),
         };
 
         let zeta_to_srs_len = zeta.pow([index.max_poly_size as u64]);
@@ -1129,11 +1509,22 @@ where
                     let (_lin_constant, mut lin) =
                         index.linearization.to_polynomial(&env, zeta, &evals);
                     lin += &f;
-                    lin.interpolate()
+                    lin.fft_interpolate()
                 };
 
                 drop(env);
 
+                // The evaluations over d8 are not used anymore: the
+                // linearization above was their last reader, through `env`
+                if index.memory_mode == MemoryMode::Budget {
+                    drop(lagrange);
+                    lookup_context.joint_lookup_table_d8 = None;
+                    lookup_context.sorted8 = None;
+                    lookup_context.aggreg8 = None;
+                    lookup_context.runtime_table_d8 = None;
+                    lookup_context.runtime_second_col_d8 = None;
+                }
+
                 // see https://o1-labs.github.io/proof-systems/kimchi/maller_15.html#the-prover-side
                 f.to_chunked_polynomial(num_chunks, index.max_poly_size)
                     .linearize(zeta_to_srs_len)
@@ -1165,6 +1556,7 @@ where
         let ft_eval1 = ft.evaluate(&zeta_omega);
 
         //~ 1. Setup the Fr-Sponge
+        let fq_sponge = fq_sponge.into_sponge();
         let fq_sponge_before_evaluations = fq_sponge.clone();
         let mut fr_sponge = EFrSponge::new(G::sponge_params());
 
@@ -1231,213 +1623,204 @@ where
         let coefficients_form = DensePolynomialOrEvaluations::DensePolynomial;
         let evaluations_form = |e| DensePolynomialOrEvaluations::Evaluations(e, index.cs.domain.d1);
 
-        let mut polynomials = polys
-            .iter()
-            .map(|(p, d1_size)| (coefficients_form(p), non_hiding(*d1_size)))
-            .collect::<Vec<_>>();
-
         let fixed_hiding = |d1_size: usize| PolyComm {
             elems: vec![G::ScalarField::one(); d1_size],
         };
 
-        //~ 1. Then, include:
+        // the blinding of the combined lookup table, if using lookup
+        let table_blinding = index.cs.lookup_constraint_system.as_ref().map(|lcs| {
+            let joint_combiner = lookup_context.joint_combiner.as_ref().unwrap();
+            let table_id_combiner = lookup_context.table_id_combiner.as_ref().unwrap();
+            let max_fixed_lookup_table_size = {
+                // CAUTION: This is not `lcs.configuration.lookup_info.max_joint_size` because
+                // the lookup table may be strictly narrower, and as such will not contribute
+                // the associated blinders.
+                // For example, using a runtime table with the lookup gate (width 2), but only
+                // width-1 fixed tables (e.g. range check), it would be incorrect to use the
+                // wider width (2) because there are no such contributing commitments!
+                // Note that lookup_table8 is a list of polynomials
+                lcs.lookup_table8.len()
+            };
+            let base_blinding = {
+                let fixed_table_blinding = if max_fixed_lookup_table_size == 0 {
+                    G::ScalarField::zero()
+                } else {
+                    (1..max_fixed_lookup_table_size).fold(G::ScalarField::one(), |acc, _| {
+                        G::ScalarField::one() + *joint_combiner * acc
+                    })
+                };
+                fixed_table_blinding + *table_id_combiner
+            };
+            if lcs.runtime_selector.is_some() {
+                let runtime_comm = lookup_context.runtime_table_comm.as_ref().unwrap();
+
+                let elems = runtime_comm
+                    .blinders
+                    .elems
+                    .iter()
+                    .map(|blinding| *joint_combiner * blinding + base_blinding)
+                    .collect();
+
+                PolyComm { elems }
+            } else {
+                let elems = vec![base_blinding; num_chunks];
+                PolyComm { elems }
+            }
+        });
+
+        //~ 1. Then, include, in the canonical opening order of the index:
         //~~ * the negated public polynomial
         //~~ * the ft polynomial
         //~~ * the permutation aggregation polynomial z polynomial
-        //~~ * the generic selector
-        //~~ * the poseidon selector
+        //~~ * the selectors of the base gates
         //~~ * the 15 registers/witness columns
+        //~~ * the 15 coefficient columns
         //~~ * the 6 sigmas
-        polynomials.push((coefficients_form(&public_poly), fixed_hiding(num_chunks)));
-        polynomials.push((coefficients_form(&ft), blinding_ft));
-        polynomials.push((coefficients_form(&z_poly), z_comm.blinders));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.generic_selector4),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.poseidon_selector8),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.complete_add_selector4),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.mul_selector8),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.emul_selector8),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.push((
-            evaluations_form(&index.column_evaluations.endomul_scalar_selector8),
-            fixed_hiding(num_chunks),
-        ));
-        polynomials.extend(
-            witness_poly
-                .iter()
-                .zip(w_comm.iter())
-                .map(|(w, c)| (coefficients_form(w), c.blinders.clone()))
-                .collect::<Vec<_>>(),
-        );
-        polynomials.extend(
-            index
-                .column_evaluations
-                .coefficients8
-                .iter()
-                .map(|coefficientm| (evaluations_form(coefficientm), non_hiding(num_chunks)))
-                .collect::<Vec<_>>(),
-        );
-        polynomials.extend(
-            index.column_evaluations.permutation_coefficients8[0..PERMUTS - 1]
-                .iter()
-                .map(|w| (evaluations_form(w), non_hiding(num_chunks)))
-                .collect::<Vec<_>>(),
-        );
-
-        //~~ * the optional gates
-        if let Some(range_check0_selector8) =
-            index.column_evaluations.range_check0_selector8.as_ref()
-        {
-            polynomials.push((
-                evaluations_form(range_check0_selector8),
-                non_hiding(num_chunks),
-            ));
-        }
-        if let Some(range_check1_selector8) =
-            index.column_evaluations.range_check1_selector8.as_ref()
-        {
-            polynomials.push((
-                evaluations_form(range_check1_selector8),
-                non_hiding(num_chunks),
-            ));
-        }
-        if let Some(foreign_field_add_selector8) = index
-            .column_evaluations
-            .foreign_field_add_selector8
-            .as_ref()
-        {
-            polynomials.push((
-                evaluations_form(foreign_field_add_selector8),
-                non_hiding(num_chunks),
-            ));
-        }
-        if let Some(foreign_field_mul_selector8) = index
-            .column_evaluations
-            .foreign_field_mul_selector8
-            .as_ref()
-        {
-            polynomials.push((
-                evaluations_form(foreign_field_mul_selector8),
-                non_hiding(num_chunks),
-            ));
-        }
-        if let Some(xor_selector8) = index.column_evaluations.xor_selector8.as_ref() {
-            polynomials.push((evaluations_form(xor_selector8), non_hiding(num_chunks)));
-        }
-        if let Some(rot_selector8) = index.column_evaluations.rot_selector8.as_ref() {
-            polynomials.push((evaluations_form(rot_selector8), non_hiding(num_chunks)));
-        }
-
-        //~~ * optionally, the runtime table
+        //~~ * the selectors of the optional gates
         //~ 1. if using lookup:
-        if let Some(lcs) = &index.cs.lookup_constraint_system {
-            //~~ * add the lookup sorted polynomials
-            let sorted_poly = lookup_context.sorted_coeffs.as_ref().unwrap();
-            let sorted_comms = lookup_context.sorted_comms.as_ref().unwrap();
-
-            for (poly, comm) in sorted_poly.iter().zip(sorted_comms) {
-                polynomials.push((coefficients_form(poly), comm.blinders.clone()));
+        //~~ * the lookup sorted polynomials
+        //~~ * the lookup aggreg polynomial
+        //~~ * the combined table polynomial
+        //~~ * if present, the runtime table polynomial and its selector
+        //~~ * the lookup selectors
+        let lookup_selector = |pattern| {
+            let selectors = &index.cs.lookup_constraint_system.as_ref()?.lookup_selectors;
+            match pattern {
+                LookupPattern::Xor => selectors.xor.as_ref(),
+                LookupPattern::Lookup => selectors.lookup.as_ref(),
+                LookupPattern::RangeCheck => selectors.range_check.as_ref(),
+                LookupPattern::ForeignFieldMul => selectors.ffmul.as_ref(),
+                LookupPattern::VectorLookup => selectors.vector_lookup.as_ref(),
             }
-
-            //~~ * add the lookup aggreg polynomial
-            let aggreg_poly = lookup_context.aggreg_coeffs.as_ref().unwrap();
-            let aggreg_comm = lookup_context.aggreg_comm.as_ref().unwrap();
-            polynomials.push((coefficients_form(aggreg_poly), aggreg_comm.blinders.clone()));
-
-            //~~ * add the combined table polynomial
-            let table_blinding = {
-                let joint_combiner = lookup_context.joint_combiner.as_ref().unwrap();
-                let table_id_combiner = lookup_context.table_id_combiner.as_ref().unwrap();
-                let max_fixed_lookup_table_size = {
-                    // CAUTION: This is not `lcs.configuration.lookup_info.max_joint_size` because
-                    // the lookup table may be strictly narrower, and as such will not contribute
-                    // the associated blinders.
-                    // For example, using a runtime table with the lookup gate (width 2), but only
-                    // width-1 fixed tables (e.g. range check), it would be incorrect to use the
-                    // wider width (2) because there are no such contributing commitments!
-                    // Note that lookup_table8 is a list of polynomials
-                    lcs.lookup_table8.len()
-                };
-                let base_blinding = {
-                    let fixed_table_blinding = if max_fixed_lookup_table_size == 0 {
-                        G::ScalarField::zero()
-                    } else {
-                        (1..max_fixed_lookup_table_size).fold(G::ScalarField::one(), |acc, _| {
-                            G::ScalarField::one() + *joint_combiner * acc
-                        })
+        };
+        let column_evaluations = &index.column_evaluations;
+        let order = index.opening_order();
+        let mut polynomials = Vec::with_capacity(order.len());
+        for polynomial in order {
+            let opened = match polynomial {
+                OpenedPolynomial::PreviousChallenge(i) => {
+                    let (p, d1_size) = &polys[i];
+                    (coefficients_form(p), non_hiding(*d1_size))
+                }
+                OpenedPolynomial::Public => {
+                    (coefficients_form(&public_poly), fixed_hiding(num_chunks))
+                }
+                OpenedPolynomial::Ft => (coefficients_form(&ft), blinding_ft.clone()),
+                OpenedPolynomial::Column(col) => {
+                    let selector = match col {
+                        Column::Index(GateType::Generic) => {
+                            Some(&column_evaluations.generic_selector4)
+                        }
+                        Column::Index(GateType::Poseidon) => {
+                            Some(&column_evaluations.poseidon_selector8)
+                        }
+                        Column::Index(GateType::CompleteAdd) => {
+                            Some(&column_evaluations.complete_add_selector4)
+                        }
+                        Column::Index(GateType::VarBaseMul) => {
+                            Some(&column_evaluations.mul_selector8)
+                        }
+                        Column::Index(GateType::EndoMul) => {
+                            Some(&column_evaluations.emul_selector8)
+                        }
+                        Column::Index(GateType::EndoMulScalar) => {
+                            Some(&column_evaluations.endomul_scalar_selector8)
+                        }
+                        _ => None,
                     };
-                    fixed_table_blinding + *table_id_combiner
-                };
-                if lcs.runtime_selector.is_some() {
-                    let runtime_comm = lookup_context.runtime_table_comm.as_ref().unwrap();
-
-                    let elems = runtime_comm
-                        .blinders
-                        .elems
-                        .iter()
-                        .map(|blinding| *joint_combiner * blinding + base_blinding)
-                        .collect();
-
-                    PolyComm { elems }
-                } else {
-                    let elems = vec![base_blinding; num_chunks];
-                    PolyComm { elems }
+                    match (col, selector) {
+                        // the selectors of the base gates are committed with a fixed blinder
+                        (_, Some(selector)) => {
+                            (evaluations_form(selector), fixed_hiding(num_chunks))
+                        }
+                        (Column::Z, _) => (coefficients_form(&z_poly), z_comm.blinders.clone()),
+                        (Column::Witness(i), _) => (
+                            coefficients_form(&witness_poly[i]),
+                            w_comm[i].blinders.clone(),
+                        ),
+                        (Column::Coefficient(i), _) => (
+                            evaluations_form(&column_evaluations.coefficients8[i]),
+                            non_hiding(num_chunks),
+                        ),
+                        (Column::Permutation(i), _) => (
+                            evaluations_form(&column_evaluations.permutation_coefficients8[i]),
+                            non_hiding(num_chunks),
+                        ),
+                        (Column::Index(typ), _) => {
+                            let selector = match typ {
+                                GateType::RangeCheck0 => {
+                                    column_evaluations.range_check0_selector8.as_ref()
+                                }
+                                GateType::RangeCheck1 => {
+                                    column_evaluations.range_check1_selector8.as_ref()
+                                }
+                                GateType::ForeignFieldAdd => {
+                                    column_evaluations.foreign_field_add_selector8.as_ref()
+                                }
+                                GateType::ForeignFieldMul => {
+                                    column_evaluations.foreign_field_mul_selector8.as_ref()
+                                }
+                                GateType::Xor16 => column_evaluations.xor_selector8.as_ref(),
+                                GateType::Rot64 => column_evaluations.rot_selector8.as_ref(),
+                                GateType::Custom => column_evaluations.custom_selector8.as_ref(),
+                                _ => None,
+                            };
+                            let selector =
+                                selector.expect("the opening order only lists present selectors");
+                            (evaluations_form(selector), non_hiding(num_chunks))
+                        }
+                        (Column::LookupSorted(i), _) => {
+                            let sorted_poly = lookup_context.sorted_coeffs.as_ref().unwrap();
+                            let sorted_comms = lookup_context.sorted_comms.as_ref().unwrap();
+                            (
+                                coefficients_form(&sorted_poly[i]),
+                                sorted_comms[i].blinders.clone(),
+                            )
+                        }
+                        (Column::LookupAggreg, _) => {
+                            let aggreg_poly = lookup_context.aggreg_coeffs.as_ref().unwrap();
+                            let aggreg_comm = lookup_context.aggreg_comm.as_ref().unwrap();
+                            (coefficients_form(aggreg_poly), aggreg_comm.blinders.clone())
+                        }
+                        (Column::LookupTable, _) => {
+                            let joint_lookup_table =
+                                lookup_context.joint_lookup_table.as_ref().unwrap();
+                            (
+                                coefficients_form(joint_lookup_table),
+                                table_blinding.clone().unwrap(),
+                            )
+                        }
+                        (Column::LookupRuntimeTable, _) => {
+                            let runtime_table_comm =
+                                lookup_context.runtime_table_comm.as_ref().unwrap();
+                            let runtime_table = lookup_context.runtime_table.as_ref().unwrap();
+                            (
+                                coefficients_form(runtime_table),
+                                runtime_table_comm.blinders.clone(),
+                            )
+                        }
+                        (Column::LookupRuntimeSelector, _) => {
+                            let runtime_selector = index
+                                .cs
+                                .lookup_constraint_system
+                                .as_ref()
+                                .and_then(|lcs| lcs.runtime_selector.as_ref())
+                                .unwrap();
+                            (evaluations_form(runtime_selector), non_hiding(1))
+                        }
+                        (Column::LookupKindIndex(pattern), _) => {
+                            let selector = lookup_selector(pattern)
+                                .expect("the opening order only lists present selectors");
+                            (evaluations_form(selector), non_hiding(1))
+                        }
+                    }
                 }
             };
-
-            let joint_lookup_table = lookup_context.joint_lookup_table.as_ref().unwrap();
-
-            polynomials.push((coefficients_form(joint_lookup_table), table_blinding));
-
-            //~~ * if present, add the runtime table polynomial
-            if lcs.runtime_selector.is_some() {
-                let runtime_table_comm = lookup_context.runtime_table_comm.as_ref().unwrap();
-                let runtime_table = lookup_context.runtime_table.as_ref().unwrap();
-
-                polynomials.push((
-                    coefficients_form(runtime_table),
-                    runtime_table_comm.blinders.clone(),
-                ));
-            }
-
-            //~~ * the lookup selectors
-
-            if let Some(runtime_lookup_table_selector) = lcs.runtime_selector.as_ref() {
-                polynomials.push((
-                    evaluations_form(runtime_lookup_table_selector),
-                    non_hiding(1),
-                ))
-            }
-            if let Some(xor_lookup_selector) = lcs.lookup_selectors.xor.as_ref() {
-                polynomials.push((evaluations_form(xor_lookup_selector), non_hiding(1)))
-            }
-            if let Some(lookup_gate_selector) = lcs.lookup_selectors.lookup.as_ref() {
-                polynomials.push((evaluations_form(lookup_gate_selector), non_hiding(1)))
-            }
-            if let Some(range_check_lookup_selector) = lcs.lookup_selectors.range_check.as_ref() {
-                polynomials.push((evaluations_form(range_check_lookup_selector), non_hiding(1)))
-            }
-            if let Some(foreign_field_mul_lookup_selector) = lcs.lookup_selectors.ffmul.as_ref() {
-                polynomials.push((
-                    evaluations_form(foreign_field_mul_lookup_selector),
-                    non_hiding(1),
-                ))
-            }
+            polynomials.push(opened);
         }
 
+        cancel.check()?;
+
         //~ 1. Create an aggregated evaluation proof for all of these polynomials at $\zeta$ and $\zeta\omega$ using $u$ and $v$.
         internal_tracing::checkpoint!(internal_traces; create_aggregated_evaluation_proof);
         let proof = OpenProof::open(
@@ -1471,6 +1854,7 @@ where
             evals: chunked_evals,
             ft_eval1,
             prev_challenges,
+            index_digest: Some(index.index_digest_from::<EFqSponge>(verifier_index_digest)),
         };
 
         internal_tracing::checkpoint!(internal_traces; create_recursive_done);
@@ -1765,6 +2149,7 @@ pub mod caml {
                     .into_iter()
                     .map(Into::into)
                     .collect(),
+                index_digest: None,
             };
 
             (proof, caml_pp.public.into_iter().map(Into::into).collect())
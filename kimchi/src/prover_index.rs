@@ -9,7 +9,7 @@ use crate::{
     },
     curve::KimchiCurve,
     linearization::expr_linearization,
-    verifier_index::VerifierIndex,
+    verifier_index::{IndexParameters, VerifierIndex},
 };
 use ark_ff::PrimeField;
 use mina_poseidon::FqSponge;
@@ -18,6 +18,24 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_with::serde_as;
 use std::sync::Arc;
 
+/// How the prover trades speed for memory
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MemoryMode {
+    /// Keeps all the evaluations of the polynomials until the end of the
+    /// proof
+    #[default]
+    Speed,
+    /// Only evaluates the witness over the domain d8 for the columns and
+    /// shifts used by the quotient, and drops the evaluations as soon as they
+    /// are not used anymore: the witness and the sorted lookup polynomials
+    /// over d1 after the permutation aggregation, and all the evaluations over
+    /// d8 once the quotient and the linearization are computed, so that they
+    /// are not kept alive with the coefficients of the polynomials while
+    /// opening them.
+    /// The proofs are the same as with [MemoryMode::Speed].
+    Budget,
+}
+
 /// The index used by the prover
 #[serde_as]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -53,6 +71,10 @@ pub struct ProverIndex<G: KimchiCurve, OpeningProof: OpenProof<G>> {
     /// The verifier index digest corresponding to this prover index
     #[serde_as(as = "Option<o1_utils::serialization::SerdeAs>")]
     pub verifier_index_digest: Option<G::BaseField>,
+
+    /// How the prover trades speed for memory
+    #[serde(skip)]
+    pub memory_mode: MemoryMode,
 }
 //~spec:endcode
 
@@ -70,7 +92,14 @@ where
         cs.endo = endo_q;
 
         // pre-compute the linearization
-        let (linearization, powers_of_alpha) = expr_linearization(Some(&cs.feature_flags), true);
+        let (linearization, powers_of_alpha) = expr_linearization(
+            Some(&cs.feature_flags),
+            true,
+            &cs.base_gates,
+            cs.custom_gates.as_deref(),
+            cs.lookup_argument(),
+            cs.max_lookups_per_row(),
+        );
 
         let evaluated_column_coefficients = cs.evaluated_column_coefficients();
 
@@ -85,6 +114,7 @@ where
             column_evaluations,
             verifier_index: None,
             verifier_index_digest: None,
+            memory_mode: MemoryMode::default(),
         }
     }
 
@@ -130,6 +160,39 @@ where
             Some(verifier_index) => verifier_index.digest::<EFqSponge>(),
         }
     }
+
+    /// Compute the integrity digest of the index, which is equal to the
+    /// [`VerifierIndex::index_digest`] of the corresponding verifier index.
+    pub fn index_digest<EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>>(
+        &self,
+    ) -> G::BaseField
+    where
+        VerifierIndex<G, OpeningProof>: Clone,
+    {
+        self.index_digest_from::<EFqSponge>(self.verifier_index_digest::<EFqSponge>())
+    }
+
+    /// Compute the integrity digest of the index from the digest of the
+    /// commitments of the verifier index
+    pub(crate) fn index_digest_from<EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>>(
+        &self,
+        verifier_index_digest: G::BaseField,
+    ) -> G::BaseField {
+        IndexParameters {
+            domain_size: self.cs.domain.d1.size,
+            max_poly_size: self.max_poly_size,
+            zk_rows: self.cs.zk_rows,
+            public: self.cs.public,
+            prev_challenges: self.cs.prev_challenges,
+            shift: &self.cs.shift,
+            lookup: self
+                .cs
+                .lookup_constraint_system
+                .as_ref()
+                .map(|lcs| (&lcs.configuration.lookup_info, lcs.configuration.argument)),
+        }
+        .digest::<G, EFqSponge>(verifier_index_digest)
+    }
 }
 
 pub mod testing {
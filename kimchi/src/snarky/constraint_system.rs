@@ -990,7 +990,7 @@ impl<Field: PrimeField> SnarkyConstraintSystem<Field> {
             (s, ConstantOrVar::Constant) => match self.cached_constants.get(&s) {
                 Some(x) => *x,
                 None => {
-                    let x = self.create_internal(None, vec![]);
+                    let x = self.create_internal(Some(s), vec![]);
                     self.add_generic_constraint(
                         labels,
                         loc,
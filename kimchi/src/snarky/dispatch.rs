@@ -0,0 +1,239 @@
+//! Compilation of several circuits, the methods of an application, into a
+//! single circuit with a method selector.
+//!
+//! An application with several entry points (like a zkApp) would otherwise
+//! need one prover index and one verifier index per method. Instead, a
+//! [Dispatcher] is a [SnarkyCircuit] whose public input is the index of the
+//! called method followed by the public input of the methods:
+//!
+//! - a boolean selector is computed for each method, and the selectors are
+//!   constrained to select exactly the method of the public index;
+//! - every method is laid out in the circuit. The selected method is given the
+//!   public input and the private input of the call, while the other ones are
+//!   given their [SnarkyMethod::dummy_inputs], which satisfy their constraints;
+//! - the public output is the output of the selected method.
+//!
+//! The rows of the public input and output and of the selection are shared by
+//! all the methods, and the size of the circuit is the sum of the sizes of the
+//! methods.
+
+use std::marker::PhantomData;
+
+use ark_ec::AffineCurve;
+use ark_ff::{One, PrimeField};
+
+use crate::{
+    curve::KimchiCurve,
+    loc,
+    snarky::{
+        api::SnarkyCircuit, boolean::Boolean, cvar::FieldVar, errors::SnarkyResult,
+        runner::RunState, snarky_type::SnarkyType,
+    },
+};
+use poly_commitment::OpenProof;
+
+type ScalarField<C> = <C as AffineCurve>::ScalarField;
+
+/// A method of an application, compiled together with the other methods by a
+/// [Dispatcher]. All the methods share the same public input, public output
+/// and private input types.
+pub trait SnarkyMethod<F: PrimeField> {
+    /// The public input of the method.
+    type PublicInput: SnarkyType<F>;
+
+    /// The public output returned by the method.
+    type PublicOutput: SnarkyType<F>;
+
+    /// The private input used by the method.
+    type PrivateInput;
+
+    /// The circuit of the method, see [SnarkyCircuit::circuit].
+    fn method(
+        &self,
+        sys: &mut RunState<F>,
+        public_input: Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput>;
+
+    /// Inputs satisfying the constraints of the method, used to fill its rows
+    /// when another method is called.
+    fn dummy_inputs(
+        &self,
+    ) -> (
+        <Self::PublicInput as SnarkyType<F>>::OutOfCircuit,
+        Self::PrivateInput,
+    );
+}
+
+/// A method of a [Dispatcher]
+pub type BoxedMethod<F, PublicInput, PublicOutput, PrivateInput> = Box<
+    dyn SnarkyMethod<
+        F,
+        PublicInput = PublicInput,
+        PublicOutput = PublicOutput,
+        PrivateInput = PrivateInput,
+    >,
+>;
+
+/// The circuit of all the methods of an application, see the
+/// [module documentation](self).
+/// The public input of a call is `(index, public_input)`, where `index` is the
+/// position of the called method in the list given to [Dispatcher::new].
+pub struct Dispatcher<Curve, Proof, PublicInput, PublicOutput, PrivateInput>
+where
+    Curve: KimchiCurve,
+{
+    methods: Vec<BoxedMethod<ScalarField<Curve>, PublicInput, PublicOutput, PrivateInput>>,
+    _proof: PhantomData<Proof>,
+}
+
+impl<Curve, Proof, PublicInput, PublicOutput, PrivateInput>
+    Dispatcher<Curve, Proof, PublicInput, PublicOutput, PrivateInput>
+where
+    Curve: KimchiCurve,
+{
+    /// Creates the dispatcher of `methods`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if there are no methods.
+    pub fn new(
+        methods: Vec<BoxedMethod<ScalarField<Curve>, PublicInput, PublicOutput, PrivateInput>>,
+    ) -> Self {
+        assert!(
+            !methods.is_empty(),
+            "a dispatcher needs at least one method"
+        );
+        Self {
+            methods,
+            _proof: PhantomData,
+        }
+    }
+
+    /// Returns the number of methods.
+    pub fn num_methods(&self) -> usize {
+        self.methods.len()
+    }
+}
+
+impl<Curve, Proof, PublicInput, PublicOutput, PrivateInput> SnarkyCircuit
+    for Dispatcher<Curve, Proof, PublicInput, PublicOutput, PrivateInput>
+where
+    Curve: KimchiCurve,
+    Proof: OpenProof<Curve>,
+    PublicInput: SnarkyType<ScalarField<Curve>>,
+    PublicOutput: SnarkyType<ScalarField<Curve>>,
+{
+    type Curve = Curve;
+    type Proof = Proof;
+
+    type PrivateInput = PrivateInput;
+    type PublicInput = (FieldVar<ScalarField<Curve>>, PublicInput);
+    type PublicOutput = PublicOutput;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<ScalarField<Curve>>,
+        (index, public_input): Self::PublicInput,
+        private_input: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        // the selectors of the methods
+        let mut selectors = Vec::with_capacity(self.methods.len());
+        for i in 0..self.methods.len() {
+            let index = index.clone();
+            let selector: Boolean<_> = sys.compute(loc!(), move |env| {
+                env.read_var(&index) == ScalarField::<Curve>::from(i as u64)
+            })?;
+            selectors.push(selector.to_field_var());
+        }
+        let one_method = FieldVar::sum(&selectors.iter().collect::<Vec<_>>());
+        sys.assert_eq(
+            Some("one method is called".into()),
+            loc!(),
+            one_method,
+            FieldVar::constant(ScalarField::<Curve>::one()),
+        )?;
+        let selected = FieldVar::linear_combination(
+            &selectors
+                .iter()
+                .enumerate()
+                .map(|(i, selector)| (ScalarField::<Curve>::from(i as u64), selector.clone()))
+                .collect::<Vec<_>>(),
+        );
+        sys.assert_eq(
+            Some("the called method is selected".into()),
+            loc!(),
+            selected,
+            index.clone(),
+        )?;
+
+        // the index of the called method, when generating the witness
+        let called = private_input.map(|_| index.eval(sys));
+
+        let (public_cvars, _) = public_input.to_cvars();
+        let mut output: Option<Vec<FieldVar<_>>> = None;
+        for (i, (method, selector)) in self.methods.iter().zip(selectors).enumerate() {
+            let is_called = called == Some(ScalarField::<Curve>::from(i as u64));
+            let (dummy_public_input, dummy_private_input) = method.dummy_inputs();
+            let (dummy_public_input, _) = PublicInput::value_to_field_elements(&dummy_public_input);
+
+            // the public input of the call, or the dummy one
+            let mut input_cvars = Vec::with_capacity(public_cvars.len());
+            for (public_cvar, dummy) in public_cvars.iter().zip(dummy_public_input) {
+                let public = public_cvar.clone();
+                let input: FieldVar<_> = sys.compute(loc!(), move |env| {
+                    if is_called {
+                        env.read_var(&public)
+                    } else {
+                        dummy
+                    }
+                })?;
+                // the called method must use the public input
+                sys.assert_r1cs(
+                    Some("the public input is passed to the called method".into()),
+                    loc!(),
+                    selector.clone(),
+                    &input - public_cvar,
+                    FieldVar::zero(),
+                )?;
+                input_cvars.push(input);
+            }
+            let input = PublicInput::from_cvars_unsafe(
+                input_cvars,
+                PublicInput::constraint_system_auxiliary(),
+            );
+
+            // the private input of the call, or the dummy one
+            let private = match private_input {
+                Some(private_input) if is_called => Some(private_input),
+                Some(_) => Some(&dummy_private_input),
+                None => None,
+            };
+            let (method_output, _) = method.method(sys, input, private)?.to_cvars();
+
+            // only the output of the called method is kept
+            let mut selected_output = Vec::with_capacity(method_output.len());
+            for cvar in method_output {
+                selected_output.push(cvar.mul(
+                    &selector,
+                    Some("output of the called method".into()),
+                    loc!(),
+                    sys,
+                )?);
+            }
+            output = Some(match output {
+                None => selected_output,
+                Some(output) => output
+                    .iter()
+                    .zip(selected_output.iter())
+                    .map(|(acc, cvar)| acc + cvar)
+                    .collect(),
+            });
+        }
+
+        Ok(PublicOutput::from_cvars_unsafe(
+            output.expect("a dispatcher has at least one method"),
+            PublicOutput::constraint_system_auxiliary(),
+        ))
+    }
+}
@@ -0,0 +1,171 @@
+//! Membership proofs for the Merkle trees of [mina_poseidon::merkle].
+//!
+//! The root is recomputed in the circuit from the leaf and its path, with the
+//! same compression function and the same tags as the native implementation,
+//! so that the roots computed by [mina_poseidon::merkle::MerkleTree] can be
+//! used as public inputs.
+//!
+//! The position of the node on the path is given, at each level, as a one-hot
+//! vector of [Boolean]s, from which the node is inserted among its siblings
+//! without branching. The index of the leaf is recomposed from the positions,
+//! see [MerklePathVar::index].
+
+use crate::snarky::{boolean::Boolean, cvar::FieldVar, errors::SnarkyResult, runner::RunState};
+use ark_ff::PrimeField;
+use mina_poseidon::merkle::{tag, MerklePath};
+use std::borrow::Cow;
+
+/// The membership proof of a leaf in a Merkle tree, in the circuit
+#[derive(Debug, Clone)]
+pub struct MerklePathVar<F: PrimeField> {
+    /// For each level, from the leaves to the root, the one-hot encoding of
+    /// the position of the node on the path among its siblings
+    pub positions: Vec<Vec<Boolean<F>>>,
+    /// For each level, from the leaves to the root, the siblings of the node
+    /// on the path, in order and without the node itself
+    pub siblings: Vec<Vec<FieldVar<F>>>,
+}
+
+impl<F: PrimeField> MerklePathVar<F> {
+    /// Allocates the path of a leaf in a tree of arity `arity` and depth
+    /// `depth`. `path` is only read when generating the witness.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `path` is `None` when generating the witness, or if it
+    /// does not have the given arity and depth.
+    pub fn compute(
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        arity: usize,
+        depth: usize,
+        path: Option<&MerklePath<F>>,
+    ) -> SnarkyResult<Self> {
+        assert!(arity >= 2, "the arity of a Merkle tree must be at least 2");
+        let mut positions = Vec::with_capacity(depth);
+        let mut siblings = Vec::with_capacity(depth);
+        for level in 0..depth {
+            let one_hot = (0..arity)
+                .map(|p| {
+                    sys.compute::<Boolean<F>, _>(loc.clone(), |_| {
+                        path.unwrap().positions()[level] == p
+                    })
+                })
+                .collect::<SnarkyResult<Vec<_>>>()?;
+            sys.assert_eq(
+                Some("merkle position".into()),
+                loc.clone(),
+                sum(&one_hot),
+                FieldVar::constant(F::one()),
+            )?;
+            positions.push(one_hot);
+
+            let level_siblings = (0..arity - 1)
+                .map(|j| {
+                    sys.compute::<FieldVar<F>, _>(loc.clone(), |_| path.unwrap().siblings[level][j])
+                })
+                .collect::<SnarkyResult<Vec<_>>>()?;
+            siblings.push(level_siblings);
+        }
+        Ok(Self {
+            positions,
+            siblings,
+        })
+    }
+
+    /// Returns the arity of the tree of the path
+    pub fn arity(&self) -> usize {
+        self.positions.first().map_or(0, Vec::len)
+    }
+
+    /// Returns the index of the leaf, recomposed from the positions of the
+    /// path
+    pub fn index(&self) -> FieldVar<F> {
+        let arity = F::from(self.arity() as u64);
+        let mut weight = F::one();
+        let mut terms = vec![];
+        for one_hot in &self.positions {
+            for (p, bit) in one_hot.iter().enumerate().skip(1) {
+                terms.push((weight * F::from(p as u64), bit.to_field_var()));
+            }
+            weight *= arity;
+        }
+        FieldVar::linear_combination(&terms)
+    }
+
+    /// Returns the root of the tree obtained when the leaf of the path is
+    /// `leaf`
+    pub fn root(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        leaf: &FieldVar<F>,
+    ) -> SnarkyResult<FieldVar<F>> {
+        let arity = self.arity();
+        let (mut digest, _) = sys.poseidon(
+            loc.clone(),
+            (FieldVar::constant(tag(arity, 0)), leaf.clone()),
+        );
+        for (level, (one_hot, siblings)) in self.positions.iter().zip(&self.siblings).enumerate() {
+            // the j-th child is the node if it is at position j, the j-th
+            // sibling if the node is after it, and the (j - 1)-th sibling if
+            // the node is before it
+            let mut children = Vec::with_capacity(arity);
+            for (j, bit) in one_hot.iter().enumerate() {
+                let mut child = bit.to_field_var().mul(
+                    &digest,
+                    Some("merkle node".into()),
+                    loc.clone(),
+                    sys,
+                )?;
+                if j < arity - 1 {
+                    child = child
+                        + sum(&one_hot[j + 1..]).mul(
+                            &siblings[j],
+                            Some("merkle sibling".into()),
+                            loc.clone(),
+                            sys,
+                        )?;
+                }
+                if j > 0 {
+                    child = child
+                        + sum(&one_hot[..j]).mul(
+                            &siblings[j - 1],
+                            Some("merkle sibling".into()),
+                            loc.clone(),
+                            sys,
+                        )?;
+                }
+                children.push(child);
+            }
+
+            digest = children
+                .into_iter()
+                .fold(FieldVar::constant(tag(arity, level + 1)), |acc, child| {
+                    sys.poseidon(loc.clone(), (acc, child)).0
+                });
+        }
+        Ok(digest)
+    }
+
+    /// Asserts that `leaf` is the leaf of the path in the tree of root `root`
+    pub fn assert_membership(
+        &self,
+        sys: &mut RunState<F>,
+        loc: Cow<'static, str>,
+        root: &FieldVar<F>,
+        leaf: &FieldVar<F>,
+    ) -> SnarkyResult<()> {
+        let computed = self.root(sys, loc.clone(), leaf)?;
+        sys.assert_eq(Some("merkle root".into()), loc, computed, root.clone())
+    }
+}
+
+/// The sum of the given booleans
+fn sum<F: PrimeField>(bits: &[Boolean<F>]) -> FieldVar<F> {
+    let terms: Vec<_> = bits
+        .iter()
+        .map(|bit| (F::one(), bit.to_field_var()))
+        .collect();
+    FieldVar::linear_combination(&terms)
+}
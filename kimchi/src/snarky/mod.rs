@@ -13,8 +13,10 @@ pub mod boolean;
 pub mod constants;
 pub mod constraint_system;
 pub mod cvar;
+pub mod dispatch;
 pub mod errors;
 pub mod folding;
+pub mod merkle;
 pub mod poseidon;
 pub(crate) mod range_checks;
 pub mod runner;
@@ -27,6 +27,11 @@ pub fn poseidon<F: PrimeField>(
     let (constraint, hash) = {
         let params = runner.poseidon_params();
         let mut iter = successors((initial_state, 0_usize).into(), |(prev, i)| {
+            // the successor of the last state is computed when the last state
+            // is yielded, and there is no round after it
+            if *i == ROUNDS_PER_HASH {
+                return None;
+            }
             //this case may justify moving to Cow
             let state = round(runner, loc.clone(), prev, *i, &params);
             Some((state, i + 1))
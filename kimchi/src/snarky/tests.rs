@@ -1,17 +1,21 @@
+use crate::curve::KimchiCurve;
 use crate::{
     loc,
     snarky::{
         api::SnarkyCircuit,
         boolean::Boolean,
         cvar::FieldVar,
+        dispatch::{Dispatcher, SnarkyMethod},
         errors::{SnarkyError, SnarkyRuntimeError},
+        merkle::MerklePathVar,
         runner::RunState,
     },
 };
-use ark_ff::One;
+use ark_ff::{One, Zero};
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
+    merkle::{MerklePath, MerkleTree},
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
 use poly_commitment::evaluation_proof::OpeningProof;
@@ -141,3 +145,148 @@ fn test_simple_circuit() {
         }
     }
 }
+
+struct MerkleCircuit {
+    arity: usize,
+    depth: usize,
+}
+
+impl SnarkyCircuit for MerkleCircuit {
+    type Curve = Vesta;
+    type Proof = OpeningProof<Self::Curve>;
+
+    type PrivateInput = (Fp, MerklePath<Fp>);
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = FieldVar<Fp>;
+
+    fn circuit(
+        &self,
+        sys: &mut RunState<Fp>,
+        root: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let leaf: FieldVar<Fp> = sys.compute(loc!(), |_| private.unwrap().0)?;
+        let path = MerklePathVar::compute(
+            sys,
+            loc!(),
+            self.arity,
+            self.depth,
+            private.map(|(_, path)| path),
+        )?;
+        path.assert_membership(sys, loc!(), &root, &leaf)?;
+        Ok(path.index())
+    }
+}
+
+#[test]
+fn test_merkle_membership() {
+    let params = Vesta::sponge_params();
+    // a full binary tree of depth 3
+    let leaves: Vec<Fp> = (10u64..18).map(Fp::from).collect();
+
+    for arity in [2, 3] {
+        let tree = MerkleTree::new(params, arity, 3, &leaves).unwrap();
+        let circuit = MerkleCircuit { arity, depth: 3 };
+        let (mut prover_index, verifier_index) = circuit.compile_to_indexes().unwrap();
+
+        // the index of the leaf is recomputed from its path
+        let index = 6;
+        let path = tree.path(index).unwrap();
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(tree.root(), (leaves[index], path.clone()), true)
+            .unwrap();
+        assert_eq!(*public_output, Fp::from(index as u64));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, tree.root(), *public_output);
+
+        // a different leaf is not in the tree
+        assert!(prover_index
+            .prove::<BaseSponge, ScalarSponge>(tree.root(), (leaves[index + 1], path), true)
+            .is_err());
+    }
+}
+
+/// Returns the square of `x + 1`
+struct Increment;
+
+impl SnarkyMethod<Fp> for Increment {
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = FieldVar<Fp>;
+    type PrivateInput = Fp;
+
+    fn method(
+        &self,
+        sys: &mut RunState<Fp>,
+        x: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let w: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+        sys.assert_eq(None, loc!(), w.clone(), x + FieldVar::constant(Fp::one()))?;
+        w.mul(&w, None, loc!(), sys)
+    }
+
+    fn dummy_inputs(&self) -> (Fp, Fp) {
+        (Fp::zero(), Fp::one())
+    }
+}
+
+/// Returns half of `x`, plus 3
+struct Halve;
+
+impl SnarkyMethod<Fp> for Halve {
+    type PublicInput = FieldVar<Fp>;
+    type PublicOutput = FieldVar<Fp>;
+    type PrivateInput = Fp;
+
+    fn method(
+        &self,
+        sys: &mut RunState<Fp>,
+        x: Self::PublicInput,
+        private: Option<&Self::PrivateInput>,
+    ) -> SnarkyResult<Self::PublicOutput> {
+        let w: FieldVar<Fp> = sys.compute(loc!(), |_| *private.unwrap())?;
+        sys.assert_eq(None, loc!(), w.scale(Fp::from(2)), x)?;
+        Ok(w + FieldVar::constant(Fp::from(3)))
+    }
+
+    fn dummy_inputs(&self) -> (Fp, Fp) {
+        (Fp::zero(), Fp::zero())
+    }
+}
+
+type TestDispatcher = Dispatcher<Vesta, OpeningProof<Vesta>, FieldVar<Fp>, FieldVar<Fp>, Fp>;
+
+fn test_dispatcher() -> TestDispatcher {
+    Dispatcher::new(vec![Box::new(Increment), Box::new(Halve)])
+}
+
+#[test]
+fn test_method_dispatch() {
+    // a single pair of indexes for both methods
+    let (mut prover_index, verifier_index) = test_dispatcher().compile_to_indexes().unwrap();
+
+    // the constraints of the other method are not satisfied by the inputs
+    // of the call, but by its dummy inputs
+    for (index, x, w, expected) in [(0u64, 4u64, 5u64, 25u64), (1, 10, 5, 8)] {
+        let public_input = (Fp::from(index), Fp::from(x));
+        let (proof, public_output) = prover_index
+            .prove::<BaseSponge, ScalarSponge>(public_input, Fp::from(w), true)
+            .unwrap();
+        assert_eq!(*public_output, Fp::from(expected));
+        verifier_index.verify::<BaseSponge, ScalarSponge>(proof, public_input, *public_output);
+    }
+}
+
+#[test]
+fn test_method_dispatch_invalid_calls() {
+    // the private input does not satisfy the called method
+    let (mut prover_index, _) = test_dispatcher().compile_to_indexes().unwrap();
+    assert!(prover_index
+        .prove::<BaseSponge, ScalarSponge>((Fp::one(), Fp::from(10)), Fp::from(4), true)
+        .is_err());
+
+    // there is no method with this index
+    let (mut prover_index, _) = test_dispatcher().compile_to_indexes().unwrap();
+    assert!(prover_index
+        .prove::<BaseSponge, ScalarSponge>((Fp::from(2), Fp::from(10)), Fp::from(5), true)
+        .is_err());
+}
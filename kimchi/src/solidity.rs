@@ -0,0 +1,623 @@
+//! This module generates a Solidity contract for the on-chain verification of
+//! kimchi proofs over BN254, whose polynomial commitments are KZG commitments
+//! (see [PairingProof]).
+//!
+//! The contract is specific to a [VerifierIndex], whose parameters are
+//! hardcoded as constants: the domain, the commitments to the index
+//! polynomials, the verifier SRS, and the constants used by the constraints.
+//! The linearization of the constraints is compiled from its reverse Polish
+//! notation into straight-line code. The contract exposes:
+//!
+//! * `linearizationConstantTerm`, the evaluation of the constant term of the
+//!   linearization, given the evaluations of the proof and the challenges;
+//! * `linearizationCommitment`, the commitment to the linearized part, as a
+//!   combination of the commitments of the index;
+//! * `verifyOpening`, the pairing check of a KZG opening of a combined
+//!   commitment at `zeta` and `zeta * omega`, using the precompiled contracts
+//!   of the EVM for BN254.
+//!
+//! The evaluations are passed as an array, whose layout is documented in the
+//! generated contract. The challenges and the combined commitment are derived
+//! by the caller from the transcript of the proof.
+
+use crate::{
+    circuits::{
+        berkeley_columns::Column,
+        expr::{ChallengeTerm, ConstantTerm, PolishToken, RowOffset, Variable},
+        gate::{CurrOrNext, GateType},
+    },
+    curve::KimchiCurve,
+    verifier_index::VerifierIndex,
+};
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{Field, Zero};
+use o1_utils::FieldHelpers;
+use poly_commitment::{commitment::PolyComm, pairing_proof::PairingProof};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// The pairing of BN254
+pub type BN254 = ark_ec::bn::Bn<ark_bn254::Parameters>;
+
+/// A verifier index of a circuit over BN254 with KZG commitments
+pub type BN254VerifierIndex = VerifierIndex<G1Affine, PairingProof<BN254>>;
+
+/// Errors that can arise when generating a Solidity verifier
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SolidityError {
+    #[error("the token {0} cannot be compiled to Solidity")]
+    UnsupportedToken(String),
+    #[error("the column {0} has no commitment in the verifier index")]
+    UnsupportedColumn(String),
+    #[error("the commitment to the column {0} has more than one chunk")]
+    ChunkedCommitment(String),
+    #[error("the verifier SRS has {0} points, at least 3 are needed")]
+    VerifierSRSTooSmall(usize),
+    #[error("the expression does not evaluate to a single value")]
+    InvalidStack,
+}
+
+fn scalar(x: Fr) -> String {
+    x.to_biguint().to_string()
+}
+
+fn base(x: Fq) -> String {
+    x.to_biguint().to_string()
+}
+
+/// The evaluations used by the linearization, in the order of the array
+/// passed to the generated contract
+#[derive(Default)]
+struct Evaluations {
+    variables: Vec<Variable<Column>>,
+}
+
+impl Evaluations {
+    fn index(&mut self, variable: Variable<Column>) -> usize {
+        match self.variables.iter().position(|v| *v == variable) {
+            Some(i) => i,
+            None => {
+                self.variables.push(variable);
+                self.variables.len() - 1
+            }
+        }
+    }
+}
+
+/// Compiles expressions to Solidity, collecting the constants they use
+struct Compiler<'a> {
+    index: &'a BN254VerifierIndex,
+    evaluations: Evaluations,
+    mds: Vec<(usize, usize)>,
+    lagrange_roots: Vec<i32>,
+    max_stack: usize,
+    max_cache: usize,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(index: &'a BN254VerifierIndex) -> Self {
+        Compiler {
+            index,
+            evaluations: Evaluations::default(),
+            mds: vec![],
+            lagrange_roots: vec![],
+            max_stack: 0,
+            max_cache: 0,
+        }
+    }
+
+    /// Compiles the tokens to statements storing the result in `result`
+    fn compile(
+        &mut self,
+        tokens: &[PolishToken<Fr, Column>],
+        result: &str,
+        out: &mut String,
+    ) -> Result<(), SolidityError> {
+        let mut depth = 0usize;
+        let mut cache = 0usize;
+        let push = |out: &mut String, depth: &mut usize, value: String| {
+            writeln!(out, "        s[{depth}] = {value};").unwrap();
+            *depth += 1;
+        };
+        for token in tokens {
+            match token {
+                PolishToken::Constant(ConstantTerm::Literal(x)) => {
+                    push(out, &mut depth, scalar(*x));
+                }
+                PolishToken::Constant(ConstantTerm::EndoCoefficient) => {
+                    push(out, &mut depth, "ENDO_COEFFICIENT".to_string());
+                }
+                PolishToken::Constant(ConstantTerm::Mds { row, col }) => {
+                    if !self.mds.contains(&(*row, *col)) {
+                        self.mds.push((*row, *col));
+                    }
+                    push(out, &mut depth, format!("MDS_{row}_{col}"));
+                }
+                PolishToken::Challenge(challenge) => {
+                    let name = match challenge {
+                        ChallengeTerm::Alpha => "alpha",
+                        ChallengeTerm::Beta => "beta",
+                        ChallengeTerm::Gamma => "gamma",
+                        ChallengeTerm::JointCombiner => "jointCombiner",
+                    };
+                    push(out, &mut depth, format!("c.{name}"));
+                }
+                PolishToken::Cell(variable) => {
+                    let i = self.evaluations.index(*variable);
+                    push(out, &mut depth, format!("evals[{i}]"));
+                }
+                PolishToken::VanishesOnZeroKnowledgeAndPreviousRows => {
+                    push(
+                        out,
+                        &mut depth,
+                        "vanishesOnZkAndPreviousRows(c.zeta)".to_string(),
+                    );
+                }
+                PolishToken::UnnormalizedLagrangeBasis(RowOffset { zk_rows, offset }) => {
+                    let i = if *zk_rows {
+                        offset - self.index.zk_rows as i32
+                    } else {
+                        *offset
+                    };
+                    if !self.lagrange_roots.contains(&i) {
+                        self.lagrange_roots.push(i);
+                    }
+                    push(
+                        out,
+                        &mut depth,
+                        format!("unnormalizedLagrangeBasis(c.zeta, {})", root_name(i)),
+                    );
+                }
+                PolishToken::Dup => {
+                    let top = depth.checked_sub(1).ok_or(SolidityError::InvalidStack)?;
+                    push(out, &mut depth, format!("s[{top}]"));
+                }
+                PolishToken::Pow(n) => {
+                    let top = depth.checked_sub(1).ok_or(SolidityError::InvalidStack)?;
+                    writeln!(out, "        s[{top}] = expmod(s[{top}], {n});").unwrap();
+                }
+                PolishToken::Add | PolishToken::Mul | PolishToken::Sub => {
+                    let y = depth.checked_sub(1).ok_or(SolidityError::InvalidStack)?;
+                    let x = depth.checked_sub(2).ok_or(SolidityError::InvalidStack)?;
+                    let value = match token {
+                        PolishToken::Add => format!("addmod(s[{x}], s[{y}], R)"),
+                        PolishToken::Mul => format!("mulmod(s[{x}], s[{y}], R)"),
+                        _ => format!("addmod(s[{x}], R - s[{y}], R)"),
+                    };
+                    writeln!(out, "        s[{x}] = {value};").unwrap();
+                    depth -= 1;
+                }
+                PolishToken::Store => {
+                    let top = depth.checked_sub(1).ok_or(SolidityError::InvalidStack)?;
+                    writeln!(out, "        cache[{cache}] = s[{top}];").unwrap();
+                    cache += 1;
+                }
+                PolishToken::Load(i) => {
+                    if *i >= cache {
+                        return Err(SolidityError::InvalidStack);
+                    }
+                    push(out, &mut depth, format!("cache[{i}]"));
+                }
+                PolishToken::SkipIf(..) | PolishToken::SkipIfNot(..) => {
+                    return Err(SolidityError::UnsupportedToken(format!("{token:?}")));
+                }
+            }
+            self.max_stack = self.max_stack.max(depth);
+        }
+        if depth != 1 {
+            return Err(SolidityError::InvalidStack);
+        }
+        self.max_cache = self.max_cache.max(cache);
+        writeln!(out, "        {result} = s[0];").unwrap();
+        Ok(())
+    }
+}
+
+fn root_name(i: i32) -> String {
+    if i < 0 {
+        format!("LAGRANGE_ROOT_MINUS_{}", -i)
+    } else {
+        format!("LAGRANGE_ROOT_{i}")
+    }
+}
+
+/// Returns the commitment of the verifier index to the column, if any
+fn index_commitment(index: &BN254VerifierIndex, col: Column) -> Option<&PolyComm<G1Affine>> {
+    match col {
+        Column::Coefficient(i) => Some(&index.coefficients_comm[i]),
+        Column::Permutation(i) => Some(&index.sigma_comm[i]),
+        Column::Index(gate) => match gate {
+            GateType::Generic => Some(&index.generic_comm),
+            GateType::Poseidon => index.psm_comm.as_ref(),
+            GateType::CompleteAdd => index.complete_add_comm.as_ref(),
+            GateType::VarBaseMul => index.mul_comm.as_ref(),
+            GateType::EndoMul => index.emul_comm.as_ref(),
+            GateType::EndoMulScalar => index.endomul_scalar_comm.as_ref(),
+            GateType::RangeCheck0 => index.range_check0_comm.as_ref(),
+            GateType::RangeCheck1 => index.range_check1_comm.as_ref(),
+            GateType::ForeignFieldAdd => index.foreign_field_add_comm.as_ref(),
+            GateType::ForeignFieldMul => index.foreign_field_mul_comm.as_ref(),
+            GateType::Xor16 => index.xor_comm.as_ref(),
+            GateType::Rot64 => index.rot_comm.as_ref(),
+            GateType::Custom => index.custom_comm.as_ref(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn g1_constant(out: &mut String, name: &str, point: &G1Affine) {
+    let (x, y) = if point.infinity {
+        (Fq::zero(), Fq::zero())
+    } else {
+        (point.x, point.y)
+    };
+    writeln!(out, "    uint256 internal constant {name}_X = {};", base(x)).unwrap();
+    writeln!(out, "    uint256 internal constant {name}_Y = {};", base(y)).unwrap();
+}
+
+fn g2_constant(out: &mut String, name: &str, point: &G2Affine) {
+    let coordinates: [(&str, Fq2); 2] = [("X", point.x), ("Y", point.y)];
+    for (coordinate, value) in coordinates {
+        // the precompiled contracts expect the imaginary part first
+        writeln!(
+            out,
+            "    uint256 internal constant {name}_{coordinate}_IM = {};",
+            base(value.c1)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    uint256 internal constant {name}_{coordinate}_RE = {};",
+            base(value.c0)
+        )
+        .unwrap();
+    }
+}
+
+const HELPERS: &str = r#"
+    function expmod(uint256 b, uint256 e) internal view returns (uint256 r) {
+        bool success;
+        assembly {
+            let p := mload(0x40)
+            mstore(p, 0x20)
+            mstore(add(p, 0x20), 0x20)
+            mstore(add(p, 0x40), 0x20)
+            mstore(add(p, 0x60), b)
+            mstore(add(p, 0x80), e)
+            mstore(add(p, 0xa0), R)
+            success := staticcall(gas(), 0x05, p, 0xc0, p, 0x20)
+            r := mload(p)
+        }
+        require(success, "expmod failed");
+    }
+
+    function inverse(uint256 a) internal view returns (uint256) {
+        require(a != 0, "inverse of zero");
+        return expmod(a, R - 2);
+    }
+
+    function ecAdd(G1Point memory a, G1Point memory b) internal view returns (G1Point memory r) {
+        uint256[4] memory input = [a.x, a.y, b.x, b.y];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x06, input, 0x80, r, 0x40)
+        }
+        require(success, "ecAdd failed");
+    }
+
+    function ecMul(G1Point memory p, uint256 s) internal view returns (G1Point memory r) {
+        uint256[3] memory input = [p.x, p.y, s];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x07, input, 0x60, r, 0x40)
+        }
+        require(success, "ecMul failed");
+    }
+
+    function negate(G1Point memory p) internal pure returns (G1Point memory) {
+        if (p.x == 0 && p.y == 0) {
+            return p;
+        }
+        return G1Point(p.x, Q - (p.y % Q));
+    }
+
+    function unnormalizedLagrangeBasis(uint256 zeta, uint256 root) internal view returns (uint256) {
+        uint256 vanishing = addmod(expmod(zeta, DOMAIN_SIZE), R - 1, R);
+        return mulmod(vanishing, inverse(addmod(zeta, R - root, R)), R);
+    }
+"#;
+
+const VERIFY_OPENING: &str = r#"
+    /// @notice Checks the KZG opening `quotient` of `combined` at `zeta` and
+    /// `zeta * omega`, where it evaluates to `evalZeta` and `evalZetaOmega`,
+    /// and `blinding` is the combined blinding factor of the commitments.
+    function verifyOpening(
+        G1Point memory combined,
+        uint256 evalZeta,
+        uint256 evalZetaOmega,
+        uint256 zeta,
+        G1Point memory quotient,
+        uint256 blinding
+    ) public view returns (bool) {
+        uint256 zetaOmega = mulmod(zeta, DOMAIN_GENERATOR, R);
+        // the polynomial a + b X evaluating to evalZeta at zeta and to
+        // evalZetaOmega at zeta * omega
+        uint256 b = mulmod(
+            addmod(evalZetaOmega, R - evalZeta, R),
+            inverse(addmod(zetaOmega, R - zeta, R)),
+            R
+        );
+        uint256 a = addmod(evalZeta, R - mulmod(b, zeta, R), R);
+        G1Point memory evalCommitment = ecAdd(
+            ecMul(G1Point(SRS_G1_0_X, SRS_G1_0_Y), a),
+            ecMul(G1Point(SRS_G1_1_X, SRS_G1_1_Y), b)
+        );
+        G1Point memory numerator = ecAdd(
+            combined,
+            negate(ecAdd(evalCommitment, ecMul(G1Point(SRS_H_X, SRS_H_Y), blinding)))
+        );
+
+        // the divisor is X^2 - s X + p, committed in G2 with the verifier SRS,
+        // which gives e(numerator - p quotient, [1]) e(s quotient, [x])
+        // e(-quotient, [x^2]) = 1
+        uint256 s = addmod(zeta, zetaOmega, R);
+        uint256 p = mulmod(zeta, zetaOmega, R);
+        G1Point memory first = ecAdd(numerator, negate(ecMul(quotient, p)));
+        G1Point memory second = ecMul(quotient, s);
+        G1Point memory third = negate(quotient);
+
+        uint256[18] memory input = [
+            first.x, first.y,
+            SRS_G2_0_X_IM, SRS_G2_0_X_RE, SRS_G2_0_Y_IM, SRS_G2_0_Y_RE,
+            second.x, second.y,
+            SRS_G2_1_X_IM, SRS_G2_1_X_RE, SRS_G2_1_Y_IM, SRS_G2_1_Y_RE,
+            third.x, third.y,
+            SRS_G2_2_X_IM, SRS_G2_2_X_RE, SRS_G2_2_Y_IM, SRS_G2_2_Y_RE
+        ];
+        uint256[1] memory result;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 0x08, input, 0x240, result, 0x20)
+        }
+        return success && result[0] == 1;
+    }
+"#;
+
+/// Generates the Solidity contract `name` for the verifier index, see the
+/// [module documentation](self).
+///
+/// # Errors
+///
+/// Will give error if the linearization uses feature flags that are not
+/// resolved, or columns that are not committed in the verifier index, if a
+/// commitment has several chunks, or if the verifier SRS is too small.
+pub fn generate_verifier(index: &BN254VerifierIndex, name: &str) -> Result<String, SolidityError> {
+    let verifier_srs = &index.srs.verifier_srs.g;
+    if verifier_srs.len() < 3 {
+        return Err(SolidityError::VerifierSRSTooSmall(verifier_srs.len()));
+    }
+    let full_srs = &index.srs.full_srs;
+
+    // compile the linearization first, to collect the constants it uses
+    let mut compiler = Compiler::new(index);
+    let mut constant_term = String::new();
+    compiler.compile(
+        &index.linearization.constant_term,
+        "result",
+        &mut constant_term,
+    )?;
+    let mut commitment = String::new();
+    let mut commitments = vec![];
+    for (col, tokens) in &index.linearization.index_terms {
+        let comm = index_commitment(index, *col)
+            .ok_or_else(|| SolidityError::UnsupportedColumn(format!("{col:?}")))?;
+        if comm.elems.len() != 1 {
+            return Err(SolidityError::ChunkedCommitment(format!("{col:?}")));
+        }
+        let i = commitments.len();
+        commitments.push((*col, comm.elems[0]));
+        writeln!(commitment, "        // {col:?}").unwrap();
+        compiler.compile(tokens, "scalar", &mut commitment)?;
+        writeln!(
+            commitment,
+            "        result = ecAdd(result, ecMul(G1Point(COMM_{i}_X, COMM_{i}_Y), scalar));"
+        )
+        .unwrap();
+    }
+
+    let domain = index.domain;
+    let mut out = String::new();
+    writeln!(out, "// SPDX-License-Identifier: Apache-2.0").unwrap();
+    writeln!(out, "pragma solidity ^0.8.0;").unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "/// @notice Verifier of kimchi proofs for a circuit of {} rows with {} public inputs.",
+        domain.size, index.public
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "/// Generated from the verifier index, with the linearization of digest"
+    )
+    .unwrap();
+    writeln!(out, "/// {}", index.linearization.digest()).unwrap();
+    writeln!(out, "contract {name} {{").unwrap();
+    writeln!(out, "    struct G1Point {{ uint256 x; uint256 y; }}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    struct Challenges {{ uint256 alpha; uint256 beta; uint256 gamma; uint256 jointCombiner; uint256 zeta; }}"
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant R = {};",
+        Fr::modulus_biguint()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant Q = {};",
+        Fq::modulus_biguint()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant DOMAIN_SIZE = {};",
+        domain.size
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant DOMAIN_GENERATOR = {};",
+        scalar(domain.group_gen)
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant ZK_ROWS = {};",
+        index.zk_rows
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant PUBLIC_INPUTS = {};",
+        index.public
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    uint256 internal constant ENDO_COEFFICIENT = {};",
+        scalar(index.endo)
+    )
+    .unwrap();
+    let mds = &G1Affine::sponge_params().mds;
+    for (row, col) in &compiler.mds {
+        writeln!(
+            out,
+            "    uint256 internal constant MDS_{row}_{col} = {};",
+            scalar(mds[*row][*col])
+        )
+        .unwrap();
+    }
+    for i in &compiler.lagrange_roots {
+        let root = if *i < 0 {
+            domain.group_gen_inv.pow([-i as u64])
+        } else {
+            domain.group_gen.pow([*i as u64])
+        };
+        writeln!(
+            out,
+            "    uint256 internal constant {} = {};",
+            root_name(*i),
+            scalar(root)
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+
+    // the SRS
+    g1_constant(&mut out, "SRS_G1_0", &full_srs.g[0]);
+    g1_constant(&mut out, "SRS_G1_1", &full_srs.g[1]);
+    g1_constant(&mut out, "SRS_H", &full_srs.h);
+    for (i, point) in verifier_srs.iter().take(3).enumerate() {
+        g2_constant(&mut out, &format!("SRS_G2_{i}"), point);
+    }
+    writeln!(out).unwrap();
+
+    // the commitments of the index used by the linearization
+    for (i, (col, comm)) in commitments.iter().enumerate() {
+        writeln!(out, "    // {col:?}").unwrap();
+        g1_constant(&mut out, &format!("COMM_{i}"), comm);
+    }
+    writeln!(out).unwrap();
+
+    // the layout of the evaluations
+    writeln!(
+        out,
+        "    uint256 internal constant NUM_EVALUATIONS = {};",
+        compiler.evaluations.variables.len()
+    )
+    .unwrap();
+    writeln!(out, "    // Layout of the evaluations:").unwrap();
+    for (i, variable) in compiler.evaluations.variables.iter().enumerate() {
+        let point = match variable.row {
+            CurrOrNext::Curr => "zeta",
+            CurrOrNext::Next => "zeta * omega",
+        };
+        writeln!(out, "    // evals[{i}]: {:?} at {point}", variable.col).unwrap();
+    }
+
+    out.push_str(HELPERS);
+
+    // the vanishing polynomial of the zero-knowledge rows and the previous one
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    function vanishesOnZkAndPreviousRows(uint256 zeta) internal pure returns (uint256 acc) {{"
+    )
+    .unwrap();
+    writeln!(out, "        acc = 1;").unwrap();
+    for i in 0..=index.zk_rows {
+        let root = domain.group_gen.pow([domain.size - 1 - i]);
+        writeln!(
+            out,
+            "        acc = mulmod(acc, addmod(zeta, R - {}, R), R);",
+            scalar(root)
+        )
+        .unwrap();
+    }
+    writeln!(out, "    }}").unwrap();
+
+    let stack = compiler.max_stack.max(1);
+    let cache = compiler.max_cache.max(1);
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    /// @notice Evaluates the constant term of the linearization"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    function linearizationConstantTerm(uint256[] memory evals, Challenges memory c) public view returns (uint256 result) {{"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        require(evals.length == NUM_EVALUATIONS, \"invalid number of evaluations\");"
+    )
+    .unwrap();
+    writeln!(out, "        uint256[{stack}] memory s;").unwrap();
+    writeln!(out, "        uint256[{cache}] memory cache;").unwrap();
+    out.push_str(&constant_term);
+    writeln!(out, "    }}").unwrap();
+
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "    /// @notice Computes the commitment to the linearized part of the constraints"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    function linearizationCommitment(uint256[] memory evals, Challenges memory c) public view returns (G1Point memory result) {{"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "        require(evals.length == NUM_EVALUATIONS, \"invalid number of evaluations\");"
+    )
+    .unwrap();
+    writeln!(out, "        uint256[{stack}] memory s;").unwrap();
+    writeln!(out, "        uint256[{cache}] memory cache;").unwrap();
+    writeln!(out, "        uint256 scalar;").unwrap();
+    out.push_str(&commitment);
+    writeln!(out, "    }}").unwrap();
+
+    out.push_str(VERIFY_OPENING);
+    writeln!(out, "}}").unwrap();
+    Ok(out)
+}
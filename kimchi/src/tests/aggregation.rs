@@ -0,0 +1,94 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    curve::KimchiCurve,
+    proof::{ProverProof, RecursionChallenge},
+    prover_index::ProverIndex,
+    verifier::{batch_verify_succinct, Context},
+};
+use ark_ff::Zero;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+    FqSponge,
+};
+use poly_commitment::{
+    aggregation::{self, Accumulator},
+    commitment::CommitmentCurve,
+    evaluation_proof::OpeningProof,
+    srs::SRS,
+};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+#[test]
+fn test_aggregate_kimchi_proofs() {
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let rng = &mut o1_utils::tests::make_test_rng(None);
+
+    // Independent circuits of different sizes, sharing the same SRS
+    let srs = Arc::new(SRS::<Vesta>::create(1 << 7));
+    let mut indexes = vec![];
+    let mut proofs = vec![];
+    for num_public in [1, 5, 40] {
+        let gates = create_circuit(0, num_public);
+        let public = vec![Fp::from(3u8); num_public];
+        let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+        fill_in_witness(0, &mut witness, &public);
+
+        let cs = ConstraintSystem::create(gates)
+            .public(num_public)
+            .build()
+            .unwrap();
+        let mut srs = SRS::clone(&srs);
+        srs.add_lagrange_basis(cs.domain.d1);
+        let index = ProverIndex::<Vesta, OpeningProof<Vesta>>::create(
+            cs,
+            *Vesta::other_curve_endo(),
+            Arc::new(srs),
+        );
+        let proof =
+            ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index)
+                .unwrap();
+        indexes.push(index.verifier_index());
+        proofs.push((proof, public));
+    }
+    let contexts: Vec<_> = indexes
+        .iter()
+        .zip(proofs.iter())
+        .map(|(verifier_index, (proof, public_input))| Context {
+            verifier_index,
+            proof,
+            public_input,
+        })
+        .collect();
+
+    let accumulators =
+        batch_verify_succinct::<Vesta, BaseSponge, ScalarSponge>(&group_map, &contexts).unwrap();
+    assert_eq!(accumulators.len(), contexts.len());
+
+    let sponge = || BaseSponge::new(Vesta::other_curve_sponge_params());
+    let (accumulator, proof) = Accumulator::fold(&srs, &group_map, &accumulators, sponge(), rng);
+    assert_eq!(
+        aggregation::verify_aggregation(&srs, &group_map, &accumulators, &proof, sponge(), rng),
+        Some(accumulator.clone())
+    );
+    assert!(accumulator.verify_final(&srs));
+
+    // The accumulator can be serialized, and passed to the next proof as the
+    // challenges of a previous opening
+    let serialized = serde_json::to_string(&accumulator).unwrap();
+    let deserialized: Accumulator<Vesta> = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, accumulator);
+
+    let prev_challenge = RecursionChallenge::from(accumulator.clone());
+    assert_eq!(prev_challenge.accumulator(), Some(accumulator));
+}
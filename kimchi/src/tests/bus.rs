@@ -0,0 +1,165 @@
+use crate::{
+    circuits::{
+        bus::{
+            check_sorted, BusError, BusOp, BusSorted, BusTrace, SortedEvent, BUS_SORTED_GATE_ID,
+        },
+        constraints::ConstraintSystem,
+        custom_gate::CustomGateRegistry,
+        gate::CircuitGate,
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+};
+use ark_ff::{UniformRand, Zero};
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const INCREMENT_BITS: u32 = 16;
+
+// Writes, overwrites and reads a few addresses
+fn create_trace() -> BusTrace<Fp> {
+    let mut trace = BusTrace::new();
+    for addr in [7, 3, 12] {
+        trace.write(addr, Fp::from(addr * 10));
+    }
+    assert_eq!(trace.read(3), Ok(Fp::from(30u32)));
+    trace.write(3, Fp::from(31u32));
+    assert_eq!(trace.read(12), Ok(Fp::from(120u32)));
+    assert_eq!(trace.read(3), Ok(Fp::from(31u32)));
+    assert_eq!(trace.read(3), Ok(Fp::from(31u32)));
+    trace
+}
+
+fn check(trace: &BusTrace<Fp>, sorted: &[SortedEvent<Fp>]) -> Result<(), BusError> {
+    let rng = &mut rand::rngs::OsRng;
+    check_sorted(
+        trace.events(),
+        sorted,
+        INCREMENT_BITS,
+        Fp::rand(rng),
+        Fp::rand(rng),
+    )
+}
+
+#[test]
+fn test_bus_trace() {
+    let trace = create_trace();
+    assert_eq!(trace.events().len(), 8);
+
+    let sorted = trace.sorted();
+    let addrs: Vec<_> = sorted.iter().map(|row| row.event.addr).collect();
+    assert_eq!(addrs, vec![3, 3, 3, 3, 3, 7, 12, 12]);
+    assert!(sorted[1..5].iter().all(|row| row.same_addr));
+    assert!(!sorted[0].same_addr && !sorted[5].same_addr && !sorted[6].same_addr);
+
+    let rng = &mut rand::rngs::OsRng;
+    assert_eq!(
+        trace.check(INCREMENT_BITS, Fp::rand(rng), Fp::rand(rng)),
+        Ok(())
+    );
+
+    let mut trace = BusTrace::<Fp>::new();
+    assert_eq!(trace.read(5), Err(BusError::UninitializedRead(5)));
+}
+
+#[test]
+fn test_bus_invalid_witness() {
+    let trace = create_trace();
+    let sorted = trace.sorted();
+
+    // a read returning a stale value
+    let mut bad_sorted = sorted.clone();
+    bad_sorted[3].event.value = Fp::from(30u32);
+    assert_eq!(check(&trace, &bad_sorted), Err(BusError::Constraint(3, 4)));
+
+    // a first access to an address that is a read
+    let mut bad_sorted = sorted.clone();
+    bad_sorted[5].event.op = BusOp::Read;
+    assert_eq!(check(&trace, &bad_sorted), Err(BusError::Constraint(5, 3)));
+
+    // accesses that are not sorted
+    let mut bad_sorted = sorted.clone();
+    bad_sorted.swap(6, 7);
+    bad_sorted[6].same_addr = false;
+    bad_sorted[7].same_addr = true;
+    assert!(check(&trace, &bad_sorted).is_err());
+
+    // a claimed sorted trace that is not a permutation of the accesses
+    let mut bad_sorted = sorted;
+    bad_sorted[4].event.ts += 1;
+    assert_eq!(check(&trace, &bad_sorted), Err(BusError::Multiset));
+}
+
+fn create_index(rows: usize) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    // the gate constrains each row with the next one
+    let mut gates: Vec<_> = (0..rows - 1)
+        .map(|row| CircuitGate::create_custom(BUS_SORTED_GATE_ID, Wire::for_row(row), &[]))
+        .collect();
+    gates.push(CircuitGate::zero(Wire::for_row(rows - 1)));
+
+    let mut registry = CustomGateRegistry::new();
+    registry.register::<BusSorted>().unwrap();
+    let cs = ConstraintSystem::create(gates)
+        .custom_gates(registry)
+        .build()
+        .unwrap();
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+fn create_witness(trace: &BusTrace<Fp>) -> [Vec<Fp>; COLUMNS] {
+    let rows = trace.sorted_rows();
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows.len()]);
+    for (i, row) in rows.iter().enumerate() {
+        for (col, value) in row.iter().enumerate() {
+            witness[col][i] = *value;
+        }
+    }
+    witness
+}
+
+#[test]
+fn test_bus_sorted_gate() {
+    let trace = create_trace();
+    let witness = create_witness(&trace);
+    let index = create_index(witness[0].len());
+    index.verify(&witness, &[]).unwrap();
+
+    // a read returning a stale value
+    let mut bad_witness = witness.clone();
+    bad_witness[3][2] = Fp::from(30u32);
+    assert!(index.verify(&bad_witness, &[]).is_err());
+
+    // a wrong increment
+    let mut bad_witness = witness.clone();
+    bad_witness[5][3] += Fp::from(1u32);
+    assert!(index.verify(&bad_witness, &[]).is_err());
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        &proof,
+        &[],
+    )
+    .unwrap();
+}
@@ -0,0 +1,122 @@
+use crate::circuits::{
+    constraints::ConstraintSystem,
+    gate::{CircuitGate, CircuitGateError},
+    polynomials::chacha::{chacha20_block, chacha20_encrypt, create_chacha20_witness, ChaChaCells},
+};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+
+use super::framework::TestFramework;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type VestaBaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type VestaScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+// The key 00:01:02:...:1f of the test vectors of RFC 8439
+fn rfc_key() -> [u32; 8] {
+    std::array::from_fn(|i| u32::from_le_bytes(std::array::from_fn(|j| (4 * i + j) as u8)))
+}
+
+// The nonce 00:00:00:09:00:00:00:4a:00:00:00:00 of section 2.3.2 of RFC 8439
+const BLOCK_NONCE: [u32; 3] = [0x09000000, 0x4a000000, 0];
+
+// The output of the block function of section 2.3.2 of RFC 8439
+const BLOCK_OUTPUT: [u32; 16] = [
+    0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204, 0x4e6cd4c3,
+    0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de, 0xe883d0cb, 0x4e3c50a2,
+];
+
+fn create_test_gates_chacha20() -> (Vec<CircuitGate<Fp>>, ChaChaCells) {
+    let mut gates = vec![];
+    let (next_row, cells) = CircuitGate::<Fp>::extend_chacha20(&mut gates);
+    assert_eq!(next_row, gates.len());
+    (gates, cells)
+}
+
+#[test]
+// Test vector of section 2.3.2 of RFC 8439
+fn test_chacha20_block() {
+    assert_eq!(chacha20_block(&rfc_key(), 1, &BLOCK_NONCE), BLOCK_OUTPUT);
+}
+
+#[test]
+// Test vector of section 2.4.2 of RFC 8439
+fn test_chacha20_encrypt() {
+    let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+    let ciphertext = hex::decode(
+        "6e2e359a2568f98041ba0728dd0d6981e97e7aec1d4360c20a27afccfd9fae0b\
+         f91b65c5524733ab8f593dabcd62b3571639d624e65152ab8f530c359f0861d8\
+         07ca0dbf500d6a6156a38e088a22b65e52bc514d16ccf806818ce91ab7793736\
+         5af90bbf74a35be6b40b8eedf2785e42874d",
+    )
+    .unwrap();
+    let nonce = [0, 0x4a000000, 0];
+    assert_eq!(
+        chacha20_encrypt(&rfc_key(), 1, &nonce, plaintext),
+        ciphertext
+    );
+    assert_eq!(
+        chacha20_encrypt(&rfc_key(), 1, &nonce, &ciphertext),
+        plaintext
+    );
+}
+
+#[test]
+fn test_chacha20_witness() {
+    let (gates, cells) = create_test_gates_chacha20();
+    let (witness, output) = create_chacha20_witness::<Fp>(&rfc_key(), 1, &BLOCK_NONCE);
+    assert_eq!(output, BLOCK_OUTPUT);
+    assert_eq!(witness[0].len(), gates.len());
+
+    // The cells of the gadget hold the inputs and the outputs
+    for (&(row, col), word) in cells.key.iter().zip(rfc_key()) {
+        assert_eq!(witness[col][row], Fp::from(word));
+    }
+    assert_eq!(witness[cells.counter.1][cells.counter.0], Fp::from(1u32));
+    for (&(row, col), word) in cells.nonce.iter().zip(BLOCK_NONCE) {
+        assert_eq!(witness[col][row], Fp::from(word));
+    }
+    for (&(row, col), word) in cells.output.iter().zip(BLOCK_OUTPUT) {
+        assert_eq!(witness[col][row], Fp::from(word));
+    }
+
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+    for row in 0..witness[0].len() {
+        assert_eq!(
+            cs.gates[row].verify_witness::<Vesta>(row, &witness, &cs, &[]),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+// Test that an output which is not the one of the block function is detected
+fn test_chacha20_wrong_output() {
+    let (gates, cells) = create_test_gates_chacha20();
+    let (mut witness, _) = create_chacha20_witness::<Fp>(&rfc_key(), 1, &BLOCK_NONCE);
+    let (row, col) = cells.output[0];
+    witness[col][row] += Fp::from(1u32);
+
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+    assert!(matches!(
+        cs.gates[row].verify_witness::<Vesta>(row, &witness, &cs, &[]),
+        Err(CircuitGateError::CopyConstraint { .. })
+    ));
+}
+
+#[test]
+// End-to-end test
+fn test_chacha20_prove_and_verify() {
+    let (gates, _) = create_test_gates_chacha20();
+    let (witness, _) = create_chacha20_witness(&rfc_key(), 1, &BLOCK_NONCE);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
@@ -1,7 +1,10 @@
 use super::framework::TestFramework;
 use crate::circuits::{
     gate::CircuitGate,
-    polynomials::generic::GenericGateSpec,
+    polynomials::generic::{
+        testing::{create_circuit, fill_in_witness},
+        GenericGateSpec,
+    },
     wires::{Wire, COLUMNS},
 };
 use ark_ff::{UniformRand, Zero};
@@ -11,6 +14,7 @@ use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
+use poly_commitment::srs::SRS;
 use std::array;
 
 type SpongeParams = PlonkSpongeConstantsKimchi;
@@ -113,3 +117,26 @@ fn heavy_test_2_to_17_chunked_generic_gate_pub() {
 fn test_2_to_16_unchunked_generic_gate_pub() {
     test_generic_gate_with_srs_override(16, None)
 }*/
+
+#[test]
+fn test_chunked_generic_gate_with_srs_size_not_dividing_domain_size() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    // the last chunk of each polynomial is smaller than the others
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .public_inputs(public)
+        .override_srs_size(12)
+        .setup_with_custom_srs(|d1, srs_size| {
+            let mut srs = SRS::<Vesta>::create(srs_size);
+            srs.add_lagrange_basis(d1);
+            srs
+        })
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
@@ -0,0 +1,155 @@
+use crate::{
+    circuits::{
+        berkeley_columns::Column,
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    committed_column::{verify_with_committed_columns, zk_rows_for_uses, CommittedColumn},
+    curve::KimchiCurve,
+    error::{ProverError, VerifyError},
+    proof::ProverProof,
+    prover_index::ProverIndex,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+fn witness(public: &[Fp], num_rows: usize) -> [Vec<Fp>; COLUMNS] {
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); num_rows]);
+    fill_in_witness(0, &mut witness, public);
+    witness
+}
+
+fn create_index(
+    gates: Vec<CircuitGate<Fp>>,
+    zk_rows: u64,
+) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let cs = ConstraintSystem::create(gates)
+        .public(2)
+        .zk_rows(zk_rows)
+        .build()
+        .unwrap();
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_committed_column_reused_by_proofs() {
+    let gates = create_circuit(0, 2);
+    let public_a = vec![Fp::from(1u8), Fp::from(2u8)];
+    let public_b = vec![Fp::from(3u8), Fp::from(4u8)];
+    let witness_a = witness(&public_a, gates.len());
+    let witness_b = witness(&public_b, gates.len());
+
+    // The zero-knowledge rows hide the two proofs using the column
+    let index = &create_index(gates.clone(), zk_rows_for_uses(2, 1));
+    let verifier_index = index.verifier_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let rng = &mut o1_utils::tests::make_test_rng(None);
+
+    // The column 1 is the same in both witnesses, and only its commitment is
+    // given to the verifier
+    let column = CommittedColumn::create(index, 1, &witness_a[1], 2, rng).unwrap();
+    let commitments = vec![(1, column.commitment().clone())];
+
+    for (witness, public) in [(witness_a.clone(), public_a), (witness_b, public_b.clone())] {
+        let proof = ProverProof::create_with_committed_columns::<BaseSponge, ScalarSponge>(
+            &group_map,
+            witness,
+            &[],
+            index,
+            vec![],
+            std::slice::from_ref(&column),
+        )
+        .unwrap();
+        assert_eq!(&proof.commitments.w_comm[1], column.commitment());
+        verify_with_committed_columns::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &verifier_index,
+            &proof,
+            &public,
+            &commitments,
+        )
+        .unwrap();
+    }
+    assert_eq!(column.remaining_uses(), 0);
+
+    // A third proof would reveal too many evaluations of the column
+    let res = ProverProof::create_with_committed_columns::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness_a.clone(),
+        &[],
+        index,
+        vec![],
+        std::slice::from_ref(&column),
+    );
+    assert!(matches!(
+        res,
+        Err(ProverError::CommittedColumnExhausted {
+            column: 1,
+            max_uses: 2
+        })
+    ));
+
+    // The zero-knowledge rows of the circuit cannot hide three proofs
+    let res = CommittedColumn::create(index, 1, &witness_a[1], 3, rng);
+    assert!(matches!(
+        res,
+        Err(ProverError::CommittedColumnNotZeroKnowledge {
+            column: 1,
+            expected: 7,
+            got: 5
+        })
+    ));
+
+    // Another commitment of the same column is rejected
+    let other =
+        CommittedColumn::create(index, 1, &witness(&public_b, gates.len())[1], 1, rng).unwrap();
+    let proof = ProverProof::create_with_committed_columns::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness(&public_b, gates.len()),
+        &[],
+        index,
+        vec![],
+        &[other],
+    )
+    .unwrap();
+    let res = verify_with_committed_columns::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &public_b,
+        &commitments,
+    );
+    assert!(matches!(
+        res,
+        Err(VerifyError::CommittedColumnMismatch(Column::Witness(1)))
+    ));
+
+    // The witness must agree with the committed values
+    let wrong = CommittedColumn::create(index, 1, &[Fp::from(5u8)], 1, rng).unwrap();
+    let res = ProverProof::create_with_committed_columns::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness(&public_b, gates.len()),
+        &[],
+        index,
+        vec![],
+        &[wrong],
+    );
+    assert!(matches!(res, Err(ProverError::CommittedColumnMismatch(1))));
+}
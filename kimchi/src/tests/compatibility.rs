@@ -0,0 +1,117 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        lookup::{constraints::LookupArgument, tables::LookupTable},
+        polynomials::generic::testing::create_circuit,
+        wires::Wire,
+    },
+    compatibility::{CompatibilityDescriptor, LookupDescriptor},
+    curve::KimchiCurve,
+    opening_order::OPENING_ORDER_VERSION,
+    prover_index::ProverIndex,
+};
+use ark_ff::Zero;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::{evaluation_proof::OpeningProof, srs::SRS};
+use std::sync::Arc;
+
+fn create_index(
+    cs: ConstraintSystem<Fp>,
+    srs_size: usize,
+) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let mut srs = SRS::<Vesta>::create(srs_size);
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+fn generic_cs() -> ConstraintSystem<Fp> {
+    ConstraintSystem::create(create_circuit(0, 2))
+        .public(2)
+        .build()
+        .unwrap()
+}
+
+fn lookup_cs(argument: LookupArgument) -> ConstraintSystem<Fp> {
+    let gates = (0..4)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+    let table = LookupTable {
+        id: 0,
+        data: vec![vec![Fp::zero(); 2], vec![Fp::zero(); 2]],
+    };
+    ConstraintSystem::create(gates)
+        .lookup(vec![table])
+        .lookup_argument(argument)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_compatibility_descriptor() {
+    let cs = generic_cs();
+    let domain_size = cs.domain.d1.size;
+    let index = create_index(cs, domain_size as usize);
+
+    let descriptor = index.compatibility();
+    assert_eq!(descriptor.curve, "vesta");
+    assert_eq!(descriptor.domain_size, domain_size);
+    assert_eq!(descriptor.max_poly_size, domain_size as usize);
+    assert_eq!(descriptor.zk_rows, 3);
+    assert_eq!(descriptor.public, 2);
+    assert_eq!(descriptor.lookup, None);
+    assert_eq!(descriptor.opening_order_version, OPENING_ORDER_VERSION);
+
+    // the prover and the verifier agree
+    let verifier_descriptor = index.verifier_index().compatibility();
+    assert_eq!(verifier_descriptor, descriptor);
+    assert_eq!(verifier_descriptor.check(&descriptor), Ok(()));
+
+    // the descriptor can be shared between services
+    let json = serde_json::to_string(&descriptor).unwrap();
+    let deserialized: CompatibilityDescriptor = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, descriptor);
+}
+
+#[test]
+fn test_compatibility_mismatches() {
+    let cs = generic_cs();
+    let domain_size = cs.domain.d1.size as usize;
+    let expected = create_index(cs, domain_size).compatibility();
+
+    // a larger SRS
+    let found = create_index(generic_cs(), 2 * domain_size).compatibility();
+    let mismatches = found.check(&expected).unwrap_err();
+    let parameters: Vec<_> = mismatches.iter().map(|m| m.parameter).collect();
+    assert_eq!(parameters, vec!["SRS hash", "SRS size"]);
+    assert_eq!(
+        mismatches[1].to_string(),
+        format!(
+            "SRS size: expected {}, found {}",
+            domain_size,
+            2 * domain_size
+        )
+    );
+
+    // a different lookup argument
+    let plookup = lookup_cs(LookupArgument::Plookup);
+    let srs_size = plookup.domain.d1.size as usize;
+    let plookup = create_index(plookup, srs_size).compatibility();
+    let logup = create_index(lookup_cs(LookupArgument::LogUp), srs_size).compatibility();
+    assert_eq!(
+        plookup
+            .lookup
+            .map(|lookup: LookupDescriptor| lookup.argument),
+        Some(LookupArgument::Plookup)
+    );
+    let parameters: Vec<_> = logup
+        .mismatches(&plookup)
+        .iter()
+        .map(|m| m.parameter)
+        .collect();
+    assert_eq!(
+        parameters,
+        vec!["lookup configuration", "linearization hash"]
+    );
+}
@@ -0,0 +1,373 @@
+use crate::{
+    circuits::{
+        argument::ArgumentEnv,
+        constraints::ConstraintSystem,
+        custom_gate::{
+            CustomGate, CustomGateError, CustomGateRegistry, WitnessCell, MAX_CUSTOM_GATE_DEGREE,
+        },
+        expr::{constraints::ExprOps, Cache},
+        gate::{CircuitGate, CircuitGateError, CurrOrNext, GateType},
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    error::SetupError,
+    linearization::expr_linearization,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+    verifier_index::VerifierIndex,
+};
+use ark_ff::{PrimeField, Zero};
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+/// Constrains `w1 = w0^2`
+struct Square;
+
+impl<F: PrimeField> CustomGate<F> for Square {
+    const ID: u32 = 1;
+    const NAME: &'static str = "Square";
+    const CONSTRAINTS: u32 = 1;
+
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        vec![env.witness_curr(1) - env.witness_curr(0).square()]
+    }
+
+    fn witness_layout() -> Vec<WitnessCell> {
+        vec![
+            WitnessCell {
+                row: CurrOrNext::Curr,
+                col: 0,
+                name: "x",
+            },
+            WitnessCell {
+                row: CurrOrNext::Curr,
+                col: 1,
+                name: "x^2",
+            },
+        ]
+    }
+}
+
+/// Constrains `w1 = c * w0` and `w2 = w1 + w0`, where `c` is the first coefficient of the gate
+struct ScaleAdd;
+
+impl<F: PrimeField> CustomGate<F> for ScaleAdd {
+    const ID: u32 = 7;
+    const NAME: &'static str = "ScaleAdd";
+    const CONSTRAINTS: u32 = 2;
+
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        vec![
+            env.witness_curr(1) - env.coeff(1) * env.witness_curr(0),
+            env.witness_curr(2) - env.witness_curr(1) - env.witness_curr(0),
+        ]
+    }
+}
+
+/// Uses a column that does not exist
+struct BadLayout;
+
+impl<F: PrimeField> CustomGate<F> for BadLayout {
+    const ID: u32 = 2;
+    const NAME: &'static str = "BadLayout";
+    const CONSTRAINTS: u32 = 1;
+
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        vec![env.witness_curr(0)]
+    }
+
+    fn witness_layout() -> Vec<WitnessCell> {
+        vec![WitnessCell {
+            row: CurrOrNext::Next,
+            col: COLUMNS,
+            name: "out of bounds",
+        }]
+    }
+}
+
+/// Has more constraints than a gate can have
+struct TooBig;
+
+impl<F: PrimeField> CustomGate<F> for TooBig {
+    const ID: u32 = 3;
+    const NAME: &'static str = "TooBig";
+    const CONSTRAINTS: u32 = 100;
+
+    fn constraint_checks<T: ExprOps<F>>(_env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        vec![]
+    }
+}
+
+/// Constrains `w1 = w0^7`, which reaches the maximum degree once selected
+struct Seventh;
+
+impl<F: PrimeField> CustomGate<F> for Seventh {
+    const ID: u32 = 4;
+    const NAME: &'static str = "Seventh";
+    const CONSTRAINTS: u32 = 1;
+
+    fn constraint_checks<T: ExprOps<F>>(env: &ArgumentEnv<F, T>, _cache: &mut Cache) -> Vec<T> {
+        let x = env.witness_curr(0);
+        vec![env.witness_curr(1) - x.square() * x.square() * x.square() * x]
+    }
+}
+
+fn registry() -> CustomGateRegistry<Fp> {
+    let mut registry = CustomGateRegistry::new();
+    registry.register::<Square>().unwrap();
+    registry.register::<ScaleAdd>().unwrap();
+    registry
+}
+
+fn create_circuit(rows: usize) -> Vec<CircuitGate<Fp>> {
+    (0..rows)
+        .map(|row| {
+            if row % 2 == 0 {
+                CircuitGate::create_custom(<Square as CustomGate<Fp>>::ID, Wire::for_row(row), &[])
+            } else {
+                CircuitGate::create_custom(
+                    <ScaleAdd as CustomGate<Fp>>::ID,
+                    Wire::for_row(row),
+                    &[Fp::from(row as u64)],
+                )
+            }
+        })
+        .collect()
+}
+
+fn create_witness(rows: usize) -> [Vec<Fp>; COLUMNS] {
+    let cells: Vec<[Fp; 3]> = (0..rows)
+        .map(|row| {
+            let x = Fp::from(row as u64 + 3);
+            if row % 2 == 0 {
+                [x, x * x, Fp::zero()]
+            } else {
+                let y = Fp::from(row as u64) * x;
+                [x, y, y + x]
+            }
+        })
+        .collect();
+    array::from_fn(|col| {
+        cells
+            .iter()
+            .map(|row| row.get(col).copied().unwrap_or_else(Fp::zero))
+            .collect()
+    })
+}
+
+fn create_index(rows: usize) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let cs = ConstraintSystem::create(create_circuit(rows))
+        .custom_gates(registry())
+        .build()
+        .unwrap();
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_custom_gate_registry() {
+    let registry = registry();
+    assert_eq!(registry.len(), 2);
+    assert_eq!(
+        registry.iter().map(|gate| gate.name()).collect::<Vec<_>>(),
+        vec!["Square", "ScaleAdd"]
+    );
+    assert_eq!(registry.get(1).unwrap().witness_layout().len(), 2);
+    assert!(registry.get(2).is_none());
+
+    let mut registry = registry;
+    assert_eq!(
+        registry.register::<Square>(),
+        Err(CustomGateError::DuplicateId(1))
+    );
+    assert_eq!(
+        registry.register::<BadLayout>(),
+        Err(CustomGateError::InvalidLayout {
+            name: "BadLayout".to_string(),
+            col: COLUMNS
+        })
+    );
+    assert!(matches!(
+        registry.register::<TooBig>(),
+        Err(CustomGateError::TooManyConstraints { .. })
+    ));
+    assert_eq!(registry.len(), 2);
+
+    // the identifier selector of a third gate raises its degree beyond d8
+    assert_eq!(
+        registry.register::<Seventh>(),
+        Err(CustomGateError::DegreeTooHigh {
+            name: "Seventh".to_string(),
+            degree: 10,
+            max: MAX_CUSTOM_GATE_DEGREE
+        })
+    );
+    assert_eq!(registry.len(), 2);
+    let mut alone = CustomGateRegistry::<Fp>::new();
+    alone.register::<Seventh>().unwrap();
+}
+
+#[test]
+fn test_custom_gate_setup_errors() {
+    // custom rows without a registry
+    let res = ConstraintSystem::create(create_circuit(4)).build();
+    assert!(matches!(
+        res,
+        Err(SetupError::CustomGate(CustomGateError::MissingRegistry))
+    ));
+
+    // custom rows with an unregistered identifier
+    let mut gates = create_circuit(4);
+    gates.push(CircuitGate::create_custom(5, Wire::for_row(4), &[]));
+    let res = ConstraintSystem::create(gates)
+        .custom_gates(registry())
+        .build();
+    assert!(matches!(
+        res,
+        Err(SetupError::CustomGate(CustomGateError::UnknownGate(4)))
+    ));
+
+    // a registry is not needed if no custom gate is used
+    let gates = vec![CircuitGate::<Fp>::zero(Wire::for_row(0)); 4];
+    let cs = ConstraintSystem::create(gates)
+        .custom_gates(registry())
+        .build()
+        .unwrap();
+    assert!(cs.custom_gates.is_none());
+}
+
+#[test]
+fn test_custom_gate_witness() {
+    let rows = 8;
+    let index = create_index(rows);
+    let witness = create_witness(rows);
+    index.verify(&witness, &[]).unwrap();
+
+    // break the square gate
+    let mut bad_witness = witness.clone();
+    bad_witness[1][2] += Fp::from(1u32);
+    assert_eq!(
+        index.cs.gates[2].verify_witness::<Vesta>(2, &bad_witness, &index.cs, &[]),
+        Err(CircuitGateError::Constraint(GateType::Custom, 1))
+    );
+
+    // break the second constraint of the scale-add gate
+    let mut bad_witness = witness;
+    bad_witness[2][3] += Fp::from(1u32);
+    assert_eq!(
+        index.cs.gates[3].verify_witness::<Vesta>(3, &bad_witness, &index.cs, &[]),
+        Err(CircuitGateError::Constraint(GateType::Custom, 2))
+    );
+}
+
+#[test]
+fn test_custom_gate_prove_and_verify() {
+    let rows = 8;
+    let index = create_index(rows);
+    let witness = create_witness(rows);
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    assert!(proof.evals.custom_selector.is_some());
+
+    let verifier_index = index.verifier_index();
+    assert!(verifier_index.custom_comm.is_some());
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_custom_gate_verifier_index_serialization() {
+    let rows = 8;
+    let index = create_index(rows);
+    let witness = create_witness(rows);
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness.clone(), &[], &index)
+            .unwrap();
+
+    let verifier_index = index.verifier_index();
+    let bytes = rmp_serde::to_vec(&verifier_index).unwrap();
+    let mut deserialized: VerifierIndex<Vesta, OpeningProof<Vesta>> =
+        rmp_serde::from_slice(&bytes).unwrap();
+
+    // the deserialized index carries the constraints of the custom gates
+    let custom_gates = deserialized.custom_gates.clone().unwrap();
+    assert_eq!(custom_gates.len(), 2);
+    assert_eq!(custom_gates.digest(), registry().digest());
+    assert_eq!(
+        deserialized.digest::<BaseSponge>(),
+        verifier_index.digest::<BaseSponge>()
+    );
+
+    // the deserialized gates check the witness like the registered ones
+    let cs = ConstraintSystem::create(create_circuit(rows))
+        .custom_gates(custom_gates.clone())
+        .build()
+        .unwrap();
+    for (row, gate) in cs.gates.iter().enumerate().take(rows) {
+        gate.verify_witness::<Vesta>(row, &witness, &cs, &[])
+            .unwrap();
+    }
+    let mut bad_witness = witness;
+    bad_witness[2][3] += Fp::from(1u32);
+    assert_eq!(
+        cs.gates[3].verify_witness::<Vesta>(3, &bad_witness, &cs, &[]),
+        Err(CircuitGateError::Constraint(GateType::Custom, 2))
+    );
+
+    // the linearization is rebuilt from the deserialized constraints
+    let (linearization, powers_of_alpha) = expr_linearization(
+        Some(&index.cs.feature_flags),
+        true,
+        &index.cs.base_gates,
+        Some(&custom_gates),
+        index.cs.lookup_argument(),
+        index.cs.max_lookups_per_row(),
+    );
+    assert_eq!(linearization.digest(), index.linearization.digest());
+    let mut srs = SRS::<Vesta>::create(deserialized.max_poly_size);
+    srs.add_lagrange_basis(deserialized.domain);
+    deserialized.srs = Arc::new(srs);
+    deserialized.linearization = linearization;
+    deserialized.powers_of_alpha = powers_of_alpha;
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &deserialized,
+        &proof,
+        &[],
+    )
+    .unwrap();
+
+    // the digest covers the constraints of the custom gates
+    let mut other = CustomGateRegistry::new();
+    other.register::<Square>().unwrap();
+    deserialized.custom_gates = Some(other);
+    assert_ne!(
+        deserialized.digest::<BaseSponge>(),
+        verifier_index.digest::<BaseSponge>()
+    );
+}
@@ -0,0 +1,155 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomial::COLUMNS,
+        polynomials::decomposition::{
+            decompose, extend_decomposition_witness, limb_table, pack, Endianness,
+        },
+    },
+    error::WitnessError,
+};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use std::array;
+
+use super::framework::TestFramework;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type VestaBaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type VestaScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const TABLE_ID: i32 = 7;
+
+#[test]
+fn test_decompose_and_pack() {
+    let x = Fp::from(0x1234_5678u64);
+    let little = decompose(x, 8, 4, Endianness::Little);
+    let big = decompose(x, 8, 4, Endianness::Big);
+    assert_eq!(little, [0x78u64, 0x56, 0x34, 0x12].map(Fp::from).to_vec());
+    assert_eq!(big, [0x12u64, 0x34, 0x56, 0x78].map(Fp::from).to_vec());
+    assert_eq!(pack(&little, 8, Endianness::Little), x);
+    assert_eq!(pack(&big, 8, Endianness::Big), x);
+
+    let bits = decompose(Fp::from(6u32), 1, 3, Endianness::Big);
+    assert_eq!(bits, [1u32, 1, 0].map(Fp::from).to_vec());
+    assert_eq!(pack(&bits, 1, Endianness::Big), Fp::from(6u32));
+}
+
+#[test]
+#[should_panic(expected = "the value does not fit in 8 bits")]
+fn test_decompose_too_large() {
+    decompose(Fp::from(256u32), 4, 2, Endianness::Little);
+}
+
+#[test]
+fn test_decomposition() {
+    let x = Fp::from(0xdead_beefu64);
+    for (limb_bits, num_limbs) in [(1, 32), (1, 40), (4, 8), (8, 4), (12, 3)] {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            let mut gates = vec![];
+            let (next_row, cells) = CircuitGate::<Fp>::extend_decomposition(
+                &mut gates, TABLE_ID, limb_bits, num_limbs, endianness,
+            );
+            assert_eq!(next_row, gates.len());
+            assert_eq!(cells.limbs.len(), num_limbs);
+
+            let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+            let limbs = extend_decomposition_witness(
+                &mut witness,
+                TABLE_ID,
+                limb_bits,
+                num_limbs,
+                endianness,
+                x,
+            );
+            assert_eq!(witness[0].len(), next_row);
+            assert_eq!(limbs, decompose(x, limb_bits, num_limbs, endianness));
+            assert_eq!(witness[cells.value.1][cells.value.0], x);
+            for (&(row, col), limb) in cells.limbs.iter().zip(&limbs) {
+                assert_eq!(witness[col][row], *limb);
+            }
+
+            let cs = ConstraintSystem::create(gates)
+                .lookup(vec![limb_table(TABLE_ID, limb_bits)])
+                .build()
+                .unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+        }
+    }
+}
+
+#[test]
+// Test that a bit which is not boolean is detected by the lookups
+fn test_decomposition_not_boolean() {
+    let num_limbs = 8;
+    let mut gates = vec![];
+    let (next_row, cells) = CircuitGate::<Fp>::extend_decomposition(
+        &mut gates,
+        TABLE_ID,
+        1,
+        num_limbs,
+        Endianness::Little,
+    );
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_decomposition_witness(
+        &mut witness,
+        TABLE_ID,
+        1,
+        num_limbs,
+        Endianness::Little,
+        Fp::from(2u32),
+    );
+
+    // 2 = 2 * 2^0 + 0 * 2^1 still packs to the value, in every cell of the
+    // first two bits
+    let lookup_row = next_row - (num_limbs + 2) / 3;
+    for (i, bit) in [(0, 2u32), (1, 0)] {
+        let (row, col) = cells.limbs[i];
+        witness[col][row] = Fp::from(bit);
+        witness[2 * i + 1][lookup_row] = Fp::from(bit);
+        witness[2 * i + 2][lookup_row] = Fp::from(bit);
+    }
+    let cs = ConstraintSystem::create(gates)
+        .lookup(vec![limb_table(TABLE_ID, 1)])
+        .build()
+        .unwrap();
+    assert!(matches!(
+        cs.check_witness::<Vesta>(&witness, &[]),
+        Err(WitnessError::ValueNotInTable { .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "is not unique in the field")]
+fn test_decomposition_not_unique() {
+    let mut gates = vec![];
+    CircuitGate::<Fp>::extend_decomposition(&mut gates, TABLE_ID, 1, 255, Endianness::Little);
+}
+
+#[test]
+// End-to-end test
+fn test_decomposition_prove_and_verify() {
+    let mut gates = vec![];
+    CircuitGate::<Fp>::extend_decomposition(&mut gates, TABLE_ID, 8, 5, Endianness::Big);
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_decomposition_witness(
+        &mut witness,
+        TABLE_ID,
+        8,
+        5,
+        Endianness::Big,
+        Fp::from(0x01_0203_0405u64),
+    );
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .lookup_tables(vec![limb_table(TABLE_ID, 8)])
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
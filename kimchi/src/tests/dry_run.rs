@@ -0,0 +1,92 @@
+use super::framework::TestFramework;
+use crate::{
+    circuits::{
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    proof::ProverProof,
+};
+use ark_ff::Zero;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::commitment::CommitmentCurve;
+use std::array;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+#[test]
+fn test_dry_run_matches_proof() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness.clone())
+        .public_inputs(public)
+        .setup();
+    let index = runner.prover_index();
+    let shape = index.dry_run();
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], index).unwrap();
+
+    // the commitments
+    let commitments = &proof.commitments;
+    let points = commitments
+        .w_comm
+        .iter()
+        .chain([&commitments.z_comm, &commitments.t_comm])
+        .map(|comm| comm.elems.len())
+        .sum::<usize>()
+        + 2 * proof.proof.lr.len()
+        + 2;
+    assert_eq!(shape.elements().points, points);
+    assert_eq!(shape.num_chunks, commitments.z_comm.elems.len());
+
+    // the evaluations, without optional gates nor lookups
+    let evals = proof.evals.map_ref(&|e| e.zeta.len() + e.zeta_omega.len());
+    let scalars = evals.public.unwrap()
+        + evals.w.iter().sum::<usize>()
+        + evals.z
+        + evals.s.iter().sum::<usize>()
+        + evals.coefficients.iter().sum::<usize>()
+        + evals.generic_selector
+        + evals.poseidon_selector
+        + evals.complete_add_selector
+        + evals.mul_selector
+        + evals.emul_selector
+        + evals.endomul_scalar_selector
+        // ft_eval1, z1 and z2
+        + 3;
+    assert!(evals.lookup_aggregation.is_none() && evals.range_check0_selector.is_none());
+    assert_eq!(shape.elements().scalars, scalars);
+
+    // the estimate ignores the framing of the serialization only
+    let serialized = rmp_serde::to_vec(&proof).unwrap();
+    assert!(shape.size_in_bytes() <= serialized.len());
+    assert!(serialized.len() < shape.size_in_bytes() * 5 / 4);
+
+    // the rounds are the ones of the prover
+    let names: Vec<_> = shape.rounds.iter().map(|round| round.name).collect();
+    assert_eq!(
+        names,
+        [
+            "previous challenges",
+            "witness",
+            "beta and gamma",
+            "aggregation",
+            "quotient",
+            "evaluations",
+            "opening"
+        ]
+    );
+}
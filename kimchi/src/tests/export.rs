@@ -0,0 +1,146 @@
+use crate::circuits::{
+    export::{CircuitExportError, ExportedCircuit, CIRCUIT_FORMAT_VERSION},
+    gate::{Circuit, CircuitGate},
+    lookup::{constraints::LookupArgument, runtime_tables::RuntimeTableCfg, tables::LookupTable},
+    polynomials::generic::testing::{create_circuit, fill_in_witness},
+    wires::{Wire, COLUMNS},
+};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use o1_utils::hasher::CryptoDigest;
+use std::array;
+
+use super::framework::TestFramework;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const PUBLIC: usize = 5;
+
+fn exported_circuit() -> ExportedCircuit<Fp> {
+    let lookup_table = LookupTable {
+        id: 7,
+        data: vec![
+            (0u64..8).map(Fp::from).collect(),
+            (0u64..8).map(|x| Fp::from(x * x)).collect(),
+        ],
+    };
+    let runtime_table = RuntimeTableCfg {
+        id: 8,
+        first_column: (0u64..4).map(Fp::from).collect(),
+        unique_keys: true,
+    };
+    ExportedCircuit::new(PUBLIC, create_circuit(0, PUBLIC))
+        .lookup(&[lookup_table])
+        .runtime(Some(&[runtime_table]))
+        .lookup_argument(LookupArgument::LogUp)
+}
+
+fn assert_same_circuit(left: &ExportedCircuit<Fp>, right: &ExportedCircuit<Fp>) {
+    assert_eq!(left.version, right.version);
+    assert_eq!(left.public_input_size, right.public_input_size);
+    assert_eq!(left.gates.len(), right.gates.len());
+    for (left, right) in left.gates.iter().zip(&right.gates) {
+        assert_eq!(left.typ, right.typ);
+        assert_eq!(left.wires, right.wires);
+        assert_eq!(left.coeffs, right.coeffs);
+    }
+    assert_eq!(left.lookup_tables, right.lookup_tables);
+    assert_eq!(left.runtime_tables, right.runtime_tables);
+    assert_eq!(left.lookup_argument, right.lookup_argument);
+}
+
+#[test]
+fn test_export_json_and_binary() {
+    let circuit = exported_circuit();
+    let hash = circuit.hash();
+
+    let from_json = ExportedCircuit::<Fp>::from_json(&circuit.to_json().unwrap()).unwrap();
+    assert_same_circuit(&circuit, &from_json);
+    let from_bytes = ExportedCircuit::<Fp>::from_bytes(&circuit.to_bytes().unwrap()).unwrap();
+    assert_same_circuit(&circuit, &from_bytes);
+
+    // the hash does not depend on the encoding
+    assert_eq!(from_json.hash(), hash);
+    assert_eq!(from_bytes.hash(), hash);
+    assert_eq!(from_bytes.verify_hash(&hash), Ok(()));
+}
+
+#[test]
+fn test_export_hash_covers_the_circuit() {
+    let circuit = exported_circuit();
+    let hash = circuit.hash();
+
+    let mut other = circuit.clone();
+    other.gates[PUBLIC].coeffs[0] += Fp::from(1u64);
+    assert_ne!(other.hash(), hash);
+
+    let mut other = circuit.clone();
+    other.gates[PUBLIC].wires[0] = Wire::new(PUBLIC + 1, 0);
+    assert_ne!(other.hash(), hash);
+
+    let mut other = circuit.clone();
+    other.public_input_size -= 1;
+    assert_ne!(other.hash(), hash);
+
+    let mut other = circuit.clone();
+    other.lookup_tables[0].data[1][3] = Fp::from(0u64);
+    assert_ne!(other.hash(), hash);
+
+    let mut other = circuit.clone();
+    other.runtime_tables.as_mut().unwrap()[0].unique_keys = false;
+    assert_ne!(other.hash(), hash);
+
+    let other = circuit.clone().lookup_argument(LookupArgument::Plookup);
+    assert!(matches!(
+        other.verify_hash(&hash),
+        Err(CircuitExportError::HashMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_export_unsupported_version() {
+    let mut circuit = exported_circuit();
+    circuit.version = CIRCUIT_FORMAT_VERSION + 1;
+    assert_eq!(
+        ExportedCircuit::<Fp>::from_bytes(&circuit.to_bytes().unwrap()).unwrap_err(),
+        CircuitExportError::UnsupportedVersion(CIRCUIT_FORMAT_VERSION + 1)
+    );
+    assert!(matches!(
+        ExportedCircuit::<Fp>::from_json("{\"version\": 1}"),
+        Err(CircuitExportError::Decoding(_))
+    ));
+}
+
+#[test]
+fn test_export_load_and_prove() {
+    let gates: Vec<CircuitGate<Fp>> = create_circuit(0, PUBLIC);
+    let json = ExportedCircuit::new(PUBLIC, gates).to_json().unwrap();
+
+    // the circuit is loaded by the prover
+    let circuit = ExportedCircuit::<Fp>::from_json(&json).unwrap();
+    let cs = circuit.constraint_system().build().unwrap();
+    assert_eq!(cs.public, PUBLIC);
+    // the gates of the constraint system are padded to the size of the domain
+    let num_gates = circuit.gates.len();
+    assert_eq!(
+        Circuit::new(PUBLIC, &cs.gates[..num_gates]).digest(),
+        circuit.circuit().digest()
+    );
+
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::from(0u64); num_gates]);
+    let public: Vec<Fp> = (1u64..=PUBLIC as u64).map(Fp::from).collect();
+    fill_in_witness(0, &mut witness, &public);
+
+    TestFramework::<Vesta>::default()
+        .gates(circuit.gates)
+        .witness(witness)
+        .public_inputs(public)
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
@@ -0,0 +1,155 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::fixed_base_mul::{
+            gadget::{FixedBaseMulCells, FixedBaseMulParams},
+            witness,
+        },
+    },
+    tests::framework::TestFramework,
+};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{One, PrimeField, Zero};
+use mina_curves::pasta::{Fp, Fq, Pallas, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use num_bigint::BigUint;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+fn pallas_params(num_bits: usize, window: usize) -> FixedBaseMulParams<Pallas> {
+    FixedBaseMulParams::new(Pallas::prime_subgroup_generator(), num_bits, window, 2)
+}
+
+fn pallas_mul(scalar: &BigUint) -> Pallas {
+    Pallas::prime_subgroup_generator()
+        .mul(Fq::from(scalar.clone()).into_repr())
+        .into_affine()
+}
+
+fn create_circuit(
+    params: &FixedBaseMulParams<Pallas>,
+) -> (Vec<CircuitGate<Fp>>, FixedBaseMulCells) {
+    let mut gates = vec![];
+    let mut next_row = 0;
+    let cells = CircuitGate::extend_fixed_base_mul(&mut gates, &mut next_row, params);
+    assert_eq!(gates.len(), params.num_rows());
+    (gates, cells)
+}
+
+#[test]
+fn test_fixed_base_mul() {
+    // the last window only has 1 bit
+    let params = pallas_params(16, 3);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    for scalar in [1u32, 0xb7, 0x8000, 0xffff] {
+        let scalar = BigUint::from(scalar);
+        let (witness, result) = witness::create(&scalar, &params);
+        assert_eq!(result, pallas_mul(&scalar));
+
+        // the cells of the gadget hold the scalar and the result
+        let cell = |(row, col): (usize, usize)| witness[col][row];
+        assert_eq!(cell(cells.scalar), Fp::from(scalar));
+        assert_eq!(cells.result.map(cell), [result.x, result.y]);
+        assert_eq!(cell(cells.infinity), Fp::zero());
+
+        assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+    }
+}
+
+#[test]
+fn test_fixed_base_mul_single_window() {
+    let params = pallas_params(4, 4);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    let scalar = BigUint::from(11u32);
+    let (witness, result) = witness::create(&scalar, &params);
+    assert_eq!(result, pallas_mul(&scalar));
+    assert_eq!(witness[cells.scalar.1][cells.scalar.0], Fp::from(11u32));
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+}
+
+#[test]
+fn test_fixed_base_mul_zero() {
+    // the result is the point at infinity
+    let params = pallas_params(16, 4);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    let (witness, result) = witness::create(&BigUint::zero(), &params);
+    assert!(result.is_zero());
+    assert_eq!(witness[cells.infinity.1][cells.infinity.0], Fp::one());
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+}
+
+#[test]
+fn test_fixed_base_mul_invalid_witness() {
+    let params = pallas_params(16, 4);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+    let (witness, _) = witness::create(&BigUint::from(0x5a3cu32), &params);
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // a wrong coordinate of the result
+    let mut bad_witness = witness.clone();
+    let (row, col) = cells.result[0];
+    bad_witness[col][row] += Fp::one();
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+
+    // a wrong scalar
+    let mut bad_witness = witness;
+    let (row, col) = cells.scalar;
+    bad_witness[col][row] += Fp::from(16u32);
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+}
+
+#[test]
+fn test_fixed_base_mul_rows() {
+    // VarBaseMul uses 2 rows per 5 bits
+    let params = pallas_params(248, 8);
+    let (gates, _) = create_circuit(&params);
+    assert_eq!(gates.len(), 70);
+    assert!(gates.len() < 2 * ((248 + 4) / 5));
+}
+
+#[test]
+#[should_panic]
+fn test_fixed_base_mul_too_many_bits() {
+    // the sum of the keys could wrap around the field
+    pallas_params(250, 8);
+}
+
+#[test]
+fn test_fixed_base_mul_prove_and_verify() {
+    let params = pallas_params(32, 5);
+    let (gates, _) = create_circuit(&params);
+    let (witness, _) = witness::create(&BigUint::from(0xdeadbeefu32), &params);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .lookup_tables(params.lookup_tables())
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
@@ -0,0 +1,230 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        lookup::tables::fixed::{FixedTable, FixedTableError, FixedTableRegistry},
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    error::{ProverError, SetupError},
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Pallas, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+/// The x-coordinates of the first multiples of the Pallas generator, indexed by the scalar
+struct GeneratorMultiples {
+    n: u64,
+}
+
+impl FixedTable<Fp> for GeneratorMultiples {
+    const ID: i32 = 3;
+    const NAME: &'static str = "GeneratorMultiples";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        let g = Pallas::prime_subgroup_generator().into_projective();
+        let mut acc = g;
+        let mut scalars = Vec::with_capacity(self.n as usize);
+        let mut xs = Vec::with_capacity(self.n as usize);
+        for i in 1..=self.n {
+            scalars.push(Fp::from(i));
+            xs.push(acc.into_affine().x);
+            acc += g;
+        }
+        vec![scalars, xs]
+    }
+}
+
+/// The 4-bit S-box of PRESENT
+struct SBox;
+
+const SBOX: [u64; 16] = [
+    0xC, 0x5, 0x6, 0xB, 0x9, 0x0, 0xA, 0xD, 0x3, 0xE, 0xF, 0x8, 0x4, 0x7, 0x1, 0x2,
+];
+
+impl FixedTable<Fp> for SBox {
+    const ID: i32 = 4;
+    const NAME: &'static str = "SBox";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        vec![
+            (0..16u64).map(Fp::from).collect(),
+            SBOX.iter().copied().map(Fp::from).collect(),
+        ]
+    }
+}
+
+/// Uses the identifier of the XOR table
+struct Reserved;
+
+impl FixedTable<Fp> for Reserved {
+    const ID: i32 = 0;
+    const NAME: &'static str = "Reserved";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::zero()]]
+    }
+}
+
+/// Has columns of different lengths
+struct Ragged;
+
+impl FixedTable<Fp> for Ragged {
+    const ID: i32 = 5;
+    const NAME: &'static str = "Ragged";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::zero(); 2], vec![Fp::zero(); 3]]
+    }
+}
+
+fn registry(n: u64) -> FixedTableRegistry<Fp> {
+    let mut registry = FixedTableRegistry::new();
+    registry.register(&GeneratorMultiples { n }).unwrap();
+    registry.register(&SBox).unwrap();
+    registry
+}
+
+/// Creates `rows` lookup gates, alternating between the two tables, and their witness
+fn create_circuit(rows: usize, n: u64) -> (Vec<CircuitGate<Fp>>, [Vec<Fp>; COLUMNS]) {
+    let gates = (0..rows)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+
+    let multiples = GeneratorMultiples { n }.columns();
+    let sbox = SBox.columns();
+    let cells: Vec<Vec<Fp>> = (0..rows)
+        .map(|row| {
+            let (id, table) = if row % 2 == 0 {
+                (<GeneratorMultiples as FixedTable<Fp>>::ID, &multiples)
+            } else {
+                (<SBox as FixedTable<Fp>>::ID, &sbox)
+            };
+            let lookups = (0..3).flat_map(|i| {
+                let entry = (row + i) % table[0].len();
+                [table[0][entry], table[1][entry]]
+            });
+            std::iter::once(Fp::from(id as u64))
+                .chain(lookups)
+                .collect()
+        })
+        .collect();
+    let witness = array::from_fn(|col| {
+        cells
+            .iter()
+            .map(|row| row.get(col).copied().unwrap_or_else(Fp::zero))
+            .collect()
+    });
+    (gates, witness)
+}
+
+fn create_index(
+    gates: Vec<CircuitGate<Fp>>,
+    registry: FixedTableRegistry<Fp>,
+) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let cs = ConstraintSystem::create(gates)
+        .fixed_tables(registry)
+        .build()
+        .unwrap();
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_fixed_table_registry() {
+    let mut registry = registry(10);
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.num_rows(), 10 + 16);
+    assert_eq!(registry.name(4), Some("SBox"));
+    assert_eq!(registry.get(3).unwrap().width(), 2);
+    assert!(registry.get(5).is_none());
+
+    assert_eq!(
+        registry.register(&SBox),
+        Err(FixedTableError::DuplicateId(4))
+    );
+    assert_eq!(
+        registry.register(&Reserved),
+        Err(FixedTableError::ReservedId(0))
+    );
+    assert_eq!(
+        registry.register(&Ragged),
+        Err(FixedTableError::InconsistentLength("Ragged"))
+    );
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn test_fixed_table_prove_and_verify() {
+    let (gates, witness) = create_circuit(20, 10);
+    let index = create_index(gates, registry(10));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+
+    let verifier_index = index.verifier_index();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_fixed_table_rejects_bad_lookup() {
+    let (gates, mut witness) = create_circuit(20, 10);
+    let index = create_index(gates, registry(10));
+
+    // 16 is not in the domain of the S-box
+    witness[1][1] = Fp::from(16u64);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let res = ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index);
+    assert!(matches!(res, Err(ProverError::ValueNotInTable(_))));
+}
+
+#[test]
+fn test_fixed_table_domain_budget() {
+    // 100 + 16 table rows, the dummy entry and the zero-knowledge rows fit in 2^7 rows
+    let (gates, _) = create_circuit(4, 100);
+    let cs = ConstraintSystem::create(gates.clone())
+        .fixed_tables(registry(100).max_domain_size(128))
+        .build()
+        .unwrap();
+    assert_eq!(cs.domain.d1.size(), 128);
+
+    let res = ConstraintSystem::create(gates)
+        .fixed_tables(registry(120).max_domain_size(128))
+        .build();
+    assert!(matches!(
+        res,
+        Err(SetupError::FixedTable(
+            FixedTableError::DomainBudgetExceeded {
+                domain_size: 256,
+                max_domain_size: 128,
+                fixed_table_rows: 136,
+            }
+        ))
+    ));
+}
@@ -0,0 +1,171 @@
+use crate::circuits::{
+    constraints::ConstraintSystem,
+    gate::CircuitGate,
+    polynomials::{
+        foreign_field_common::{
+            check_foreign_field_modulus, BigUintForeignFieldHelpers, ForeignFieldModulusError,
+        },
+        foreign_field_ecdsa::{
+            curve::ForeignCurve,
+            gadget::{EcdsaCells, EcdsaParams},
+            witness::{self, Signature},
+        },
+        foreign_field_scalar_mul::witness::ForeignAffine,
+    },
+};
+use ark_ff::{One, Zero};
+use mina_curves::{
+    goldilocks::Goldilocks,
+    pasta::{Fp, Vesta},
+};
+use num_bigint::BigUint;
+
+// A secret key and its public key
+fn key_pair() -> (BigUint, ForeignAffine) {
+    let curve = ForeignCurve::secp256r1();
+    let secret = BigUint::parse_bytes(
+        b"c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        16,
+    )
+    .unwrap();
+    let public = curve
+        .generator
+        .mul(&secret, &curve.a, &curve.modulus)
+        .unwrap();
+    (secret, public)
+}
+
+fn ecdsa_params(public_key: ForeignAffine) -> EcdsaParams {
+    EcdsaParams::new::<Fp>(ForeignCurve::secp256r1(), public_key, 4, 2)
+}
+
+fn create_circuit(params: &EcdsaParams) -> (Vec<CircuitGate<Fp>>, EcdsaCells) {
+    let mut gates = vec![];
+    let mut next_row = 0;
+    let cells = CircuitGate::extend_foreign_field_ecdsa(&mut gates, &mut next_row, params);
+    assert_eq!(gates.len(), params.num_rows());
+    (gates, cells)
+}
+
+#[test]
+fn test_secp256r1_parameters() {
+    let curve = ForeignCurve::secp256r1();
+    let p256 = BigUint::two().pow(256) - BigUint::two().pow(224)
+        + BigUint::two().pow(192)
+        + BigUint::two().pow(96)
+        - BigUint::one();
+    assert_eq!(curve.modulus, p256);
+    assert!(curve.is_on_curve(&curve.generator));
+    assert!(curve
+        .generator
+        .mul(&curve.order, &curve.a, &curve.modulus)
+        .is_none());
+    assert_eq!(curve.check::<Fp>(), Ok(()));
+    // the low limb of the modulus is the largest limb
+    assert_eq!(
+        curve.modulus.to_limbs()[0],
+        BigUint::two_to_limb() - BigUint::one()
+    );
+}
+
+#[test]
+fn test_check_foreign_field_modulus() {
+    let max = BigUint::max_foreign_field_modulus::<Fp>();
+    assert_eq!(check_foreign_field_modulus::<Fp>(&max), Ok(()));
+    assert_eq!(
+        check_foreign_field_modulus::<Fp>(&(&max + BigUint::one())),
+        Err(ForeignFieldModulusError::TooLarge(&max + BigUint::one()))
+    );
+    assert_eq!(
+        check_foreign_field_modulus::<Fp>(&BigUint::one()),
+        Err(ForeignFieldModulusError::TooSmall(BigUint::one()))
+    );
+    // the products of the P-256 multiplications wrap around a 64-bit native field
+    let p256 = ForeignCurve::secp256r1().modulus;
+    assert_eq!(
+        check_foreign_field_modulus::<Goldilocks>(&p256),
+        Err(ForeignFieldModulusError::NativeFieldTooSmall(p256))
+    );
+}
+
+#[test]
+fn test_ecdsa_p256_reference() {
+    let (secret, public) = key_pair();
+    let params = ecdsa_params(public);
+    let z = BigUint::from(0x5ea1u32) << 200;
+    let k = BigUint::from(0xc0ffeeu32) << 100;
+    let signature = Signature::sign(&params, &z, &secret, &k).unwrap();
+    assert!(signature.verify(&params, &z));
+    assert!(!signature.verify(&params, &(&z + BigUint::one())));
+    // ECDSA signatures are malleable, (r, -s) is valid too
+    let negated = Signature {
+        r: signature.r.clone(),
+        s: &params.curve.order - &signature.s,
+    };
+    assert!(negated.verify(&params, &z));
+    let unreduced = Signature {
+        r: &signature.r + &params.curve.order,
+        s: signature.s.clone(),
+    };
+    assert!(!unreduced.verify(&params, &z));
+}
+
+#[test]
+fn test_ecdsa_p256() {
+    let (secret, public) = key_pair();
+    let params = ecdsa_params(public);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    let z = BigUint::parse_bytes(
+        b"af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf",
+        16,
+    )
+    .unwrap();
+    let k = BigUint::parse_bytes(
+        b"a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60",
+        16,
+    )
+    .unwrap();
+    let signature = Signature::sign(&params, &z, &secret, &k).unwrap();
+    let witness = witness::create::<Fp>(&z, &signature, &params);
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // the cells of the gadget hold the hash and the signature
+    let cell = |(row, col): (usize, usize)| witness[col][row];
+    assert_eq!(cells.hash.map(cell), z.to_field_limbs::<Fp>());
+    assert_eq!(cells.r.map(cell), signature.r.to_field_limbs::<Fp>());
+    assert_eq!(cells.s.map(cell), signature.s.to_field_limbs::<Fp>());
+
+    // the signature of another hash
+    let witness = witness::create::<Fp>(&(&z + BigUint::one()), &signature, &params);
+    assert!(cs.check_witness::<Vesta>(&witness, &[]).is_err());
+
+    // a signature by another key
+    let other = Signature::sign(&params, &z, &(&secret + BigUint::one()), &k).unwrap();
+    let witness = witness::create::<Fp>(&z, &other, &params);
+    assert!(cs.check_witness::<Vesta>(&witness, &[]).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_ecdsa_public_key_not_on_curve() {
+    let (_, public) = key_pair();
+    let public = ForeignAffine::new(public.x, public.y + BigUint::one());
+    ecdsa_params(public);
+}
+
+#[test]
+#[should_panic]
+fn test_ecdsa_zero_s() {
+    let (_, public) = key_pair();
+    let params = ecdsa_params(public);
+    let signature = Signature {
+        r: BigUint::one(),
+        s: BigUint::zero(),
+    };
+    witness::create::<Fp>(&BigUint::one(), &signature, &params);
+}
@@ -0,0 +1,163 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::{
+            foreign_field_common::BigUintForeignFieldHelpers,
+            foreign_field_scalar_mul::{
+                gadget::{ScalarMulCells, ScalarMulParams},
+                witness::{self, ForeignAffine},
+            },
+        },
+    },
+    tests::framework::TestFramework,
+};
+use ark_ff::{One, Zero};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use num_bigint::BigUint;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+// The secp256k1 base field modulus
+fn secp256k1_modulus() -> BigUint {
+    BigUint::from_bytes_be(&secp256k1::constants::FIELD_SIZE)
+}
+
+// The generator of secp256k1
+fn secp256k1_generator() -> ForeignAffine {
+    ForeignAffine::new(
+        BigUint::parse_bytes(
+            b"79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            16,
+        )
+        .unwrap(),
+        BigUint::parse_bytes(
+            b"483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
+            16,
+        )
+        .unwrap(),
+    )
+}
+
+fn secp256k1_params(num_bits: usize, window: usize) -> ScalarMulParams {
+    ScalarMulParams::new(
+        secp256k1_modulus(),
+        BigUint::zero(),
+        secp256k1_generator(),
+        num_bits,
+        window,
+        2,
+    )
+}
+
+// Computes scalar * G with the secp256k1 library
+fn secp256k1_mul(scalar: &BigUint) -> ForeignAffine {
+    let mut bytes = [0u8; 32];
+    let scalar = scalar.to_bytes_be();
+    bytes[(32 - scalar.len())..].copy_from_slice(&scalar);
+    let secret = SecretKey::from_slice(&bytes).unwrap();
+    let public = PublicKey::from_secret_key(&Secp256k1::new(), &secret).serialize_uncompressed();
+    ForeignAffine::new(
+        BigUint::from_bytes_be(&public[1..33]),
+        BigUint::from_bytes_be(&public[33..65]),
+    )
+}
+
+fn create_circuit(params: &ScalarMulParams) -> (Vec<CircuitGate<Fp>>, ScalarMulCells) {
+    let mut gates = vec![];
+    let mut next_row = 0;
+    let cells = CircuitGate::extend_foreign_field_scalar_mul(&mut gates, &mut next_row, params);
+    assert_eq!(gates.len(), params.num_rows());
+    (gates, cells)
+}
+
+#[test]
+fn test_foreign_field_ec_add_witness() {
+    let modulus = secp256k1_modulus();
+    let g = secp256k1_generator();
+    let g2 = g.double(&BigUint::zero(), &modulus);
+    let mut witness = std::array::from_fn(|_| vec![]);
+    let g3 = witness::extend_ec_add::<Fp>(&mut witness, &g, &g2, &modulus);
+    assert_eq!(g3, secp256k1_mul(&BigUint::from(3u32)));
+    assert_eq!(witness[0].len(), 111);
+}
+
+#[test]
+fn test_foreign_field_scalar_mul() {
+    let params = secp256k1_params(8, 2);
+    let (gates, cells) = create_circuit(&params);
+
+    for scalar in [1u32, 0xb7, 0xff] {
+        let scalar = BigUint::from(scalar);
+        let (witness, result) = witness::create::<Fp>(&scalar, &params);
+        assert_eq!(result, secp256k1_mul(&scalar));
+
+        // the cells of the gadget hold the scalar and the result
+        let cell = |(row, col): (usize, usize)| witness[col][row];
+        assert_eq!(cells.scalar.len(), 1);
+        assert_eq!(cell(cells.scalar[0]), Fp::from(scalar.clone()));
+        let [x, y] = cells.result;
+        assert_eq!(x.map(cell), result.x.to_field_limbs::<Fp>());
+        assert_eq!(y.map(cell), result.y.to_field_limbs::<Fp>());
+
+        let cs = ConstraintSystem::create(gates.clone())
+            .lookup(params.lookup_tables())
+            .build()
+            .unwrap();
+        assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+    }
+}
+
+#[test]
+fn test_foreign_field_scalar_mul_invalid_witness() {
+    let params = secp256k1_params(8, 4);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+    let (witness, _) = witness::create::<Fp>(&BigUint::from(0x5au32), &params);
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // a wrong coordinate of the result
+    let mut bad_witness = witness.clone();
+    let (row, col) = cells.result[0][0];
+    bad_witness[col][row] += Fp::one();
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+
+    // a wrong scalar
+    let mut bad_witness = witness;
+    let (row, col) = cells.scalar[0];
+    bad_witness[col][row] += Fp::from(16u32);
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_foreign_field_scalar_mul_zero() {
+    // the result is the point at infinity
+    let params = secp256k1_params(8, 2);
+    witness::create::<Fp>(&BigUint::zero(), &params);
+}
+
+#[test]
+fn test_foreign_field_scalar_mul_prove_and_verify() {
+    let params = secp256k1_params(8, 4);
+    let (gates, _) = create_circuit(&params);
+    let (witness, _) = witness::create::<Fp>(&BigUint::from(0xc3u32), &params);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .lookup_tables(params.lookup_tables())
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
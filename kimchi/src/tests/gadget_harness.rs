@@ -0,0 +1,166 @@
+//! Property-based testing of gadgets
+//!
+//! A [GadgetHarness] creates the witness of a gadget from inputs generated by
+//! proptest, and checks that it satisfies the constraints of the gates of the
+//! gadget. It also checks that the constraints reject the witness once one of
+//! its constrained cells has been mutated.
+//!
+//! The strategies of this module favor the edge values of the inputs: 0,
+//! p - 1, and the values at the boundaries of the limbs.
+//!
+//! NOTE: the lookups are not checked by the harness, the witness must be
+//! proven with the [TestFramework](super::framework::TestFramework) for that.
+
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        polynomial::COLUMNS,
+        wires::Wire,
+    },
+    curve::KimchiCurve,
+};
+use ark_ff::{One, PrimeField, Zero};
+use num_bigint::BigUint;
+use proptest::{prelude::*, sample::Index, test_runner::TestRunner};
+use rand::{rngs::StdRng, SeedableRng};
+use std::fmt::Debug;
+
+/// Number of inputs checked by [GadgetHarness::run]
+const CASES: u32 = 32;
+
+/// Values of at most `bits` bits: 0, the powers of two and the powers of two
+/// minus one (including `2^bits - 1`), or random values
+pub(crate) fn arb_edge_bits(bits: usize) -> impl Strategy<Value = BigUint> {
+    assert!(bits > 0, "the values must have at least one bit");
+    let max = (BigUint::one() << bits) - 1u32;
+    prop_oneof![
+        1 => Just(BigUint::zero()),
+        1 => Just(max.clone()),
+        1 => (0..bits).prop_map(|k| BigUint::one() << k),
+        1 => (1..bits).prop_map(|k| (BigUint::one() << k) - 1u32),
+        4 => prop::collection::vec(any::<u8>(), (bits + 7) / 8)
+            .prop_map(move |bytes| BigUint::from_bytes_le(&bytes) & &max),
+    ]
+}
+
+/// Field elements: 0, 1, p - 1, the powers of two and the powers of two minus
+/// one, or random elements
+pub(crate) fn arb_edge_field<F: PrimeField>() -> impl Strategy<Value = F> {
+    let bits = F::size_in_bits();
+    prop_oneof![
+        1 => Just(F::zero()),
+        1 => Just(F::one()),
+        1 => Just(-F::one()),
+        1 => (0..bits).prop_map(|k| F::from(2u64).pow([k as u64])),
+        1 => (1..bits).prop_map(|k| F::from(2u64).pow([k as u64]) - F::one()),
+        4 => any::<[u8; 32]>().prop_map(|seed| F::rand(&mut StdRng::from_seed(seed))),
+    ]
+}
+
+/// A builder of the witness of a gadget from its input
+type WitnessBuilder<I, F> = Box<dyn Fn(&I) -> [Vec<F>; COLUMNS]>;
+
+/// The gates of a gadget, with the builder of its witness
+pub(crate) struct GadgetHarness<G: KimchiCurve, I>
+where
+    G::BaseField: PrimeField,
+{
+    cs: ConstraintSystem<G::ScalarField>,
+    num_rows: usize,
+    witness: WitnessBuilder<I, G::ScalarField>,
+    constrained_cells: Vec<Wire>,
+}
+
+impl<G: KimchiCurve, I: Debug> GadgetHarness<G, I>
+where
+    G::BaseField: PrimeField,
+{
+    /// Creates the harness of the gadget `gates`, whose witness is created from
+    /// an input by `witness`
+    pub(crate) fn new(
+        mut gates: Vec<CircuitGate<G::ScalarField>>,
+        witness: impl Fn(&I) -> [Vec<G::ScalarField>; COLUMNS] + 'static,
+    ) -> Self {
+        let num_rows = gates.len();
+        // A constraint system has at least two gates, the zero gates added are
+        // not checked
+        while gates.len() < 2 {
+            gates.push(CircuitGate::zero(Wire::for_row(gates.len())));
+        }
+        Self {
+            cs: ConstraintSystem::create(gates).build().unwrap(),
+            num_rows,
+            witness: Box::new(witness),
+            constrained_cells: vec![],
+        }
+    }
+
+    /// Sets the cells mutated by the negative tests. A mutation of any of them
+    /// must break a constraint of the gadget, or one of its copy constraints.
+    #[must_use]
+    pub(crate) fn constrained_cells(mut self, cells: impl IntoIterator<Item = Wire>) -> Self {
+        self.constrained_cells = cells.into_iter().collect();
+        self
+    }
+
+    /// Verifies the witness against the gates of the gadget, returning the
+    /// first failure
+    pub(crate) fn verify(&self, witness: &[Vec<G::ScalarField>; COLUMNS]) -> Result<(), String> {
+        let public = &witness[0][0..self.cs.public];
+        for (row, gate) in self.cs.gates.iter().enumerate().take(self.num_rows) {
+            gate.verify_witness::<G>(row, witness, &self.cs, public)
+                .map_err(|e| format!("row {row}: {e}"))?;
+            // The generic constraints are not checked by verify_witness
+            if gate.typ == GateType::Generic {
+                gate.verify_generic(row, witness, public)
+                    .map_err(|e| format!("row {row}: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the witness of `input` satisfies the constraints
+    pub(crate) fn check(&self, input: &I) -> Result<(), TestCaseError> {
+        let witness = (self.witness)(input);
+        prop_assert_eq!(witness[0].len(), self.num_rows);
+        self.verify(&witness).map_err(TestCaseError::fail)
+    }
+
+    /// Checks that the witness of `input` is rejected once `delta` is added to
+    /// the constrained cell at `index`
+    pub(crate) fn check_mutation(
+        &self,
+        input: &I,
+        index: Index,
+        delta: u64,
+    ) -> Result<(), TestCaseError> {
+        if self.constrained_cells.is_empty() {
+            return Ok(());
+        }
+        let cell = *index.get(&self.constrained_cells);
+        let mut witness = (self.witness)(input);
+        witness[cell.col][cell.row] += G::ScalarField::from(delta);
+        prop_assert!(
+            self.verify(&witness).is_err(),
+            "the mutation of the cell {:?} is not detected",
+            cell
+        );
+        Ok(())
+    }
+
+    /// Runs [Self::check] and [Self::check_mutation] on inputs generated by
+    /// `inputs`, panicking with the smallest failing input
+    pub(crate) fn run<S: Strategy<Value = I>>(&self, inputs: S) {
+        let mut runner = TestRunner::new(ProptestConfig::with_cases(CASES));
+        runner
+            .run(
+                &(inputs, any::<Index>(), 1..u64::MAX),
+                |(input, index, delta)| {
+                    self.check(&input)?;
+                    self.check_mutation(&input, index, delta)
+                },
+            )
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+}
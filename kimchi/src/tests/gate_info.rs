@@ -0,0 +1,91 @@
+use crate::circuits::{
+    argument::Argument,
+    gate::{CurrOrNext, GateType},
+    gate_info::{gate_registry, GateLookup, GATE_TYPES},
+    lookup::{lookups::LookupPattern, tables::GateLookupTable},
+    polynomials::{generic::Generic, keccak::circuitgates::KeccakRound, poseidon::Poseidon},
+    wires::COLUMNS,
+};
+use mina_curves::pasta::Fp;
+
+#[test]
+fn test_gate_registry() {
+    let registry = gate_registry::<Fp>();
+    assert_eq!(registry.0.len(), GATE_TYPES.len());
+
+    let generic = registry.get(GateType::Generic).unwrap();
+    assert_eq!(generic.constraints, Generic::<Fp>::CONSTRAINTS as usize);
+    // c3 * l * r
+    assert_eq!(generic.degree, 3);
+    assert_eq!(generic.curr_witness, (0..6).collect::<Vec<_>>());
+    assert!(generic.next_witness.is_empty());
+    assert_eq!(generic.coefficients, (0..10).collect::<Vec<_>>());
+    assert_eq!(generic.rows, 1);
+    assert!(generic.lookups.is_empty());
+
+    let poseidon = registry.get(GateType::Poseidon).unwrap();
+    assert_eq!(poseidon.constraints, Poseidon::<Fp>::CONSTRAINTS as usize);
+    assert_eq!(poseidon.degree, 7);
+    assert_eq!(poseidon.curr_witness, (0..COLUMNS).collect::<Vec<_>>());
+    // the output state
+    assert_eq!(poseidon.next_witness, vec![0, 1, 2]);
+    assert_eq!(poseidon.rows, 2);
+
+    let keccak = registry.get(GateType::KeccakRound).unwrap();
+    assert_eq!(keccak.constraints, KeccakRound::<Fp>::CONSTRAINTS as usize);
+
+    for spec in &registry.0 {
+        // the selector of the gate adds one to the degree of its constraints
+        assert!(spec.degree < 8, "{:?}", spec.typ);
+        assert!(!spec.description.is_empty());
+        assert_eq!(
+            spec.constraints == 0,
+            matches!(
+                spec.typ,
+                GateType::Zero | GateType::Lookup | GateType::VectorLookup | GateType::Custom
+            )
+        );
+        assert_eq!(spec.degrees.len(), spec.constraints);
+        assert_eq!(spec.degrees.iter().max().copied().unwrap_or(0), spec.degree);
+    }
+
+    let table = registry.to_string();
+    assert_eq!(table.lines().count(), 1 + GATE_TYPES.len());
+    assert!(table.contains("ForeignFieldMul"));
+}
+
+#[test]
+fn test_gate_spec_lookups() {
+    let ffmul = GateType::ForeignFieldMul.spec::<Fp>();
+    assert_eq!(ffmul.rows, 2);
+    assert_eq!(
+        ffmul.lookups,
+        [CurrOrNext::Curr, CurrOrNext::Next].map(|row| GateLookup {
+            row,
+            pattern: LookupPattern::ForeignFieldMul,
+            count: 4,
+            table: Some(GateLookupTable::RangeCheck),
+        })
+    );
+
+    let xor = GateType::Xor16.spec::<Fp>();
+    assert_eq!(
+        xor.lookups,
+        vec![GateLookup {
+            row: CurrOrNext::Curr,
+            pattern: LookupPattern::Xor,
+            count: 4,
+            table: Some(GateLookupTable::Xor),
+        }]
+    );
+
+    // the table of the lookup gate is chosen by the circuit
+    let lookup = GateType::Lookup.spec::<Fp>();
+    assert_eq!(lookup.rows, 1);
+    assert_eq!(lookup.lookups[0].table, None);
+
+    // the specs can be exported for external tooling
+    let json = serde_json::to_value(gate_registry::<Fp>()).unwrap();
+    assert_eq!(json[15]["typ"], "ForeignFieldMul");
+    assert_eq!(json[15]["lookups"][1]["row"], "Next");
+}
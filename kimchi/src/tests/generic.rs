@@ -1,15 +1,34 @@
-use super::framework::TestFramework;
-use crate::circuits::{
-    polynomials::generic::testing::{create_circuit, fill_in_witness},
-    wires::COLUMNS,
+use super::{
+    framework::TestFramework,
+    gadget_harness::{arb_edge_field, GadgetHarness},
+};
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::generic::{
+            testing::{create_circuit, fill_in_witness},
+            GenericGateSpec,
+        },
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    error::{ProverError, SetupError, WitnessError},
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
 };
 use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
-use std::array;
+use o1_utils::cancellation::{CancellationToken, Cancelled};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
 
 type SpongeParams = PlonkSpongeConstantsKimchi;
 type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
@@ -125,3 +144,339 @@ fn test_generic_gate_pairing() {
     .prove_and_verify::<BaseSponge, ScalarSponge>()
     .unwrap();
 }
+
+#[cfg(feature = "bn254")]
+#[test]
+fn test_generic_gate_bn254_ipa() {
+    type Fp = ark_bn254::Fr;
+    type SpongeParams = PlonkSpongeConstantsKimchi;
+    type BaseSponge = DefaultFqSponge<mina_curves::bn254::Bn254Parameters, SpongeParams>;
+    type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    // create and verify proof based on the witness, committing with the
+    // inner product argument instead of the pairing-based commitment.
+    // The stored BN254 SRS is a pairing SRS, so the IPA one is created here.
+    TestFramework::<mina_curves::bn254::Bn254>::default()
+        .gates(gates)
+        .witness(witness)
+        .public_inputs(public)
+        .setup_with_custom_srs(|d1, size| {
+            let mut srs = SRS::create(size);
+            srs.add_lagrange_basis(d1);
+            srs
+        })
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
+
+#[cfg(feature = "bn254")]
+#[test]
+fn test_generic_gate_solidity_verifier() {
+    use crate::{
+        circuits::expr::{FeatureFlag, PolishToken},
+        solidity::{generate_verifier, SolidityError, BN254},
+    };
+    type Fp = ark_bn254::Fr;
+
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let x = Fp::from(42u8);
+    let runner = <TestFramework<_, poly_commitment::pairing_proof::PairingProof<BN254>> as Default>::default()
+        .gates(gates)
+        .witness(witness)
+        .public_inputs(public)
+        .setup_with_custom_srs(|d1, usize| {
+            let mut srs = poly_commitment::pairing_proof::PairingSRS::create(x, usize);
+            srs.full_srs.add_lagrange_basis(d1);
+            srs
+        });
+    let mut index = runner.prover_index().verifier_index();
+
+    let contract = generate_verifier(&index, "GenericVerifier").unwrap();
+    assert!(contract.contains("contract GenericVerifier {"));
+    assert!(contract.contains(&format!(
+        "uint256 internal constant DOMAIN_SIZE = {};",
+        index.domain.size
+    )));
+    assert!(contract.contains("uint256 internal constant PUBLIC_INPUTS = 5;"));
+    assert!(contract.contains(&index.linearization.digest()));
+    assert!(contract.contains("function linearizationConstantTerm("));
+    assert!(contract.contains("function linearizationCommitment("));
+    assert!(contract.contains("function verifyOpening("));
+    // the generation is deterministic
+    assert_eq!(
+        contract,
+        generate_verifier(&index, "GenericVerifier").unwrap()
+    );
+
+    // unresolved feature flags cannot be compiled
+    index
+        .linearization
+        .constant_term
+        .insert(0, PolishToken::SkipIf(FeatureFlag::RangeCheck0, 0));
+    assert!(matches!(
+        generate_verifier(&index, "GenericVerifier"),
+        Err(SolidityError::UnsupportedToken(_))
+    ));
+}
+
+#[test]
+fn test_generic_gate_check_witness() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let cs = ConstraintSystem::create(gates)
+        .public(public.len())
+        .build()
+        .unwrap();
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &public), Ok(()));
+
+    // wrong number of public inputs
+    assert_eq!(
+        cs.check_witness::<Vesta>(&witness, &public[1..]),
+        Err(WitnessError::PublicInputLength(5, 4))
+    );
+
+    // wrong public input
+    let mut bad_public = public.clone();
+    bad_public[2] = Fp::from(4u8);
+    assert!(matches!(
+        cs.check_witness::<Vesta>(&witness, &bad_public),
+        Err(WitnessError::Generic(2, _))
+    ));
+
+    // columns of different lengths
+    let mut bad_witness = witness.clone();
+    bad_witness[3].push(Fp::zero());
+    assert_eq!(
+        cs.check_witness::<Vesta>(&bad_witness, &public),
+        Err(WitnessError::WitnessCsInconsistent)
+    );
+}
+
+#[test]
+fn test_generic_gate_cancellation() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness.clone())
+        .public_inputs(public.clone())
+        .setup();
+    let index = runner.prover_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let create = |cancel: &CancellationToken| {
+        ProverProof::create_recursive_cancellable::<BaseSponge, ScalarSponge>(
+            &group_map,
+            witness.clone(),
+            &[],
+            index,
+            vec![],
+            None,
+            cancel,
+        )
+    };
+
+    // a token which is not cancelled does not change the proof creation
+    let cancel = CancellationToken::new();
+    let proof = create(&cancel).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        &proof,
+        &public,
+    )
+    .unwrap();
+
+    cancel.cancel();
+    assert!(matches!(
+        create(&cancel),
+        Err(ProverError::Cancelled(Cancelled))
+    ));
+}
+
+#[test]
+fn test_generic_gate_deterministic() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness.clone())
+        .public_inputs(public.clone())
+        .setup();
+    let index = runner.prover_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let prove = |seed: [u8; 32]| {
+        let proof = ProverProof::prove_with_rng_seed::<BaseSponge, ScalarSponge>(
+            &group_map,
+            witness.clone(),
+            &[],
+            index,
+            vec![],
+            None,
+            seed,
+        )
+        .unwrap();
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &index.verifier_index(),
+            &proof,
+            &public,
+        )
+        .unwrap();
+        rmp_serde::to_vec(&proof).unwrap()
+    };
+
+    // the same seed gives the same proof, and different seeds different proofs
+    assert_eq!(prove([1; 32]), prove([1; 32]));
+    assert_ne!(prove([1; 32]), prove([2; 32]));
+}
+
+#[test]
+fn test_generic_gate_zk_rows() {
+    let public = vec![Fp::from(3u8); 5];
+    let gates = create_circuit(0, public.len());
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let cs = ConstraintSystem::create(gates)
+        .public(public.len())
+        .zk_rows(8)
+        .build()
+        .unwrap();
+    assert_eq!(cs.zk_rows, 8);
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    let index = ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, Arc::new(srs));
+    assert_eq!(index.verifier_index().zk_rows, 8);
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        &proof,
+        &public,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_generic_gate_not_enough_zk_rows() {
+    let gates = create_circuit(0, 0);
+
+    // the permutation argument needs 3 rows
+    let res = ConstraintSystem::<Fp>::create(gates.clone())
+        .zk_rows(2)
+        .build();
+    assert!(matches!(res, Err(SetupError::NotEnoughZkRows(3, 2))));
+
+    // chunked polynomials need more rows
+    let cs = ConstraintSystem::<Fp>::create(gates.clone())
+        .max_poly_size(Some(8))
+        .build()
+        .unwrap();
+    let zk_rows = cs.zk_rows;
+    assert!(zk_rows > 3);
+    let res = ConstraintSystem::<Fp>::create(gates.clone())
+        .max_poly_size(Some(8))
+        .zk_rows(zk_rows - 1)
+        .build();
+    assert!(matches!(
+        res,
+        Err(SetupError::NotEnoughZkRows(expected, got)) if expected == zk_rows && got == zk_rows - 1
+    ));
+    let cs = ConstraintSystem::<Fp>::create(gates)
+        .max_poly_size(Some(8))
+        .zk_rows(zk_rows)
+        .build()
+        .unwrap();
+    assert_eq!(cs.zk_rows, zk_rows);
+}
+
+#[test]
+fn test_generic_gadget_properties() {
+    let gates = vec![CircuitGate::<Fp>::create_generic_gadget(
+        Wire::for_row(0),
+        GenericGateSpec::Add {
+            left_coeff: None,
+            right_coeff: None,
+            output_coeff: None,
+        },
+        Some(GenericGateSpec::Mul {
+            output_coeff: None,
+            mul_coeff: None,
+        }),
+    )];
+    GadgetHarness::<Vesta, (Fp, Fp)>::new(gates, |&(x, y)| {
+        array::from_fn(|col| match col {
+            0 | 3 => vec![x],
+            1 | 4 => vec![y],
+            2 => vec![x + y],
+            5 => vec![x * y],
+            _ => vec![Fp::zero()],
+        })
+    })
+    // a factor of the multiplication can be mutated when the other one is zero
+    .constrained_cells([0, 1, 2, 5].map(|col| Wire::new(0, col)))
+    .run((arb_edge_field(), arb_edge_field()));
+}
+
+#[cfg(feature = "quotient_diagnostics")]
+#[test]
+fn test_generic_gate_quotient_diagnostics() {
+    use crate::circuits::{argument::ArgumentType, gate::GateType};
+
+    let gates = create_circuit(0, 0);
+
+    // create witness
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &[]);
+
+    // the output of the generic gate at row 1 is wrong
+    witness[2][1] += Fp::from(1u8);
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .disable_gates_checks(true)
+        .setup();
+    let index = runner.prover_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+
+    assert!(matches!(
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], index),
+        Err(ProverError::UnsatisfiedConstraints(
+            ArgumentType::Gate(GateType::Generic),
+            1
+        ))
+    ));
+}
@@ -0,0 +1,75 @@
+use crate::{
+    circuits::{
+        berkeley_columns::{witness_curr, E},
+        constraints::ConstraintSystem,
+        expr::{Challenges, Constants, PolishToken},
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    proof::ProofEvaluations,
+};
+use ark_ff::{One, Zero};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
+use mina_curves::goldilocks::Goldilocks;
+use std::array;
+
+/// The circuits over Goldilocks have no Poseidon gate, so no MDS matrix
+fn mds() -> &'static Vec<Vec<Goldilocks>> {
+    Box::leak(Box::default())
+}
+
+#[test]
+fn test_generic_gates_over_goldilocks() {
+    let gates = create_circuit(0, 0);
+    let mut witness: [Vec<Goldilocks>; COLUMNS] =
+        array::from_fn(|_| vec![Goldilocks::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &[]);
+
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+    assert_eq!(
+        cs.check_witness_with_mds(mds(), &witness, &[], None),
+        Ok(())
+    );
+
+    witness[2][0] += Goldilocks::one();
+    assert!(cs
+        .check_witness_with_mds(mds(), &witness, &[], None)
+        .is_err());
+}
+
+#[test]
+fn test_expr_over_goldilocks() {
+    // 2^40 * 2^40 wraps around the modulus
+    let x = Goldilocks::from(1u64 << 40);
+    let mut curr = [Goldilocks::zero(); COLUMNS];
+    curr[0] = x;
+    curr[1] = x;
+    curr[2] = Goldilocks::from(1u128 << 80);
+    let evals = ProofEvaluations::dummy_with_witness_evaluations(curr, curr);
+
+    let expr: E<Goldilocks> = witness_curr(0) * witness_curr(1) - witness_curr(2);
+    let constants = Constants {
+        endo_coefficient: Goldilocks::zero(),
+        mds: mds(),
+        zk_rows: 3,
+    };
+    let challenges = Challenges {
+        alpha: Goldilocks::one(),
+        beta: Goldilocks::one(),
+        gamma: Goldilocks::one(),
+        joint_combiner: None,
+    };
+    let domain = D::new(8).unwrap();
+    for expr in [expr.clone(), expr.optimize()] {
+        let value = PolishToken::evaluate(
+            &expr.to_polish(),
+            domain,
+            Goldilocks::from(5u64),
+            &evals,
+            &constants,
+            &challenges,
+        )
+        .unwrap();
+        assert_eq!(value, Goldilocks::zero());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    curve::KimchiCurve,
+    error::VerifyError,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+};
+use ark_ff::{One, Zero};
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const PUBLIC: usize = 3;
+
+fn create_index(gates: Vec<CircuitGate<Fp>>) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let cs = ConstraintSystem::create(gates)
+        .public(PUBLIC)
+        .build()
+        .unwrap();
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+// The same circuit, with a different coefficient in its first gate after the
+// public inputs
+fn other_version(mut gates: Vec<CircuitGate<Fp>>) -> Vec<CircuitGate<Fp>> {
+    gates[PUBLIC].coeffs[0] += Fp::one();
+    gates
+}
+
+fn create_proof(
+    gates: &[CircuitGate<Fp>],
+    index: &ProverIndex<Vesta, OpeningProof<Vesta>>,
+) -> (ProverProof<Vesta, OpeningProof<Vesta>>, Vec<Fp>) {
+    let public = vec![Fp::from(3u8); PUBLIC];
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], index).unwrap();
+    (proof, public)
+}
+
+#[test]
+fn test_index_digest() {
+    let gates = create_circuit(0, PUBLIC);
+    let index = create_index(gates.clone());
+    let digest = index.index_digest::<BaseSponge>();
+
+    // the prover and verifier indexes agree
+    assert_eq!(digest, index.verifier_index().index_digest::<BaseSponge>());
+    // the digest covers the parameters, on top of the commitments
+    assert_ne!(digest, index.verifier_index_digest::<BaseSponge>());
+    // and is deterministic
+    assert_eq!(
+        digest,
+        create_index(gates.clone()).index_digest::<BaseSponge>()
+    );
+
+    // another version of the circuit has another digest
+    let other = create_index(other_version(gates));
+    assert_ne!(digest, other.index_digest::<BaseSponge>());
+}
+
+#[test]
+fn test_proof_carries_index_digest() {
+    let gates = create_circuit(0, PUBLIC);
+    let index = create_index(gates.clone());
+    let verifier_index = index.verifier_index();
+    let (mut proof, public) = create_proof(&gates, &index);
+    assert_eq!(proof.index_digest, Some(index.index_digest::<BaseSponge>()));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &public,
+    )
+    .unwrap();
+
+    // the proofs without digest are accepted by default
+    proof.index_digest = None;
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &public,
+    )
+    .unwrap();
+
+    // and rejected when the verifier index requires the digest
+    let mut verifier_index = verifier_index;
+    verifier_index.require_index_digest = true;
+    let res = verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &public,
+    );
+    assert!(matches!(res, Err(VerifyError::MissingIndexDigest)));
+}
+
+#[test]
+fn test_proof_with_index_of_other_circuit() {
+    let gates = create_circuit(0, PUBLIC);
+    let index = create_index(gates.clone());
+    let (proof, public) = create_proof(&gates, &index);
+
+    let other = create_index(other_version(gates)).verifier_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let res = verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map, &other, &proof, &public,
+    );
+    assert!(matches!(res, Err(VerifyError::IndexDigestMismatch)));
+}
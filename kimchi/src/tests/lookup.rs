@@ -1,31 +1,57 @@
 use super::framework::{print_witness, TestFramework};
-use crate::circuits::{
-    gate::{CircuitGate, GateType},
-    lookup::{
-        runtime_tables::{RuntimeTable, RuntimeTableCfg},
-        tables::LookupTable,
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        lookup::{
+            constraints::LookupArgument,
+            index::LookupError,
+            runtime_tables::{LazyRuntimeTable, RuntimeTable, RuntimeTableCfg},
+            shared_tables::{SharedLookupTablesStore, SharedTablesError},
+            tables::LookupTable,
+        },
+        polynomial::COLUMNS,
+        wires::Wire,
     },
-    polynomial::COLUMNS,
-    wires::Wire,
+    curve::KimchiCurve,
+    error::{ProverError, SetupError, VerifyError, WitnessError},
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
 };
-use ark_ff::{UniformRand, Zero};
+use ark_ff::{Field, One, UniformRand, Zero};
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::PlonkSpongeConstantsKimchi,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
 use rand::{prelude::*, Rng};
-use std::array;
+use std::{array, sync::Arc};
 
 type SpongeParams = PlonkSpongeConstantsKimchi;
 type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
 type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
-
-fn setup_lookup_proof(use_values_from_table: bool, num_lookups: usize, table_sizes: Vec<usize>) {
-    let seed: [u8; 32] = thread_rng().gen();
-    eprintln!("Seed: {:?}", seed);
-    let mut rng = StdRng::from_seed(seed);
-
+type LogUpIndex = ProverIndex<Vesta, OpeningProof<Vesta>>;
+
+/// The gates, the witness and the tables of a lookup circuit
+type LookupCircuit = (
+    Vec<CircuitGate<Fp>>,
+    [Vec<Fp>; COLUMNS],
+    Vec<LookupTable<Fp>>,
+);
+
+/// Creates `num_lookups` lookup gates into random tables of sizes `table_sizes`,
+/// and a witness looking up values from the tables if `use_values_from_table`
+/// is set, or random values otherwise.
+fn create_lookup_circuit(
+    rng: &mut StdRng,
+    use_values_from_table: bool,
+    num_lookups: usize,
+    table_sizes: Vec<usize>,
+) -> LookupCircuit {
     let mut lookup_table_values: Vec<Vec<_>> = table_sizes
         .iter()
         .map(|size| (0..*size).map(|_| rng.gen()).collect())
@@ -101,6 +127,17 @@ fn setup_lookup_proof(use_values_from_table: bool, num_lookups: usize, table_siz
         ]
     };
 
+    (gates, witness, lookup_tables)
+}
+
+fn setup_lookup_proof(use_values_from_table: bool, num_lookups: usize, table_sizes: Vec<usize>) {
+    let seed: [u8; 32] = thread_rng().gen();
+    eprintln!("Seed: {:?}", seed);
+    let mut rng = StdRng::from_seed(seed);
+
+    let (gates, witness, lookup_tables) =
+        create_lookup_circuit(&mut rng, use_values_from_table, num_lookups, table_sizes);
+
     TestFramework::<Vesta>::default()
         .gates(gates)
         .witness(witness)
@@ -110,6 +147,63 @@ fn setup_lookup_proof(use_values_from_table: bool, num_lookups: usize, table_siz
         .unwrap();
 }
 
+/// Proves the lookups with the logUp argument
+fn create_logup_proof(
+    use_values_from_table: bool,
+    num_lookups: usize,
+    table_sizes: Vec<usize>,
+) -> Result<(LogUpIndex, ProverProof<Vesta, OpeningProof<Vesta>>), ProverError> {
+    let seed: [u8; 32] = thread_rng().gen();
+    eprintln!("Seed: {:?}", seed);
+    let mut rng = StdRng::from_seed(seed);
+
+    let (gates, witness, lookup_tables) =
+        create_lookup_circuit(&mut rng, use_values_from_table, num_lookups, table_sizes);
+
+    let cs = ConstraintSystem::create(gates)
+        .lookup(lookup_tables)
+        .lookup_argument(LookupArgument::LogUp)
+        .build()
+        .unwrap();
+    assert_eq!(cs.lookup_argument(), LookupArgument::LogUp);
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    let index = ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, Arc::new(srs));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof = ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index)?;
+
+    // Only the multiplicities are committed, instead of the sorted polynomials
+    assert_eq!(proof.commitments.lookup.as_ref().unwrap().sorted.len(), 1);
+    Ok((index, proof))
+}
+
+fn verify_logup_proof(
+    index: &LogUpIndex,
+    proof: &ProverProof<Vesta, OpeningProof<Vesta>>,
+) -> Result<(), VerifyError> {
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        proof,
+        &[],
+    )
+}
+
+/// Proves and verifies the lookups with the logUp argument
+fn setup_logup_proof(
+    use_values_from_table: bool,
+    num_lookups: usize,
+    table_sizes: Vec<usize>,
+) -> Result<(), ProverError> {
+    let (index, proof) = create_logup_proof(use_values_from_table, num_lookups, table_sizes)?;
+    verify_logup_proof(&index, &proof).unwrap();
+    Ok(())
+}
+
 #[test]
 fn lookup_gate_proving_works() {
     setup_lookup_proof(true, 500, vec![256])
@@ -132,6 +226,155 @@ fn lookup_gate_rejects_bad_lookups_multiple_tables() {
     setup_lookup_proof(false, 500, vec![100, 50, 50, 2, 2])
 }
 
+#[test]
+fn logup_proving_works() {
+    setup_logup_proof(true, 500, vec![256]).unwrap()
+}
+
+#[test]
+fn logup_proving_works_multiple_tables() {
+    setup_logup_proof(true, 500, vec![100, 50, 50, 2, 2]).unwrap()
+}
+
+#[test]
+fn logup_rejects_bad_lookups() {
+    let err = setup_logup_proof(false, 500, vec![256]).unwrap_err();
+    assert!(matches!(err, ProverError::ValueNotInTable(_)));
+    assert_eq!(err.code(), "prover.value_not_in_table");
+    assert!(err.row().is_some());
+}
+
+#[test]
+fn logup_verifier_rejects_tampered_evaluations() {
+    let (index, proof) = create_logup_proof(true, 500, vec![256]).unwrap();
+    verify_logup_proof(&index, &proof).unwrap();
+
+    // the running sum of the logUp argument
+    let mut tampered = proof.clone();
+    let aggregation = tampered.evals.lookup_aggregation.as_mut().unwrap();
+    aggregation.zeta[0] += Fp::one();
+    assert!(matches!(
+        verify_logup_proof(&index, &tampered),
+        Err(VerifyError::OpenProof)
+    ));
+
+    // the multiplicities of the table entries
+    let mut tampered = proof;
+    let multiplicities = tampered.evals.lookup_sorted[0].as_mut().unwrap();
+    multiplicities.zeta_omega[0] += Fp::one();
+    assert!(matches!(
+        verify_logup_proof(&index, &tampered),
+        Err(VerifyError::OpenProof)
+    ));
+}
+
+#[test]
+fn logup_is_bound_by_the_verifier_index_digest() {
+    let mut rng = StdRng::from_seed([0; 32]);
+    let (gates, _, lookup_tables) = create_lookup_circuit(&mut rng, true, 10, vec![16]);
+
+    let digest = |argument| {
+        let cs = ConstraintSystem::create(gates.clone())
+            .lookup(lookup_tables.clone())
+            .lookup_argument(argument)
+            .build()
+            .unwrap();
+        let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+        srs.add_lagrange_basis(cs.domain.d1);
+        let &endo_q = Vesta::other_curve_endo();
+        ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, Arc::new(srs))
+            .verifier_index()
+            .digest::<BaseSponge>()
+    };
+
+    // the commitments of the index do not depend on the argument
+    assert_ne!(
+        digest(LookupArgument::Plookup),
+        digest(LookupArgument::LogUp)
+    );
+}
+
+/// Proves and verifies the lookups with at most `max_per_row` lookups per row,
+/// splitting the lookup gates over additional rows
+fn setup_split_lookup_proof(num_lookups: usize, max_per_row: usize) {
+    let seed: [u8; 32] = thread_rng().gen();
+    eprintln!("Seed: {:?}", seed);
+    let mut rng = StdRng::from_seed(seed);
+
+    let (gates, mut witness, lookup_tables) =
+        create_lookup_circuit(&mut rng, true, num_lookups, vec![100, 50]);
+
+    let cs = ConstraintSystem::create(gates)
+        .lookup(lookup_tables)
+        .max_lookups_per_row(max_per_row)
+        .build()
+        .unwrap();
+    assert_eq!(cs.max_lookups_per_row(), Some(max_per_row));
+
+    // the 3 lookups of each gate are spread over several rows
+    cs.extend_split_lookups_witness(&mut witness);
+    let rows_per_gate = (3 + max_per_row - 1) / max_per_row;
+    assert_eq!(witness[0].len(), num_lookups * rows_per_gate);
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // the lookups of the additional rows are copied from the original gates
+    let mut bad_witness = witness.clone();
+    bad_witness[1][num_lookups] += Fp::from(1u32);
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    let index = ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, Arc::new(srs));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+
+    // Fewer sorted polynomials are committed
+    assert_eq!(
+        proof.commitments.lookup.as_ref().unwrap().sorted.len(),
+        max_per_row + 1
+    );
+
+    let verifier_index = index.verifier_index();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn lookup_gate_split_proving_works() {
+    setup_split_lookup_proof(100, 1);
+    setup_split_lookup_proof(100, 2);
+}
+
+#[test]
+fn lookup_gate_max_lookups_per_row_out_of_range() {
+    let mut rng = StdRng::from_seed(thread_rng().gen());
+    let (gates, _, lookup_tables) = create_lookup_circuit(&mut rng, true, 10, vec![16]);
+    for max_per_row in [0, 5] {
+        let cs = ConstraintSystem::create(gates.clone())
+            .lookup(lookup_tables.clone())
+            .max_lookups_per_row(max_per_row)
+            .build();
+        assert!(matches!(
+            cs,
+            Err(SetupError::LookupCreation(
+                LookupError::MaxLookupsPerRowOutOfRange {
+                    minimum: 1,
+                    maximum: 4,
+                    ..
+                }
+            ))
+        ));
+    }
+}
+
 fn setup_successful_runtime_table_test(
     runtime_table_cfgs: Vec<RuntimeTableCfg<Fp>>,
     runtime_tables: Vec<RuntimeTable<Fp>>,
@@ -214,6 +457,7 @@ fn test_runtime_table() {
         let cfg = RuntimeTableCfg {
             id: table_id,
             first_column: first_column.into_iter().map(Into::into).collect(),
+            unique_keys: false,
         };
         runtime_tables_setup.push(cfg);
     }
@@ -282,6 +526,7 @@ fn test_negative_test_runtime_table_value_not_in_table() {
     let cfg = RuntimeTableCfg {
         id: 1,
         first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     let data: Vec<Fp> = [0u32, 2, 3, 4, 5].into_iter().map(Into::into).collect();
@@ -343,6 +588,7 @@ fn test_negative_test_runtime_table_prover_with_undefined_id_in_index_and_witnes
     let cfg = RuntimeTableCfg {
         id: 1,
         first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     // We give a different ID, not defined in the index.
@@ -404,6 +650,7 @@ fn test_negative_test_runtime_table_prover_uses_undefined_id_in_index_and_witnes
     let cfg = RuntimeTableCfg {
         id: 1,
         first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     let data: Vec<Fp> = [0u32, 2, 3, 4, 5].into_iter().map(Into::into).collect();
@@ -468,6 +715,7 @@ fn test_runtime_table_with_more_than_one_runtime_table_data_given_by_prover() {
     let cfg = RuntimeTableCfg {
         id: 1,
         first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     /* We want to simulate this
@@ -551,6 +799,7 @@ fn test_runtime_table_only_one_table_with_id_zero_with_non_zero_entries_fixed_va
     let cfg = RuntimeTableCfg {
         id: table_id,
         first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     let data: Vec<Fp> = [0u32, 1, 2, 3, 4, 5].into_iter().map(Into::into).collect();
@@ -575,6 +824,7 @@ fn test_runtime_table_only_one_table_with_id_zero_with_non_zero_entries_random_v
     let cfg = RuntimeTableCfg {
         id: table_id,
         first_column: first_column.clone().into_iter().map(Into::into).collect(),
+        unique_keys: false,
     };
 
     let data: Vec<Fp> = first_column
@@ -588,6 +838,87 @@ fn test_runtime_table_only_one_table_with_id_zero_with_non_zero_entries_random_v
     setup_successful_runtime_table_test(vec![cfg], vec![runtime_table], lookups);
 }
 
+#[test]
+fn test_runtime_table_with_unique_keys() {
+    let first_column = [3u32, 1, 4, 15, 9, 2];
+    let table_id = 1;
+
+    let cfg = RuntimeTableCfg {
+        id: table_id,
+        first_column: first_column.into_iter().map(Into::into).collect(),
+        unique_keys: true,
+    };
+
+    let data: Vec<Fp> = [2u32, 7, 1, 8, 28, 18]
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let runtime_table = RuntimeTable { id: table_id, data };
+
+    let lookups: Vec<i32> = [table_id; 20].into();
+
+    setup_successful_runtime_table_test(vec![cfg], vec![runtime_table], lookups);
+}
+
+#[test]
+fn test_negative_test_runtime_table_with_unique_keys_and_duplicate_keys() {
+    let gates: Vec<_> = (0..20)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+
+    let cfg = RuntimeTableCfg {
+        id: 1,
+        first_column: [8u32, 9, 8, 7, 1].into_iter().map(Into::into).collect(),
+        unique_keys: true,
+    };
+    let cs = ConstraintSystem::<Fp>::create(gates.clone())
+        .runtime(Some(vec![cfg.clone()]))
+        .build();
+    assert!(matches!(
+        cs,
+        Err(SetupError::LookupCreation(
+            LookupError::RuntimeTableDuplicateKeys { id: 1 }
+        ))
+    ));
+
+    // Without the option, a key can be repeated
+    let cfg = RuntimeTableCfg {
+        unique_keys: false,
+        ..cfg
+    };
+    assert!(ConstraintSystem::<Fp>::create(gates)
+        .runtime(Some(vec![cfg]))
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn test_negative_test_runtime_table_with_unique_keys_and_fixed_table_with_same_id() {
+    let gates: Vec<_> = (0..20)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+
+    let lookup_table = LookupTable {
+        id: 1,
+        data: vec![[1u32, 2].into_iter().map(Into::into).collect()],
+    };
+    let cfg = RuntimeTableCfg {
+        id: 1,
+        first_column: [3u32, 4].into_iter().map(Into::into).collect(),
+        unique_keys: true,
+    };
+    let cs = ConstraintSystem::<Fp>::create(gates)
+        .lookup(vec![lookup_table])
+        .runtime(Some(vec![cfg]))
+        .build();
+    assert!(matches!(
+        cs,
+        Err(SetupError::LookupCreation(
+            LookupError::LookupTableIdCollision { .. }
+        ))
+    ));
+}
+
 // This test verifies that if there is a table with ID 0, it contains a row with only zeroes.
 // This is to enforce the constraint we have on the so-called "dummy value".
 // FIXME: see https://github.com/o1-labs/proof-systems/issues/1460
@@ -731,3 +1062,184 @@ fn test_dummy_zero_entry_is_counted_while_computing_domain_size() {
     // As the dummy entry has been added, we reached the next power of two
     assert!(domain_size == (1 << (power_of_2 + 1)));
 }
+
+#[test]
+fn lookup_gate_check_witness() {
+    let seed: [u8; 32] = thread_rng().gen();
+    eprintln!("Seed: {:?}", seed);
+    let mut rng = StdRng::from_seed(seed);
+
+    let (gates, mut witness, lookup_tables) =
+        create_lookup_circuit(&mut rng, true, 100, vec![100, 50]);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(lookup_tables)
+        .build()
+        .unwrap();
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // look up a value at the wrong index of the table
+    witness[4][7] += Fp::from(1u32);
+    assert_eq!(
+        cs.check_witness::<Vesta>(&witness, &[]),
+        Err(WitnessError::ValueNotInTable { row: 7, index: 1 })
+    );
+}
+
+#[test]
+fn test_shared_lookup_tables() {
+    let seed: [u8; 32] = thread_rng().gen();
+    eprintln!("Seed: {:?}", seed);
+
+    // two circuits using the same tables over domains of the same size
+    let circuits: Vec<_> = [100, 120]
+        .into_iter()
+        .map(|num_lookups| {
+            let mut rng = StdRng::from_seed(seed);
+            create_lookup_circuit(&mut rng, true, num_lookups, vec![100, 50])
+        })
+        .collect();
+    let constraint_systems: Vec<_> = circuits
+        .iter()
+        .map(|(gates, _, lookup_tables)| {
+            ConstraintSystem::create(gates.clone())
+                .lookup(lookup_tables.clone())
+                .build()
+                .unwrap()
+        })
+        .collect();
+    let domain = constraint_systems[0].domain.d1;
+    assert_eq!(constraint_systems[1].domain.d1, domain);
+
+    let mut srs = SRS::<Vesta>::create(domain.size());
+    srs.add_lagrange_basis(domain);
+    let srs = Arc::new(srs);
+    let &endo_q = Vesta::other_curve_endo();
+    let indexes: Vec<_> = constraint_systems
+        .into_iter()
+        .map(|cs| ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, srs.clone()))
+        .collect();
+
+    // the commitments to the tables are stored once
+    let mut store = SharedLookupTablesStore::default();
+    let mut verifier_indexes: Vec<_> = indexes
+        .iter()
+        .map(|index| {
+            let mut verifier_index = index.verifier_index();
+            let tables = verifier_index.share_lookup_tables().unwrap();
+            assert!(tables.is_consistent());
+            store.insert(tables);
+            verifier_index
+        })
+        .collect();
+    assert_eq!(store.len(), 1);
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let (_, witness, _) = &circuits[1];
+    let proof = ProverProof::create::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness.clone(),
+        &[],
+        &indexes[1],
+    )
+    .unwrap();
+
+    // the tables must be attached before verifying
+    let verifier_index = &mut verifier_indexes[1];
+    assert!(matches!(
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            verifier_index,
+            &proof,
+            &[],
+        ),
+        Err(VerifyError::MissingSharedLookupTables)
+    ));
+    verifier_index.attach_lookup_tables_from(&store).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+
+    // an index not using shared tables cannot attach them
+    let mut verifier_index = indexes[0].verifier_index();
+    assert_eq!(
+        verifier_index.attach_lookup_tables_from(&store),
+        Err(SharedTablesError::NotShared)
+    );
+}
+
+#[test]
+fn test_lazy_runtime_table() {
+    let table_id = 1;
+    let len = 100;
+    let runtime_tables_setup = vec![RuntimeTableCfg {
+        id: table_id,
+        first_column: (0..len as u64).map(Fp::from).collect(),
+        unique_keys: true,
+    }];
+
+    // The queries look up the squares of their keys
+    let num_rows = 20;
+    let gates: Vec<_> = (0..num_rows)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+    let witness = {
+        let mut cols: [_; COLUMNS] = array::from_fn(|_col| vec![Fp::zero(); num_rows]);
+        for row in 0..num_rows {
+            cols[0][row] = Fp::from(table_id);
+            for (i, chunk) in cols[1..7].chunks_mut(2).enumerate() {
+                let key = Fp::from((7 * row + i) as u64 % len as u64);
+                chunk[0][row] = key;
+                chunk[1][row] = key.square();
+            }
+        }
+        cols
+    };
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness.clone())
+        .runtime_tables_setup(runtime_tables_setup)
+        .setup();
+    let index = runner.prover_index();
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+
+    // The table is only produced during proof creation
+    let lazy_table = || {
+        LazyRuntimeTable::new(table_id, len, |context, data| {
+            assert_eq!(context.id, table_id);
+            assert_eq!(context.witness[0][0], Fp::from(table_id));
+            for (key, value) in data.iter_mut().enumerate() {
+                *value = Fp::from(key as u64).square();
+            }
+        })
+    };
+    let proof = ProverProof::create_with_lazy_runtime_tables::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness.clone(),
+        vec![lazy_table()],
+        index,
+        vec![],
+    )
+    .unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        &proof,
+        &[],
+    )
+    .unwrap();
+
+    // The length of the table must match its configuration
+    let res = ProverProof::create_with_lazy_runtime_tables::<BaseSponge, ScalarSponge>(
+        &group_map,
+        witness,
+        vec![LazyRuntimeTable::new(table_id, len - 1, |_, _| ())],
+        index,
+        vec![],
+    );
+    assert!(matches!(res, Err(ProverError::RuntimeTablesInconsistent)));
+}
@@ -0,0 +1,60 @@
+use super::framework::TestFramework;
+use crate::{
+    circuits::{gate::CircuitGate, polynomials::range_check},
+    proof::ProverProof,
+    prover_index::MemoryMode,
+    verifier::verify,
+};
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+#[test]
+fn test_memory_budget_gives_the_same_proof() {
+    let (_, gates) = CircuitGate::<Fp>::create_multi_range_check(0);
+    let witness = range_check::witness::create_multi::<Fp>(
+        Fp::from(1u64 << 60),
+        Fp::from(12345u64),
+        Fp::from((1u64 << 63) + 7),
+    );
+
+    let runner = TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness.clone())
+        .setup();
+    let mut index = runner.prover_index().clone();
+    assert_eq!(index.memory_mode, MemoryMode::Speed);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+
+    let mut proofs = vec![];
+    for memory_mode in [MemoryMode::Speed, MemoryMode::Budget] {
+        index.memory_mode = memory_mode;
+        let proof = ProverProof::prove_with_rng_seed::<BaseSponge, ScalarSponge>(
+            &group_map,
+            witness.clone(),
+            &[],
+            &index,
+            vec![],
+            None,
+            [3; 32],
+        )
+        .unwrap();
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &index.verifier_index(),
+            &proof,
+            &[],
+        )
+        .unwrap();
+        proofs.push(rmp_serde::to_vec(&proof).unwrap());
+    }
+    assert_eq!(proofs[0], proofs[1]);
+}
@@ -1,20 +1,49 @@
+mod aggregation;
 mod and;
+mod bus;
+mod chacha;
 mod chunked;
+mod committed_column;
+mod compatibility;
+mod custom_gate;
+mod decomposition;
+mod dry_run;
 mod ec;
 mod endomul;
 mod endomul_scalar;
+mod export;
+mod fixed_base_mul;
+mod fixed_table;
 mod foreign_field_add;
+mod foreign_field_ecdsa;
 mod foreign_field_mul;
+mod foreign_field_scalar_mul;
 mod framework;
+mod gadget_harness;
+mod gate_info;
 mod generic;
+mod goldilocks;
+mod index_digest;
 mod keccak;
 mod lookup;
+mod memory_mode;
 mod not;
+mod opening_order;
 mod poseidon;
+mod public_input;
 mod range_check;
 mod recursion;
+mod report;
 mod rot;
+mod schnorr;
+mod select;
+mod selectors;
 mod serde;
+mod split;
+mod transcript;
 mod turshi;
+mod uint;
 mod varbasemul;
+mod vector_lookup;
+mod witness_tracer;
 mod xor;
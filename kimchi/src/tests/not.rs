@@ -448,3 +448,46 @@ fn test_bad_not_xor() {
         ))
     );
 }
+
+// Proves a NOT of exact length using XOR of the given input, which can be longer than
+// `bits` as long as it fits in the Xor16 rows of the gadget
+fn prove_not_xor_exact_length(input: PallasField, bits: usize) -> Result<(), String> {
+    let gates = {
+        let mut gates = vec![CircuitGate::<Fp>::create_generic_gadget(
+            Wire::for_row(0),
+            GenericGateSpec::Pub,
+            None,
+        )];
+        let _next_row = CircuitGate::<Fp>::extend_not_gadget_exact_length(&mut gates, 0, bits);
+        gates
+    };
+    let all_ones = all_ones::<Vesta>(bits);
+    let mut witness: [Vec<PallasField>; COLUMNS] = array::from_fn(|_| vec![PallasField::zero()]);
+    witness[0][0] = all_ones;
+    xor::extend_xor_witness(&mut witness, input, all_ones, 16 * xor::num_xors(bits));
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .public_inputs(vec![all_ones])
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+}
+
+#[test]
+// End-to-end test of NOT of exact length using XOR gadget for several lengths
+fn test_prove_and_verify_not_xor_exact_length() {
+    let rng = &mut o1_utils::tests::make_test_rng(None);
+    for bits in [8, 16, 32, 44, 128] {
+        prove_not_xor_exact_length(rng.gen_field_with_bits(bits), bits).unwrap();
+    }
+}
+
+#[test]
+// Inputs longer than the length of a NOT of exact length are rejected, even when they
+// fit in the Xor16 rows
+fn test_not_xor_exact_length_too_long_input() {
+    let input = PallasField::from(0x1A5u16);
+    assert!(prove_not_xor_exact_length(input, 12).is_ok());
+    assert!(prove_not_xor_exact_length(input, 8).is_err());
+}
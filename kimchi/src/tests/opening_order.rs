@@ -0,0 +1,115 @@
+use crate::{
+    circuits::{
+        berkeley_columns::Column,
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        lookup::{constraints::LookupArgument, lookups::LookupPattern, tables::LookupTable},
+        wires::Wire,
+    },
+    curve::KimchiCurve,
+    opening_order::{opening_order_digest, OpenedPolynomial, OPENING_ORDER_VERSION},
+    prover_index::{testing::new_index_for_test, ProverIndex},
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::{evaluation_proof::OpeningProof, srs::SRS};
+use std::sync::Arc;
+
+// The digests pin the order in which the polynomials are opened. If one of
+// these tests fails, the order has changed and external verifiers will break:
+// either revert the change, or bump `OPENING_ORDER_VERSION` and update the
+// digests, documenting the new order.
+
+fn lookup_index(argument: LookupArgument) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let gates = (0..4)
+        .map(|row| CircuitGate::new(GateType::Lookup, Wire::for_row(row), vec![]))
+        .collect();
+    let table = LookupTable {
+        id: 0,
+        data: vec![vec![Fp::zero(); 2], vec![Fp::zero(); 2]],
+    };
+    let cs = ConstraintSystem::create(gates)
+        .lookup(vec![table])
+        .lookup_argument(argument)
+        .build()
+        .unwrap();
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_opening_order_version() {
+    assert_eq!(OPENING_ORDER_VERSION, 1);
+}
+
+#[test]
+fn test_opening_order_without_lookup() {
+    let gates = vec![CircuitGate::<Fp>::zero(Wire::for_row(0)); 2];
+    let index = new_index_for_test::<Vesta>(gates, 0);
+    let verifier_index = index.verifier_index();
+    let order = verifier_index.opening_order();
+    // the prover opens the polynomials in the same order
+    assert_eq!(index.opening_order(), order);
+
+    assert_eq!(
+        order[..4],
+        [
+            OpenedPolynomial::Public,
+            OpenedPolynomial::Ft,
+            OpenedPolynomial::Column(Column::Z),
+            OpenedPolynomial::Column(Column::Index(GateType::Generic)),
+        ]
+    );
+    assert_eq!(order.len(), 3 + 6 + 15 + 15 + 6);
+    assert_eq!(
+        verifier_index.opening_order_digest(),
+        "1af27098bc72a08bda5964ef6a35a5b5063d6e3c3dd3510b2d397d49965d1bb3d6c739badef85394cf8fa5a6a27fe3869f1aff5425a10c951bb87fe99cd8fc66"
+    );
+}
+
+#[test]
+fn test_opening_order_with_lookup() {
+    let index = lookup_index(LookupArgument::Plookup);
+    let verifier_index = index.verifier_index();
+    let order = verifier_index.opening_order();
+    assert_eq!(index.opening_order(), order);
+    assert_eq!(
+        order[order.len() - 7..],
+        [
+            OpenedPolynomial::Column(Column::LookupSorted(0)),
+            OpenedPolynomial::Column(Column::LookupSorted(1)),
+            OpenedPolynomial::Column(Column::LookupSorted(2)),
+            OpenedPolynomial::Column(Column::LookupSorted(3)),
+            OpenedPolynomial::Column(Column::LookupAggreg),
+            OpenedPolynomial::Column(Column::LookupTable),
+            OpenedPolynomial::Column(Column::LookupKindIndex(LookupPattern::Lookup)),
+        ]
+    );
+    assert_eq!(
+        opening_order_digest(&order),
+        "ac008a6328c1479cd4da5673de026be6a6ca1200be672dfbb85907830ce34a03a94626af73e7e6e11352e38cc1001a2a7a2d85104d51e5aa81cb32c8873e3fcb"
+    );
+
+    // With logUp, only the multiplicities replace the sorted polynomials
+    let index = lookup_index(LookupArgument::LogUp);
+    let verifier_index = index.verifier_index();
+    let order = verifier_index.opening_order();
+    assert_eq!(index.opening_order(), order);
+    assert_eq!(
+        order[order.len() - 4..],
+        [
+            OpenedPolynomial::Column(Column::LookupSorted(0)),
+            OpenedPolynomial::Column(Column::LookupAggreg),
+            OpenedPolynomial::Column(Column::LookupTable),
+            OpenedPolynomial::Column(Column::LookupKindIndex(LookupPattern::Lookup)),
+        ]
+    );
+    assert_eq!(
+        opening_order_digest(&order),
+        "c667f3e598ced0ab0a302bd3beb0df63d29d3017a4e351aecb89ebfc2e6d768b8f1addad93747ab28137c6770abf32b4169a8e854d9f69281ce33874e92fac1c"
+    );
+}
@@ -1,5 +1,6 @@
 use crate::{
     circuits::{
+        constraints::ConstraintSystem,
         gate::CircuitGate,
         polynomials,
         polynomials::poseidon::ROUNDS_PER_ROW,
@@ -12,6 +13,7 @@ use ark_ff::Zero;
 use mina_curves::pasta::{Fp, Vesta, VestaParameters};
 use mina_poseidon::{
     constants::{PlonkSpongeConstantsKimchi, SpongeConstants},
+    permutation::poseidon_block_cipher,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
 use o1_utils::math;
@@ -88,3 +90,149 @@ fn test_poseidon() {
         .prove_and_verify::<BaseSponge, ScalarSponge>()
         .unwrap();
 }
+
+#[test]
+fn test_poseidon_witness_batch() {
+    let num_hashes = 100;
+    let rows = num_hashes * (POS_ROWS_PER_HASH + 1);
+    let hashes: Vec<_> = (0..num_hashes)
+        .map(|h| {
+            let input = array::from_fn(|i| Fp::from((SpongeParams::SPONGE_WIDTH * h + i) as u64));
+            (h * (POS_ROWS_PER_HASH + 1), input)
+        })
+        .collect();
+
+    let mut expected: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    for (row, input) in hashes.iter() {
+        polynomials::poseidon::generate_witness(
+            *row,
+            Vesta::sponge_params(),
+            &mut expected,
+            *input,
+        );
+    }
+
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    polynomials::poseidon::generate_witness_batch(Vesta::sponge_params(), &mut witness, &hashes);
+    assert_eq!(witness, expected);
+
+    // the gadgets of the hashes follow each other, each one ending with a zero gate
+    let round_constants = &*Vesta::sponge_params().round_constants;
+    let gates: Vec<CircuitGate<Fp>> = hashes
+        .iter()
+        .flat_map(|(row, _)| {
+            let last_row = row + POS_ROWS_PER_HASH;
+            CircuitGate::<Fp>::create_poseidon_gadget(
+                *row,
+                [Wire::for_row(*row), Wire::for_row(last_row)],
+                round_constants,
+            )
+            .0
+        })
+        .collect();
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
+
+fn create_chain(absorbed: &[[Fp; 3]]) -> Vec<CircuitGate<Fp>> {
+    let round_constants = &*Vesta::sponge_params().round_constants;
+    let last_row = POS_ROWS_PER_HASH * (absorbed.len() + 1);
+    let (gates, row) = CircuitGate::<Fp>::create_poseidon_chain(
+        0,
+        [Wire::for_row(0), Wire::for_row(last_row)],
+        round_constants,
+        absorbed,
+    );
+    assert_eq!(row, last_row);
+    gates
+}
+
+fn chain_absorbed(num_permutations: usize) -> Vec<[Fp; 3]> {
+    (1..num_permutations)
+        .map(|k| array::from_fn(|i| Fp::from((10 * k + i) as u64)))
+        .collect()
+}
+
+#[test]
+fn test_poseidon_chain() {
+    let absorbed = chain_absorbed(4);
+    let gates = create_chain(&absorbed);
+    // the permutations share their boundary rows
+    assert_eq!(gates.len(), 4 * POS_ROWS_PER_HASH + 1);
+
+    let input = [Fp::from(1u32), Fp::from(2u32), Fp::from(3u32)];
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    let output = polynomials::poseidon::generate_chain_witness(
+        0,
+        Vesta::sponge_params(),
+        &mut witness,
+        input,
+        &absorbed,
+    );
+
+    // the same permutations, with the constants added in between
+    let mut state = input.to_vec();
+    for k in 0..4 {
+        if k > 0 {
+            for (s, a) in state.iter_mut().zip(absorbed[k - 1]) {
+                *s += a;
+            }
+            // the state given to the permutation is on its first row
+            let row = k * POS_ROWS_PER_HASH;
+            assert_eq!(state, (0..3).map(|i| witness[i][row]).collect::<Vec<_>>());
+        }
+        poseidon_block_cipher::<Fp, SpongeParams>(Vesta::sponge_params(), &mut state);
+    }
+    assert_eq!(output.to_vec(), state);
+    assert_eq!(
+        (0..3)
+            .map(|i| witness[i][gates.len() - 1])
+            .collect::<Vec<_>>(),
+        state
+    );
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
+
+#[test]
+fn test_poseidon_chain_wrong_absorption() {
+    let absorbed = chain_absorbed(3);
+    let cs = ConstraintSystem::create(create_chain(&absorbed))
+        .build()
+        .unwrap();
+
+    let input = [Fp::from(4u32), Fp::from(5u32), Fp::from(6u32)];
+    let rows = 3 * POS_ROWS_PER_HASH + 1;
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    polynomials::poseidon::generate_chain_witness(
+        0,
+        Vesta::sponge_params(),
+        &mut witness,
+        input,
+        &absorbed,
+    );
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // the witness of a chain absorbing other constants
+    let mut other_absorbed = absorbed;
+    other_absorbed[1][0] += Fp::from(1u32);
+    let mut bad_witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    polynomials::poseidon::generate_chain_witness(
+        0,
+        Vesta::sponge_params(),
+        &mut bad_witness,
+        input,
+        &other_absorbed,
+    );
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+}
@@ -0,0 +1,266 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        polynomials::{
+            foreign_field_common::{BINARY_MODULUS_EXP, LIMB_BITS, LIMB_COUNT},
+            generic::testing::{create_circuit, fill_in_witness},
+        },
+        public_input::{
+            PublicInputEncoding, PublicInputError, PublicInputLayout, PublicInputValue,
+        },
+        wires::COLUMNS,
+    },
+    curve::KimchiCurve,
+    error::{ProverError, VerifyError},
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use num_bigint::BigUint;
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+fn layout() -> PublicInputLayout {
+    PublicInputLayout::new()
+        .field("root", PublicInputEncoding::Field)
+        .array("balances", PublicInputEncoding::U64, 3)
+        .field("is_final", PublicInputEncoding::Bool)
+}
+
+#[test]
+fn test_public_input_foreign_field() {
+    let layout = PublicInputLayout::new()
+        .field("flag", PublicInputEncoding::Bool)
+        .array("points", PublicInputEncoding::ForeignField, 2)
+        .field("count", PublicInputEncoding::U64);
+    assert_eq!(layout.len(), 1 + 2 * LIMB_COUNT + 1);
+    assert_eq!(layout.offset("count"), Some(1 + 2 * LIMB_COUNT));
+
+    let x: BigUint = BigUint::from(1u8) << 200;
+    let y = (BigUint::from(1u8) << BINARY_MODULUS_EXP) - BigUint::from(1u8);
+    let points = [
+        PublicInputValue::ForeignField(x.clone()),
+        PublicInputValue::ForeignField(y),
+    ];
+    let public: Vec<Fp> = layout
+        .encoder()
+        .set("flag", PublicInputValue::Bool(false))
+        .set_array("points", &points)
+        .set("count", PublicInputValue::U64(2))
+        .encode()
+        .unwrap();
+    // the limbs are in little-endian order
+    assert_eq!(
+        public[1..4],
+        [Fp::zero(), Fp::zero(), Fp::from(1u128 << 24)]
+    );
+    assert_eq!(layout.get(&public, "points").unwrap(), points);
+    layout.check(&public).unwrap();
+
+    // the foreign field elements and their limbs are range checked
+    let too_large = layout
+        .encoder::<Fp>()
+        .set_array(
+            "points",
+            &[
+                PublicInputValue::ForeignField(x.clone()),
+                PublicInputValue::ForeignField(x << 64),
+            ],
+        )
+        .encode();
+    assert_eq!(
+        too_large,
+        Err(PublicInputError::InvalidValue("points".to_string()))
+    );
+    let mut bad_public = public.clone();
+    bad_public[5] = Fp::from(1u128 << LIMB_BITS);
+    assert_eq!(
+        layout.check(&bad_public),
+        Err(PublicInputError::InvalidValue("points".to_string()))
+    );
+    assert_eq!(layout.first_invalid(&bad_public), Some(4));
+}
+
+#[test]
+fn test_public_input_layout() {
+    let layout = layout();
+    assert_eq!(layout.len(), 5);
+    assert_eq!(layout.offset("balances"), Some(1));
+    assert_eq!(layout.offset("is_final"), Some(4));
+
+    let balances = [7u64, u64::MAX, 0].map(PublicInputValue::U64);
+    let public: Vec<Fp> = layout
+        .encoder()
+        .set("is_final", PublicInputValue::Bool(true))
+        .set("root", PublicInputValue::Field(Fp::from(42u8)))
+        .set_array("balances", &balances)
+        .encode()
+        .unwrap();
+    assert_eq!(public, [42u64, 7, u64::MAX, 0, 1].map(Fp::from).to_vec());
+
+    let decoded = layout.decode(&public).unwrap();
+    assert_eq!(decoded["root"], [PublicInputValue::Field(Fp::from(42u8))]);
+    assert_eq!(decoded["balances"], balances);
+    assert_eq!(
+        layout.get(&public, "is_final").unwrap(),
+        [PublicInputValue::Bool(true)]
+    );
+
+    // errors when encoding
+    let missing = layout
+        .encoder::<Fp>()
+        .set("root", PublicInputValue::Field(Fp::zero()))
+        .encode();
+    assert_eq!(
+        missing,
+        Err(PublicInputError::MissingField("balances".to_string()))
+    );
+    let wrong_length = layout
+        .encoder::<Fp>()
+        .set("balances", PublicInputValue::U64(1))
+        .encode();
+    assert_eq!(
+        wrong_length,
+        Err(PublicInputError::FieldLength {
+            name: "balances".to_string(),
+            expected: 3,
+            found: 1
+        })
+    );
+    let wrong_encoding = layout
+        .encoder::<Fp>()
+        .set("is_final", PublicInputValue::U64(1))
+        .encode();
+    assert_eq!(
+        wrong_encoding,
+        Err(PublicInputError::InvalidValue("is_final".to_string()))
+    );
+    let unknown = layout
+        .encoder::<Fp>()
+        .set("leaf", PublicInputValue::Field(Fp::zero()))
+        .encode();
+    assert_eq!(
+        unknown,
+        Err(PublicInputError::UnknownField("leaf".to_string()))
+    );
+
+    // errors when decoding
+    assert_eq!(
+        layout.decode(&public[1..]),
+        Err(PublicInputError::Length {
+            expected: 5,
+            found: 4
+        })
+    );
+    let mut bad_public = public.clone();
+    bad_public[4] = Fp::from(2u8);
+    assert_eq!(
+        layout.get(&bad_public, "is_final"),
+        Err(PublicInputError::InvalidValue("is_final".to_string()))
+    );
+    bad_public[2] = Fp::from(u64::MAX) + Fp::from(1u8);
+    assert_eq!(
+        layout.get(&bad_public, "balances"),
+        Err(PublicInputError::InvalidValue("balances".to_string()))
+    );
+}
+
+#[test]
+fn test_public_input_layout_in_index() {
+    let layout = layout();
+    let gates = create_circuit(0, layout.len());
+    let cs = ConstraintSystem::create(gates.clone())
+        .public_input_layout(layout.clone())
+        .build()
+        .unwrap();
+    assert_eq!(cs.public, 5);
+
+    let public = cs
+        .public_input_encoder()
+        .unwrap()
+        .set("root", PublicInputValue::Field(Fp::from(3u8)))
+        .set_array("balances", &vec![PublicInputValue::U64(3); 3])
+        .set("is_final", PublicInputValue::Bool(false))
+        .encode()
+        .unwrap();
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut witness, &public);
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    let index = ProverIndex::<Vesta, OpeningProof<Vesta>>::create(cs, endo_q, Arc::new(srs));
+    let verifier_index = index.verifier_index();
+    assert_eq!(verifier_index.public_input_layout, Some(layout.clone()));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &public,
+    )
+    .unwrap();
+    assert_eq!(
+        verifier_index.public_input(&public, "balances").unwrap(),
+        vec![PublicInputValue::U64(3); 3]
+    );
+
+    // the layout is kept by the serialization of the verifier index
+    let serialized = rmp_serde::to_vec(&verifier_index).unwrap();
+    let deserialized: crate::verifier_index::VerifierIndex<Vesta, OpeningProof<Vesta>> =
+        rmp_serde::from_slice(&serialized).unwrap();
+    assert_eq!(
+        deserialized.public_input_layout,
+        verifier_index.public_input_layout
+    );
+
+    // public inputs which do not match the layout are rejected
+    let mut bad_public = public.clone();
+    bad_public[4] = Fp::from(2u8);
+    assert!(matches!(
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &verifier_index,
+            &proof,
+            &bad_public,
+        ),
+        Err(VerifyError::InvalidPublicInput(4))
+    ));
+    assert!(matches!(
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &verifier_index,
+            &proof,
+            &public[1..],
+        ),
+        Err(VerifyError::IncorrectPubicInputLength(5))
+    ));
+    let mut bad_witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); gates.len()]);
+    fill_in_witness(0, &mut bad_witness, &bad_public);
+    assert!(matches!(
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, bad_witness, &[], &index),
+        Err(ProverError::InvalidPublicInput(4))
+    ));
+
+    // the layout must describe all the public inputs
+    assert!(ConstraintSystem::create(gates)
+        .public_input_layout(layout)
+        .public(4)
+        .build()
+        .is_err());
+}
@@ -23,6 +23,7 @@ use ark_poly::EvaluationDomain;
 use mina_curves::pasta::{Fp, Pallas, Vesta, VestaParameters};
 use num_bigint::{BigUint, RandBigInt};
 use o1_utils::{foreign_field::ForeignFieldHelpers, FieldHelpers};
+use proptest::strategy::Strategy;
 
 use std::{array, sync::Arc};
 
@@ -38,7 +39,10 @@ use poly_commitment::{
     srs::{endos, SRS},
 };
 
-use super::framework::TestFramework;
+use super::{
+    framework::TestFramework,
+    gadget_harness::{arb_edge_bits, GadgetHarness},
+};
 
 type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
 type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
@@ -1235,3 +1239,21 @@ fn verify_compact_multi_range_check_proof() {
         .prove_and_verify::<BaseSponge, ScalarSponge>()
         .unwrap();
 }
+
+#[test]
+fn test_multi_range_check_properties() {
+    let (_, gates) = CircuitGate::<Fp>::create_multi_range_check(0);
+    GadgetHarness::<Vesta, [Fp; 3]>::new(gates, |&[v0, v1, v2]| {
+        range_check::witness::create_multi(v0, v1, v2)
+    })
+    // the decompositions of v0 and v1, and v2
+    .constrained_cells(
+        (0..2)
+            .flat_map(|row| (0..COLUMNS).map(move |col| Wire::new(row, col)))
+            .chain([Wire::new(2, 0)]),
+    )
+    .run(
+        [arb_edge_bits(88), arb_edge_bits(88), arb_edge_bits(88)]
+            .prop_map(|limbs| limbs.map(|limb| Fp::from_biguint(&limb).unwrap())),
+    );
+}
@@ -0,0 +1,70 @@
+use super::framework::TestFramework;
+use crate::{
+    circuits::{
+        gate::{CircuitGate, GateType},
+        lookup::tables::RANGE_CHECK_TABLE_ID,
+        polynomials::generic::GenericGateSpec,
+        wires::Wire,
+    },
+    curve::KimchiCurve,
+    prover_index::ProverIndex,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::{evaluation_proof::OpeningProof, srs::SRS};
+use std::sync::Arc;
+
+#[test]
+fn test_report_of_range_check_circuit() {
+    let mut gates = vec![CircuitGate::<Fp>::create_generic_gadget(
+        Wire::for_row(0),
+        GenericGateSpec::Const(Fp::zero()),
+        None,
+    )];
+    CircuitGate::extend_multi_range_check(&mut gates, &mut 1);
+
+    let runner = TestFramework::<Vesta>::default().gates(gates).setup();
+    let index = runner.prover_index();
+    let report = index.cs.report::<Vesta>();
+
+    // the rows of each gate type
+    assert_eq!(report.rows, 5);
+    assert_eq!(report.public, 0);
+    assert_eq!(report.domain_size, index.cs.domain.d1.size());
+    assert_eq!(report.rows_of(GateType::Generic), 1);
+    assert_eq!(report.rows_of(GateType::RangeCheck0), 2);
+    assert_eq!(report.rows_of(GateType::RangeCheck1), 1);
+    assert_eq!(report.rows_of(GateType::Zero), 1);
+    assert_eq!(report.rows_of(GateType::Poseidon), 0);
+    assert_eq!(
+        report.gates.iter().map(|count| count.rows).sum::<usize>(),
+        report.rows
+    );
+
+    // the 12-bit table of the range check
+    let table = report
+        .lookup_tables
+        .iter()
+        .find(|table| table.id == RANGE_CHECK_TABLE_ID)
+        .unwrap();
+    assert_eq!(table.length, 1 << 12);
+    assert!(!table.runtime);
+    assert!(report.lookup_entries() < report.domain_size);
+    assert_eq!(report.lookup_table_width, 1);
+
+    // the degree of the range check gates
+    assert_eq!(report.max_degree, GateType::RangeCheck0.spec::<Fp>().degree);
+
+    // the proof is the one of the prover index, with an SRS of the domain size
+    let srs = SRS::<Vesta>::create(report.domain_size);
+    let &endo_q = Vesta::other_curve_endo();
+    let sized_index =
+        ProverIndex::<Vesta, OpeningProof<Vesta>>::create(index.cs.clone(), endo_q, Arc::new(srs));
+    assert_eq!(report.proof, sized_index.dry_run());
+    assert!(report.prover_memory > report.domain_size * 32 * 8 * 2);
+
+    let table = report.to_string();
+    assert!(table.contains("RangeCheck0"));
+    assert!(table.contains(&format!("proof: {} bytes", report.proof.size_in_bytes())));
+}
@@ -1,6 +1,9 @@
 use std::{array, sync::Arc};
 
-use super::framework::TestFramework;
+use super::{
+    framework::TestFramework,
+    gadget_harness::{arb_edge_bits, GadgetHarness},
+};
 use crate::{
     circuits::{
         constraints::ConstraintSystem,
@@ -31,6 +34,7 @@ use poly_commitment::{
     evaluation_proof::OpeningProof,
     srs::{endos, SRS},
 };
+use proptest::strategy::Strategy;
 use rand::Rng;
 
 type PallasField = <Pallas as AffineCurve>::BaseField;
@@ -440,3 +444,19 @@ fn test_keccak_table() {
         }
     }
 }
+
+#[test]
+fn test_rot64_properties() {
+    for (rot, side) in [
+        (1, RotMode::Left),
+        (32, RotMode::Right),
+        (63, RotMode::Left),
+    ] {
+        GadgetHarness::<Vesta, u64>::new(create_rot_gadget::<Vesta>(rot, side), move |&word| {
+            create_rot_witness::<Vesta>(word, rot, side)
+        })
+        // the rotation and its two range checks, the first row is the zero value
+        .constrained_cells((1..=3).flat_map(|row| (0..COLUMNS).map(move |col| Wire::new(row, col))))
+        .run(arb_edge_bits(64).prop_map(|word| u64::try_from(word).unwrap()));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomials::schnorr::{
+            gadget::{SchnorrCells, SchnorrParams},
+            witness::{self, Signature},
+        },
+    },
+    tests::framework::TestFramework,
+};
+use ark_ff::{One, UniformRand};
+use mina_curves::pasta::{Fp, Fq, Vesta, VestaParameters};
+use mina_hasher::{Hashable, ROInput};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use mina_signer::{Keypair, NetworkId, PubKey, SecKey, Signer};
+use rand::{rngs::StdRng, SeedableRng};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const TESTNET_DOMAIN: &str = "CodaSignature";
+const MAINNET_DOMAIN: &str = "MinaSignatureMainnet";
+const TABLE_ID: i32 = 7;
+
+// A message of field elements, signed by Mina's signer
+#[derive(Clone)]
+struct FieldMessage(Vec<Fp>);
+
+impl Hashable for FieldMessage {
+    type D = NetworkId;
+
+    fn to_roinput(&self) -> ROInput {
+        self.0
+            .iter()
+            .fold(ROInput::new(), |input, &x| input.append_field(x))
+    }
+
+    fn domain_string(network_id: NetworkId) -> Option<String> {
+        match network_id {
+            NetworkId::TESTNET => Some(TESTNET_DOMAIN.to_string()),
+            NetworkId::MAINNET => Some(MAINNET_DOMAIN.to_string()),
+        }
+    }
+}
+
+fn create_circuit(params: &SchnorrParams) -> (Vec<CircuitGate<Fp>>, SchnorrCells) {
+    let mut gates = vec![];
+    let mut next_row = 0;
+    let cells = CircuitGate::extend_schnorr_verification(&mut gates, &mut next_row, params);
+    assert_eq!(gates.len(), params.num_rows());
+    (gates, cells)
+}
+
+fn random_message(rng: &mut StdRng, len: usize) -> Vec<Fp> {
+    (0..len).map(|_| Fp::rand(rng)).collect()
+}
+
+#[test]
+fn test_schnorr_sign_and_verify() {
+    let rng = &mut StdRng::from_seed([0; 32]);
+    let params = SchnorrParams::new(TESTNET_DOMAIN, 3, TABLE_ID);
+    let secret = Fq::rand(rng);
+    let public_key = witness::public_key(secret);
+    let message = random_message(rng, 3);
+
+    let signature = Signature::sign(&params, secret, &message);
+    assert!(signature.verify(&params, public_key, &message));
+    // the signature is deterministic
+    assert_eq!(Signature::sign(&params, secret, &message), signature);
+
+    // a wrong message, public key, signature or domain
+    let mut wrong_message = message.clone();
+    wrong_message[2] += Fp::one();
+    assert!(!signature.verify(&params, public_key, &wrong_message));
+    let wrong_key = witness::public_key(secret + Fq::one());
+    assert!(!signature.verify(&params, wrong_key, &message));
+    let wrong_signature = Signature {
+        s: signature.s + Fq::one(),
+        ..signature
+    };
+    assert!(!wrong_signature.verify(&params, public_key, &message));
+    let mainnet = SchnorrParams::new(MAINNET_DOMAIN, 3, TABLE_ID);
+    assert!(!signature.verify(&mainnet, public_key, &message));
+}
+
+#[test]
+fn test_schnorr_mina_signer_compatibility() {
+    let rng = &mut StdRng::from_seed([1; 32]);
+    for (network_id, domain) in [
+        (NetworkId::TESTNET, TESTNET_DOMAIN),
+        (NetworkId::MAINNET, MAINNET_DOMAIN),
+    ] {
+        let params = SchnorrParams::new(domain, 2, TABLE_ID);
+        let secret = Fq::rand(rng);
+        let keypair = Keypair::from_secret_key(SecKey::new(secret)).unwrap();
+        let public_key = witness::public_key(secret);
+        assert_eq!(*keypair.public.point(), public_key);
+        let message = random_message(rng, 2);
+        let mut signer = mina_signer::create_kimchi::<FieldMessage>(network_id);
+
+        // the signatures of Mina's signer are valid
+        let mina_signature = signer.sign(&keypair, &FieldMessage(message.clone()));
+        let signature = Signature {
+            rx: mina_signature.rx,
+            s: mina_signature.s,
+        };
+        assert!(signature.verify(&params, public_key, &message));
+
+        // and conversely
+        let signature = Signature::sign(&params, secret, &message);
+        let mina_signature = mina_signer::Signature::new(signature.rx, signature.s);
+        assert!(signer.verify(
+            &mina_signature,
+            &PubKey::from_point_unsafe(public_key),
+            &FieldMessage(message)
+        ));
+    }
+}
+
+#[test]
+fn test_schnorr_circuit() {
+    let rng = &mut StdRng::from_seed([2; 32]);
+    let params = SchnorrParams::new(TESTNET_DOMAIN, 3, TABLE_ID);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let secret = Fq::rand(rng);
+        let public_key = witness::public_key(secret);
+        let message = random_message(rng, 3);
+        let signature = Signature::sign(&params, secret, &message);
+        let witness = witness::create(&params, public_key, &message, &signature);
+
+        // the cells of the gadget hold the inputs and the hash
+        let cell = |(row, col): (usize, usize)| witness[col][row];
+        assert_eq!(
+            cells.message.iter().map(|&c| cell(c)).collect::<Vec<_>>(),
+            message
+        );
+        assert_eq!(cells.public_key.map(cell), [public_key.x, public_key.y]);
+        assert_eq!(cell(cells.rx), signature.rx);
+        assert_eq!(
+            cell(cells.hash),
+            witness::message_hash(&params, public_key, signature.rx, &message)
+        );
+
+        assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+    }
+}
+
+#[test]
+fn test_schnorr_circuit_invalid_witness() {
+    let rng = &mut StdRng::from_seed([3; 32]);
+    let params = SchnorrParams::new(TESTNET_DOMAIN, 1, TABLE_ID);
+    let (gates, cells) = create_circuit(&params);
+    let cs = ConstraintSystem::create(gates)
+        .lookup(params.lookup_tables())
+        .build()
+        .unwrap();
+
+    let secret = Fq::rand(rng);
+    let public_key = witness::public_key(secret);
+    let message = random_message(rng, 1);
+    let signature = Signature::sign(&params, secret, &message);
+    let witness = witness::create(&params, public_key, &message, &signature);
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // a different message
+    let mut bad_witness = witness.clone();
+    let (row, col) = cells.message[0];
+    bad_witness[col][row] += Fp::one();
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+
+    // a different public key, with the witness of its own valid signature
+    let other_secret = secret + Fq::one();
+    let other_key = witness::public_key(other_secret);
+    let other_signature = Signature::sign(&params, other_secret, &message);
+    let mut bad_witness = witness::create(&params, other_key, &message, &other_signature);
+    for (&(row, col), x) in cells.public_key.iter().zip([public_key.x, public_key.y]) {
+        bad_witness[col][row] = x;
+    }
+    assert!(cs.check_witness::<Vesta>(&bad_witness, &[]).is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_schnorr_invalid_signature() {
+    let rng = &mut StdRng::from_seed([4; 32]);
+    let params = SchnorrParams::new(TESTNET_DOMAIN, 2, TABLE_ID);
+    let secret = Fq::rand(rng);
+    let message = random_message(rng, 2);
+    let signature = Signature::sign(&params, secret, &message);
+    let wrong_key = witness::public_key(secret + Fq::one());
+    witness::create(&params, wrong_key, &message, &signature);
+}
+
+#[test]
+fn test_schnorr_rows() {
+    // 8 + 3 * 3 / 2 generic rows, 3 permutations, 2 scalar multiplications, 4 additions
+    // and the decomposition
+    let params = SchnorrParams::new(TESTNET_DOMAIN, 2, TABLE_ID);
+    assert_eq!(params.num_permutations(), 3);
+    let (gates, _) = create_circuit(&params);
+    assert_eq!(gates.len(), 13 + 3 * 12 + 2 * 102 + 4 + 20);
+}
+
+#[test]
+#[should_panic]
+fn test_schnorr_domain_too_long() {
+    SchnorrParams::new("ADomainStringTooLongForMina", 1, TABLE_ID);
+}
+
+#[test]
+fn test_schnorr_prove_and_verify() {
+    let rng = &mut StdRng::from_seed([5; 32]);
+    let params = SchnorrParams::new(MAINNET_DOMAIN, 2, TABLE_ID);
+    let (gates, _) = create_circuit(&params);
+    let secret = Fq::rand(rng);
+    let message = random_message(rng, 2);
+    let signature = Signature::sign(&params, secret, &message);
+    let witness = witness::create(&params, witness::public_key(secret), &message, &signature);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .lookup_tables(params.lookup_tables())
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
@@ -0,0 +1,170 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomial::COLUMNS,
+        polynomials::select::{
+            cond_select, create_multiplexer_witness, extend_cond_select_witness,
+            extend_lookup_multiplexer_witness, multiplexer_table,
+        },
+    },
+    error::WitnessError,
+};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use std::array;
+
+use super::framework::TestFramework;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type VestaBaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type VestaScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const TABLE_ID: i32 = 7;
+
+fn inputs(n: usize) -> Vec<Fp> {
+    (0..n as u64).map(|i| Fp::from(100 + 3 * i)).collect()
+}
+
+#[test]
+fn test_cond_select() {
+    let (x, y) = (Fp::from(5u32), Fp::from(9u32));
+    for b in [false, true] {
+        let mut gates = vec![];
+        let (next_row, cells) = CircuitGate::<Fp>::extend_cond_select(&mut gates);
+        assert_eq!(next_row, 2);
+
+        let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+        let output = extend_cond_select_witness(&mut witness, b, x, y);
+        assert_eq!(output, cond_select(b, x, y));
+        assert_eq!(witness[cells.b.1][cells.b.0], Fp::from(b));
+        assert_eq!(witness[cells.x.1][cells.x.0], x);
+        assert_eq!(witness[cells.y.1][cells.y.0], y);
+        assert_eq!(witness[cells.output.1][cells.output.0], output);
+
+        let cs = ConstraintSystem::create(gates).build().unwrap();
+        assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+    }
+}
+
+#[test]
+// Test that a condition which is not boolean is detected
+fn test_cond_select_not_boolean() {
+    let mut gates = vec![];
+    let (_, cells) = CircuitGate::<Fp>::extend_cond_select(&mut gates);
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_cond_select_witness(&mut witness, false, Fp::from(5u32), Fp::from(9u32));
+
+    // b = 2 in all the cells of b, the booleanity of the first row fails
+    assert_eq!(cells.b, (0, 0));
+    for (row, col) in [(0, 0), (0, 1), (0, 2), (1, 0)] {
+        witness[col][row] = Fp::from(2u32);
+    }
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+    assert!(matches!(
+        cs.check_witness::<Vesta>(&witness, &[]),
+        Err(WitnessError::Generic(0, _))
+    ));
+}
+
+#[test]
+fn test_multiplexer() {
+    for n in [2, 3, 5, 8, 13] {
+        let mut gates = vec![];
+        let (next_row, cells) = CircuitGate::<Fp>::extend_multiplexer(&mut gates, n);
+        assert_eq!(next_row, gates.len());
+        assert_eq!(cells.inputs.len(), n);
+        let cs = ConstraintSystem::create(gates).build().unwrap();
+
+        let inputs = inputs(n);
+        for index in 0..n {
+            let (witness, output) = create_multiplexer_witness(index, &inputs);
+            assert_eq!(output, inputs[index]);
+            assert_eq!(witness[0].len(), next_row);
+            assert_eq!(
+                witness[cells.index.1][cells.index.0],
+                Fp::from(index as u64)
+            );
+            for (&(row, col), input) in cells.inputs.iter().zip(&inputs) {
+                assert_eq!(witness[col][row], *input);
+            }
+            assert_eq!(witness[cells.output.1][cells.output.0], output);
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+        }
+    }
+}
+
+#[test]
+// Test that an output which is not the selected input is detected
+fn test_multiplexer_wrong_output() {
+    let mut gates = vec![];
+    let (_, cells) = CircuitGate::<Fp>::extend_multiplexer(&mut gates, 5);
+    let (mut witness, _) = create_multiplexer_witness(3, &inputs(5));
+    let (row, col) = cells.output;
+    witness[col][row] = inputs(5)[2];
+
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+    assert!(matches!(
+        cs.check_witness::<Vesta>(&witness, &[]),
+        Err(WitnessError::Generic(r, _)) if r == row
+    ));
+}
+
+#[test]
+fn test_lookup_multiplexer() {
+    let values = inputs(1000);
+    let indices = [999, 0, 512, 7];
+    let mut gates = vec![];
+    let (next_row, cells) =
+        CircuitGate::<Fp>::extend_lookup_multiplexer(&mut gates, TABLE_ID, indices.len());
+    assert_eq!(next_row, 3);
+
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+    let outputs = extend_lookup_multiplexer_witness(&mut witness, TABLE_ID, &values, &indices);
+    for ((cell, &index), output) in cells.iter().zip(&indices).zip(&outputs) {
+        assert_eq!(*output, values[index]);
+        assert_eq!(witness[cell.index.1][cell.index.0], Fp::from(index as u64));
+        assert_eq!(witness[cell.output.1][cell.output.0], *output);
+    }
+
+    let cs = ConstraintSystem::create(gates.clone())
+        .lookup(vec![multiplexer_table(TABLE_ID, &values)])
+        .build()
+        .unwrap();
+    assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+    // an index out of the table is detected
+    let mut wrong = witness.clone();
+    let (row, col) = cells[1].index;
+    wrong[col][row] = Fp::from(values.len() as u64);
+    assert!(matches!(
+        cs.check_witness::<Vesta>(&wrong, &[]),
+        Err(WitnessError::ValueNotInTable { .. })
+    ));
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .lookup_tables(vec![multiplexer_table(TABLE_ID, &values)])
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
+
+#[test]
+// End-to-end test
+fn test_multiplexer_prove_and_verify() {
+    let mut gates = vec![];
+    CircuitGate::<Fp>::extend_multiplexer(&mut gates, 6);
+    let (witness, _) = create_multiplexer_witness(4, &inputs(6));
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
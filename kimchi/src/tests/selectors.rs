@@ -0,0 +1,173 @@
+use crate::{
+    circuits::{
+        constraints::{selector_polynomial, sparse_selector_rows, BaseGates, ConstraintSystem},
+        domains::EvaluationDomains,
+        gate::{CircuitGate, GateType},
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+    verifier_index::VerifierIndex,
+};
+use ark_ff::{One, Zero};
+use ark_poly::{EvaluationDomain, Evaluations};
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type BaseSponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+type ScalarSponge = DefaultFrSponge<Fp, PlonkSpongeConstantsKimchi>;
+
+// A circuit of zero gates, except for `CompleteAdd` gates at the given rows
+fn create_gates(n: usize, rows: &[usize]) -> Vec<CircuitGate<Fp>> {
+    (0..n)
+        .map(|row| {
+            let typ = if rows.contains(&row) {
+                GateType::CompleteAdd
+            } else {
+                GateType::Zero
+            };
+            CircuitGate::new(typ, Wire::for_row(row), vec![])
+        })
+        .collect()
+}
+
+#[test]
+fn test_sparse_selector_polynomial() {
+    let n = 64;
+    let domain = EvaluationDomains::<Fp>::create(n).unwrap();
+    let rows = [0, 5, 63];
+    let gates = create_gates(n, &rows);
+
+    assert_eq!(
+        sparse_selector_rows(GateType::CompleteAdd, &gates, &domain.d1, false),
+        Some(rows.to_vec())
+    );
+    assert_eq!(
+        sparse_selector_rows(GateType::Xor16, &gates, &domain.d1, false),
+        Some(vec![])
+    );
+
+    // the sparse evaluations match the interpolation of the selector
+    let mut evals = vec![Fp::zero(); n];
+    for row in rows {
+        evals[row] = Fp::one();
+    }
+    let selector = Evaluations::from_vec_and_domain(evals, domain.d1).interpolate();
+    for target_domain in [domain.d1, domain.d4, domain.d8] {
+        assert_eq!(
+            selector_polynomial(
+                GateType::CompleteAdd,
+                &gates,
+                &domain,
+                &target_domain,
+                false
+            ),
+            selector.evaluate_over_domain_by_ref(target_domain)
+        );
+    }
+}
+
+#[test]
+fn test_dense_selector_polynomial() {
+    let n = 64;
+    let domain = EvaluationDomains::<Fp>::create(n).unwrap();
+    let rows: Vec<_> = (0..=domain.d1.log_size_of_group as usize).collect();
+    let gates = create_gates(n, &rows);
+
+    // too many gates for the sparse paths
+    assert_eq!(
+        sparse_selector_rows(GateType::CompleteAdd, &gates, &domain.d1, false),
+        None
+    );
+    let mut evals = vec![Fp::zero(); n];
+    for row in rows {
+        evals[row] = Fp::one();
+    }
+    let selector = Evaluations::from_vec_and_domain(evals, domain.d1).interpolate();
+    assert_eq!(
+        selector_polynomial(GateType::CompleteAdd, &gates, &domain, &domain.d8, false),
+        selector.evaluate_over_domain_by_ref(domain.d8)
+    );
+}
+
+// Proves and verifies the generic test circuit with the given constraint system
+fn prove_and_verify(cs: ConstraintSystem<Fp>) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    // the witness leaves the last rows of the domain for zero-knowledge
+    let rows = cs.domain.d1.size() - cs.zk_rows as usize;
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    fill_in_witness(0, &mut witness, &[]);
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    let index = ProverIndex::create(cs, endo_q, Arc::new(srs));
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &index.verifier_index(),
+        &proof,
+        &[],
+    )
+    .unwrap();
+    index
+}
+
+#[test]
+fn test_unused_base_gates() {
+    let gates = create_circuit::<Fp>(0, 0);
+    assert_eq!(
+        BaseGates::GATE_TYPES.map(|typ| BaseGates::from_gates(&gates).is_used(typ)),
+        [false; 5]
+    );
+
+    // the generic circuit does not use any of the base gates
+    let pruned = prove_and_verify(
+        ConstraintSystem::create(gates.clone())
+            .prune_unused_gates(true)
+            .build()
+            .unwrap(),
+    );
+    let verifier_index = pruned.verifier_index();
+    assert!(verifier_index.psm_comm.is_none());
+    assert!(verifier_index.complete_add_comm.is_none());
+    assert!(verifier_index.mul_comm.is_none());
+    assert!(verifier_index.emul_comm.is_none());
+    assert!(verifier_index.endomul_scalar_comm.is_none());
+
+    // all the selectors are kept by default
+    let full = prove_and_verify(ConstraintSystem::create(gates).build().unwrap());
+    assert_eq!(full.cs.base_gates, BaseGates::all());
+    assert!(full.verifier_index().psm_comm.is_some());
+
+    // a used selector is serialized as a mandatory commitment was, and a
+    // missing one is read as an unused gate
+    let full_index = full.verifier_index();
+    let mut json = serde_json::to_value(&full_index).unwrap();
+    assert_eq!(
+        json["psm_comm"],
+        serde_json::to_value(full_index.psm_comm.as_ref().unwrap()).unwrap()
+    );
+    json.as_object_mut().unwrap().remove("mul_comm");
+    let decoded: VerifierIndex<Vesta, OpeningProof<Vesta>> = serde_json::from_value(json).unwrap();
+    assert_eq!(decoded.psm_comm, full_index.psm_comm);
+    assert!(decoded.mul_comm.is_none());
+
+    // the linearization is smaller, and the proofs open the same polynomials
+    assert!(pruned.linearization.constant_term.len() < full.linearization.constant_term.len());
+    assert_eq!(
+        verifier_index.opening_order_digest(),
+        full.verifier_index().opening_order_digest()
+    );
+}
@@ -4,7 +4,7 @@ use crate::{
         polynomials::generic::testing::{create_circuit, fill_in_witness},
         wires::COLUMNS,
     },
-    proof::ProverProof,
+    proof::{PointEvaluations, ProofEvaluations, ProverProof},
     prover_index::testing::new_index_for_test,
     verifier::verify,
     verifier_index::VerifierIndex,
@@ -47,6 +47,29 @@ mod tests {
         ctx.batch_verification(&vec![(de_pf, public_input)]);
     }
 
+    #[test]
+    fn test_rmp_serde_evaluations_of_the_first_release() {
+        let ctx = BenchmarkCtx::new(4);
+        let (proof, _) = ctx.create_proof();
+        assert!(proof.evals.vector_lookup_selector.is_none());
+        assert!(proof.evals.custom_selector.is_none());
+        let bytes = rmp_serde::to_vec(&proof.evals).unwrap();
+
+        // the evaluations are serialized as an array of fields, which lacks
+        // the last two (absent) ones in the proofs created before they were added
+        assert_eq!(bytes[0], 0xdc);
+        let len = u16::from_be_bytes([bytes[1], bytes[2]]);
+        assert_eq!(bytes[bytes.len() - 2..], [0xc0, 0xc0]);
+        let mut old_bytes = bytes[..bytes.len() - 2].to_vec();
+        old_bytes[1..3].copy_from_slice(&(len - 2).to_be_bytes());
+
+        let evals: ProofEvaluations<PointEvaluations<Vec<Fp>>> =
+            rmp_serde::from_slice(&old_bytes).unwrap();
+        assert!(evals.vector_lookup_selector.is_none());
+        assert!(evals.custom_selector.is_none());
+        assert_eq!(rmp_serde::to_vec(&evals).unwrap(), bytes);
+    }
+
     #[test]
     pub fn test_serialization() {
         let public = vec![Fp::from(3u8); 5];
@@ -0,0 +1,180 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, GateType},
+        polynomials::generic::GenericGateSpec,
+        split::{split_circuit, verify_split, SplitError},
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    error::VerifyError,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::Context,
+    verifier_index::VerifierIndex,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+/// A public input followed by `n` additions, each adding its row index to the
+/// output of the previous row
+fn running_sum(n: usize, x: Fp) -> (Vec<CircuitGate<Fp>>, [Vec<Fp>; COLUMNS]) {
+    let mut wires: Vec<_> = (0..=n).map(Wire::for_row).collect();
+    for row in 1..=n {
+        let prev = if row == 1 {
+            Wire::new(0, 0)
+        } else {
+            Wire::new(row - 1, 2)
+        };
+        wires[row][0] = prev;
+        wires[prev.row][prev.col] = Wire::new(row, 0);
+    }
+    let mut gates = vec![CircuitGate::create_generic_gadget(
+        wires[0],
+        GenericGateSpec::Pub,
+        None,
+    )];
+    let add = GenericGateSpec::Add {
+        left_coeff: None,
+        right_coeff: None,
+        output_coeff: None,
+    };
+    for row_wires in &wires[1..] {
+        gates.push(CircuitGate::create_generic_gadget(
+            *row_wires,
+            add.clone(),
+            None,
+        ));
+    }
+
+    let mut acc = x;
+    let cells: Vec<[Fp; 3]> = std::iter::once([x, Fp::zero(), Fp::zero()])
+        .chain((1..=n).map(|row| {
+            let y = Fp::from(row as u64);
+            let cells = [acc, y, acc + y];
+            acc += y;
+            cells
+        }))
+        .collect();
+    let witness = array::from_fn(|col| {
+        cells
+            .iter()
+            .map(|row| row.get(col).copied().unwrap_or_else(Fp::zero))
+            .collect()
+    });
+    (gates, witness)
+}
+
+/// Pairs each sub-circuit's verifier index and proof with its public input
+fn contexts<'a>(
+    verifier_indexes: &'a [VerifierIndex<Vesta, OpeningProof<Vesta>>],
+    proofs: &'a [ProverProof<Vesta, OpeningProof<Vesta>>],
+    publics: &'a [Vec<Fp>],
+) -> Vec<Context<'a, Vesta, OpeningProof<Vesta>>> {
+    verifier_indexes
+        .iter()
+        .zip(proofs.iter())
+        .zip(publics.iter())
+        .map(|((verifier_index, proof), public_input)| Context {
+            verifier_index,
+            proof,
+            public_input,
+        })
+        .collect()
+}
+
+#[test]
+fn test_split_circuit() {
+    let max_rows = 8;
+    let (gates, witness) = running_sum(30, Fp::from(5u8));
+    let split = split_circuit(&gates, 1, max_rows).unwrap();
+    assert!(split.sub_circuits.len() > 1);
+    assert!(split
+        .sub_circuits
+        .iter()
+        .all(|sub| sub.gates.len() <= max_rows));
+    assert_eq!(split.links.len(), split.sub_circuits.len() - 1);
+
+    let constraint_systems: Vec<_> = split
+        .sub_circuits
+        .iter()
+        .map(|sub| {
+            ConstraintSystem::create(sub.gates.clone())
+                .public(sub.public)
+                .build()
+                .unwrap()
+        })
+        .collect();
+    let srs_size = constraint_systems
+        .iter()
+        .map(|cs| cs.domain.d1.size())
+        .max()
+        .unwrap();
+    let mut srs = SRS::<Vesta>::create(srs_size);
+    for cs in &constraint_systems {
+        srs.add_lagrange_basis(cs.domain.d1);
+    }
+    let srs = Arc::new(srs);
+    let &endo_q = Vesta::other_curve_endo();
+    let indexes: Vec<ProverIndex<Vesta, OpeningProof<Vesta>>> = constraint_systems
+        .into_iter()
+        .map(|cs| ProverIndex::create(cs, endo_q, srs.clone()))
+        .collect();
+    let verifier_indexes: Vec<_> = indexes.iter().map(|index| index.verifier_index()).collect();
+
+    // prove each sub-circuit
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let witnesses = split.split_witness(&witness).unwrap();
+    let mut publics = vec![];
+    let mut proofs = vec![];
+    for ((sub_witness, public), index) in witnesses.into_iter().zip(indexes.iter()) {
+        let proof =
+            ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, sub_witness, &[], index)
+                .unwrap();
+        publics.push(public);
+        proofs.push(proof);
+    }
+
+    verify_split::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &split,
+        &contexts(&verifier_indexes, &proofs, &publics),
+    )
+    .unwrap();
+
+    // the sub-circuits must agree on the values they share
+    let mut tampered = publics.clone();
+    let (sub, idx) = split.links[0][1];
+    tampered[sub][idx] += Fp::from(1u8);
+    assert!(matches!(
+        verify_split::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &split,
+            &contexts(&verifier_indexes, &proofs, &tampered),
+        ),
+        Err(VerifyError::SplitLink(0))
+    ));
+}
+
+#[test]
+fn test_split_circuit_no_cut_point() {
+    let gates: Vec<CircuitGate<Fp>> = (0..10)
+        .map(|row| CircuitGate::new(GateType::ForeignFieldAdd, Wire::for_row(row), vec![]))
+        .collect();
+    assert_eq!(
+        split_circuit(&gates, 0, 4).unwrap_err(),
+        SplitError::NoCutPoint(4, 0)
+    );
+}
@@ -0,0 +1,76 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        polynomials::generic::testing::{create_circuit, fill_in_witness},
+        wires::COLUMNS,
+    },
+    curve::KimchiCurve,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    transcript::TranscriptVersion,
+    verifier::verify,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+fn create_index(version: TranscriptVersion) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let cs = ConstraintSystem::create(create_circuit(0, 0))
+        .transcript_version(version)
+        .build()
+        .unwrap();
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_domain_separated_transcript() {
+    let index = create_index(TranscriptVersion::DomainSeparated);
+    let verifier_index = index.verifier_index();
+    assert_eq!(
+        verifier_index.transcript_version,
+        TranscriptVersion::DomainSeparated
+    );
+
+    let rows = create_circuit::<Fp>(0, 0).len();
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero(); rows]);
+    fill_in_witness(0, &mut witness, &[]);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+
+    // the prover and the verifier absorb the same labels
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+
+    // and the challenges differ from the ones of the legacy transcript
+    let mut legacy_index = verifier_index;
+    legacy_index.transcript_version = TranscriptVersion::Legacy;
+    assert!(
+        verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+            &group_map,
+            &legacy_index,
+            &proof,
+            &[],
+        )
+        .is_err()
+    );
+}
@@ -1,9 +1,27 @@
-use crate::circuits::{
-    gate::CircuitGate,
-    polynomials::turshi::{testing::*, witness::*},
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::CircuitGate,
+        polynomial::COLUMNS,
+        polynomials::turshi::{builtins::*, testing::*, witness::*},
+    },
+    tests::framework::TestFramework,
 };
-use mina_curves::pasta::Fp as F;
-use turshi::{CairoMemory, CairoProgram};
+use ark_ff::{Field, One};
+use mina_curves::pasta::{Fp as F, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use std::array;
+use turshi::{
+    builtins::{BuiltinError, RANGE_CHECK_BITS},
+    CairoBuiltin, CairoMemory, CairoProgram,
+};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<F, SpongeParams>;
 
 #[test]
 fn test_cairo_should_fail() {
@@ -80,3 +98,80 @@ fn test_cairo_gate() {
         assert_eq!(Ok(()), res_ensure);
     }
 }
+
+// Creates a memory with a range check segment and a bitwise segment
+fn builtins_memory() -> CairoMemory<F> {
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::RangeCheck, F::from(10u32));
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(20u32));
+    mem.write(F::from(10u32), F::from(0u32));
+    mem.write(F::from(11u32), F::from(u128::MAX));
+    mem.write(F::from(12u32), F::from(0x1234_5678u64));
+    mem.write(F::from(20u32), F::from(u128::MAX) * F::from(u64::MAX));
+    mem.write(F::from(21u32), F::from(0xff00_ff00u64));
+    mem.write(F::from(25u32), F::from(12u32));
+    mem.write(F::from(26u32), F::from(10u32));
+    mem
+}
+
+#[test]
+fn test_cairo_builtins() {
+    let mem = builtins_memory();
+
+    let mut gates = vec![];
+    let next_row = CircuitGate::<F>::extend_cairo_builtins(&mut gates, &mem);
+    assert_eq!(next_row, gates.len());
+
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_cairo_builtins_witness(&mut witness, &mem).unwrap();
+    assert_eq!(witness[0].len(), gates.len());
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<BaseSponge, ScalarSponge>()
+        .unwrap();
+}
+
+#[test]
+fn test_cairo_builtins_invalid_memory() {
+    let mut mem = builtins_memory();
+    mem.write(F::from(22u32), F::from(1u32));
+
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    assert_eq!(
+        extend_cairo_builtins_witness(&mut witness, &mem),
+        Err(BuiltinError::WrongOutput("bitwise", 22))
+    );
+}
+
+#[test]
+fn test_cairo_range_check_out_of_range() {
+    let mut gates = vec![];
+    CircuitGate::<F>::extend_cairo_range_check(&mut gates);
+    let cs = ConstraintSystem::create(gates).build().unwrap();
+
+    // 2^128 does not pass the range check
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_cairo_range_check_witness(&mut witness, F::from(2u32).pow([RANGE_CHECK_BITS as u64]));
+    let ok = (0..witness[0].len()).all(|row| {
+        cs.gates[row]
+            .verify_witness::<Vesta>(row, &witness, &cs, &[])
+            .is_ok()
+    });
+    assert!(!ok);
+
+    // 2^128 - 1 does
+    let mut witness: [Vec<F>; COLUMNS] = array::from_fn(|_| vec![]);
+    extend_cairo_range_check_witness(
+        &mut witness,
+        F::from(2u32).pow([RANGE_CHECK_BITS as u64]) - F::one(),
+    );
+    for row in 0..witness[0].len() {
+        assert_eq!(
+            cs.gates[row].verify_witness::<Vesta>(row, &witness, &cs, &[]),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::circuits::{
+    constraints::ConstraintSystem,
+    gate::{CircuitGate, Connect},
+    polynomial::COLUMNS,
+    polynomials::{
+        range_check,
+        uint::{
+            extend_uint_add_witness, extend_uint_le_witness, extend_uint_lt_witness,
+            extend_uint_mul_witness, extend_uint_range_check_witness, extend_uint_sub_witness,
+            UintWidth,
+        },
+    },
+};
+use ark_ff::{Field, Zero};
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use std::array;
+
+use super::framework::TestFramework;
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type VestaBaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type VestaScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+const WIDTHS: [UintWidth; 2] = [UintWidth::U32, UintWidth::U64];
+
+// Pairs of inputs covering the carries, the borrows and the equality
+fn inputs(width: UintWidth) -> Vec<(u64, u64)> {
+    let max = width.max();
+    vec![
+        (0, 0),
+        (1, 2),
+        (2, 1),
+        (max, 1),
+        (max, max),
+        (max - 5, max - 5),
+        (0x1234_5678 & max, 0x9abc_def0_1234 & max),
+    ]
+}
+
+fn empty_witness() -> [Vec<Fp>; COLUMNS] {
+    array::from_fn(|_| vec![])
+}
+
+#[test]
+fn test_uint_add_sub_mul() {
+    for width in WIDTHS {
+        let bits = width.bits();
+        let modulus = 1u128 << bits;
+        for (left, right) in inputs(width) {
+            let (a, b) = (left as u128, right as u128);
+
+            let mut gates = vec![];
+            let (_, cells) = CircuitGate::<Fp>::extend_uint_add(&mut gates, width);
+            let mut witness = empty_witness();
+            let (sum, carry) = extend_uint_add_witness(&mut witness, width, left, right);
+            assert_eq!(sum as u128 + ((carry as u128) << bits), a + b);
+            assert_eq!(witness[cells.output.1][cells.output.0], Fp::from(sum));
+            assert_eq!(witness[cells.carry.1][cells.carry.0], Fp::from(carry));
+            let cs = ConstraintSystem::create(gates).build().unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+            let mut gates = vec![];
+            let (_, cells) = CircuitGate::<Fp>::extend_uint_sub(&mut gates, width);
+            let mut witness = empty_witness();
+            let (difference, borrow) = extend_uint_sub_witness(&mut witness, width, left, right);
+            assert_eq!(borrow, left < right);
+            assert_eq!(difference as u128, (a + modulus - b) % modulus);
+            assert_eq!(witness[cells.left.1][cells.left.0], Fp::from(left));
+            assert_eq!(witness[cells.right.1][cells.right.0], Fp::from(right));
+            assert_eq!(witness[cells.carry.1][cells.carry.0], Fp::from(borrow));
+            let cs = ConstraintSystem::create(gates).build().unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+            let mut gates = vec![];
+            let (next_row, cells) = CircuitGate::<Fp>::extend_uint_mul(&mut gates, width);
+            let mut witness = empty_witness();
+            let (low, high) = extend_uint_mul_witness(&mut witness, width, left, right);
+            assert_eq!(low as u128 + ((high as u128) << bits), a * b);
+            assert_eq!(witness[0].len(), next_row);
+            assert_eq!(witness[cells.output.1][cells.output.0], Fp::from(low));
+            assert_eq!(witness[cells.carry.1][cells.carry.0], Fp::from(high));
+            let cs = ConstraintSystem::create(gates).build().unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn test_uint_comparisons() {
+    for width in WIDTHS {
+        for (left, right) in inputs(width) {
+            let mut gates = vec![];
+            let (_, cells) = CircuitGate::<Fp>::extend_uint_lt(&mut gates, width);
+            let mut witness = empty_witness();
+            let lt = extend_uint_lt_witness(&mut witness, width, left, right);
+            assert_eq!(lt, left < right);
+            assert_eq!(witness[cells.output.1][cells.output.0], Fp::from(lt));
+            let cs = ConstraintSystem::create(gates).build().unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+
+            let mut gates = vec![];
+            let (_, cells) = CircuitGate::<Fp>::extend_uint_le(&mut gates, width);
+            let mut witness = empty_witness();
+            let le = extend_uint_le_witness(&mut witness, width, left, right);
+            assert_eq!(le, left <= right);
+            assert_eq!(witness[cells.left.1][cells.left.0], Fp::from(left));
+            assert_eq!(witness[cells.output.1][cells.output.0], Fp::from(le));
+            let cs = ConstraintSystem::create(gates).build().unwrap();
+            assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn test_uint_range_check() {
+    for width in WIDTHS {
+        let mut gates = vec![];
+        let (next_row, cell) = CircuitGate::<Fp>::extend_uint_range_check(&mut gates, width);
+        let cs = ConstraintSystem::create(gates).build().unwrap();
+
+        let mut witness = empty_witness();
+        extend_uint_range_check_witness(&mut witness, width, width.max());
+        assert_eq!(witness[0].len(), next_row);
+        assert_eq!(witness[cell.1][cell.0], Fp::from(width.max()));
+        assert_eq!(cs.check_witness::<Vesta>(&witness, &[]), Ok(()));
+    }
+}
+
+#[test]
+// Test that a value which does not fit in the width is detected
+fn test_uint_range_check_too_large() {
+    for width in WIDTHS {
+        let mut gates = vec![];
+        CircuitGate::<Fp>::extend_uint_range_check(&mut gates, width);
+        let cs = ConstraintSystem::create(gates).build().unwrap();
+
+        // the witness of the value 2^w, built by hand since the witness
+        // generation rejects it
+        let x = Fp::from(2u64).pow([width.bits() as u64]);
+        let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![Fp::zero()]);
+        let mut checked = vec![x];
+        if width == UintWidth::U32 {
+            let y = x + Fp::from(2u64).pow([64]) - Fp::from(1u64 << 32);
+            for (col, value) in [(3, x), (4, x), (5, y)] {
+                witness[col][0] = value;
+            }
+            checked.push(y);
+        }
+        for value in checked {
+            range_check::witness::extend(&mut witness, value);
+        }
+        assert!(cs.check_witness::<Vesta>(&witness, &[]).is_err());
+    }
+}
+
+#[test]
+#[should_panic(expected = "the value does not fit in 32 bits")]
+fn test_uint_input_too_large() {
+    let mut witness = empty_witness();
+    extend_uint_add_witness::<Fp>(&mut witness, UintWidth::U32, 1 << 32, 0);
+}
+
+#[test]
+// End-to-end test of a chain of gadgets: (a + b) * c < d
+fn test_uint_prove_and_verify() {
+    let width = UintWidth::U64;
+    let (a, b, c, d) = (u64::MAX - 1, 7u64, 3u64, 100u64);
+
+    let mut gates = vec![];
+    let mut check = vec![];
+    for _ in 0..4 {
+        let (_, cell) = CircuitGate::<Fp>::extend_uint_range_check(&mut gates, width);
+        check.push(cell);
+    }
+    let (_, add) = CircuitGate::<Fp>::extend_uint_add(&mut gates, width);
+    let (_, mul) = CircuitGate::<Fp>::extend_uint_mul(&mut gates, width);
+    let (_, lt) = CircuitGate::<Fp>::extend_uint_lt(&mut gates, width);
+    let wires = [
+        (check[0], add.left),
+        (check[1], add.right),
+        (add.output, mul.left),
+        (check[2], mul.right),
+        (mul.output, lt.left),
+        (check[3], lt.right),
+    ];
+    for (cell_pre, cell_new) in wires {
+        gates.connect_cell_pair(cell_pre, cell_new);
+    }
+
+    let mut witness = empty_witness();
+    for x in [a, b, c, d] {
+        extend_uint_range_check_witness(&mut witness, width, x);
+    }
+    let (sum, carry) = extend_uint_add_witness(&mut witness, width, a, b);
+    assert!(carry);
+    let (product, _) = extend_uint_mul_witness(&mut witness, width, sum, c);
+    assert_eq!(product, 15);
+    assert!(extend_uint_lt_witness(&mut witness, width, product, d));
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
@@ -0,0 +1,167 @@
+use crate::{
+    circuits::{
+        constraints::ConstraintSystem,
+        gate::{CircuitGate, CurrOrNext, GateType},
+        lookup::{
+            lookups::{LookupPattern, VECTOR_LOOKUP_WIDTH},
+            tables::fixed::{FixedTable, FixedTableRegistry},
+        },
+        wires::{Wire, COLUMNS},
+    },
+    curve::KimchiCurve,
+    error::ProverError,
+    proof::ProverProof,
+    prover_index::ProverIndex,
+    verifier::verify,
+};
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    sponge::{DefaultFqSponge, DefaultFrSponge},
+};
+use poly_commitment::{commitment::CommitmentCurve, evaluation_proof::OpeningProof, srs::SRS};
+use std::{array, sync::Arc};
+
+type SpongeParams = PlonkSpongeConstantsKimchi;
+type BaseSponge = DefaultFqSponge<VestaParameters, SpongeParams>;
+type ScalarSponge = DefaultFrSponge<Fp, SpongeParams>;
+
+/// A toy instruction decoding table, mapping an opcode to its format and to
+/// the number of its operands
+struct Decode;
+
+impl FixedTable<Fp> for Decode {
+    const ID: i32 = 6;
+    const NAME: &'static str = "Decode";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        vec![
+            (0..32u64).map(Fp::from).collect(),
+            (0..32u64).map(|op| Fp::from(op % 3)).collect(),
+            (0..32u64).map(|op| Fp::from(1 + op % 3)).collect(),
+        ]
+    }
+}
+
+/// A table using all the cells of a vector lookup
+struct Wide;
+
+impl FixedTable<Fp> for Wide {
+    const ID: i32 = 7;
+    const NAME: &'static str = "Wide";
+
+    fn columns(&self) -> Vec<Vec<Fp>> {
+        (0..VECTOR_LOOKUP_WIDTH as u64)
+            .map(|col| (0..8u64).map(|row| Fp::from(row * (col + 1))).collect())
+            .collect()
+    }
+}
+
+/// Creates `rows` vector lookup gates, alternating between the two tables, and their witness
+fn create_circuit(rows: usize) -> (Vec<CircuitGate<Fp>>, [Vec<Fp>; COLUMNS]) {
+    let gates = (0..rows)
+        .map(|row| CircuitGate::new(GateType::VectorLookup, Wire::for_row(row), vec![]))
+        .collect();
+
+    let decode = Decode.columns();
+    let wide = Wide.columns();
+    let cells: Vec<Vec<Fp>> = (0..rows)
+        .map(|row| {
+            let (id, table) = if row % 2 == 0 {
+                (<Decode as FixedTable<Fp>>::ID, &decode)
+            } else {
+                (<Wide as FixedTable<Fp>>::ID, &wide)
+            };
+            let entry = (3 * row) % table[0].len();
+            std::iter::once(Fp::from(id as u64))
+                .chain(table.iter().map(|column| column[entry]))
+                .collect()
+        })
+        .collect();
+    let witness = array::from_fn(|col| {
+        cells
+            .iter()
+            .map(|row| row.get(col).copied().unwrap_or_else(Fp::zero))
+            .collect()
+    });
+    (gates, witness)
+}
+
+fn create_index(gates: Vec<CircuitGate<Fp>>) -> ProverIndex<Vesta, OpeningProof<Vesta>> {
+    let mut registry = FixedTableRegistry::new();
+    registry.register(&Decode).unwrap();
+    registry.register(&Wide).unwrap();
+
+    let cs = ConstraintSystem::create(gates)
+        .fixed_tables(registry)
+        .build()
+        .unwrap();
+
+    let mut srs = SRS::<Vesta>::create(cs.domain.d1.size());
+    srs.add_lagrange_basis(cs.domain.d1);
+
+    let &endo_q = Vesta::other_curve_endo();
+    ProverIndex::create(cs, endo_q, Arc::new(srs))
+}
+
+#[test]
+fn test_vector_lookup_pattern() {
+    let lookups = LookupPattern::VectorLookup.lookups::<Fp>();
+    assert_eq!(lookups.len(), 1);
+    assert_eq!(lookups[0].entry.len(), VECTOR_LOOKUP_WIDTH);
+    assert_eq!(
+        LookupPattern::from_gate(GateType::VectorLookup, CurrOrNext::Curr),
+        Some(LookupPattern::VectorLookup)
+    );
+}
+
+#[test]
+fn test_vector_lookup_prove_and_verify() {
+    let (gates, witness) = create_circuit(20);
+    let index = create_index(gates);
+
+    let lcs = index.cs.lookup_constraint_system.as_ref().unwrap();
+    assert!(lcs.lookup_selectors.vector_lookup.is_some());
+    assert!(lcs.lookup_selectors.lookup.is_none());
+
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let proof =
+        ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index).unwrap();
+    assert!(proof.evals.vector_lookup_selector.is_some());
+
+    let verifier_index = index.verifier_index();
+    verify::<Vesta, BaseSponge, ScalarSponge, OpeningProof<Vesta>>(
+        &group_map,
+        &verifier_index,
+        &proof,
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_vector_lookup_rejects_bad_tuple() {
+    let (gates, mut witness) = create_circuit(20);
+    let index = create_index(gates);
+
+    // each cell is in the decoding table, but not the tuple
+    witness[2][0] = Fp::from(1u64);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let res = ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index);
+    assert!(matches!(res, Err(ProverError::ValueNotInTable(_))));
+}
+
+#[test]
+fn test_vector_lookup_rejects_extra_cell() {
+    let (gates, mut witness) = create_circuit(20);
+    let index = create_index(gates);
+
+    // the cells after the width of the decoding table must be zero
+    witness[4][0] = Fp::from(1u64);
+    let group_map = <Vesta as CommitmentCurve>::Map::setup();
+    let res = ProverProof::create::<BaseSponge, ScalarSponge>(&group_map, witness, &[], &index);
+    assert!(matches!(res, Err(ProverError::ValueNotInTable(_))));
+}
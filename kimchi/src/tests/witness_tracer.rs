@@ -0,0 +1,67 @@
+use crate::circuits::{
+    gate::GateType,
+    polynomials::{range_check, xor},
+    witness::{trace_witness, WitnessRecorder, WitnessTracer},
+};
+use mina_curves::pasta::Fp;
+
+#[test]
+fn test_witness_recorder() {
+    let (in1, in2) = (Fp::from(0xF0F0_1234u64), Fp::from(0x0FF0_4321u64));
+    let (witness, recorder) = trace_witness(WitnessRecorder::<Fp>::default(), || {
+        xor::create_xor_witness(in1, in2, 32)
+    });
+    assert_eq!(witness, xor::create_xor_witness(in1, in2, 32));
+
+    // The xor of 32 bits reports its inputs and output for its first row
+    assert_eq!(recorder.events.len(), 1);
+    let event = &recorder.events[0];
+    assert_eq!((event.gate, event.row), (GateType::Xor16, 0));
+    let names: Vec<&str> = event.values.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["in1", "in2", "out"]);
+    assert_eq!(recorder.value(0, "in1"), Some(in1));
+    assert_eq!(
+        recorder.value(0, "out"),
+        Some(Fp::from(0xF0F0_1234u64 ^ 0x0FF0_4321u64))
+    );
+    assert_eq!(recorder.value(1, "in1"), None);
+
+    // The range checks report a value per row
+    let (_, recorder) = trace_witness(WitnessRecorder::<Fp>::default(), || {
+        range_check::witness::create_multi(Fp::from(1u8), Fp::from(2u8), Fp::from(3u8))
+    });
+    let gates: Vec<_> = recorder.events.iter().map(|event| event.gate).collect();
+    assert_eq!(
+        gates,
+        [
+            GateType::RangeCheck0,
+            GateType::RangeCheck0,
+            GateType::RangeCheck1
+        ]
+    );
+    assert_eq!(recorder.value(2, "v2"), Some(Fp::from(3u8)));
+}
+
+#[test]
+fn test_witness_tracer_nesting() {
+    // A tracer counting the reports
+    struct Counter(usize);
+    impl WitnessTracer<Fp> for Counter {
+        fn trace(&mut self, _gate: GateType, _row: usize, _values: &[(&str, Fp)]) {
+            self.0 += 1;
+        }
+    }
+
+    let ((_, inner), outer) = trace_witness(Counter(0), || {
+        range_check::witness::create::<Fp>(Fp::from(5u8));
+        trace_witness(WitnessRecorder::<Fp>::default(), || {
+            range_check::witness::create::<Fp>(Fp::from(7u8))
+        })
+    });
+    // The inner tracer replaces the outer one until it returns
+    assert_eq!(outer.0, 1);
+    assert_eq!(inner.value(0, "v0"), Some(Fp::from(7u8)));
+
+    // Nothing is recorded without a tracer
+    range_check::witness::create::<Fp>(Fp::from(9u8));
+}
@@ -4,11 +4,13 @@ use crate::{
     circuits::{
         constraints::ConstraintSystem,
         gate::{CircuitGate, CircuitGateError, Connect, GateType},
+        lookup::tables::xor::{xor_table_with, XorTableBits},
         polynomial::COLUMNS,
         polynomials::{generic::GenericGateSpec, xor},
         wires::Wire,
     },
     curve::KimchiCurve,
+    error::SetupError,
     prover_index::ProverIndex,
 };
 use ark_ec::AffineCurve;
@@ -26,7 +28,11 @@ use poly_commitment::{
     srs::{endos, SRS},
 };
 
-use super::framework::TestFramework;
+use super::{
+    framework::TestFramework,
+    gadget_harness::{arb_edge_bits, GadgetHarness},
+};
+use proptest::strategy::Strategy;
 
 type PallasField = <Pallas as AffineCurve>::BaseField;
 type SpongeParams = PlonkSpongeConstantsKimchi;
@@ -417,3 +423,176 @@ fn test_xor_finalization() {
         .prove_and_verify::<BaseSponge, ScalarSponge>()
         .unwrap();
 }
+
+// Proves a XOR of exact length of the given inputs
+fn prove_xor_exact_length(input1: Fp, input2: Fp, bits: usize) -> Result<(), String> {
+    let mut gates = vec![];
+    let next_row = CircuitGate::<Fp>::extend_xor_gadget_exact_length(&mut gates, bits);
+    assert_eq!(next_row, xor::num_rows_exact_length(bits));
+
+    let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+    xor::extend_xor_witness_exact_length(&mut witness, input1, input2, bits);
+    assert_eq!(witness[0].len(), next_row);
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+}
+
+#[test]
+// End-to-end test of XOR of exact length for words of several lengths
+fn test_prove_and_verify_xor_exact_length() {
+    let rng = &mut o1_utils::tests::make_test_rng(None);
+    for bits in [8, 16, 32, 44, 128] {
+        let input1 = rng.gen_field_with_bits(bits);
+        let input2 = rng.gen_field_with_bits(bits);
+        prove_xor_exact_length(input1, input2, bits).unwrap();
+    }
+    // The extra rows are only needed for lengths that are not multiples of 16
+    assert_eq!(xor::num_rows_exact_length(8), 4);
+    assert_eq!(xor::num_rows_exact_length(32), 3);
+}
+
+#[test]
+// Words longer than the length of a XOR of exact length are rejected, even when they
+// fit in the Xor16 rows
+fn test_xor_exact_length_too_long_inputs() {
+    let bits = 8;
+    let short = Fp::from(0xA5u8);
+    let long = Fp::from(0x1A5u16);
+    for (input1, input2) in [(long, short), (short, long)] {
+        let mut gates = vec![];
+        CircuitGate::<Fp>::extend_xor_gadget_exact_length(&mut gates, bits);
+        // A witness of the same rows, for words of 16 bits
+        let mut witness: [Vec<Fp>; COLUMNS] = array::from_fn(|_| vec![]);
+        xor::extend_xor_witness(&mut witness, input1, input2, 16);
+        xor::extend_xor_witness(&mut witness, input2, input1, 16);
+
+        assert!(TestFramework::<Vesta>::default()
+            .gates(gates)
+            .witness(witness)
+            .setup()
+            .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+            .is_err());
+    }
+}
+
+#[test]
+#[should_panic]
+// The length of a XOR of exact length must be a multiple of the width of the table
+fn test_xor_exact_length_unsupported_length() {
+    let mut gates = vec![];
+    CircuitGate::<Fp>::extend_xor_gadget_exact_length(&mut gates, 10);
+}
+
+#[test]
+// The coefficients of the Xor16 gates identify the size of the XOR table
+fn test_xor_table_bits() {
+    for table_bits in XorTableBits::ALL {
+        let coeffs = table_bits.coeffs::<Fp>();
+        assert_eq!(coeffs[0], Fp::from(1u64 << table_bits.bits()));
+        assert_eq!(coeffs[3], Fp::from(1u64 << table_bits.row_bits()));
+        assert_eq!(XorTableBits::from_coeffs(&coeffs), Some(table_bits));
+
+        let table = xor_table_with::<Fp>(table_bits);
+        assert_eq!(table.len(), table_bits.table_size());
+        assert!(table.has_zero_entry());
+    }
+    assert_eq!(XorTableBits::from_coeffs::<Fp>(&[]), None);
+
+    // the entries of the 8-bit table are the XORs of bytes
+    let table = xor_table_with::<Fp>(XorTableBits::Eight);
+    let row = table.data[0]
+        .iter()
+        .zip(&table.data[1])
+        .position(|(&x, &y)| (x, y) == (Fp::from(0xA5u64), Fp::from(0x3Cu64)))
+        .unwrap();
+    assert_eq!(table.data[2][row], Fp::from(0xA5u64 ^ 0x3Cu64));
+}
+
+#[test]
+// End-to-end test of XOR with the 8-bit table, which halves the rows of the gadget
+fn test_prove_and_verify_xor_bytes() {
+    let rng = &mut o1_utils::tests::make_test_rng(None);
+
+    let bits = 64;
+    let mut gates = vec![];
+    let next_row = CircuitGate::<Fp>::extend_xor_gadget_with(&mut gates, bits, XorTableBits::Eight);
+    assert_eq!(next_row, xor::num_xors_with(bits, XorTableBits::Eight) + 1);
+    assert_eq!(next_row, 3);
+
+    let input1: PallasField = rng.gen_field_with_bits(bits);
+    let input2 = rng.gen_field_with_bits(bits);
+    let witness = xor::create_xor_witness_with(input1, input2, bits, XorTableBits::Eight);
+
+    // the bytes of the least significant 32 bits of the output
+    let output = BigUint::bitwise_xor(&input1.to_biguint(), &input2.to_biguint());
+    for byte in 0..4 {
+        assert_eq!(
+            witness[11 + byte][0],
+            ((output.clone() >> (8 * byte)) % 256u32).into()
+        );
+    }
+
+    // the size of the table is the one of the gates
+    let cs = ConstraintSystem::create(gates.clone()).build().unwrap();
+    assert_eq!(cs.xor_table_bits, XorTableBits::Eight);
+    for row in 0..witness[0].len() {
+        assert_eq!(
+            cs.gates[row].verify_witness::<Vesta>(row, &witness, &cs, &[]),
+            Ok(())
+        );
+    }
+
+    TestFramework::<Vesta>::default()
+        .gates(gates)
+        .witness(witness)
+        .setup()
+        .prove_and_verify::<VestaBaseSponge, VestaScalarSponge>()
+        .unwrap();
+}
+
+#[test]
+// The Xor16 gates must have the coefficients of the XOR table of the constraint system
+fn test_xor_table_bits_mismatch() {
+    let mut gates = vec![];
+    CircuitGate::<Fp>::extend_xor_gadget(&mut gates, 32);
+    let cs = ConstraintSystem::create(gates.clone())
+        .xor_table_bits(XorTableBits::Eight)
+        .build();
+    assert!(matches!(
+        cs,
+        Err(SetupError::XorTable(0, XorTableBits::Eight))
+    ));
+
+    // gadgets with different tables cannot be mixed
+    CircuitGate::<Fp>::extend_xor_gadget_with(&mut gates, 32, XorTableBits::Eight);
+    assert!(matches!(
+        ConstraintSystem::create(gates).build(),
+        Err(SetupError::XorTable(3, XorTableBits::Four))
+    ));
+}
+
+#[test]
+fn test_xor64_properties() {
+    let (_, gates) = CircuitGate::<Fp>::create_xor_gadget(0, 64);
+    let num_xors = gates.len() - 1;
+    GadgetHarness::<Vesta, (Fp, Fp)>::new(gates, |&(in1, in2)| {
+        xor::create_xor_witness(in1, in2, 64)
+    })
+    // every cell of the Xor16 rows, and the inputs and the output of the final row
+    .constrained_cells((0..=num_xors).flat_map(|row| {
+        let cols = if row < num_xors { COLUMNS } else { 3 };
+        (0..cols).map(move |col| Wire::new(row, col))
+    }))
+    .run(
+        (arb_edge_bits(64), arb_edge_bits(64)).prop_map(|(in1, in2)| {
+            (
+                Fp::from_biguint(&in1).unwrap(),
+                Fp::from_biguint(&in2).unwrap(),
+            )
+        }),
+    );
+}
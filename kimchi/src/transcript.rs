@@ -0,0 +1,48 @@
+//! This module defines the versions of the Fiat-Shamir transcript of kimchi
+//! proofs.
+//!
+//! The prover and the verifier absorb the elements of the proof in a labeled
+//! [SpongeTranscript] over the base field sponge. The transcript of the
+//! proofs verified by Mina, and by the recursion circuits of pickles, absorbs
+//! neither a domain separator nor the labels: this is
+//! [TranscriptVersion::Legacy], the default, which keeps the existing proofs
+//! valid. A circuit set up with [TranscriptVersion::DomainSeparated] (see
+//! [crate::circuits::constraints::Builder::transcript_version]) absorbs the
+//! domain separator [KIMCHI_DOMAIN] first, and the label of every element of
+//! the transcript, so that its challenges cannot be confused with the ones of
+//! another protocol sharing the sponge.
+
+use ark_ff::PrimeField;
+use mina_poseidon::{transcript::SpongeTranscript, FqSponge};
+use serde::{Deserialize, Serialize};
+
+/// The domain separator of [TranscriptVersion::DomainSeparated]
+pub const KIMCHI_DOMAIN: &str = "kimchi-v1";
+
+/// The version of the Fiat-Shamir transcript of the proofs of a circuit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TranscriptVersion {
+    /// The sponge absorbs neither a domain separator nor the labels, like the
+    /// proofs verified by Mina
+    #[default]
+    Legacy,
+    /// The sponge absorbs [KIMCHI_DOMAIN] first, and the label of every
+    /// element before the element
+    DomainSeparated,
+}
+
+impl TranscriptVersion {
+    /// Returns the transcript of this version over `sponge`
+    pub fn transcript<Fq, G, Fr, S>(self, sponge: S) -> SpongeTranscript<S>
+    where
+        Fq: PrimeField,
+        S: FqSponge<Fq, G, Fr>,
+    {
+        match self {
+            TranscriptVersion::Legacy => SpongeTranscript::new(sponge),
+            TranscriptVersion::DomainSeparated => {
+                SpongeTranscript::with_domain(sponge, KIMCHI_DOMAIN)
+            }
+        }
+    }
+}
@@ -4,30 +4,31 @@ use crate::{
     circuits::{
         argument::ArgumentType,
         berkeley_columns::Column,
-        constraints::ConstraintSystem,
+        constraints::{num_chunks, BaseGates, ConstraintSystem},
         expr::{Challenges, Constants, PolishToken},
         gate::GateType,
-        lookup::{lookups::LookupPattern, tables::combine_table},
+        lookup::tables::combine_table,
         polynomials::permutation,
         scalars::RandomOracles,
-        wires::{COLUMNS, PERMUTS},
+        wires::PERMUTS,
     },
     curve::KimchiCurve,
     error::VerifyError,
+    opening_order::{opening_order, OpenedPolynomial},
     oracles::OraclesResult,
     plonk_sponge::FrSponge,
     proof::{PointEvaluations, ProofEvaluations, ProverProof, RecursionChallenge},
-    verifier_index::VerifierIndex,
+    verifier_index::{LookupVerifierIndex, VerifierIndex},
 };
 use ark_ec::AffineCurve;
 use ark_ff::{Field, One, PrimeField, Zero};
 use ark_poly::{univariate::DensePolynomial, EvaluationDomain, Polynomial};
-use mina_poseidon::{sponge::ScalarChallenge, FqSponge};
+use mina_poseidon::{sponge::ScalarChallenge, transcript::Transcript, FqSponge};
 use o1_utils::ExtendedDensePolynomial;
 use poly_commitment::{
-    commitment::{
-        absorb_commitment, combined_inner_product, BatchEvaluationProof, Evaluation, PolyComm,
-    },
+    aggregation::{self, Accumulator},
+    commitment::{combined_inner_product, BatchEvaluationProof, Evaluation, PolyComm},
+    evaluation_proof::OpeningProof as IPAOpeningProof,
     OpenProof, SRS as _,
 };
 use rand::thread_rng;
@@ -73,12 +74,12 @@ impl<'a, G: KimchiCurve, OpeningProof: OpenProof<G>> Context<'a, G, OpeningProof
                 match t {
                     Zero => None,
                     Generic => Some(&self.verifier_index.generic_comm),
-                    Lookup => None,
-                    CompleteAdd => Some(&self.verifier_index.complete_add_comm),
-                    VarBaseMul => Some(&self.verifier_index.mul_comm),
-                    EndoMul => Some(&self.verifier_index.emul_comm),
-                    EndoMulScalar => Some(&self.verifier_index.endomul_scalar_comm),
-                    Poseidon => Some(&self.verifier_index.psm_comm),
+                    Lookup | VectorLookup => None,
+                    CompleteAdd => Some(self.verifier_index.complete_add_comm.as_ref()?),
+                    VarBaseMul => Some(self.verifier_index.mul_comm.as_ref()?),
+                    EndoMul => Some(self.verifier_index.emul_comm.as_ref()?),
+                    EndoMulScalar => Some(self.verifier_index.endomul_scalar_comm.as_ref()?),
+                    Poseidon => Some(self.verifier_index.psm_comm.as_ref()?),
                     CairoClaim | CairoInstruction | CairoFlags | CairoTransition => None,
                     RangeCheck0 => Some(self.verifier_index.range_check0_comm.as_ref()?),
                     RangeCheck1 => Some(self.verifier_index.range_check1_comm.as_ref()?),
@@ -88,6 +89,7 @@ impl<'a, G: KimchiCurve, OpeningProof: OpenProof<G>> Context<'a, G, OpeningProof
                     Rot64 => Some(self.verifier_index.rot_comm.as_ref()?),
                     KeccakRound => todo!(),
                     KeccakSponge => todo!(),
+                    Custom => Some(self.verifier_index.custom_comm.as_ref()?),
                 }
             }
         }
@@ -124,39 +126,36 @@ where
         let n = index.domain.size;
         let (_, endo_r) = G::endos();
 
-        let chunk_size = {
-            let d1_size = index.domain.size();
-            if d1_size < index.max_poly_size {
-                1
-            } else {
-                d1_size / index.max_poly_size
-            }
-        };
+        let chunk_size = num_chunks(index.domain.size(), index.max_poly_size);
 
         let zk_rows = index.zk_rows;
 
         //~ 1. Setup the Fq-Sponge. This sponge mostly absorbs group
         // elements (points as tuples over the base field), but it
         // squeezes out elements of the group's scalar field.
-        let mut fq_sponge = EFqSponge::new(G::other_curve_sponge_params());
+        //~    With the domain-separated transcript, absorb the domain separator `kimchi-v1`,
+        //~    and the label of each element before the element in the following steps.
+        let mut fq_sponge = index
+            .transcript_version
+            .transcript(EFqSponge::new(G::other_curve_sponge_params()));
 
         //~ 1. Absorb the digest of the VerifierIndex.
         let verifier_index_digest = index.digest::<EFqSponge>();
-        fq_sponge.absorb_fq(&[verifier_index_digest]);
+        fq_sponge.absorb_fq("verifier_index_digest", &[verifier_index_digest]);
 
         //~ 1. Absorb the commitments of the previous challenges with the Fq-sponge.
         for RecursionChallenge { comm, .. } in &self.prev_challenges {
-            absorb_commitment(&mut fq_sponge, comm);
+            fq_sponge.absorb_g("prev_challenge_comm", &comm.elems);
         }
 
         //~ 1. Absorb the commitment of the public input polynomial with the Fq-Sponge.
-        absorb_commitment(&mut fq_sponge, public_comm);
+        fq_sponge.absorb_g("public_comm", &public_comm.elems);
 
         //~ 1. Absorb the commitments to the registers / witness columns with the Fq-Sponge.
         self.commitments
             .w_comm
             .iter()
-            .for_each(|c| absorb_commitment(&mut fq_sponge, c));
+            .for_each(|c| fq_sponge.absorb_g("w_comm", &c.elems));
 
         //~ 1. If lookup is used:
         if let Some(l) = &index.lookup_index {
@@ -172,7 +171,7 @@ where
                     .runtime
                     .as_ref()
                     .ok_or(VerifyError::IncorrectRuntimeProof)?;
-                absorb_commitment(&mut fq_sponge, runtime_commit);
+                fq_sponge.absorb_g("runtime_table_comm", &runtime_commit.elems);
             }
         }
 
@@ -181,7 +180,7 @@ where
             //~~   then squeeze the Fq-Sponge to obtain the joint combiner challenge $j'$,
             //~~   otherwise set the joint combiner challenge $j'$ to $0$.
             let joint_combiner = if l.joint_lookup_used {
-                fq_sponge.challenge()
+                fq_sponge.challenge("joint_combiner")?
             } else {
                 G::ScalarField::zero()
             };
@@ -197,25 +196,36 @@ where
             None
         };
 
-        if index.lookup_index.is_some() {
+        if let Some(l) = &index.lookup_index {
             let lookup_commits = self
                 .commitments
                 .lookup
                 .as_ref()
                 .ok_or(VerifyError::LookupCommitmentMissing)?;
 
+            //~~ * check that the number of sorted polynomials matches the lookup argument
+            //~~   (a single polynomial of multiplicities for logUp).
+            let num_sorted = l.argument.num_sorted(&l.lookup_info);
+            if lookup_commits.sorted.len() != num_sorted {
+                return Err(VerifyError::IncorrectCommitmentLength(
+                    "lookup sorted",
+                    num_sorted,
+                    lookup_commits.sorted.len(),
+                ));
+            }
+
             //~~ * absorb the commitments to the sorted polynomials.
             for com in &lookup_commits.sorted {
-                absorb_commitment(&mut fq_sponge, com);
+                fq_sponge.absorb_g("lookup_sorted_comm", &com.elems);
             }
         }
 
         // --- PlonK - Round 2
         //~ 1. Sample the first permutation challenge $\beta$ with the Fq-Sponge.
-        let beta = fq_sponge.challenge();
+        let beta = fq_sponge.challenge("beta")?;
 
         //~ 1. Sample the second permutation challenge $\gamma$ with the Fq-Sponge.
-        let gamma = fq_sponge.challenge();
+        let gamma = fq_sponge.challenge("gamma")?;
 
         //~ 1. If using lookup, absorb the commitment to the aggregation lookup polynomial.
         if index.lookup_index.is_some() {
@@ -225,15 +235,15 @@ where
                 .lookup
                 .as_ref()
                 .ok_or(VerifyError::LookupCommitmentMissing)?;
-            absorb_commitment(&mut fq_sponge, &lookup_commits.aggreg);
+            fq_sponge.absorb_g("lookup_aggreg_comm", &lookup_commits.aggreg.elems);
         }
 
         //~ 1. Absorb the commitment to the permutation trace with the Fq-Sponge.
-        absorb_commitment(&mut fq_sponge, &self.commitments.z_comm);
+        fq_sponge.absorb_g("z_comm", &self.commitments.z_comm.elems);
 
         // --- PlonK - Round 3
         //~ 1. Sample the quotient challenge $\alpha'$ with the Fq-Sponge.
-        let alpha_chal = ScalarChallenge(fq_sponge.challenge());
+        let alpha_chal = ScalarChallenge(fq_sponge.challenge("alpha")?);
 
         //~ 1. Derive $\alpha$ from $\alpha'$ using the endomorphism (TODO: details).
         let alpha = alpha_chal.to_field(endo_r);
@@ -248,11 +258,11 @@ where
         }
 
         //~ 1. Absorb the commitment to the quotient polynomial $t$ into the argument.
-        absorb_commitment(&mut fq_sponge, &self.commitments.t_comm);
+        fq_sponge.absorb_g("t_comm", &self.commitments.t_comm.elems);
 
         // --- PlonK - Round 4
         //~ 1. Sample $\zeta'$ with the Fq-Sponge.
-        let zeta_chal = ScalarChallenge(fq_sponge.challenge());
+        let zeta_chal = ScalarChallenge(fq_sponge.challenge("zeta")?);
 
         //~ 1. Derive $\zeta$ from $\zeta'$ using the endomorphism (TODO: specify).
         let zeta = zeta_chal.to_field(endo_r);
@@ -262,6 +272,7 @@ where
         // the previous recursion round), and squeezes scalar elements
         // of the field. The squeeze result is the same as with the
         // `fq_sponge`.
+        let fq_sponge = fq_sponge.into_sponge();
         let digest = fq_sponge.clone().digest();
         let mut fr_sponge = EFrSponge::new(G::sponge_params());
 
@@ -468,121 +479,34 @@ where
             ft_eval0
         };
 
-        let combined_inner_product =
-            {
-                let ft_eval0 = vec![ft_eval0];
-                let ft_eval1 = vec![self.ft_eval1];
-
-                #[allow(clippy::type_complexity)]
-                let mut es: Vec<Vec<Vec<G::ScalarField>>> =
-                    polys.iter().map(|(_, e)| e.clone()).collect();
-                es.push(public_evals.to_vec());
-                es.push(vec![ft_eval0, ft_eval1]);
-                for col in [
-                    Column::Z,
-                    Column::Index(GateType::Generic),
-                    Column::Index(GateType::Poseidon),
-                    Column::Index(GateType::CompleteAdd),
-                    Column::Index(GateType::VarBaseMul),
-                    Column::Index(GateType::EndoMul),
-                    Column::Index(GateType::EndoMulScalar),
-                ]
-                .into_iter()
-                .chain((0..COLUMNS).map(Column::Witness))
-                .chain((0..COLUMNS).map(Column::Coefficient))
-                .chain((0..PERMUTS - 1).map(Column::Permutation))
-                .chain(
-                    index
-                        .range_check0_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::RangeCheck0)),
-                )
-                .chain(
-                    index
-                        .range_check1_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::RangeCheck1)),
-                )
-                .chain(
-                    index
-                        .foreign_field_add_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::ForeignFieldAdd)),
-                )
-                .chain(
-                    index
-                        .foreign_field_mul_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::ForeignFieldMul)),
-                )
-                .chain(
-                    index
-                        .xor_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::Xor16)),
-                )
-                .chain(
-                    index
-                        .rot_comm
-                        .as_ref()
-                        .map(|_| Column::Index(GateType::Rot64)),
-                )
-                .chain(
-                    index
-                        .lookup_index
-                        .as_ref()
-                        .map(|li| {
-                            (0..li.lookup_info.max_per_row + 1)
-                                .map(Column::LookupSorted)
-                                .chain([Column::LookupAggreg, Column::LookupTable].into_iter())
-                                .chain(
-                                    li.runtime_tables_selector
-                                        .as_ref()
-                                        .map(|_| [Column::LookupRuntimeTable].into_iter())
-                                        .into_iter()
-                                        .flatten(),
-                                )
-                                .chain(
-                                    self.evals
-                                        .runtime_lookup_table_selector
-                                        .as_ref()
-                                        .map(|_| Column::LookupRuntimeSelector),
-                                )
-                                .chain(
-                                    self.evals
-                                        .xor_lookup_selector
-                                        .as_ref()
-                                        .map(|_| Column::LookupKindIndex(LookupPattern::Xor)),
-                                )
-                                .chain(
-                                    self.evals
-                                        .lookup_gate_lookup_selector
-                                        .as_ref()
-                                        .map(|_| Column::LookupKindIndex(LookupPattern::Lookup)),
-                                )
-                                .chain(
-                                    self.evals.range_check_lookup_selector.as_ref().map(|_| {
-                                        Column::LookupKindIndex(LookupPattern::RangeCheck)
-                                    }),
-                                )
-                                .chain(self.evals.foreign_field_mul_lookup_selector.as_ref().map(
-                                    |_| Column::LookupKindIndex(LookupPattern::ForeignFieldMul),
-                                ))
-                        })
-                        .into_iter()
-                        .flatten(),
-                ) {
-                    es.push({
-                        let evals = self
-                            .evals
-                            .get_column(col)
-                            .ok_or(VerifyError::MissingEvaluation(col))?;
-                        vec![evals.zeta.clone(), evals.zeta_omega.clone()]
-                    })
-                }
+        let combined_inner_product = {
+            let ft_eval0 = vec![ft_eval0];
+            let ft_eval1 = vec![self.ft_eval1];
+
+            #[allow(clippy::type_complexity)]
+            let mut es: Vec<Vec<Vec<G::ScalarField>>> =
+                polys.iter().map(|(_, e)| e.clone()).collect();
+            es.push(public_evals.to_vec());
+            es.push(vec![ft_eval0, ft_eval1]);
+            for polynomial in opening_order(index) {
+                let col = match polynomial {
+                    // already added above
+                    OpenedPolynomial::PreviousChallenge(_)
+                    | OpenedPolynomial::Public
+                    | OpenedPolynomial::Ft => continue,
+                    OpenedPolynomial::Column(col) => col,
+                };
+                es.push({
+                    let evals = self
+                        .evals
+                        .get_column(col)
+                        .ok_or(VerifyError::MissingEvaluation(col))?;
+                    vec![evals.zeta.clone(), evals.zeta_omega.clone()]
+                })
+            }
 
-                combined_inner_product(&v, &u, &es)
-            };
+            combined_inner_product(&v, &u, &es)
+        };
 
         let oracles = RandomOracles {
             joint_combiner,
@@ -642,6 +566,7 @@ where
         foreign_field_mul_selector,
         xor_selector,
         rot_selector,
+        custom_selector,
         lookup_aggregation,
         lookup_table,
         lookup_sorted,
@@ -651,6 +576,7 @@ where
         lookup_gate_lookup_selector,
         range_check_lookup_selector,
         foreign_field_mul_lookup_selector,
+        vector_lookup_selector,
     } = &proof.evals;
 
     let check_eval_len = |eval: &PointEvaluations<Vec<_>>, str: &'static str| -> Result<()> {
@@ -728,6 +654,9 @@ where
     if let Some(rot_selector) = rot_selector {
         check_eval_len(rot_selector, "rot selector")?
     }
+    if let Some(custom_selector) = custom_selector {
+        check_eval_len(custom_selector, "custom selector")?
+    }
 
     // Lookup selectors
 
@@ -752,6 +681,9 @@ where
             "foreign field mul lookup selector",
         )?
     }
+    if let Some(vector_lookup_selector) = vector_lookup_selector {
+        check_eval_len(vector_lookup_selector, "vector lookup selector")?
+    }
 
     Ok(())
 }
@@ -788,16 +720,36 @@ where
             verifier_index.public,
         ));
     }
+    if let Some(layout) = &verifier_index.public_input_layout {
+        if let Some(position) = layout.first_invalid(public_input) {
+            return Err(VerifyError::InvalidPublicInput(position));
+        }
+    }
+    if let Some(LookupVerifierIndex {
+        shared_tables: Some(_),
+        lookup_table,
+        ..
+    }) = &verifier_index.lookup_index
+    {
+        if lookup_table.is_empty() {
+            return Err(VerifyError::MissingSharedLookupTables);
+        }
+    }
+    match proof.index_digest {
+        Some(index_digest) => {
+            if index_digest != verifier_index.index_digest::<EFqSponge>() {
+                return Err(VerifyError::IndexDigestMismatch);
+            }
+        }
+        None => {
+            if verifier_index.require_index_digest {
+                return Err(VerifyError::MissingIndexDigest);
+            }
+        }
+    }
 
     //~ 1. Check the length of evaluations inside the proof.
-    let chunk_size = {
-        let d1_size = verifier_index.domain.size();
-        if d1_size < verifier_index.max_poly_size {
-            1
-        } else {
-            d1_size / verifier_index.max_poly_size
-        }
-    };
+    let chunk_size = num_chunks(verifier_index.domain.size(), verifier_index.max_poly_size);
     check_proof_evals_len(proof, chunk_size)?;
 
     //~ 1. Commit to the negated public input polynomial.
@@ -928,219 +880,123 @@ where
     };
 
     //~ 1. List the polynomial commitments, and their associated evaluations,
-    //~    that are associated to the aggregated evaluation proof in the proof:
+    //~    that are associated to the aggregated evaluation proof in the proof,
+    //~    in the canonical opening order:
+    let mut polys = polys.into_iter();
     let mut evaluations = vec![];
-
-    //~~ * recursion
-    evaluations.extend(polys.into_iter().map(|(c, e)| Evaluation {
-        commitment: c,
-        evaluations: e,
-    }));
-
-    //~~ * public input commitment
-    evaluations.push(Evaluation {
-        commitment: public_comm,
-        evaluations: public_evals.to_vec(),
-    });
-
-    //~~ * ft commitment (chunks of it)
-    evaluations.push(Evaluation {
-        commitment: ft_comm,
-        evaluations: vec![vec![ft_eval0], vec![proof.ft_eval1]],
-    });
-
-    for col in [
-        //~~ * permutation commitment
-        Column::Z,
-        //~~ * index commitments that use the coefficients
-        Column::Index(GateType::Generic),
-        Column::Index(GateType::Poseidon),
-        Column::Index(GateType::CompleteAdd),
-        Column::Index(GateType::VarBaseMul),
-        Column::Index(GateType::EndoMul),
-        Column::Index(GateType::EndoMulScalar),
-    ]
-    .into_iter()
-    //~~ * witness commitments
-    .chain((0..COLUMNS).map(Column::Witness))
-    //~~ * coefficient commitments
-    .chain((0..COLUMNS).map(Column::Coefficient))
-    //~~ * sigma commitments
-    .chain((0..PERMUTS - 1).map(Column::Permutation))
-    //~~ * optional gate commitments
-    .chain(
-        verifier_index
-            .range_check0_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::RangeCheck0)),
-    )
-    .chain(
-        verifier_index
-            .range_check1_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::RangeCheck1)),
-    )
-    .chain(
-        verifier_index
-            .foreign_field_add_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::ForeignFieldAdd)),
-    )
-    .chain(
-        verifier_index
-            .foreign_field_mul_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::ForeignFieldMul)),
-    )
-    .chain(
-        verifier_index
-            .xor_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::Xor16)),
-    )
-    .chain(
-        verifier_index
-            .rot_comm
-            .as_ref()
-            .map(|_| Column::Index(GateType::Rot64)),
-    )
-    //~~ * lookup commitments
-    //~
-    .chain(
-        verifier_index
-            .lookup_index
-            .as_ref()
-            .map(|li| {
-                // add evaluations of sorted polynomials
-                (0..li.lookup_info.max_per_row + 1)
-                    .map(Column::LookupSorted)
-                    // add evaluations of the aggreg polynomial
-                    .chain([Column::LookupAggreg].into_iter())
-            })
-            .into_iter()
-            .flatten(),
-    ) {
-        let evals = proof
-            .evals
-            .get_column(col)
-            .ok_or(VerifyError::MissingEvaluation(col))?;
-        evaluations.push(Evaluation {
-            commitment: context
-                .get_column(col)
-                .ok_or(VerifyError::MissingCommitment(col))?
-                .clone(),
-            evaluations: vec![evals.zeta.clone(), evals.zeta_omega.clone()],
-        });
-    }
-
-    if let Some(li) = &verifier_index.lookup_index {
-        let lookup_comms = proof
-            .commitments
-            .lookup
-            .as_ref()
-            .ok_or(VerifyError::LookupCommitmentMissing)?;
-
-        let lookup_table = proof
-            .evals
-            .lookup_table
-            .as_ref()
-            .ok_or(VerifyError::LookupEvalsMissing)?;
-        let runtime_lookup_table = proof.evals.runtime_lookup_table.as_ref();
-
-        // compute table commitment
-        let table_comm = {
-            let joint_combiner = oracles
-                .joint_combiner
-                .expect("joint_combiner should be present if lookups are used");
-            // The table ID is added as the last column of the vector.
-            // Therefore, the exponent for the combiner for the table ID is the
-            // width of the concatenated table, i.e. max_joint_size.
-            let table_id_combiner = joint_combiner
-                .1
-                .pow([u64::from(li.lookup_info.max_joint_size)]);
-            let lookup_table: Vec<_> = li.lookup_table.iter().collect();
-            let runtime = lookup_comms.runtime.as_ref();
-
-            combine_table(
-                &lookup_table,
-                joint_combiner.1,
-                table_id_combiner,
-                li.table_ids.as_ref(),
-                runtime,
-            )
-        };
-
-        // add evaluation of the table polynomial
-        evaluations.push(Evaluation {
-            commitment: table_comm,
-            evaluations: vec![lookup_table.zeta.clone(), lookup_table.zeta_omega.clone()],
-        });
-
-        // add evaluation of the runtime table polynomial
-        if li.runtime_tables_selector.is_some() {
-            let runtime = lookup_comms
-                .runtime
-                .as_ref()
-                .ok_or(VerifyError::IncorrectRuntimeProof)?;
-            let runtime_eval = runtime_lookup_table
-                .as_ref()
-                .map(|x| x.map_ref(&|x| x.clone()))
-                .ok_or(VerifyError::IncorrectRuntimeProof)?;
-
-            evaluations.push(Evaluation {
-                commitment: runtime.clone(),
-                evaluations: vec![runtime_eval.zeta, runtime_eval.zeta_omega],
-            });
-        }
-    }
-
-    for col in verifier_index
-        .lookup_index
-        .as_ref()
-        .map(|li| {
-            (li.runtime_tables_selector
-                .as_ref()
-                .map(|_| Column::LookupRuntimeSelector))
-            .into_iter()
-            .chain(
-                li.lookup_selectors
-                    .xor
+    for polynomial in opening_order(verifier_index) {
+        let evaluation = match polynomial {
+            //~~ * recursion
+            OpenedPolynomial::PreviousChallenge(_) => {
+                let (commitment, evaluations) =
+                    polys
+                        .next()
+                        .ok_or(VerifyError::IncorrectPrevChallengesLength(
+                            verifier_index.prev_challenges,
+                            proof.prev_challenges.len(),
+                        ))?;
+                Evaluation {
+                    commitment,
+                    evaluations,
+                }
+            }
+            //~~ * public input commitment
+            OpenedPolynomial::Public => Evaluation {
+                commitment: public_comm.clone(),
+                evaluations: public_evals.to_vec(),
+            },
+            //~~ * ft commitment (chunks of it)
+            OpenedPolynomial::Ft => Evaluation {
+                commitment: ft_comm.clone(),
+                evaluations: vec![vec![ft_eval0], vec![proof.ft_eval1]],
+            },
+            //~~ * the combined lookup table commitment
+            OpenedPolynomial::Column(Column::LookupTable) => {
+                let li = verifier_index
+                    .lookup_index
                     .as_ref()
-                    .map(|_| Column::LookupKindIndex(LookupPattern::Xor)),
-            )
-            .chain(
-                li.lookup_selectors
+                    .ok_or(VerifyError::MissingCommitment(Column::LookupTable))?;
+                let lookup_comms = proof
+                    .commitments
                     .lookup
                     .as_ref()
-                    .map(|_| Column::LookupKindIndex(LookupPattern::Lookup)),
-            )
-            .chain(
-                li.lookup_selectors
-                    .range_check
+                    .ok_or(VerifyError::LookupCommitmentMissing)?;
+                let lookup_table = proof
+                    .evals
+                    .lookup_table
                     .as_ref()
-                    .map(|_| Column::LookupKindIndex(LookupPattern::RangeCheck)),
-            )
-            .chain(
-                li.lookup_selectors
-                    .ffmul
+                    .ok_or(VerifyError::LookupEvalsMissing)?;
+
+                // compute table commitment
+                let table_comm = {
+                    let joint_combiner = oracles
+                        .joint_combiner
+                        .as_ref()
+                        .expect("joint_combiner should be present if lookups are used");
+                    // The table ID is added as the last column of the vector.
+                    // Therefore, the exponent for the combiner for the table ID is the
+                    // width of the concatenated table, i.e. max_joint_size.
+                    let table_id_combiner = joint_combiner
+                        .1
+                        .pow([u64::from(li.lookup_info.max_joint_size)]);
+                    let lookup_table: Vec<_> = li.lookup_table.iter().collect();
+                    let runtime = lookup_comms.runtime.as_ref();
+
+                    combine_table(
+                        &lookup_table,
+                        joint_combiner.1,
+                        table_id_combiner,
+                        li.table_ids.as_ref(),
+                        runtime,
+                    )
+                };
+
+                Evaluation {
+                    commitment: table_comm,
+                    evaluations: vec![lookup_table.zeta.clone(), lookup_table.zeta_omega.clone()],
+                }
+            }
+            //~~ * the runtime table commitment, which is part of the proof
+            OpenedPolynomial::Column(Column::LookupRuntimeTable) => {
+                let runtime = proof
+                    .commitments
+                    .lookup
                     .as_ref()
-                    .map(|_| Column::LookupKindIndex(LookupPattern::ForeignFieldMul)),
-            )
-        })
-        .into_iter()
-        .flatten()
-    {
-        let evals = proof
-            .evals
-            .get_column(col)
-            .ok_or(VerifyError::MissingEvaluation(col))?;
-        evaluations.push(Evaluation {
-            commitment: context
-                .get_column(col)
-                .ok_or(VerifyError::MissingCommitment(col))?
-                .clone(),
-            evaluations: vec![evals.zeta.clone(), evals.zeta_omega.clone()],
-        });
+                    .and_then(|lookup_comms| lookup_comms.runtime.as_ref())
+                    .ok_or(VerifyError::IncorrectRuntimeProof)?;
+                let runtime_eval = proof
+                    .evals
+                    .runtime_lookup_table
+                    .as_ref()
+                    .ok_or(VerifyError::IncorrectRuntimeProof)?;
+
+                Evaluation {
+                    commitment: runtime.clone(),
+                    evaluations: vec![runtime_eval.zeta.clone(), runtime_eval.zeta_omega.clone()],
+                }
+            }
+            //~~ * the other commitments, from the index or the proof.
+            //~~   The selectors of the unused base gates are zero, and their commitment
+            //~~   is the masked commitment to zero.
+            OpenedPolynomial::Column(col) => {
+                let evals = proof
+                    .evals
+                    .get_column(col)
+                    .ok_or(VerifyError::MissingEvaluation(col))?;
+                let commitment = match (context.get_column(col), col) {
+                    (Some(commitment), _) => commitment.clone(),
+                    (None, Column::Index(typ)) if BaseGates::GATE_TYPES.contains(&typ) => {
+                        verifier_index.unused_selector_comm()
+                    }
+                    (None, _) => return Err(VerifyError::MissingCommitment(col)),
+                };
+                Evaluation {
+                    commitment,
+                    evaluations: vec![evals.zeta.clone(), evals.zeta_omega.clone()],
+                }
+            }
+        };
+        evaluations.push(evaluation);
     }
 
     // prepare for the opening proof verification
@@ -1185,6 +1041,12 @@ where
 ///     proofs: vector of Plonk proofs
 ///     RETURN: verification status
 ///
+/// With the feature `sequential_verifier`, the opening proofs are verified one
+/// at a time, and the commitments are combined, without thread pool (see
+/// `SRS::verify_sequential`). Built without the default feature `parallel`,
+/// the routines of arkworks used on the way, such as the evaluation of
+/// polynomials, do not use the rayon thread pool either.
+///
 /// # Errors
 ///
 /// Will give error if `srs` of `proof` is invalid or `verify` process fails.
@@ -1241,3 +1103,51 @@ where
         Err(VerifyError::OpenProof)
     }
 }
+
+/// Verifies a batch of proofs like [batch_verify], except the checks that the
+/// components `sg` of their opening proofs are the commitments to the polynomials
+/// of their challenges, which are linear in the size of the SRS. They are returned
+/// as accumulators, in the order of the proofs, to be aggregated with those of
+/// other proofs and checked at once (see [poly_commitment::aggregation]).
+///
+/// # Errors
+///
+/// Will give error if `srs` of `proof` is invalid or `verify` process fails.
+pub fn batch_verify_succinct<G, EFqSponge, EFrSponge>(
+    group_map: &G::Map,
+    proofs: &[Context<G, IPAOpeningProof<G>>],
+) -> Result<Vec<Accumulator<G>>>
+where
+    G: KimchiCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    EFrSponge: FrSponge<G::ScalarField>,
+{
+    if proofs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let srs = proofs[0].verifier_index.srs();
+    for &Context { verifier_index, .. } in proofs {
+        if verifier_index.srs().max_poly_size() != srs.max_poly_size() {
+            return Err(VerifyError::DifferentSRS);
+        }
+    }
+
+    let mut batch = vec![];
+    for &Context {
+        verifier_index,
+        proof,
+        public_input,
+    } in proofs
+    {
+        batch.push(to_batch::<G, EFqSponge, EFrSponge, IPAOpeningProof<G>>(
+            verifier_index,
+            proof,
+            public_input,
+        )?);
+    }
+
+    aggregation::verify_succinct(srs, group_map, &mut batch, &mut thread_rng())
+        .ok_or(VerifyError::OpenProof)
+}
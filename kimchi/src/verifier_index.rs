@@ -5,16 +5,23 @@ use crate::{
     alphas::Alphas,
     circuits::{
         berkeley_columns::Column,
+        constraints::{num_chunks, sparse_selector_rows},
+        custom_gate::CustomGateRegistry,
         expr::{Linearization, PolishToken},
-        lookup::{index::LookupSelectors, lookups::LookupInfo},
+        gate::GateType,
+        lookup::{constraints::LookupArgument, index::LookupSelectors, lookups::LookupInfo},
         polynomials::permutation::{vanishes_on_last_n_rows, zk_w},
+        public_input::PublicInputLayout,
         wires::{COLUMNS, PERMUTS},
     },
     curve::KimchiCurve,
     prover_index::ProverIndex,
+    transcript::TranscriptVersion,
 };
 use ark_ff::{One, PrimeField};
-use ark_poly::{univariate::DensePolynomial, Radix2EvaluationDomain as D};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
+};
 use mina_poseidon::FqSponge;
 use once_cell::sync::OnceCell;
 use poly_commitment::{
@@ -52,6 +59,16 @@ pub struct LookupVerifierIndex<G: CommitmentCurve> {
     /// An optional selector polynomial for runtime tables
     #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
     pub runtime_tables_selector: Option<PolyComm<G>>,
+
+    /// The argument used to prove the lookups
+    #[serde(default)]
+    pub argument: LookupArgument,
+
+    /// The hash of the shared lookup tables that `lookup_table` and `table_ids`
+    /// are taken from, if they are detached from the index.
+    /// See [crate::circuits::lookup::shared_tables]
+    #[serde(default)]
+    pub shared_tables: Option<String>,
 }
 
 #[serde_as]
@@ -84,24 +101,33 @@ pub struct VerifierIndex<G: KimchiCurve, OpeningProof: OpenProof<G>> {
     #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
     pub generic_comm: PolyComm<G>,
 
+    // The commitments of the base gates below used to be mandatory. A present
+    // commitment is serialized as before, and a missing field is read as an
+    // unused gate, so that indexes serialized by both versions are accepted.
+
     // poseidon polynomial commitments
-    /// poseidon constraint selector polynomial commitment
-    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
-    pub psm_comm: PolyComm<G>,
+    /// poseidon constraint selector polynomial commitment, if the gate is used
+    #[serde(default)]
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub psm_comm: Option<PolyComm<G>>,
 
     // ECC arithmetic polynomial commitments
-    /// EC addition selector polynomial commitment
-    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
-    pub complete_add_comm: PolyComm<G>,
-    /// EC variable base scalar multiplication selector polynomial commitment
-    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
-    pub mul_comm: PolyComm<G>,
-    /// endoscalar multiplication selector polynomial commitment
-    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
-    pub emul_comm: PolyComm<G>,
-    /// endoscalar multiplication scalar computation selector polynomial commitment
-    #[serde(bound = "PolyComm<G>: Serialize + DeserializeOwned")]
-    pub endomul_scalar_comm: PolyComm<G>,
+    /// EC addition selector polynomial commitment, if the gate is used
+    #[serde(default)]
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub complete_add_comm: Option<PolyComm<G>>,
+    /// EC variable base scalar multiplication selector polynomial commitment, if the gate is used
+    #[serde(default)]
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub mul_comm: Option<PolyComm<G>>,
+    /// endoscalar multiplication selector polynomial commitment, if the gate is used
+    #[serde(default)]
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub emul_comm: Option<PolyComm<G>>,
+    /// endoscalar multiplication scalar computation selector polynomial commitment, if the gate is used
+    #[serde(default)]
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub endomul_scalar_comm: Option<PolyComm<G>>,
 
     /// RangeCheck0 polynomial commitments
     #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
@@ -127,6 +153,10 @@ pub struct VerifierIndex<G: KimchiCurve, OpeningProof: OpenProof<G>> {
     #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
     pub rot_comm: Option<PolyComm<G>>,
 
+    /// Custom gates commitments
+    #[serde(bound = "Option<PolyComm<G>>: Serialize + DeserializeOwned")]
+    pub custom_comm: Option<PolyComm<G>>,
+
     /// wire coordinate shifts
     #[serde_as(as = "[o1_utils::serialization::SerdeAs; PERMUTS]")]
     pub shift: [G::ScalarField; PERMUTS],
@@ -149,6 +179,28 @@ pub struct VerifierIndex<G: KimchiCurve, OpeningProof: OpenProof<G>> {
     /// The mapping between powers of alpha and constraints
     #[serde(skip)]
     pub powers_of_alpha: Alphas<G::ScalarField>,
+
+    /// The names of the public inputs, if any
+    #[serde(default)]
+    pub public_input_layout: Option<PublicInputLayout>,
+
+    /// The version of the Fiat-Shamir transcript of the proofs
+    #[serde(default)]
+    pub transcript_version: TranscriptVersion,
+
+    /// The custom gates of the circuit, with their constraints, if they are used
+    #[serde(default)]
+    #[serde(bound = "Option<CustomGateRegistry<G::ScalarField>>: Serialize + DeserializeOwned")]
+    pub custom_gates: Option<CustomGateRegistry<G::ScalarField>>,
+
+    /// Whether the proofs must carry the integrity digest of the index (see
+    /// [`Self::index_digest`]). By default, only the digests carried by the
+    /// proofs are checked, and the proofs without digest are accepted.
+    #[serde(skip)]
+    pub require_index_digest: bool,
+    /// The integrity digest of the index, see [`Self::index_digest`]
+    #[serde(skip)]
+    pub index_digest: OnceCell<G::BaseField>,
 }
 //~spec:endcode
 
@@ -178,6 +230,28 @@ where
         };
 
         let domain = self.cs.domain.d1;
+        let base_gates = self.cs.base_gates;
+
+        // the selectors of rarely used gates are committed from their few nonzero evaluations
+        let commit_selector =
+            |typ: GateType, eval: &Evaluations<G::ScalarField, D<G::ScalarField>>| {
+                match sparse_selector_rows(
+                    typ,
+                    &self.cs.gates,
+                    &domain,
+                    self.cs.disable_gates_checks,
+                ) {
+                    Some(rows) => {
+                        let evals: Vec<_> = rows
+                            .into_iter()
+                            .map(|row| (row, G::ScalarField::one()))
+                            .collect();
+                        self.srs
+                            .commit_sparse_evaluations_non_hiding(domain, &evals)
+                    }
+                    None => self.srs.commit_evaluations_non_hiding(domain, eval),
+                }
+            };
 
         let lookup_index = {
             self.cs
@@ -202,6 +276,8 @@ where
                         .runtime_selector
                         .as_ref()
                         .map(|e| self.srs.commit_evaluations_non_hiding(domain, e)),
+                    argument: cs.configuration.argument,
+                    shared_tables: None,
                 })
         };
 
@@ -214,6 +290,11 @@ where
             public: self.cs.public,
             prev_challenges: self.cs.prev_challenges,
             srs: Arc::clone(&self.srs),
+            public_input_layout: self.cs.public_input_layout.clone(),
+            transcript_version: self.cs.transcript_version,
+            custom_gates: self.cs.custom_gates.as_deref().cloned(),
+            require_index_digest: false,
+            index_digest: OnceCell::new(),
 
             sigma_comm: array::from_fn(|i| {
                 self.srs.commit_evaluations_non_hiding(
@@ -234,62 +315,79 @@ where
                 ),
             ),
 
-            psm_comm: mask_fixed(self.srs.commit_evaluations_non_hiding(
-                domain,
-                &self.column_evaluations.poseidon_selector8,
-            )),
-
-            complete_add_comm: mask_fixed(self.srs.commit_evaluations_non_hiding(
-                domain,
-                &self.column_evaluations.complete_add_selector4,
-            )),
-            mul_comm: mask_fixed(
-                self.srs
-                    .commit_evaluations_non_hiding(domain, &self.column_evaluations.mul_selector8),
-            ),
-            emul_comm: mask_fixed(
-                self.srs
-                    .commit_evaluations_non_hiding(domain, &self.column_evaluations.emul_selector8),
-            ),
+            // the commitments to the selectors of the unused base gates are
+            // left out, see [crate::circuits::constraints::BaseGates]
+            psm_comm: base_gates.poseidon.then(|| {
+                mask_fixed(self.srs.commit_evaluations_non_hiding(
+                    domain,
+                    &self.column_evaluations.poseidon_selector8,
+                ))
+            }),
+
+            complete_add_comm: base_gates.complete_add.then(|| {
+                mask_fixed(commit_selector(
+                    GateType::CompleteAdd,
+                    &self.column_evaluations.complete_add_selector4,
+                ))
+            }),
+            mul_comm: base_gates.var_base_mul.then(|| {
+                mask_fixed(commit_selector(
+                    GateType::VarBaseMul,
+                    &self.column_evaluations.mul_selector8,
+                ))
+            }),
+            emul_comm: base_gates.endo_mul.then(|| {
+                mask_fixed(commit_selector(
+                    GateType::EndoMul,
+                    &self.column_evaluations.emul_selector8,
+                ))
+            }),
 
-            endomul_scalar_comm: mask_fixed(self.srs.commit_evaluations_non_hiding(
-                domain,
-                &self.column_evaluations.endomul_scalar_selector8,
-            )),
+            endomul_scalar_comm: base_gates.endo_mul_scalar.then(|| {
+                mask_fixed(commit_selector(
+                    GateType::EndoMulScalar,
+                    &self.column_evaluations.endomul_scalar_selector8,
+                ))
+            }),
 
             range_check0_comm: self
                 .column_evaluations
                 .range_check0_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::RangeCheck0, eval8)),
 
             range_check1_comm: self
                 .column_evaluations
                 .range_check1_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::RangeCheck1, eval8)),
 
             foreign_field_add_comm: self
                 .column_evaluations
                 .foreign_field_add_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::ForeignFieldAdd, eval8)),
 
             foreign_field_mul_comm: self
                 .column_evaluations
                 .foreign_field_mul_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::ForeignFieldMul, eval8)),
             xor_comm: self
                 .column_evaluations
                 .xor_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::Xor16, eval8)),
             rot_comm: self
                 .column_evaluations
                 .rot_selector8
                 .as_ref()
-                .map(|eval8| self.srs.commit_evaluations_non_hiding(domain, eval8)),
+                .map(|eval8| commit_selector(GateType::Rot64, eval8)),
+            custom_comm: self
+                .column_evaluations
+                .custom_selector8
+                .as_ref()
+                .map(|eval8| commit_selector(GateType::Custom, eval8)),
 
             shift: self.cs.shift,
             permutation_vanishing_polynomial_m: {
@@ -324,6 +422,19 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
         &self.srs
     }
 
+    /// Returns the commitment to the selector of an unused base gate, that is the
+    /// commitment to the zero polynomial masked like the other fixed selectors
+    /// (see [crate::circuits::constraints::BaseGates]).
+    pub fn unused_selector_comm(&self) -> PolyComm<G> {
+        let chunks = num_chunks(self.domain.size(), self.max_poly_size);
+        let zero = PolyComm::new(vec![G::zero(); chunks]);
+        let blinders = zero.map(|_| G::ScalarField::one());
+        self.srs
+            .mask_custom(zero, &blinders)
+            .expect("the blinders match the chunks of the commitment")
+            .commitment
+    }
+
     /// Gets permutation_vanishing_polynomial_m from [`VerifierIndex`] lazily
     pub fn permutation_vanishing_polynomial_m(&self) -> &DensePolynomial<G::ScalarField> {
         self.permutation_vanishing_polynomial_m
@@ -406,11 +517,17 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
             srs: _,
             public: _,
             prev_challenges: _,
+            public_input_layout: _,
+            transcript_version: _,
+            require_index_digest: _,
+            index_digest: _,
 
             // Always present
             sigma_comm,
             coefficients_comm,
             generic_comm,
+
+            // Base gates; absent if unused
             psm_comm,
             complete_add_comm,
             mul_comm,
@@ -424,6 +541,8 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
             foreign_field_mul_comm,
             xor_comm,
             rot_comm,
+            custom_comm,
+            custom_gates,
 
             // Lookup index; optional
             lookup_index,
@@ -446,11 +565,21 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
             fq_sponge.absorb_g(&comm.elems);
         }
         fq_sponge.absorb_g(&generic_comm.elems);
-        fq_sponge.absorb_g(&psm_comm.elems);
-        fq_sponge.absorb_g(&complete_add_comm.elems);
-        fq_sponge.absorb_g(&mul_comm.elems);
-        fq_sponge.absorb_g(&emul_comm.elems);
-        fq_sponge.absorb_g(&endomul_scalar_comm.elems);
+
+        // Base gates
+
+        for comm in [
+            psm_comm,
+            complete_add_comm,
+            mul_comm,
+            emul_comm,
+            endomul_scalar_comm,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            fq_sponge.absorb_g(&comm.elems);
+        }
 
         // Optional gates
 
@@ -477,12 +606,31 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
         if let Some(rot_comm) = rot_comm {
             fq_sponge.absorb_g(&rot_comm.elems);
         }
+        if let Some(custom_comm) = custom_comm {
+            fq_sponge.absorb_g(&custom_comm.elems);
+        }
+        // The constraints of the custom gates are not fixed by kimchi, so
+        // their digest is absorbed along with their selector
+        if let Some(custom_gates) = custom_gates {
+            let digest: Vec<_> = custom_gates
+                .digest()
+                .chunks(16)
+                .map(|chunk| {
+                    G::BaseField::from(u128::from_le_bytes(
+                        chunk.try_into().expect("the chunks have 16 bytes"),
+                    ))
+                })
+                .collect();
+            fq_sponge.absorb_fq(&digest);
+        }
 
         // Lookup index; optional
 
         if let Some(LookupVerifierIndex {
             joint_lookup_used: _,
             lookup_info: _,
+            argument,
+            shared_tables: _,
             lookup_table,
             table_ids,
             runtime_tables_selector,
@@ -493,6 +641,7 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
                     lookup,
                     range_check,
                     ffmul,
+                    vector_lookup,
                 },
         }) = lookup_index
         {
@@ -518,7 +667,96 @@ impl<G: KimchiCurve, OpeningProof: OpenProof<G>> VerifierIndex<G, OpeningProof>
             if let Some(ffmul) = ffmul {
                 fq_sponge.absorb_g(&ffmul.elems);
             }
+            if let Some(vector_lookup) = vector_lookup {
+                fq_sponge.absorb_g(&vector_lookup.elems);
+            }
+
+            // The argument is tagged after the commitments. Plookup, the
+            // default, is not tagged, so that the digests of the indexes
+            // created before the other arguments are unchanged.
+            if *argument != LookupArgument::Plookup {
+                fq_sponge.absorb_fq(&[G::BaseField::from(*argument as u64)]);
+            }
+        }
+        fq_sponge.digest_fq()
+    }
+
+    /// Compute the integrity digest of the [`VerifierIndex`]: a Poseidon digest of its
+    /// commitments (see [`Self::digest`]) and of its parameters. It is equal to the
+    /// digest of the [`ProverIndex`] this index was derived from (see
+    /// [`ProverIndex::index_digest`]), and is carried by the proofs so that the
+    /// verifier rejects a proof created with the index of another circuit.
+    /// The digest is computed once, and cached in the index.
+    pub fn index_digest<EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>>(
+        &self,
+    ) -> G::BaseField {
+        *self.index_digest.get_or_init(|| {
+            IndexParameters::from(self).digest::<G, EFqSponge>(self.digest::<EFqSponge>())
+        })
+    }
+}
+
+/// The parameters of an index absorbed by its integrity digest, see
+/// [`VerifierIndex::index_digest`]
+pub(crate) struct IndexParameters<'a, F> {
+    pub(crate) domain_size: u64,
+    pub(crate) max_poly_size: usize,
+    pub(crate) zk_rows: u64,
+    pub(crate) public: usize,
+    pub(crate) prev_challenges: usize,
+    pub(crate) shift: &'a [F; PERMUTS],
+    pub(crate) lookup: Option<(&'a LookupInfo, LookupArgument)>,
+}
+
+impl<'a, G: KimchiCurve, OpeningProof: OpenProof<G>> From<&'a VerifierIndex<G, OpeningProof>>
+    for IndexParameters<'a, G::ScalarField>
+{
+    fn from(index: &'a VerifierIndex<G, OpeningProof>) -> Self {
+        IndexParameters {
+            domain_size: index.domain.size,
+            max_poly_size: index.max_poly_size,
+            zk_rows: index.zk_rows,
+            public: index.public,
+            prev_challenges: index.prev_challenges,
+            shift: &index.shift,
+            lookup: index
+                .lookup_index
+                .as_ref()
+                .map(|lookup_index| (&lookup_index.lookup_info, lookup_index.argument)),
         }
+    }
+}
+
+impl<'a, F: PrimeField> IndexParameters<'a, F> {
+    /// Absorbs the parameters after the digest of the commitments of the index
+    pub(crate) fn digest<G, EFqSponge>(&self, commitments_digest: G::BaseField) -> G::BaseField
+    where
+        G: KimchiCurve<ScalarField = F>,
+        EFqSponge: FqSponge<G::BaseField, G, F>,
+    {
+        let mut fq_sponge = EFqSponge::new(G::other_curve_sponge_params());
+        fq_sponge.absorb_fq(&[commitments_digest]);
+        fq_sponge.absorb_fq(
+            &[
+                self.domain_size,
+                self.max_poly_size as u64,
+                self.zk_rows,
+                self.public as u64,
+                self.prev_challenges as u64,
+            ]
+            .map(G::BaseField::from),
+        );
+        fq_sponge.absorb_fr(self.shift);
+        let lookup = match self.lookup {
+            None => [0u64; 4],
+            Some((lookup_info, argument)) => [
+                1,
+                lookup_info.max_per_row as u64,
+                lookup_info.max_joint_size as u64,
+                argument as u64,
+            ],
+        };
+        fq_sponge.absorb_fq(&lookup.map(G::BaseField::from));
         fq_sponge.digest_fq()
     }
 }
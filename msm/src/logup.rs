@@ -139,6 +139,8 @@
 //! - evaluate all polynomials at the evaluation points ζ and ζω (because we
 //! access the "next" row for the accumulator in the quotient polynomial).
 use ark_ff::{Field, PrimeField, Zero};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{DeserializeAs, Same, SerializeAs};
 use std::{collections::BTreeMap, hash::Hash};
 
 use kimchi::circuits::expr::{ChallengeTerm, ConstantExpr, ConstantTerm, Expr, ExprInner};
@@ -282,6 +284,46 @@ pub struct LookupProof<T, ID> {
     pub(crate) fixed_tables: BTreeMap<ID, T>,
 }
 
+/// The proof is serialized as the tuple `(m, h, sum, fixed_tables)`, the
+/// commitments (resp. evaluations) being serialized with `U`
+impl<T, U: SerializeAs<T>, ID: Serialize> SerializeAs<LookupProof<T, ID>> for LookupProof<U, ID> {
+    fn serialize_as<S: Serializer>(
+        source: &LookupProof<T, ID>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        <(
+            &BTreeMap<Same, Vec<U>>,
+            &BTreeMap<Same, Vec<U>>,
+            &U,
+            &BTreeMap<Same, U>,
+        )>::serialize_as(
+            &(&source.m, &source.h, &source.sum, &source.fixed_tables),
+            serializer,
+        )
+    }
+}
+
+impl<'de, T, U: DeserializeAs<'de, T>, ID: Deserialize<'de> + Ord>
+    DeserializeAs<'de, LookupProof<T, ID>> for LookupProof<U, ID>
+{
+    fn deserialize_as<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<LookupProof<T, ID>, D::Error> {
+        let (m, h, sum, fixed_tables) = <(
+            BTreeMap<Same, Vec<U>>,
+            BTreeMap<Same, Vec<U>>,
+            U,
+            BTreeMap<Same, U>,
+        )>::deserialize_as(deserializer)?;
+        Ok(LookupProof {
+            m,
+            h,
+            sum,
+            fixed_tables,
+        })
+    }
+}
+
 /// Iterator implementation to abstract the content of the structure.
 /// It can be used to iterate over the commitments (resp. the evaluations)
 /// without requiring to have a look at the inner fields.
@@ -4,6 +4,7 @@ use crate::logup::{Logup, LogupWitness, LookupTableID};
 use ark_ff::{FftField, PrimeField};
 use kimchi::circuits::domains::EvaluationDomains;
 use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 use std::{cmp::Ord, iter};
 
 /// Dummy lookup table. For the cases when you don't need one -- a single dummy element 0.
@@ -60,7 +61,7 @@ impl DummyLookupTable {
 
 /// Lookup tables used in the MSM project
 // TODO: Add more built-in lookup tables
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
 pub enum LookupTableIDs {
     RangeCheck16,
     /// Custom lookup table
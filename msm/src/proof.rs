@@ -5,6 +5,7 @@ use crate::{
     LogupWitness, DOMAIN_SIZE,
 };
 use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use kimchi::{
     circuits::{
         domains::EvaluationDomains,
@@ -13,8 +14,11 @@ use kimchi::{
     curve::KimchiCurve,
     proof::PointEvaluations,
 };
+use o1_utils::serialization::SerdeAs;
 use poly_commitment::{commitment::PolyComm, OpenProof};
 use rand::thread_rng;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{serde_as, DeserializeAs, Same, SerializeAs};
 use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,7 +67,36 @@ impl<const N_WIT: usize, F: PrimeField, ID: LookupTableID> Default for ProofInpu
     }
 }
 
-#[derive(Debug, Clone)]
+/// Serializes the evaluations of a column at ζ and ζω with [SerdeAs]
+struct PointEvaluationsAs;
+
+impl<F: CanonicalSerialize + Clone> SerializeAs<PointEvaluations<F>> for PointEvaluationsAs {
+    fn serialize_as<S: Serializer>(
+        source: &PointEvaluations<F>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        <(SerdeAs, SerdeAs)>::serialize_as(
+            &(source.zeta.clone(), source.zeta_omega.clone()),
+            serializer,
+        )
+    }
+}
+
+impl<'de, F: CanonicalDeserialize> DeserializeAs<'de, PointEvaluations<F>> for PointEvaluationsAs {
+    fn deserialize_as<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PointEvaluations<F>, D::Error> {
+        let (zeta, zeta_omega) = <(SerdeAs, SerdeAs)>::deserialize_as(deserializer)?;
+        Ok(PointEvaluations { zeta, zeta_omega })
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "F: CanonicalSerialize + Clone, ID: Serialize",
+    deserialize = "F: CanonicalDeserialize, ID: DeserializeOwned"
+))]
 // TODO Should public input and fixed selectors evaluations be here?
 pub struct ProofEvaluations<
     const N_WIT: usize,
@@ -74,12 +107,16 @@ pub struct ProofEvaluations<
     ID: LookupTableID,
 > {
     /// Witness evaluations, including public inputs
+    #[serde_as(as = "Witness<N_WIT, PointEvaluationsAs>")]
     pub(crate) witness_evals: Witness<N_WIT, PointEvaluations<F>>,
     /// Evaluations of fixed selectors.
+    #[serde_as(as = "Box<[PointEvaluationsAs; N_FSEL]>")]
     pub(crate) fixed_selectors_evals: Box<[PointEvaluations<F>; N_FSEL]>,
     /// Logup argument evaluations
+    #[serde_as(as = "Option<LookupProof<PointEvaluationsAs, ID>>")]
     pub(crate) logup_evals: Option<LookupProof<PointEvaluations<F>, ID>>,
     /// Evaluation of Z_H(ζ) (t_0(X) + ζ^n t_1(X) + ...) at ζω.
+    #[serde_as(as = "SerdeAs")]
     pub(crate) ft_eval1: F,
 }
 
@@ -146,20 +183,28 @@ impl<
     }
 }
 
-#[derive(Debug, Clone)]
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "ID: Serialize", deserialize = "ID: DeserializeOwned"))]
 pub struct ProofCommitments<const N_WIT: usize, G: KimchiCurve, ID: LookupTableID> {
     /// Commitments to the N columns of the circuits, also called the 'witnesses'.
     /// If some columns are considered as public inputs, it is counted in the witness.
+    #[serde_as(as = "Witness<N_WIT, Same>")]
     pub(crate) witness_comms: Witness<N_WIT, PolyComm<G>>,
     /// Commitments to the polynomials used by the lookup argument, coined "logup".
     /// The values contains the chunked polynomials.
+    #[serde_as(as = "Option<LookupProof<Same, ID>>")]
     pub(crate) logup_comms: Option<LookupProof<PolyComm<G>, ID>>,
     /// Commitments to the quotient polynomial.
     /// The value contains the chunked polynomials.
     pub(crate) t_comm: PolyComm<G>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "OpeningProof: Serialize, ID: Serialize",
+    deserialize = "OpeningProof: DeserializeOwned, ID: DeserializeOwned"
+))]
 pub struct Proof<
     const N_WIT: usize,
     const N_REL: usize,
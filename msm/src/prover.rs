@@ -88,22 +88,23 @@ where
         o1_utils::array::vec_to_boxed_array(
             fixed_selectors
                 .into_par_iter()
-                .map(|evals| Evaluations::from_vec_and_domain(evals, domain.d1))
-                .collect(),
-        );
-
-    let fixed_selectors_polys: Box<[DensePolynomial<G::ScalarField>; N_FSEL]> =
-        o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_evals_d1
-                .into_par_iter()
-                .map(|evals| evals.interpolate())
+                .map(|mut evals| {
+                    // The commitments in the Lagrange basis need all the
+                    // evaluations, the shorter columns are padded with zeroes
+                    evals.resize(domain.d1.size(), G::ScalarField::zero());
+                    Evaluations::from_vec_and_domain(evals, domain.d1)
+                })
                 .collect(),
         );
 
+    // The columns are committed in the Lagrange basis, directly from their
+    // evaluations, the interpolation being only required for the quotient.
     let fixed_selectors_comms: Box<[PolyComm<G>; N_FSEL]> = {
-        let comm = |poly: &DensePolynomial<G::ScalarField>| srs.commit_non_hiding(poly, 1);
+        let comm = |evals: &Evaluations<G::ScalarField, R2D<G::ScalarField>>| {
+            srs.commit_evaluations_non_hiding(domain.d1, evals)
+        };
         o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_polys
+            fixed_selectors_evals_d1
                 .as_ref()
                 .into_par_iter()
                 .map(comm)
@@ -111,6 +112,14 @@ where
         )
     };
 
+    let fixed_selectors_polys: Box<[DensePolynomial<G::ScalarField>; N_FSEL]> =
+        o1_utils::array::vec_to_boxed_array(
+            fixed_selectors_evals_d1
+                .into_par_iter()
+                .map(|evals| evals.interpolate())
+                .collect(),
+        );
+
     // Do not use parallelism
     (fixed_selectors_comms)
         .into_iter()
@@ -120,39 +129,42 @@ where
     let witness_evals_d1: Witness<N_WIT, Evaluations<G::ScalarField, R2D<G::ScalarField>>> = inputs
         .evaluations
         .into_par_iter()
-        .map(|evals| {
+        .map(|mut evals| {
+            evals.resize(domain.d1.size(), G::ScalarField::zero());
             Evaluations::<G::ScalarField, R2D<G::ScalarField>>::from_vec_and_domain(
                 evals, domain.d1,
             )
         })
         .collect::<Witness<N_WIT, Evaluations<G::ScalarField, R2D<G::ScalarField>>>>();
 
-    let witness_polys: Witness<N_WIT, DensePolynomial<G::ScalarField>> = {
-        let interpolate =
-            |evals: Evaluations<G::ScalarField, R2D<G::ScalarField>>| evals.interpolate();
-        witness_evals_d1
-            .into_par_iter()
-            .map(interpolate)
-            .collect::<Witness<N_WIT, DensePolynomial<G::ScalarField>>>()
-    };
-
     let witness_comms: Witness<N_WIT, PolyComm<G>> = {
         let blinders = PolyComm {
             elems: vec![G::ScalarField::one()],
         };
         let comm = {
-            |poly: &DensePolynomial<G::ScalarField>| {
+            |evals: &Evaluations<G::ScalarField, R2D<G::ScalarField>>| {
                 // In case the column polynomial is all zeroes, we want to mask the commitment
-                let comm = srs.commit_custom(poly, 1, &blinders).unwrap();
+                let comm = srs
+                    .commit_evaluations_custom(domain.d1, evals, &blinders)
+                    .unwrap();
                 comm.commitment
             }
         };
-        (&witness_polys)
+        (&witness_evals_d1)
             .into_par_iter()
             .map(comm)
             .collect::<Witness<N_WIT, PolyComm<G>>>()
     };
 
+    let witness_polys: Witness<N_WIT, DensePolynomial<G::ScalarField>> = {
+        let interpolate =
+            |evals: Evaluations<G::ScalarField, R2D<G::ScalarField>>| evals.interpolate();
+        witness_evals_d1
+            .into_par_iter()
+            .map(interpolate)
+            .collect::<Witness<N_WIT, DensePolynomial<G::ScalarField>>>()
+    };
+
     // Do not use parallelism
     (&witness_comms)
         .into_iter()
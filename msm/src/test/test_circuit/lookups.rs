@@ -2,9 +2,12 @@ use crate::logup::LookupTableID;
 use ark_ff::PrimeField;
 use num_bigint::BigUint;
 use o1_utils::FieldHelpers;
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, EnumIter)]
+#[derive(
+    Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, EnumIter, Serialize, Deserialize,
+)]
 pub enum LookupTable {
     /// Fixed table, x ∈ [0, 2^15].
     RangeCheck15,
@@ -6,16 +6,23 @@ pub mod lookups;
 mod tests {
     use crate::{
         circuit_design::{ConstraintBuilderEnv, WitnessBuilderEnv},
+        expr::E,
         logup::LookupTableID,
         lookups::DummyLookupTable,
+        proof::{Proof, ProofInputs},
+        prover::prove,
         test::test_circuit::{
             columns::{TestColumn, N_COL_TEST, N_FSEL_TEST},
             interpreter as test_interpreter,
             lookups::LookupTable as TestLookupTable,
         },
-        Ff1, Fp,
+        verifier::verify,
+        witness::Witness,
+        BN254G1Affine, BaseSponge, Ff1, Fp, OpeningProof, ScalarSponge, BN254,
     };
     use ark_ff::UniformRand;
+    use kimchi::circuits::domains::EvaluationDomains;
+    use poly_commitment::pairing_proof::PairingSRS;
     use rand::{CryptoRng, Rng, RngCore};
     use std::collections::BTreeMap;
 
@@ -427,14 +434,14 @@ mod tests {
         build_test_mul_circuit::<_, DummyLookupTable>(&mut rng, 1 << 4);
     }
 
-    #[test]
-    fn test_completeness_lookups() {
-        let mut rng = o1_utils::tests::make_test_rng(None);
+    type LookupsProofInputs = ProofInputs<{ N_COL_TEST - N_FSEL_TEST }, Fp, TestLookupTable>;
 
-        // Include tests for completeness for Logup as the random witness
-        // includes all arguments
-        let domain_size = 1 << 15;
+    /// The constraints, the fixed selectors and the witness of a circuit
+    type LookupsCircuit = (Vec<E<Fp>>, Box<[Vec<Fp>; N_FSEL_TEST]>, LookupsProofInputs);
 
+    /// Returns the constraints, the fixed selectors and the witness of the
+    /// circuit using all the lookup tables
+    fn build_lookups_circuit(domain_size: usize) -> LookupsCircuit {
         let fixed_selectors = test_interpreter::build_fixed_selectors(domain_size);
 
         let mut constraint_env = ConstraintBuilderEnv::<Fp, TestLookupTable>::create();
@@ -466,6 +473,18 @@ mod tests {
         }
 
         let proof_inputs = witness_env.get_proof_inputs(domain_size, lookup_tables_data);
+        (constraints, fixed_selectors, proof_inputs)
+    }
+
+    #[test]
+    fn test_completeness_lookups() {
+        let mut rng = o1_utils::tests::make_test_rng(None);
+
+        // Include tests for completeness for Logup as the random witness
+        // includes all arguments
+        let domain_size = 1 << 15;
+
+        let (constraints, fixed_selectors, proof_inputs) = build_lookups_circuit(domain_size);
 
         crate::test::test_completeness_generic::<
             { N_COL_TEST - N_FSEL_TEST },
@@ -483,6 +502,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_proof_serialization() {
+        let mut rng = o1_utils::tests::make_test_rng(None);
+        let domain_size = 1 << 15;
+        let domain = EvaluationDomains::<Fp>::create(domain_size).unwrap();
+        let srs: PairingSRS<BN254> = crate::precomputed_srs::get_bn254_srs(domain);
+
+        // The proof contains the commitments and evaluations of the lookups
+        let (constraints, fixed_selectors, proof_inputs) = build_lookups_circuit(domain_size);
+        let proof = prove::<
+            _,
+            OpeningProof,
+            BaseSponge,
+            ScalarSponge,
+            _,
+            { N_COL_TEST - N_FSEL_TEST },
+            { N_COL_TEST - N_FSEL_TEST },
+            0,
+            N_FSEL_TEST,
+            TestLookupTable,
+        >(
+            domain,
+            &srs,
+            &constraints,
+            fixed_selectors.clone(),
+            proof_inputs,
+            &mut rng,
+        )
+        .unwrap();
+
+        let bytes = rmp_serde::to_vec(&proof).unwrap();
+        let deserialized: Proof<
+            { N_COL_TEST - N_FSEL_TEST },
+            { N_COL_TEST - N_FSEL_TEST },
+            0,
+            N_FSEL_TEST,
+            BN254G1Affine,
+            OpeningProof,
+            TestLookupTable,
+        > = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(rmp_serde::to_vec(&deserialized).unwrap(), bytes);
+
+        let verifies = verify::<
+            _,
+            OpeningProof,
+            BaseSponge,
+            ScalarSponge,
+            { N_COL_TEST - N_FSEL_TEST },
+            { N_COL_TEST - N_FSEL_TEST },
+            0,
+            N_FSEL_TEST,
+            0,
+            TestLookupTable,
+        >(
+            domain,
+            &srs,
+            &constraints,
+            fixed_selectors,
+            &deserialized,
+            Witness::zero_vec(domain_size),
+        );
+        assert!(verifies);
+    }
+
     #[test]
     fn test_completeness() {
         let mut rng = o1_utils::tests::make_test_rng(None);
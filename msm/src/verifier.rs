@@ -3,10 +3,7 @@
 
 use crate::logup::LookupTableID;
 use ark_ff::{Field, Zero};
-use ark_poly::{
-    univariate::DensePolynomial, EvaluationDomain, Evaluations, Polynomial,
-    Radix2EvaluationDomain as R2D,
-};
+use ark_poly::{EvaluationDomain, Evaluations, Polynomial, Radix2EvaluationDomain as R2D};
 use rand::thread_rng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
@@ -66,24 +63,24 @@ where
         o1_utils::array::vec_to_boxed_array(
             fixed_selectors
                 .into_par_iter()
-                .map(|evals| Evaluations::from_vec_and_domain(evals, domain.d1))
-                .collect(),
-        )
-    };
-
-    let fixed_selectors_polys: Box<[DensePolynomial<G::ScalarField>; N_FSEL]> = {
-        o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_evals_d1
-                .into_par_iter()
-                .map(|evals| evals.interpolate())
+                .map(|mut evals| {
+                    // The commitments in the Lagrange basis need all the
+                    // evaluations, the shorter columns are padded with zeroes
+                    evals.resize(domain.d1.size(), G::ScalarField::zero());
+                    Evaluations::from_vec_and_domain(evals, domain.d1)
+                })
                 .collect(),
         )
     };
 
+    // The commitments are computed in the Lagrange basis, without
+    // interpolating the columns.
     let fixed_selectors_comms: Box<[PolyComm<G>; N_FSEL]> = {
-        let comm = |poly: &DensePolynomial<G::ScalarField>| srs.commit_non_hiding(poly, 1);
+        let comm = |evals: &Evaluations<G::ScalarField, R2D<G::ScalarField>>| {
+            srs.commit_evaluations_non_hiding(domain.d1, evals)
+        };
         o1_utils::array::vec_to_boxed_array(
-            fixed_selectors_polys
+            fixed_selectors_evals_d1
                 .as_ref()
                 .into_par_iter()
                 .map(comm)
@@ -91,29 +88,23 @@ where
         )
     };
 
-    // Interpolate public input columns on d1, using trait Into.
+    // Public input columns on d1, using trait Into.
     let public_input_evals_d1: Witness<NPUB, Evaluations<G::ScalarField, R2D<G::ScalarField>>> =
         public_inputs
             .into_par_iter()
-            .map(|evals| {
+            .map(|mut evals| {
+                evals.resize(domain.d1.size(), G::ScalarField::zero());
                 Evaluations::<G::ScalarField, R2D<G::ScalarField>>::from_vec_and_domain(
                     evals, domain.d1,
                 )
             })
             .collect::<Witness<NPUB, Evaluations<G::ScalarField, R2D<G::ScalarField>>>>();
 
-    let public_input_polys: Witness<NPUB, DensePolynomial<G::ScalarField>> = {
-        let interpolate =
-            |evals: Evaluations<G::ScalarField, R2D<G::ScalarField>>| evals.interpolate();
-        public_input_evals_d1
-            .into_par_iter()
-            .map(interpolate)
-            .collect::<Witness<NPUB, DensePolynomial<G::ScalarField>>>()
-    };
-
     let public_input_comms: Witness<NPUB, PolyComm<G>> = {
-        let comm = |poly: &DensePolynomial<G::ScalarField>| srs.commit_non_hiding(poly, 1);
-        (&public_input_polys)
+        let comm = |evals: &Evaluations<G::ScalarField, R2D<G::ScalarField>>| {
+            srs.commit_evaluations_non_hiding(domain.d1, evals)
+        };
+        (&public_input_evals_d1)
             .into_par_iter()
             .map(comm)
             .collect::<Witness<NPUB, PolyComm<G>>>()
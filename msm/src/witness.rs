@@ -3,6 +3,8 @@ use ark_poly::{Evaluations, Radix2EvaluationDomain};
 use folding::{instance_witness::Foldable, Witness as FoldingWitnessT};
 use poly_commitment::commitment::CommitmentCurve;
 use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+use serde::{Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
 use std::ops::Index;
 
 /// The witness columns used by a gate of the MSM circuits.
@@ -18,6 +20,30 @@ pub struct Witness<const N_WIT: usize, T> {
     pub cols: Box<[T; N_WIT]>,
 }
 
+/// The witness is serialized as the sequence of its columns, each of them
+/// serialized with `U`
+impl<const N_WIT: usize, T, U: SerializeAs<T>> SerializeAs<Witness<N_WIT, T>>
+    for Witness<N_WIT, U>
+{
+    fn serialize_as<S: Serializer>(
+        source: &Witness<N_WIT, T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        <Box<[U; N_WIT]>>::serialize_as(&source.cols, serializer)
+    }
+}
+
+impl<'de, const N_WIT: usize, T, U: DeserializeAs<'de, T>> DeserializeAs<'de, Witness<N_WIT, T>>
+    for Witness<N_WIT, U>
+{
+    fn deserialize_as<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Witness<N_WIT, T>, D::Error> {
+        let cols = <Box<[U; N_WIT]>>::deserialize_as(deserializer)?;
+        Ok(Witness { cols })
+    }
+}
+
 impl<const N_WIT: usize, T: Zero + Clone> Default for Witness<N_WIT, T> {
     fn default() -> Self {
         Witness {
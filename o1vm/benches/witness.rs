@@ -0,0 +1,116 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use o1vm::{
+    cannon::{Meta, Page, Start, State, StepFrequency, VmConfiguration, PAGE_SIZE},
+    keccak::environment::KeccakEnv,
+    mips::{fpu::FpPolicy, witness as mips_witness},
+    preimage_oracle::KeyValuePreImageOracle,
+    Fp,
+};
+
+const NUM_INCREMENTS: usize = 1000;
+
+fn vm_configuration() -> VmConfiguration {
+    VmConfiguration {
+        input_state_file: String::new(),
+        output_state_file: String::new(),
+        metadata_file: String::new(),
+        proof_at: StepFrequency::Never,
+        stop_at: StepFrequency::Never,
+        snapshot_state_at: StepFrequency::Never,
+        info_at: StepFrequency::Never,
+        proof_fmt: String::new(),
+        snapshot_fmt: String::new(),
+        checkpoint_at: StepFrequency::Never,
+        checkpoint_fmt: String::new(),
+        resume_from: None,
+        pprof_cpu: false,
+        host: None,
+        preimage_db_dir: None,
+        preimage_rpc: None,
+        preimage_rpc_method: None,
+        record_journal: None,
+        replay_journal: None,
+        elf_file: None,
+        profile: None,
+        fp_policy: FpPolicy::default(),
+    }
+}
+
+// A program incrementing $t0 NUM_INCREMENTS times before exiting
+fn program_state() -> State {
+    let mut instructions = vec![0x25080001u32; NUM_INCREMENTS]; // addiu $t0, $t0, 1
+    instructions.push(0x24021096); // addiu $v0, $zero, 4246 (exit_group)
+    instructions.push(0x0000000c); // syscall
+    let mut data: Vec<u8> = instructions
+        .into_iter()
+        .flat_map(|instruction| instruction.to_be_bytes())
+        .collect();
+    data.resize(PAGE_SIZE as usize, 0);
+    State {
+        memory: vec![Page { index: 0, data }],
+        preimage_key: [0u8; 32],
+        preimage_offset: 0,
+        pc: 0,
+        next_pc: 4,
+        lo: 0,
+        hi: 0,
+        heap: 0x4000_0000,
+        exit: 0,
+        exited: false,
+        step: 0,
+        registers: [0u32; 32],
+        last_hint: None,
+        preimage: None,
+    }
+}
+
+pub fn bench_mips_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("MIPS witness");
+    group.sample_size(10);
+
+    let config = vm_configuration();
+    let meta = Meta { symbols: vec![] };
+    let start = Start::create(0);
+    group.bench_function(
+        format!("MIPS witness ({} steps)", NUM_INCREMENTS + 2),
+        |b| {
+            b.iter_batched(
+                || {
+                    mips_witness::Env::<Fp, KeyValuePreImageOracle>::create(
+                        PAGE_SIZE as usize,
+                        program_state(),
+                        KeyValuePreImageOracle::new(),
+                    )
+                },
+                |mut env| {
+                    while !env.halt {
+                        black_box(env.step(&config, &meta, &start));
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        },
+    );
+}
+
+pub fn bench_keccak_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Keccak witness");
+    group.sample_size(10);
+
+    // one and four blocks of the sponge
+    for len in [100, 500] {
+        let preimage: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        group.bench_function(format!("keccak witness ({len} bytes)"), |b| {
+            b.iter(|| {
+                let mut env = KeccakEnv::<Fp>::new(0, black_box(&preimage));
+                while env.step.is_some() {
+                    env.step();
+                }
+                env
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_mips_witness, bench_keccak_witness);
+criterion_main!(benches);
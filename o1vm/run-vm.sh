@@ -8,6 +8,8 @@ cargo run --bin o1vm \
       --pprof.cpu \
       --info-at "${INFO_AT:-%10000000}" \
       --snapshot-state-at "${SNAPSHOT_STATE_AT:-%10000000}" \
+      --checkpoint-at "${CHECKPOINT_AT:-%10000000}" \
+      ${RESUME_FROM:+--resume "${RESUME_FROM}"} \
       --proof-at never \
       --stop-at "${STOP_AT:-never}" \
       --input "${ZKVM_STATE_FILENAME:-./state.json}" \
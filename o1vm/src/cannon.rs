@@ -1,17 +1,22 @@
 // Data structure and stuff for compatibility with Cannon
 
+use crate::mips::fpu::FpPolicy;
 use base64::{engine::general_purpose, Engine as _};
 
 use libflate::zlib::{Decoder, Encoder};
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::io::{Read, Write};
+use sha3::{Digest, Keccak256};
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+};
 
 pub const PAGE_ADDRESS_SIZE: u32 = 12;
 pub const PAGE_SIZE: u32 = 1 << PAGE_ADDRESS_SIZE;
 pub const PAGE_ADDRESS_MASK: u32 = PAGE_SIZE - 1;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Page {
     pub index: u32,
     #[serde(deserialize_with = "from_base64", serialize_with = "to_base64")]
@@ -44,7 +49,7 @@ where
 }
 
 // The renaming below keeps compatibility with OP Cannon's state format
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct State {
     pub memory: Vec<Page>,
     #[serde(
@@ -69,6 +74,115 @@ pub struct State {
     pub preimage: Option<Vec<u8>>,
 }
 
+/// Depth of the Merkle tree of a page, whose leaves are its 32-byte words
+const PAGE_TREE_DEPTH: usize = PAGE_ADDRESS_SIZE as usize - 5;
+
+/// Depth of the Merkle tree of the memory above the roots of the pages
+const MEMORY_TREE_DEPTH: usize = 32 - PAGE_ADDRESS_SIZE as usize;
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Roots of the Merkle trees of zero words, indexed by their depth
+fn zero_hashes() -> Vec<[u8; 32]> {
+    let mut hashes = vec![[0u8; 32]];
+    for depth in 0..PAGE_TREE_DEPTH + MEMORY_TREE_DEPTH {
+        hashes.push(hash_pair(&hashes[depth], &hashes[depth]));
+    }
+    hashes
+}
+
+impl Page {
+    /// Merkle root of the page, whose leaves are its 32-byte words, as in
+    /// Cannon
+    pub fn root(&self) -> [u8; 32] {
+        let mut nodes: Vec<[u8; 32]> = (0..PAGE_SIZE as usize)
+            .step_by(32)
+            .map(|offset| {
+                let mut word = [0u8; 32];
+                let end = self.data.len().clamp(offset, offset + 32);
+                word[..end - offset].copy_from_slice(&self.data[offset..end]);
+                word
+            })
+            .collect();
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], &pair[1]))
+                .collect();
+        }
+        nodes[0]
+    }
+}
+
+impl State {
+    /// Hash of the state of the virtual machine, as computed by Cannon
+    /// (`StateHash`): the Keccak hash of the memory root (see
+    /// [State::memory_root]), the preimage key and offset, the special
+    /// registers, the exit code and flag, the step and the general purpose
+    /// registers, in big-endian, whose first byte is replaced by the status of
+    /// the virtual machine.
+    ///
+    /// The hints and the preimage being read are part of the communication
+    /// with the host, and are not included.
+    pub fn root(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.memory_root());
+        hasher.update(self.preimage_key);
+        hasher.update(self.preimage_offset.to_be_bytes());
+        for value in [self.pc, self.next_pc, self.lo, self.hi, self.heap] {
+            hasher.update(value.to_be_bytes());
+        }
+        hasher.update([self.exit, self.exited as u8]);
+        hasher.update(self.step.to_be_bytes());
+        for register in self.registers {
+            hasher.update(register.to_be_bytes());
+        }
+        let mut root: [u8; 32] = hasher.finalize().into();
+        // The status is unfinished, valid, invalid or panic
+        root[0] = match (self.exited, self.exit) {
+            (false, _) => 3,
+            (true, 0) => 0,
+            (true, 1) => 1,
+            (true, _) => 2,
+        };
+        root
+    }
+
+    /// Merkle root of the memory, as computed by Cannon: the leaves are the
+    /// 32-byte words of the 32-bit address space, and the subtrees of the
+    /// pages absent from the state are trees of zero words.
+    pub fn memory_root(&self) -> [u8; 32] {
+        let zero_hashes = zero_hashes();
+        let mut nodes: BTreeMap<u32, [u8; 32]> = self
+            .memory
+            .iter()
+            .map(|page| (page.index, page.root()))
+            .collect();
+        for zero in &zero_hashes[PAGE_TREE_DEPTH..PAGE_TREE_DEPTH + MEMORY_TREE_DEPTH] {
+            let mut parents = BTreeMap::new();
+            for (&index, node) in nodes.iter() {
+                parents.entry(index >> 1).or_insert_with(|| {
+                    let sibling = nodes.get(&(index ^ 1)).unwrap_or(zero);
+                    if index & 1 == 0 {
+                        hash_pair(node, sibling)
+                    } else {
+                        hash_pair(sibling, node)
+                    }
+                });
+            }
+            nodes = parents;
+        }
+        nodes
+            .remove(&0)
+            .unwrap_or(zero_hashes[PAGE_TREE_DEPTH + MEMORY_TREE_DEPTH])
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsePreimageKeyError(String);
 
@@ -213,8 +327,36 @@ pub struct VmConfiguration {
     pub info_at: StepFrequency,
     pub proof_fmt: String,
     pub snapshot_fmt: String,
+    /// When to write a checkpoint of the witness generation, see
+    /// [crate::mips::checkpoint]
+    pub checkpoint_at: StepFrequency,
+    /// Name of the checkpoint files, `%d` being replaced by the step
+    pub checkpoint_fmt: String,
+    /// Checkpoint from which the execution is resumed, instead of the initial
+    /// state
+    pub resume_from: Option<String>,
     pub pprof_cpu: bool,
     pub host: Option<HostProgram>,
+    /// Directory of the local preimage store, used instead of the host program
+    pub preimage_db_dir: Option<String>,
+    /// URL of the JSON-RPC server serving the preimages, used instead of the host program
+    pub preimage_rpc: Option<String>,
+    /// JSON-RPC method used to request a preimage
+    pub preimage_rpc_method: Option<String>,
+    /// File to which the answers of the preimage oracle are recorded, see
+    /// [crate::syscall_journal]
+    pub record_journal: Option<String>,
+    /// File of recorded answers served instead of the preimage oracle
+    pub replay_journal: Option<String>,
+    /// MIPS ELF binary from which the initial state and metadata are loaded,
+    /// instead of the state and metadata files
+    pub elf_file: Option<String>,
+    /// Prefix of the files in which the profile of the execution is written,
+    /// see [crate::mips::profiler]. The execution is not profiled if absent.
+    pub profile: Option<String>,
+    /// What to do with the floating point instructions of the ELF binary, see
+    /// [crate::mips::fpu]
+    pub fp_policy: FpPolicy,
 }
 
 #[derive(Debug, Clone)]
@@ -350,6 +492,95 @@ mod tests {
         serde_json::from_str::<Meta>(META_SAMPLE).unwrap()
     }
 
+    fn dummy_state(pages: Vec<Page>) -> State {
+        State {
+            memory: pages,
+            preimage_key: [0u8; 32],
+            preimage_offset: 0,
+            pc: 0x1000,
+            next_pc: 0x1004,
+            lo: 0,
+            hi: 0,
+            heap: 0x4000_0000,
+            exit: 0,
+            exited: false,
+            step: 0,
+            registers: [0u32; 32],
+            last_hint: None,
+            preimage: None,
+        }
+    }
+
+    #[test]
+    fn test_state_root() {
+        let page = |index: u32, byte: u8| Page {
+            index,
+            data: vec![byte; PAGE_SIZE as usize],
+        };
+        let state = dummy_state(vec![page(0, 1), page(1, 2)]);
+        let root = state.root();
+
+        // The root does not depend on the order of the pages
+        assert_eq!(dummy_state(vec![page(1, 2), page(0, 1)]).root(), root);
+
+        // But it depends on their content
+        assert_ne!(dummy_state(vec![page(0, 1), page(1, 3)]).root(), root);
+
+        // And on the registers
+        let mut other = dummy_state(vec![page(0, 1), page(1, 2)]);
+        other.registers[2] = 4090;
+        assert_ne!(other.root(), root);
+
+        // But not on the communication with the host
+        let mut other = dummy_state(vec![page(0, 1), page(1, 2)]);
+        other.last_hint = Some(vec![1, 2, 3]);
+        assert_eq!(other.root(), root);
+
+        // Its first byte is the status of the virtual machine
+        assert_eq!(root[0], 3);
+        let mut other = dummy_state(vec![page(0, 1), page(1, 2)]);
+        other.exited = true;
+        for (exit, status) in [(0, 0), (1, 1), (2, 2), (255, 2)] {
+            other.exit = exit;
+            assert_eq!(other.root()[0], status);
+        }
+    }
+
+    #[test]
+    fn test_memory_root() {
+        let page = |index: u32, byte: u8| Page {
+            index,
+            data: vec![byte; PAGE_SIZE as usize],
+        };
+        // An absent page is a page of zeroes
+        let empty_root = dummy_state(vec![]).memory_root();
+        assert_eq!(empty_root, zero_hashes()[27]);
+        assert_eq!(dummy_state(vec![page(3, 0)]).memory_root(), empty_root);
+
+        let state = dummy_state(vec![page(0, 1), page(1, 2), page(5, 3)]);
+        let root = state.memory_root();
+
+        // The root does not depend on the order of the pages
+        assert_eq!(
+            dummy_state(vec![page(5, 3), page(0, 1), page(1, 2)]).memory_root(),
+            root
+        );
+        // But it depends on their content and on their index
+        assert_ne!(
+            dummy_state(vec![page(0, 1), page(1, 2), page(5, 4)]).memory_root(),
+            root
+        );
+        assert_ne!(
+            dummy_state(vec![page(0, 1), page(1, 2), page(6, 3)]).memory_root(),
+            root
+        );
+
+        // And not on the registers
+        let mut other = dummy_state(vec![page(0, 1), page(1, 2), page(5, 3)]);
+        other.registers[2] = 4090;
+        assert_eq!(other.memory_root(), root);
+    }
+
     #[test]
     fn test_serialize_deserialize_page() {
         let value: &str = r#"{"index":16,"data":"eJztlkFoE0EUht8k21ZEtFYFg1FCTW0qSGoTS6pFJU3TFlNI07TEQJHE1kJMmhwi1ihaRJCqiAdBKR5Ez4IXvQk5eBaP4iEWpAchV0Hoof5vd14SoQcvve0H/5s3O//OzuzMLHtvNBZVDkUNHLQLUdHugSTKINJgnDoNZB60+MhFBq63Q0G4LCFYQptZoKR9r0hpEc1r4bopy8WRtdptmCJqM+t89RHiY60Xc39M8b26XXUjHLdEbf4qdTyMIWvn9vnyxhTy7eBxGwvGoRWU23ASIqNE5MT4H2DslogOa/EY+f38LxiNKYyrEwW02sV9CJLfgdjnMOfLc0+6biMKHohJFLe2fqO0qLl4Hui0AfcB1H0EzEFTc73GtSfIBO0jnhvnDvpx5CLVIJoKoS7Ic59C2pdfoRpEe+KoC+J7CWnf8leqQf/CbcwbiHP2rcO3TuENfr+C9HcGYp+T15nXnMjdOl/JOyDtc3tUt9tDzto31AXprwuyfCc2SfVsohZ8j7ogPh4Lr7NT+fxV1Yv9pXJ11AXxHYUsX99aVfnWqkT11vcsvk8QnstWJD4EUr0Igt4HqodD0wdP59kIUkH76DvU9IXOXSfnr0tIBe1T5zlAJmrY+xHFICRIG+8p5Lq/YW+djt1tfX/S314ODV/67Wc6eOEZUkF8CxwavqWfSWo/9QWpoH2UhXjtHDhn+E6wzO+EIL4RnEk+nOzDnmWZayRYDyJ6BzkgE3Vjv5faYrjV9F6DuD/eMx+gxvlQlbnndMDdh1TA2G1sbGxsbGxsbGx2Co9Sqvk/2gL/r05DxlgRP8bZK0O50cJQPjMxO5HKhCOlQr8/sVy5uRTuD5RGKuXFaDgYSQ+E/LOlsZlEIZ8NBqKlcmby8mIpPOjPpWYmxwPF06lI+mpqPB+O35ou0l+FGHpe"}"#;
@@ -1,4 +1,4 @@
-use crate::cannon::*;
+use crate::{cannon::*, mips::fpu::FpPolicy};
 use clap::{arg, value_parser, Arg, ArgAction};
 
 pub fn main_cli() -> clap::Command {
@@ -9,6 +9,20 @@ pub fn main_cli() -> clap::Command {
         .arg(arg!(--input <FILE> "initial state file").default_value("state.json"))
         .arg(arg!(--output <FILE> "output state file").default_value("out.json"))
         .arg(arg!(--meta <FILE> "metadata file").default_value("meta.json"))
+        .arg(
+            Arg::new("elf")
+                .long("elf")
+                .value_name("FILE")
+                .help("load the initial state and metadata from a MIPS ELF binary instead"),
+        )
+        .arg(
+            Arg::new("delay-slots")
+                .long("delay-slots")
+                .value_name("MODE")
+                .help("semantics of the branches and jumps of the program")
+                .value_parser(["legacy", "strict", "none"])
+                .default_value("legacy"),
+        )
         // The CLI arguments below this line are ignored at this point
         .arg(
             Arg::new("proof-at")
@@ -62,6 +76,85 @@ pub fn main_cli() -> clap::Command {
                 .default_value("never")
                 .value_parser(step_frequency_parser),
         )
+        .arg(
+            Arg::new("checkpoint-at")
+                .long("checkpoint-at")
+                .value_name("FREQ")
+                .default_value("never")
+                .value_parser(step_frequency_parser)
+                .help("write a checkpoint of the witness generation, e.g. %1000000"),
+        )
+        .arg(
+            Arg::new("checkpoint-fmt")
+                .long("checkpoint-fmt")
+                .value_name("FORMAT")
+                .default_value("checkpoint-%d.msgpack"),
+        )
+        .arg(
+            Arg::new("resume")
+                .long("resume")
+                .value_name("FILE")
+                .help("resume the witness generation from a checkpoint instead of the input state"),
+        )
+        .arg(
+            Arg::new("preimage-db-dir")
+                .long("preimage-db-dir")
+                .value_name("PREIMAGE_DB_DIR")
+                .help(
+                    "read the preimages from a local op-program store instead of the host program",
+                ),
+        )
+        .arg(
+            Arg::new("preimage-rpc")
+                .long("preimage-rpc")
+                .value_name("URL")
+                .help("fetch the preimages from a JSON-RPC server instead of the host program"),
+        )
+        .arg(
+            Arg::new("preimage-rpc-method")
+                .long("preimage-rpc-method")
+                .value_name("METHOD")
+                .help("JSON-RPC method used to request a preimage"),
+        )
+        .arg(
+            Arg::new("record-journal")
+                .long("record-journal")
+                .value_name("FILE")
+                .help("record the answers of the preimage oracle to a journal"),
+        )
+        .arg(
+            Arg::new("replay-journal")
+                .long("replay-journal")
+                .value_name("FILE")
+                .conflicts_with("record-journal")
+                .help("serve the preimages from a recorded journal instead of the oracle"),
+        )
+        .arg(
+            Arg::new("fp-instructions")
+                .long("fp-instructions")
+                .value_name("POLICY")
+                .value_parser(["reject", "warn"])
+                .default_value("reject")
+                .help(
+                    "reject the ELF binaries containing floating point instructions, or only warn",
+                ),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("PREFIX")
+                .help("profile the execution, writing PREFIX.json and PREFIX.folded"),
+        )
+        .subcommand(
+            clap::Command::new("keccak-layout")
+                .about("Export the layout of the Keccak witness columns as JSON")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("file to write the layout to, instead of the standard output"),
+                ),
+        )
 }
 
 pub fn read_configuration(cli: &clap::ArgMatches) -> VmConfiguration {
@@ -75,9 +168,11 @@ pub fn read_configuration(cli: &clap::ArgMatches) -> VmConfiguration {
     let info_at = cli.get_one::<StepFrequency>("info-at").unwrap();
     let stop_at = cli.get_one::<StepFrequency>("stop-at").unwrap();
     let snapshot_state_at = cli.get_one::<StepFrequency>("snapshot-state-at").unwrap();
+    let checkpoint_at = cli.get_one::<StepFrequency>("checkpoint-at").unwrap();
 
     let proof_fmt = cli.get_one::<String>("proof-fmt").unwrap();
     let snapshot_fmt = cli.get_one::<String>("snapshot-fmt").unwrap();
+    let checkpoint_fmt = cli.get_one::<String>("checkpoint-fmt").unwrap();
     let pprof_cpu = cli.get_one::<bool>("pprof-cpu").unwrap();
 
     let host_spec = cli
@@ -108,7 +203,21 @@ pub fn read_configuration(cli: &clap::ArgMatches) -> VmConfiguration {
         info_at: info_at.clone(),
         proof_fmt: proof_fmt.to_string(),
         snapshot_fmt: snapshot_fmt.to_string(),
+        checkpoint_at: checkpoint_at.clone(),
+        checkpoint_fmt: checkpoint_fmt.to_string(),
+        resume_from: cli.get_one::<String>("resume").cloned(),
         pprof_cpu: *pprof_cpu,
         host,
+        preimage_db_dir: cli.get_one::<String>("preimage-db-dir").cloned(),
+        preimage_rpc: cli.get_one::<String>("preimage-rpc").cloned(),
+        preimage_rpc_method: cli.get_one::<String>("preimage-rpc-method").cloned(),
+        record_journal: cli.get_one::<String>("record-journal").cloned(),
+        replay_journal: cli.get_one::<String>("replay-journal").cloned(),
+        elf_file: cli.get_one::<String>("elf").cloned(),
+        profile: cli.get_one::<String>("profile").cloned(),
+        fp_policy: match cli.get_one::<String>("fp-instructions").map(String::as_str) {
+            Some("warn") => FpPolicy::Warn,
+            _ => FpPolicy::Reject,
+        },
     }
 }
@@ -0,0 +1,478 @@
+//! Loader of MIPS ELF32 binaries, producing the initial state of the VM.
+//!
+//! The loader follows the layout used by Cannon:
+//! - the `PT_LOAD` segments are copied at their virtual address (shifted by
+//!   the base address for position-independent executables), and their
+//!   remaining `p_memsz - p_filesz` bytes are zeroed;
+//! - the `R_MIPS_32` and `R_MIPS_REL32` relocations are applied;
+//! - the initialization image of the thread-local storage (`PT_TLS`) is copied
+//!   after the loaded segments, and the thread pointer is set as prescribed by
+//!   the MIPS ABI;
+//! - the stack holds an empty `argc`/`argv`/`envp`/`auxv` block, and the heap
+//!   starts at a fixed address.
+
+use crate::cannon::{Meta, Page, State, Symbol, PAGE_ADDRESS_MASK, PAGE_ADDRESS_SIZE, PAGE_SIZE};
+use elf::{
+    abi::{EM_MIPS, ET_DYN, ET_EXEC, PF_X, PT_LOAD, PT_TLS, SHT_REL, SHT_RELA, STT_FUNC},
+    endian::AnyEndian,
+    file::Class,
+    ElfBytes, ParseError,
+};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// No relocation
+const R_MIPS_NONE: u32 = 0;
+/// Direct 32 bits relocation, `S + A`
+const R_MIPS_32: u32 = 2;
+/// Relative 32 bits relocation, `B + S + A`
+const R_MIPS_REL32: u32 = 3;
+
+/// The register holding the stack pointer
+pub const REGISTER_SP: usize = 29;
+
+/// The register holding the thread pointer. The VM has no `UserLocal` hardware
+/// register, and the thread pointer is given in the destination register of
+/// the `rdhwr $3, $29` instruction emitted by compilers to read it.
+pub const REGISTER_TP: usize = 3;
+
+/// The offset of the thread pointer from the end of the thread control block,
+/// as prescribed by the MIPS ABI
+pub const TP_OFFSET: u32 = 0x7000;
+
+/// The size of the thread control block preceding the TLS block
+pub const TCB_SIZE: u32 = 8;
+
+/// The auxiliary vector entry giving the page size
+const AT_PAGESZ: u32 = 6;
+
+/// Errors that can arise when loading an ELF binary
+#[derive(Error, Debug)]
+pub enum ElfError {
+    #[error("the ELF binary could not be parsed: {0}")]
+    Parse(#[from] ParseError),
+    #[error("the ELF binary is not a 32 bits MIPS executable")]
+    UnsupportedBinary,
+    #[error("the ELF binary has no loadable segment")]
+    NoLoadableSegment,
+    #[error("the segment at address {0:#x} does not fit in the 32 bits address space")]
+    SegmentOutOfRange(u64),
+    #[error("the relocation of type {0} at address {1:#x} is not supported")]
+    UnsupportedRelocation(u32, u64),
+    #[error("the symbol {0} of a relocation does not exist")]
+    MissingSymbol(u32),
+}
+
+/// The configuration of the memory layout of a loaded binary
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ElfLoaderConfig {
+    /// The address at which position-independent executables are loaded
+    pub base_address: u32,
+    /// The initial stack pointer
+    pub stack_pointer: u32,
+    /// The number of bytes allocated below the stack pointer
+    pub stack_size: u32,
+    /// The initial heap pointer
+    pub heap_start: u32,
+}
+
+impl Default for ElfLoaderConfig {
+    fn default() -> Self {
+        ElfLoaderConfig {
+            base_address: 0x0040_0000,
+            stack_pointer: 0x7fff_d000,
+            stack_size: 4 * PAGE_SIZE,
+            heap_start: 0x2000_0000,
+        }
+    }
+}
+
+/// The thread-local storage of a loaded binary
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tls {
+    /// The address of the TLS block
+    pub address: u32,
+    /// The size of the TLS block
+    pub size: u32,
+    /// The thread pointer, stored in [REGISTER_TP]
+    pub thread_pointer: u32,
+}
+
+/// A loaded binary
+#[derive(Debug)]
+pub struct ElfProgram {
+    /// The initial state of the VM
+    pub state: State,
+    /// The function symbols of the binary, if it is not stripped
+    pub meta: Meta,
+    /// The thread-local storage, if the binary uses any
+    pub tls: Option<Tls>,
+    /// The address ranges `[start, end)` of the executable segments
+    pub code: Vec<(u32, u32)>,
+}
+
+/// The memory of a binary being loaded, by page index
+#[derive(Default)]
+struct Memory {
+    pages: BTreeMap<u32, Vec<u8>>,
+    big_endian: bool,
+}
+
+impl Memory {
+    fn byte_mut(&mut self, address: u32) -> &mut u8 {
+        let page = self
+            .pages
+            .entry(address >> PAGE_ADDRESS_SIZE)
+            .or_insert_with(|| vec![0; PAGE_SIZE as usize]);
+        &mut page[(address & PAGE_ADDRESS_MASK) as usize]
+    }
+
+    fn write(&mut self, address: u32, data: &[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            *self.byte_mut(address.wrapping_add(i as u32)) = *byte;
+        }
+    }
+
+    fn zero(&mut self, address: u32, len: u32) {
+        for i in 0..len {
+            *self.byte_mut(address.wrapping_add(i)) = 0;
+        }
+    }
+
+    fn read_u32(&mut self, address: u32) -> u32 {
+        let bytes = std::array::from_fn(|i| *self.byte_mut(address.wrapping_add(i as u32)));
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn write_u32(&mut self, address: u32, value: u32) {
+        let bytes = if self.big_endian {
+            value.to_be_bytes()
+        } else {
+            value.to_le_bytes()
+        };
+        self.write(address, &bytes)
+    }
+
+    fn into_pages(self) -> Vec<Page> {
+        self.pages
+            .into_iter()
+            .map(|(index, data)| Page { index, data })
+            .collect()
+    }
+}
+
+fn to_address(address: u64) -> Result<u32, ElfError> {
+    u32::try_from(address).map_err(|_| ElfError::SegmentOutOfRange(address))
+}
+
+fn align_up(value: u32, align: u32) -> u32 {
+    if align <= 1 {
+        value
+    } else {
+        value.wrapping_add(align - 1) & !(align - 1)
+    }
+}
+
+/// Loads an ELF32 MIPS binary with the default memory layout
+///
+/// # Errors
+///
+/// Will give error if the binary is not a valid 32 bits MIPS executable, or if
+/// it uses relocations that are not supported.
+pub fn load(data: &[u8]) -> Result<ElfProgram, ElfError> {
+    load_with_config(data, &ElfLoaderConfig::default())
+}
+
+/// Loads an ELF32 MIPS binary with the memory layout `config`
+///
+/// # Errors
+///
+/// Will give error if the binary is not a valid 32 bits MIPS executable, or if
+/// it uses relocations that are not supported.
+pub fn load_with_config(data: &[u8], config: &ElfLoaderConfig) -> Result<ElfProgram, ElfError> {
+    let file = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+    if file.ehdr.class != Class::ELF32
+        || file.ehdr.e_machine != EM_MIPS
+        || !(file.ehdr.e_type == ET_EXEC || file.ehdr.e_type == ET_DYN)
+    {
+        return Err(ElfError::UnsupportedBinary);
+    }
+    let base = if file.ehdr.e_type == ET_DYN {
+        config.base_address
+    } else {
+        0
+    };
+    let relocate =
+        |address: u64| -> Result<u32, ElfError> { Ok(to_address(address)?.wrapping_add(base)) };
+
+    let mut memory = Memory {
+        big_endian: matches!(file.ehdr.endianness, AnyEndian::Big),
+        ..Memory::default()
+    };
+
+    // Loadable segments
+    let segments: Vec<_> = file
+        .segments()
+        .map(|s| s.iter().collect())
+        .unwrap_or_default();
+    let mut end_of_segments = None;
+    let mut code = vec![];
+    for phdr in segments.iter().filter(|phdr| phdr.p_type == PT_LOAD) {
+        let address = relocate(phdr.p_vaddr)?;
+        let file_size = to_address(phdr.p_filesz)?;
+        let mem_size = to_address(phdr.p_memsz)?;
+        address
+            .checked_add(mem_size)
+            .ok_or(ElfError::SegmentOutOfRange(phdr.p_vaddr))?;
+        memory.write(address, file.segment_data(phdr)?);
+        memory.zero(address + file_size, mem_size.saturating_sub(file_size));
+        end_of_segments = end_of_segments.max(Some(address + mem_size));
+        if phdr.p_flags & PF_X != 0 {
+            code.push((address, address + mem_size));
+        }
+    }
+    let end_of_segments = end_of_segments.ok_or(ElfError::NoLoadableSegment)?;
+
+    // Relocations
+    let symbol_value = |index: u32| -> Result<u32, ElfError> {
+        if index == 0 {
+            return Ok(0);
+        }
+        let (symbols, _) = file
+            .dynamic_symbol_table()?
+            .or(file.symbol_table()?)
+            .ok_or(ElfError::MissingSymbol(index))?;
+        let symbol = symbols
+            .get(index as usize)
+            .map_err(|_| ElfError::MissingSymbol(index))?;
+        relocate(symbol.st_value)
+    };
+    let mut relocations = vec![];
+    for shdr in file.section_headers().iter().flat_map(|table| table.iter()) {
+        match shdr.sh_type {
+            SHT_REL => relocations.extend(
+                file.section_data_as_rels(&shdr)?
+                    .map(|rel| (rel.r_offset, rel.r_sym, rel.r_type, None)),
+            ),
+            SHT_RELA => relocations.extend(
+                file.section_data_as_relas(&shdr)?
+                    .map(|rela| (rela.r_offset, rela.r_sym, rela.r_type, Some(rela.r_addend))),
+            ),
+            _ => (),
+        }
+    }
+    for (offset, symbol, typ, addend) in relocations {
+        let address = relocate(offset)?;
+        // REL relocations store their addend in the relocated word
+        let addend = addend.map_or_else(|| memory.read_u32(address), |addend| addend as u32);
+        let value = match typ {
+            R_MIPS_NONE => continue,
+            R_MIPS_32 => symbol_value(symbol)?.wrapping_add(addend),
+            R_MIPS_REL32 if symbol == 0 => base.wrapping_add(addend),
+            R_MIPS_REL32 => symbol_value(symbol)?.wrapping_add(addend),
+            _ => return Err(ElfError::UnsupportedRelocation(typ, offset)),
+        };
+        memory.write_u32(address, value);
+    }
+
+    let mut registers = [0u32; 32];
+
+    // Thread-local storage, in the variant I layout of the MIPS ABI:
+    // the thread control block is followed by the TLS block, and the thread
+    // pointer is at a fixed offset from the end of the thread control block
+    let mut tls = None;
+    if let Some(phdr) = segments.iter().find(|phdr| phdr.p_type == PT_TLS) {
+        let align = to_address(phdr.p_align)?.max(TCB_SIZE);
+        let size = to_address(phdr.p_memsz)?;
+        let file_size = to_address(phdr.p_filesz)?;
+        let address = align_up(end_of_segments + TCB_SIZE, align);
+        memory.zero(address - TCB_SIZE, TCB_SIZE);
+        memory.write(address, file.segment_data(phdr)?);
+        memory.zero(address + file_size, size.saturating_sub(file_size));
+        let thread_pointer = address + TP_OFFSET;
+        registers[REGISTER_TP] = thread_pointer;
+        tls = Some(Tls {
+            address,
+            size,
+            thread_pointer,
+        });
+    }
+
+    // Stack, holding argc = 0, an empty argv, an empty envp, and the auxiliary
+    // vector with the page size
+    let sp = config.stack_pointer;
+    memory.zero(sp - config.stack_size, config.stack_size + PAGE_SIZE);
+    for (i, word) in [0, 0, 0, AT_PAGESZ, PAGE_SIZE, 0, 0]
+        .into_iter()
+        .enumerate()
+    {
+        memory.write_u32(sp + 4 * i as u32, word);
+    }
+    registers[REGISTER_SP] = sp;
+
+    // Function symbols
+    let mut symbols = vec![];
+    if let Some((table, strings)) = file.symbol_table()? {
+        for symbol in table.iter() {
+            if symbol.st_symtype() != STT_FUNC || symbol.st_size == 0 {
+                continue;
+            }
+            symbols.push(Symbol {
+                name: strings.get(symbol.st_name as usize)?.to_string(),
+                start: relocate(symbol.st_value)?,
+                size: symbol.st_size as usize,
+            });
+        }
+    }
+    symbols.sort_by_key(|symbol| symbol.start);
+
+    let pc = relocate(file.ehdr.e_entry)?;
+    let state = State {
+        memory: memory.into_pages(),
+        preimage_key: [0u8; 32],
+        preimage_offset: 0,
+        pc,
+        next_pc: pc.wrapping_add(4),
+        lo: 0,
+        hi: 0,
+        heap: config.heap_start,
+        exit: 0,
+        exited: false,
+        step: 0,
+        registers,
+        last_hint: None,
+        preimage: None,
+    };
+    Ok(ElfProgram {
+        state,
+        meta: Meta { symbols },
+        tls,
+        code,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: u32 = 0x0040_0000;
+
+    fn push_u16(out: &mut Vec<u8>, values: &[u16]) {
+        values.iter().for_each(|v| out.extend(v.to_be_bytes()));
+    }
+
+    fn push_u32(out: &mut Vec<u8>, values: &[u32]) {
+        values.iter().for_each(|v| out.extend(v.to_be_bytes()));
+    }
+
+    // A big-endian position-independent executable with a loadable segment,
+    // a TLS segment, two relocations and a function symbol
+    fn create_binary(machine: u16) -> Vec<u8> {
+        let mut out = vec![0x7f, b'E', b'L', b'F', 1, 2, 1];
+        out.resize(16, 0);
+        push_u16(&mut out, &[ET_DYN, machine]);
+        // version, entry, phoff, shoff, flags
+        push_u32(&mut out, &[1, 0x1000, 52, 204, 0]);
+        // ehsize, phentsize, phnum, shentsize, shnum, shstrndx
+        push_u16(&mut out, &[52, 32, 2, 40, 4, 0]);
+
+        // program headers
+        push_u32(&mut out, &[PT_LOAD, 128, 0x1000, 0x1000, 16, 32, 5, 4]);
+        push_u32(&mut out, &[PT_TLS, 144, 0x1010, 0x1010, 4, 8, 4, 4]);
+        out.resize(128, 0);
+
+        // loaded data, with the addend of the REL32 relocation
+        push_u32(&mut out, &[0, 0x1008, 0, 0]);
+        // TLS initialization image
+        push_u32(&mut out, &[0xdead_beef]);
+        // relocations
+        push_u32(
+            &mut out,
+            &[0x1004, R_MIPS_REL32, 0x1008, (1 << 8) | R_MIPS_32],
+        );
+        // symbols
+        push_u32(&mut out, &[0, 0, 0, 0]);
+        push_u32(&mut out, &[1, 0x1000, 16]);
+        out.extend([0x12, 0]);
+        push_u16(&mut out, &[0]);
+        // strings
+        out.extend(b"\0main\0");
+        out.resize(204, 0);
+
+        // section headers
+        push_u32(&mut out, &[0; 10]);
+        push_u32(&mut out, &[0, SHT_REL, 0, 0, 148, 16, 2, 0, 4, 8]);
+        push_u32(
+            &mut out,
+            &[0, elf::abi::SHT_SYMTAB, 0, 0, 164, 32, 3, 1, 4, 16],
+        );
+        push_u32(
+            &mut out,
+            &[0, elf::abi::SHT_STRTAB, 0, 0, 196, 6, 0, 0, 1, 0],
+        );
+        out
+    }
+
+    fn read_u32(state: &State, address: u32) -> u32 {
+        let page = state
+            .memory
+            .iter()
+            .find(|page| page.index == address >> PAGE_ADDRESS_SIZE)
+            .unwrap();
+        let offset = (address & PAGE_ADDRESS_MASK) as usize;
+        u32::from_be_bytes(page.data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_load_elf() {
+        let program = load(&create_binary(EM_MIPS)).unwrap();
+        let state = &program.state;
+        assert_eq!(state.pc, BASE + 0x1000);
+        assert_eq!(state.next_pc, BASE + 0x1004);
+        assert_eq!(state.heap, ElfLoaderConfig::default().heap_start);
+
+        // relocations
+        assert_eq!(read_u32(state, BASE + 0x1004), BASE + 0x1008);
+        assert_eq!(read_u32(state, BASE + 0x1008), BASE + 0x1000);
+        // zeroed end of the segment
+        assert_eq!(read_u32(state, BASE + 0x101c), 0);
+
+        // executable segment
+        assert_eq!(program.code, vec![(BASE + 0x1000, BASE + 0x1020)]);
+
+        // thread-local storage
+        let tls = program.tls.unwrap();
+        assert_eq!(tls.address % 8, 0);
+        assert!(tls.address >= BASE + 0x1020 + TCB_SIZE);
+        assert_eq!(tls.size, 8);
+        assert_eq!(read_u32(state, tls.address), 0xdead_beef);
+        assert_eq!(read_u32(state, tls.address + 4), 0);
+        assert_eq!(state.registers[REGISTER_TP], tls.address + TP_OFFSET);
+
+        // stack
+        let sp = state.registers[REGISTER_SP];
+        assert_eq!(sp, ElfLoaderConfig::default().stack_pointer);
+        assert_eq!(read_u32(state, sp), 0);
+        assert_eq!(read_u32(state, sp + 12), AT_PAGESZ);
+        assert_eq!(read_u32(state, sp + 16), PAGE_SIZE);
+
+        // symbols
+        assert_eq!(
+            program.meta.find_address_symbol(BASE + 0x1008),
+            Some("main".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_elf_errors() {
+        assert!(matches!(
+            load(&create_binary(elf::abi::EM_ARM)),
+            Err(ElfError::UnsupportedBinary)
+        ));
+        assert!(matches!(load(&[0u8; 16]), Err(ElfError::Parse(_))));
+    }
+}
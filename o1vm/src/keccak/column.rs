@@ -17,6 +17,7 @@ use kimchi_msm::{
     columns::{Column, ColumnIndexer},
     witness::Witness,
 };
+use serde::{Deserialize, Serialize};
 use std::ops::{Index, IndexMut};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount, EnumIter};
@@ -53,6 +54,7 @@ pub(crate) const PAD_SUFFIX_LEN: usize = 5;
 const PAD_BYTES_OFF: usize = PAD_SUFFIX_OFF + PAD_SUFFIX_LEN; // Offset of the PadBytesFlags inside the sponge coefficients
 /// The maximum number of padding bytes involved
 pub(crate) const PAD_BYTES_LEN: usize = RATE_IN_BYTES;
+const PAD_MODE_OFF: usize = PAD_BYTES_OFF + PAD_BYTES_LEN; // Offset of the PadMode column inside the sponge coefficients
 
 const FLAG_ROUND_OFF: usize = N_ZKVM_KECCAK_REL_COLS; // Offset of the Round selector inside DynamicSelector
 const FLAG_FST_OFF: usize = FLAG_ROUND_OFF + 1; // Offset of the Absorb(First) selector inside DynamicSelector
@@ -61,6 +63,49 @@ const FLAG_LST_OFF: usize = FLAG_ROUND_OFF + 3; // Offset of the Absorb(Last) se
 const FLAG_ONE_OFF: usize = FLAG_ROUND_OFF + 4; // Offset of the Absorb(Only) selector  inside DynamicSelector
 const FLAG_SQUEEZE_OFF: usize = FLAG_ROUND_OFF + 5; // Offset of the Squeeze selector inside DynamicSelector
 
+/// The sub-tables of the Keccak witness, each of them using a subset of the
+/// columns.
+/// The round steps use all the relation columns, whereas the sponge steps only
+/// use the status columns, the first [SPONGE_COLS] columns of the current row
+/// with the padding flags, and the output columns, about half of them.
+/// The columns of the sponge steps outside of their sub-table are zero and are
+/// not committed to (see [crate::trace::commit_sub_table]), the sub-tables
+/// being connected by the lookups of the [KeccakStepLookup] table on the
+/// inputs and outputs of the steps.
+///
+/// [KeccakStepLookup]: crate::lookups::LookupTableIDs::KeccakStepLookup
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubTable {
+    /// The columns of the round steps
+    Round,
+    /// The columns of the sponge steps
+    Sponge,
+}
+
+impl From<Steps> for SubTable {
+    fn from(step: Steps) -> Self {
+        match step {
+            Round(_) => SubTable::Round,
+            Sponge(_) => SubTable::Sponge,
+        }
+    }
+}
+
+impl SubTable {
+    /// Returns the indices of the columns of the sub-table, including the
+    /// selector columns, in increasing order
+    pub fn columns(&self) -> Vec<usize> {
+        match self {
+            SubTable::Round => (0..N_ZKVM_KECCAK_COLS).collect(),
+            SubTable::Sponge => (0..=PAD_FLAGS_OFF + PAD_MODE_OFF)
+                .chain(NEXT_OFF..NEXT_OFF + NEXT_LEN)
+                .chain(N_ZKVM_KECCAK_REL_COLS..N_ZKVM_KECCAK_COLS)
+                .collect(),
+        }
+    }
+}
+
 /// Column aliases used by the Keccak circuit.
 /// The number of aliases is not necessarily equal to the actual number of
 /// columns.
@@ -105,12 +150,26 @@ pub enum ColumnAlias {
     TwoToPad,             // Only nonzero when Selector(Flag::Pad) = 1 : 2^PadLength
     PadSuffix(usize),     // Only nonzero when Selector(Flag::Pad) = 1 : 5 field elements
     PadBytesFlags(usize), // Only nonzero when Selector(Flag::Pad) = 1 : 136 boolean values
+    PadMode,              // Only nonzero when Selector(Flag::Pad) = 1 : 0 for Keccak | 1 for SHA3
 }
 
 /// Variants of Keccak steps available for the interpreter.
 /// These selectors determine the specific behaviour so that Keccak steps
 /// can be split into different instances for folding
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, EnumIter, EnumCount)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    EnumIter,
+    EnumCount,
+    Serialize,
+    Deserialize,
+)]
 pub enum Steps {
     /// Current step performs a round of the permutation.
     /// The round number stored in the Step is only used for the environment execution.
@@ -120,7 +179,19 @@ pub enum Steps {
 }
 /// Variants of Keccak sponges
 #[derive(
-    Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, EnumIter, EnumCount, Default,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    EnumIter,
+    EnumCount,
+    Default,
+    Serialize,
+    Deserialize,
 )]
 pub enum Sponges {
     Absorb(Absorbs),
@@ -130,7 +201,19 @@ pub enum Sponges {
 
 /// Order of absorb steps in the computation depending on the number of blocks to absorb
 #[derive(
-    Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, EnumIter, EnumCount, Default,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    EnumIter,
+    EnumCount,
+    Default,
+    Serialize,
+    Deserialize,
 )]
 pub enum Absorbs {
     First,  // Also known as the root absorb
@@ -290,10 +373,70 @@ impl From<ColumnAlias> for usize {
                 assert!(i < PAD_BYTES_LEN);
                 PAD_FLAGS_OFF + PAD_BYTES_OFF + i
             }
+            ColumnAlias::PadMode => PAD_FLAGS_OFF + PAD_MODE_OFF,
         }
     }
 }
 
+impl ColumnAlias {
+    /// Returns the alias of the relation column `column` in the steps of
+    /// `sub_table`, the inverse of `usize::from`, or `None` if the column is
+    /// not used by the sub-table.
+    /// Where two aliases overlap, the most specific one is returned (e.g.
+    /// `SpongeZeros` rather than `SpongeNewState`).
+    pub fn from_column(column: usize, sub_table: SubTable) -> Option<Self> {
+        use ColumnAlias::*;
+        // the constructors of the aliases, with their number of columns
+        type Aliases<const N: usize> = [(fn(usize) -> ColumnAlias, usize); N];
+        let shared: Aliases<5> = [
+            (|_| HashIndex, 1),
+            (|_| BlockIndex, 1),
+            (|_| StepIndex, 1),
+            (Input, STATE_LEN),
+            (Output, STATE_LEN),
+        ];
+        let round: Aliases<16> = [
+            (ThetaShiftsC, THETA_SHIFTS_C_LEN),
+            (ThetaDenseC, THETA_DENSE_C_LEN),
+            (ThetaQuotientC, THETA_QUOTIENT_C_LEN),
+            (ThetaRemainderC, THETA_REMAINDER_C_LEN),
+            (ThetaDenseRotC, THETA_DENSE_ROT_C_LEN),
+            (ThetaExpandRotC, THETA_EXPAND_ROT_C_LEN),
+            (PiRhoShiftsE, PIRHO_SHIFTS_E_LEN),
+            (PiRhoDenseE, PIRHO_DENSE_E_LEN),
+            (PiRhoQuotientE, PIRHO_QUOTIENT_E_LEN),
+            (PiRhoRemainderE, PIRHO_REMAINDER_E_LEN),
+            (PiRhoDenseRotE, PIRHO_DENSE_ROT_E_LEN),
+            (PiRhoExpandRotE, PIRHO_EXPAND_ROT_E_LEN),
+            (ChiShiftsB, CHI_SHIFTS_B_LEN),
+            (ChiShiftsSum, CHI_SHIFTS_SUM_LEN),
+            (|_| RoundNumber, 1),
+            (RoundConstants, ROUND_CONST_LEN),
+        ];
+        let sponge: Aliases<9> = [
+            (SpongeZeros, SPONGE_ZEROS_LEN),
+            (SpongeNewState, SPONGE_NEW_STATE_LEN),
+            (SpongeBytes, SPONGE_BYTES_LEN),
+            (SpongeShifts, SPONGE_SHIFTS_LEN),
+            (|_| PadLength, 1),
+            (|_| TwoToPad, 1),
+            (PadSuffix, PAD_SUFFIX_LEN),
+            (PadBytesFlags, PAD_BYTES_LEN),
+            (|_| PadMode, 1),
+        ];
+        let specific: &[_] = match sub_table {
+            SubTable::Round => &round,
+            SubTable::Sponge => &sponge,
+        };
+        shared.iter().chain(specific).find_map(|&(alias, len)| {
+            let offset = usize::from(alias(0));
+            (offset..offset + len)
+                .contains(&column)
+                .then(|| alias(column - offset))
+        })
+    }
+}
+
 /// The witness columns used by the Keccak circuit.
 /// The Keccak circuit is split into two main modes: Sponge and Round.
 /// The columns are shared between the Sponge and Round steps.
@@ -305,7 +448,7 @@ impl From<ColumnAlias> for usize {
 /// - curr: Contains 1965 witnesses used in the current step including Input
 /// - next: Contains the 100 Output witnesses
 /// - round_flags: contain 5 elements with information about the current round step
-/// - pad_flags: PadLength, TwoToPad, PadBytesFlags, PadSuffix
+/// - pad_flags: PadLength, TwoToPad, PadBytesFlags, PadSuffix, PadMode
 /// - mode_flags: what kind of mode is running: round, root, absorb, pad, rootpad, squeeze. Only 1 of them can be active.
 ///
 ///   Keccak Witness Columns: KeccakWitness.cols
@@ -345,11 +488,12 @@ impl From<ColumnAlias> for usize {
 ///
 ///   2073..=2078 -> selectors
 ///
-///   803..=945 -> pad_flags
+///   803..=946 -> pad_flags
 ///             -> 803: PadLength
 ///             -> 804: TwoToPad
 ///             -> 805..=809: PadSuffix
 ///             -> 810..=945: PadBytesFlags
+///             -> 946: PadMode
 ///
 pub type KeccakWitness<T> = Witness<N_ZKVM_KECCAK_REL_COLS, T>;
 
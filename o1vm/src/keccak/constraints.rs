@@ -1,6 +1,13 @@
 //! This module contains the constraints for one Keccak step.
+//!
+//! When created with [Env::recording], the environment also keeps the name of
+//! each constraint, so that the constraints of the circuit can be exported,
+//! with their degrees and the columns they use, see [export_circuit]. The
+//! export can be dumped in JSON or in text, to be compared with the Keccak
+//! specification.
 use crate::{
     keccak::{
+        column::Steps,
         helpers::{ArithHelpers, BoolHelpers, LogupHelpers},
         interpreter::{Interpreter, KeccakInterpreter},
         Constraint, KeccakColumn,
@@ -8,15 +15,21 @@ use crate::{
     lookups::Lookup,
     E,
 };
-use ark_ff::{Field, One};
+use ark_ff::{Field, One, PrimeField};
 use kimchi::{
     circuits::{
-        expr::{ConstantTerm::Literal, Expr, ExprInner, Operations, Variable},
+        expr::{ConstantTerm::Literal, Expr, ExprInner, FormattedOutput, Operations, Variable},
         gate::CurrOrNext,
     },
     o1_utils::Two,
 };
-use kimchi_msm::columns::ColumnIndexer;
+use kimchi_msm::columns::{Column, ColumnIndexer};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+use strum::IntoEnumIterator;
 
 /// This struct contains all that needs to be kept track of during the execution of the Keccak step interpreter
 #[derive(Clone, Debug)]
@@ -25,6 +38,8 @@ pub struct Env<Fp> {
     pub constraints: Vec<E<Fp>>,
     /// Variables that are looked up in the circuit
     pub lookups: Vec<Lookup<E<Fp>>>,
+    /// The names of the constraints, in the same order, if they are recorded
+    pub names: Option<Vec<Constraint>>,
 }
 
 impl<F: Field> Default for Env<F> {
@@ -32,6 +47,132 @@ impl<F: Field> Default for Env<F> {
         Self {
             constraints: Vec::new(),
             lookups: Vec::new(),
+            names: None,
+        }
+    }
+}
+
+impl<F: Field> Env<F> {
+    /// Creates an environment which records the names of the constraints
+    pub fn recording() -> Self {
+        Self {
+            names: Some(Vec::new()),
+            ..Self::default()
+        }
+    }
+}
+
+/// A constraint of the Keccak circuit, exported to be inspected
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedConstraint {
+    /// The name of the constraint, with its indices
+    pub name: String,
+    /// The degree of the constraint in the columns
+    pub degree: u64,
+    /// The columns used by the constraint, sorted
+    pub columns: Vec<String>,
+    /// The constraint, which must evaluate to zero
+    pub expression: String,
+}
+
+/// The constraints of a Keccak step
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedStep {
+    /// The step, with the round number standardized to 0
+    pub step: String,
+    /// The constraints of the step, in the order of the interpreter
+    pub constraints: Vec<ExportedConstraint>,
+}
+
+impl<F: PrimeField> Env<F> {
+    /// Returns the constraints added so far, with their names
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the environment was not created with [Env::recording].
+    pub fn export(&self) -> Vec<ExportedConstraint> {
+        let names = self
+            .names
+            .as_ref()
+            .expect("the names of the constraints must be recorded to export them");
+        self.constraints
+            .iter()
+            .zip(names)
+            .map(|(constraint, name)| {
+                let mut columns = BTreeSet::new();
+                collect_columns(constraint, &mut columns);
+                ExportedConstraint {
+                    name: format!("{:?}", name),
+                    degree: constraint.degree(1, 0),
+                    columns: columns
+                        .into_iter()
+                        .map(|column| column.text(&mut HashMap::new()))
+                        .collect(),
+                    expression: constraint.text_str(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the constraints of each step of the Keccak circuit
+pub fn export_circuit<F: PrimeField>() -> Vec<ExportedStep> {
+    Steps::iter()
+        .flat_map(|step| step.into_iter())
+        .map(|step| {
+            let mut env = Env::<F>::recording();
+            env.constraints(step);
+            ExportedStep {
+                step: format!("{:?}", step),
+                constraints: env.export(),
+            }
+        })
+        .collect()
+}
+
+/// Dumps the exported constraints in text, one constraint per line
+pub fn export_to_text(steps: &[ExportedStep]) -> String {
+    let mut text = String::new();
+    for step in steps {
+        writeln!(
+            text,
+            "# {} ({} constraints)",
+            step.step,
+            step.constraints.len()
+        )
+        .unwrap();
+        for constraint in &step.constraints {
+            writeln!(
+                text,
+                "{} [degree {}] [{}]: {}",
+                constraint.name,
+                constraint.degree,
+                constraint.columns.join(", "),
+                constraint.expression
+            )
+            .unwrap();
+        }
+    }
+    text
+}
+
+// Adds the columns of the cells of `expr` to `columns`
+fn collect_columns<F: Field>(expr: &E<F>, columns: &mut BTreeSet<Column>) {
+    match expr {
+        Operations::Atom(ExprInner::Cell(Variable { col, .. })) => {
+            columns.insert(*col);
+        }
+        Operations::Atom(_) => (),
+        Operations::Pow(x, _)
+        | Operations::Double(x)
+        | Operations::Square(x)
+        | Operations::Cache(_, x) => collect_columns(x, columns),
+        Operations::Add(x, y)
+        | Operations::Mul(x, y)
+        | Operations::Sub(x, y)
+        | Operations::IfFeature(_, x, y) => {
+            collect_columns(x, columns);
+            collect_columns(y, columns);
         }
     }
 }
@@ -66,9 +207,12 @@ impl<F: Field> Interpreter<F> for Env<F> {
         }))
     }
 
-    fn constrain(&mut self, _tag: Constraint, if_true: Self::Variable, x: Self::Variable) {
+    fn constrain(&mut self, tag: Constraint, if_true: Self::Variable, x: Self::Variable) {
         if if_true == Self::Variable::one() {
             self.constraints.push(x);
+            if let Some(names) = &mut self.names {
+                names.push(tag);
+            }
         }
     }
 
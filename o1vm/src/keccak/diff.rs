@@ -0,0 +1,174 @@
+//! This module compares two generators of the Keccak witness side by side, to
+//! debug a witness generator against another one, e.g. the [KeccakEnv] of
+//! this crate against a reference implementation exporting its rows as
+//! [KeccakRows](crate::keccak::layout::KeccakRows).
+//!
+//! A generator is an iterator over the rows of the witness with their step.
+//! [first_difference] runs two generators until the first row where they
+//! differ, and reports the differing column with its [KeccakColumn] alias in
+//! the sub-table of the step, instead of a bare index among the thousands of
+//! columns of a row.
+
+use crate::keccak::{
+    column::{Absorbs, KeccakWitness, Sponges, Steps, SubTable},
+    environment::KeccakEnv,
+    KeccakColumn,
+};
+use ark_ff::Field;
+use kimchi::circuits::polynomials::keccak::constants::ROUNDS;
+use std::fmt;
+
+/// The first difference between two Keccak witnesses
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WitnessDiff<F> {
+    /// The rows have different steps
+    Step {
+        /// The index of the row
+        row: usize,
+        /// The step of the row in the left witness
+        left: Steps,
+        /// The step of the row in the right witness
+        right: Steps,
+    },
+    /// The rows have different values
+    Cell {
+        /// The index of the row
+        row: usize,
+        /// The step of the row
+        step: Steps,
+        /// The first differing column
+        column: usize,
+        /// The alias of the column in the sub-table of the step, if any
+        alias: Option<KeccakColumn>,
+        /// The value of the column in the left witness
+        left: F,
+        /// The value of the column in the right witness
+        right: F,
+        /// The number of differing columns in the row
+        count: usize,
+    },
+    /// The witnesses have the same rows, but not the same number of rows
+    Length {
+        /// The number of rows of the left witness
+        left: usize,
+        /// The number of rows of the right witness
+        right: usize,
+    },
+}
+
+impl<F: fmt::Display> fmt::Display for WitnessDiff<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessDiff::Step { row, left, right } => {
+                write!(
+                    f,
+                    "row {row}: step {left:?} on the left, {right:?} on the right"
+                )
+            }
+            WitnessDiff::Cell {
+                row,
+                step,
+                column,
+                alias,
+                left,
+                right,
+                count,
+            } => {
+                write!(f, "row {row} ({step:?}): column {column}")?;
+                if let Some(alias) = alias {
+                    write!(f, " ({alias:?})")?;
+                }
+                write!(
+                    f,
+                    " is {left} on the left, {right} on the right ({count} differing columns)"
+                )
+            }
+            WitnessDiff::Length { left, right } => {
+                write!(f, "{left} rows on the left, {right} rows on the right")
+            }
+        }
+    }
+}
+
+/// Runs the generators `left` and `right` side by side, and returns their
+/// first difference, or `None` if they generate the same witness
+pub fn first_difference<F: Field>(
+    left: impl IntoIterator<Item = (Steps, KeccakWitness<F>)>,
+    right: impl IntoIterator<Item = (Steps, KeccakWitness<F>)>,
+) -> Option<WitnessDiff<F>> {
+    let (mut left, mut right) = (left.into_iter(), right.into_iter());
+    let mut row = 0;
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return None,
+            (Some(_), None) => {
+                return Some(WitnessDiff::Length {
+                    left: row + 1 + left.count(),
+                    right: row,
+                })
+            }
+            (None, Some(_)) => {
+                return Some(WitnessDiff::Length {
+                    left: row,
+                    right: row + 1 + right.count(),
+                })
+            }
+            (Some((left_step, _)), Some((right_step, _))) if left_step != right_step => {
+                return Some(WitnessDiff::Step {
+                    row,
+                    left: left_step,
+                    right: right_step,
+                })
+            }
+            (Some((step, left_row)), Some((_, right_row))) => {
+                let mut columns = left_row
+                    .cols
+                    .iter()
+                    .zip(right_row.cols.iter())
+                    .enumerate()
+                    .filter(|(_, (l, r))| l != r);
+                if let Some((column, (&left, &right))) = columns.next() {
+                    return Some(WitnessDiff::Cell {
+                        row,
+                        step,
+                        column,
+                        alias: KeccakColumn::from_column(column, SubTable::from(step)),
+                        left,
+                        right,
+                        count: 1 + columns.count(),
+                    });
+                }
+            }
+        }
+        row += 1;
+    }
+}
+
+/// The generator of the rows of the hash of `env`, starting at its current
+/// step
+pub fn env_rows<F: Field>(
+    mut env: KeccakEnv<F>,
+) -> impl Iterator<Item = (Steps, KeccakWitness<F>)> {
+    std::iter::from_fn(move || {
+        let step = env.step?;
+        env.step();
+        Some((step, env.witness_env.witness.clone()))
+    })
+}
+
+/// The steps of a hash of `num_blocks` blocks, in order, to turn the rows of
+/// a hash produced without their steps into a generator
+pub fn hash_steps(num_blocks: usize) -> impl Iterator<Item = Steps> {
+    (0..num_blocks)
+        .flat_map(move |block| {
+            let absorb = match (block, num_blocks) {
+                (_, 1) => Absorbs::Only,
+                (0, _) => Absorbs::First,
+                (b, n) if b == n - 1 => Absorbs::Last,
+                _ => Absorbs::Middle,
+            };
+            std::iter::once(Steps::Sponge(Sponges::Absorb(absorb)))
+                .chain((0..ROUNDS as u64).map(Steps::Round))
+        })
+        .chain(std::iter::once(Steps::Sponge(Sponges::Squeeze)))
+}
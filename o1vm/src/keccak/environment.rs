@@ -16,9 +16,9 @@ use crate::{
         interpreter::KeccakInterpreter,
         pad_blocks, standardize,
         witness::Env as WitnessEnv,
-        KeccakColumn, DIM, HASH_BYTELENGTH, QUARTERS, WORDS_IN_HASH,
+        KeccakColumn, PadMode, DIM, HASH_BYTELENGTH, QUARTERS, WORDS_IN_HASH,
     },
-    lookups::Lookup,
+    lookups::{Lookup, LookupTableIDs::KeccakStepLookup},
     E,
 };
 use ark_ff::Field;
@@ -59,6 +59,8 @@ pub struct KeccakEnv<F> {
     pub(crate) padded: Vec<u8>,
     /// Byte-length of the 10*1 pad (<=136)
     pub(crate) pad_len: u64,
+    /// Padding rule of the hash
+    pub(crate) pad_mode: PadMode,
 
     /// Precomputed 2^pad_len
     two_to_pad: [F; RATE_IN_BYTES],
@@ -66,6 +68,94 @@ pub struct KeccakEnv<F> {
     pad_suffixes: [[F; PAD_SUFFIX_LEN]; RATE_IN_BYTES],
 }
 
+/// The state of a Keccak hash between two steps, which is the entry of the
+/// `KeccakStepLookup` table read by the next step.
+/// It is used to prove a sub-range of the steps of a hash in a segment of its
+/// own, see [KeccakEnv::run_segment].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeccakBoundary {
+    /// Hash index in the circuit
+    pub hash_idx: u64,
+    /// Index of the next step to be executed
+    pub step_idx: u64,
+    /// Next step to be executed, None if the hash has ended
+    pub step: Option<Steps>,
+    /// Expanded state passed to the next step
+    pub state: Vec<u64>,
+}
+
+impl KeccakBoundary {
+    /// Whether this is the boundary before the first step of the hash
+    pub fn is_root(&self) -> bool {
+        self.step_idx == 0
+    }
+
+    /// Whether this is the boundary after the last step of the hash
+    pub fn is_final(&self) -> bool {
+        self.step.is_none()
+    }
+
+    /// Returns the 25 lanes of the state of the boundary, in the order of the
+    /// standard (lane `x + 5y` holds `A[x, y]`)
+    pub fn lanes(&self) -> [u64; DIM * DIM] {
+        let shifts = Keccak::shift(&self.state);
+        let dense = Keccak::collapse(&Keccak::reset(&shifts));
+        array::from_fn(|i| Keccak::compose(&dense[QUARTERS * i..QUARTERS * (i + 1)]))
+    }
+
+    /// Returns the public values of the boundary, in the order of the
+    /// `KeccakStepLookup` entries: hash index, step index and state
+    pub fn public_values<F: Field>(&self) -> Vec<F> {
+        let mut values = Vec::with_capacity(STATE_LEN + 2);
+        values.push(F::from(self.hash_idx));
+        values.push(F::from(self.step_idx));
+        values.extend(self.state.iter().map(|x| F::from(*x)));
+        values
+    }
+}
+
+/// The boundaries of a segment of steps `[start.step_idx, end.step_idx)` of a
+/// Keccak hash.
+///
+/// The steps of a hash are chained with the `KeccakStepLookup` table, in which
+/// each step reads its input and writes its output. In a segment, the input of
+/// its first step and the output of its last step are not balanced by another
+/// row: they are given by the boundaries, which are the public values of the
+/// segment. Two segments are continued by one another if the end of the first
+/// is the start of the second.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeccakSegment {
+    /// The boundary before the first step of the segment
+    pub start: KeccakBoundary,
+    /// The boundary after the last step of the segment
+    pub end: KeccakBoundary,
+}
+
+impl KeccakSegment {
+    /// Returns the lookups balancing the `KeccakStepLookup` entries of the
+    /// segment that are not balanced by its rows: a write of the start
+    /// boundary, unless the segment starts at the root, and a read of the end
+    /// boundary, unless the segment ends the hash
+    pub fn boundary_lookups<F: Field>(&self) -> Vec<Lookup<F>> {
+        let mut lookups = vec![];
+        if !self.start.is_root() {
+            lookups.push(Lookup::write_one(
+                KeccakStepLookup,
+                self.start.public_values(),
+            ));
+        }
+        if !self.end.is_final() {
+            lookups.push(Lookup::read_one(KeccakStepLookup, self.end.public_values()));
+        }
+        lookups
+    }
+
+    /// Whether `next` continues this segment
+    pub fn is_continued_by(&self, next: &KeccakSegment) -> bool {
+        self.end == next.start
+    }
+}
+
 impl<F: Field> Default for KeccakEnv<F> {
     fn default() -> Self {
         Self {
@@ -79,8 +169,9 @@ impl<F: Field> Default for KeccakEnv<F> {
             blocks_left_to_absorb: 0,
             padded: vec![],
             pad_len: 0,
+            pad_mode: PadMode::Keccak,
             two_to_pad: array::from_fn(|i| F::two_pow(1 + i as u64)),
-            pad_suffixes: array::from_fn(|i| pad_blocks::<F>(1 + i)),
+            pad_suffixes: array::from_fn(|i| pad_blocks::<F>(1 + i, PadMode::Keccak)),
         }
     }
 }
@@ -88,14 +179,22 @@ impl<F: Field> Default for KeccakEnv<F> {
 impl<F: Field> KeccakEnv<F> {
     /// Starts a new Keccak environment for a given hash index and bytestring of preimage data
     pub fn new(hash_idx: u64, preimage: &[u8]) -> Self {
+        Self::new_with_mode(hash_idx, preimage, PadMode::Keccak)
+    }
+
+    /// Starts a new environment for a given hash index and bytestring of
+    /// preimage data, padded with the rule of `pad_mode`, so that the digest
+    /// is either Keccak-256 or SHA3-256
+    pub fn new_with_mode(hash_idx: u64, preimage: &[u8], pad_mode: PadMode) -> Self {
         // Must update the flag type at each step from the witness interpretation
         let mut env = KeccakEnv::<F> {
             hash_idx,
+            pad_mode,
             ..Default::default()
         };
-
-        // Store hash index in the witness
-        env.write_column(KeccakColumn::HashIndex, env.hash_idx);
+        if pad_mode != PadMode::Keccak {
+            env.pad_suffixes = array::from_fn(|i| pad_blocks::<F>(1 + i, pad_mode));
+        }
 
         // Update the number of blocks left to be absorbed depending on the length of the preimage
         env.blocks_left_to_absorb = Keccak::num_blocks(preimage.len()) as u64;
@@ -112,13 +211,52 @@ impl<F: Field> KeccakEnv<F> {
         env.prev_block = vec![0u64; STATE_LEN];
 
         // Pad preimage with the 10*1 padding rule
-        env.padded = Keccak::pad(preimage);
+        env.padded = pad_mode.pad(preimage);
         env.block_idx = 0;
         env.pad_len = (env.padded.len() - preimage.len()) as u64;
 
         env
     }
 
+    /// Starts a Keccak environment for a given hash index which only proves
+    /// the Keccak-f[1600] permutation of `state`, given by its 25 lanes in the
+    /// order of the standard (lane `x + 5y` holds `A[x, y]`), without the
+    /// sponge and the padding.
+    ///
+    /// The permutation is the 24 rounds following a single absorb: it is the
+    /// segment of steps `[1, 25)` of a hash, see [KeccakEnv::run_permutation].
+    pub fn new_permutation(hash_idx: u64, state: &[u64; DIM * DIM]) -> Self {
+        let mut env = KeccakEnv::<F> {
+            hash_idx,
+            ..Default::default()
+        };
+
+        // The squeeze step that would follow the rounds is never executed
+        env.blocks_left_to_absorb = 1;
+        env.step = Some(Round(0));
+        env.step_idx = 1;
+
+        let bytes: Vec<u8> = state.iter().flat_map(|lane| lane.to_le_bytes()).collect();
+        env.prev_block = Keccak::expand_state(&bytes);
+
+        env
+    }
+
+    /// Runs the 24 rounds of an environment created with
+    /// [KeccakEnv::new_permutation], calling `push_row` with the step and the
+    /// witness of each of them.
+    ///
+    /// The input and output states are not balanced by other rows: they are
+    /// the boundaries of the returned segment, see
+    /// [KeccakSegment::boundary_lookups]. The output lanes are given by
+    /// [KeccakBoundary::lanes].
+    pub fn run_permutation(
+        &mut self,
+        push_row: impl FnMut(Steps, &KeccakWitness<F>),
+    ) -> KeccakSegment {
+        self.run_segment(self.step_idx + ROUNDS as u64, push_row)
+    }
+
     /// Writes an integer value to a column of the Keccak witness
     pub fn write_column(&mut self, column: KeccakColumn, value: u64) {
         self.write_column_field(column, F::from(value));
@@ -146,6 +284,74 @@ impl<F: Field> KeccakEnv<F> {
         standardize(self.step.unwrap())
     }
 
+    /// Returns the boundary between the last executed step and the next one
+    pub fn boundary(&self) -> KeccakBoundary {
+        KeccakBoundary {
+            hash_idx: self.hash_idx,
+            step_idx: self.step_idx,
+            step: self.step,
+            state: self.prev_block.clone(),
+        }
+    }
+
+    /// Skips the next `n` steps of the hash, only updating the state of the
+    /// environment without assigning the witness, so that a segment can start
+    /// at any step of the hash
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the hash has less than `n` steps left.
+    pub fn skip_steps(&mut self, n: u64) {
+        for _ in 0..n {
+            match self.step.expect("No step to skip") {
+                Sponge(Absorb(_)) => {
+                    let ini_idx = RATE_IN_BYTES * self.block_idx as usize;
+                    let mut block = self.padded[ini_idx..ini_idx + RATE_IN_BYTES].to_vec();
+                    block.append(&mut vec![0; CAPACITY_IN_BYTES]);
+                    let new_state = Keccak::expand_state(&block);
+                    for (x, y) in self.prev_block.iter_mut().zip(new_state) {
+                        *x += y;
+                    }
+                    self.block_idx += 1;
+                }
+                Sponge(Squeeze) => (),
+                Round(round) => {
+                    let state_e = Theta::create(&self.prev_block).state_e();
+                    let state_b = PiRho::create(&state_e).state_b();
+                    let state_f = Chi::create(&state_b).state_f();
+                    self.prev_block = Iota::create(&state_f, round as usize).state_g();
+                }
+            }
+            self.update_step();
+        }
+    }
+
+    /// Runs the steps `[self.step_idx, end)` of the hash, calling `push_row`
+    /// with the step and the witness of each of them, and returns the
+    /// boundaries of the segment, see [KeccakSegment].
+    /// The environment can be brought to the start of the segment with
+    /// [KeccakEnv::skip_steps].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the hash ends before `end`.
+    pub fn run_segment(
+        &mut self,
+        end: u64,
+        mut push_row: impl FnMut(Steps, &KeccakWitness<F>),
+    ) -> KeccakSegment {
+        let start = self.boundary();
+        while self.step_idx < end {
+            let step = self.selector();
+            self.step();
+            push_row(step, &self.witness_env.witness);
+        }
+        KeccakSegment {
+            start,
+            end: self.boundary(),
+        }
+    }
+
     /// Entrypoint for the interpreter. It executes one step of the Keccak circuit (one row),
     /// and updates the environment accordingly (including the witness and inter-step lookups).
     /// When it finishes, it updates the value of the current step, so that the next call to
@@ -153,6 +359,7 @@ impl<F: Field> KeccakEnv<F> {
     pub fn step(&mut self) {
         // Reset columns to zeros to avoid conflicts between steps
         self.null_state();
+        self.write_column(KeccakColumn::HashIndex, self.hash_idx);
 
         match self.step.unwrap() {
             Sponge(typ) => self.run_sponge(typ),
@@ -205,7 +412,7 @@ impl<F: Field> KeccakEnv<F> {
             First | Middle => (), // Step flag has been updated already,
         }
     }
-    /// Sets the flag columns related to padding flags such as `PadLength`, `TwoToPad`, `PadBytesFlags`, and `PadSuffix`.
+    /// Sets the flag columns related to padding flags such as `PadLength`, `TwoToPad`, `PadBytesFlags`, `PadSuffix`, and `PadMode`.
     fn set_flags_pad(&mut self) {
         // Initialize padding columns with precomputed values to speed up interpreter
         self.write_column(KeccakColumn::PadLength, self.pad_len);
@@ -221,6 +428,7 @@ impl<F: Field> KeccakEnv<F> {
         for (idx, value) in pad_suffix.iter().enumerate() {
             self.write_column_field(KeccakColumn::PadSuffix(idx), *value);
         }
+        self.write_column(KeccakColumn::PadMode, self.pad_mode.flag());
     }
 
     /// Assigns the witness values needed in a sponge step (absorb or squeeze)
@@ -442,6 +650,7 @@ impl<F: Field> KeccakEnv<F> {
         let mut env = ConstraintsEnv {
             constraints: vec![],
             lookups: vec![],
+            names: None,
         };
         env.constraints(step);
         env.constraints
@@ -452,6 +661,7 @@ impl<F: Field> KeccakEnv<F> {
         let mut env = ConstraintsEnv {
             constraints: vec![],
             lookups: vec![],
+            names: None,
         };
         env.lookups(step);
         env.lookups
@@ -481,14 +481,18 @@ where
         }
     }
 
-    /// When in Squeeze mode, writes a Lookup containing the 31byte output of the hash (excludes the MSB)
+    /// When in Squeeze mode, writes a Lookup containing the output of the hash, in two limbs: its
+    /// most significant byte, and its 31 other bytes
     /// - if is_squeeze, adds 1 lookup
     /// - otherwise, adds 0 lookups
     fn lookup_syscall_hash(&mut self, step: Steps) {
         let bytes31 = (1..32).fold(Self::zero(), |acc, i| {
             acc * Self::two_pow(8) + self.sponge_byte(i)
         });
-        self.write_syscall(self.is_squeeze(step), vec![self.hash_index(), bytes31]);
+        self.write_syscall(
+            self.is_squeeze(step),
+            vec![self.hash_index(), self.sponge_byte(0), bytes31],
+        );
     }
 
     /// Reads a Lookup containing the input of a step
@@ -515,7 +519,7 @@ where
     fn lookups_sponge(&mut self, step: Steps) {
         // PADDING LOOKUPS
         // Power of two corresponds to 2^pad_length
-        // Pad suffixes correspond to 10*1 rule
+        // Pad suffixes correspond to 10*1 rule, with the domain byte of the mode
         self.lookup_pad(
             self.is_pad(step),
             vec![
@@ -526,6 +530,7 @@ where
                 self.pad_suffix(2),
                 self.pad_suffix(3),
                 self.pad_suffix(4),
+                self.pad_mode(),
             ],
         );
         // BYTES LOOKUPS
@@ -795,6 +800,11 @@ where
         self.variable(KeccakColumn::TwoToPad)
     }
 
+    /// Returns a variable that encodes the padding rule (0 = Keccak, 1 = SHA3)
+    fn pad_mode(&self) -> Self::Variable {
+        self.variable(KeccakColumn::PadMode)
+    }
+
     /// Returns a variable that encodes whether the `idx`-th byte of the new block is involved in the padding (1 = yes)
     fn in_padding(&self, idx: usize) -> Self::Variable {
         self.variable(KeccakColumn::PadBytesFlags(idx))
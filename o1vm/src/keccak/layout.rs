@@ -0,0 +1,412 @@
+//! This module describes the layout of the Keccak witness columns in a
+//! machine-readable way, and provides the serialization of the witness rows,
+//! so that traces can be produced by witness generators external to this
+//! crate.
+//!
+//! The [KeccakLayout] descriptor lists the regions of the relation columns
+//! (see [ColumnAlias]) and the selector columns (see [Steps]), and can be
+//! exported as JSON. The rows serialized as [KeccakRows] are tagged with the
+//! version of the layout, which is bumped every time the column layout
+//! changes, so that traces generated for a different layout are rejected.
+
+use crate::keccak::column::{
+    Absorbs, ColumnAlias, KeccakWitness, Sponges, Steps, SubTable, N_ZKVM_KECCAK_REL_COLS,
+    N_ZKVM_KECCAK_SEL_COLS, PAD_BYTES_LEN, PAD_SUFFIX_LEN, ROUND_CONST_LEN,
+};
+use ark_ff::PrimeField;
+use kimchi::{
+    circuits::polynomials::keccak::constants::{
+        CHI_SHIFTS_B_LEN, CHI_SHIFTS_SUM_LEN, PIRHO_DENSE_E_LEN, PIRHO_DENSE_ROT_E_LEN,
+        PIRHO_EXPAND_ROT_E_LEN, PIRHO_QUOTIENT_E_LEN, PIRHO_REMAINDER_E_LEN, PIRHO_SHIFTS_E_LEN,
+        SPONGE_BYTES_LEN, SPONGE_NEW_STATE_LEN, SPONGE_SHIFTS_LEN, SPONGE_ZEROS_LEN, STATE_LEN,
+        THETA_DENSE_C_LEN, THETA_DENSE_ROT_C_LEN, THETA_EXPAND_ROT_C_LEN, THETA_QUOTIENT_C_LEN,
+        THETA_REMAINDER_C_LEN, THETA_SHIFTS_C_LEN,
+    },
+    o1_utils::serialization::SerdeAs,
+};
+use kimchi_msm::witness::Witness;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{ser::SerializeAsWrap, serde_as, DeserializeAs, SerializeAs};
+use std::marker::PhantomData;
+use strum::IntoEnumIterator;
+
+/// The version of the layout of the Keccak witness columns.
+/// It must be bumped whenever the offset, the length or the meaning of a
+/// column changes.
+pub const KECCAK_LAYOUT_VERSION: u32 = 2;
+
+/// A region of consecutive relation columns, corresponding to a column alias
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    /// The name of the column alias
+    pub name: String,
+    /// The sub-table using the region, or `None` if it is used by both
+    pub sub_table: Option<SubTable>,
+    /// The index of the first column of the region
+    pub offset: usize,
+    /// The number of columns of the region
+    pub length: usize,
+    /// The meaning of the columns of the region
+    pub description: String,
+}
+
+/// A selector column, set to one in the rows of the given step and to zero
+/// otherwise
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selector {
+    /// The name of the step
+    pub step: String,
+    /// The index of the column
+    pub column: usize,
+    /// The meaning of the selector
+    pub description: String,
+}
+
+/// The machine-readable description of the Keccak witness columns.
+/// The regions of the round and sponge sub-tables overlap, the columns of a
+/// row outside of the sub-table of its step being zero.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeccakLayout {
+    /// The version of the layout, see [KECCAK_LAYOUT_VERSION]
+    pub version: u32,
+    /// The number of relation columns of a row, see [N_ZKVM_KECCAK_REL_COLS]
+    pub relation_columns: usize,
+    /// The number of selector columns, located after the relation columns
+    pub selector_columns: usize,
+    /// How the field elements of a row are encoded in [KeccakRows]
+    pub field_encoding: String,
+    /// The regions of the relation columns
+    pub regions: Vec<Region>,
+    /// The selector columns
+    pub selectors: Vec<Selector>,
+}
+
+impl Default for KeccakLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeccakLayout {
+    /// Returns the descriptor of the current layout of the columns
+    pub fn new() -> Self {
+        use ColumnAlias::*;
+        use SubTable::*;
+
+        let region = |alias: ColumnAlias,
+                      name: &str,
+                      sub_table: Option<SubTable>,
+                      length: usize,
+                      description: &str| Region {
+            name: name.to_string(),
+            sub_table,
+            offset: usize::from(alias),
+            length,
+            description: description.to_string(),
+        };
+        let regions = vec![
+            region(HashIndex, "hash_index", None, 1, "identifier of the hash"),
+            region(
+                BlockIndex,
+                "block_index",
+                None,
+                1,
+                "index of the block inside the hash",
+            ),
+            region(
+                StepIndex,
+                "step_index",
+                None,
+                1,
+                "index of the step inside the hash",
+            ),
+            region(
+                Input(0),
+                "input",
+                None,
+                STATE_LEN,
+                "input state of the step, in expanded quarters",
+            ),
+            region(
+                Output(0),
+                "output",
+                None,
+                STATE_LEN,
+                "output state of the step, in expanded quarters",
+            ),
+            region(
+                ThetaShiftsC(0),
+                "theta_shifts_c",
+                Some(Round),
+                THETA_SHIFTS_C_LEN,
+                "shifts of the theta column parities",
+            ),
+            region(
+                ThetaDenseC(0),
+                "theta_dense_c",
+                Some(Round),
+                THETA_DENSE_C_LEN,
+                "dense theta column parities",
+            ),
+            region(
+                ThetaQuotientC(0),
+                "theta_quotient_c",
+                Some(Round),
+                THETA_QUOTIENT_C_LEN,
+                "quotients of the rotation of the theta column parities",
+            ),
+            region(
+                ThetaRemainderC(0),
+                "theta_remainder_c",
+                Some(Round),
+                THETA_REMAINDER_C_LEN,
+                "remainders of the rotation of the theta column parities",
+            ),
+            region(
+                ThetaDenseRotC(0),
+                "theta_dense_rot_c",
+                Some(Round),
+                THETA_DENSE_ROT_C_LEN,
+                "dense rotated theta column parities",
+            ),
+            region(
+                ThetaExpandRotC(0),
+                "theta_expand_rot_c",
+                Some(Round),
+                THETA_EXPAND_ROT_C_LEN,
+                "expanded rotated theta column parities",
+            ),
+            region(
+                PiRhoShiftsE(0),
+                "pirho_shifts_e",
+                Some(Round),
+                PIRHO_SHIFTS_E_LEN,
+                "shifts of the pi-rho state",
+            ),
+            region(
+                PiRhoDenseE(0),
+                "pirho_dense_e",
+                Some(Round),
+                PIRHO_DENSE_E_LEN,
+                "dense pi-rho state",
+            ),
+            region(
+                PiRhoQuotientE(0),
+                "pirho_quotient_e",
+                Some(Round),
+                PIRHO_QUOTIENT_E_LEN,
+                "quotients of the rotation of the pi-rho state",
+            ),
+            region(
+                PiRhoRemainderE(0),
+                "pirho_remainder_e",
+                Some(Round),
+                PIRHO_REMAINDER_E_LEN,
+                "remainders of the rotation of the pi-rho state",
+            ),
+            region(
+                PiRhoDenseRotE(0),
+                "pirho_dense_rot_e",
+                Some(Round),
+                PIRHO_DENSE_ROT_E_LEN,
+                "dense rotated pi-rho state",
+            ),
+            region(
+                PiRhoExpandRotE(0),
+                "pirho_expand_rot_e",
+                Some(Round),
+                PIRHO_EXPAND_ROT_E_LEN,
+                "expanded rotated pi-rho state",
+            ),
+            region(
+                ChiShiftsB(0),
+                "chi_shifts_b",
+                Some(Round),
+                CHI_SHIFTS_B_LEN,
+                "shifts of the chi state",
+            ),
+            region(
+                ChiShiftsSum(0),
+                "chi_shifts_sum",
+                Some(Round),
+                CHI_SHIFTS_SUM_LEN,
+                "shifts of the sums of the chi state",
+            ),
+            region(
+                SpongeNewState(0),
+                "sponge_new_state",
+                Some(Sponge),
+                SPONGE_NEW_STATE_LEN,
+                "state after absorbing or squeezing the block",
+            ),
+            region(
+                SpongeZeros(0),
+                "sponge_zeros",
+                Some(Sponge),
+                SPONGE_ZEROS_LEN,
+                "capacity of the new state, zero in root absorbs",
+            ),
+            region(
+                SpongeBytes(0),
+                "sponge_bytes",
+                Some(Sponge),
+                SPONGE_BYTES_LEN,
+                "bytes of the new state",
+            ),
+            region(
+                SpongeShifts(0),
+                "sponge_shifts",
+                Some(Sponge),
+                SPONGE_SHIFTS_LEN,
+                "shifts of the new state",
+            ),
+            region(
+                RoundNumber,
+                "round_number",
+                Some(Round),
+                1,
+                "number of the round, from 0 to 23",
+            ),
+            region(
+                RoundConstants(0),
+                "round_constants",
+                Some(Round),
+                ROUND_CONST_LEN,
+                "expanded quarters of the round constant",
+            ),
+            region(
+                PadLength,
+                "pad_length",
+                Some(Sponge),
+                1,
+                "number of padding bytes of the block, zero if it is not padded",
+            ),
+            region(
+                TwoToPad,
+                "two_to_pad",
+                Some(Sponge),
+                1,
+                "two to the power of the number of padding bytes",
+            ),
+            region(
+                PadSuffix(0),
+                "pad_suffix",
+                Some(Sponge),
+                PAD_SUFFIX_LEN,
+                "padding suffix of the block, as 1x12 and 4x31 bytes",
+            ),
+            region(
+                PadBytesFlags(0),
+                "pad_bytes_flags",
+                Some(Sponge),
+                PAD_BYTES_LEN,
+                "boolean flags set for the padding bytes of the block",
+            ),
+            region(
+                PadMode,
+                "pad_mode",
+                Some(Sponge),
+                1,
+                "padding rule of the block, 0 for Keccak and 1 for SHA3",
+            ),
+        ];
+
+        let selectors = Steps::iter()
+            .flat_map(|step| step.into_iter())
+            .map(|step| {
+                let (name, description) = match step {
+                    Steps::Round(_) => ("round", "round of the permutation"),
+                    Steps::Sponge(Sponges::Absorb(Absorbs::First)) => {
+                        ("absorb_first", "absorb of the first block of a hash")
+                    }
+                    Steps::Sponge(Sponges::Absorb(Absorbs::Middle)) => {
+                        ("absorb_middle", "absorb of a block neither first nor last")
+                    }
+                    Steps::Sponge(Sponges::Absorb(Absorbs::Last)) => {
+                        ("absorb_last", "absorb of the last, padded, block of a hash")
+                    }
+                    Steps::Sponge(Sponges::Absorb(Absorbs::Only)) => {
+                        ("absorb_only", "absorb of the only, padded, block of a hash")
+                    }
+                    Steps::Sponge(Sponges::Squeeze) => ("squeeze", "squeeze of the digest"),
+                };
+                Selector {
+                    step: name.to_string(),
+                    column: usize::from(step),
+                    description: description.to_string(),
+                }
+            })
+            .collect();
+
+        KeccakLayout {
+            version: KECCAK_LAYOUT_VERSION,
+            relation_columns: N_ZKVM_KECCAK_REL_COLS,
+            selector_columns: N_ZKVM_KECCAK_SEL_COLS,
+            field_encoding: "hex of the 32-byte little-endian canonical serialization".to_string(),
+            regions,
+            selectors,
+        }
+    }
+
+    /// Returns the JSON encoding of the layout
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("The layout is serializable")
+    }
+}
+
+/// A [serde_with] adapter serializing a [Witness] as the sequence of its
+/// columns, each of them being serialized with `U`
+pub struct WitnessAs<U>(PhantomData<U>);
+
+impl<const N: usize, T, U: SerializeAs<T>> SerializeAs<Witness<N, T>> for WitnessAs<U> {
+    fn serialize_as<S: Serializer>(
+        source: &Witness<N, T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(source.cols.iter().map(SerializeAsWrap::<T, U>::new))
+    }
+}
+
+impl<'de, const N: usize, T, U: DeserializeAs<'de, T>> DeserializeAs<'de, Witness<N, T>>
+    for WitnessAs<U>
+{
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Witness<N, T>, D::Error> {
+        let cols: Vec<T> = <Vec<U> as DeserializeAs<'de, Vec<T>>>::deserialize_as(deserializer)?;
+        Witness::try_from(cols).map_err(D::Error::custom)
+    }
+}
+
+/// The rows of a Keccak witness, tagged with the version of the layout of
+/// their columns. The selector columns are not included, as they are derived
+/// from the step of each row.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeccakRows<F: PrimeField> {
+    /// The version of the layout of the rows, see [KECCAK_LAYOUT_VERSION]
+    pub version: u32,
+    /// The relation columns of each row
+    #[serde_as(as = "Vec<WitnessAs<SerdeAs>>")]
+    pub rows: Vec<KeccakWitness<F>>,
+}
+
+impl<F: PrimeField> KeccakRows<F> {
+    /// Tags the rows with the current layout version
+    pub fn new(rows: Vec<KeccakWitness<F>>) -> Self {
+        KeccakRows {
+            version: KECCAK_LAYOUT_VERSION,
+            rows,
+        }
+    }
+
+    /// Returns the rows, or an error if they were produced for another
+    /// version of the layout
+    pub fn into_rows(self) -> Result<Vec<KeccakWitness<F>>, String> {
+        if self.version != KECCAK_LAYOUT_VERSION {
+            return Err(format!(
+                "Layout version mismatch: expected {KECCAK_LAYOUT_VERSION} got {}",
+                self.version
+            ));
+        }
+        Ok(self.rows)
+    }
+}
@@ -3,16 +3,19 @@ use crate::{
     lookups::LookupTableIDs,
 };
 use ark_ff::Field;
-use kimchi::circuits::polynomials::keccak::constants::{
-    DIM, KECCAK_COLS, QUARTERS, RATE_IN_BYTES, STATE_LEN,
+use kimchi::circuits::polynomials::keccak::{
+    constants::{DIM, KECCAK_COLS, QUARTERS, RATE_IN_BYTES, STATE_LEN},
+    Keccak,
 };
 
 pub mod column;
 pub mod constraints;
+pub mod diff;
 pub mod environment;
 pub mod folding;
 pub mod helpers;
 pub mod interpreter;
+pub mod layout;
 #[cfg(test)]
 pub mod tests;
 pub mod trace;
@@ -89,9 +92,46 @@ fn grid_index(length: usize, i: usize, y: usize, x: usize, q: usize) -> usize {
     }
 }
 
+/// The padding rule of a hash instance. Both rules follow the 10*1 padding,
+/// and only differ in the domain separation bits starting the padding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PadMode {
+    /// Ethereum-style Keccak-256, padding with `0x01 0x00* 0x80`
+    #[default]
+    Keccak,
+    /// NIST SHA3-256, padding with `0x06 0x00* 0x80`
+    Sha3,
+}
+
+impl PadMode {
+    /// Returns the byte starting the padding, before adding the final `0x80`
+    pub fn domain_byte(&self) -> u8 {
+        match self {
+            PadMode::Keccak => 0x01,
+            PadMode::Sha3 => 0x06,
+        }
+    }
+
+    /// Returns the value of the `PadMode` column: 0 for Keccak, 1 for SHA3
+    pub fn flag(&self) -> u64 {
+        match self {
+            PadMode::Keccak => 0,
+            PadMode::Sha3 => 1,
+        }
+    }
+
+    /// Pads the message with the padding rule until reaching a length that is
+    /// a multiple of the rate
+    pub fn pad(&self, message: &[u8]) -> Vec<u8> {
+        let mut padded = Keccak::pad(message);
+        padded[message.len()] += self.domain_byte() - PadMode::Keccak.domain_byte();
+        padded
+    }
+}
+
 /// This function returns a vector of field elements that represent the 5 padding suffixes.
 /// The first one uses at most 12 bytes, and the rest use at most 31 bytes.
-pub fn pad_blocks<F: Field>(pad_bytelength: usize) -> [F; PAD_SUFFIX_LEN] {
+pub fn pad_blocks<F: Field>(pad_bytelength: usize, mode: PadMode) -> [F; PAD_SUFFIX_LEN] {
     assert!(pad_bytelength > 0, "Padding length must be at least 1 byte");
     assert!(
         pad_bytelength <= 136,
@@ -100,7 +140,7 @@ pub fn pad_blocks<F: Field>(pad_bytelength: usize) -> [F; PAD_SUFFIX_LEN] {
     // Blocks to store padding. The first one uses at most 12 bytes, and the rest use at most 31 bytes.
     let mut blocks = [F::zero(); PAD_SUFFIX_LEN];
     let mut pad = [F::zero(); RATE_IN_BYTES];
-    pad[RATE_IN_BYTES - pad_bytelength] = F::one();
+    pad[RATE_IN_BYTES - pad_bytelength] = F::from(mode.domain_byte());
     pad[RATE_IN_BYTES - 1] += F::from(0x80u8);
     blocks[0] = pad
         .iter()
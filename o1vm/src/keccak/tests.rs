@@ -1,17 +1,21 @@
 use crate::{
     keccak::{
+        column::SubTable,
         column::{
             Absorbs::*,
             Sponges::*,
             Steps::{self, *},
             N_ZKVM_KECCAK_COLS, N_ZKVM_KECCAK_REL_COLS, N_ZKVM_KECCAK_SEL_COLS,
         },
-        environment::KeccakEnv,
+        constraints::{export_circuit, export_to_text, ExportedStep},
+        diff::{env_rows, first_difference, hash_steps, WitnessDiff},
+        environment::{KeccakEnv, KeccakSegment},
         folding::KeccakConfig,
         interpreter::KeccakInterpreter,
+        layout::{KeccakLayout, KeccakRows, KECCAK_LAYOUT_VERSION},
         trace::DecomposedKeccakTrace,
         Constraint::*,
-        Error, KeccakColumn,
+        Error, KeccakColumn, PadMode,
     },
     lookups::{FixedLookupTables, LookupTable, LookupTableIDs::*},
     trace::{DecomposableTracer, Tracer},
@@ -26,13 +30,20 @@ use folding::{
     FoldingCompatibleExpr, FoldingConfig, FoldingScheme,
 };
 use kimchi::{
-    circuits::polynomials::keccak::{constants::RATE_IN_BYTES, Keccak},
-    o1_utils::{self, FieldHelpers, Two},
+    circuits::polynomials::keccak::{
+        constants::{RATE_IN_BYTES, STATE_LEN},
+        Keccak,
+    },
+    o1_utils::{
+        self,
+        kat::{KatFile, KECCAK_256_SHORT_MSG, SHA3_256_SHORT_MSG},
+        FieldHelpers, Two,
+    },
 };
 use kimchi_msm::test::test_completeness_generic_no_lookups;
 use mina_poseidon::{constants::PlonkSpongeConstantsKimchi, sponge::DefaultFqSponge};
 use rand::{rngs::StdRng, Rng};
-use sha3::{Digest, Keccak256};
+use sha3::{Digest, Keccak256, Sha3_256};
 use std::{
     array,
     collections::{BTreeMap, HashMap},
@@ -111,19 +122,34 @@ fn create_trace_all_steps(domain_size: usize, rng: &mut StdRng) -> DecomposedKec
 
 #[test]
 fn test_pad_blocks() {
-    let blocks_1 = crate::keccak::pad_blocks::<Fp>(1);
+    let blocks_1 = crate::keccak::pad_blocks::<Fp>(1, PadMode::Keccak);
     assert_eq!(blocks_1[0], Fp::from(0x00));
     assert_eq!(blocks_1[1], Fp::from(0x00));
     assert_eq!(blocks_1[2], Fp::from(0x00));
     assert_eq!(blocks_1[3], Fp::from(0x00));
     assert_eq!(blocks_1[4], Fp::from(0x81));
 
-    let blocks_136 = crate::keccak::pad_blocks::<Fp>(136);
+    let blocks_136 = crate::keccak::pad_blocks::<Fp>(136, PadMode::Keccak);
     assert_eq!(blocks_136[0], Fp::from(0x010000000000000000000000u128));
     assert_eq!(blocks_136[1], Fp::from(0x00));
     assert_eq!(blocks_136[2], Fp::from(0x00));
     assert_eq!(blocks_136[3], Fp::from(0x00));
     assert_eq!(blocks_136[4], Fp::from(0x80));
+
+    // SHA3 pads with the domain byte 0x06 instead of 0x01
+    let blocks_1 = crate::keccak::pad_blocks::<Fp>(1, PadMode::Sha3);
+    assert_eq!(blocks_1[0], Fp::from(0x00));
+    assert_eq!(blocks_1[4], Fp::from(0x86));
+
+    let blocks_2 = crate::keccak::pad_blocks::<Fp>(2, PadMode::Sha3);
+    assert_eq!(blocks_2[4], Fp::from(0x0680));
+
+    let blocks_136 = crate::keccak::pad_blocks::<Fp>(136, PadMode::Sha3);
+    assert_eq!(blocks_136[0], Fp::from(0x060000000000000000000000u128));
+    assert_eq!(blocks_136[4], Fp::from(0x80));
+
+    assert_eq!(PadMode::Sha3.pad(&[0xff; 135]).last(), Some(&0x86));
+    assert_eq!(PadMode::Sha3.pad(&[]).len(), RATE_IN_BYTES);
 }
 
 #[test]
@@ -144,7 +170,8 @@ fn test_is_in_table() {
             Fp::zero(),
             Fp::zero(),
             Fp::zero(),
-            Fp::from(0x81) // Least significant chunk of padding suffix
+            Fp::from(0x81), // Least significant chunk of padding suffix
+            Fp::zero(),     // Keccak padding mode
         ]
     )
     .is_some());
@@ -157,10 +184,40 @@ fn test_is_in_table() {
             Fp::zero(),
             Fp::zero(),
             Fp::zero(),
-            Fp::from(0x80) // Least significant chunk of padding suffix
+            Fp::from(0x80), // Least significant chunk of padding suffix
+            Fp::zero(),     // Keccak padding mode
         ]
     )
     .is_some());
+    assert!(LookupTable::is_in_table(
+        &table_pad,
+        vec![
+            Fp::one(),      // Length of padding
+            Fp::two_pow(1), // 2^length of padding
+            Fp::zero(),     // Most significant chunk of padding suffix
+            Fp::zero(),
+            Fp::zero(),
+            Fp::zero(),
+            Fp::from(0x86), // Least significant chunk of padding suffix
+            Fp::one(),      // SHA3 padding mode
+        ]
+    )
+    .is_some());
+    // The Keccak suffix does not hold in SHA3 mode
+    assert!(LookupTable::is_in_table(
+        &table_pad,
+        vec![
+            Fp::one(),
+            Fp::two_pow(1),
+            Fp::zero(),
+            Fp::zero(),
+            Fp::zero(),
+            Fp::zero(),
+            Fp::from(0x81),
+            Fp::one(),
+        ]
+    )
+    .is_none());
     assert!(LookupTable::is_in_table(&table_pad, vec![Fp::from(137u32)]).is_none());
     // RoundConstantsLookup
     assert!(LookupTable::is_in_table(
@@ -534,6 +591,141 @@ fn test_keccak_fake_witness_wont_satisfy_constraints() {
     witness_env[1].errors.clear();
 }
 
+#[test]
+fn test_keccak_sub_tables() {
+    use crate::{keccak::column::SubTable, trace::commit_sub_table, Curve};
+    use ark_poly::{EvaluationDomain, Evaluations, Radix2EvaluationDomain as D};
+    use poly_commitment::{srs::SRS, SRS as _};
+
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    let domain_size = 1 << 4;
+    let domain = D::<Fp>::new(domain_size).unwrap();
+    let mut srs = SRS::<Curve>::create(domain_size);
+    srs.add_lagrange_basis(domain);
+
+    let trace = create_trace_all_steps(domain_size, &mut rng);
+    let sponge_columns = SubTable::Sponge.columns();
+    // The sponge steps only use the status, sponge and pad columns (0..=946),
+    // the next state and the selectors, skipping the round columns
+    assert_eq!(
+        sponge_columns.len(),
+        947 + STATE_LEN + N_ZKVM_KECCAK_SEL_COLS
+    );
+    assert_eq!(SubTable::Round.columns().len(), N_ZKVM_KECCAK_COLS);
+
+    for step in Steps::iter().flat_map(|x| x.into_iter()) {
+        let columns = &trace[step].columns;
+        assert_eq!(*columns, SubTable::from(step).columns());
+        // the columns outside of the sub-table are zero
+        for (i, column) in trace[step].witness.cols.iter().enumerate() {
+            if columns.binary_search(&i).is_err() {
+                assert!(column.iter().all(Zero::is_zero), "{step:?}: column {i}");
+            }
+        }
+    }
+
+    // the commitments are the ones of all the columns
+    let step = Sponge(Absorb(Only));
+    let commitments = commit_sub_table(&srs, domain, &trace[step].witness, &trace[step].columns);
+    for (column, commitment) in trace[step].witness.cols.iter().zip(commitments.cols.iter()) {
+        let evals = Evaluations::from_vec_and_domain(column.clone(), domain);
+        assert_eq!(
+            *commitment,
+            srs.commit_evaluations_non_hiding(domain, &evals)
+        );
+    }
+}
+
+#[test]
+fn test_keccak_segments() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+
+    // Generate 3 blocks of preimage data, for 3 * 25 + 1 steps
+    let bytelength = rng.gen_range(2 * RATE_IN_BYTES..RATE_IN_BYTES * 3);
+    let preimage: Vec<u8> = (0..bytelength).map(|_| rng.gen()).collect();
+    let n_steps = 76;
+
+    // Rows of the full hash
+    let mut keccak_env = KeccakEnv::<Fp>::new(0, &preimage);
+    let mut rows = vec![];
+    let full = keccak_env.run_segment(n_steps, |step, witness| rows.push((step, witness.clone())));
+    assert!(full.start.is_root() && full.end.is_final());
+    assert!(full.boundary_lookups::<Fp>().is_empty());
+
+    // Rows of the segments
+    let bounds = [0, 20, 26, 60, n_steps];
+    let mut segments: Vec<KeccakSegment> = vec![];
+    let mut segment_rows = vec![];
+    for range in bounds.windows(2) {
+        let mut keccak_env = KeccakEnv::<Fp>::new(0, &preimage);
+        keccak_env.skip_steps(range[0]);
+        let segment = keccak_env.run_segment(range[1], |step, witness| {
+            segment_rows.push((step, witness.clone()))
+        });
+        assert_eq!(segment.start.step_idx, range[0]);
+        assert_eq!(segment.end.step_idx, range[1]);
+        if let Some(previous) = segments.last() {
+            assert!(previous.is_continued_by(&segment));
+        }
+        // the first and last rows are bound by the boundaries
+        let (_, first) = &segment_rows[range[0] as usize];
+        let (last_step, last) = &segment_rows[range[1] as usize - 1];
+        assert_eq!(first[KeccakColumn::StepIndex], Fp::from(range[0]));
+        for (idx, value) in segment.start.state.iter().enumerate() {
+            assert_eq!(first[KeccakColumn::Input(idx)], Fp::from(*value));
+        }
+        if *last_step != Sponge(Squeeze) {
+            for (idx, value) in segment.end.state.iter().enumerate() {
+                assert_eq!(last[KeccakColumn::Output(idx)], Fp::from(*value));
+            }
+        }
+        assert_eq!(
+            segment.boundary_lookups::<Fp>().len(),
+            !segment.start.is_root() as usize + !segment.end.is_final() as usize
+        );
+        segments.push(segment);
+    }
+    assert_eq!(segments[0].start, full.start);
+    assert_eq!(segments.last().unwrap().end, full.end);
+    assert_eq!(segment_rows, rows);
+}
+
+#[test]
+fn test_keccak_permutation() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+
+    // The state after absorbing a single block is the padded block followed by
+    // the zeros of the capacity
+    let bytelength = rng.gen_range(0..RATE_IN_BYTES);
+    let preimage: Vec<u8> = (0..bytelength).map(|_| rng.gen()).collect();
+    let mut state_bytes = Keccak::pad(&preimage);
+    state_bytes.resize(200, 0);
+    let state: [u64; 25] =
+        array::from_fn(|i| u64::from_le_bytes(state_bytes[8 * i..8 * (i + 1)].try_into().unwrap()));
+
+    let mut keccak_env = KeccakEnv::<Fp>::new(3, &preimage);
+    let mut rows = vec![];
+    keccak_env.run_segment(26, |step, witness| rows.push((step, witness.clone())));
+
+    let mut permutation_env = KeccakEnv::<Fp>::new_permutation(3, &state);
+    let mut permutation_rows = vec![];
+    let permutation = permutation_env
+        .run_permutation(|step, witness| permutation_rows.push((step, witness.clone())));
+
+    // The rows are the rounds of the hash
+    assert_eq!(permutation_rows, rows[1..25]);
+    assert_eq!(permutation.start.lanes(), state);
+    assert_eq!(permutation.end.step, Some(Sponge(Squeeze)));
+    assert_eq!(permutation.boundary_lookups::<Fp>().len(), 2);
+
+    // The digest is the first 32 bytes of the permuted state
+    let digest: Vec<u8> = permutation.end.lanes()[..4]
+        .iter()
+        .flat_map(|lane| lane.to_le_bytes())
+        .collect();
+    assert_eq!(digest, Keccak256::digest(&preimage).to_vec());
+}
+
 #[test]
 fn test_keccak_multiplicities() {
     let mut rng = o1_utils::tests::make_test_rng(None);
@@ -576,58 +768,135 @@ fn test_keccak_multiplicities() {
     );
 }
 
+// Proves the constraints of every step used to hash the preimage
+fn prove_keccak_steps(preimage: &[u8], rng: &mut StdRng) {
+    let domain_size = 1 << 8;
+
+    // Initialize the environment and run the interpreter
+    let mut keccak_env = KeccakEnv::<Fp>::new(0, preimage);
+
+    // Keep track of the constraints and lookups of the sub-circuits
+    let mut keccak_circuit = <DecomposedKeccakTrace as DecomposableTracer<KeccakEnv<Fp>>>::new(
+        domain_size,
+        &mut keccak_env,
+    );
+
+    while keccak_env.step.is_some() {
+        let step = keccak_env.selector();
+
+        // Run the interpreter, which sets the witness columns
+        keccak_env.step();
+
+        // Add the witness row to the circuit
+        keccak_circuit.push_row(step, &keccak_env.witness_env.witness.cols);
+    }
+    keccak_circuit.pad_witnesses();
+
+    for step in Steps::iter().flat_map(|x| x.into_iter()) {
+        if keccak_circuit.in_circuit(step) {
+            test_completeness_generic_no_lookups::<
+                N_ZKVM_KECCAK_COLS,
+                N_ZKVM_KECCAK_REL_COLS,
+                N_ZKVM_KECCAK_SEL_COLS,
+                0,
+                _,
+            >(
+                keccak_circuit[step].constraints.clone(),
+                Box::new([]),
+                keccak_circuit[step].witness.clone(),
+                domain_size,
+                rng,
+            );
+        }
+    }
+}
+
 // Prover/Verifier test includidng the Keccak constraints
 #[test]
 fn test_keccak_prover_constraints() {
     // guaranteed to have at least 30MB of stack
     stacker::grow(30 * 1024 * 1024, || {
         let mut rng = o1_utils::tests::make_test_rng(None);
-        let domain_size = 1 << 8;
 
         // Generate 3 blocks of preimage data
         let bytelength = rng.gen_range(2 * RATE_IN_BYTES..RATE_IN_BYTES * 3);
         let preimage: Vec<u8> = (0..bytelength).map(|_| rng.gen()).collect();
 
-        // Initialize the environment and run the interpreter
-        let mut keccak_env = KeccakEnv::<Fp>::new(0, &preimage);
+        prove_keccak_steps(&preimage, &mut rng);
+    });
+}
 
-        // Keep track of the constraints and lookups of the sub-circuits
-        let mut keccak_circuit = <DecomposedKeccakTrace as DecomposableTracer<KeccakEnv<Fp>>>::new(
-            domain_size,
-            &mut keccak_env,
-        );
+// Runs the known-answer tests of Keccak-256 through the interpreter, checking
+// the constraints and lookups of every step, and proves a sampled vector
+#[test]
+fn test_keccak_kat() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    let kat = KatFile::parse(KECCAK_256_SHORT_MSG).unwrap();
+    let vectors: Vec<_> = kat.byte_vectors().collect();
 
+    for (preimage, digest) in vectors.iter() {
+        let mut keccak_env = KeccakEnv::<Fp>::new(0, preimage);
         while keccak_env.step.is_some() {
-            let step = keccak_env.selector();
-
-            // Run the interpreter, which sets the witness columns
+            let step = keccak_env.step.unwrap();
             keccak_env.step();
-
-            // Add the witness row to the circuit
-            keccak_circuit.push_row(step, &keccak_env.witness_env.witness.cols);
+            keccak_env.witness_env.constraints(step);
+            keccak_env.witness_env.lookups(step);
+            assert!(keccak_env.witness_env.errors.is_empty());
         }
-        keccak_circuit.pad_witnesses();
+        let output = keccak_env.witness_env.sponge_bytes()[0..32]
+            .iter()
+            .map(|byte| byte.to_bytes()[0])
+            .collect::<Vec<_>>();
+        assert_eq!(output, *digest, "preimage {}", hex::encode(preimage));
+    }
 
-        for step in Steps::iter().flat_map(|x| x.into_iter()) {
-            if keccak_circuit.in_circuit(step) {
-                test_completeness_generic_no_lookups::<
-                    N_ZKVM_KECCAK_COLS,
-                    N_ZKVM_KECCAK_REL_COLS,
-                    N_ZKVM_KECCAK_SEL_COLS,
-                    0,
-                    _,
-                >(
-                    keccak_circuit[step].constraints.clone(),
-                    Box::new([]),
-                    keccak_circuit[step].witness.clone(),
-                    domain_size,
-                    &mut rng,
-                );
-            }
-        }
+    // guaranteed to have at least 30MB of stack
+    stacker::grow(30 * 1024 * 1024, || {
+        let (preimage, _) = vectors[rng.gen_range(0..vectors.len())];
+        prove_keccak_steps(preimage, &mut rng);
     });
 }
 
+// Runs the known-answer tests of SHA3-256 through the interpreter in SHA3
+// padding mode, checking the constraints and lookups of every step
+#[test]
+fn test_sha3_kat() {
+    let kat = KatFile::parse(SHA3_256_SHORT_MSG).unwrap();
+    assert_eq!(kat.output_bits, Some(256));
+    for (preimage, digest) in kat.byte_vectors() {
+        assert_eq!(Sha3_256::digest(preimage).as_slice(), digest);
+
+        let mut keccak_env = KeccakEnv::<Fp>::new_with_mode(0, preimage, PadMode::Sha3);
+        while keccak_env.step.is_some() {
+            let step = keccak_env.step.unwrap();
+            keccak_env.step();
+            keccak_env.witness_env.constraints(step);
+            keccak_env.witness_env.lookups(step);
+            assert!(keccak_env.witness_env.errors.is_empty());
+        }
+        let output = keccak_env.witness_env.sponge_bytes()[0..32]
+            .iter()
+            .map(|byte| byte.to_bytes()[0])
+            .collect::<Vec<_>>();
+        assert_eq!(output, digest, "preimage {}", hex::encode(preimage));
+    }
+}
+
+// A SHA3 witness claiming the Keccak padding mode does not satisfy the
+// padding lookup
+#[test]
+fn test_sha3_padding_with_keccak_mode() {
+    let mut keccak_env = KeccakEnv::<Fp>::new_with_mode(0, b"abc", PadMode::Sha3);
+    let step = keccak_env.step.unwrap();
+    keccak_env.step();
+    keccak_env.witness_env.witness[KeccakColumn::PadMode] = Fp::zero();
+    keccak_env.witness_env.lookups(step);
+    assert_eq!(
+        keccak_env.witness_env.errors,
+        vec![Error::Lookup(PadLookup)]
+    );
+}
+
 fn dummy_constraints() -> BTreeMap<Steps, Vec<FoldingCompatibleExpr<KeccakConfig>>> {
     Steps::iter()
         .flat_map(|x| x.into_iter())
@@ -900,3 +1169,185 @@ fn heavy_test_keccak_folding() {
         check_decomposable_folding_mix((Sponge(Absorb(First)), Round(0)), &mut fq_sponge);
     });
 }
+
+#[test]
+fn test_export_circuit() {
+    let steps = export_circuit::<Fp>();
+
+    // One entry per standardized step, with the constraints of the interpreter
+    assert_eq!(steps.len(), 6);
+    for (exported, step) in steps.iter().zip(Steps::iter().flat_map(|x| x.into_iter())) {
+        assert_eq!(exported.step, format!("{:?}", step));
+        let constraints = KeccakEnv::<Fp>::constraints_of(step);
+        assert_eq!(exported.constraints.len(), constraints.len());
+        for (constraint, expr) in exported.constraints.iter().zip(constraints.iter()) {
+            assert_eq!(constraint.degree, expr.degree(1, 0));
+            assert!(constraint.degree <= 2);
+            assert!(!constraint.columns.is_empty());
+        }
+    }
+    let round = &steps[0];
+    assert_eq!(round.constraints.len(), 389);
+    assert!(round.constraints[0].name.starts_with("ThetaWordC"));
+
+    // The export is deterministic and can be dumped
+    assert_eq!(steps, export_circuit::<Fp>());
+    let json = serde_json::to_string(&steps).unwrap();
+    let decoded: Vec<ExportedStep> = serde_json::from_str(&json).unwrap();
+    assert_eq!(steps, decoded);
+    let text = export_to_text(&steps);
+    assert!(text.starts_with("# Round(0) (389 constraints)\n"));
+    assert_eq!(
+        text.lines().count(),
+        steps.iter().map(|s| 1 + s.constraints.len()).sum::<usize>()
+    );
+}
+
+#[test]
+fn test_keccak_layout_is_consistent() {
+    let layout = KeccakLayout::new();
+    assert_eq!(layout.relation_columns, N_ZKVM_KECCAK_REL_COLS);
+    assert_eq!(layout.selector_columns, N_ZKVM_KECCAK_SEL_COLS);
+    for region in layout.regions.iter() {
+        assert!(
+            region.offset + region.length <= N_ZKVM_KECCAK_REL_COLS,
+            "Region {} out of bounds",
+            region.name
+        );
+        // The columns of a region are committed to in its sub-table
+        if let Some(sub_table) = region.sub_table {
+            let columns = sub_table.columns();
+            assert!((region.offset..region.offset + region.length).all(|c| columns.contains(&c)));
+        }
+    }
+    let mut selectors: Vec<usize> = layout.selectors.iter().map(|s| s.column).collect();
+    selectors.sort();
+    assert_eq!(
+        selectors,
+        (N_ZKVM_KECCAK_REL_COLS..N_ZKVM_KECCAK_COLS).collect::<Vec<_>>()
+    );
+
+    let json = layout.to_json();
+    assert_eq!(serde_json::from_str::<KeccakLayout>(&json).unwrap(), layout);
+}
+
+#[test]
+fn test_keccak_rows_serialization() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    let preimage: Vec<u8> = (0..rng.gen_range(1..300)).map(|_| rng.gen()).collect();
+
+    let mut keccak_env = KeccakEnv::<Fp>::new(0, &preimage);
+    let mut rows = vec![];
+    while keccak_env.step.is_some() {
+        keccak_env.step();
+        rows.push(keccak_env.witness_env.witness.clone());
+    }
+
+    let json = serde_json::to_string(&KeccakRows::new(rows.clone())).unwrap();
+    let read: KeccakRows<Fp> = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.clone().into_rows().unwrap(), rows);
+
+    // Rows produced for another layout are rejected
+    let outdated = KeccakRows {
+        version: KECCAK_LAYOUT_VERSION + 1,
+        ..read
+    };
+    assert!(outdated.into_rows().is_err());
+
+    // Rows with a missing column are rejected
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["rows"][0].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<KeccakRows<Fp>>(value).is_err());
+}
+
+#[test]
+fn test_keccak_column_from_index() {
+    for (alias, sub_table) in [
+        (KeccakColumn::StepIndex, SubTable::Round),
+        (KeccakColumn::Input(42), SubTable::Sponge),
+        (KeccakColumn::ThetaShiftsC(7), SubTable::Round),
+        (KeccakColumn::ChiShiftsSum(399), SubTable::Round),
+        (KeccakColumn::RoundConstants(3), SubTable::Round),
+        (KeccakColumn::SpongeZeros(0), SubTable::Sponge),
+        (KeccakColumn::PadBytesFlags(135), SubTable::Sponge),
+        (KeccakColumn::PadMode, SubTable::Sponge),
+    ] {
+        assert_eq!(
+            KeccakColumn::from_column(usize::from(alias), sub_table),
+            Some(alias)
+        );
+    }
+    // the round constants are not used by the sponge steps
+    let round_number = usize::from(KeccakColumn::RoundNumber);
+    assert_eq!(
+        KeccakColumn::from_column(round_number, SubTable::Sponge),
+        None
+    );
+}
+
+#[test]
+fn test_keccak_witness_diff() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    let preimage: Vec<u8> = (0..rng.gen_range(137..300)).map(|_| rng.gen()).collect();
+    let env = KeccakEnv::<Fp>::new(0, &preimage);
+    let num_blocks = Keccak::num_blocks(preimage.len());
+
+    // A reference witness produced without the steps, as the rows of a
+    // generator exported as JSON
+    let rows: Vec<_> = env_rows(env.clone()).map(|(_, row)| row).collect();
+    assert_eq!(
+        first_difference(
+            env_rows(env.clone()),
+            hash_steps(num_blocks).zip(rows.clone())
+        ),
+        None
+    );
+
+    // A wrong cell in the third round of the first block
+    let alias = KeccakColumn::ThetaShiftsC(7);
+    let mut wrong = rows.clone();
+    wrong[3][alias] += Fp::one();
+    let diff = first_difference(env_rows(env.clone()), hash_steps(num_blocks).zip(wrong));
+    assert_eq!(
+        diff,
+        Some(WitnessDiff::Cell {
+            row: 3,
+            step: Round(2),
+            column: usize::from(alias),
+            alias: Some(alias),
+            left: rows[3][alias],
+            right: rows[3][alias] + Fp::one(),
+            count: 1,
+        })
+    );
+    assert!(diff.unwrap().to_string().contains("ThetaShiftsC(7)"));
+
+    // Rows of the wrong steps
+    let diff = first_difference(
+        env_rows(env.clone()),
+        hash_steps(num_blocks).skip(1).zip(rows.clone()),
+    );
+    assert_eq!(
+        diff,
+        Some(WitnessDiff::Step {
+            row: 0,
+            left: Sponge(Absorb(First)),
+            right: Round(0),
+        })
+    );
+
+    // A missing row
+    let diff = first_difference(
+        env_rows(env),
+        hash_steps(num_blocks)
+            .zip(rows.clone())
+            .take(rows.len() - 1),
+    );
+    assert_eq!(
+        diff,
+        Some(WitnessDiff::Length {
+            left: rows.len(),
+            right: rows.len() - 1,
+        })
+    );
+}
@@ -1,13 +1,14 @@
 use std::{array, collections::BTreeMap};
 
 use ark_ff::Zero;
+use kimchi::o1_utils::transpose::append_transposed;
 use kimchi_msm::witness::Witness;
 use strum::IntoEnumIterator;
 
 use crate::{
     folding::ScalarField,
     keccak::{
-        column::{Steps, N_ZKVM_KECCAK_COLS, N_ZKVM_KECCAK_REL_COLS},
+        column::{Steps, SubTable, N_ZKVM_KECCAK_COLS, N_ZKVM_KECCAK_REL_COLS},
         environment::KeccakEnv,
         standardize,
     },
@@ -63,6 +64,7 @@ impl Tracer<N_ZKVM_KECCAK_REL_COLS, KeccakConfig, KeccakEnv<ScalarField<KeccakCo
             },
             constraints: KeccakEnv::constraints_of(step),
             lookups: KeccakEnv::lookups_of(step),
+            columns: SubTable::from(step).columns(),
         }
     }
 
@@ -78,6 +80,16 @@ impl Tracer<N_ZKVM_KECCAK_REL_COLS, KeccakConfig, KeccakEnv<ScalarField<KeccakCo
         }
     }
 
+    fn push_rows(
+        &mut self,
+        _selector: Self::Selector,
+        rows: &[[ScalarField<KeccakConfig>; N_ZKVM_KECCAK_REL_COLS]],
+    ) {
+        let len = self.witness.cols[0].len();
+        let rows = &rows[..rows.len().min(self.witness.cols[0].capacity() - len)];
+        append_transposed(&mut self.witness.cols[..], rows);
+    }
+
     fn pad_with_row(
         &mut self,
         _selector: Self::Selector,
@@ -87,8 +99,8 @@ impl Tracer<N_ZKVM_KECCAK_REL_COLS, KeccakConfig, KeccakEnv<ScalarField<KeccakCo
         assert!(len <= self.domain_size);
         let rows_to_add = self.domain_size - len;
         // When we reach the domain size, we don't need to pad anymore.
-        for _ in 0..rows_to_add {
-            self.push_row((), row);
+        for (col, value) in self.witness.cols.iter_mut().zip(row.iter()) {
+            col.resize(self.domain_size, *value);
         }
         rows_to_add
     }
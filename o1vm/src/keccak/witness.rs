@@ -34,6 +34,9 @@ pub struct Env<F> {
     pub tables: HashMap<LookupTableIDs, LookupTable<F>>,
     /// The multiplicities of each lookup entry. Should not be cleared between steps.
     pub multiplicities: HashMap<LookupTableIDs, Vec<u32>>,
+    /// The lookups to the syscall channel, which are balanced by the
+    /// environment requesting the hashes. Should not be cleared between steps.
+    pub syscalls: Vec<Lookup<F>>,
     /// If any, an error that occurred during the execution of the constraints, to help with debugging
     pub(crate) errors: Vec<Error>,
 }
@@ -64,6 +67,7 @@ impl<F: Field> Default for Env<F> {
                 m.insert(ResetLookup, vec![0; ResetLookup.length()]);
                 m
             },
+            syscalls: vec![],
             errors: vec![],
         }
     }
@@ -102,6 +106,10 @@ impl<F: Field> Interpreter<F> for Env<F> {
     }
 
     fn add_lookup(&mut self, if_true: Self::Variable, lookup: Lookup<Self::Variable>) {
+        // Keep track of the communication with the requester of the hash
+        if if_true == Self::Variable::one() && lookup.table_id == SyscallLookup {
+            self.syscalls.push(lookup.clone());
+        }
         // Keep track of multiplicities for fixed lookups
         if if_true == Self::Variable::one() && lookup.table_id.is_fixed() {
             // Only when reading. We ignore the other values.
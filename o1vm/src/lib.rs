@@ -8,6 +8,9 @@ pub mod cannon;
 /// A CLI mimicking the Cannon CLI.
 pub mod cannon_cli;
 
+/// Loader of MIPS ELF binaries, producing the initial state of the VM.
+pub mod elf_loader;
+
 /// Integration with folding. Contains common trait implementations to be used
 /// by each circuit.
 pub mod folding;
@@ -21,6 +24,10 @@ pub mod lookups;
 /// MIPS interpreter.
 pub mod mips;
 
+/// Proofs of the Merkle-Patricia tries of Ethereum, hashed with the Keccak
+/// circuit.
+pub mod mpt;
+
 /// Preimage oracle interface used by the zkVM.
 pub mod preimage_oracle;
 
@@ -30,10 +37,21 @@ pub mod proof;
 /// The RAM lookup argument.
 pub mod ramlookup;
 
+/// Splitting long executions into segments of a fixed number of instructions.
+pub mod segments;
+
+/// Recording and replay of the nondeterministic syscall results of an
+/// execution.
+pub mod syscall_journal;
+
 /// Abstract execution traces, possible long, that can be folded.
 /// A trace is a sequence of data points organized in a 2D array, constrained.
 pub mod trace;
 
+/// Storage of the columns of the witness, possibly backed by memory-mapped
+/// files for traces larger than the memory.
+pub mod witness_storage;
+
 use ark_ec::bn::Bn;
 use kimchi::circuits::expr::{ConstantExpr, Expr};
 use kimchi_msm::columns::Column;
@@ -1,7 +1,11 @@
 //! Instantiation of the lookups for the VM project.
 
 use self::LookupTableIDs::*;
-use crate::{keccak::pad_blocks, ramlookup::RAMLookup};
+use crate::{
+    keccak::{pad_blocks, PadMode},
+    mips::isa,
+    ramlookup::RAMLookup,
+};
 use ark_ff::{Field, PrimeField};
 use kimchi::{
     circuits::polynomials::keccak::{
@@ -11,6 +15,7 @@ use kimchi::{
     o1_utils::{FieldHelpers, Two},
 };
 use kimchi_msm::{LogupTable, LogupWitness, LookupTableID};
+use serde::{Deserialize, Serialize};
 
 /// The lookups struct based on RAMLookups for the VM table IDs
 pub(crate) type Lookup<F> = RAMLookup<F, LookupTableIDs>;
@@ -23,12 +28,13 @@ pub(crate) type LookupWitness<F> = LogupWitness<F, LookupTableIDs>;
 pub(crate) type LookupTable<F> = LogupTable<F, LookupTableIDs>;
 
 /// All of the possible lookup table IDs used in the zkVM
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
 pub enum LookupTableIDs {
     // PadLookup ID is 0 because this is the only fixed table whose first entry is not 0.
     // This way, it is guaranteed that the 0 value is not always in the tables after the
     // randomization with the joint combiner is applied.
-    /// All [1..136] values of possible padding lengths, the value 2^len, and the 5 corresponding pad suffixes with the 10*1 rule
+    /// All [1..136] values of possible padding lengths, the value 2^len, the 5 corresponding pad suffixes with the 10*1 rule,
+    /// and the padding mode, first for Keccak then for SHA3 (see [crate::keccak::PadMode])
     PadLookup = 0,
     /// 24-row table with all possible values for round and their round constant in expanded form (in big endian) [0..=23]
     RoundConstantsLookup = 1,
@@ -51,6 +57,10 @@ pub enum LookupTableIDs {
     SyscallLookup = 9,
     /// Input/Output of Keccak steps
     KeccakStepLookup = 10,
+
+    /// The (opcode, funct, selector) rows of the instruction set decoded by the
+    /// MIPS interpreter, generated from [crate::mips::isa::MIPS]
+    DecodingLookup = 11,
 }
 
 impl LookupTableID for LookupTableIDs {
@@ -70,13 +80,14 @@ impl LookupTableID for LookupTableIDs {
             7 => RegisterLookup,
             8 => SyscallLookup,
             9 => KeccakStepLookup,
+            11 => DecodingLookup,
             _ => panic!("Invalid table ID"),
         }
     }
 
     fn length(&self) -> usize {
         match self {
-            PadLookup => RATE_IN_BYTES,
+            PadLookup => 2 * RATE_IN_BYTES,
             RoundConstantsLookup => ROUNDS,
             AtMost4Lookup => 5,
             ByteLookup => 1 << 8,
@@ -84,13 +95,14 @@ impl LookupTableID for LookupTableIDs {
             MemoryLookup | RegisterLookup | SyscallLookup | KeccakStepLookup => {
                 panic!("RAM Tables do not have a fixed length")
             }
+            DecodingLookup => isa::MIPS.rows().count(),
         }
     }
 
     fn is_fixed(&self) -> bool {
         match self {
             PadLookup | RoundConstantsLookup | AtMost4Lookup | ByteLookup | RangeCheck16Lookup
-            | SparseLookup | ResetLookup => true,
+            | SparseLookup | ResetLookup | DecodingLookup => true,
             MemoryLookup | RegisterLookup | SyscallLookup | KeccakStepLookup => false,
         }
     }
@@ -116,6 +128,7 @@ impl LookupTableID for LookupTableIDs {
             Self::RegisterLookup,
             Self::SyscallLookup,
             Self::KeccakStepLookup,
+            Self::DecodingLookup,
         ]
     }
 }
@@ -160,9 +173,15 @@ impl<F: Field> FixedLookupTables<F> for LookupTable<F> {
                 }
             }
             PadLookup => {
-                // Because this table starts with entry 1
-                if idx - 1 < id.length() && table.entries[idx - 1] == value {
-                    Some(idx - 1)
+                // Because this table starts with entry 1, and the SHA3 entries
+                // come after the Keccak ones
+                let idx = if value.last() == Some(&F::one()) {
+                    idx - 1 + RATE_IN_BYTES
+                } else {
+                    idx - 1
+                };
+                if idx < id.length() && table.entries[idx] == value {
+                    Some(idx)
                 } else {
                     None
                 }
@@ -180,6 +199,9 @@ impl<F: Field> FixedLookupTables<F> for LookupTable<F> {
                     None
                 }
             }
+            // The instruction set is small, and its rows are not indexed by
+            // their first value
+            DecodingLookup => table.entries.iter().position(|entry| *entry == value),
             MemoryLookup | RegisterLookup | SyscallLookup | KeccakStepLookup => None,
         }
     }
@@ -187,18 +209,22 @@ impl<F: Field> FixedLookupTables<F> for LookupTable<F> {
     fn table_pad() -> Self {
         Self {
             table_id: PadLookup,
-            entries: (1..=PadLookup.length())
-                .map(|i| {
-                    let suffix = pad_blocks(i);
-                    vec![
-                        F::from(i as u64),
-                        F::two_pow(i as u64),
-                        suffix[0],
-                        suffix[1],
-                        suffix[2],
-                        suffix[3],
-                        suffix[4],
-                    ]
+            entries: [PadMode::Keccak, PadMode::Sha3]
+                .into_iter()
+                .flat_map(|mode| {
+                    (1..=RATE_IN_BYTES).map(move |i| {
+                        let suffix = pad_blocks(i, mode);
+                        vec![
+                            F::from(i as u64),
+                            F::two_pow(i as u64),
+                            suffix[0],
+                            suffix[1],
+                            suffix[2],
+                            suffix[3],
+                            suffix[4],
+                            F::from(mode.flag()),
+                        ]
+                    })
                 })
                 .collect(),
         }
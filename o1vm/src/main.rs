@@ -2,26 +2,29 @@ use ark_ff::UniformRand;
 use folding::decomposable_folding::DecomposableFoldingScheme;
 use kimchi::o1_utils;
 use kimchi_msm::{proof::ProofInputs, prover::prove, verifier::verify, witness::Witness};
-use log::debug;
+use log::{debug, error, info};
 use o1vm::{
     cannon::{self, Meta, Start, State},
-    cannon_cli,
+    cannon_cli, elf_loader,
     keccak::{
         column::{Steps, N_ZKVM_KECCAK_COLS, N_ZKVM_KECCAK_REL_COLS, N_ZKVM_KECCAK_SEL_COLS},
         environment::KeccakEnv,
+        layout::KeccakLayout,
         trace::DecomposedKeccakTrace,
     },
     lookups::LookupTableIDs,
     mips::{
+        checkpoint::Checkpoint,
         column::{N_MIPS_COLS, N_MIPS_REL_COLS, N_MIPS_SEL_COLS},
         constraints as mips_constraints,
         folding::DecomposableMIPSFoldingConfig,
-        interpreter::Instruction,
+        fpu,
+        interpreter::{DelaySlotMode, Instruction},
+        profiler::Profiler,
         trace::DecomposedMIPSTrace,
         witness::{self as mips_witness, SCRATCH_SIZE},
     },
-    preimage_oracle::PreImageOracle,
-    proof,
+    preimage_oracle, proof,
     trace::{DecomposableTracer, Foldable, Tracer},
     BaseSponge, Fp, OpeningProof, ScalarSponge, DOMAIN_SIZE,
 };
@@ -29,36 +32,63 @@ use std::{cmp::Ordering, collections::HashMap, fs::File, io::BufReader, process:
 use strum::IntoEnumIterator;
 
 pub fn main() -> ExitCode {
-    let cli = cannon_cli::main_cli();
+    let cli = cannon_cli::main_cli().get_matches();
+    if let Some(("keccak-layout", sub_matches)) = cli.subcommand() {
+        let layout = KeccakLayout::new().to_json();
+        match sub_matches.get_one::<String>("output") {
+            Some(output) => std::fs::write(output, layout)
+                .unwrap_or_else(|_| panic!("Could not write layout file {}", output)),
+            None => println!("{}", layout),
+        }
+        return ExitCode::SUCCESS;
+    }
 
-    let configuration = cannon_cli::read_configuration(&cli.get_matches());
+    let configuration = cannon_cli::read_configuration(&cli);
 
-    let file =
-        File::open(&configuration.input_state_file).expect("Error opening input state file ");
+    let (state, meta): (State, Meta) = match &configuration.elf_file {
+        Some(elf_file) => {
+            let data = std::fs::read(elf_file)
+                .unwrap_or_else(|_| panic!("Could not open ELF file {}", elf_file));
+            let program = elf_loader::load(&data)
+                .unwrap_or_else(|e| panic!("Error loading ELF file {}: {}", elf_file, e));
+            fpu::check_fp_instructions(&program.state, &program.code, configuration.fp_policy)
+                .unwrap_or_else(|e| panic!("Error loading ELF file {}: {}", elf_file, e));
+            (program.state, program.meta)
+        }
+        None => {
+            let file = File::open(&configuration.input_state_file)
+                .expect("Error opening input state file ");
 
-    let reader = BufReader::new(file);
-    // Read the JSON contents of the file as an instance of `State`.
-    let state: State = serde_json::from_reader(reader).expect("Error reading input state file");
+            let reader = BufReader::new(file);
+            // Read the JSON contents of the file as an instance of `State`.
+            let state: State =
+                serde_json::from_reader(reader).expect("Error reading input state file");
 
-    let meta_file = File::open(&configuration.metadata_file).unwrap_or_else(|_| {
-        panic!(
-            "Could not open metadata file {}",
-            &configuration.metadata_file
-        )
-    });
+            let meta_file = File::open(&configuration.metadata_file).unwrap_or_else(|_| {
+                panic!(
+                    "Could not open metadata file {}",
+                    &configuration.metadata_file
+                )
+            });
 
-    let meta: Meta = serde_json::from_reader(BufReader::new(meta_file)).unwrap_or_else(|_| {
-        panic!(
-            "Error deserializing metadata file {}",
-            &configuration.metadata_file
-        )
-    });
+            let meta: Meta =
+                serde_json::from_reader(BufReader::new(meta_file)).unwrap_or_else(|_| {
+                    panic!(
+                        "Error deserializing metadata file {}",
+                        &configuration.metadata_file
+                    )
+                });
+            (state, meta)
+        }
+    };
 
-    let mut po = PreImageOracle::create(&configuration.host);
-    let _child = po.start();
+    let po = preimage_oracle::create(&configuration);
 
-    // Initialize some data used for statistical computations
-    let start = Start::create(state.step as usize);
+    let delay_slot_mode = match cli.get_one::<String>("delay-slots").map(String::as_str) {
+        Some("strict") => DelaySlotMode::Strict,
+        Some("none") => DelaySlotMode::NoDelaySlots,
+        _ => DelaySlotMode::Legacy,
+    };
 
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
@@ -77,11 +107,32 @@ pub fn main() -> ExitCode {
 
     // Initialize the environments
     // The Keccak environment is extracted inside the loop
-    let mut mips_wit_env =
-        mips_witness::Env::<Fp, PreImageOracle>::create(cannon::PAGE_SIZE as usize, state, po);
-    let mut mips_con_env = mips_constraints::Env::<Fp>::default();
+    let mut mips_wit_env = match &configuration.resume_from {
+        Some(path) => {
+            let checkpoint = Checkpoint::read(path)
+                .unwrap_or_else(|e| panic!("Error reading checkpoint {}: {}", path, e));
+            info!(
+                "Resuming from the checkpoint {}, step {}",
+                path,
+                checkpoint.step()
+            );
+            mips_witness::Env::<Fp, _>::resume(cannon::PAGE_SIZE as usize, checkpoint, po)
+        }
+        None => mips_witness::Env::<Fp, _>::create(cannon::PAGE_SIZE as usize, state, po),
+    };
+    if configuration.profile.is_some() {
+        mips_wit_env.profiler = Some(Profiler::new());
+    }
+    mips_wit_env.delay_slot_mode = delay_slot_mode;
+    let mut mips_con_env = mips_constraints::Env::<Fp> {
+        delay_slot_mode,
+        ..Default::default()
+    };
     // The keccak environment is extracted inside the loop
 
+    // Initialize some data used for statistical computations
+    let start = Start::create(mips_wit_env.instruction_counter as usize);
+
     // Initialize the circuits. Includes pre-folding witnesses.
     let mut mips_trace = DecomposedMIPSTrace::new(DOMAIN_SIZE, &mut mips_con_env);
     let mut keccak_trace = DecomposedKeccakTrace::new(DOMAIN_SIZE, &mut KeccakEnv::<Fp>::default());
@@ -139,6 +190,7 @@ pub fn main() -> ExitCode {
                         &srs,
                         keccak_folded_instance.get_mut(&step).unwrap(),
                         &keccak_trace[step].witness,
+                        &keccak_trace[step].columns,
                     );
                     keccak_trace.reset(step);
                 }
@@ -170,11 +222,26 @@ pub fn main() -> ExitCode {
                 &srs,
                 mips_folded_instance.get_mut(&instr).unwrap(),
                 &mips_trace[instr].witness,
+                &mips_trace[instr].columns,
             );
             mips_trace.reset(instr);
         }
     }
 
+    // A failure to write the profile does not stop the proving
+    if let (Some(prefix), Some(profiler)) = (&configuration.profile, &mips_wit_env.profiler) {
+        match profiler.write(prefix, &meta) {
+            Ok(()) => info!(
+                "Profile of {} steps, touching {} pages, written in {}.json and {}.folded",
+                profiler.num_steps(),
+                profiler.num_pages(),
+                prefix,
+                prefix
+            ),
+            Err(e) => error!("Error writing the profile: {}", e),
+        }
+    }
+
     // Pad any possible remaining rows if the execution was not a multiple of the domain size
     for instr in Instruction::iter().flat_map(|x| x.into_iter()) {
         // Start by padding with the first row
@@ -189,6 +256,7 @@ pub fn main() -> ExitCode {
                 &srs,
                 mips_folded_instance.get_mut(&instr).unwrap(),
                 &mips_trace[instr].witness,
+                &mips_trace[instr].columns,
             );
         }
     }
@@ -202,6 +270,7 @@ pub fn main() -> ExitCode {
                 &srs,
                 keccak_folded_instance.get_mut(&step).unwrap(),
                 &keccak_trace[step].witness,
+                &keccak_trace[step].columns,
             );
         }
     }
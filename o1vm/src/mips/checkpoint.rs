@@ -0,0 +1,249 @@
+//! Checkpoints of the witness generation of the MIPS zkVM.
+//!
+//! A long execution can take hours to be run and proven. To avoid restarting
+//! it from its initial state after a crash, the interpreter can write a
+//! [Checkpoint] every `n` steps (see
+//! [VmConfiguration::checkpoint_at](crate::cannon::VmConfiguration::checkpoint_at)), from
+//! which the witness generation is resumed with
+//! [Env::resume](crate::mips::witness::Env::resume).
+//!
+//! A checkpoint extends the [State] of Cannon with what the witness generation
+//! needs to continue exactly as if it had not been interrupted: the indices of
+//! the last writes to the memory and to the registers, which are used by the
+//! RAM lookups, the position in the preimage being read, and the counter of
+//! the hashed preimages.
+//!
+//! The statistics of the execution (the decoding histogram and the profile)
+//! are not saved, they only cover the execution after the checkpoint.
+//!
+//! NOTE: the proofs created after resuming start from the state of the
+//! checkpoint. The execution must be resumed with the same preimage oracle.
+//!
+//! The checkpoints are encoded with MessagePack, the pages of the memory being
+//! compressed as in the states of Cannon.
+
+use crate::{
+    cannon::State,
+    mips::{registers::Registers, witness::MAX_ACC},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+/// Errors that can arise when writing or reading a checkpoint
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("the checkpoint file could not be accessed: {0}")]
+    Io(#[from] io::Error),
+    #[error("the checkpoint could not be encoded: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("the checkpoint could not be decoded: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+/// The state of the witness generation at the start of a step
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The state of the virtual machine, including the preimage being read
+    pub state: State,
+    /// The step of the last write to each byte of the memory, by page
+    pub memory_write_index: Vec<(u32, Vec<u64>)>,
+    /// The step of the last write to each register
+    pub registers_write_index: Registers<u64>,
+    /// Number of bytes of the current preimage already read
+    pub preimage_bytes_read: u64,
+    /// Key of the preimage being read, if any
+    pub preimage_key: Option<[u8; 32]>,
+    /// Number of preimages hashed so far
+    pub hash_counter: u64,
+}
+
+impl Checkpoint {
+    /// The number of instructions executed before the checkpoint
+    pub fn step(&self) -> u64 {
+        self.state.step / MAX_ACC
+    }
+
+    /// The name of the checkpoint file of the step `step`, obtained by
+    /// replacing `%d` in `fmt` by the step
+    pub fn file_name(fmt: &str, step: u64) -> String {
+        fmt.replace("%d", &step.to_string())
+    }
+
+    /// Writes the checkpoint in the file `path`. The file is first written
+    /// under a temporary name, so that a crash while writing never leaves a
+    /// truncated checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the file cannot be written.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        rmp_serde::encode::write_named(&mut writer, self)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Reads the checkpoint written in the file `path`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the file cannot be read or is not a checkpoint.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, CheckpointError> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(rmp_serde::decode::from_read(reader)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cannon::{Meta, Page, Start, StepFrequency, VmConfiguration, PAGE_SIZE},
+        mips::{fpu::FpPolicy, witness::Env},
+        preimage_oracle::KeyValuePreImageOracle,
+        Fp,
+    };
+
+    const NUM_ITERATIONS: usize = 6;
+
+    fn vm_configuration() -> VmConfiguration {
+        VmConfiguration {
+            input_state_file: String::new(),
+            output_state_file: String::new(),
+            metadata_file: String::new(),
+            proof_at: StepFrequency::Never,
+            stop_at: StepFrequency::Never,
+            snapshot_state_at: StepFrequency::Never,
+            info_at: StepFrequency::Never,
+            proof_fmt: String::new(),
+            snapshot_fmt: String::new(),
+            checkpoint_at: StepFrequency::Never,
+            checkpoint_fmt: String::new(),
+            resume_from: None,
+            pprof_cpu: false,
+            host: None,
+            preimage_db_dir: None,
+            preimage_rpc: None,
+            preimage_rpc_method: None,
+            record_journal: None,
+            replay_journal: None,
+            elf_file: None,
+            profile: None,
+            fp_policy: FpPolicy::default(),
+        }
+    }
+
+    // A program incrementing $t0 and storing it in memory NUM_ITERATIONS times
+    // before exiting
+    fn program_state() -> State {
+        let mut instructions = vec![];
+        for _ in 0..NUM_ITERATIONS {
+            instructions.push(0x25080001u32); // addiu $t0, $t0, 1
+            instructions.push(0xac080100); // sw $t0, 0x100($zero)
+        }
+        instructions.push(0x24021096); // addiu $v0, $zero, 4246 (exit_group)
+        instructions.push(0x0000000c); // syscall
+        let mut data: Vec<u8> = instructions
+            .into_iter()
+            .flat_map(|instruction| instruction.to_be_bytes())
+            .collect();
+        data.resize(PAGE_SIZE as usize, 0);
+        State {
+            memory: vec![Page { index: 0, data }],
+            preimage_key: [0u8; 32],
+            preimage_offset: 0,
+            pc: 0,
+            next_pc: 4,
+            lo: 0,
+            hi: 0,
+            heap: 0x4000_0000,
+            exit: 0,
+            exited: false,
+            step: 0,
+            registers: [0u32; 32],
+            last_hint: None,
+            preimage: None,
+        }
+    }
+
+    // Runs the environment until it halts, returning the witness of each step
+    fn run(env: &mut Env<Fp, KeyValuePreImageOracle>) -> Vec<Vec<Fp>> {
+        let (config, meta) = (vm_configuration(), Meta { symbols: vec![] });
+        let start = Start::create(env.normalized_instruction_counter() as usize);
+        let mut witness = vec![];
+        while !env.halt {
+            env.step(&config, &meta, &start);
+            witness.push(env.scratch_state.to_vec());
+        }
+        witness
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let checkpoint_step = 5;
+        let path =
+            std::env::temp_dir().join(format!("o1vm-checkpoint-{}.msgpack", std::process::id()));
+
+        // The execution without interruption
+        let mut env = Env::<Fp, _>::create(
+            PAGE_SIZE as usize,
+            program_state(),
+            KeyValuePreImageOracle::new(),
+        );
+        let (config, meta) = (vm_configuration(), Meta { symbols: vec![] });
+        let start = Start::create(0);
+        while env.normalized_instruction_counter() < checkpoint_step {
+            env.step(&config, &meta, &start);
+        }
+        env.checkpoint().write(&path).unwrap();
+        let witness = run(&mut env);
+
+        // The execution resumed from the checkpoint
+        let checkpoint = Checkpoint::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checkpoint.step(), checkpoint_step);
+        let mut resumed = Env::<Fp, _>::resume(
+            PAGE_SIZE as usize,
+            checkpoint,
+            KeyValuePreImageOracle::new(),
+        );
+        assert_eq!(run(&mut resumed), witness);
+        assert_eq!(resumed.state().root(), env.state().root());
+        assert_eq!(resumed.memory_write_index, env.memory_write_index);
+        assert_eq!(
+            resumed.registers_write_index.general_purpose,
+            env.registers_write_index.general_purpose
+        );
+        assert_eq!(resumed.state().registers[8], NUM_ITERATIONS as u32);
+    }
+
+    #[test]
+    fn test_checkpoint_file_name() {
+        assert_eq!(
+            Checkpoint::file_name("checkpoint-%d.msgpack", 3_000_000),
+            "checkpoint-3000000.msgpack"
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_checkpoint() {
+        let path = std::env::temp_dir().join(format!(
+            "o1vm-invalid-checkpoint-{}.msgpack",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"not a checkpoint").unwrap();
+        let result = Checkpoint::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(CheckpointError::Decode(_))));
+    }
+}
@@ -28,6 +28,9 @@ pub(crate) const MIPS_PREIMAGE_BYTES_OFF: usize = 85;
 /// Flags indicating whether at least N bytes have been processed in this step.
 /// Contains 4 field elements of boolean type each.
 pub(crate) const MIPS_HAS_N_BYTES_OFF: usize = 89;
+/// The most significant byte of the digest of the preimage, which is not part
+/// of the preimage key, whose most significant byte is its type.
+pub(crate) const MIPS_DIGEST_MSB_OFF: usize = 93;
 
 /// The number of columns used for relation witness in the MIPS circuit
 pub const N_MIPS_REL_COLS: usize = SCRATCH_SIZE + 2;
@@ -104,6 +107,7 @@ impl From<Instruction> for usize {
 /// - the (at most) 4 bytes of the preimage key that are currently being
 ///   processed
 /// - 4 helpers to check if at least n bytes were read in the current row
+/// - the most significant byte of the digest of the current preimage
 pub type MIPSWitness<T> = Witness<N_MIPS_COLS, T>;
 
 // IMPLEMENTATIONS FOR COLUMN ALIAS
@@ -2,11 +2,11 @@ use crate::{
     lookups::{Lookup, LookupTableIDs},
     mips::{
         column::{
-            ColumnAlias as MIPSColumn, MIPS_BYTE_COUNTER_OFF, MIPS_END_OF_PREIMAGE_OFF,
-            MIPS_HASH_COUNTER_OFF, MIPS_HAS_N_BYTES_OFF, MIPS_NUM_BYTES_READ_OFF,
-            MIPS_PREIMAGE_BYTES_OFF, MIPS_PREIMAGE_CHUNK_OFF,
+            ColumnAlias as MIPSColumn, MIPS_BYTE_COUNTER_OFF, MIPS_DIGEST_MSB_OFF,
+            MIPS_END_OF_PREIMAGE_OFF, MIPS_HASH_COUNTER_OFF, MIPS_HAS_N_BYTES_OFF,
+            MIPS_NUM_BYTES_READ_OFF, MIPS_PREIMAGE_BYTES_OFF, MIPS_PREIMAGE_CHUNK_OFF,
         },
-        interpreter::{InterpreterEnv, MIPS_CHUNK_BYTES_LEN},
+        interpreter::{DelaySlotMode, InterpreterEnv, MIPS_CHUNK_BYTES_LEN},
         registers::REGISTER_PREIMAGE_KEY_START,
     },
     E,
@@ -26,6 +26,8 @@ pub struct Env<Fp> {
     /// represented using the expression framework of `kimchi`.
     pub constraints: Vec<E<Fp>>,
     pub lookups: Vec<Lookup<E<Fp>>>,
+    /// The semantics of the control-flow instructions of the circuit
+    pub delay_slot_mode: DelaySlotMode,
 }
 
 impl<Fp: Field> Default for Env<Fp> {
@@ -34,6 +36,7 @@ impl<Fp: Field> Default for Env<Fp> {
             scratch_state_idx: 0,
             constraints: Vec::new(),
             lookups: Vec::new(),
+            delay_slot_mode: DelaySlotMode::default(),
         }
     }
 }
@@ -65,6 +68,10 @@ impl<Fp: Field> InterpreterEnv for Env<Fp> {
         }))
     }
 
+    fn delay_slot_mode(&self) -> DelaySlotMode {
+        self.delay_slot_mode
+    }
+
     fn add_constraint(&mut self, assert_equals_zero: Self::Variable) {
         self.constraints.push(assert_equals_zero)
     }
@@ -550,9 +557,11 @@ impl<Fp: Field> InterpreterEnv for Env<Fp> {
                 ],
             ));
         }
-        // COMMUNICATION CHANNEL: Read hash output
-        // FIXME: check if the most significant byte is zero or 0x02
+        // COMMUNICATION CHANNEL: Read hash output, whose most significant
+        // byte is replaced by the type of the key in the preimage key
+        // FIXME: check if the most significant byte of the key is zero or 0x02
         //       so we know what exactly needs to be passed to the lookup
+        let digest_msb = self.variable(Self::Position::ScratchState(MIPS_DIGEST_MSB_OFF));
         let preimage_key = (0..8).fold(Expr::from(0), |acc, i| {
             acc * Expr::from(2u64.pow(32))
                 + self.variable(Self::Position::ScratchState(
@@ -567,7 +576,7 @@ impl<Fp: Field> InterpreterEnv for Env<Fp> {
         self.add_lookup(Lookup::read_if(
             end_of_preimage,
             LookupTableIDs::SyscallLookup,
-            vec![hash_counter, preimage_key],
+            vec![hash_counter, digest_msb, preimage_key],
         ));
 
         // Byte checks with lookups: The preimage bytes are checked to be 8-bits
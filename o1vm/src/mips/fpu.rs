@@ -0,0 +1,141 @@
+//! Detection of the floating point instructions of a MIPS program.
+//!
+//! The VM has no floating point unit, and the guest programs must be built
+//! with soft-float. The C libraries built this way may still contain a few
+//! floating point instructions, for instance to save the floating point
+//! registers in `setjmp` or to handle the floating point environment, which
+//! stop the execution when they are reached.
+//!
+//! The loaded code is therefore scanned for these instructions before the
+//! execution ([check_fp_instructions]), and the program is rejected with the
+//! list of their addresses, or only warned about them with
+//! [FpPolicy::Warn] when they are known to be unreachable. An instruction
+//! reached during the execution still stops it, with its address
+//! ([FpInstruction]).
+//!
+//! The instructions of the coprocessor 1 (`COP1` and `COP1X`), its loads and
+//! stores (`lwc1`, `ldc1`, `swc1` and `sdc1`), and the moves conditioned on its
+//! flags (`movf` and `movt`) are floating point instructions.
+
+use crate::cannon::{State, PAGE_ADDRESS_MASK, PAGE_ADDRESS_SIZE};
+use log::warn;
+use std::{collections::HashMap, fmt};
+use thiserror::Error;
+
+/// `COP1`, the arithmetic and the moves of the floating point unit
+const OPCODE_COP1: u32 = 0x11;
+/// `COP1X`, the indexed loads and stores and the fused operations
+const OPCODE_COP1X: u32 = 0x13;
+/// `lwc1`, `ldc1`, `swc1` and `sdc1`
+const OPCODES_FP_MEMORY: [u32; 4] = [0x31, 0x35, 0x39, 0x3d];
+/// `movf` and `movt`, in the `SPECIAL` opcode
+const FUNCT_MOVCI: u32 = 0x01;
+
+/// Returns true if `instruction` is a floating point instruction
+pub fn is_fp_instruction(instruction: u32) -> bool {
+    match instruction >> 26 {
+        0x00 => instruction & 0x3F == FUNCT_MOVCI,
+        OPCODE_COP1 | OPCODE_COP1X => true,
+        opcode => OPCODES_FP_MEMORY.contains(&opcode),
+    }
+}
+
+/// What to do with a program containing floating point instructions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FpPolicy {
+    /// The program is rejected
+    #[default]
+    Reject,
+    /// The instructions are logged, and the program is executed
+    Warn,
+}
+
+/// A floating point instruction of a program
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FpInstruction {
+    /// The address of the instruction
+    pub pc: u32,
+    /// The instruction
+    pub instruction: u32,
+}
+
+impl fmt::Display for FpInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "floating point instruction {:#010x} at pc {:#010x}, the program must be built with soft-float",
+            self.instruction, self.pc
+        )
+    }
+}
+
+/// The floating point instructions found in a program
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("the program contains {} floating point instructions, at pc {}", .0.len(), pcs(.0))]
+pub struct FpInstructionsError(pub Vec<FpInstruction>);
+
+fn pcs(instructions: &[FpInstruction]) -> String {
+    instructions
+        .iter()
+        .map(|instruction| format!("{:#010x}", instruction.pc))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the floating point instructions of the address ranges
+/// `[start, end)` of `code` in the memory of `state`, in the order of the
+/// ranges
+pub fn find_fp_instructions(state: &State, code: &[(u32, u32)]) -> Vec<FpInstruction> {
+    // The pages are indexed once, the code spanning many pages
+    let pages: HashMap<u32, &[u8]> = state
+        .memory
+        .iter()
+        .map(|page| (page.index, page.data.as_slice()))
+        .collect();
+    let read_byte = |address: u32| {
+        pages
+            .get(&(address >> PAGE_ADDRESS_SIZE))
+            .and_then(|data| data.get((address & PAGE_ADDRESS_MASK) as usize))
+            .copied()
+            .unwrap_or(0)
+    };
+    let mut instructions = vec![];
+    for &(start, end) in code {
+        // instructions are aligned on 4 bytes
+        let start = (start + 3) & !3;
+        for pc in (start..end.saturating_sub(3)).step_by(4) {
+            let instruction = u32::from_be_bytes(std::array::from_fn(|i| read_byte(pc + i as u32)));
+            if is_fp_instruction(instruction) {
+                instructions.push(FpInstruction { pc, instruction });
+            }
+        }
+    }
+    instructions
+}
+
+/// Checks that the address ranges `[start, end)` of `code` in the memory of
+/// `state` contain no floating point instruction, or only logs them with
+/// [FpPolicy::Warn]
+///
+/// # Errors
+///
+/// Will give error if the code contains floating point instructions and the
+/// policy is [FpPolicy::Reject].
+pub fn check_fp_instructions(
+    state: &State,
+    code: &[(u32, u32)],
+    policy: FpPolicy,
+) -> Result<(), FpInstructionsError> {
+    let instructions = find_fp_instructions(state, code);
+    if instructions.is_empty() {
+        return Ok(());
+    }
+    let error = FpInstructionsError(instructions);
+    match policy {
+        FpPolicy::Reject => Err(error),
+        FpPolicy::Warn => {
+            warn!("{error}");
+            Ok(())
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use crate::{
     cannon::PAGE_ADDRESS_SIZE,
     lookups::{Lookup, LookupTableIDs},
-    mips::registers::{
-        REGISTER_CURRENT_IP, REGISTER_HEAP_POINTER, REGISTER_HI, REGISTER_LO, REGISTER_NEXT_IP,
-        REGISTER_PREIMAGE_KEY_END, REGISTER_PREIMAGE_OFFSET,
+    mips::{
+        isa,
+        registers::{
+            REGISTER_CURRENT_IP, REGISTER_HEAP_POINTER, REGISTER_HI, REGISTER_LO, REGISTER_NEXT_IP,
+            REGISTER_PREIMAGE_KEY_END, REGISTER_PREIMAGE_OFFSET,
+        },
     },
 };
 use ark_ff::{One, Zero};
+use serde::{Deserialize, Serialize};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount, EnumIter};
 
@@ -26,10 +30,68 @@ pub const SYSCALL_READ: u32 = 4003;
 pub const SYSCALL_WRITE: u32 = 4004;
 pub const SYSCALL_FCNTL: u32 = 4055;
 
+/// How the control-flow instructions (branches and jumps) take effect, chosen
+/// when the circuit is created as the constraints differ
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Hash, Serialize, Deserialize)]
+pub enum DelaySlotMode {
+    /// MIPS32r2 semantics, as in Cannon: a control-flow instruction takes
+    /// effect after the instruction following it (its delay slot). A
+    /// control-flow instruction in a delay slot is not checked, and jumps to the
+    /// target of the second one after executing the target of the first one.
+    #[default]
+    Legacy,
+    /// MIPS32r2 semantics, where a control-flow instruction in a delay slot,
+    /// whose behavior is unpredictable, is rejected by the constraints
+    Strict,
+    /// MIPS32r6 semantics of the supported instructions: control-flow
+    /// instructions take effect immediately, and the return address of a
+    /// jump-and-link is the address of the following instruction
+    NoDelaySlots,
+}
+
+impl DelaySlotMode {
+    /// Whether control-flow instructions are followed by a delay slot
+    pub fn has_delay_slots(self) -> bool {
+        self != DelaySlotMode::NoDelaySlots
+    }
+
+    /// Offset of the next instruction executed after a branch that is not
+    /// taken, from the instruction following the branch
+    pub fn fallthrough_offset(self) -> u32 {
+        if self.has_delay_slots() {
+            4
+        } else {
+            0
+        }
+    }
+
+    /// Offset of the return address of a jump-and-link, from its address
+    pub fn return_address_offset(self) -> u32 {
+        if self.has_delay_slots() {
+            8
+        } else {
+            4
+        }
+    }
+}
+
 /// The maximum size of a preimage chunk (4 bytes)
 pub(crate) const MIPS_CHUNK_BYTES_LEN: usize = 4;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, EnumCount, EnumIter, Hash, Ord, PartialOrd)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    EnumCount,
+    EnumIter,
+    Hash,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
+)]
 pub enum Instruction {
     RType(RTypeInstruction),
     JType(JTypeInstruction),
@@ -37,7 +99,19 @@ pub enum Instruction {
 }
 
 #[derive(
-    Debug, Clone, Copy, Eq, PartialEq, EnumCount, EnumIter, Default, Hash, Ord, PartialOrd,
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    EnumCount,
+    EnumIter,
+    Default,
+    Hash,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
 )]
 pub enum RTypeInstruction {
     #[default]
@@ -86,7 +160,19 @@ pub enum RTypeInstruction {
 }
 
 #[derive(
-    Debug, Clone, Copy, Eq, PartialEq, EnumCount, EnumIter, Default, Hash, Ord, PartialOrd,
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    EnumCount,
+    EnumIter,
+    Default,
+    Hash,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
 )]
 pub enum JTypeInstruction {
     #[default]
@@ -95,7 +181,19 @@ pub enum JTypeInstruction {
 }
 
 #[derive(
-    Debug, Clone, Copy, Eq, PartialEq, EnumCount, EnumIter, Default, Hash, Ord, PartialOrd,
+    Debug,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    EnumCount,
+    EnumIter,
+    Default,
+    Hash,
+    Ord,
+    PartialOrd,
+    Serialize,
+    Deserialize,
 )]
 pub enum ITypeInstruction {
     #[default]
@@ -185,6 +283,9 @@ pub trait InterpreterEnv {
     // Returns the variable in the current row corresponding to a given column alias.
     fn variable(&self, column: Self::Position) -> Self::Variable;
 
+    /// Returns the semantics of the control-flow instructions of the environment
+    fn delay_slot_mode(&self) -> DelaySlotMode;
+
     /// Add a constraint to the proof system, asserting that `assert_equals_zero` is 0.
     fn add_constraint(&mut self, assert_equals_zero: Self::Variable);
 
@@ -552,6 +653,26 @@ pub trait InterpreterEnv {
         self.lookup_2bits(value);
     }
 
+    /// Adds a lookup to the DecodingLookup table, checking that
+    /// `opcode` and `funct` encode `instr` in the instruction set (see
+    /// [crate::mips::isa]). `funct` is zero for the instructions identified by
+    /// their opcode only.
+    fn lookup_decoding(
+        &mut self,
+        opcode: &Self::Variable,
+        funct: &Self::Variable,
+        instr: Instruction,
+    ) {
+        self.add_lookup(Lookup::read_one(
+            LookupTableIDs::DecodingLookup,
+            vec![
+                opcode.clone(),
+                funct.clone(),
+                Self::constant(isa::selector(instr) as u32),
+            ],
+        ));
+    }
+
     fn range_check64(&mut self, _value: &Self::Variable) {
         // TODO
     }
@@ -979,6 +1100,37 @@ pub trait InterpreterEnv {
     fn request_hint_write(&mut self, addr: &Self::Variable, len: &Self::Variable);
 }
 
+/// In [DelaySlotMode::Strict], constrains a control-flow instruction not to be
+/// in the delay slot of another one, i.e. the next instruction pointer to
+/// follow the current one
+fn check_not_in_delay_slot<Env: InterpreterEnv>(
+    env: &mut Env,
+    instruction_pointer: &Env::Variable,
+    next_instruction_pointer: &Env::Variable,
+) {
+    if env.delay_slot_mode() == DelaySlotMode::Strict {
+        let fallthrough = instruction_pointer.clone() + Env::constant(4);
+        Env::check_equal(next_instruction_pointer, &fallthrough);
+        env.add_constraint(next_instruction_pointer.clone() - fallthrough);
+    }
+}
+
+/// Moves to the target of a control-flow instruction, after its delay slot if
+/// the environment has delay slots
+fn jump_to<Env: InterpreterEnv>(
+    env: &mut Env,
+    next_instruction_pointer: Env::Variable,
+    target: Env::Variable,
+) {
+    if env.delay_slot_mode().has_delay_slots() {
+        env.set_instruction_pointer(next_instruction_pointer);
+        env.set_next_instruction_pointer(target);
+    } else {
+        env.set_instruction_pointer(target.clone());
+        env.set_next_instruction_pointer(target + Env::constant(4));
+    }
+}
+
 pub fn interpret_instruction<Env: InterpreterEnv>(env: &mut Env, instr: Instruction) {
     match instr {
         Instruction::RType(instr) => interpret_rtype(env, instr),
@@ -1035,6 +1187,7 @@ pub fn interpret_rtype<Env: InterpreterEnv>(env: &mut Env, instr: RTypeInstructi
         unsafe { env.bitmask(&instruction, 6, 0, pos) }
     };
     env.range_check8(&funct, 6);
+    env.lookup_decoding(&opcode, &funct, Instruction::RType(instr));
 
     // Check correctness of decomposition of instruction into parts
     env.add_constraint(
@@ -1118,15 +1271,19 @@ pub fn interpret_rtype<Env: InterpreterEnv>(env: &mut Env, instr: RTypeInstructi
             env.set_next_instruction_pointer(next_instruction_pointer + Env::constant(4u32));
         }
         RTypeInstruction::JumpRegister => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
             let addr = env.read_register(&rs);
-            env.set_instruction_pointer(next_instruction_pointer.clone());
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         RTypeInstruction::JumpAndLinkRegister => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
             let addr = env.read_register(&rs);
-            env.write_register(&rd, instruction_pointer + Env::constant(8u32));
-            env.set_instruction_pointer(next_instruction_pointer.clone());
-            env.set_next_instruction_pointer(addr);
+            let return_address_offset = env.delay_slot_mode().return_address_offset();
+            env.write_register(
+                &rd,
+                instruction_pointer + Env::constant(return_address_offset),
+            );
+            jump_to(env, next_instruction_pointer, addr);
         }
         RTypeInstruction::SyscallMmap => {
             let requested_alloc_size = env.read_register(&Env::constant(5));
@@ -1192,11 +1349,8 @@ pub fn interpret_rtype<Env: InterpreterEnv>(env: &mut Env, instr: RTypeInstructi
         }
         RTypeInstruction::SyscallReadOther => {
             let fd_id = env.read_register(&Env::constant(4));
-            let mut check_equal = |expected_fd_id: u32| {
-                // FIXME: Requires constraints
-                let pos = env.alloc_scratch();
-                unsafe { env.test_zero(&(fd_id.clone() - Env::constant(expected_fd_id)), pos) }
-            };
+            let mut check_equal =
+                |expected_fd_id: u32| env.equal(&fd_id, &Env::constant(expected_fd_id));
             let is_stdin = check_equal(FD_STDIN);
             let is_preimage_read = check_equal(FD_PREIMAGE_READ);
             let is_hint_read = check_equal(FD_HINT_READ);
@@ -1378,11 +1532,8 @@ pub fn interpret_rtype<Env: InterpreterEnv>(env: &mut Env, instr: RTypeInstructi
         RTypeInstruction::SyscallWriteOther => {
             let fd_id = env.read_register(&Env::constant(4));
             let write_length = env.read_register(&Env::constant(6));
-            let mut check_equal = |expected_fd_id: u32| {
-                // FIXME: Requires constraints
-                let pos = env.alloc_scratch();
-                unsafe { env.test_zero(&(fd_id.clone() - Env::constant(expected_fd_id)), pos) }
-            };
+            let mut check_equal =
+                |expected_fd_id: u32| env.equal(&fd_id, &Env::constant(expected_fd_id));
             let is_stdout = check_equal(FD_STDOUT);
             let is_stderr = check_equal(FD_STDERR);
             let is_preimage_write = check_equal(FD_PREIMAGE_WRITE);
@@ -1743,20 +1894,25 @@ pub fn interpret_jtype<Env: InterpreterEnv>(env: &mut Env, instr: JTypeInstructi
         unsafe { env.bitmask(&next_instruction_pointer, 32, 28, pos) }
     };
     env.range_check8(&instruction_pointer_high_bits, 4);
+    env.lookup_decoding(&opcode, &Env::constant(0), Instruction::JType(instr));
 
     // Check correctness of decomposition of instruction into parts
     env.add_constraint(instruction - (opcode * Env::constant(1 << 26) + addr.clone()));
 
     let target_addr =
         (instruction_pointer_high_bits * Env::constant(1 << 28)) + (addr * Env::constant(1 << 2));
+    check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
     match instr {
         JTypeInstruction::Jump => (),
         JTypeInstruction::JumpAndLink => {
-            env.write_register(&Env::constant(31), instruction_pointer + Env::constant(8));
+            let return_address_offset = env.delay_slot_mode().return_address_offset();
+            env.write_register(
+                &Env::constant(31),
+                instruction_pointer + Env::constant(return_address_offset),
+            );
         }
     };
-    env.set_instruction_pointer(next_instruction_pointer);
-    env.set_next_instruction_pointer(target_addr);
+    jump_to(env, next_instruction_pointer, target_addr);
 }
 
 pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstruction) {
@@ -1796,6 +1952,13 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
     };
     env.lookup_16bits(&immediate);
 
+    // The REGIMM instructions are identified by their rt field
+    let funct = match isa::MIPS.funct_field(Instruction::IType(instr)) {
+        Some(_) => rt.clone(),
+        None => Env::constant(0),
+    };
+    env.lookup_decoding(&opcode, &funct, Instruction::IType(instr));
+
     // Check correctness of decomposition of instruction into parts
     env.add_constraint(
         instruction
@@ -1807,11 +1970,14 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
 
     match instr {
         ITypeInstruction::BranchEq => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let rt = env.read_register(&rt);
             let equals = env.equal(&rs, &rt);
-            let offset = (Env::constant(1) - equals.clone()) * Env::constant(4) + equals * offset;
+            let offset = (Env::constant(1) - equals.clone()) * Env::constant(fallthrough_offset)
+                + equals * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
                 let overflow_scratch = env.alloc_scratch();
@@ -1826,15 +1992,17 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::BranchNeq => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let rt = env.read_register(&rt);
             let equals = env.equal(&rs, &rt);
-            let offset = equals.clone() * Env::constant(4) + (Env::constant(1) - equals) * offset;
+            let offset = equals.clone() * Env::constant(fallthrough_offset)
+                + (Env::constant(1) - equals) * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
                 let overflow_scratch = env.alloc_scratch();
@@ -1849,10 +2017,11 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::BranchLeqZero => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let less_than_or_equal_to = {
@@ -1863,7 +2032,8 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 };
                 Env::constant(1) - greater_than_zero
             };
-            let offset = (Env::constant(1) - less_than_or_equal_to.clone()) * Env::constant(4)
+            let offset = (Env::constant(1) - less_than_or_equal_to.clone())
+                * Env::constant(fallthrough_offset)
                 + less_than_or_equal_to * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
@@ -1879,10 +2049,11 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::BranchGtZero => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let less_than = {
@@ -1890,8 +2061,8 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 let pos = env.alloc_scratch();
                 unsafe { env.test_less_than_signed(&Env::constant(0), &rs, pos) }
             };
-            let offset =
-                (Env::constant(1) - less_than.clone()) * Env::constant(4) + less_than * offset;
+            let offset = (Env::constant(1) - less_than.clone()) * Env::constant(fallthrough_offset)
+                + less_than * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
                 let overflow_scratch = env.alloc_scratch();
@@ -1906,10 +2077,11 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::BranchLtZero => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let less_than = {
@@ -1917,8 +2089,8 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 let pos = env.alloc_scratch();
                 unsafe { env.test_less_than_signed(&rs, &Env::constant(0), pos) }
             };
-            let offset =
-                (Env::constant(1) - less_than.clone()) * Env::constant(4) + less_than * offset;
+            let offset = (Env::constant(1) - less_than.clone()) * Env::constant(fallthrough_offset)
+                + less_than * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
                 let overflow_scratch = env.alloc_scratch();
@@ -1933,10 +2105,11 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::BranchGeqZero => {
+            check_not_in_delay_slot(env, &instruction_pointer, &next_instruction_pointer);
+            let fallthrough_offset = env.delay_slot_mode().fallthrough_offset();
             let offset = env.sign_extend(&(immediate * Env::constant(1 << 2)), 18);
             let rs = env.read_register(&rs);
             let less_than = {
@@ -1944,8 +2117,8 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 let pos = env.alloc_scratch();
                 unsafe { env.test_less_than_signed(&rs, &Env::constant(0), pos) }
             };
-            let offset =
-                less_than.clone() * Env::constant(4) + (Env::constant(1) - less_than) * offset;
+            let offset = less_than.clone() * Env::constant(fallthrough_offset)
+                + (Env::constant(1) - less_than) * offset;
             let addr = {
                 let res_scratch = env.alloc_scratch();
                 let overflow_scratch = env.alloc_scratch();
@@ -1960,8 +2133,7 @@ pub fn interpret_itype<Env: InterpreterEnv>(env: &mut Env, instr: ITypeInstructi
                 // FIXME: Requires a range check
                 res
             };
-            env.set_instruction_pointer(next_instruction_pointer);
-            env.set_next_instruction_pointer(addr);
+            jump_to(env, next_instruction_pointer, addr);
         }
         ITypeInstruction::AddImmediate => {
             let register_rs = env.read_register(&rs);
@@ -0,0 +1,296 @@
+//! Declarative description of the instruction set decoded by the zkVM.
+//!
+//! The instruction set is described once, as a list of [Encoding]s giving the
+//! opcode of each instruction, the bits identifying it among the instructions
+//! sharing its opcode, and its semantics in the interpreter. Everything that
+//! depends on the encoding is generated from this description:
+//! - the decoding of the instructions by the witness generation (see
+//!   [Isa::decode]),
+//! - the fixed table of the `(opcode, funct, selector)` rows allowed by the
+//!   instruction set ([LookupTableIDs::DecodingLookup], see [decoding_table]), in which
+//!   the constraints of each instruction look up the decomposition of the
+//!   instruction and its selector.
+//!
+//! Adding an instruction to the description therefore updates the decoding and
+//! the decoding constraints together. The description is generic over the
+//! instruction type and the layout of the fields, so that other instruction
+//! sets (e.g. RISC-V) can be described the same way.
+
+use crate::{
+    lookups::LookupTableIDs,
+    mips::{
+        column::N_MIPS_REL_COLS,
+        interpreter::{
+            ITypeInstruction::{self, *},
+            Instruction::{self, IType, JType, RType},
+            JTypeInstruction::{self, *},
+            RTypeInstruction::{self, *},
+        },
+    },
+};
+use ark_ff::Field;
+use kimchi_msm::LogupTable;
+
+/// A field of `len` bits of the encoding of an instruction, starting at bit
+/// `shift`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitField {
+    pub shift: u32,
+    pub len: u32,
+}
+
+impl BitField {
+    pub const fn new(shift: u32, len: u32) -> Self {
+        Self { shift, len }
+    }
+
+    /// Returns the value of the field in `instruction`
+    pub const fn extract(&self, instruction: u32) -> u32 {
+        (instruction >> self.shift) & ((1 << self.len) - 1)
+    }
+}
+
+/// The meaning of an encoding for the interpreter
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Semantics<I> {
+    /// The encoding is interpreted as the given instruction
+    Instruction(I),
+    /// The encoding is a system call, whose instruction depends on the
+    /// registers and is one of [Isa::syscalls]
+    Syscall,
+}
+
+/// The encoding of an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding<I> {
+    /// The value of the opcode field
+    pub opcode: u32,
+    /// The field identifying the instruction among the instructions sharing
+    /// its opcode, with its value, if the opcode is not enough
+    pub funct: Option<(BitField, u32)>,
+    /// The meaning of the encoding
+    pub semantics: Semantics<I>,
+}
+
+/// The description of an instruction set
+#[derive(Clone, Copy, Debug)]
+pub struct Isa<I: 'static> {
+    /// The opcode field, common to all the instructions
+    pub opcode: BitField,
+    /// The encodings of the instructions. An instruction can have several
+    /// encodings, e.g. when an instruction is interpreted as another one.
+    pub encodings: &'static [Encoding<I>],
+    /// The instructions a [Semantics::Syscall] can be interpreted as
+    pub syscalls: &'static [I],
+}
+
+impl<I: Copy + PartialEq> Isa<I> {
+    /// Decodes `instruction`, or returns `None` if it is not part of the
+    /// instruction set
+    pub fn decode(&self, instruction: u32) -> Option<Semantics<I>> {
+        let opcode = self.opcode.extract(instruction);
+        self.encodings
+            .iter()
+            .find(|encoding| {
+                encoding.opcode == opcode
+                    && encoding
+                        .funct
+                        .map_or(true, |(field, value)| field.extract(instruction) == value)
+            })
+            .map(|encoding| encoding.semantics)
+    }
+
+    /// Returns the encodings interpreted as `instr`
+    pub fn encodings_of(&self, instr: I) -> impl Iterator<Item = &Encoding<I>> {
+        let is_syscall = self.syscalls.contains(&instr);
+        self.encodings
+            .iter()
+            .filter(move |encoding| match encoding.semantics {
+                Semantics::Instruction(i) => i == instr,
+                Semantics::Syscall => is_syscall,
+            })
+    }
+
+    /// Returns the field identifying `instr` among the instructions sharing
+    /// its opcode, if any
+    pub fn funct_field(&self, instr: I) -> Option<BitField> {
+        self.encodings_of(instr)
+            .find_map(|encoding| encoding.funct.map(|(field, _)| field))
+    }
+
+    /// Returns the `(opcode, funct, instruction)` rows of the instruction set,
+    /// with one row per encoding of each instruction. The funct is `0` for the
+    /// instructions identified by their opcode only.
+    pub fn rows(&self) -> impl Iterator<Item = (u32, u32, I)> + '_ {
+        self.encodings.iter().flat_map(move |encoding| {
+            let funct = encoding.funct.map_or(0, |(_, value)| value);
+            let instrs = match encoding.semantics {
+                Semantics::Instruction(instr) => vec![instr],
+                Semantics::Syscall => self.syscalls.to_vec(),
+            };
+            instrs
+                .into_iter()
+                .map(move |instr| (encoding.opcode, funct, instr))
+        })
+    }
+}
+
+/// The function field of the SPECIAL and SPECIAL2 instructions
+pub const FUNCT: BitField = BitField::new(0, 6);
+
+/// The rt field, identifying the REGIMM instructions
+pub const RT: BitField = BitField::new(16, 5);
+
+const fn opcode(opcode: u32, instr: Instruction) -> Encoding<Instruction> {
+    Encoding {
+        opcode,
+        funct: None,
+        semantics: Semantics::Instruction(instr),
+    }
+}
+
+const fn special(funct: u32, instr: RTypeInstruction) -> Encoding<Instruction> {
+    Encoding {
+        opcode: 0x00,
+        funct: Some((FUNCT, funct)),
+        semantics: Semantics::Instruction(RType(instr)),
+    }
+}
+
+const fn special2(funct: u32, instr: RTypeInstruction) -> Encoding<Instruction> {
+    Encoding {
+        opcode: 0x1c,
+        funct: Some((FUNCT, funct)),
+        semantics: Semantics::Instruction(RType(instr)),
+    }
+}
+
+const fn regimm(rt: u32, instr: ITypeInstruction) -> Encoding<Instruction> {
+    Encoding {
+        opcode: 0x01,
+        funct: Some((RT, rt)),
+        semantics: Semantics::Instruction(IType(instr)),
+    }
+}
+
+const fn jtype(opcode: u32, instr: JTypeInstruction) -> Encoding<Instruction> {
+    self::opcode(opcode, JType(instr))
+}
+
+const fn itype(opcode: u32, instr: ITypeInstruction) -> Encoding<Instruction> {
+    self::opcode(opcode, IType(instr))
+}
+
+/// The MIPS instruction set supported by the zkVM
+pub const MIPS: Isa<Instruction> = Isa {
+    opcode: BitField::new(26, 6),
+    encodings: &[
+        special(0x00, ShiftLeftLogical),
+        special(0x02, ShiftRightLogical),
+        special(0x03, ShiftRightArithmetic),
+        special(0x04, ShiftLeftLogicalVariable),
+        special(0x06, ShiftRightLogicalVariable),
+        special(0x07, ShiftRightArithmeticVariable),
+        special(0x08, JumpRegister),
+        special(0x09, JumpAndLinkRegister),
+        special(0x0a, MoveZero),
+        special(0x0b, MoveNonZero),
+        Encoding {
+            opcode: 0x00,
+            funct: Some((FUNCT, 0x0c)),
+            semantics: Semantics::Syscall,
+        },
+        special(0x0f, Sync),
+        special(0x10, MoveFromHi),
+        special(0x11, MoveToHi),
+        special(0x12, MoveFromLo),
+        special(0x13, MoveToLo),
+        special(0x18, Multiply),
+        special(0x19, MultiplyUnsigned),
+        special(0x1a, Div),
+        special(0x1b, DivUnsigned),
+        special(0x20, Add),
+        special(0x21, AddUnsigned),
+        special(0x22, Sub),
+        special(0x23, SubUnsigned),
+        special(0x24, And),
+        special(0x25, Or),
+        special(0x26, Xor),
+        special(0x27, Nor),
+        special(0x2a, SetLessThan),
+        special(0x2b, SetLessThanUnsigned),
+        regimm(0x00, BranchLtZero),
+        regimm(0x01, BranchGeqZero),
+        jtype(0x02, Jump),
+        jtype(0x03, JumpAndLink),
+        itype(0x04, BranchEq),
+        itype(0x05, BranchNeq),
+        itype(0x06, BranchLeqZero),
+        itype(0x07, BranchGtZero),
+        itype(0x08, AddImmediate),
+        itype(0x09, AddImmediateUnsigned),
+        itype(0x0a, SetLessThanImmediate),
+        itype(0x0b, SetLessThanImmediateUnsigned),
+        itype(0x0c, AndImmediate),
+        itype(0x0d, OrImmediate),
+        itype(0x0e, XorImmediate),
+        itype(0x0f, LoadUpperImmediate),
+        special2(0x02, MultiplyToRegister),
+        special2(0x20, CountLeadingZeros),
+        special2(0x21, CountLeadingOnes),
+        itype(0x20, Load8),
+        itype(0x21, Load16),
+        itype(0x22, LoadWordLeft),
+        itype(0x23, Load32),
+        itype(0x24, Load8Unsigned),
+        itype(0x25, Load16Unsigned),
+        itype(0x26, LoadWordRight),
+        itype(0x28, Store8),
+        itype(0x29, Store16),
+        itype(0x2a, StoreWordLeft),
+        itype(0x2b, Store32),
+        itype(0x2e, StoreWordRight),
+        // Note: This is ll (LoadLinked), but we're only simulating a single
+        // processor.
+        itype(0x30, Load32),
+        // Note: This is sc (StoreConditional), but we're only simulating a
+        // single processor.
+        itype(0x38, Store32Conditional),
+    ],
+    syscalls: &[
+        RType(SyscallMmap),
+        RType(SyscallExitGroup),
+        RType(SyscallReadHint),
+        RType(SyscallReadPreimage),
+        RType(SyscallReadOther),
+        RType(SyscallWriteHint),
+        RType(SyscallWritePreimage),
+        RType(SyscallWriteOther),
+        RType(SyscallFcntl),
+        RType(SyscallOther),
+    ],
+};
+
+/// Returns the selector of `instr` in the table of the instruction set, i.e.
+/// the index of its dynamic selector column
+pub fn selector(instr: Instruction) -> usize {
+    usize::from(instr) - N_MIPS_REL_COLS
+}
+
+/// Builds the fixed table of the `[opcode, funct, selector]` rows of the
+/// MIPS instruction set, looked up by the constraints decoding the instructions
+pub fn decoding_table<F: Field>() -> LogupTable<F, LookupTableIDs> {
+    LogupTable {
+        table_id: LookupTableIDs::DecodingLookup,
+        entries: MIPS
+            .rows()
+            .map(|(opcode, funct, instr)| {
+                vec![
+                    F::from(opcode),
+                    F::from(funct),
+                    F::from(selector(instr) as u64),
+                ]
+            })
+            .collect(),
+    }
+}
@@ -15,10 +15,14 @@
 //! [crate::mips::interpreter], and the evaluations will be kept in the
 //! structure ProofInputs.
 
+pub mod checkpoint;
 pub mod column;
 pub mod constraints;
 pub mod folding;
+pub mod fpu;
 pub mod interpreter;
+pub mod isa;
+pub mod profiler;
 pub mod registers;
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,147 @@
+//! Profiling of the execution of a MIPS program.
+//!
+//! Proving an execution is much more expensive than running it, and its cost
+//! grows with the number of steps and with the number of distinct
+//! instructions and pages used. The [Profiler] tallies, while the witness is
+//! generated, the kinds of instructions executed, the syscalls and the memory
+//! pages touched. It produces a [ProfileReport], serialized in JSON, and the
+//! histogram of the program counter in the folded format of flamegraph tools
+//! (one `symbol;pc count` line per address), so that the hot spots of a guest
+//! program can be found and optimized before proving it.
+
+use crate::{
+    cannon::Meta,
+    mips::interpreter::{Instruction, RTypeInstruction},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// Name of the stack frame of the addresses which are not in any symbol of the
+/// metadata
+pub const UNKNOWN_SYMBOL: &str = "[unknown]";
+
+/// Counters of the execution of a MIPS program, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profiler {
+    instructions: BTreeMap<Instruction, u64>,
+    syscalls: BTreeMap<u32, u64>,
+    pages: BTreeMap<u32, u64>,
+    pcs: BTreeMap<u32, u64>,
+}
+
+/// The summary of a profiled execution
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileReport {
+    /// The number of steps executed
+    pub steps: u64,
+    /// The number of executions of each kind of instruction
+    pub instructions: BTreeMap<String, u64>,
+    /// The number of calls of each syscall, by syscall number
+    pub syscalls: BTreeMap<u32, u64>,
+    /// The number of memory accesses of each page, by page index
+    pub pages: BTreeMap<u32, u64>,
+    /// The number of steps executed in each symbol of the program
+    pub symbols: BTreeMap<String, u64>,
+}
+
+impl Profiler {
+    /// Creates a profiler without any recorded step
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the execution of `instruction`, stored at address `pc`
+    pub fn record_step(&mut self, pc: u32, instruction: Instruction) {
+        *self.instructions.entry(instruction).or_insert(0) += 1;
+        *self.pcs.entry(pc).or_insert(0) += 1;
+    }
+
+    /// Records a call to the syscall number `syscall`
+    pub fn record_syscall(&mut self, syscall: u32) {
+        *self.syscalls.entry(syscall).or_insert(0) += 1;
+    }
+
+    /// Records an access to the memory page of index `page`
+    pub fn record_page(&mut self, page: u32) {
+        *self.pages.entry(page).or_insert(0) += 1;
+    }
+
+    /// Returns the number of steps recorded
+    pub fn num_steps(&self) -> u64 {
+        self.pcs.values().sum()
+    }
+
+    /// Returns the number of distinct memory pages touched
+    pub fn num_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Builds the summary of the execution, the addresses being resolved to
+    /// symbols using `meta`
+    pub fn report(&self, meta: &Meta) -> ProfileReport {
+        let mut symbols = BTreeMap::new();
+        for (pc, count) in &self.pcs {
+            *symbols.entry(symbol(meta, *pc)).or_insert(0) += count;
+        }
+        ProfileReport {
+            steps: self.num_steps(),
+            instructions: self
+                .instructions
+                .iter()
+                .map(|(instruction, count)| (format!("{:?}", instruction), *count))
+                .collect(),
+            syscalls: self.syscalls.clone(),
+            pages: self.pages.clone(),
+            symbols,
+        }
+    }
+
+    /// Returns the histogram of the program counter in the folded format of
+    /// flamegraph tools, with one `symbol;pc count` line per address
+    pub fn folded(&self, meta: &Meta) -> String {
+        self.pcs
+            .iter()
+            .map(|(pc, count)| format!("{};0x{:08x} {}\n", symbol(meta, *pc), pc, count))
+            .collect()
+    }
+
+    /// Writes the report in `<prefix>.json`, and the folded histogram in
+    /// `<prefix>.folded`
+    pub fn write(&self, prefix: &str, meta: &Meta) -> std::io::Result<()> {
+        let file = File::create(format!("{prefix}.json"))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &self.report(meta))?;
+        let mut file = BufWriter::new(File::create(format!("{prefix}.folded"))?);
+        file.write_all(self.folded(meta).as_bytes())?;
+        file.flush()
+    }
+}
+
+/// Returns true if `instruction` is a syscall, the number of which is then
+/// read in the register 2
+pub fn is_syscall(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::RType(
+            RTypeInstruction::SyscallMmap
+                | RTypeInstruction::SyscallExitGroup
+                | RTypeInstruction::SyscallReadHint
+                | RTypeInstruction::SyscallReadPreimage
+                | RTypeInstruction::SyscallReadOther
+                | RTypeInstruction::SyscallWriteHint
+                | RTypeInstruction::SyscallWritePreimage
+                | RTypeInstruction::SyscallWriteOther
+                | RTypeInstruction::SyscallFcntl
+                | RTypeInstruction::SyscallOther
+        )
+    )
+}
+
+fn symbol(meta: &Meta, pc: u32) -> String {
+    meta.find_address_symbol(pc)
+        .unwrap_or_else(|| UNKNOWN_SYMBOL.to_string())
+}
@@ -1,16 +1,22 @@
 use crate::{
+    cannon::{Meta, Symbol},
+    lookups::LookupTableIDs,
     mips::{
         constraints::Env,
         interpreter::{
+            DelaySlotMode,
             ITypeInstruction::{self, *},
             Instruction::{self, *},
             JTypeInstruction::{self, *},
             RTypeInstruction::{self, *},
         },
+        isa::{self, Semantics},
+        profiler::{is_syscall, ProfileReport, Profiler, UNKNOWN_SYMBOL},
         trace::DecomposedMIPSTrace,
     },
     trace::DecomposableTracer,
 };
+use kimchi_msm::LookupTableID;
 use strum::{EnumCount, IntoEnumIterator};
 
 type Fp = ark_bn254::Fr;
@@ -26,6 +32,7 @@ fn test_mips_number_constraints() {
         scratch_state_idx: 0,
         constraints: Vec::new(),
         lookups: Vec::new(),
+        delay_slot_mode: DelaySlotMode::default(),
     };
 
     // Keep track of the constraints and lookups of the sub-circuits
@@ -69,10 +76,13 @@ fn test_mips_number_constraints() {
                 | CountLeadingOnes
                 | CountLeadingZeros => assert_num_constraints(&instr, 4),
                 MoveZero | MoveNonZero => assert_num_constraints(&instr, 6),
-                SyscallReadOther | SyscallWriteHint | SyscallWriteOther | Multiply
-                | MultiplyUnsigned | Div | DivUnsigned => assert_num_constraints(&instr, 7),
+                SyscallWriteHint | Multiply | MultiplyUnsigned | Div | DivUnsigned => {
+                    assert_num_constraints(&instr, 7)
+                }
                 SyscallOther => assert_num_constraints(&instr, 11),
                 SyscallMmap => assert_num_constraints(&instr, 12),
+                SyscallReadOther => assert_num_constraints(&instr, 13),
+                SyscallWriteOther => assert_num_constraints(&instr, 15),
                 SyscallFcntl | SyscallReadPreimage => assert_num_constraints(&instr, 23),
                 // TODO: update SyscallReadPreimage to 31 when using self.equal()
                 SyscallWritePreimage => assert_num_constraints(&instr, 31),
@@ -113,13 +123,165 @@ fn test_mips_number_constraints() {
     );
 }
 
+// The strict mode only adds a constraint to the control-flow instructions
+#[test]
+fn test_mips_strict_delay_slot_constraints() {
+    let domain_size = 1 << 8;
+    let legacy = DecomposedMIPSTrace::new(domain_size, &mut Env::<Fp>::default());
+    let strict = DecomposedMIPSTrace::new(
+        domain_size,
+        &mut Env::<Fp> {
+            delay_slot_mode: DelaySlotMode::Strict,
+            ..Default::default()
+        },
+    );
+
+    for instr in Instruction::iter().flat_map(|x| x.into_iter()) {
+        let is_control_flow = matches!(
+            instr,
+            RType(JumpRegister | JumpAndLinkRegister)
+                | JType(_)
+                | IType(
+                    BranchEq
+                        | BranchNeq
+                        | BranchLeqZero
+                        | BranchGtZero
+                        | BranchLtZero
+                        | BranchGeqZero
+                )
+        );
+        assert_eq!(
+            strict.trace[&instr].constraints.len(),
+            legacy.trace[&instr].constraints.len() + usize::from(is_control_flow),
+            "{:?}",
+            instr
+        );
+    }
+}
+
+#[test]
+fn test_mips_decoding_lookup_per_instruction() {
+    let domain_size = 1 << 8;
+
+    let mut constraints_env = Env::<Fp> {
+        scratch_state_idx: 0,
+        constraints: Vec::new(),
+        lookups: Vec::new(),
+        delay_slot_mode: DelaySlotMode::default(),
+    };
+    let mips_circuit = DecomposedMIPSTrace::new(domain_size, &mut constraints_env);
+
+    // Every instruction checks its encoding exactly once, and has an encoding
+    // in the table
+    let table = isa::decoding_table::<Fp>();
+    assert_eq!(table.entries.len(), LookupTableIDs::DecodingLookup.length());
+    for instr in Instruction::iter().flat_map(|x| x.into_iter()) {
+        let selector_lookups = mips_circuit.trace[&instr]
+            .lookups
+            .iter()
+            .filter(|lookup| lookup.table_id == LookupTableIDs::DecodingLookup)
+            .count();
+        assert_eq!(selector_lookups, 1, "{:?}", instr);
+        let selector = Fp::from(isa::selector(instr) as u64);
+        assert!(
+            table.entries.iter().any(|entry| entry[2] == selector),
+            "{:?}",
+            instr
+        );
+    }
+}
+
+#[test]
+fn test_mips_isa_decode() {
+    let decode = |instruction| isa::MIPS.decode(instruction);
+    assert_eq!(
+        decode(0x2508_0001),
+        Some(Semantics::Instruction(IType(AddImmediateUnsigned)))
+    );
+    assert_eq!(decode(0x0000_000c), Some(Semantics::Syscall));
+    assert_eq!(
+        decode(0x0401_0002),
+        Some(Semantics::Instruction(IType(BranchGeqZero)))
+    );
+    assert_eq!(
+        decode(0x7000_0020),
+        Some(Semantics::Instruction(RType(CountLeadingZeros)))
+    );
+    // ll is interpreted as lw
+    assert_eq!(
+        decode(0xc000_0000),
+        Some(Semantics::Instruction(IType(Load32)))
+    );
+    assert_eq!(decode(0xfc00_0000), None);
+    assert_eq!(decode(0x0000_0001), None);
+
+    // Every row of the table decodes to its instruction
+    for (opcode, funct, instr) in isa::MIPS.rows() {
+        let field = isa::MIPS.funct_field(instr).unwrap_or(isa::FUNCT);
+        let instruction = (opcode << isa::MIPS.opcode.shift) | (funct << field.shift);
+        let expected = if isa::MIPS.syscalls.contains(&instr) {
+            Semantics::Syscall
+        } else {
+            Semantics::Instruction(instr)
+        };
+        assert_eq!(decode(instruction), Some(expected), "{:?}", instr);
+    }
+}
+
+#[test]
+fn test_mips_profiler() {
+    let mut profiler = Profiler::new();
+
+    // A loop of 2 instructions in `main` executed 3 times, followed by an
+    // exit_group outside of any symbol
+    for _ in 0..3 {
+        profiler.record_step(0x1000, IType(AddImmediate));
+        profiler.record_page(0);
+        profiler.record_step(0x1004, RType(Add));
+        profiler.record_page(0);
+    }
+    assert!(is_syscall(RType(SyscallExitGroup)));
+    assert!(!is_syscall(RType(Add)));
+    profiler.record_step(0x2000, RType(SyscallExitGroup));
+    profiler.record_syscall(4246);
+    profiler.record_page(2);
+    assert_eq!(profiler.num_steps(), 7);
+    assert_eq!(profiler.num_pages(), 2);
+
+    let meta = Meta {
+        symbols: vec![Symbol {
+            name: "main".to_string(),
+            start: 0x1000,
+            size: 8,
+        }],
+    };
+    let report = profiler.report(&meta);
+    assert_eq!(report.steps, 7);
+    assert_eq!(report.instructions["IType(AddImmediate)"], 3);
+    assert_eq!(report.instructions["RType(SyscallExitGroup)"], 1);
+    assert_eq!(report.syscalls[&4246], 1);
+    assert_eq!(report.pages[&0], 6);
+    assert_eq!(report.symbols["main"], 6);
+    assert_eq!(report.symbols[UNKNOWN_SYMBOL], 1);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ProfileReport>(&json).unwrap(),
+        report
+    );
+    assert_eq!(
+        profiler.folded(&meta),
+        "main;0x00001000 3\nmain;0x00001004 3\n[unknown];0x00002000 1\n"
+    );
+}
+
 // Here live the unit tests for the MIPS instructions
 mod unit {
     use super::Fp;
     use crate::{
         cannon::{Hint, Preimage, PAGE_ADDRESS_MASK, PAGE_ADDRESS_SIZE, PAGE_SIZE},
         mips::{
-            interpreter::{debugging::InstructionParts, InterpreterEnv},
+            interpreter::{debugging::InstructionParts, DelaySlotMode, InterpreterEnv},
             registers::Registers,
             witness::{Env as WEnv, SyscallEnv, SCRATCH_SIZE},
         },
@@ -213,6 +375,9 @@ mod unit {
             preimage_key: None,
             keccak_env: None,
             hash_counter: 0,
+            profiler: None,
+            delay_slot_mode: DelaySlotMode::default(),
+            exit_code: 0,
         };
         // Initialize general purpose registers with random values
         for reg in env.registers.general_purpose.iter_mut() {
@@ -538,6 +703,104 @@ mod unit {
             interpret_itype(&mut dummy_env, ITypeInstruction::Load32);
             assert_eq!(dummy_env.registers.general_purpose[4], exp_v);
         }
+
+        // beq $at, $v0, 3
+        fn write_branch_eq(dummy_env: &mut WEnv<Fp, OnDiskPreImageOracle>) {
+            write_instruction(
+                dummy_env,
+                InstructionParts {
+                    op_code: 0b000100,
+                    rs: 0b00001,
+                    rt: 0b00010,
+                    rd: 0b00000,
+                    shamt: 0b00000,
+                    funct: 0b000011,
+                },
+            );
+        }
+
+        #[test]
+        fn test_unit_branch_eq_delay_slot_modes() {
+            let mut rng = o1_utils::tests::make_test_rng(None);
+            for (mode, taken, not_taken) in [
+                (DelaySlotMode::Legacy, (4, 16), (4, 8)),
+                (DelaySlotMode::Strict, (4, 16), (4, 8)),
+                (DelaySlotMode::NoDelaySlots, (16, 20), (4, 8)),
+            ] {
+                for (equal, (ip, next_ip)) in [(true, taken), (false, not_taken)] {
+                    let mut dummy_env = dummy_env(&mut rng);
+                    dummy_env.delay_slot_mode = mode;
+                    dummy_env.registers[2] = if equal {
+                        dummy_env.registers[1]
+                    } else {
+                        dummy_env.registers[1].wrapping_add(1)
+                    };
+                    let start = dummy_env.registers.current_instruction_pointer;
+                    write_branch_eq(&mut dummy_env);
+                    interpret_itype(&mut dummy_env, ITypeInstruction::BranchEq);
+                    assert_eq!(dummy_env.registers.current_instruction_pointer, start + ip);
+                    assert_eq!(
+                        dummy_env.registers.next_instruction_pointer,
+                        start + next_ip
+                    );
+                }
+            }
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_unit_branch_in_delay_slot_strict() {
+            let mut rng = o1_utils::tests::make_test_rng(None);
+            let mut dummy_env = dummy_env(&mut rng);
+            dummy_env.delay_slot_mode = DelaySlotMode::Strict;
+            // The branch is in the delay slot of a jump to another page
+            dummy_env.registers.next_instruction_pointer += PAGE_SIZE;
+            write_branch_eq(&mut dummy_env);
+            interpret_itype(&mut dummy_env, ITypeInstruction::BranchEq);
+        }
+    }
+
+    mod jtype {
+        use super::*;
+        use crate::mips::{interpreter::interpret_jtype, JTypeInstruction};
+
+        #[test]
+        fn test_unit_jump_and_link_delay_slot_modes() {
+            let mut rng = o1_utils::tests::make_test_rng(None);
+            for (mode, return_address_offset, in_delay_slot) in [
+                (DelaySlotMode::Legacy, 8, true),
+                (DelaySlotMode::NoDelaySlots, 4, false),
+            ] {
+                let mut dummy_env = dummy_env(&mut rng);
+                dummy_env.delay_slot_mode = mode;
+                let start = dummy_env.registers.current_instruction_pointer;
+                // jal 0x40
+                write_instruction(
+                    &mut dummy_env,
+                    InstructionParts {
+                        op_code: 0b000011,
+                        rs: 0b00000,
+                        rt: 0b00000,
+                        rd: 0b00000,
+                        shamt: 0b00001,
+                        funct: 0b000000,
+                    },
+                );
+                interpret_jtype(&mut dummy_env, JTypeInstruction::JumpAndLink);
+                let target = (start & 0xF000_0000) | (0x40 << 2);
+                assert_eq!(
+                    dummy_env.registers.general_purpose[31],
+                    start + return_address_offset
+                );
+                if in_delay_slot {
+                    assert_eq!(dummy_env.registers.current_instruction_pointer, start + 4);
+                    assert_eq!(dummy_env.registers.next_instruction_pointer, target);
+                } else {
+                    assert_eq!(dummy_env.registers.current_instruction_pointer, target);
+                    assert_eq!(dummy_env.registers.next_instruction_pointer, target + 4);
+                }
+            }
+        }
     }
 }
 
@@ -707,6 +970,7 @@ mod folding {
             witness: witness_one.clone(),
             constraints: constraints.clone(),
             lookups: vec![],
+            columns: (0..N_MIPS_REL_COLS).collect(),
         };
 
         impl FoldingConfig for MIPSFoldingConfig {
@@ -773,3 +1037,88 @@ mod folding {
         // FIXME: add IVC
     }
 }
+
+mod fpu {
+    use crate::{
+        cannon::{Page, State, PAGE_SIZE},
+        mips::fpu::{
+            check_fp_instructions, find_fp_instructions, is_fp_instruction, FpInstruction,
+            FpInstructionsError, FpPolicy,
+        },
+    };
+
+    const LWC1: u32 = 0xc7a00000; // lwc1 $f0, 0($sp)
+    const ADD_S: u32 = 0x46020800; // add.s $f0, $f1, $f2
+    const MOVF: u32 = 0x01204001; // movf $t0, $t1, $fcc0
+
+    fn state(instructions: &[u32]) -> State {
+        let mut data: Vec<u8> = instructions
+            .iter()
+            .flat_map(|instruction| instruction.to_be_bytes())
+            .collect();
+        data.resize(PAGE_SIZE as usize, 0);
+        State {
+            memory: vec![Page { index: 1, data }],
+            preimage_key: [0u8; 32],
+            preimage_offset: 0,
+            pc: 0x1000,
+            next_pc: 0x1004,
+            lo: 0,
+            hi: 0,
+            heap: 0x4000_0000,
+            exit: 0,
+            exited: false,
+            step: 0,
+            registers: [0u32; 32],
+            last_hint: None,
+            preimage: None,
+        }
+    }
+
+    #[test]
+    fn test_is_fp_instruction() {
+        for instruction in [LWC1, ADD_S, MOVF] {
+            assert!(is_fp_instruction(instruction));
+        }
+        // addiu, syscall, lui, sw and sc
+        for instruction in [0x25080001, 0x0000000c, 0x3c1c0042, 0xafbf0000, 0xe0a80000] {
+            assert!(!is_fp_instruction(instruction));
+        }
+    }
+
+    #[test]
+    fn test_check_fp_instructions() {
+        let state = state(&[0x25080001, LWC1, 0x25080001, ADD_S, MOVF, 0x0000000c]);
+        let code = [(0x1000, 0x1018)];
+        let expected = vec![
+            FpInstruction {
+                pc: 0x1004,
+                instruction: LWC1,
+            },
+            FpInstruction {
+                pc: 0x100c,
+                instruction: ADD_S,
+            },
+            FpInstruction {
+                pc: 0x1010,
+                instruction: MOVF,
+            },
+        ];
+        assert_eq!(find_fp_instructions(&state, &code), expected);
+
+        // only the instructions of the code are checked
+        assert_eq!(find_fp_instructions(&state, &[(0x1000, 0x1004)]), vec![]);
+        assert_eq!(
+            find_fp_instructions(&state, &[(0x1010, 0x1018)]),
+            expected[2..]
+        );
+
+        let error = check_fp_instructions(&state, &code, FpPolicy::Reject).unwrap_err();
+        assert_eq!(error, FpInstructionsError(expected));
+        assert_eq!(
+            error.to_string(),
+            "the program contains 3 floating point instructions, at pc 0x00001004, 0x0000100c, 0x00001010"
+        );
+        assert_eq!(check_fp_instructions(&state, &code, FpPolicy::Warn), Ok(()));
+    }
+}
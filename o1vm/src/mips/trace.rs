@@ -9,6 +9,7 @@ use crate::{
     trace::{DecomposableTracer, DecomposedTrace, Trace, Tracer},
 };
 use ark_ff::Zero;
+use kimchi::o1_utils::transpose::append_transposed;
 use kimchi_msm::witness::Witness;
 use std::{array, collections::BTreeMap};
 use strum::IntoEnumIterator;
@@ -62,6 +63,7 @@ impl
             },
             constraints: env.constraints.clone(),
             lookups: env.lookups.clone(),
+            columns: (0..N_MIPS_COLS).collect(),
         };
         env.scratch_state_idx = 0; // Reset the scratch state index for the next instruction
         env.constraints = vec![]; // Clear the constraints for the next instruction
@@ -82,6 +84,16 @@ impl
         }
     }
 
+    fn push_rows(
+        &mut self,
+        _selector: Self::Selector,
+        rows: &[[ScalarField<DecomposableMIPSFoldingConfig>; N_MIPS_REL_COLS]],
+    ) {
+        let len = self.witness.cols[0].len();
+        let rows = &rows[..rows.len().min(self.witness.cols[0].capacity() - len)];
+        append_transposed(&mut self.witness.cols[..], rows);
+    }
+
     fn pad_with_row(
         &mut self,
         _selector: Self::Selector,
@@ -91,8 +103,8 @@ impl
         assert!(len <= self.domain_size);
         let rows_to_add = self.domain_size - len;
         // When we reach the domain size, we don't need to pad anymore.
-        for _ in 0..rows_to_add {
-            self.push_row(_selector, row);
+        for (col, value) in self.witness.cols.iter_mut().zip(row.iter()) {
+            col.resize(self.domain_size, *value);
         }
         rows_to_add
     }
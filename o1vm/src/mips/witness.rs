@@ -6,22 +6,27 @@ use crate::{
     keccak::environment::KeccakEnv,
     lookups::Lookup,
     mips::{
+        checkpoint::Checkpoint,
         column::{
-            ColumnAlias as Column, MIPS_BYTE_COUNTER_OFF, MIPS_END_OF_PREIMAGE_OFF,
-            MIPS_HASH_COUNTER_OFF, MIPS_HAS_N_BYTES_OFF, MIPS_NUM_BYTES_READ_OFF,
-            MIPS_PREIMAGE_BYTES_OFF, MIPS_PREIMAGE_CHUNK_OFF,
+            ColumnAlias as Column, MIPS_BYTE_COUNTER_OFF, MIPS_DIGEST_MSB_OFF,
+            MIPS_END_OF_PREIMAGE_OFF, MIPS_HASH_COUNTER_OFF, MIPS_HAS_N_BYTES_OFF,
+            MIPS_NUM_BYTES_READ_OFF, MIPS_PREIMAGE_BYTES_OFF, MIPS_PREIMAGE_CHUNK_OFF,
         },
+        fpu::{self, FpInstruction},
         interpreter::{
-            self, ITypeInstruction, Instruction, InterpreterEnv, JTypeInstruction,
-            RTypeInstruction, MIPS_CHUNK_BYTES_LEN,
+            self, DelaySlotMode, Instruction, InterpreterEnv, RTypeInstruction,
+            MIPS_CHUNK_BYTES_LEN,
         },
+        isa::{self, Semantics},
+        profiler::{self, Profiler},
         registers::Registers,
     },
     preimage_oracle::PreImageOracleT,
 };
 use ark_ff::Field;
 use core::panic;
-use log::{debug, info};
+use log::{debug, error, info};
+use sha3::{Digest, Keccak256};
 use std::{
     array,
     fs::File,
@@ -44,7 +49,7 @@ pub const NUM_INSTRUCTION_LOOKUP_TERMS: usize = 5;
 pub const NUM_LOOKUP_TERMS: usize =
     NUM_GLOBAL_LOOKUP_TERMS + NUM_DECODING_LOOKUP_TERMS + NUM_INSTRUCTION_LOOKUP_TERMS;
 // TODO: Delete and use a vector instead
-pub const SCRATCH_SIZE: usize = 93; // MIPS + hash_counter + chunk_read + bytes_read + bytes_left + bytes + has_n_bytes
+pub const SCRATCH_SIZE: usize = 94; // MIPS + hash_counter + chunk_read + bytes_read + bytes_left + bytes + has_n_bytes + digest_msb
 
 #[derive(Clone, Default)]
 pub struct SyscallEnv {
@@ -82,6 +87,12 @@ pub struct Env<Fp, PreImageOracle: PreImageOracleT> {
     pub preimage_key: Option<[u8; 32]>,
     pub keccak_env: Option<KeccakEnv<Fp>>,
     pub hash_counter: u64,
+    /// Counters of the execution, only kept when profiling
+    pub profiler: Option<Profiler>,
+    /// The semantics of the control-flow instructions of the execution
+    pub delay_slot_mode: DelaySlotMode,
+    /// The exit code reported by the program, once it has exited
+    pub exit_code: u8,
 }
 
 fn fresh_scratch_state<Fp: Field, const N: usize>() -> [Fp; N] {
@@ -154,6 +165,10 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> InterpreterEnv for Env<Fp, PreI
         */
     }
 
+    fn delay_slot_mode(&self) -> DelaySlotMode {
+        self.delay_slot_mode
+    }
+
     fn add_constraint(&mut self, _assert_equals_zero: Self::Variable) {
         // No-op for witness
         // Do not assert that _assert_equals_zero is zero here!
@@ -365,12 +380,16 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> InterpreterEnv for Env<Fp, PreI
     }
 
     fn equal(&mut self, x: &Self::Variable, y: &Self::Variable) -> Self::Variable {
-        // To avoid subtraction overflow in the witness interpreter for u32
-        if x > y {
-            self.is_zero(&(*x - *y))
-        } else {
-            self.is_zero(&(*y - *x))
-        }
+        // Same columns as `is_zero(x - y)`, the difference being computed in
+        // the field to avoid subtraction overflow for u32 when x < y
+        let res = {
+            let pos = self.alloc_scratch();
+            unsafe { self.test_zero(&u64::from(x != y), pos) }
+        };
+        let pos = self.alloc_scratch();
+        let diff = Fp::from(*x) - Fp::from(*y);
+        self.write_field_column(pos, diff.inverse().unwrap_or_else(Fp::zero));
+        res
     }
 
     unsafe fn test_less_than(
@@ -619,6 +638,8 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> InterpreterEnv for Env<Fp, PreI
     }
 
     fn report_exit(&mut self, exit_code: &Self::Variable) {
+        // Only the low byte of the code is kept, as by exit_group
+        self.exit_code = *exit_code as u8;
         println!(
             "Exited with code {} at step {}",
             *exit_code,
@@ -755,6 +776,10 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> InterpreterEnv for Env<Fp, PreI
             ));
 
             // COMMUNICATION CHANNEL: only on constraint side
+            // The most significant byte of the digest is read from the channel
+            // with the key, whose most significant byte is its type
+            let digest: [u8; 32] = Keccak256::digest(self.preimage.as_ref().unwrap()).into();
+            self.write_column(Column::ScratchState(MIPS_DIGEST_MSB_OFF), digest[0] as u64);
 
             // Update hash counter column
             self.write_column(
@@ -884,6 +909,9 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
             preimage_key: None,
             keccak_env: None,
             hash_counter: 0,
+            profiler: None,
+            delay_slot_mode: DelaySlotMode::default(),
+            exit_code: state.exit,
         }
     }
 
@@ -909,6 +937,9 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
     }
 
     pub fn get_memory_page_index(&mut self, page: u32) -> usize {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record_page(page);
+        }
         for &i in self.last_memory_accesses.iter() {
             if self.memory_write_index[i].0 == page {
                 return i;
@@ -971,133 +1002,55 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
                 | ((self.get_memory_direct(self.registers.current_instruction_pointer + 2) as u32)
                     << 8)
                 | (self.get_memory_direct(self.registers.current_instruction_pointer + 3) as u32);
-        let opcode = {
-            match instruction >> 26 {
-                0x00 => match instruction & 0x3F {
-                    0x00 => Instruction::RType(RTypeInstruction::ShiftLeftLogical),
-                    0x02 => Instruction::RType(RTypeInstruction::ShiftRightLogical),
-                    0x03 => Instruction::RType(RTypeInstruction::ShiftRightArithmetic),
-                    0x04 => Instruction::RType(RTypeInstruction::ShiftLeftLogicalVariable),
-                    0x06 => Instruction::RType(RTypeInstruction::ShiftRightLogicalVariable),
-                    0x07 => Instruction::RType(RTypeInstruction::ShiftRightArithmeticVariable),
-                    0x08 => Instruction::RType(RTypeInstruction::JumpRegister),
-                    0x09 => Instruction::RType(RTypeInstruction::JumpAndLinkRegister),
-                    0x0a => Instruction::RType(RTypeInstruction::MoveZero),
-                    0x0b => Instruction::RType(RTypeInstruction::MoveNonZero),
-                    0x0c => match self.registers.general_purpose[2] {
-                        4090 => Instruction::RType(RTypeInstruction::SyscallMmap),
-                        4045 => {
-                            // sysBrk
-                            Instruction::RType(RTypeInstruction::SyscallOther)
-                        }
-                        4120 => {
-                            // sysClone
-                            Instruction::RType(RTypeInstruction::SyscallOther)
-                        }
-                        4246 => Instruction::RType(RTypeInstruction::SyscallExitGroup),
-                        4003 => match self.registers.general_purpose[4] {
-                            interpreter::FD_HINT_READ => {
-                                Instruction::RType(RTypeInstruction::SyscallReadHint)
-                            }
-                            interpreter::FD_PREIMAGE_READ => {
-                                Instruction::RType(RTypeInstruction::SyscallReadPreimage)
-                            }
-                            _ => Instruction::RType(RTypeInstruction::SyscallReadOther),
-                        },
-                        4004 => match self.registers.general_purpose[4] {
-                            interpreter::FD_PREIMAGE_WRITE => {
-                                Instruction::RType(RTypeInstruction::SyscallWritePreimage)
-                            }
-                            interpreter::FD_HINT_WRITE => {
-                                Instruction::RType(RTypeInstruction::SyscallWriteHint)
-                            }
-                            _ => Instruction::RType(RTypeInstruction::SyscallWriteOther),
-                        },
-                        4055 => Instruction::RType(RTypeInstruction::SyscallFcntl),
-                        _ => {
-                            // NB: This has well-defined behavior. Don't panic!
-                            Instruction::RType(RTypeInstruction::SyscallOther)
-                        }
-                    },
-                    0x0f => Instruction::RType(RTypeInstruction::Sync),
-                    0x10 => Instruction::RType(RTypeInstruction::MoveFromHi),
-                    0x11 => Instruction::RType(RTypeInstruction::MoveToHi),
-                    0x12 => Instruction::RType(RTypeInstruction::MoveFromLo),
-                    0x13 => Instruction::RType(RTypeInstruction::MoveToLo),
-                    0x18 => Instruction::RType(RTypeInstruction::Multiply),
-                    0x19 => Instruction::RType(RTypeInstruction::MultiplyUnsigned),
-                    0x1a => Instruction::RType(RTypeInstruction::Div),
-                    0x1b => Instruction::RType(RTypeInstruction::DivUnsigned),
-                    0x20 => Instruction::RType(RTypeInstruction::Add),
-                    0x21 => Instruction::RType(RTypeInstruction::AddUnsigned),
-                    0x22 => Instruction::RType(RTypeInstruction::Sub),
-                    0x23 => Instruction::RType(RTypeInstruction::SubUnsigned),
-                    0x24 => Instruction::RType(RTypeInstruction::And),
-                    0x25 => Instruction::RType(RTypeInstruction::Or),
-                    0x26 => Instruction::RType(RTypeInstruction::Xor),
-                    0x27 => Instruction::RType(RTypeInstruction::Nor),
-                    0x2a => Instruction::RType(RTypeInstruction::SetLessThan),
-                    0x2b => Instruction::RType(RTypeInstruction::SetLessThanUnsigned),
-                    _ => {
-                        panic!("Unhandled instruction {:#X}", instruction)
-                    }
-                },
-                0x01 => {
-                    // RegImm instructions
-                    match (instruction >> 16) & 0x1F {
-                        0x0 => Instruction::IType(ITypeInstruction::BranchLtZero),
-                        0x1 => Instruction::IType(ITypeInstruction::BranchGeqZero),
-                        _ => panic!("Unhandled instruction {:#X}", instruction),
-                    }
-                }
-                0x02 => Instruction::JType(JTypeInstruction::Jump),
-                0x03 => Instruction::JType(JTypeInstruction::JumpAndLink),
-                0x04 => Instruction::IType(ITypeInstruction::BranchEq),
-                0x05 => Instruction::IType(ITypeInstruction::BranchNeq),
-                0x06 => Instruction::IType(ITypeInstruction::BranchLeqZero),
-                0x07 => Instruction::IType(ITypeInstruction::BranchGtZero),
-                0x08 => Instruction::IType(ITypeInstruction::AddImmediate),
-                0x09 => Instruction::IType(ITypeInstruction::AddImmediateUnsigned),
-                0x0A => Instruction::IType(ITypeInstruction::SetLessThanImmediate),
-                0x0B => Instruction::IType(ITypeInstruction::SetLessThanImmediateUnsigned),
-                0x0C => Instruction::IType(ITypeInstruction::AndImmediate),
-                0x0D => Instruction::IType(ITypeInstruction::OrImmediate),
-                0x0E => Instruction::IType(ITypeInstruction::XorImmediate),
-                0x0F => Instruction::IType(ITypeInstruction::LoadUpperImmediate),
-                0x1C => match instruction & 0x3F {
-                    0x02 => Instruction::RType(RTypeInstruction::MultiplyToRegister),
-                    0x20 => Instruction::RType(RTypeInstruction::CountLeadingZeros),
-                    0x21 => Instruction::RType(RTypeInstruction::CountLeadingOnes),
-                    _ => panic!("Unhandled instruction {:#X}", instruction),
-                },
-                0x20 => Instruction::IType(ITypeInstruction::Load8),
-                0x21 => Instruction::IType(ITypeInstruction::Load16),
-                0x22 => Instruction::IType(ITypeInstruction::LoadWordLeft),
-                0x23 => Instruction::IType(ITypeInstruction::Load32),
-                0x24 => Instruction::IType(ITypeInstruction::Load8Unsigned),
-                0x25 => Instruction::IType(ITypeInstruction::Load16Unsigned),
-                0x26 => Instruction::IType(ITypeInstruction::LoadWordRight),
-                0x28 => Instruction::IType(ITypeInstruction::Store8),
-                0x29 => Instruction::IType(ITypeInstruction::Store16),
-                0x2a => Instruction::IType(ITypeInstruction::StoreWordLeft),
-                0x2b => Instruction::IType(ITypeInstruction::Store32),
-                0x2e => Instruction::IType(ITypeInstruction::StoreWordRight),
-                0x30 => {
-                    // Note: This is ll (LoadLinked), but we're only simulating
-                    // a single processor.
-                    Instruction::IType(ITypeInstruction::Load32)
+        if fpu::is_fp_instruction(instruction) {
+            let pc = self.registers.current_instruction_pointer;
+            panic!("{}", FpInstruction { pc, instruction })
+        }
+        let opcode = match isa::MIPS.decode(instruction) {
+            Some(Semantics::Instruction(instr)) => instr,
+            Some(Semantics::Syscall) => self.decode_syscall(),
+            None => panic!("Unhandled instruction {:#X}", instruction),
+        };
+        (opcode, instruction)
+    }
+
+    /// Returns the instruction a syscall is interpreted as, depending on the
+    /// syscall number in $v0 and, for reads and writes, the file descriptor
+    /// in $a0
+    fn decode_syscall(&self) -> Instruction {
+        match self.registers.general_purpose[2] {
+            4090 => Instruction::RType(RTypeInstruction::SyscallMmap),
+            4045 => {
+                // sysBrk
+                Instruction::RType(RTypeInstruction::SyscallOther)
+            }
+            4120 => {
+                // sysClone
+                Instruction::RType(RTypeInstruction::SyscallOther)
+            }
+            4246 => Instruction::RType(RTypeInstruction::SyscallExitGroup),
+            4003 => match self.registers.general_purpose[4] {
+                interpreter::FD_HINT_READ => Instruction::RType(RTypeInstruction::SyscallReadHint),
+                interpreter::FD_PREIMAGE_READ => {
+                    Instruction::RType(RTypeInstruction::SyscallReadPreimage)
                 }
-                0x38 => {
-                    // Note: This is sc (StoreConditional), but we're only
-                    // simulating a single processor.
-                    Instruction::IType(ITypeInstruction::Store32Conditional)
+                _ => Instruction::RType(RTypeInstruction::SyscallReadOther),
+            },
+            4004 => match self.registers.general_purpose[4] {
+                interpreter::FD_PREIMAGE_WRITE => {
+                    Instruction::RType(RTypeInstruction::SyscallWritePreimage)
                 }
-                _ => {
-                    panic!("Unhandled instruction {:#X}", instruction)
+                interpreter::FD_HINT_WRITE => {
+                    Instruction::RType(RTypeInstruction::SyscallWriteHint)
                 }
+                _ => Instruction::RType(RTypeInstruction::SyscallWriteOther),
+            },
+            4055 => Instruction::RType(RTypeInstruction::SyscallFcntl),
+            _ => {
+                // NB: This has well-defined behavior. Don't panic!
+                Instruction::RType(RTypeInstruction::SyscallOther)
             }
-        };
-        (opcode, instruction)
+        }
     }
 
     /// The actual number of instructions executed results from dividing the
@@ -1136,6 +1089,7 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
 
         self.pp_info(&config.info_at, metadata, start);
         self.snapshot_state_at(&config.snapshot_state_at);
+        self.checkpoint_at(&config.checkpoint_at, &config.checkpoint_fmt);
 
         // Force stops at given iteration
         if self.should_trigger_at(&config.stop_at) {
@@ -1148,6 +1102,12 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
             return opcode;
         }
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record_step(self.registers.current_instruction_pointer, opcode);
+            if profiler::is_syscall(opcode) {
+                profiler.record_syscall(self.registers.general_purpose[2]);
+            }
+        }
         interpreter::interpret_instruction(self, opcode);
 
         self.instruction_counter = self.next_instruction_counter();
@@ -1202,6 +1162,39 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
         None
     }
 
+    /// Returns the current state of the virtual machine, in the format used by Cannon
+    pub fn state(&self) -> State {
+        let mut preimage_key = [0u8; 32];
+        for i in 0..8 {
+            let bytes = u32::to_be_bytes(self.registers.preimage_key[i]);
+            for j in 0..4 {
+                preimage_key[4 * i + j] = bytes[j]
+            }
+        }
+        let memory = self
+            .memory
+            .clone()
+            .into_iter()
+            .map(|(idx, data)| Page { index: idx, data })
+            .collect();
+        State {
+            pc: self.registers.current_instruction_pointer,
+            next_pc: self.registers.next_instruction_pointer,
+            step: self.instruction_counter,
+            registers: self.registers.general_purpose,
+            lo: self.registers.lo,
+            hi: self.registers.hi,
+            heap: self.registers.heap_pointer,
+            exit: self.exit_code,
+            last_hint: self.syscall_env.last_hint.clone(),
+            exited: self.halt,
+            preimage_offset: self.registers.preimage_offset,
+            preimage_key,
+            memory,
+            preimage: self.preimage.clone(),
+        }
+    }
+
     fn snapshot_state_at(&mut self, at: &StepFrequency) {
         if self.should_trigger_at(at) {
             let filename = format!(
@@ -1210,37 +1203,7 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
             );
             let file = File::create(filename.clone()).expect("Impossible to open file");
             let mut writer = BufWriter::new(file);
-            let mut preimage_key = [0u8; 32];
-            for i in 0..8 {
-                let bytes = u32::to_be_bytes(self.registers.preimage_key[i]);
-                for j in 0..4 {
-                    preimage_key[4 * i + j] = bytes[j]
-                }
-            }
-            let memory = self
-                .memory
-                .clone()
-                .into_iter()
-                .map(|(idx, data)| Page { index: idx, data })
-                .collect();
-            let s: State = State {
-                pc: self.registers.current_instruction_pointer,
-                next_pc: self.registers.next_instruction_pointer,
-                step: self.instruction_counter,
-                registers: self.registers.general_purpose,
-                lo: self.registers.lo,
-                hi: self.registers.hi,
-                heap: self.registers.heap_pointer,
-                // FIXME: it should be the exit code. We do not keep it in the
-                // witness atm
-                exit: if self.halt { 1 } else { 0 },
-                last_hint: self.syscall_env.last_hint.clone(),
-                exited: self.halt,
-                preimage_offset: self.registers.preimage_offset,
-                preimage_key,
-                memory,
-                preimage: self.preimage.clone(),
-            };
+            let s: State = self.state();
             let _ = serde_json::to_writer(&mut writer, &s);
             info!(
                 "Snapshot state in {}, step {}",
@@ -1251,6 +1214,51 @@ impl<Fp: Field, PreImageOracle: PreImageOracleT> Env<Fp, PreImageOracle> {
         }
     }
 
+    /// Returns the checkpoint of the witness generation at the start of the
+    /// current step, see [crate::mips::checkpoint]
+    pub fn checkpoint(&self) -> Checkpoint {
+        debug_assert!(
+            self.keccak_env.is_none(),
+            "the Keccak witness of the previous step must have been generated"
+        );
+        Checkpoint {
+            state: self.state(),
+            memory_write_index: self.memory_write_index.clone(),
+            registers_write_index: self.registers_write_index.clone(),
+            preimage_bytes_read: self.preimage_bytes_read,
+            preimage_key: self.preimage_key,
+            hash_counter: self.hash_counter,
+        }
+    }
+
+    /// Creates the environment resuming the witness generation from
+    /// `checkpoint`
+    pub fn resume(
+        page_size: usize,
+        checkpoint: Checkpoint,
+        preimage_oracle: PreImageOracle,
+    ) -> Self {
+        let mut env = Self::create(page_size, checkpoint.state, preimage_oracle);
+        env.memory_write_index = checkpoint.memory_write_index;
+        env.registers_write_index = checkpoint.registers_write_index;
+        env.preimage_bytes_read = checkpoint.preimage_bytes_read;
+        env.preimage_key = checkpoint.preimage_key;
+        env.hash_counter = checkpoint.hash_counter;
+        env
+    }
+
+    fn checkpoint_at(&mut self, at: &StepFrequency, fmt: &str) {
+        if self.should_trigger_at(at) {
+            let step = self.normalized_instruction_counter();
+            let filename = Checkpoint::file_name(fmt, step);
+            // A failure to write a checkpoint does not stop the execution
+            match self.checkpoint().write(&filename) {
+                Ok(()) => info!("Checkpoint in {}, step {}", filename, step),
+                Err(e) => error!("Error writing the checkpoint {}: {}", filename, e),
+            }
+        }
+    }
+
     fn pp_info(&mut self, at: &StepFrequency, meta: &Meta, start: &Start) {
         if self.should_trigger_at(at) {
             let elapsed = start.time.elapsed();
@@ -0,0 +1,1125 @@
+//! This module implements the verification of the Merkle-Patricia trie (MPT)
+//! proofs of Ethereum, as returned by `eth_getProof`, and the Keccak hashes
+//! proving them against an L1 state root.
+//!
+//! The state of Ethereum is a "secure" trie: the path of an account is the
+//! Keccak hash of its address, and the path of a storage slot is the Keccak
+//! hash of the slot, in the storage trie of its account. A proof is the list of
+//! the RLP-encoded nodes from the root to the leaf, each of them being
+//! referenced by its parent by its Keccak hash (nodes shorter than 32 bytes are
+//! embedded in their parent instead, and are not part of the proof).
+//!
+//! The nodes of a proof are checked natively by [MptPath::verify], which
+//! returns the hash requests of the nodes with their [MptLink]s: the offset of
+//! the reference of each node in the preimage of its parent. A
+//! [StorageProof] chains the proof of an account with the proof of one of its
+//! storage slots, the storage root being a reference in the leaf of the
+//! account.
+//!
+//! The hashes are proven by the Keccak circuit, and the links by a small
+//! connecting circuit, using the syscall channel (the `SyscallLookup` table)
+//! through which the preimages read by the syscalls are hashed:
+//! - the requester writes the bytes of the padded preimages of the nodes,
+//!   except the bytes of the references to their children;
+//! - each link is a row of the connecting circuit, whose columns are the hash
+//!   indices of the parent and the child, the offset and the 32 bytes of the
+//!   reference. The row writes the bytes of the reference in the preimage of
+//!   the parent, and reads the digest of the child, computed from the same
+//!   bytes;
+//! - the requester reads the digest of the root, which is the public output
+//!   of the hashes;
+//! - the path of each proof is an [MptKey] row of the connecting circuit,
+//!   whose columns are the hash index of the key and the 64 nibbles of the
+//!   path. The row writes the padded key in the preimage of its hash, range
+//!   checks each nibble with two reads of the `ByteLookup` table, and reads
+//!   the digest of the key, each byte of which is given by its two nibbles.
+//!   The nibbles are then the decomposition of the Keccak hash of the key.
+//!
+//! The lookups of the hashes, of the links and of the keys balance the lookups
+//! of the Keccak instances (see [HashRequest::keccak_env]) only if the hash of
+//! each node is written where its parent references it.
+//!
+//! NOTE: the walk of the nodes along the nibbles, i.e. the child chosen in
+//! each branch and the partial paths of the extensions and of the leaf, is not
+//! constrained yet, it is only checked natively by [MptPath::verify].
+
+use crate::{
+    keccak::environment::KeccakEnv,
+    lookups::{
+        Lookup,
+        LookupTableIDs::{ByteLookup, SyscallLookup},
+    },
+};
+use ark_ff::Field;
+use kimchi::{circuits::polynomials::keccak::Keccak, o1_utils::Two};
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+/// The root of an empty trie, i.e. the Keccak hash of the RLP encoding of the
+/// empty string
+pub const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// A Keccak hash requested to the Keccak circuit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashRequest {
+    /// Hash index in the Keccak circuit
+    pub hash_idx: u64,
+    /// Preimage of the hash, before padding
+    pub preimage: Vec<u8>,
+    /// Digest of the preimage
+    pub digest: [u8; 32],
+}
+
+impl HashRequest {
+    /// Creates the request of the hash of `preimage` with index `hash_idx`
+    pub fn new(hash_idx: u64, preimage: Vec<u8>) -> Self {
+        let digest = Keccak256::digest(&preimage).into();
+        Self {
+            hash_idx,
+            preimage,
+            digest,
+        }
+    }
+
+    /// Returns the lookups of the requester side of the syscall channel: the
+    /// writes of the bytes of the padded preimage and the read of the digest
+    pub fn lookups<F: Field>(&self) -> Vec<Lookup<F>> {
+        let hash_idx = F::from(self.hash_idx);
+        let mut lookups: Vec<Lookup<F>> = Keccak::pad(&self.preimage)
+            .into_iter()
+            .enumerate()
+            .map(|(i, byte)| {
+                Lookup::write_one(
+                    SyscallLookup,
+                    vec![hash_idx, F::from(i as u64), F::from(byte)],
+                )
+            })
+            .collect();
+        lookups.push(Self::digest_lookup(self.hash_idx, &self.digest));
+        lookups
+    }
+
+    /// Returns the read of the digest of the hash `hash_idx` from the syscall
+    /// channel, as its most significant byte and its 31 least significant
+    /// bytes
+    pub fn digest_lookup<F: Field>(hash_idx: u64, digest: &[u8; 32]) -> Lookup<F> {
+        let bytes31 = digest[1..]
+            .iter()
+            .fold(F::zero(), |acc, byte| acc * F::two_pow(8) + F::from(*byte));
+        Lookup::read_one(
+            SyscallLookup,
+            vec![F::from(hash_idx), F::from(digest[0]), bytes31],
+        )
+    }
+
+    /// Returns the Keccak environment proving the hash
+    pub fn keccak_env<F: Field>(&self) -> KeccakEnv<F> {
+        KeccakEnv::new(self.hash_idx, &self.preimage)
+    }
+}
+
+/// Errors that can arise when verifying a Merkle-Patricia trie proof
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MptError {
+    #[error("the node {0} of the proof is not a valid trie node")]
+    InvalidNode(usize),
+    #[error("the hash of the node {0} of the proof does not match its reference")]
+    HashMismatch(usize),
+    #[error("the proof ends before the node {0}, referenced by its parent")]
+    MissingNode(usize),
+    #[error("the proof has {0} nodes after the end of the path")]
+    TrailingNodes(usize),
+    #[error("the value of the leaf is not a valid {0}")]
+    InvalidValue(&'static str),
+    #[error("the account does not exist in the state trie")]
+    MissingAccount,
+    #[error("the value of the slot {slot} is {found}, expected {expected}")]
+    ValueMismatch {
+        slot: String,
+        expected: String,
+        found: String,
+    },
+    #[error("the proof could not be decoded: {0}")]
+    Decoding(String),
+}
+
+/// An RLP item in an encoding, given by the range of its payload
+#[derive(Clone, Copy, Debug)]
+struct RlpItem {
+    is_list: bool,
+    offset: usize,
+    len: usize,
+}
+
+impl RlpItem {
+    fn payload<'a>(&self, bytes: &'a [u8]) -> &'a [u8] {
+        &bytes[self.offset..self.offset + self.len]
+    }
+}
+
+/// Decodes the header of the RLP item starting at `start`, returning the item
+/// and the end of its encoding
+fn decode_item(bytes: &[u8], start: usize) -> Option<(RlpItem, usize)> {
+    let prefix = *bytes.get(start)?;
+    let (is_list, offset, len) = match prefix {
+        0x00..=0x7f => (false, start, 1),
+        0x80..=0xb7 => (false, start + 1, (prefix - 0x80) as usize),
+        0xc0..=0xf7 => (true, start + 1, (prefix - 0xc0) as usize),
+        _ => {
+            let (is_list, len_of_len) = if prefix < 0xc0 {
+                (false, (prefix - 0xb7) as usize)
+            } else {
+                (true, (prefix - 0xf7) as usize)
+            };
+            let len_bytes = bytes.get(start + 1..start + 1 + len_of_len)?;
+            if len_of_len > 8 {
+                return None;
+            }
+            let len = len_bytes
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+            (is_list, start + 1 + len_of_len, len)
+        }
+    };
+    let end = offset.checked_add(len)?;
+    (end <= bytes.len()).then_some((
+        RlpItem {
+            is_list,
+            offset,
+            len,
+        },
+        end,
+    ))
+}
+
+/// Decodes the items of the RLP list `list`
+fn decode_list(bytes: &[u8], list: RlpItem) -> Option<Vec<RlpItem>> {
+    if !list.is_list {
+        return None;
+    }
+    let mut items = vec![];
+    let mut start = list.offset;
+    while start < list.offset + list.len {
+        let (item, end) = decode_item(bytes, start)?;
+        items.push(item);
+        start = end;
+    }
+    (start == list.offset + list.len).then_some(items)
+}
+
+/// Decodes `bytes` as a single RLP list
+fn decode_node(bytes: &[u8]) -> Option<Vec<RlpItem>> {
+    match decode_item(bytes, 0)? {
+        (list, end) if end == bytes.len() => decode_list(bytes, list),
+        _ => None,
+    }
+}
+
+/// Returns the nibbles of `bytes`, most significant first
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Decodes the hex-prefix encoding of the path of a leaf or of an extension,
+/// returning its nibbles and whether the node is a leaf
+fn decode_hex_prefix(payload: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let first = *payload.first()?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return None;
+    }
+    let mut path = if flag & 1 == 1 {
+        vec![first & 0x0f]
+    } else if first & 0x0f == 0 {
+        vec![]
+    } else {
+        return None;
+    };
+    path.extend(nibbles(&payload[1..]));
+    Some((path, flag & 2 == 2))
+}
+
+/// A value stored in a leaf of a trie
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptValue {
+    /// Offset of the value in the last node of the proof
+    pub offset: usize,
+    /// The value, i.e. the RLP encoding of an account or of a storage slot
+    pub bytes: Vec<u8>,
+}
+
+/// A path of a trie from its root, checked by [MptPath::verify]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptPath {
+    /// The root of the trie
+    pub root: [u8; 32],
+    /// The key, whose Keccak hash is the path
+    pub key: Vec<u8>,
+    /// The path in the trie, i.e. the Keccak hash of the key
+    pub path: [u8; 32],
+    /// The nodes of the proof, from the root
+    pub nodes: Vec<Vec<u8>>,
+    /// The offset of the reference of each node in the preimage of its
+    /// parent, from the second node
+    pub offsets: Vec<usize>,
+    /// The value at the end of the path, or `None` if the key is not in the
+    /// trie
+    pub value: Option<MptValue>,
+}
+
+impl MptPath {
+    /// Checks that `nodes` is a proof of the value of `key` in the trie of
+    /// root `root`, or of its absence
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a node is not a valid trie node or does not hash to
+    /// its reference, or if the proof has missing or trailing nodes.
+    pub fn verify(root: [u8; 32], key: &[u8], nodes: Vec<Vec<u8>>) -> Result<Self, MptError> {
+        let path: [u8; 32] = Keccak256::digest(key).into();
+        // The proof of the empty trie does not have any node
+        let (offsets, value) = if nodes.is_empty() && root == EMPTY_TRIE_ROOT {
+            (vec![], None)
+        } else {
+            let (offsets, value, last) = Self::walk(root, &path, &nodes)?;
+            if nodes.len() > last + 1 {
+                return Err(MptError::TrailingNodes(nodes.len() - last - 1));
+            }
+            (offsets, value)
+        };
+        Ok(Self {
+            root,
+            key: key.to_vec(),
+            path,
+            nodes,
+            offsets,
+            value,
+        })
+    }
+
+    // Walks the nodes along `path`, returning the offsets of the references,
+    // the value at the end of the path and the index of the last node
+    fn walk(
+        root: [u8; 32],
+        path: &[u8; 32],
+        nodes: &[Vec<u8>],
+    ) -> Result<(Vec<usize>, Option<MptValue>, usize), MptError> {
+        let mut path_nibbles = nibbles(path).into_iter();
+        let mut offsets = vec![];
+        let mut reference = root;
+
+        'nodes: for (i, node) in nodes.iter().enumerate() {
+            if <[u8; 32]>::from(Keccak256::digest(node)) != reference {
+                return Err(MptError::HashMismatch(i));
+            }
+            let invalid = || MptError::InvalidNode(i);
+            let mut items = decode_node(node).ok_or_else(invalid)?;
+            // Walks the node, and the nodes embedded in it
+            loop {
+                let child = match items.len() {
+                    17 => match path_nibbles.next() {
+                        Some(nibble) => items[nibble as usize],
+                        // The values of a secure trie are all in leaves
+                        None => return Err(invalid()),
+                    },
+                    2 => {
+                        let (partial, is_leaf) = decode_hex_prefix(items[0].payload(node))
+                            .filter(|_| !items[0].is_list)
+                            .ok_or_else(invalid)?;
+                        let matches = partial
+                            .iter()
+                            .all(|nibble| path_nibbles.next() == Some(*nibble));
+                        if !matches {
+                            // The path diverges, the key is not in the trie
+                            return Ok((offsets, None, i));
+                        }
+                        if is_leaf {
+                            if items[1].is_list || path_nibbles.next().is_some() {
+                                return Err(invalid());
+                            }
+                            let value = MptValue {
+                                offset: items[1].offset,
+                                bytes: items[1].payload(node).to_vec(),
+                            };
+                            return Ok((offsets, Some(value), i));
+                        }
+                        items[1]
+                    }
+                    _ => return Err(invalid()),
+                };
+                if child.is_list {
+                    items = decode_list(node, child).ok_or_else(invalid)?;
+                } else if child.len == 0 {
+                    return Ok((offsets, None, i));
+                } else if child.len == 32 {
+                    offsets.push(child.offset);
+                    reference = child.payload(node).try_into().unwrap();
+                    continue 'nodes;
+                } else {
+                    return Err(invalid());
+                }
+            }
+        }
+        Err(MptError::MissingNode(nodes.len()))
+    }
+
+    /// Returns the hashes of the nodes and their links, with hash indices
+    /// starting at `first_hash_idx`, followed by the hash of the key
+    pub fn hashes(&self, first_hash_idx: u64) -> MptHashes {
+        let requests = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| HashRequest::new(first_hash_idx + i as u64, node.clone()))
+            .collect::<Vec<_>>();
+        let links = self
+            .offsets
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| MptLink {
+                parent_idx: first_hash_idx + i as u64,
+                offset: *offset,
+                child_idx: first_hash_idx + i as u64 + 1,
+                reference: requests[i + 1].digest,
+            })
+            .collect();
+        let key = MptKey {
+            request: HashRequest::new(first_hash_idx + requests.len() as u64, self.key.clone()),
+            nibbles: nibbles(&self.path).try_into().unwrap(),
+        };
+        MptHashes {
+            requests,
+            links,
+            keys: vec![key],
+            root: self.root,
+        }
+    }
+}
+
+/// An account of the state trie
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    /// The balance, in big-endian without leading zeros
+    pub balance: Vec<u8>,
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+}
+
+impl Account {
+    /// Decodes the RLP encoding of an account, returning it with the offset of
+    /// its storage root in the encoding
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `bytes` is not the encoding of an account.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, usize), MptError> {
+        let invalid = || MptError::InvalidValue("account");
+        let items = decode_node(bytes).ok_or_else(invalid)?;
+        let [nonce, balance, storage_root, code_hash] = items[..] else {
+            return Err(invalid());
+        };
+        if [nonce, balance, storage_root, code_hash]
+            .iter()
+            .any(|item| item.is_list)
+            || nonce.len > 8
+            || storage_root.len != 32
+            || code_hash.len != 32
+        {
+            return Err(invalid());
+        }
+        let account = Self {
+            nonce: nonce
+                .payload(bytes)
+                .iter()
+                .fold(0, |acc, byte| (acc << 8) | *byte as u64),
+            balance: balance.payload(bytes).to_vec(),
+            storage_root: storage_root.payload(bytes).try_into().unwrap(),
+            code_hash: code_hash.payload(bytes).try_into().unwrap(),
+        };
+        Ok((account, storage_root.offset))
+    }
+}
+
+/// The proof of a storage slot against a state root: the proof of the
+/// account in the state trie, followed by the proof of the slot in the
+/// storage trie of the account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageProof {
+    pub account: Account,
+    /// Offset of the storage root of the account in the leaf of the account
+    pub storage_root_offset: usize,
+    pub account_path: MptPath,
+    pub storage_path: MptPath,
+    /// The value of the slot, zero if it is not in the storage trie
+    pub value: [u8; 32],
+}
+
+impl StorageProof {
+    /// Checks the proofs of the account at `address` in the state trie of
+    /// root `state_root`, and of its storage `slot`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if one of the proofs is not valid, see
+    /// [MptPath::verify], or if the account does not exist.
+    pub fn verify(
+        state_root: [u8; 32],
+        address: &[u8],
+        account_nodes: Vec<Vec<u8>>,
+        slot: &[u8; 32],
+        storage_nodes: Vec<Vec<u8>>,
+    ) -> Result<Self, MptError> {
+        let account_path = MptPath::verify(state_root, address, account_nodes)?;
+        let leaf = account_path
+            .value
+            .as_ref()
+            .ok_or(MptError::MissingAccount)?;
+        let (account, storage_root_offset) = Account::decode(&leaf.bytes)?;
+        let storage_path = MptPath::verify(account.storage_root, slot, storage_nodes)?;
+        let value = match &storage_path.value {
+            None => [0u8; 32],
+            Some(leaf) => {
+                let invalid = || MptError::InvalidValue("storage value");
+                let (item, end) = decode_item(&leaf.bytes, 0).ok_or_else(invalid)?;
+                if item.is_list || end != leaf.bytes.len() || item.len > 32 {
+                    return Err(invalid());
+                }
+                let mut value = [0u8; 32];
+                value[32 - item.len..].copy_from_slice(item.payload(&leaf.bytes));
+                value
+            }
+        };
+        Ok(Self {
+            account,
+            storage_root_offset,
+            account_path,
+            storage_path,
+            value,
+        })
+    }
+
+    /// Returns the hashes of the nodes of both proofs and their links, with
+    /// hash indices starting at `first_hash_idx`. The leaf of the account is
+    /// linked to the root of the storage trie, so that the root of the hashes
+    /// is the state root.
+    pub fn hashes(&self, first_hash_idx: u64) -> MptHashes {
+        let mut hashes = self.account_path.hashes(first_hash_idx);
+        let storage_idx = first_hash_idx + (hashes.requests.len() + hashes.keys.len()) as u64;
+        let storage = self.storage_path.hashes(storage_idx);
+        if let (Some(leaf), Some(account_leaf), Some(storage_root)) = (
+            &self.account_path.value,
+            hashes.requests.last(),
+            storage.requests.first(),
+        ) {
+            hashes.links.push(MptLink {
+                parent_idx: account_leaf.hash_idx,
+                offset: leaf.offset + self.storage_root_offset,
+                child_idx: storage_idx,
+                reference: storage_root.digest,
+            });
+        }
+        hashes.requests.extend(storage.requests);
+        hashes.links.extend(storage.links);
+        hashes.keys.extend(storage.keys);
+        hashes
+    }
+}
+
+/// The reference of a node in the preimage of its parent, see the
+/// [module documentation](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MptLink {
+    /// Hash index of the parent
+    pub parent_idx: u64,
+    /// Offset of the reference in the preimage of the parent
+    pub offset: usize,
+    /// Hash index of the child
+    pub child_idx: u64,
+    /// The reference, i.e. the digest of the child
+    pub reference: [u8; 32],
+}
+
+impl MptLink {
+    /// Returns the lookups of the row of the link to the syscall channel: the
+    /// writes of the bytes of the reference in the preimage of the parent,
+    /// and the read of the digest of the child
+    pub fn lookups<F: Field>(&self) -> Vec<Lookup<F>> {
+        let parent_idx = F::from(self.parent_idx);
+        let mut lookups: Vec<Lookup<F>> = self
+            .reference
+            .iter()
+            .enumerate()
+            .map(|(k, byte)| {
+                Lookup::write_one(
+                    SyscallLookup,
+                    vec![
+                        parent_idx,
+                        F::from((self.offset + k) as u64),
+                        F::from(*byte),
+                    ],
+                )
+            })
+            .collect();
+        lookups.push(HashRequest::digest_lookup(self.child_idx, &self.reference));
+        lookups
+    }
+}
+
+/// The decomposition of the path of a proof in nibbles, see the
+/// [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptKey {
+    /// The hash of the key, whose digest is the path
+    pub request: HashRequest,
+    /// The nibbles of the path, most significant first
+    pub nibbles: [u8; 64],
+}
+
+impl MptKey {
+    /// Returns the lookups of the row of the key: the writes of the bytes of
+    /// the padded key to the syscall channel, the range checks of the nibbles
+    /// and the read of the digest of the key, recomposed from the nibbles
+    pub fn lookups<F: Field>(&self) -> Vec<Lookup<F>> {
+        let hash_idx = F::from(self.request.hash_idx);
+        let mut lookups: Vec<Lookup<F>> = Keccak::pad(&self.request.preimage)
+            .into_iter()
+            .enumerate()
+            .map(|(i, byte)| {
+                Lookup::write_one(
+                    SyscallLookup,
+                    vec![hash_idx, F::from(i as u64), F::from(byte)],
+                )
+            })
+            .collect();
+        let nibbles = self.nibbles.map(|nibble| F::from(nibble));
+        // 0 <= nibble < 2^8 and nibble + 2^8 - 2^4 < 2^8
+        for nibble in nibbles {
+            lookups.push(Lookup::read_one(ByteLookup, vec![nibble]));
+            lookups.push(Lookup::read_one(
+                ByteLookup,
+                vec![nibble + F::from(256u64 - 16)],
+            ));
+        }
+        let bytes: Vec<F> = nibbles
+            .chunks(2)
+            .map(|pair| pair[0] * F::from(16u64) + pair[1])
+            .collect();
+        let bytes31 = bytes[1..]
+            .iter()
+            .fold(F::zero(), |acc, byte| acc * F::two_pow(8) + byte);
+        lookups.push(Lookup::read_one(
+            SyscallLookup,
+            vec![hash_idx, bytes[0], bytes31],
+        ));
+        lookups
+    }
+}
+
+/// The hashes of the nodes of a proof, with their links and the hash of its
+/// key, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptHashes {
+    /// The hashes of the nodes, the first one being the root
+    pub requests: Vec<HashRequest>,
+    pub links: Vec<MptLink>,
+    /// The keys of the paths, one per proof
+    pub keys: Vec<MptKey>,
+    /// The root of the trie
+    pub root: [u8; 32],
+}
+
+impl MptHashes {
+    /// Returns the lookups of the requester of the hashes and of the
+    /// connecting circuit
+    pub fn lookups<F: Field>(&self) -> Vec<Lookup<F>> {
+        let mut lookups = vec![];
+        for request in self.requests.iter() {
+            let hash_idx = F::from(request.hash_idx);
+            let references: Vec<usize> = self
+                .links
+                .iter()
+                .filter(|link| link.parent_idx == request.hash_idx)
+                .map(|link| link.offset)
+                .collect();
+            lookups.extend(
+                Keccak::pad(&request.preimage)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        !references
+                            .iter()
+                            .any(|offset| (*offset..*offset + 32).contains(i))
+                    })
+                    .map(|(i, byte)| {
+                        Lookup::write_one(
+                            SyscallLookup,
+                            vec![hash_idx, F::from(i as u64), F::from(byte)],
+                        )
+                    }),
+            );
+            // The digests of the children are read by the links
+            if !self
+                .links
+                .iter()
+                .any(|link| link.child_idx == request.hash_idx)
+            {
+                lookups.push(HashRequest::digest_lookup(
+                    request.hash_idx,
+                    &request.digest,
+                ));
+            }
+        }
+        lookups.extend(self.links.iter().flat_map(|link| link.lookups()));
+        lookups.extend(self.keys.iter().flat_map(|key| key.lookups()));
+        lookups
+    }
+}
+
+/// The result of `eth_getProof`, for one account and some of its slots
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthProof {
+    pub address: String,
+    pub account_proof: Vec<String>,
+    pub storage_proof: Vec<EthStorageProof>,
+}
+
+/// The proof of a slot in the result of `eth_getProof`
+#[derive(Clone, Debug, Deserialize)]
+pub struct EthStorageProof {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+}
+
+impl EthProof {
+    /// Decodes the result of `eth_getProof`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `json` is not the result of `eth_getProof`.
+    pub fn from_json(json: &str) -> Result<Self, MptError> {
+        serde_json::from_str(json).map_err(|e| MptError::Decoding(e.to_string()))
+    }
+
+    /// Checks the proofs of the slots against `state_root`, and that their
+    /// values are the ones of the result
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a proof cannot be decoded or is not valid, see
+    /// [StorageProof::verify].
+    pub fn storage_proofs(&self, state_root: [u8; 32]) -> Result<Vec<StorageProof>, MptError> {
+        let address = decode_hex(&self.address)?;
+        let account_nodes = decode_nodes(&self.account_proof)?;
+        self.storage_proof
+            .iter()
+            .map(|storage| {
+                let slot = decode_quantity(&storage.key)?;
+                let proof = StorageProof::verify(
+                    state_root,
+                    &address,
+                    account_nodes.clone(),
+                    &slot,
+                    decode_nodes(&storage.proof)?,
+                )?;
+                let expected = decode_quantity(&storage.value)?;
+                if proof.value != expected {
+                    return Err(MptError::ValueMismatch {
+                        slot: storage.key.clone(),
+                        expected: storage.value.clone(),
+                        found: format!("0x{}", hex::encode(proof.value)),
+                    });
+                }
+                Ok(proof)
+            })
+            .collect()
+    }
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, MptError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| MptError::Decoding(e.to_string()))
+}
+
+fn decode_nodes(nodes: &[String]) -> Result<Vec<Vec<u8>>, MptError> {
+    nodes.iter().map(|node| decode_hex(node)).collect()
+}
+
+// Decodes a quantity of at most 32 bytes, whose leading zeros may be omitted
+fn decode_quantity(s: &str) -> Result<[u8; 32], MptError> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = if digits.len() % 2 == 1 {
+        decode_hex(&format!("0{digits}"))?
+    } else {
+        decode_hex(digits)?
+    };
+    if bytes.len() > 32 {
+        return Err(MptError::Decoding(format!("{s} is longer than 32 bytes")));
+    }
+    let mut quantity = [0u8; 32];
+    quantity[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(quantity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keccak::interpreter::KeccakInterpreter, ramlookup::LookupMode, Fp};
+    use std::collections::HashMap;
+
+    fn rlp(prefix: u8, payload: &[u8]) -> Vec<u8> {
+        let mut encoding = if payload.len() <= 55 {
+            vec![prefix + payload.len() as u8]
+        } else {
+            let len = payload.len().to_be_bytes();
+            let len = &len[len.iter().position(|byte| *byte != 0).unwrap()..];
+            let mut header = vec![prefix + 55 + len.len() as u8];
+            header.extend_from_slice(len);
+            header
+        };
+        encoding.extend_from_slice(payload);
+        encoding
+    }
+
+    fn rlp_string(bytes: &[u8]) -> Vec<u8> {
+        match bytes {
+            [byte] if *byte < 0x80 => vec![*byte],
+            _ => rlp(0x80, bytes),
+        }
+    }
+
+    fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+        rlp(0xc0, &items.concat())
+    }
+
+    fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = if is_leaf { 2 } else { 0 };
+        let mut encoded = if path.len() % 2 == 1 {
+            vec![flag + 1]
+        } else {
+            vec![flag, 0]
+        };
+        encoded.extend_from_slice(path);
+        encoded
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect()
+    }
+
+    // Returns the reference of `node` in its parent, adding it to the proof if
+    // it is on the path
+    fn reference(node: Vec<u8>, on_path: bool, proof: &mut Vec<Vec<u8>>) -> Vec<u8> {
+        if node.len() < 32 {
+            return node;
+        }
+        let reference = rlp_string(&Keccak256::digest(&node));
+        if on_path {
+            proof.push(node);
+        }
+        reference
+    }
+
+    // Builds the node of the trie of `entries` at `depth`, adding the nodes
+    // on the path `key` to the proof, from the leaves
+    fn build(
+        entries: &[(Vec<u8>, Vec<u8>)],
+        depth: usize,
+        key: Option<&[u8]>,
+        proof: &mut Vec<Vec<u8>>,
+    ) -> Vec<u8> {
+        if let [(path, value)] = entries {
+            return rlp_list(&[
+                rlp_string(&hex_prefix(&path[depth..], true)),
+                rlp_string(value),
+            ]);
+        }
+        let (first, _) = &entries[0];
+        let common = (depth..64)
+            .take_while(|i| entries.iter().all(|(path, _)| path[*i] == first[*i]))
+            .count();
+        if common > 0 {
+            let prefix = &first[depth..depth + common];
+            let key = key.filter(|key| &key[depth..depth + common] == prefix);
+            let child = build(entries, depth + common, key, proof);
+            return rlp_list(&[
+                rlp_string(&hex_prefix(prefix, false)),
+                reference(child, key.is_some(), proof),
+            ]);
+        }
+        let mut items: Vec<Vec<u8>> = (0..16u8)
+            .map(|nibble| {
+                let subset: Vec<_> = entries
+                    .iter()
+                    .filter(|(path, _)| path[depth] == nibble)
+                    .cloned()
+                    .collect();
+                if subset.is_empty() {
+                    return rlp_string(&[]);
+                }
+                let key = key.filter(|key| key[depth] == nibble);
+                let child = build(&subset, depth + 1, key, proof);
+                reference(child, key.is_some(), proof)
+            })
+            .collect();
+        items.push(rlp_string(&[]));
+        rlp_list(&items)
+    }
+
+    // Returns the root of the secure trie of `entries`, with the proof of `key`
+    fn trie(entries: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> ([u8; 32], Vec<Vec<u8>>) {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|(key, value)| (nibbles(&Keccak256::digest(key)), value.clone()))
+            .collect();
+        let path = nibbles(&Keccak256::digest(key));
+        let mut proof = vec![];
+        let root = build(&entries, 0, Some(&path), &mut proof);
+        let root_hash = Keccak256::digest(&root).into();
+        proof.push(root);
+        proof.reverse();
+        (root_hash, proof)
+    }
+
+    fn slot(i: u8) -> [u8; 32] {
+        let mut slot = [0u8; 32];
+        slot[31] = i;
+        slot
+    }
+
+    fn account(storage_root: [u8; 32]) -> Vec<u8> {
+        rlp_list(&[
+            rlp_string(&[1]),
+            rlp_string(&[0x03, 0xe8]),
+            rlp_string(&storage_root),
+            rlp_string(&Keccak256::digest(b"")),
+        ])
+    }
+
+    // A state of 4 accounts, the one at address 2 having 6 storage slots,
+    // with the proof of `slot` of this account
+    fn state(slot_key: &[u8; 32]) -> ([u8; 32], Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        let storage: Vec<_> = (0..6u8)
+            .map(|i| (slot(i).to_vec(), rlp_string(&[i + 1, 0])))
+            .collect();
+        let (storage_root, storage_nodes) = trie(&storage, slot_key);
+        let accounts: Vec<_> = (0..4u8)
+            .map(|i| {
+                let root = if i == 2 {
+                    storage_root
+                } else {
+                    EMPTY_TRIE_ROOT
+                };
+                (vec![i; 20], account(root))
+            })
+            .collect();
+        let (state_root, account_nodes) = trie(&accounts, &[2; 20]);
+        (state_root, account_nodes, storage_nodes)
+    }
+
+    // Returns the number of entries of the syscall channel that are not
+    // balanced by `lookups`, and of reads of the `ByteLookup` table that are
+    // not bytes
+    fn unbalanced<'a>(lookups: impl Iterator<Item = &'a Lookup<Fp>>) -> usize {
+        let mut counts: HashMap<&[Fp], i64> = HashMap::new();
+        let mut not_bytes = 0;
+        for lookup in lookups {
+            if lookup.table_id == ByteLookup {
+                not_bytes += usize::from(lookup.value[0] >= Fp::from(256u64));
+                continue;
+            }
+            *counts.entry(lookup.value.as_slice()).or_default() += match lookup.mode {
+                LookupMode::Write => 1,
+                LookupMode::Read => -1,
+            };
+        }
+        counts.values().filter(|count| **count != 0).count() + not_bytes
+    }
+
+    #[test]
+    fn test_mpt_storage_proof() {
+        assert_eq!(<[u8; 32]>::from(Keccak256::digest([0x80])), EMPTY_TRIE_ROOT);
+
+        let (state_root, account_nodes, storage_nodes) = state(&slot(3));
+        let proof = StorageProof::verify(
+            state_root,
+            &[2; 20],
+            account_nodes.clone(),
+            &slot(3),
+            storage_nodes.clone(),
+        )
+        .unwrap();
+        let mut value = [0u8; 32];
+        value[30] = 4;
+        assert_eq!(proof.value, value);
+        assert_eq!(proof.account.nonce, 1);
+        assert_eq!(proof.account.balance, vec![0x03, 0xe8]);
+
+        // The hashes of the nodes are linked from the state root
+        let hashes = proof.hashes(10);
+        let num_nodes = account_nodes.len() + storage_nodes.len();
+        assert_eq!(hashes.requests.len(), num_nodes);
+        assert_eq!(hashes.links.len(), num_nodes - 1);
+        assert_eq!(hashes.root, state_root);
+        assert_eq!(hashes.requests[0].digest, state_root);
+        let request = |hash_idx| {
+            let request = hashes.requests.iter().find(|r| r.hash_idx == hash_idx);
+            request.unwrap()
+        };
+        for link in hashes.links.iter() {
+            let (parent, child) = (request(link.parent_idx), request(link.child_idx));
+            assert_eq!(link.reference, child.digest);
+            assert_eq!(
+                parent.preimage[link.offset..link.offset + 32],
+                link.reference
+            );
+        }
+
+        assert_eq!(hashes.keys.len(), 2);
+        assert_eq!(hashes.keys[0].request.preimage, vec![2; 20]);
+        assert_eq!(hashes.keys[1].request.preimage, slot(3).to_vec());
+        assert_eq!(
+            hashes.keys[1].nibbles.to_vec(),
+            nibbles(&proof.storage_path.path)
+        );
+
+        // The connecting circuit balances the lookups of the Keccak circuit
+        let mut keccak_lookups = vec![];
+        let keys = hashes.keys.iter().map(|key| &key.request);
+        for request in hashes.requests.iter().chain(keys) {
+            let mut keccak_env = request.keccak_env::<Fp>();
+            while let Some(step) = keccak_env.step {
+                keccak_env.step();
+                keccak_env.witness_env.lookups(step);
+                assert!(keccak_env.witness_env.errors.is_empty());
+            }
+            keccak_lookups.extend(keccak_env.witness_env.syscalls);
+        }
+        let lookups = hashes.lookups::<Fp>();
+        assert_eq!(unbalanced(lookups.iter().chain(keccak_lookups.iter())), 0);
+
+        // A link at a wrong offset does not
+        let mut wrong_hashes = hashes.clone();
+        wrong_hashes.links[1].offset += 1;
+        let lookups = wrong_hashes.lookups::<Fp>();
+        assert_ne!(unbalanced(lookups.iter().chain(keccak_lookups.iter())), 0);
+
+        // Nor do nibbles that are not the path
+        let mut wrong_hashes = hashes.clone();
+        wrong_hashes.keys[0].nibbles[5] ^= 1;
+        let lookups = wrong_hashes.lookups::<Fp>();
+        assert_ne!(unbalanced(lookups.iter().chain(keccak_lookups.iter())), 0);
+
+        // Nor nibbles that decompose the path but are not range checked
+        let mut wrong_hashes = hashes.clone();
+        let nibbles = &mut wrong_hashes.keys[0].nibbles;
+        let k = (0..64).step_by(2).find(|k| nibbles[*k] > 0).unwrap();
+        nibbles[k] -= 1;
+        nibbles[k + 1] += 16;
+        let lookups = wrong_hashes.lookups::<Fp>();
+        assert_ne!(unbalanced(lookups.iter().chain(keccak_lookups.iter())), 0);
+    }
+
+    #[test]
+    fn test_mpt_absent_slot() {
+        let (state_root, account_nodes, storage_nodes) = state(&slot(9));
+        let proof =
+            StorageProof::verify(state_root, &[2; 20], account_nodes, &slot(9), storage_nodes)
+                .unwrap();
+        assert_eq!(proof.storage_path.value, None);
+        assert_eq!(proof.value, [0u8; 32]);
+
+        // The storage of the account at address 1 is empty
+        let accounts: Vec<_> = (0..4u8)
+            .map(|i| (vec![i; 20], account(EMPTY_TRIE_ROOT)))
+            .collect();
+        let (state_root, account_nodes) = trie(&accounts, &[1; 20]);
+        let proof =
+            StorageProof::verify(state_root, &[1; 20], account_nodes, &slot(0), vec![]).unwrap();
+        assert_eq!(proof.value, [0u8; 32]);
+        let hashes = proof.hashes(0);
+        assert_eq!(hashes.requests.len(), proof.account_path.nodes.len());
+        assert_eq!(hashes.links.len(), hashes.requests.len() - 1);
+
+        // The account at address 7 does not exist
+        let (state_root, account_nodes) = trie(&accounts, &[7; 20]);
+        assert_eq!(
+            StorageProof::verify(state_root, &[7; 20], account_nodes, &slot(0), vec![]),
+            Err(MptError::MissingAccount)
+        );
+    }
+
+    #[test]
+    fn test_mpt_invalid_proofs() {
+        let (state_root, account_nodes, _) = state(&slot(3));
+        let verify = |nodes: Vec<Vec<u8>>| MptPath::verify(state_root, &[2; 20], nodes);
+        assert!(verify(account_nodes.clone()).is_ok());
+
+        let mut nodes = account_nodes.clone();
+        *nodes[1].last_mut().unwrap() ^= 1;
+        assert_eq!(verify(nodes), Err(MptError::HashMismatch(1)));
+
+        let mut nodes = account_nodes.clone();
+        nodes.pop();
+        assert_eq!(
+            verify(nodes),
+            Err(MptError::MissingNode(account_nodes.len() - 1))
+        );
+
+        let mut nodes = account_nodes.clone();
+        nodes.push(vec![0xc0]);
+        assert_eq!(verify(nodes), Err(MptError::TrailingNodes(1)));
+
+        let root = Keccak256::digest([0xc1, 0x80]).into();
+        assert_eq!(
+            MptPath::verify(root, &[2; 20], vec![vec![0xc1, 0x80]]),
+            Err(MptError::InvalidNode(0))
+        );
+    }
+
+    #[test]
+    fn test_mpt_eth_get_proof() {
+        let (state_root, account_nodes, storage_nodes) = state(&slot(3));
+        let hex_nodes = |nodes: &[Vec<u8>]| {
+            nodes
+                .iter()
+                .map(|node| format!("\"0x{}\"", hex::encode(node)))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let json = |value: &str| {
+            format!(
+                r#"{{"address":"0x{}","accountProof":[{}],"balance":"0x3e8","codeHash":"0x{}","nonce":"0x1","storageHash":"0x00","storageProof":[{{"key":"0x3","value":"{value}","proof":[{}]}}]}}"#,
+                hex::encode([2; 20]),
+                hex_nodes(&account_nodes),
+                hex::encode(Keccak256::digest(b"")),
+                hex_nodes(&storage_nodes),
+            )
+        };
+
+        let proofs = EthProof::from_json(&json("0x400"))
+            .unwrap()
+            .storage_proofs(state_root)
+            .unwrap();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].value, decode_quantity("0x400").unwrap());
+
+        assert!(matches!(
+            EthProof::from_json(&json("0x401"))
+                .unwrap()
+                .storage_proofs(state_root),
+            Err(MptError::ValueMismatch { .. })
+        ));
+        assert!(matches!(
+            EthProof::from_json("{}"),
+            Err(MptError::Decoding(_))
+        ));
+    }
+}
@@ -1,15 +1,30 @@
-use crate::cannon::{
-    Hint, HostProgram, Preimage, HINT_CLIENT_READ_FD, HINT_CLIENT_WRITE_FD,
-    PREIMAGE_CLIENT_READ_FD, PREIMAGE_CLIENT_WRITE_FD,
+use crate::{
+    cannon::{
+        Hint, HostProgram, Preimage, VmConfiguration, HINT_CLIENT_READ_FD, HINT_CLIENT_WRITE_FD,
+        PREIMAGE_CLIENT_READ_FD, PREIMAGE_CLIENT_WRITE_FD,
+    },
+    syscall_journal::{RecordingPreImageOracle, ReplayPreImageOracle, SyscallJournal},
 };
 use command_fds::{CommandFdExt, FdMapping};
 use log::debug;
 use os_pipe::{PipeReader, PipeWriter};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
 use std::{
-    io::{Read, Write},
+    collections::HashMap,
+    io::{self, Read, Write},
+    net::TcpStream,
     os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    path::Path,
     process::{Child, Command},
 };
+use thiserror::Error;
+
+/// The type of the preimage keys that are the Keccak hash of their preimage
+pub const KECCAK_KEY_TYPE: u8 = 2;
+
+/// The type of the preimage keys that are the SHA-256 hash of their preimage
+pub const SHA256_KEY_TYPE: u8 = 4;
 
 pub struct PreImageOracle {
     pub cmd: Command,
@@ -19,12 +34,37 @@ pub struct PreImageOracle {
     pub hint_server: RW,
 }
 
+/// A source of preimages for the `read` syscall of the zkVM.
+///
+/// The witness generator is generic over this trait, so that preimages can be
+/// served by the host program ([PreImageOracle]), by a local key-value store
+/// ([KeyValuePreImageOracle]), or by a JSON-RPC server ([RpcPreImageOracle]).
 pub trait PreImageOracleT {
     fn get_preimage(&mut self, key: [u8; 32]) -> Preimage;
 
     fn hint(&mut self, hint: Hint);
 }
 
+impl<T: PreImageOracleT + ?Sized> PreImageOracleT for Box<T> {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        (**self).get_preimage(key)
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        (**self).hint(hint)
+    }
+}
+
+impl<T: PreImageOracleT + ?Sized> PreImageOracleT for &mut T {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        (**self).get_preimage(key)
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        (**self).hint(hint)
+    }
+}
+
 pub struct ReadWrite<R, W> {
     pub reader: R,
     pub writer: W,
@@ -224,6 +264,381 @@ impl PreImageOracleT for PreImageOracle {
     }
 }
 
+/// Returns the preimage key of type [KECCAK_KEY_TYPE] of `preimage`
+pub fn keccak_preimage_key(preimage: &[u8]) -> [u8; 32] {
+    let mut key: [u8; 32] = Keccak256::digest(preimage).into();
+    key[0] = KECCAK_KEY_TYPE;
+    key
+}
+
+/// Returns the preimage key of type [SHA256_KEY_TYPE] of `preimage`
+pub fn sha256_preimage_key(preimage: &[u8]) -> [u8; 32] {
+    let mut key: [u8; 32] = Sha256::digest(preimage).into();
+    key[0] = SHA256_KEY_TYPE;
+    key
+}
+
+/// Returns false if `key` is a hash key, i.e. of type [KECCAK_KEY_TYPE] or
+/// [SHA256_KEY_TYPE], which is not the key of `preimage`.
+/// The other key types, e.g. local keys, are not derived from the preimage, so
+/// any preimage is accepted for them.
+pub fn check_preimage(key: &[u8; 32], preimage: &[u8]) -> bool {
+    match key[0] {
+        KECCAK_KEY_TYPE => keccak_preimage_key(preimage) == *key,
+        SHA256_KEY_TYPE => sha256_preimage_key(preimage) == *key,
+        _ => true,
+    }
+}
+
+/// A preimage oracle backed by an in-memory key-value store.
+///
+/// The store can be filled programmatically, or loaded from a directory using
+/// the layout of the op-program disk store, i.e. one file `0x<key>.txt` per
+/// preimage, containing the preimage encoded in hexadecimal.
+/// The preimages served for hash keys are checked against their key, see
+/// [check_preimage].
+#[derive(Clone, Debug, Default)]
+pub struct KeyValuePreImageOracle {
+    preimages: HashMap<[u8; 32], Vec<u8>>,
+    hints: Vec<Vec<u8>>,
+}
+
+impl KeyValuePreImageOracle {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads all the preimages stored in the directory `dir`.
+    /// Files that are not named after a preimage key are ignored.
+    pub fn from_directory<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut oracle = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(key) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".txt"))
+                .and_then(|name| decode_hex(name).ok())
+                .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            else {
+                continue;
+            };
+            let contents = std::fs::read_to_string(&path)?;
+            let preimage = decode_hex(contents.trim())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            oracle.insert(key, preimage);
+        }
+        Ok(oracle)
+    }
+
+    /// Stores `preimage` under `key`, and returns the preimage previously
+    /// stored under `key`, if any
+    pub fn insert(&mut self, key: [u8; 32], preimage: Vec<u8>) -> Option<Vec<u8>> {
+        self.preimages.insert(key, preimage)
+    }
+
+    /// Stores `preimage` under its Keccak preimage key, and returns the key
+    pub fn insert_keccak(&mut self, preimage: Vec<u8>) -> [u8; 32] {
+        let key = keccak_preimage_key(&preimage);
+        self.insert(key, preimage);
+        key
+    }
+
+    /// Returns the preimage stored under `key`
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8]> {
+        self.preimages.get(key).map(|preimage| preimage.as_slice())
+    }
+
+    /// Returns the number of stored preimages
+    pub fn len(&self) -> usize {
+        self.preimages.len()
+    }
+
+    /// Returns true if no preimage is stored
+    pub fn is_empty(&self) -> bool {
+        self.preimages.is_empty()
+    }
+
+    /// Returns the hints received by the oracle, in order
+    pub fn hints(&self) -> &[Vec<u8>] {
+        &self.hints
+    }
+}
+
+impl PreImageOracleT for KeyValuePreImageOracle {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        let preimage = self
+            .get(&key)
+            .unwrap_or_else(|| panic!("No preimage for key 0x{}", hex::encode(key)));
+        assert!(
+            check_preimage(&key, preimage),
+            "The stored preimage does not hash to the key 0x{}",
+            hex::encode(key)
+        );
+        Preimage::create(preimage.to_vec())
+    }
+
+    // Hints are only useful to a server that fetches preimages on demand, so
+    // they are just recorded.
+    fn hint(&mut self, hint: Hint) {
+        self.hints.push(hint.get())
+    }
+}
+
+/// Errors that can arise when talking to a JSON-RPC preimage server
+#[derive(Error, Debug)]
+pub enum RpcError {
+    #[error("unsupported url {0}, expected http://<host>[:<port>][/<path>]")]
+    UnsupportedUrl(String),
+    #[error("the connection to the server failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("the server answered with HTTP status {0}")]
+    HttpStatus(u16),
+    #[error("the response of the server is malformed: {0}")]
+    MalformedResponse(String),
+    #[error("the server returned the error {code}: {message}")]
+    Server { code: i64, message: String },
+    #[error("the server returned a preimage which does not hash to the key 0x{}", hex::encode(.0))]
+    InvalidPreimage([u8; 32]),
+}
+
+/// A preimage oracle fetching the preimages from a JSON-RPC server, e.g. an
+/// op-node or the execution client behind it.
+///
+/// Each preimage is requested by calling the method [Self::get_method] with the
+/// key encoded as a `0x`-prefixed hexadecimal string, and the server must
+/// answer with the preimage encoded the same way. If [Self::hint_method] is
+/// set, the hints are forwarded to the server with this method. The fetched
+/// preimages are checked against their key, see [check_preimage], and cached,
+/// so that each key is only requested once.
+pub struct RpcPreImageOracle {
+    host: String,
+    path: String,
+    pub get_method: String,
+    pub hint_method: Option<String>,
+    next_id: u64,
+    cache: KeyValuePreImageOracle,
+}
+
+impl RpcPreImageOracle {
+    /// The method used by default to request a preimage
+    pub const DEFAULT_GET_METHOD: &'static str = "debug_dbGet";
+
+    /// Creates a client for the server at `url`. Only plain HTTP is supported.
+    pub fn create(url: &str) -> Result<Self, RpcError> {
+        let unsupported = || RpcError::UnsupportedUrl(url.to_string());
+        let rest = url.strip_prefix("http://").ok_or_else(unsupported)?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(unsupported());
+        }
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{host}:80")
+        };
+        Ok(RpcPreImageOracle {
+            host,
+            path: path.to_string(),
+            get_method: Self::DEFAULT_GET_METHOD.to_string(),
+            hint_method: None,
+            next_id: 0,
+            cache: KeyValuePreImageOracle::new(),
+        })
+    }
+
+    /// Sets the method used to request a preimage
+    pub fn get_method(mut self, method: &str) -> Self {
+        self.get_method = method.to_string();
+        self
+    }
+
+    /// Sets the method used to forward the hints
+    pub fn hint_method(mut self, method: &str) -> Self {
+        self.hint_method = Some(method.to_string());
+        self
+    }
+
+    /// Calls `method` with the parameters `params`, and returns the result
+    pub fn call(
+        &mut self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<serde_json::Value, RpcError> {
+        self.next_id += 1;
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+        let mut stream = TcpStream::connect(&self.host)?;
+        stream.write_all(request.as_bytes())?;
+        stream.flush()?;
+        let mut response = vec![];
+        stream.read_to_end(&mut response)?;
+
+        let body = http_response_body(&response)?;
+        let mut response: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| RpcError::MalformedResponse(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Server {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        response
+            .get_mut("result")
+            .map(serde_json::Value::take)
+            .ok_or_else(|| RpcError::MalformedResponse("missing result".to_string()))
+    }
+
+    /// Fetches the preimage of `key`, from the cache if it was already fetched
+    pub fn fetch(&mut self, key: [u8; 32]) -> Result<Vec<u8>, RpcError> {
+        if let Some(preimage) = self.cache.get(&key) {
+            return Ok(preimage.to_vec());
+        }
+        let method = self.get_method.clone();
+        let result = self.call(&method, vec![format!("0x{}", hex::encode(key)).into()])?;
+        let preimage = result
+            .as_str()
+            .ok_or_else(|| RpcError::MalformedResponse(format!("expected a string, got {result}")))
+            .and_then(|s| decode_hex(s).map_err(RpcError::MalformedResponse))?;
+        if !check_preimage(&key, &preimage) {
+            return Err(RpcError::InvalidPreimage(key));
+        }
+        self.cache.insert(key, preimage.clone());
+        Ok(preimage)
+    }
+}
+
+impl PreImageOracleT for RpcPreImageOracle {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        match self.fetch(key) {
+            Ok(preimage) => Preimage::create(preimage),
+            Err(e) => panic!(
+                "Could not fetch the preimage of 0x{}: {e}",
+                hex::encode(key)
+            ),
+        }
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        if let Some(method) = self.hint_method.clone() {
+            let hint = format!("0x{}", hex::encode(hint.get()));
+            if let Err(e) = self.call(&method, vec![hint.into()]) {
+                debug!("The hint was not processed by the server: {e}");
+            }
+        }
+    }
+}
+
+/// Creates the preimage oracle described by `configuration`: a JSON-RPC server
+/// if one is given, otherwise a local store if a directory is given, otherwise
+/// the host program, which is started.
+/// If a journal is replayed, its answers are served instead, and if a journal
+/// is recorded, the answers of the oracle are recorded to it, see
+/// [crate::syscall_journal].
+pub fn create(configuration: &VmConfiguration) -> Box<dyn PreImageOracleT> {
+    if let Some(path) = &configuration.replay_journal {
+        let journal = SyscallJournal::read(path)
+            .unwrap_or_else(|e| panic!("Could not read the syscall journal {path}: {e}"));
+        debug!("Replaying {} syscall results from {}", journal.len(), path);
+        return Box::new(ReplayPreImageOracle::new(journal));
+    }
+    let oracle = create_source(configuration);
+    match &configuration.record_journal {
+        Some(path) => Box::new(
+            RecordingPreImageOracle::to_file(oracle, path)
+                .unwrap_or_else(|e| panic!("Could not create the syscall journal {path}: {e}")),
+        ),
+        None => oracle,
+    }
+}
+
+// Creates the oracle answering the requests, when no journal is replayed
+fn create_source(configuration: &VmConfiguration) -> Box<dyn PreImageOracleT> {
+    if let Some(url) = &configuration.preimage_rpc {
+        let mut oracle = RpcPreImageOracle::create(url)
+            .unwrap_or_else(|e| panic!("Could not create the JSON-RPC preimage oracle: {e}"));
+        if let Some(method) = &configuration.preimage_rpc_method {
+            oracle = oracle.get_method(method);
+        }
+        Box::new(oracle)
+    } else if let Some(dir) = &configuration.preimage_db_dir {
+        let oracle = KeyValuePreImageOracle::from_directory(dir)
+            .unwrap_or_else(|e| panic!("Could not load the preimages from {dir}: {e}"));
+        debug!("Loaded {} preimages from {}", oracle.len(), dir);
+        Box::new(oracle)
+    } else {
+        let mut oracle = PreImageOracle::create(&configuration.host);
+        let _child = oracle.start();
+        Box::new(oracle)
+    }
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| e.to_string())
+}
+
+// Extracts the body of an HTTP/1.1 response, which may use the chunked transfer encoding
+fn http_response_body(response: &[u8]) -> Result<Vec<u8>, RpcError> {
+    let malformed = |msg: &str| RpcError::MalformedResponse(msg.to_string());
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| malformed("missing HTTP headers"))?;
+    let headers = String::from_utf8_lossy(&response[..header_end]).to_lowercase();
+    let body = &response[header_end + 4..];
+
+    let status = headers
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| malformed("missing HTTP status"))?;
+    if status != 200 {
+        return Err(RpcError::HttpStatus(status));
+    }
+
+    if !headers.contains("transfer-encoding: chunked") {
+        return Ok(body.to_vec());
+    }
+    let mut decoded = vec![];
+    let mut rest = body;
+    loop {
+        let line_end = rest
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(|| malformed("truncated chunk"))?;
+        let size = std::str::from_utf8(&rest[..line_end])
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.split(';').next()?.trim(), 16).ok())
+            .ok_or_else(|| malformed("invalid chunk size"))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = rest
+            .get(line_end + 2..line_end + 2 + size)
+            .ok_or_else(|| malformed("truncated chunk"))?;
+        decoded.extend_from_slice(chunk);
+        rest = rest.get(line_end + 4 + size..).unwrap_or_default();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -299,4 +714,166 @@ mod tests {
         assert_eq!(n, len);
         assert_eq!(data, msg2);
     }
+
+    #[test]
+    fn test_key_value_oracle() {
+        let mut oracle = KeyValuePreImageOracle::new();
+        let key = oracle.insert_keccak(b"hello".to_vec());
+        assert_eq!(key[0], KECCAK_KEY_TYPE);
+        assert_eq!(key[1..], Keccak256::digest(b"hello")[1..]);
+        assert_eq!(oracle.get_preimage(key).get(), b"hello".to_vec());
+
+        oracle.hint(Hint::create(b"l1-block 0x00".to_vec()));
+        assert_eq!(oracle.hints(), &[b"l1-block 0x00".to_vec()]);
+    }
+
+    #[test]
+    fn test_check_preimage() {
+        let keccak_key = keccak_preimage_key(b"hello");
+        let sha256_key = sha256_preimage_key(b"hello");
+        assert_eq!(sha256_key[0], SHA256_KEY_TYPE);
+        assert_eq!(sha256_key[1..], Sha256::digest(b"hello")[1..]);
+        assert!(check_preimage(&keccak_key, b"hello"));
+        assert!(check_preimage(&sha256_key, b"hello"));
+        assert!(!check_preimage(&keccak_key, b"world"));
+        assert!(!check_preimage(&sha256_key, b"world"));
+        // Local keys are not derived from their preimage
+        let mut local_key = [0u8; 32];
+        local_key[0] = 1;
+        assert!(check_preimage(&local_key, b"world"));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not hash to the key")]
+    fn test_key_value_oracle_wrong_preimage() {
+        let mut oracle = KeyValuePreImageOracle::new();
+        let key = keccak_preimage_key(b"hello");
+        oracle.insert(key, b"world".to_vec());
+        oracle.get_preimage(key);
+    }
+
+    #[test]
+    fn test_key_value_oracle_from_directory() {
+        let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/tests");
+        let mut oracle = KeyValuePreImageOracle::from_directory(dir).unwrap();
+        assert_eq!(oracle.len(), 1);
+
+        // The test preimage is stored under its Keccak key
+        let mut key = [0u8; 32];
+        hex::decode_to_slice(
+            "022107307879258577230c5aa2f90567bda40877a7e85dceb6ff1f37480fef3d",
+            &mut key,
+        )
+        .unwrap();
+        let preimage = oracle.get_preimage(key).get();
+        assert_eq!(keccak_preimage_key(&preimage), key);
+    }
+
+    // Serves `responses` on a local port, one per connection, and returns the
+    // URL of the server and the handle to the received requests
+    fn serve(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/rpc", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                // The body of the requests is a JSON object
+                let mut request = vec![];
+                let mut buf = [0u8; 1024];
+                while request.last() != Some(&b'}') {
+                    let n = stream.read(&mut buf).unwrap();
+                    assert!(n > 0);
+                    request.extend_from_slice(&buf[..n]);
+                }
+                requests.push(String::from_utf8(request).unwrap());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_rpc_oracle() {
+        let preimage = b"hello".to_vec();
+        let key = keccak_preimage_key(&preimage);
+        let result = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":"0x{}"}}"#,
+            hex::encode(&preimage)
+        );
+        let responses = vec![
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                result.len(),
+                result
+            ),
+            format!(
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                result.len(),
+                result
+            ),
+        ];
+        let (url, server) = serve(responses);
+
+        let mut oracle = RpcPreImageOracle::create(&url).unwrap();
+        assert_eq!(oracle.get_preimage(key).get(), preimage);
+        // The second request is served from the cache
+        assert_eq!(oracle.get_preimage(key).get(), preimage);
+        // Chunked responses are supported
+        assert_eq!(
+            oracle.call("debug_dbGet", vec![]).unwrap(),
+            format!("0x{}", hex::encode(&preimage))
+        );
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("POST /rpc HTTP/1.1"));
+        assert!(requests[0].contains(r#""method":"debug_dbGet""#));
+        assert!(requests[0].contains(&hex::encode(key)));
+    }
+
+    #[test]
+    fn test_rpc_oracle_errors() {
+        assert!(matches!(
+            RpcPreImageOracle::create("https://localhost:8545"),
+            Err(RpcError::UnsupportedUrl(_))
+        ));
+
+        let error = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"not found"}}"#;
+        let wrong = format!(
+            r#"{{"jsonrpc":"2.0","id":3,"result":"0x{}"}}"#,
+            hex::encode(b"world")
+        );
+        let responses = vec![
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                error.len(),
+                error
+            ),
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".to_string(),
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                wrong.len(),
+                wrong
+            ),
+        ];
+        let (url, server) = serve(responses);
+
+        let mut oracle = RpcPreImageOracle::create(&url).unwrap();
+        assert!(matches!(
+            oracle.fetch([0u8; 32]),
+            Err(RpcError::Server { code: -32000, .. })
+        ));
+        assert!(matches!(
+            oracle.fetch([0u8; 32]),
+            Err(RpcError::HttpStatus(500))
+        ));
+        let key = keccak_preimage_key(b"hello");
+        assert!(matches!(
+            oracle.fetch(key),
+            Err(RpcError::InvalidPreimage(k)) if k == key
+        ));
+        server.join().unwrap();
+    }
 }
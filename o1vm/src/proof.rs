@@ -1,4 +1,4 @@
-use crate::lookups::LookupTableIDs;
+use crate::{lookups::LookupTableIDs, trace::commit_sub_table};
 use ark_poly::{Evaluations, Radix2EvaluationDomain as D};
 use kimchi::{circuits::domains::EvaluationDomains, curve::KimchiCurve, plonk_sponge::FrSponge};
 use kimchi_msm::{proof::ProofInputs, witness::Witness};
@@ -20,21 +20,12 @@ pub fn fold<
     srs: &OpeningProof::SRS,
     accumulator: &mut ProofInputs<N, G::ScalarField, LookupTableIDs>,
     inputs: &Witness<N, Vec<G::ScalarField>>,
+    columns: &[usize],
 ) where
     <OpeningProof as poly_commitment::OpenProof<G>>::SRS: std::marker::Sync,
 {
-    let commitments = {
-        inputs
-            .par_iter()
-            .map(|evals: &Vec<G::ScalarField>| {
-                let evals = Evaluations::<G::ScalarField, D<G::ScalarField>>::from_vec_and_domain(
-                    evals.clone(),
-                    domain.d1,
-                );
-                srs.commit_evaluations_non_hiding(domain.d1, &evals)
-            })
-            .collect::<Witness<N, _>>()
-    };
+    // The columns outside of the sub-table of the inputs are zero
+    let commitments = commit_sub_table(srs, domain.d1, inputs, columns);
     let mut fq_sponge = EFqSponge::new(G::other_curve_sponge_params());
 
     commitments.into_iter().for_each(|comm| {
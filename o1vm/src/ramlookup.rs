@@ -1,4 +1,5 @@
 use ark_ff::{Field, One, Zero};
+use kimchi::circuits::bus::BusAccess;
 use kimchi_msm::{Logup, LookupTableID};
 
 /// Enum representing the two different modes of a RAMLookup
@@ -42,6 +43,18 @@ where
         }
     }
 
+    /// Creates a RAMLookup of an access to a bus (see [kimchi::circuits::bus]).
+    /// Writing the accesses in the order of the execution and reading them in
+    /// the sorted order shows that both lists are a permutation of each other.
+    pub fn bus(mode: LookupMode, table_id: ID, access: BusAccess<T>) -> Self {
+        Self {
+            mode,
+            table_id,
+            magnitude: T::one(),
+            value: vec![access.op, access.addr, access.ts, access.value],
+        }
+    }
+
     /// Returns the numerator corresponding to this lookup in the Logup argument
     pub fn numerator(&self) -> T {
         match self.mode {
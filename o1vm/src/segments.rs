@@ -0,0 +1,199 @@
+//! Splitting a long execution into fixed-size segments.
+//!
+//! An execution is run without being proven ([split]), to record the state of
+//! the virtual machine every `segment_length` instructions. The boundaries of
+//! the segments are exported as [SegmentBoundary], which commit to the
+//! registers and to the memory (see [State::memory_root]).
+//!
+//! NOTE: the segments are not proven. The proofs of the zkVM do not constrain
+//! the state at their start nor at their end, so that the proofs of
+//! consecutive segments could not be chained by their boundaries.
+//!
+//! A segment never ends in the middle of the read of a preimage, so segments
+//! can be slightly longer than `segment_length`.
+
+use crate::{
+    cannon::{Meta, Start, State, VmConfiguration, PAGE_SIZE},
+    mips::witness::{self as mips_witness, MAX_ACC},
+    preimage_oracle::PreImageOracleT,
+    Fp,
+};
+use serde::{Deserialize, Serialize};
+
+/// Public commitment to the state of the virtual machine at the start or at
+/// the end of a segment
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentBoundary {
+    /// Step counter of the state (non-normalized, see [State::step])
+    pub step: u64,
+    pub pc: u32,
+    pub next_pc: u32,
+    pub registers: [u32; 32],
+    /// Merkle root of the memory, see [State::memory_root]
+    #[serde(with = "hex")]
+    pub memory_root: [u8; 32],
+    /// Root of the whole state, see [State::root]
+    #[serde(with = "hex")]
+    pub state_root: [u8; 32],
+}
+
+impl From<&State> for SegmentBoundary {
+    fn from(state: &State) -> Self {
+        SegmentBoundary {
+            step: state.step,
+            pc: state.pc,
+            next_pc: state.next_pc,
+            registers: state.registers,
+            memory_root: state.memory_root(),
+            state_root: state.root(),
+        }
+    }
+}
+
+/// Returns whether the execution started at the (non-normalized) step
+/// `start_step` has executed `max_steps` instructions, and can be stopped.
+/// The execution is never stopped in the middle of the read of a preimage, as
+/// the number of bytes already read is not part of the [State].
+fn reached_max_steps<PreImageOracle: PreImageOracleT>(
+    env: &mips_witness::Env<Fp, PreImageOracle>,
+    start_step: u64,
+    max_steps: u64,
+) -> bool {
+    env.preimage_bytes_read == 0
+        && env.normalized_instruction_counter() - start_step / MAX_ACC >= max_steps
+}
+
+/// Runs the program from `state` without proving it, and returns the states
+/// at the boundaries of the segments of `segment_length` instructions: the
+/// initial state, the start state of each following segment, and the final
+/// state. An execution of `n` segments gives `n + 1` states.
+///
+/// # Panics
+///
+/// Will panic if `segment_length` is zero.
+pub fn split<PreImageOracle: PreImageOracleT>(
+    vm: &VmConfiguration,
+    state: State,
+    meta: &Meta,
+    preimage_oracle: PreImageOracle,
+    segment_length: u64,
+) -> Vec<State> {
+    assert!(segment_length > 0, "segments must not be empty");
+    let start = Start::create(state.step as usize);
+    let mut env =
+        mips_witness::Env::<Fp, PreImageOracle>::create(PAGE_SIZE as usize, state, preimage_oracle);
+
+    let mut boundaries = vec![env.state()];
+    while !env.halt {
+        let start_step = env.instruction_counter;
+        while !env.halt && !reached_max_steps(&env, start_step, segment_length) {
+            env.step(vm, meta, &start);
+            // The Keccak witness is not needed when the execution is not proven
+            env.keccak_env = None;
+        }
+        boundaries.push(env.state());
+    }
+    boundaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cannon::{Page, StepFrequency},
+        mips::fpu::FpPolicy,
+        preimage_oracle::KeyValuePreImageOracle,
+    };
+
+    const NUM_INCREMENTS: usize = 10;
+
+    fn vm_configuration() -> VmConfiguration {
+        VmConfiguration {
+            input_state_file: String::new(),
+            output_state_file: String::new(),
+            metadata_file: String::new(),
+            proof_at: StepFrequency::Never,
+            stop_at: StepFrequency::Never,
+            snapshot_state_at: StepFrequency::Never,
+            info_at: StepFrequency::Never,
+            proof_fmt: String::new(),
+            snapshot_fmt: String::new(),
+            checkpoint_at: StepFrequency::Never,
+            checkpoint_fmt: String::new(),
+            resume_from: None,
+            pprof_cpu: false,
+            host: None,
+            preimage_db_dir: None,
+            preimage_rpc: None,
+            preimage_rpc_method: None,
+            record_journal: None,
+            replay_journal: None,
+            elf_file: None,
+            profile: None,
+            fp_policy: FpPolicy::default(),
+        }
+    }
+
+    // A program incrementing $t0 NUM_INCREMENTS times before exiting
+    fn program_state() -> State {
+        let mut instructions = vec![0x25080001u32; NUM_INCREMENTS]; // addiu $t0, $t0, 1
+        instructions.push(0x24021096); // addiu $v0, $zero, 4246 (exit_group)
+        instructions.push(0x0000000c); // syscall
+        let mut data: Vec<u8> = instructions
+            .into_iter()
+            .flat_map(|instruction| instruction.to_be_bytes())
+            .collect();
+        data.resize(PAGE_SIZE as usize, 0);
+        State {
+            memory: vec![Page { index: 0, data }],
+            preimage_key: [0u8; 32],
+            preimage_offset: 0,
+            pc: 0,
+            next_pc: 4,
+            lo: 0,
+            hi: 0,
+            heap: 0x4000_0000,
+            exit: 0,
+            exited: false,
+            step: 0,
+            registers: [0u32; 32],
+            last_hint: None,
+            preimage: None,
+        }
+    }
+
+    fn meta() -> Meta {
+        Meta { symbols: vec![] }
+    }
+
+    #[test]
+    fn test_split_execution() {
+        let segment_length = 4;
+        let boundaries = split(
+            &vm_configuration(),
+            program_state(),
+            &meta(),
+            KeyValuePreImageOracle::new(),
+            segment_length,
+        );
+
+        // 12 instructions in segments of 4
+        assert_eq!(boundaries.len(), 4);
+        assert!(boundaries.last().unwrap().exited);
+        for (i, state) in boundaries.iter().enumerate().take(boundaries.len() - 1) {
+            assert!(!state.exited);
+            assert_eq!(state.step / MAX_ACC, i as u64 * segment_length);
+            assert_eq!(state.registers[8], i as u32 * segment_length as u32);
+            assert_eq!(state.pc, 4 * i as u32 * segment_length as u32);
+        }
+        assert_eq!(
+            boundaries.last().unwrap().registers[8],
+            NUM_INCREMENTS as u32
+        );
+        // The program does not write to the memory
+        let memory_root = program_state().memory_root();
+        assert!(boundaries
+            .iter()
+            .all(|state| state.memory_root() == memory_root));
+    }
+}
@@ -0,0 +1,452 @@
+//! Recording and replay of the nondeterministic syscall results of an
+//! execution.
+//!
+//! The results of the syscalls of the zkVM only depend on the state of the VM,
+//! except for the answers of the preimage oracle: a host program or a JSON-RPC
+//! server may answer differently, or not at all, when the program is run a
+//! second time. The other syscalls that would be nondeterministic on a real
+//! kernel, like the clock, return constants.
+//!
+//! An execution run with a [RecordingPreImageOracle] writes these answers, in
+//! order, to a [SyscallJournal]. Running the same execution with a
+//! [ReplayPreImageOracle] serves the answers from the journal, and panics as
+//! soon as the program makes a request that was not recorded. This guarantees
+//! that the fast pass of an execution (see [crate::segments::split]) and the
+//! constrained pass generating the witness go through the same trace.
+//!
+//! The journal is stored as JSON lines, one [JournalEntry] per line. The hints
+//! do not change the trace, but they are recorded as well, as a divergence
+//! check.
+
+use crate::{
+    cannon::{Hint, Preimage},
+    preimage_oracle::PreImageOracleT,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+use thiserror::Error;
+
+/// Errors that can arise when reading or writing a journal
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("the journal file cannot be accessed: {0}")]
+    Io(#[from] io::Error),
+    #[error("line {line} of the journal is malformed: {message}")]
+    Format { line: usize, message: String },
+}
+
+/// A syscall result of a recorded execution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum JournalEntry {
+    /// The preimage of `key` returned by the oracle
+    Preimage {
+        #[serde(with = "hex_bytes")]
+        key: Vec<u8>,
+        #[serde(with = "hex_bytes")]
+        preimage: Vec<u8>,
+    },
+    /// A hint sent to the oracle
+    Hint {
+        #[serde(with = "hex_bytes")]
+        hint: Vec<u8>,
+    },
+}
+
+/// The syscall results of an execution, in order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyscallJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl SyscallJournal {
+    /// Creates an empty journal
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded entries
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of recorded entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends `entry` to the journal
+    pub fn push(&mut self, entry: JournalEntry) {
+        self.entries.push(entry)
+    }
+
+    /// Reads the journal stored in the file at `path`
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, JournalError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut journal = Self::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|e| JournalError::Format {
+                line: i + 1,
+                message: e.to_string(),
+            })?;
+            journal.push(entry);
+        }
+        Ok(journal)
+    }
+
+    /// Writes the journal to the file at `path`
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), JournalError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn write_entry<W: Write>(writer: &mut W, entry: &JournalEntry) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")
+}
+
+/// A preimage oracle recording the answers of another oracle to a
+/// [SyscallJournal].
+///
+/// If a file is given, each entry is also written to it as soon as it is
+/// recorded, so that the journal of an execution that is interrupted can still
+/// be replayed up to the interruption.
+pub struct RecordingPreImageOracle<PreImageOracle> {
+    inner: PreImageOracle,
+    journal: SyscallJournal,
+    writer: Option<BufWriter<File>>,
+}
+
+impl<PreImageOracle: PreImageOracleT> RecordingPreImageOracle<PreImageOracle> {
+    /// Records the answers of `inner` in memory
+    pub fn new(inner: PreImageOracle) -> Self {
+        RecordingPreImageOracle {
+            inner,
+            journal: SyscallJournal::new(),
+            writer: None,
+        }
+    }
+
+    /// Records the answers of `inner` in memory and to the file at `path`,
+    /// which is truncated
+    pub fn to_file<P: AsRef<Path>>(inner: PreImageOracle, path: P) -> Result<Self, JournalError> {
+        let writer = BufWriter::new(File::create(path)?);
+        Ok(RecordingPreImageOracle {
+            writer: Some(writer),
+            ..Self::new(inner)
+        })
+    }
+
+    /// Returns the answers recorded so far
+    pub fn journal(&self) -> &SyscallJournal {
+        &self.journal
+    }
+
+    /// Returns the recorded answers, dropping the recorded oracle
+    pub fn into_journal(self) -> SyscallJournal {
+        self.journal
+    }
+
+    fn record(&mut self, entry: JournalEntry) {
+        if let Some(writer) = &mut self.writer {
+            write_entry(writer, &entry)
+                .and_then(|()| writer.flush())
+                .unwrap_or_else(|e| panic!("Could not write to the syscall journal: {e}"));
+        }
+        self.journal.push(entry)
+    }
+}
+
+impl<PreImageOracle: PreImageOracleT> PreImageOracleT for RecordingPreImageOracle<PreImageOracle> {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        let preimage = self.inner.get_preimage(key).get();
+        self.record(JournalEntry::Preimage {
+            key: key.to_vec(),
+            preimage: preimage.clone(),
+        });
+        Preimage::create(preimage)
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        let hint = hint.get();
+        self.record(JournalEntry::Hint { hint: hint.clone() });
+        self.inner.hint(Hint::create(hint))
+    }
+}
+
+/// A preimage oracle serving the answers of a [SyscallJournal], in order.
+///
+/// The requests must be exactly the recorded ones: the oracle panics if the
+/// program requests another key, sends another hint, or makes more requests
+/// than recorded.
+#[derive(Clone, Debug)]
+pub struct ReplayPreImageOracle {
+    journal: SyscallJournal,
+    position: usize,
+}
+
+impl ReplayPreImageOracle {
+    /// Replays `journal` from its start
+    pub fn new(journal: SyscallJournal) -> Self {
+        Self::from_position(journal, 0)
+    }
+
+    /// Replays `journal` from the entry at `position`, e.g. to resume an
+    /// execution from a checkpoint
+    pub fn from_position(journal: SyscallJournal, position: usize) -> Self {
+        assert!(
+            position <= journal.len(),
+            "position {position} is past the end of the journal of {} entries",
+            journal.len()
+        );
+        ReplayPreImageOracle { journal, position }
+    }
+
+    /// Returns the position of the next entry to replay
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns true if all the entries were replayed
+    pub fn is_exhausted(&self) -> bool {
+        self.position == self.journal.len()
+    }
+
+    fn next_entry(&mut self) -> &JournalEntry {
+        let entry = self.journal.entries.get(self.position).unwrap_or_else(|| {
+            panic!(
+                "The execution made more requests than the {} recorded ones",
+                self.journal.len()
+            )
+        });
+        self.position += 1;
+        entry
+    }
+}
+
+impl PreImageOracleT for ReplayPreImageOracle {
+    fn get_preimage(&mut self, key: [u8; 32]) -> Preimage {
+        let position = self.position;
+        match self.next_entry() {
+            JournalEntry::Preimage {
+                key: recorded,
+                preimage,
+            } if recorded[..] == key[..] => Preimage::create(preimage.clone()),
+            entry => panic!(
+                "The execution diverged from the journal at entry {position}: requested the preimage of 0x{}, recorded {entry:?}",
+                hex::encode(key)
+            ),
+        }
+    }
+
+    fn hint(&mut self, hint: Hint) {
+        let position = self.position;
+        let hint = hint.get();
+        match self.next_entry() {
+            JournalEntry::Hint { hint: recorded } if *recorded == hint => (),
+            entry => panic!(
+                "The execution diverged from the journal at entry {position}: sent the hint 0x{}, recorded {entry:?}",
+                hex::encode(hint)
+            ),
+        }
+    }
+}
+
+// (De)serialization of bytes as 0x-prefixed hexadecimal strings
+mod hex_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s.strip_prefix("0x").unwrap_or(&s)).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cannon::{Meta, Page, State, StepFrequency, VmConfiguration, PAGE_SIZE},
+        mips::fpu::FpPolicy,
+        preimage_oracle::{keccak_preimage_key, KeyValuePreImageOracle},
+        segments,
+    };
+
+    fn oracle() -> KeyValuePreImageOracle {
+        let mut oracle = KeyValuePreImageOracle::new();
+        oracle.insert_keccak(b"hello".to_vec());
+        oracle.insert_keccak(b"world".to_vec());
+        oracle
+    }
+
+    // Makes the same requests to `oracle`, and returns the preimages
+    fn requests<O: PreImageOracleT>(mut oracle: O) -> Vec<Vec<u8>> {
+        let hello = oracle.get_preimage(keccak_preimage_key(b"hello")).get();
+        oracle.hint(Hint::create(b"l1-block 0x00".to_vec()));
+        let world = oracle.get_preimage(keccak_preimage_key(b"world")).get();
+        vec![hello, world]
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut recorder = RecordingPreImageOracle::new(oracle());
+        let recorded = requests(&mut recorder);
+        assert_eq!(recorded, vec![b"hello".to_vec(), b"world".to_vec()]);
+        let journal = recorder.into_journal();
+        assert_eq!(journal.len(), 3);
+        assert_eq!(
+            journal.entries()[1],
+            JournalEntry::Hint {
+                hint: b"l1-block 0x00".to_vec()
+            }
+        );
+
+        // The replay does not need the recorded oracle
+        let mut replay = ReplayPreImageOracle::new(journal);
+        assert_eq!(requests(&mut replay), recorded);
+        assert!(replay.is_exhausted());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_replay_divergence() {
+        let mut recorder = RecordingPreImageOracle::new(oracle());
+        requests(&mut recorder);
+        let mut replay = ReplayPreImageOracle::new(recorder.into_journal());
+        replay.get_preimage(keccak_preimage_key(b"world"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_replay_past_the_end() {
+        let mut replay = ReplayPreImageOracle::new(SyscallJournal::new());
+        replay.hint(Hint::create(vec![]));
+    }
+
+    #[test]
+    fn test_journal_file() {
+        let path = std::env::temp_dir().join(format!("o1vm-journal-{}.jsonl", std::process::id()));
+        let mut recorder = RecordingPreImageOracle::to_file(oracle(), &path).unwrap();
+        requests(&mut recorder);
+        // The file is written while recording
+        assert_eq!(&SyscallJournal::read(&path).unwrap(), recorder.journal());
+
+        let journal = recorder.into_journal();
+        journal.write(&path).unwrap();
+        assert_eq!(SyscallJournal::read(&path).unwrap(), journal);
+
+        std::fs::write(&path, "{\"type\":\"hint\"}\n").unwrap();
+        assert!(matches!(
+            SyscallJournal::read(&path),
+            Err(JournalError::Format { line: 1, .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn vm_configuration() -> VmConfiguration {
+        VmConfiguration {
+            input_state_file: String::new(),
+            output_state_file: String::new(),
+            metadata_file: String::new(),
+            proof_at: StepFrequency::Never,
+            stop_at: StepFrequency::Never,
+            snapshot_state_at: StepFrequency::Never,
+            info_at: StepFrequency::Never,
+            proof_fmt: String::new(),
+            snapshot_fmt: String::new(),
+            checkpoint_at: StepFrequency::Never,
+            checkpoint_fmt: String::new(),
+            resume_from: None,
+            pprof_cpu: false,
+            host: None,
+            preimage_db_dir: None,
+            preimage_rpc: None,
+            preimage_rpc_method: None,
+            record_journal: None,
+            replay_journal: None,
+            elf_file: None,
+            profile: None,
+            fp_policy: FpPolicy::default(),
+        }
+    }
+
+    // A program reading the length of the preimage of "hello" before exiting
+    fn program_state() -> State {
+        let instructions = [
+            0x24040005u32, // addiu $a0, $zero, 5 (preimage read fd)
+            0x24050800,    // addiu $a1, $zero, 0x800
+            0x24060004,    // addiu $a2, $zero, 4
+            0x24020fa3,    // addiu $v0, $zero, 4003 (read)
+            0x0000000c,    // syscall
+            0x24021096,    // addiu $v0, $zero, 4246 (exit_group)
+            0x0000000c,    // syscall
+        ];
+        let mut data: Vec<u8> = instructions
+            .into_iter()
+            .flat_map(|instruction| instruction.to_be_bytes())
+            .collect();
+        data.resize(PAGE_SIZE as usize, 0);
+        State {
+            memory: vec![Page { index: 0, data }],
+            preimage_key: keccak_preimage_key(b"hello"),
+            preimage_offset: 0,
+            pc: 0,
+            next_pc: 4,
+            lo: 0,
+            hi: 0,
+            heap: 0x4000_0000,
+            exit: 0,
+            exited: false,
+            step: 0,
+            registers: [0u32; 32],
+            last_hint: None,
+            preimage: None,
+        }
+    }
+
+    #[test]
+    fn test_replayed_execution() {
+        let meta = Meta { symbols: vec![] };
+        let mut recorder = RecordingPreImageOracle::new(oracle());
+        let recorded = segments::split(
+            &vm_configuration(),
+            program_state(),
+            &meta,
+            &mut recorder,
+            2,
+        );
+        let journal = recorder.into_journal();
+        assert_eq!(journal.len(), 1);
+
+        let mut replay = ReplayPreImageOracle::new(journal);
+        let replayed = segments::split(&vm_configuration(), program_state(), &meta, &mut replay, 2);
+        assert!(replay.is_exhausted());
+        assert_eq!(replayed.len(), recorded.len());
+        for (recorded, replayed) in recorded.iter().zip(replayed.iter()) {
+            assert_eq!(recorded.root(), replayed.root());
+        }
+        assert!(replayed.last().unwrap().exited);
+    }
+}
@@ -1,4 +1,3 @@
-use clap::Arg;
 use log::{debug, error};
 use o1vm::{
     cannon::PreimageKey,
@@ -17,20 +16,12 @@ fn main() -> ExitCode {
 
     env_logger::init();
 
-    // Add command-line parameter to read the Optimism op-program DB directory
-    let cli = main_cli().arg(
-        Arg::new("preimage-db-dir")
-            .long("preimage-db-dir")
-            .value_name("PREIMAGE_DB_DIR"),
-    );
-
-    // Now read matches with the additional argument(s)
-    let matches = cli.get_matches();
+    let matches = main_cli().get_matches();
 
     let configuration = read_configuration(&matches);
 
-    // Get DB directory and abort if unset
-    let preimage_db_dir = matches.get_one::<String>("preimage-db-dir");
+    // Get the Optimism op-program DB directory and abort if unset
+    let preimage_db_dir = configuration.preimage_db_dir.as_ref();
 
     if let Some(preimage_key_dir) = preimage_db_dir {
         let mut po = PreImageOracle::create(&configuration.host);
@@ -8,6 +8,7 @@
 use crate::{
     folding::{BaseField, FoldingInstance, FoldingWitness, ScalarField},
     lookups::Lookup,
+    witness_storage::commit_column,
     E,
 };
 use ark_ff::{One, Zero};
@@ -17,7 +18,10 @@ use itertools::Itertools;
 use kimchi::circuits::expr::ChallengeTerm;
 use kimchi_msm::{columns::Column, witness::Witness};
 use mina_poseidon::sponge::FqSponge;
-use poly_commitment::{commitment::absorb_commitment, PolyComm, SRS as _};
+use poly_commitment::{
+    commitment::{absorb_commitment, CommitmentCurve},
+    PolyComm, SRS,
+};
 use rayon::{iter::ParallelIterator, prelude::IntoParallelIterator};
 use std::{collections::BTreeMap, ops::Index};
 
@@ -33,6 +37,40 @@ pub struct Trace<const N: usize, C: FoldingConfig> {
     pub witness: Witness<N, Vec<ScalarField<C>>>,
     pub constraints: Vec<E<ScalarField<C>>>,
     pub lookups: Vec<Lookup<E<ScalarField<C>>>>,
+    /// The columns of the sub-table used by the trace, in increasing order.
+    /// The other columns are zero, and are committed to without any
+    /// multi-scalar multiplication, see [commit_sub_table].
+    pub columns: Vec<usize>,
+}
+
+/// Commits to the columns of a witness whose columns outside of `columns` are
+/// zero, the commitments to the latter being the commitment to zero.
+///
+/// # Panics
+///
+/// Will panic if the Lagrange basis of the domain has not been computed, and,
+/// in debug mode, if a column outside of the sub-table is not zero.
+pub fn commit_sub_table<const N: usize, G: CommitmentCurve, S: SRS<G> + Sync>(
+    srs: &S,
+    domain: D<G::ScalarField>,
+    witness: &Witness<N, Vec<G::ScalarField>>,
+    columns: &[usize],
+) -> Witness<N, PolyComm<G>> {
+    (0..N)
+        .into_par_iter()
+        .map(|i| {
+            let column = &witness.cols[i];
+            if columns.binary_search(&i).is_ok() {
+                commit_column(srs, domain, column)
+            } else {
+                debug_assert!(
+                    column.iter().all(Zero::is_zero),
+                    "column {i} is outside of the sub-table but is not zero"
+                );
+                commit_column(srs, domain, &[])
+            }
+        })
+        .collect()
 }
 
 /// Struct representing a circuit execution trace which is decomposable in
@@ -164,10 +202,12 @@ where
                 .collect(),
         };
 
-        let commitments: Witness<N, PolyComm<C::Curve>> = (&folding_witness.witness)
-            .into_par_iter()
-            .map(|w| srs.commit_evaluations_non_hiding(domain, w))
-            .collect();
+        let commitments: Witness<N, PolyComm<C::Curve>> = commit_sub_table(
+            srs,
+            domain,
+            &self[selector].witness,
+            &self[selector].columns,
+        );
 
         // Absorbing commitments
         (&commitments)
@@ -234,6 +274,10 @@ pub trait Tracer<const N_REL: usize, C: FoldingConfig, Env> {
     /// Add a witness row to the circuit (only for relation columns)
     fn push_row(&mut self, selector: Self::Selector, row: &[ScalarField<C>; N_REL]);
 
+    /// Add several witness rows to the circuit (only for relation columns),
+    /// transposing them into the columns of the witness
+    fn push_rows(&mut self, selector: Self::Selector, rows: &[[ScalarField<C>; N_REL]]);
+
     /// Pad the rows of one opcode with the given row until
     /// reaching the domain size if needed.
     /// Returns the number of rows that were added.
@@ -295,6 +339,10 @@ where
         self.trace.get_mut(&selector).unwrap().push_row((), row);
     }
 
+    fn push_rows(&mut self, selector: Self::Selector, rows: &[[ScalarField<C>; N_REL]]) {
+        self.trace.get_mut(&selector).unwrap().push_rows((), rows);
+    }
+
     fn pad_with_row(&mut self, selector: Self::Selector, row: &[ScalarField<C>; N_REL]) -> usize {
         // We only want to pad non-empty witnesses.
         if !self.in_circuit(selector) {
@@ -0,0 +1,371 @@
+//! This module defines [WitnessStorage], the storage of the columns of an
+//! execution trace.
+//!
+//! The columns of the traces of the zkVM are usually stored in vectors, see
+//! [Witness]. For executions whose trace does not fit in memory, the columns
+//! can be backed by a memory-mapped file with [MmapWitness]: rows are written
+//! to the file as they are generated, and the columns are committed to with
+//! [commit_column] directly from the mapping, the operating system loading and
+//! evicting the pages as needed.
+//!
+//! The columns are stored in column-major order, each column occupying a
+//! contiguous region of the file, so that a column can be read as a slice
+//! without any copy.
+//! The field elements are written in their in-memory representation, so that
+//! the files are only meant to be read back by the process that wrote them.
+
+use ark_ff::PrimeField;
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
+use kimchi_msm::witness::Witness;
+use poly_commitment::{
+    commitment::{CommitmentCurve, PolyComm},
+    SRS,
+};
+use rayon::prelude::*;
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    marker::PhantomData,
+    mem,
+    os::unix::io::AsRawFd,
+    path::Path,
+    ptr::{self, NonNull},
+    slice,
+};
+use thiserror::Error;
+
+/// Errors that can arise when storing a witness
+#[derive(Error, Debug)]
+pub enum WitnessStorageError {
+    #[error("the witness storage is full ({0} rows)")]
+    Full(usize),
+    #[error("expected a row of {0} values, got {1}")]
+    RowLength(usize, usize),
+    #[error("the witness file could not be mapped: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// The storage of the columns of a witness, filled row by row
+pub trait WitnessStorage<F> {
+    /// The number of columns
+    fn num_columns(&self) -> usize;
+
+    /// The maximum number of rows
+    fn capacity(&self) -> usize;
+
+    /// The number of rows stored
+    fn len(&self) -> usize;
+
+    /// Whether no row is stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a row, with a value for each column
+    fn push_row(&mut self, row: &[F]) -> Result<(), WitnessStorageError>;
+
+    /// The rows stored in the given column
+    fn column(&self, col: usize) -> &[F];
+
+    /// The rows stored in the given column, mutably
+    fn column_mut(&mut self, col: usize) -> &mut [F];
+}
+
+/// The in-memory storage used by the traces of the zkVM, whose capacity is the
+/// capacity of its columns
+impl<const N: usize, F: Copy> WitnessStorage<F> for Witness<N, Vec<F>> {
+    fn num_columns(&self) -> usize {
+        N
+    }
+
+    fn capacity(&self) -> usize {
+        self.cols[0].capacity()
+    }
+
+    fn len(&self) -> usize {
+        self.cols[0].len()
+    }
+
+    fn push_row(&mut self, row: &[F]) -> Result<(), WitnessStorageError> {
+        if row.len() != N {
+            return Err(WitnessStorageError::RowLength(N, row.len()));
+        }
+        if self.len() == self.capacity() {
+            return Err(WitnessStorageError::Full(self.capacity()));
+        }
+        self.cols
+            .iter_mut()
+            .zip(row.iter())
+            .for_each(|(col, value)| col.push(*value));
+        Ok(())
+    }
+
+    fn column(&self, col: usize) -> &[F] {
+        &self.cols[col]
+    }
+
+    fn column_mut(&mut self, col: usize) -> &mut [F] {
+        &mut self.cols[col]
+    }
+}
+
+/// A witness stored in a memory-mapped file, see the
+/// [module documentation](self)
+pub struct MmapWitness<F> {
+    // Kept open for the lifetime of the mapping
+    _file: File,
+    data: NonNull<F>,
+    num_columns: usize,
+    capacity: usize,
+    len: usize,
+    _marker: PhantomData<F>,
+}
+
+// The mapping is owned by the witness, as a vector would be
+unsafe impl<F: Send> Send for MmapWitness<F> {}
+unsafe impl<F: Sync> Sync for MmapWitness<F> {}
+
+impl<F: PrimeField> MmapWitness<F> {
+    fn size_in_bytes(num_columns: usize, capacity: usize) -> usize {
+        num_columns * capacity * mem::size_of::<F>()
+    }
+
+    /// Creates an empty witness with `num_columns` columns of up to `capacity`
+    /// rows, stored in the file at `path`, which is overwritten.
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the file cannot be created or mapped in memory.
+    pub fn create(
+        path: &Path,
+        num_columns: usize,
+        capacity: usize,
+    ) -> Result<Self, WitnessStorageError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        // The file is filled with zeroes, which represent the zero field element
+        let size = Self::size_in_bytes(num_columns, capacity);
+        file.set_len(size as u64)?;
+
+        let data = if size == 0 {
+            NonNull::dangling()
+        } else {
+            let ptr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error().into());
+            }
+            NonNull::new(ptr as *mut F).expect("mmap does not return a null pointer")
+        };
+
+        Ok(MmapWitness {
+            _file: file,
+            data,
+            num_columns,
+            capacity,
+            len: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Writes the modified pages back to the file
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the pages cannot be written.
+    pub fn flush(&self) -> Result<(), WitnessStorageError> {
+        let size = Self::size_in_bytes(self.num_columns, self.capacity);
+        if size == 0 {
+            return Ok(());
+        }
+        let res = unsafe { libc::msync(self.data.as_ptr() as *mut _, size, libc::MS_SYNC) };
+        if res != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    /// Copies the witness in memory, for traces that fit in memory
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the witness does not have `N` columns.
+    pub fn to_witness<const N: usize>(&self) -> Witness<N, Vec<F>> {
+        assert_eq!(self.num_columns, N);
+        Witness {
+            cols: Box::new(std::array::from_fn(|col| {
+                let mut column = Vec::with_capacity(self.capacity);
+                column.extend_from_slice(self.column(col));
+                column
+            })),
+        }
+    }
+
+    fn column_ptr(&self, col: usize) -> *mut F {
+        assert!(col < self.num_columns, "column {col} out of bounds");
+        // the pointer is dangling but the column empty when the size is zero
+        unsafe { self.data.as_ptr().add(col * self.capacity) }
+    }
+}
+
+impl<F: PrimeField> WitnessStorage<F> for MmapWitness<F> {
+    fn num_columns(&self) -> usize {
+        self.num_columns
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn push_row(&mut self, row: &[F]) -> Result<(), WitnessStorageError> {
+        if row.len() != self.num_columns {
+            return Err(WitnessStorageError::RowLength(self.num_columns, row.len()));
+        }
+        if self.len == self.capacity {
+            return Err(WitnessStorageError::Full(self.capacity));
+        }
+        for (col, value) in row.iter().enumerate() {
+            unsafe { self.column_ptr(col).add(self.len).write(*value) };
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    fn column(&self, col: usize) -> &[F] {
+        unsafe { slice::from_raw_parts(self.column_ptr(col), self.len) }
+    }
+
+    fn column_mut(&mut self, col: usize) -> &mut [F] {
+        unsafe { slice::from_raw_parts_mut(self.column_ptr(col), self.len) }
+    }
+}
+
+impl<F> Drop for MmapWitness<F> {
+    fn drop(&mut self) {
+        let size = self.num_columns * self.capacity * mem::size_of::<F>();
+        if size != 0 {
+            unsafe { libc::munmap(self.data.as_ptr() as *mut _, size) };
+        }
+    }
+}
+
+/// Commits to the evaluations of a column over the domain, the missing rows
+/// being zero, without copying the column.
+///
+/// # Panics
+///
+/// Will panic if the column is larger than the domain, or if the Lagrange
+/// basis of the domain has not been computed.
+pub fn commit_column<G: CommitmentCurve, S: SRS<G>>(
+    srs: &S,
+    domain: D<G::ScalarField>,
+    column: &[G::ScalarField],
+) -> PolyComm<G> {
+    assert!(column.len() <= domain.size());
+    let basis = srs
+        .get_lagrange_basis(domain.size())
+        .unwrap_or_else(|| panic!("lagrange bases for size {} not found", domain.size()));
+    if column.is_empty() {
+        return basis[0].map(|_| G::zero());
+    }
+    let bases: Vec<_> = basis[..column.len()].iter().collect();
+    PolyComm::multi_scalar_mul(&bases, column)
+}
+
+/// Commits to all the columns of a witness, see [commit_column]
+pub fn commit_columns<G, S, W>(srs: &S, domain: D<G::ScalarField>, witness: &W) -> Vec<PolyComm<G>>
+where
+    G: CommitmentCurve,
+    S: SRS<G> + Sync,
+    W: WitnessStorage<G::ScalarField> + Sync,
+{
+    (0..witness.num_columns())
+        .into_par_iter()
+        .map(|col| commit_column(srs, domain, witness.column(col)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{UniformRand, Zero};
+    use ark_poly::Evaluations;
+    use kimchi::o1_utils;
+    use mina_curves::pasta::{Fp, Vesta};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("o1vm-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_mmap_witness() {
+        let mut rng = o1_utils::tests::make_test_rng(None);
+        let path = temp_path("mmap-witness");
+        let domain = D::<Fp>::new(8).unwrap();
+        let mut witness = MmapWitness::<Fp>::create(&path, 3, domain.size()).unwrap();
+        let mut in_memory: Witness<3, Vec<Fp>> = Witness {
+            cols: Box::new(std::array::from_fn(|_| Vec::with_capacity(domain.size()))),
+        };
+
+        for _ in 0..6 {
+            let row = [Fp::rand(&mut rng), Fp::rand(&mut rng), Fp::zero()];
+            witness.push_row(&row).unwrap();
+            in_memory.push_row(&row).unwrap();
+        }
+        assert_eq!(witness.len(), 6);
+        for col in 0..3 {
+            assert_eq!(witness.column(col), in_memory.column(col));
+        }
+        assert!(matches!(
+            witness.push_row(&[Fp::zero()]),
+            Err(WitnessStorageError::RowLength(3, 1))
+        ));
+        witness.flush().unwrap();
+
+        // the commitments agree with the ones of the padded columns
+        let mut srs = poly_commitment::srs::SRS::<Vesta>::create(domain.size());
+        srs.add_lagrange_basis(domain);
+        let commitments = commit_columns(&srs, domain, &witness);
+        for (col, commitment) in commitments.iter().enumerate() {
+            let mut evals = witness.column(col).to_vec();
+            evals.resize(domain.size(), Fp::zero());
+            let evals = Evaluations::from_vec_and_domain(evals, domain);
+            assert_eq!(
+                *commitment,
+                srs.commit_evaluations_non_hiding(domain, &evals)
+            );
+        }
+        assert_eq!(commitments, commit_columns(&srs, domain, &in_memory));
+
+        let copy: Witness<3, Vec<Fp>> = witness.to_witness();
+        assert_eq!(copy.cols, in_memory.cols);
+
+        // the storage is bounded by its capacity
+        for _ in 6..domain.size() {
+            witness.push_row(&[Fp::zero(); 3]).unwrap();
+        }
+        assert!(matches!(
+            witness.push_row(&[Fp::zero(); 3]),
+            Err(WitnessStorageError::Full(8))
+        ));
+
+        drop(witness);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+use ark_ff::UniformRand;
+use ark_poly::{univariate::DensePolynomial, UVPolynomial};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mina_curves::pasta::{Fp, Vesta};
+use poly_commitment::{srs::SRS, SRS as _};
+
+pub fn bench_msm(c: &mut Criterion) {
+    let mut group = c.benchmark_group("msm");
+    group.sample_size(10);
+    let mut rng = o1_utils::tests::make_test_rng(None);
+
+    for log_size in [10, 14, 16] {
+        let n = 1 << log_size;
+        let srs = SRS::<Vesta>::create(n);
+        let coeffs: Vec<Fp> = (0..n).map(|_| Fp::rand(&mut rng)).collect();
+        let poly = DensePolynomial::from_coefficients_vec(coeffs);
+
+        group.bench_with_input(
+            BenchmarkId::new("commit non hiding", format!("2^{log_size}")),
+            &poly,
+            |b, poly| b.iter(|| srs.commit_non_hiding(black_box(poly), 1)),
+        );
+    }
+}
+
+criterion_group!(benches, bench_msm);
+criterion_main!(benches);
@@ -0,0 +1,301 @@
+//! Aggregation of the opening proofs of independent proofs.
+//!
+//! Checking an opening proof of the inner product argument has two parts: a
+//! succinct part, logarithmic in the size of the SRS, and the check that the
+//! component `sg` of the proof is the commitment to the polynomial `b(X)` of
+//! the challenges of the proof, which is a multi-scalar multiplication of the
+//! size of the SRS.
+//!
+//! [verify_succinct] only checks the first part, and returns the second one as
+//! an [Accumulator]. [aggregate] combines any number of accumulators into a
+//! single one: it opens a random linear combination of their polynomials at a
+//! random point, which is checked succinctly by [verify_aggregation], giving
+//! the new accumulator ([Accumulator::fold] does both on the side of the
+//! prover). Accumulators can be carried forward and aggregated again, and only
+//! the last one needs the linear check [Accumulator::verify_final].
+//!
+//! The verifier of N proofs then performs a single multi-scalar multiplication
+//! of the size of the SRS, instead of checking the N polynomials `b(X)`.
+
+use crate::{
+    commitment::{
+        add_succinct_check_terms, b_poly, b_poly_coefficients, combined_inner_product,
+        BatchEvaluationProof, CommitmentCurve, EndoCurve, Evaluation,
+    },
+    evaluation_proof::{DensePolynomialOrEvaluations, OpeningProof},
+    srs::SRS,
+    PolyComm,
+};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ff::{One, PrimeField, UniformRand, Zero};
+use ark_poly::{univariate::DensePolynomial, Radix2EvaluationDomain as D, UVPolynomial};
+use mina_poseidon::FqSponge;
+use o1_utils::math;
+use rand_core::{CryptoRng, RngCore};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+/// The deferred check of an opening proof: `comm` must be the commitment to the
+/// polynomial `b(X) = prod_i (1 + chals_{k-1-i} X^{2^i})` of its challenges
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "G: ark_serialize::CanonicalDeserialize + ark_serialize::CanonicalSerialize")]
+pub struct Accumulator<G: CommitmentCurve> {
+    /// The challenges of the opening proof
+    #[serde_as(as = "Vec<o1_utils::serialization::SerdeAs>")]
+    pub chals: Vec<G::ScalarField>,
+    /// The component `sg` of the opening proof
+    #[serde_as(as = "o1_utils::serialization::SerdeAs")]
+    pub comm: G,
+}
+
+impl<G: CommitmentCurve> Accumulator<G> {
+    /// Creates the accumulator of the opening proof with challenges `chals` and
+    /// component `sg` equal to `comm`
+    pub fn new(chals: Vec<G::ScalarField>, comm: G) -> Self {
+        Accumulator { chals, comm }
+    }
+
+    /// The polynomial `b(X)` of the challenges
+    pub fn polynomial(&self) -> DensePolynomial<G::ScalarField> {
+        DensePolynomial::from_coefficients_vec(b_poly_coefficients(&self.chals))
+    }
+
+    /// Evaluates the polynomial `b(X)` of the challenges at `x`, in time
+    /// logarithmic in its degree
+    pub fn evaluate(&self, x: G::ScalarField) -> G::ScalarField {
+        b_poly(&self.chals, x)
+    }
+
+    /// Checks that `comm` is the commitment to the polynomial of the challenges,
+    /// with a multi-scalar multiplication of the size of the SRS. This is the
+    /// only check deferred by the accumulator, see [decide] to check several of
+    /// them at once.
+    pub fn verify_final(&self, srs: &SRS<G>) -> bool {
+        decide(srs, std::slice::from_ref(self), &mut rand::thread_rng())
+    }
+
+    /// Folds `accumulators` into a single one, and returns it with the proof of
+    /// the folding. The verifier obtains the same accumulator from the proof with
+    /// [verify_aggregation], given `accumulators` and the same sponge.
+    ///
+    /// # Panics
+    ///
+    /// Will panic in the same cases as [aggregate].
+    pub fn fold<EFqSponge, RNG>(
+        srs: &SRS<G>,
+        group_map: &G::Map,
+        accumulators: &[Accumulator<G>],
+        sponge: EFqSponge,
+        rng: &mut RNG,
+    ) -> (Accumulator<G>, OpeningProof<G>)
+    where
+        G: EndoCurve,
+        G::BaseField: PrimeField,
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        RNG: RngCore + CryptoRng,
+    {
+        let proof = aggregate(srs, group_map, accumulators, sponge.clone(), rng);
+        let accumulator = verify_aggregation(srs, group_map, accumulators, &proof, sponge, rng)
+            .expect("the aggregation proof of the prover is valid");
+        (accumulator, proof)
+    }
+}
+
+/// Checks a list of accumulators at once, with a single multi-scalar
+/// multiplication of the size of the SRS
+pub fn decide<G, RNG>(srs: &SRS<G>, accumulators: &[Accumulator<G>], rng: &mut RNG) -> bool
+where
+    G: CommitmentCurve,
+    RNG: RngCore + CryptoRng,
+{
+    let rounds = math::ceil_log2(srs.g.len());
+    if accumulators.iter().any(|acc| acc.chals.len() != rounds) {
+        return false;
+    }
+
+    // sum_i r^i (<b_i, g> - comm_i) == 0
+    let rand_base = G::ScalarField::rand(rng);
+    let mut scalars = vec![G::ScalarField::zero(); srs.g.len()];
+    let mut points = srs.g.clone();
+    let mut rand_base_i = G::ScalarField::one();
+    for acc in accumulators {
+        let s = b_poly_coefficients(&acc.chals);
+        scalars[..srs.g.len()]
+            .par_iter_mut()
+            .zip(s.par_iter())
+            .for_each(|(scalar, s)| *scalar += rand_base_i * s);
+        scalars.push(-rand_base_i);
+        points.push(acc.comm);
+        rand_base_i *= rand_base;
+    }
+
+    let scalars: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
+    VariableBaseMSM::multi_scalar_mul(&points, &scalars).is_zero()
+}
+
+/// Verifies a batch of opening proofs like [SRS::verify], except the checks of
+/// their components `sg`, which are returned as accumulators, in the order of
+/// the batch. Returns `None` if the succinct part of a proof is invalid.
+pub fn verify_succinct<G, EFqSponge, RNG>(
+    srs: &SRS<G>,
+    group_map: &G::Map,
+    batch: &mut [BatchEvaluationProof<G, EFqSponge, OpeningProof<G>>],
+    rng: &mut RNG,
+) -> Option<Vec<Accumulator<G>>>
+where
+    G: CommitmentCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+    RNG: RngCore + CryptoRng,
+{
+    let rounds = math::ceil_log2(srs.g.len());
+    if batch.iter().any(|proof| proof.opening.lr.len() != rounds) {
+        return None;
+    }
+
+    let mut scalars = vec![G::ScalarField::zero()];
+    let mut points = vec![srs.h];
+    let rand_base = G::ScalarField::rand(rng);
+    let chals = add_succinct_check_terms(group_map, batch, rand_base, &mut scalars, &mut points);
+
+    let scalars: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
+    if !VariableBaseMSM::multi_scalar_mul(&points, &scalars).is_zero() {
+        return None;
+    }
+
+    Some(
+        batch
+            .iter()
+            .zip(chals)
+            .map(|(proof, chals)| Accumulator {
+                chals,
+                comm: proof.opening.sg,
+            })
+            .collect(),
+    )
+}
+
+// Absorbs the accumulators, and returns the challenges combining their
+// polynomials and the point at which the combination is opened
+fn aggregation_challenges<G, EFqSponge>(
+    accumulators: &[Accumulator<G>],
+    sponge: &mut EFqSponge,
+) -> (G::ScalarField, G::ScalarField)
+where
+    G: CommitmentCurve,
+    EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+{
+    for acc in accumulators {
+        sponge.absorb_g(&[acc.comm]);
+        sponge.absorb_fr(&acc.chals);
+    }
+    let polyscale = sponge.challenge();
+    let point = sponge.challenge();
+    (polyscale, point)
+}
+
+/// Aggregates accumulators of the same SRS into a single one, by opening a
+/// random linear combination of their polynomials at a random point. The
+/// aggregated accumulator is obtained from the proof by [verify_aggregation].
+///
+/// # Panics
+///
+/// Will panic if `accumulators` is empty, if the size of the SRS is not a power
+/// of two, or if the number of challenges of an accumulator does not match it.
+pub fn aggregate<G, EFqSponge, RNG>(
+    srs: &SRS<G>,
+    group_map: &G::Map,
+    accumulators: &[Accumulator<G>],
+    mut sponge: EFqSponge,
+    rng: &mut RNG,
+) -> OpeningProof<G>
+where
+    G: CommitmentCurve + EndoCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+    RNG: RngCore + CryptoRng,
+{
+    assert!(!accumulators.is_empty(), "no accumulator to aggregate");
+    assert!(
+        srs.g.len().is_power_of_two(),
+        "the size of the SRS must be a power of two"
+    );
+    let rounds = math::ceil_log2(srs.g.len());
+    assert!(
+        accumulators.iter().all(|acc| acc.chals.len() == rounds),
+        "the accumulators must have {rounds} challenges"
+    );
+
+    let (polyscale, point) = aggregation_challenges(accumulators, &mut sponge);
+    let polys: Vec<_> = accumulators.iter().map(Accumulator::polynomial).collect();
+    let plnms: Vec<_> = polys
+        .iter()
+        .map(|poly| {
+            (
+                DensePolynomialOrEvaluations::<_, D<_>>::DensePolynomial(poly),
+                PolyComm {
+                    elems: vec![G::ScalarField::zero()],
+                },
+            )
+        })
+        .collect();
+    srs.open(
+        group_map,
+        &plnms,
+        &[point],
+        polyscale,
+        G::ScalarField::one(),
+        sponge,
+        rng,
+    )
+}
+
+/// Verifies succinctly the aggregation of `accumulators` created by
+/// [aggregate], and returns the aggregated accumulator, or `None` if the proof
+/// is invalid
+pub fn verify_aggregation<G, EFqSponge, RNG>(
+    srs: &SRS<G>,
+    group_map: &G::Map,
+    accumulators: &[Accumulator<G>],
+    proof: &OpeningProof<G>,
+    mut sponge: EFqSponge,
+    rng: &mut RNG,
+) -> Option<Accumulator<G>>
+where
+    G: CommitmentCurve,
+    G::BaseField: PrimeField,
+    EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+    RNG: RngCore + CryptoRng,
+{
+    if accumulators.is_empty() || !srs.g.len().is_power_of_two() {
+        return None;
+    }
+    let (polyscale, point) = aggregation_challenges(accumulators, &mut sponge);
+    let evaluations: Vec<_> = accumulators
+        .iter()
+        .map(|acc| Evaluation {
+            commitment: PolyComm {
+                elems: vec![acc.comm],
+            },
+            evaluations: vec![vec![acc.evaluate(point)]],
+        })
+        .collect();
+    let evals: Vec<_> = evaluations
+        .iter()
+        .map(|eval| eval.evaluations.clone())
+        .collect();
+    let combined_inner_product = combined_inner_product(&polyscale, &G::ScalarField::one(), &evals);
+
+    let mut batch = [BatchEvaluationProof {
+        sponge,
+        evaluations,
+        evaluation_points: vec![point],
+        polyscale,
+        evalscale: G::ScalarField::one(),
+        opening: proof,
+        combined_inner_product,
+    }];
+    verify_succinct(srs, group_map, &mut batch, rng).and_then(|mut accs| accs.pop())
+}
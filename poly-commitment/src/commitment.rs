@@ -6,10 +6,9 @@
 //!     producing the batched opening proof
 //! 3. Verify batch of batched opening proofs
 
-use std::time::{Duration, Instant};
 use crate::{
     error::CommitmentError,
-    srs::{endos, SRS},
+    srs::{try_endos, SRS},
     SRS as SRSTrait,
 };
 use ark_ec::{
@@ -23,16 +22,19 @@ use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
 };
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::{Blake2b512, Digest};
 use core::ops::{Add, Sub};
 use groupmap::{BWParameters, GroupMap};
 use mina_poseidon::{sponge::ScalarChallenge, FqSponge};
 use o1_utils::{math, ExtendedDensePolynomial as _};
-use rand_core::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
 use rayon::prelude::*;
 use serde::{de::Visitor, Deserialize, Serialize};
 use serde_with::{
     de::DeserializeAsWrap, ser::SerializeAsWrap, serde_as, DeserializeAs, SerializeAs,
 };
+use std::time::{Duration, Instant};
 use std::{iter::Iterator, marker::PhantomData};
 
 use super::evaluation_proof::*;
@@ -287,6 +289,11 @@ impl<C: AffineCurve> PolyComm<C> {
                 .filter_map(|(com, scalar)| com.elems.get(chunk).map(|c| (c, scalar)))
                 .unzip();
 
+            // verifiers built with `sequential_verifier` combine their
+            // commitments without thread pool
+            #[cfg(feature = "sequential_verifier")]
+            let chunk_msm = msm_sequential::<C>(&points, &scalars);
+            #[cfg(not(feature = "sequential_verifier"))]
             let chunk_msm = VariableBaseMSM::multi_scalar_mul::<C>(&points, &scalars);
             elems.push(chunk_msm.into_affine());
         }
@@ -296,6 +303,21 @@ impl<C: AffineCurve> PolyComm<C> {
     }
 }
 
+/// Returns a random number generator deterministically derived from `seed`,
+/// to produce the blinders of commitments when a proof must be reproducible.
+///
+/// The seed is hashed with a domain separator before being used as the key of
+/// ChaCha20, so that the stream of blinders is independent of other uses of
+/// the same seed.
+pub fn blinding_rng(seed: [u8; 32]) -> ChaCha20Rng {
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"poly-commitment blinding rng");
+    hasher.update(seed);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize()[..32]);
+    ChaCha20Rng::from_seed(key)
+}
+
 /// Returns the product of all the field elements belonging to an iterator.
 pub fn product<F: Field>(xs: impl Iterator<Item = F>) -> F {
     let mut res = F::one();
@@ -363,6 +385,16 @@ pub fn squeeze_challenge<
     squeeze_prechallenge(sponge).to_field(endo_r)
 }
 
+/// Maps the scalar challenge `chal` to a scalar with the endomorphism
+/// coefficient `endo_r` if the curve has an endomorphism, or takes the
+/// challenge as it is otherwise.
+pub fn challenge_to_field<Fr: PrimeField>(chal: &ScalarChallenge<Fr>, endo_r: Option<&Fr>) -> Fr {
+    match endo_r {
+        Some(endo_r) => chal.to_field(endo_r),
+        None => chal.0,
+    }
+}
+
 pub fn absorb_commitment<
     Fq: Field,
     G: Clone,
@@ -792,6 +824,26 @@ where
         }
     }
 
+    fn commit_sparse_evaluations_non_hiding(
+        &self,
+        domain: D<G::ScalarField>,
+        evals: &[(usize, G::ScalarField)],
+    ) -> PolyComm<G> {
+        let basis = self
+            .lagrange_bases
+            .get(&domain.size())
+            .unwrap_or_else(|| panic!("lagrange bases for size {} not found", domain.size()));
+        if evals.is_empty() {
+            // same number of chunks as the commitment to the zero evaluations
+            return basis[0].map(|_| G::zero());
+        }
+        let (bases, scalars): (Vec<_>, Vec<_>) = evals
+            .iter()
+            .map(|(row, eval)| (&basis[*row], *eval))
+            .unzip();
+        PolyComm::<G>::multi_scalar_mul(&bases, &scalars)
+    }
+
     fn commit_evaluations(
         &self,
         domain: D<G::ScalarField>,
@@ -818,6 +870,10 @@ where
         self.add_lagrange_basis(domain)
     }
 
+    fn add_lagrange_bases(&mut self, domains: &[D<<G>::ScalarField>]) {
+        self.add_lagrange_bases(domains)
+    }
+
     fn size(&self) -> usize {
         self.g.len()
     }
@@ -863,8 +919,6 @@ impl<G: CommitmentCurve> SRS<G> {
 
         let padded_length = 1 << max_rounds;
 
-        let (_, endo_r) = endos::<G>();
-
         // TODO: This will need adjusting
         let padding = padded_length - nonzero_length;
         let mut points = vec![self.h];
@@ -878,53 +932,16 @@ impl<G: CommitmentCurve> SRS<G> {
         let rand_base = G::ScalarField::rand(rng);
         let sg_rand_base = G::ScalarField::rand(rng);
 
-        let mut rand_base_i = G::ScalarField::one();
-        let mut sg_rand_base_i = G::ScalarField::one();
-
-        for BatchEvaluationProof {
-            sponge,
-            evaluation_points,
-            polyscale,
-            evalscale,
-            evaluations,
-            opening,
-            combined_inner_product,
-        } in batch.iter_mut()
-        {
-            sponge.absorb_fr(&[shift_scalar::<G>(*combined_inner_product)]);
-
-            let t = sponge.challenge_fq();
-            let u: G = to_group(group_map, t);
-
-            let Challenges { chal, chal_inv } = opening.challenges::<EFqSponge>(&endo_r, sponge);
-
-            sponge.absorb_g(&[opening.delta]);
-            let c = ScalarChallenge(sponge.challenge()).to_field(&endo_r);
-
-            // < s, sum_i evalscale^i pows(evaluation_point[i]) >
-            // ==
-            // sum_i evalscale^i < s, pows(evaluation_point[i]) >
-            let b0 = {
-                let mut scale = G::ScalarField::one();
-                let mut res = G::ScalarField::zero();
-                for &e in evaluation_points.iter() {
-                    let term = b_poly(&chal, e);
-                    res += &(scale * term);
-                    scale *= *evalscale;
-                }
-                res
-            };
+        let chals =
+            add_succinct_check_terms(group_map, batch, rand_base, &mut scalars, &mut points);
 
+        let mut sg_rand_base_i = G::ScalarField::one();
+        for (BatchEvaluationProof { opening, .. }, chal) in batch.iter().zip(chals) {
             let s = b_poly_coefficients(&chal);
 
-            let neg_rand_base_i = -rand_base_i;
-
-            // TERM
-            // - rand_base_i z1 G
-            //
-            // we also add -sg_rand_base_i * G to check correctness of sg.
+            // We add -sg_rand_base_i * sg to check correctness of sg.
             points.push(opening.sg);
-            scalars.push(neg_rand_base_i * opening.z1 - sg_rand_base_i);
+            scalars.push(-sg_rand_base_i);
 
             // Here we add
             // sg_rand_base_i * ( < s, self.g > )
@@ -940,55 +957,170 @@ impl<G: CommitmentCurve> SRS<G> {
                 }
             }
 
-            // TERM
-            // - rand_base_i * z2 * H
-            scalars[0] -= &(rand_base_i * opening.z2);
-
-            // TERM
-            // -rand_base_i * (z1 * b0 * U)
-            scalars.push(neg_rand_base_i * (opening.z1 * b0));
-            points.push(u);
-
-            // TERM
-            // rand_base_i c_i Q_i
-            // = rand_base_i c_i
-            //   (sum_j (chal_invs[j] L_j + chals[j] R_j) + P_prime)
-            // where P_prime = combined commitment + combined_inner_product * U
-            let rand_base_i_c_i = c * rand_base_i;
-            for ((l, r), (u_inv, u)) in opening.lr.iter().zip(chal_inv.iter().zip(chal.iter())) {
-                points.push(*l);
-                scalars.push(rand_base_i_c_i * u_inv);
-
-                points.push(*r);
-                scalars.push(rand_base_i_c_i * u);
-            }
+            sg_rand_base_i *= &sg_rand_base;
+        }
+
+        // verify the equation
+        let scalars: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
+        VariableBaseMSM::multi_scalar_mul(&points, &scalars) == G::Projective::zero()
+    }
 
-            // TERM
-            // sum_j evalscale^j (sum_i polyscale^i f_i) (elm_j)
-            // == sum_j sum_i evalscale^j polyscale^i f_i(elm_j)
-            // == sum_i polyscale^i sum_j evalscale^j f_i(elm_j)
-            combine_commitments(
-                evaluations,
+    /// Verifies a batch of polynomial commitment opening proofs like
+    /// [SRS::verify], one proof at a time and without thread pool, for
+    /// environments such as wasm smart contracts or mobile light clients.
+    ///
+    /// The proofs are not combined with random scalars, so that no source of
+    /// randomness is needed, and the points of the SRS are not copied: each
+    /// proof is checked with two sequential multi-scalar multiplications (see
+    /// [msm_sequential]), one over the few points of its succinct check and
+    /// one over the SRS for its component `sg`. This is slower than
+    /// [SRS::verify] for batches of several proofs.
+    /// Return `true` if the verification is successful, `false` otherwise.
+    pub fn verify_sequential<EFqSponge>(
+        &self,
+        group_map: &G::Map,
+        batch: &mut [BatchEvaluationProof<G, EFqSponge, OpeningProof<G>>],
+    ) -> bool
+    where
+        EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+        G::BaseField: PrimeField,
+    {
+        // the buffers of the succinct checks are reused across proofs
+        let mut points = vec![];
+        let mut scalars = vec![];
+
+        batch.iter_mut().all(|proof| {
+            points.clear();
+            points.push(self.h);
+            scalars.clear();
+            scalars.push(G::ScalarField::zero());
+
+            let chals = add_succinct_check_terms(
+                group_map,
+                std::slice::from_mut(proof),
+                G::ScalarField::one(),
                 &mut scalars,
                 &mut points,
-                *polyscale,
-                rand_base_i_c_i,
             );
+            let reprs: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
+            if !msm_sequential(&points, &reprs).is_zero() {
+                return false;
+            }
 
-            scalars.push(rand_base_i_c_i * *combined_inner_product);
-            points.push(u);
+            // sg = < s, self.g >, the coefficients of s beyond the size of
+            // the SRS multiply the padding of the SRS, which is zero
+            let s: Vec<_> = b_poly_coefficients(&chals[0])
+                .into_iter()
+                .map(|s| s.into_repr())
+                .collect();
+            msm_sequential(&self.g, &s) == proof.opening.sg.into_projective()
+        })
+    }
+}
 
-            scalars.push(rand_base_i);
-            points.push(opening.delta);
+/// Adds to the multi-scalar multiplication `(scalars, points)` the terms of the
+/// checks of the opening proofs of `batch`, scaled by the powers of `rand_base`,
+/// except the checks that their `sg` components are the commitments to the
+/// polynomials of their challenges, which need a multi-scalar multiplication of
+/// the size of the SRS. The scalar of `h` must be `scalars[0]`.
+/// Returns the challenges of each opening proof.
+pub(crate) fn add_succinct_check_terms<G, EFqSponge>(
+    group_map: &G::Map,
+    batch: &mut [BatchEvaluationProof<G, EFqSponge, OpeningProof<G>>],
+    rand_base: G::ScalarField,
+    scalars: &mut Vec<G::ScalarField>,
+    points: &mut Vec<G>,
+) -> Vec<Vec<G::ScalarField>>
+where
+    G: CommitmentCurve,
+    EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+    G::BaseField: PrimeField,
+{
+    let endo_r = try_endos::<G>().map(|(_, endo_r)| endo_r);
+    let mut rand_base_i = G::ScalarField::one();
+    let mut chals = Vec::with_capacity(batch.len());
+
+    for BatchEvaluationProof {
+        sponge,
+        evaluation_points,
+        polyscale,
+        evalscale,
+        evaluations,
+        opening,
+        combined_inner_product,
+    } in batch.iter_mut()
+    {
+        sponge.absorb_fr(&[shift_scalar::<G>(*combined_inner_product)]);
+
+        let t = sponge.challenge_fq();
+        let u: G = to_group(group_map, t);
+
+        let Challenges { chal, chal_inv } =
+            opening.challenges::<EFqSponge>(endo_r.as_ref(), sponge);
+
+        sponge.absorb_g(&[opening.delta]);
+        let c = challenge_to_field(&ScalarChallenge(sponge.challenge()), endo_r.as_ref());
+
+        // < s, sum_i evalscale^i pows(evaluation_point[i]) >
+        // ==
+        // sum_i evalscale^i < s, pows(evaluation_point[i]) >
+        let b0 = {
+            let mut scale = G::ScalarField::one();
+            let mut res = G::ScalarField::zero();
+            for &e in evaluation_points.iter() {
+                let term = b_poly(&chal, e);
+                res += &(scale * term);
+                scale *= *evalscale;
+            }
+            res
+        };
 
-            rand_base_i *= &rand_base;
-            sg_rand_base_i *= &sg_rand_base;
+        let neg_rand_base_i = -rand_base_i;
+
+        // TERM
+        // - rand_base_i z1 G
+        points.push(opening.sg);
+        scalars.push(neg_rand_base_i * opening.z1);
+
+        // TERM
+        // - rand_base_i * z2 * H
+        scalars[0] -= &(rand_base_i * opening.z2);
+
+        // TERM
+        // -rand_base_i * (z1 * b0 * U)
+        scalars.push(neg_rand_base_i * (opening.z1 * b0));
+        points.push(u);
+
+        // TERM
+        // rand_base_i c_i Q_i
+        // = rand_base_i c_i
+        //   (sum_j (chal_invs[j] L_j + chals[j] R_j) + P_prime)
+        // where P_prime = combined commitment + combined_inner_product * U
+        let rand_base_i_c_i = c * rand_base_i;
+        for ((l, r), (u_inv, u)) in opening.lr.iter().zip(chal_inv.iter().zip(chal.iter())) {
+            points.push(*l);
+            scalars.push(rand_base_i_c_i * u_inv);
+
+            points.push(*r);
+            scalars.push(rand_base_i_c_i * u);
         }
 
-        // verify the equation
-        let scalars: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
-        VariableBaseMSM::multi_scalar_mul(&points, &scalars) == G::Projective::zero()
+        // TERM
+        // sum_j evalscale^j (sum_i polyscale^i f_i) (elm_j)
+        // == sum_j sum_i evalscale^j polyscale^i f_i(elm_j)
+        // == sum_i polyscale^i sum_j evalscale^j f_i(elm_j)
+        combine_commitments(evaluations, scalars, points, *polyscale, rand_base_i_c_i);
+
+        scalars.push(rand_base_i_c_i * *combined_inner_product);
+        points.push(u);
+
+        scalars.push(rand_base_i);
+        points.push(opening.delta);
+
+        rand_base_i *= &rand_base;
+        chals.push(chal);
     }
+    chals
 }
 
 pub fn inner_prod<F: Field>(xs: &[F], ys: &[F]) -> F {
@@ -999,6 +1131,51 @@ pub fn inner_prod<F: Field>(xs: &[F], ys: &[F]) -> F {
     res
 }
 
+/// Computes the multi-scalar multiplication `sum_i scalars[i] * bases[i]` on a
+/// single thread, with the bucket method of [VariableBaseMSM], whose
+/// implementation uses a thread pool when arkworks is built with its parallel
+/// feature. The extra memory is a bucket per value of a window of bits.
+/// The shortest of `bases` and `scalars` is padded with zeros.
+pub fn msm_sequential<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Projective {
+    let size = std::cmp::min(bases.len(), scalars.len());
+    // the size of the windows, as in arkworks
+    let c = if size < 32 {
+        3
+    } else {
+        (size as f64).ln().ceil() as usize + 2
+    };
+    let num_bits = G::ScalarField::size_in_bits();
+    let zero = G::Projective::zero();
+
+    let mut buckets = vec![zero; (1 << c) - 1];
+    let mut res = zero;
+    // the windows are processed from the most significant bits
+    for start in (0..num_bits).step_by(c).rev() {
+        for _ in 0..c {
+            res.double_in_place();
+        }
+        buckets.iter_mut().for_each(|bucket| *bucket = zero);
+        for (base, scalar) in bases.iter().zip(scalars).filter(|(_, s)| !s.is_zero()) {
+            let mut scalar = *scalar;
+            scalar.divn(start as u32);
+            let index = (scalar.as_ref()[0] % (1 << c)) as usize;
+            if index != 0 {
+                buckets[index - 1].add_assign_mixed(base);
+            }
+        }
+        // sum_j j * buckets[j - 1], with running sums
+        let mut running_sum = zero;
+        for bucket in buckets.iter().rev() {
+            running_sum += bucket;
+            res += &running_sum;
+        }
+    }
+    res
+}
+
 #[cfg(feature = "ocaml_types")]
 pub mod caml {
     use super::*;
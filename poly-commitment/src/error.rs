@@ -6,4 +6,6 @@ pub enum CommitmentError {
         "the length of the given blinders ({0}) don't match the length of the commitment ({1})"
     )]
     BlindersDontMatch(usize, usize),
+    #[error("the evaluations don't match the points of the opening groups")]
+    OpeningGroupsDontMatch,
 }
@@ -5,7 +5,8 @@
 
 use crate::{
     commitment::*,
-    srs::{endos, SRS},
+    error::CommitmentError,
+    srs::{try_endos, SRS},
     PolynomialsToCombine, SRS as _,
 };
 use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
@@ -25,11 +26,33 @@ use std::iter::Iterator;
 #[derive(Default)]
 struct ScaledChunkedPolynomial<F, P>(Vec<(F, P)>);
 
+#[derive(Clone, Copy)]
 pub enum DensePolynomialOrEvaluations<'a, F: FftField, D: EvaluationDomain<F>> {
     DensePolynomial(&'a DensePolynomial<F>),
     Evaluations(&'a Evaluations<F, D>, D),
 }
 
+impl<'a, F: FftField, D: EvaluationDomain<F>> DensePolynomialOrEvaluations<'a, F, D> {
+    /// Evaluates at `x` the `num_chunks` chunks of size `chunk_size` of the
+    /// polynomial, as it is combined by [combine_polys].
+    pub fn evaluate_chunks(&self, x: F, num_chunks: usize, chunk_size: usize) -> Vec<F> {
+        let interpolated;
+        let poly = match self {
+            DensePolynomialOrEvaluations::DensePolynomial(poly) => *poly,
+            DensePolynomialOrEvaluations::Evaluations(evals, sub_domain) => {
+                let stride = evals.evals.len() / sub_domain.size();
+                let evals = (0..sub_domain.size())
+                    .map(|i| evals.evals[i * stride])
+                    .collect();
+                interpolated = Evaluations::from_vec_and_domain(evals, *sub_domain).interpolate();
+                &interpolated
+            }
+        };
+        poly.to_chunked_polynomial(num_chunks, chunk_size)
+            .evaluate_chunks(x)
+    }
+}
+
 impl<F, P> ScaledChunkedPolynomial<F, P> {
     fn add_poly(&mut self, scale: F, p: P) {
         self.0.push((scale, p))
@@ -172,7 +195,9 @@ impl<G: CommitmentCurve> SRS<G> {
         G::BaseField: PrimeField,
         G: EndoCurve,
     {
-        let (endo_q, endo_r) = endos::<G>();
+        // Without an endomorphism, the challenges are used as they are
+        let endos = try_endos::<G>();
+        let endo_r = endos.map(|(_, endo_r)| endo_r);
 
         let rounds = math::ceil_log2(self.g.len());
         let padded_length = 1 << rounds;
@@ -273,7 +298,7 @@ impl<G: CommitmentCurve> SRS<G> {
 
             // Round #i challenges
             let u_pre = squeeze_prechallenge(&mut sponge);
-            let u = u_pre.to_field(&endo_r);
+            let u = challenge_to_field(&u_pre, endo_r.as_ref());
             let u_inv = u.inverse().unwrap();
 
             chals.push(u);
@@ -306,7 +331,10 @@ impl<G: CommitmentCurve> SRS<G> {
                 .collect();
 
             // IPA-folding bases
-            g = G::combine_one_endo(endo_r, endo_q, g_lo, g_hi, u_pre);
+            g = match endos {
+                Some((endo_q, endo_r)) => G::combine_one_endo(endo_r, endo_q, g_lo, g_hi, u_pre),
+                None => G::combine_one(g_lo, g_hi, u),
+            };
         }
 
         assert!(
@@ -337,7 +365,7 @@ impl<G: CommitmentCurve> SRS<G> {
         .into_affine();
 
         sponge.absorb_g(&[delta]);
-        let c = ScalarChallenge(sponge.challenge()).to_field(&endo_r);
+        let c = challenge_to_field(&ScalarChallenge(sponge.challenge()), endo_r.as_ref());
 
         let z1 = a0 * c + d;
         let z2 = r_prime * c + r_delta;
@@ -351,6 +379,77 @@ impl<G: CommitmentCurve> SRS<G> {
         }
     }
 
+    /// This function opens in a single proof several groups of polynomials,
+    /// each group being opened at its own set of points.
+    ///
+    /// All the polynomials are opened at the union of the points with
+    /// [SRS::open], the evaluations of each polynomial at the points of the
+    /// other groups being sent along the opening. This is a few field elements
+    /// per polynomial instead of the `2 log(n) + 2` group elements of an
+    /// independent opening per group.
+    ///
+    /// The evaluations at the points of each group must have been absorbed
+    /// into `sponge` by the caller. The additional evaluations are absorbed in
+    /// turn, before squeezing `polyscale` and `evalscale`, so that the
+    /// prover cannot choose them after the random combination.
+    ///
+    /// Note that the additional evaluations are revealed to the verifier: a
+    /// polynomial whose evaluations must stay hidden at the points of the
+    /// other groups should be opened in its own proof.
+    ///
+    /// The proof is checked with [SRS::verify], after recovering its
+    /// [BatchEvaluationProof] with [ManyOpeningsProof::batch_evaluation_proof].
+    pub fn open_many<EFqSponge, RNG, D: EvaluationDomain<G::ScalarField>>(
+        &self,
+        group_map: &G::Map,
+        groups: &[OpeningGroup<G, D>],
+        mut sponge: EFqSponge,
+        rng: &mut RNG,
+    ) -> ManyOpeningsProof<G>
+    where
+        EFqSponge: Clone + FqSponge<G::BaseField, G, G::ScalarField>,
+        RNG: RngCore + CryptoRng,
+        G::BaseField: PrimeField,
+        G: EndoCurve,
+    {
+        let points = union_points(groups.iter().map(|group| group.points));
+
+        let cross_evaluations: Vec<Vec<Vec<Vec<_>>>> = groups
+            .iter()
+            .map(|group| {
+                group
+                    .polys
+                    .iter()
+                    .map(|(poly, blinders)| {
+                        points
+                            .iter()
+                            .filter(|x| !group.points.contains(*x))
+                            .map(|x| poly.evaluate_chunks(*x, blinders.len(), self.g.len()))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        for evals in cross_evaluations.iter().flatten().flatten() {
+            sponge.absorb_fr(evals);
+        }
+        let polyscale = sponge.challenge();
+        let evalscale = sponge.challenge();
+
+        let plnms: Vec<_> = groups
+            .iter()
+            .flat_map(|group| group.polys.iter().cloned())
+            .collect();
+        let opening = self.open(
+            group_map, &plnms, &points, polyscale, evalscale, sponge, rng,
+        );
+
+        ManyOpeningsProof {
+            cross_evaluations,
+            opening,
+        }
+    }
+
     /// This function is a debugging helper.
     pub fn prover_polynomials_to_verifier_evaluations<D: EvaluationDomain<G::ScalarField>>(
         &self,
@@ -444,10 +543,137 @@ impl<
         EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
         RNG: RngCore + CryptoRng,
     {
+        // embedded builds verify the proofs one at a time, without thread pool
+        #[cfg(feature = "sequential_verifier")]
+        {
+            let _ = rng;
+            srs.verify_sequential(group_map, batch)
+        }
+        #[cfg(not(feature = "sequential_verifier"))]
         srs.verify(group_map, batch, rng)
     }
 }
 
+/// A group of polynomials opened at the same points, see [SRS::open_many]
+pub struct OpeningGroup<'a, G: CommitmentCurve, D: EvaluationDomain<G::ScalarField>> {
+    /// The polynomials with their commitment randomness
+    pub polys: PolynomialsToCombine<'a, G, D>,
+    /// The points at which the polynomials are opened
+    pub points: &'a [G::ScalarField],
+}
+
+/// The opening of several groups of polynomials at different points, created
+/// by [SRS::open_many]
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(bound = "G: ark_serialize::CanonicalDeserialize + ark_serialize::CanonicalSerialize")]
+pub struct ManyOpeningsProof<G: AffineCurve> {
+    /// For each group, for each polynomial of the group, the chunked
+    /// evaluations of the polynomial at the points of the other groups, in
+    /// the order of the union of the points
+    #[serde_as(as = "Vec<Vec<Vec<Vec<o1_utils::serialization::SerdeAs>>>>")]
+    pub cross_evaluations: Vec<Vec<Vec<Vec<G::ScalarField>>>>,
+    /// The opening of all the polynomials at the union of the points
+    pub opening: OpeningProof<G>,
+}
+
+/// The points of `sets`, without repetition, in the order of their first
+/// occurrence
+fn union_points<'a, F: PartialEq + Copy + 'a>(sets: impl Iterator<Item = &'a [F]>) -> Vec<F> {
+    let mut points = vec![];
+    for x in sets.flatten() {
+        if !points.contains(x) {
+            points.push(*x);
+        }
+    }
+    points
+}
+
+impl<G: CommitmentCurve> ManyOpeningsProof<G> {
+    /// Recovers the batch of evaluations checked by the opening, from the
+    /// evaluations of each group at its points, given in the order of
+    /// [SRS::open_many]. `sponge` must be in the same state as the sponge
+    /// given to [SRS::open_many].
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the evaluations of a group do not match its points
+    /// or the evaluations sent in the proof.
+    #[allow(clippy::type_complexity)]
+    pub fn batch_evaluation_proof<EFqSponge>(
+        &self,
+        groups: Vec<(Vec<Evaluation<G>>, Vec<G::ScalarField>)>,
+        mut sponge: EFqSponge,
+    ) -> Result<BatchEvaluationProof<G, EFqSponge, OpeningProof<G>>, CommitmentError>
+    where
+        EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>,
+    {
+        if groups.len() != self.cross_evaluations.len() {
+            return Err(CommitmentError::OpeningGroupsDontMatch);
+        }
+        let points = union_points(groups.iter().map(|(_, points)| points.as_slice()));
+
+        for evals in self.cross_evaluations.iter().flatten().flatten() {
+            sponge.absorb_fr(evals);
+        }
+        let polyscale = sponge.challenge();
+        let evalscale = sponge.challenge();
+
+        let mut evaluations = vec![];
+        for ((group, group_points), cross_evaluations) in
+            groups.into_iter().zip(self.cross_evaluations.iter())
+        {
+            if group.len() != cross_evaluations.len() {
+                return Err(CommitmentError::OpeningGroupsDontMatch);
+            }
+            for (evaluation, cross_evaluations) in group.into_iter().zip(cross_evaluations) {
+                if evaluation.evaluations.len() != group_points.len() {
+                    return Err(CommitmentError::OpeningGroupsDontMatch);
+                }
+                let mut cross_evaluations = cross_evaluations.iter();
+                let mut all_evaluations = Vec::with_capacity(points.len());
+                for x in points.iter() {
+                    let evals = match group_points.iter().position(|y| y == x) {
+                        Some(i) => &evaluation.evaluations[i],
+                        None => cross_evaluations
+                            .next()
+                            .ok_or(CommitmentError::OpeningGroupsDontMatch)?,
+                    };
+                    if evals.len() != evaluation.commitment.len() {
+                        return Err(CommitmentError::OpeningGroupsDontMatch);
+                    }
+                    all_evaluations.push(evals.clone());
+                }
+                if cross_evaluations.next().is_some() {
+                    return Err(CommitmentError::OpeningGroupsDontMatch);
+                }
+                evaluations.push(Evaluation {
+                    commitment: evaluation.commitment,
+                    evaluations: all_evaluations,
+                });
+            }
+        }
+
+        let combined_inner_product = {
+            let es: Vec<_> = evaluations
+                .iter()
+                .map(|Evaluation { evaluations, .. }| evaluations.clone())
+                .collect();
+            combined_inner_product(&polyscale, &evalscale, &es)
+        };
+
+        Ok(BatchEvaluationProof {
+            sponge,
+            evaluations,
+            evaluation_points: points,
+            polyscale,
+            evalscale,
+            opening: &self.opening,
+            combined_inner_product,
+        })
+    }
+}
+
 /// Commitment round challenges (endo mapped) and their inverses.
 pub struct Challenges<F> {
     pub chal: Vec<F>,
@@ -473,10 +699,10 @@ impl<G: AffineCurve> OpeningProof<G> {
     }
 
     /// Same as `prechallenges`, but maps scalar challenges using the
-    /// provided endomorphism, and computes their inverses.
+    /// provided endomorphism if any, and computes their inverses.
     pub fn challenges<EFqSponge: FqSponge<G::BaseField, G, G::ScalarField>>(
         &self,
-        endo_r: &G::ScalarField,
+        endo_r: Option<&G::ScalarField>,
         sponge: &mut EFqSponge,
     ) -> Challenges<G::ScalarField> {
         let chal: Vec<_> = self
@@ -485,7 +711,7 @@ impl<G: AffineCurve> OpeningProof<G> {
             .map(|(l, r)| {
                 sponge.absorb_g(&[*l]);
                 sponge.absorb_g(&[*r]);
-                squeeze_challenge(endo_r, sponge)
+                challenge_to_field(&squeeze_prechallenge(sponge), endo_r)
             })
             .collect();
 
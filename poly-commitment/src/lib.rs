@@ -1,3 +1,4 @@
+pub mod aggregation;
 pub mod chunked;
 mod combine;
 pub mod commitment;
@@ -108,6 +109,17 @@ pub trait SRS<G: CommitmentCurve>: Clone {
         plnm: &Evaluations<G::ScalarField, D<G::ScalarField>>,
     ) -> PolyComm<G>;
 
+    /// Commit to sparse evaluations, without blinding factors.
+    /// The nonzero evaluations are given as pairs `(row, value)`.
+    /// It is equivalent to [SRS::commit_evaluations_non_hiding], but only
+    /// combines the Lagrange bases of the given rows, which is faster for
+    /// polynomials that are nonzero on a few rows only.
+    fn commit_sparse_evaluations_non_hiding(
+        &self,
+        domain: D<G::ScalarField>,
+        evals: &[(usize, G::ScalarField)],
+    ) -> PolyComm<G>;
+
     /// Commit to evaluations with blinding factors, generated using the random
     /// number generator `rng`.
     /// It is analogous to [SRS::commit] but for evaluations.
@@ -140,6 +152,13 @@ pub trait SRS<G: CommitmentCurve>: Clone {
 
     fn add_lagrange_basis(&mut self, domain: D<G::ScalarField>);
 
+    /// Adds the Lagrange bases of several domains, computing each size once
+    fn add_lagrange_bases(&mut self, domains: &[D<G::ScalarField>]) {
+        for domain in domains {
+            self.add_lagrange_basis(*domain);
+        }
+    }
+
     fn size(&self) -> usize;
 }
 
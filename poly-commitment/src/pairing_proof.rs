@@ -3,7 +3,7 @@ use crate::{
     PolynomialsToCombine, SRS as SRSTrait,
 };
 
-use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine};
+use ark_ec::{AffineCurve, PairingEngine};
 use ark_ff::{One, PrimeField, Zero};
 use ark_poly::{
     univariate::{DenseOrSparsePolynomial, DensePolynomial},
@@ -218,6 +218,15 @@ impl<
         self.full_srs.commit_evaluations_non_hiding(domain, plnm)
     }
 
+    fn commit_sparse_evaluations_non_hiding(
+        &self,
+        domain: D<G::ScalarField>,
+        evals: &[(usize, G::ScalarField)],
+    ) -> PolyComm<G> {
+        self.full_srs
+            .commit_sparse_evaluations_non_hiding(domain, evals)
+    }
+
     fn commit_evaluations(
         &self,
         domain: D<G::ScalarField>,
@@ -245,6 +254,10 @@ impl<
         self.full_srs.add_lagrange_basis(domain);
     }
 
+    fn add_lagrange_bases(&mut self, domains: &[D<<G>::ScalarField>]) {
+        self.full_srs.add_lagrange_bases(domains);
+    }
+
     fn size(&self) -> usize {
         self.full_srs.g.len()
     }
@@ -353,7 +366,12 @@ impl<
             );
             let scalars: Vec<_> = scalars.iter().map(|x| x.into_repr()).collect();
 
-            VariableBaseMSM::multi_scalar_mul(&points, &scalars)
+            #[cfg(feature = "sequential_verifier")]
+            {
+                msm_sequential(&points, &scalars)
+            }
+            #[cfg(not(feature = "sequential_verifier"))]
+            ark_ec::msm::VariableBaseMSM::multi_scalar_mul(&points, &scalars)
         };
 
         // IMPROVEME: we could have a single flat array for all evaluations, see
@@ -41,23 +41,45 @@ where
     }
 }
 
-pub fn endos<G: CommitmentCurve>() -> (G::BaseField, G::ScalarField)
+/// Returns the coefficients `(endo_q, endo_r)` of the endomorphism
+/// `(x, y) -> (endo_q * x, y)` of the curve, which acts as the multiplication
+/// by `endo_r` on the prime subgroup, or `None` if the curve has no such
+/// endomorphism (e.g. when its coefficient `a` is not zero, or when one of its
+/// fields has no non-trivial cube root of unity).
+pub fn try_endos<G: CommitmentCurve>() -> Option<(G::BaseField, G::ScalarField)>
 where
     G::BaseField: PrimeField,
 {
+    // `endo_coefficient` only gives a cube root of unity when there is one
+    fn is_cube_root_of_unity<F: Field>(x: F) -> bool {
+        !x.is_one() && x.square() * x == F::one()
+    }
+
     let endo_q: G::BaseField = mina_poseidon::sponge::endo_coefficient();
-    let endo_r = {
-        let potential_endo_r: G::ScalarField = mina_poseidon::sponge::endo_coefficient();
-        let t = G::prime_subgroup_generator();
-        let (x, y) = t.to_coordinates().unwrap();
-        let phi_t = G::of_coordinates(x * endo_q, y);
-        if t.mul(potential_endo_r) == phi_t.into_projective() {
-            potential_endo_r
-        } else {
-            potential_endo_r * potential_endo_r
-        }
-    };
-    (endo_q, endo_r)
+    let potential_endo_r: G::ScalarField = mina_poseidon::sponge::endo_coefficient();
+    if !is_cube_root_of_unity(endo_q) || !is_cube_root_of_unity(potential_endo_r) {
+        return None;
+    }
+    let t = G::prime_subgroup_generator();
+    let (x, y) = t.to_coordinates()?;
+    let phi_t = G::of_coordinates(x * endo_q, y).into_projective();
+    [potential_endo_r, potential_endo_r.square()]
+        .into_iter()
+        .find(|&endo_r| t.mul(endo_r) == phi_t)
+        .map(|endo_r| (endo_q, endo_r))
+}
+
+/// Returns the coefficients `(endo_q, endo_r)` of the endomorphism of the
+/// curve, see [try_endos].
+///
+/// # Panics
+///
+/// Will panic if the curve has no such endomorphism.
+pub fn endos<G: CommitmentCurve>() -> (G::BaseField, G::ScalarField)
+where
+    G::BaseField: PrimeField,
+{
+    try_endos::<G>().expect("the curve has no endomorphism (x, y) -> (endo_q * x, y)")
 }
 
 fn point_of_random_bytes<G: CommitmentCurve>(map: &G::Map, random_bytes: &[u8]) -> G
@@ -105,6 +127,35 @@ impl<G: CommitmentCurve> SRS<G> {
             return;
         }
 
+        let basis = self.lagrange_basis(domain);
+        self.lagrange_bases.insert(n, basis);
+    }
+
+    /// Compute commitments to the lagrange bases corresponding to the given
+    /// domains and cache them in the SRS.
+    /// The bases of the different sizes which are not cached yet are computed
+    /// in parallel, and each size is computed only once.
+    pub fn add_lagrange_bases(&mut self, domains: &[D<G::ScalarField>]) {
+        let mut missing: Vec<D<G::ScalarField>> = domains
+            .iter()
+            .filter(|domain| !self.lagrange_bases.contains_key(&domain.size()))
+            .copied()
+            .collect();
+        missing.sort_by_key(|domain| domain.size());
+        missing.dedup_by_key(|domain| domain.size());
+
+        let bases: Vec<_> = missing
+            .into_par_iter()
+            .map(|domain| (domain.size(), self.lagrange_basis(domain)))
+            .collect();
+        self.lagrange_bases.extend(bases);
+    }
+
+    /// Compute commitments to the lagrange basis corresponding to the given
+    /// domain
+    fn lagrange_basis(&self, domain: D<G::ScalarField>) -> Vec<PolyComm<G>> {
+        let n = domain.size();
+
         // Let V be a vector space over the field F.
         //
         // Given
@@ -200,12 +251,11 @@ impl<G: CommitmentCurve> SRS<G> {
             elems.push(lg)
         }
 
-        let chunked_commitments: Vec<_> = (0..n)
+        (0..n)
             .map(|i| PolyComm {
                 elems: elems.iter().map(|v| v[i].into_affine()).collect(),
             })
-            .collect();
-        self.lagrange_bases.insert(n, chunked_commitments);
+            .collect()
     }
 
     /// This function creates a trusted-setup SRS instance for circuits with
@@ -307,4 +357,54 @@ where
             lagrange_bases: HashMap::new(),
         }
     }
+
+    /// This function creates SRS instance for circuits with number of rows up
+    /// to `depth`, whose group elements are derived from the setup string
+    /// `seed`.
+    /// The seed is prefixed by its length in the preimage of each element, so
+    /// that the SRS of distinct seeds are independent, and independent of the
+    /// one of [SRS::create].
+    pub fn create_from_seed(seed: &str, depth: usize) -> Self {
+        let m = G::Map::setup();
+        let hasher = || {
+            let mut h = Blake2b512::new();
+            h.update((seed.len() as u64).to_be_bytes());
+            h.update(seed.as_bytes());
+            h
+        };
+
+        let g: Vec<_> = (0..depth)
+            .into_par_iter()
+            .map(|i| {
+                let mut h = hasher();
+                h.update((i as u32).to_be_bytes());
+                point_of_random_bytes(&m, &h.finalize())
+            })
+            .collect();
+
+        // Compute a blinder
+        let h = {
+            let mut h = hasher();
+            h.update("srs_misc".as_bytes());
+            point_of_random_bytes(&m, &h.finalize())
+        };
+
+        SRS {
+            g,
+            h,
+            lagrange_bases: HashMap::new(),
+        }
+    }
+
+    /// Same as [SRS::create_from_seed], with the lagrange bases of the
+    /// `domains` computed and cached, see [SRS::add_lagrange_bases].
+    pub fn create_from_seed_with_domains(
+        seed: &str,
+        depth: usize,
+        domains: &[D<G::ScalarField>],
+    ) -> Self {
+        let mut srs = Self::create_from_seed(seed, depth);
+        srs.add_lagrange_bases(domains);
+        srs
+    }
 }
@@ -0,0 +1,158 @@
+use ark_ff::{One, UniformRand};
+use ark_poly::{univariate::DensePolynomial, Polynomial, Radix2EvaluationDomain, UVPolynomial};
+use groupmap::GroupMap;
+use mina_curves::pasta::{Fp, Vesta as VestaG};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi as SC, sponge::DefaultFqSponge, FqSponge,
+};
+use poly_commitment::{
+    aggregation::{self, Accumulator},
+    commitment::{BatchEvaluationProof, CommitmentCurve, Evaluation},
+    evaluation_proof::{DensePolynomialOrEvaluations, OpeningProof},
+    srs::SRS,
+    SRS as _,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+type Sponge = DefaultFqSponge<mina_curves::pasta::VestaParameters, SC>;
+
+const SRS_SIZE: usize = 32;
+
+fn sponge() -> Sponge {
+    Sponge::new(mina_poseidon::pasta::fq_kimchi::static_params())
+}
+
+// Opens a random polynomial at a random point, and returns the proof with its
+// evaluation
+fn random_opening(
+    srs: &SRS<VestaG>,
+    group_map: &<VestaG as CommitmentCurve>::Map,
+    rng: &mut StdRng,
+) -> (Evaluation<VestaG>, Fp, OpeningProof<VestaG>) {
+    let poly = DensePolynomial::<Fp>::rand(SRS_SIZE - 1, rng);
+    let comm = srs.commit(&poly, 1, rng);
+    let point = Fp::rand(rng);
+    let proof = srs.open(
+        group_map,
+        &[(
+            DensePolynomialOrEvaluations::<_, Radix2EvaluationDomain<_>>::DensePolynomial(&poly),
+            comm.blinders,
+        )],
+        &[point],
+        Fp::one(),
+        Fp::one(),
+        sponge(),
+        rng,
+    );
+    let evaluation = Evaluation {
+        commitment: comm.commitment,
+        evaluations: vec![vec![poly.evaluate(&point)]],
+    };
+    (evaluation, point, proof)
+}
+
+fn batch<'a>(
+    openings: &'a [(Evaluation<VestaG>, Fp, OpeningProof<VestaG>)],
+) -> Vec<BatchEvaluationProof<'a, VestaG, Sponge, OpeningProof<VestaG>>> {
+    openings
+        .iter()
+        .map(|(evaluation, point, proof)| BatchEvaluationProof {
+            sponge: sponge(),
+            evaluations: vec![Evaluation {
+                commitment: evaluation.commitment.clone(),
+                evaluations: evaluation.evaluations.clone(),
+            }],
+            evaluation_points: vec![*point],
+            polyscale: Fp::one(),
+            evalscale: Fp::one(),
+            opening: proof,
+            combined_inner_product: evaluation.evaluations[0][0],
+        })
+        .collect()
+}
+
+#[test]
+fn test_aggregate_opening_proofs() {
+    let rng = &mut StdRng::from_seed([0u8; 32]);
+    let srs = SRS::<VestaG>::create(SRS_SIZE);
+    let group_map = <VestaG as CommitmentCurve>::Map::setup();
+
+    let openings: Vec<_> = (0..4)
+        .map(|_| random_opening(&srs, &group_map, rng))
+        .collect();
+    assert!(srs.verify(&group_map, &mut batch(&openings), rng));
+
+    // The succinct verification defers one check per proof
+    let accumulators =
+        aggregation::verify_succinct(&srs, &group_map, &mut batch(&openings), rng).unwrap();
+    assert_eq!(accumulators.len(), openings.len());
+    assert!(accumulators.iter().all(|acc| acc.verify_final(&srs)));
+    assert!(aggregation::decide(&srs, &accumulators, rng));
+
+    // which are aggregated into a single one
+    let proof = aggregation::aggregate(&srs, &group_map, &accumulators, sponge(), rng);
+    let aggregated =
+        aggregation::verify_aggregation(&srs, &group_map, &accumulators, &proof, sponge(), rng)
+            .unwrap();
+    assert!(aggregated.verify_final(&srs));
+
+    // or folded directly by the prover
+    let (folded, _) = Accumulator::fold(&srs, &group_map, &accumulators, sponge(), rng);
+    assert!(folded.verify_final(&srs));
+
+    // that can be carried forward and aggregated with the accumulators of other proofs
+    let others: Vec<_> = (0..2)
+        .map(|_| random_opening(&srs, &group_map, rng))
+        .collect();
+    let mut accumulators =
+        aggregation::verify_succinct(&srs, &group_map, &mut batch(&others), rng).unwrap();
+    accumulators.push(aggregated);
+    let proof = aggregation::aggregate(&srs, &group_map, &accumulators, sponge(), rng);
+    let aggregated =
+        aggregation::verify_aggregation(&srs, &group_map, &accumulators, &proof, sponge(), rng)
+            .unwrap();
+    assert!(aggregated.verify_final(&srs));
+
+    // The aggregation proof is bound to its accumulators
+    accumulators.swap(0, 1);
+    assert!(aggregation::verify_aggregation(
+        &srs,
+        &group_map,
+        &accumulators,
+        &proof,
+        sponge(),
+        rng
+    )
+    .is_none());
+}
+
+#[test]
+fn test_aggregate_invalid_accumulator() {
+    let rng = &mut StdRng::from_seed([1u8; 32]);
+    let srs = SRS::<VestaG>::create(SRS_SIZE);
+    let group_map = <VestaG as CommitmentCurve>::Map::setup();
+
+    let openings: Vec<_> = (0..2)
+        .map(|_| random_opening(&srs, &group_map, rng))
+        .collect();
+    let mut accumulators =
+        aggregation::verify_succinct(&srs, &group_map, &mut batch(&openings), rng).unwrap();
+
+    // An accumulator whose commitment is not the one of its polynomial
+    let valid = accumulators[1].comm;
+    accumulators[1].comm = accumulators[0].comm + valid;
+    assert!(!accumulators[1].verify_final(&srs));
+    assert!(!aggregation::decide(&srs, &accumulators, rng));
+
+    // is still invalid after aggregation
+    let proof = aggregation::aggregate(&srs, &group_map, &accumulators, sponge(), rng);
+    let aggregated: Option<Accumulator<VestaG>> =
+        aggregation::verify_aggregation(&srs, &group_map, &accumulators, &proof, sponge(), rng);
+    assert!(!matches!(aggregated, Some(acc) if acc.verify_final(&srs)));
+
+    // A proof whose succinct part is invalid is rejected
+    let mut openings = openings;
+    openings[0].0.evaluations[0][0] += Fp::one();
+    let mut batch = batch(&openings);
+    assert!(aggregation::verify_succinct(&srs, &group_map, &mut batch, rng).is_none());
+}
@@ -1,4 +1,5 @@
-use ark_ff::{UniformRand, Zero};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
+use ark_ff::{One, PrimeField, UniformRand, Zero};
 use ark_poly::{univariate::DensePolynomial, Radix2EvaluationDomain, UVPolynomial};
 use colored::Colorize;
 use groupmap::GroupMap;
@@ -9,8 +10,8 @@ use mina_poseidon::{
 use o1_utils::{tests::make_test_rng, ExtendedDensePolynomial as _};
 use poly_commitment::{
     commitment::{
-        combined_inner_product, BatchEvaluationProof, BlindedCommitment, CommitmentCurve,
-        Evaluation, PolyComm,
+        combined_inner_product, msm_sequential, BatchEvaluationProof, BlindedCommitment,
+        CommitmentCurve, Evaluation, PolyComm,
     },
     evaluation_proof::{DensePolynomialOrEvaluations, OpeningProof},
     srs::SRS,
@@ -238,6 +239,19 @@ fn test_randomised<RNG: Rng + CryptoRng>(mut rng: &mut RNG) {
         "batch verification time:".green(),
         timer.elapsed()
     );
+
+    // verify the proofs one at a time
+    let mut batch: Vec<_> = proofs.iter().map(|p| p.verify_type()).collect();
+    assert!(srs.verify_sequential(&group_map, &mut batch));
+
+    // a wrong opening proof is rejected by both verifiers
+    proofs[0].proof.z1 += Fp::one();
+    let mut batch: Vec<_> = proofs.iter().map(|p| p.verify_type()).collect();
+    assert!(
+        !srs.verify::<DefaultFqSponge<VestaParameters, SC>, _>(&group_map, &mut batch, &mut rng)
+    );
+    let mut batch: Vec<_> = proofs.iter().map(|p| p.verify_type()).collect();
+    assert!(!srs.verify_sequential(&group_map, &mut batch));
 }
 
 #[test]
@@ -268,3 +282,32 @@ where
     let mut rng = <rand_chacha::ChaCha20Rng as SeedableRng>::from_seed(seed);
     test_randomised(&mut rng)
 }
+
+#[test]
+/// Tests the sequential multi-scalar multiplication against the one of arkworks
+fn test_msm_sequential() {
+    let mut rng = make_test_rng(None);
+    let srs = SRS::<Vesta>::create(1 << 7);
+    for size in [0, 1, 31, 32, 100, 1 << 7] {
+        let mut scalars: Vec<_> = (0..size).map(|_| Fp::rand(&mut rng).into_repr()).collect();
+        // zero and small scalars leave buckets empty
+        if size > 2 {
+            scalars[0] = Fp::zero().into_repr();
+            scalars[1] = Fp::one().into_repr();
+        }
+        assert_eq!(
+            msm_sequential(&srs.g, &scalars),
+            VariableBaseMSM::multi_scalar_mul(&srs.g[..size], &scalars)
+        );
+    }
+    let g = srs.g[0];
+    assert_eq!(
+        msm_sequential(&[g, g], &[(-Fp::one()).into_repr()]),
+        g.mul(-Fp::one())
+    );
+    assert!(msm_sequential::<Vesta>(&[], &[]).is_zero());
+    assert_eq!(
+        msm_sequential(&[g], &[Fp::from(2u64).into_repr()]),
+        g.into_projective().double()
+    );
+}
@@ -1,3 +1,4 @@
+use ark_ec::{AffineCurve, ProjectiveCurve};
 use ark_ff::{One, UniformRand, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, Evaluations, Polynomial,
@@ -14,8 +15,8 @@ use poly_commitment::{
         combine_evaluations, combined_inner_product, BatchEvaluationProof, CommitmentCurve,
         Evaluation,
     },
-    evaluation_proof::DensePolynomialOrEvaluations,
-    srs::SRS,
+    evaluation_proof::{DensePolynomialOrEvaluations, OpeningGroup},
+    srs::{endos, try_endos, SRS},
     PolyComm, SRS as _,
 };
 use rand::{rngs::StdRng, SeedableRng};
@@ -168,6 +169,34 @@ fn test_lagrange_commitments() {
     }
 }
 
+#[test]
+fn test_srs_from_seed_with_domains() {
+    let n = 32;
+    let srs = SRS::<VestaG>::create_from_seed("test setup", n);
+    assert_eq!(srs, SRS::<VestaG>::create_from_seed("test setup", n));
+    assert_ne!(
+        srs.g[0],
+        SRS::<VestaG>::create_from_seed("other setup", n).g[0]
+    );
+    assert_ne!(srs.g[0], SRS::<VestaG>::create(n).g[0]);
+
+    // the bases of several sizes, including chunked ones, are computed once
+    let domains: Vec<_> = [8, 32, 8, 64]
+        .into_iter()
+        .map(|size| D::<Fp>::new(size).unwrap())
+        .collect();
+    let srs = SRS::<VestaG>::create_from_seed_with_domains("test setup", n, &domains);
+    assert_eq!(srs.lagrange_bases.len(), 3);
+    for domain in &domains {
+        let mut expected = SRS::<VestaG>::create_from_seed("test setup", n);
+        expected.add_lagrange_basis(*domain);
+        assert_eq!(
+            srs.lagrange_bases.get(&domain.size()),
+            expected.lagrange_bases.get(&domain.size())
+        );
+    }
+}
+
 #[test]
 // This tests with two chunks.
 fn test_chunked_lagrange_commitments() {
@@ -199,6 +228,66 @@ fn test_chunked_lagrange_commitments() {
     }
 }
 
+#[test]
+fn test_sparse_evaluations_commitments() {
+    let n = 64;
+    let domain = D::<Fp>::new(n).unwrap();
+    let rng = &mut StdRng::from_seed([0u8; 32]);
+
+    // with one and with two chunks
+    for srs_size in [n, n / 2] {
+        let mut srs = SRS::<VestaG>::create(srs_size);
+        srs.add_lagrange_basis(domain);
+
+        let sparse: Vec<_> = [3, 17, 42].map(|row| (row, Fp::rand(rng))).to_vec();
+        let mut e = vec![Fp::zero(); n];
+        for (row, value) in sparse.iter() {
+            e[*row] = *value;
+        }
+        let evals = Evaluations::<Fp, D<Fp>>::from_vec_and_domain(e, domain);
+        assert_eq!(
+            srs.commit_sparse_evaluations_non_hiding(domain, &sparse),
+            srs.commit_evaluations_non_hiding(domain, &evals),
+        );
+
+        let zero = Evaluations::<Fp, D<Fp>>::from_vec_and_domain(vec![Fp::zero(); n], domain);
+        assert_eq!(
+            srs.commit_sparse_evaluations_non_hiding(domain, &[]),
+            srs.commit_evaluations_non_hiding(domain, &zero),
+        );
+    }
+}
+
+#[test]
+fn test_evaluations_custom_commitments() {
+    let n = 64;
+    let domain = D::<Fp>::new(n).unwrap();
+    let rng = &mut StdRng::from_seed([0u8; 32]);
+
+    // committing to the evaluations in the Lagrange basis gives the same
+    // commitment as committing to the interpolated polynomial
+    for srs_size in [n, n / 2] {
+        let mut srs = SRS::<VestaG>::create(srs_size);
+        srs.add_lagrange_basis(domain);
+
+        let evals = Evaluations::<Fp, D<Fp>>::from_vec_and_domain(
+            (0..n).map(|_| Fp::rand(rng)).collect(),
+            domain,
+        );
+        let poly = evals.interpolate_by_ref();
+        let num_chunks = n / srs_size;
+        let blinders = PolyComm::new(vec![Fp::one(); num_chunks]);
+        assert_eq!(
+            srs.commit_evaluations_custom(domain, &evals, &blinders)
+                .unwrap()
+                .commitment,
+            srs.commit_custom(&poly, num_chunks, &blinders)
+                .unwrap()
+                .commitment,
+        );
+    }
+}
+
 #[test]
 // TODO @volhovm I don't understand what this test does and
 // whether it is worth leaving.
@@ -331,3 +420,122 @@ fn test_opening_proof() {
 
     assert!(srs.verify(&group_map, &mut batch, rng));
 }
+
+#[test]
+fn test_endos() {
+    // the endomorphism (x, y) -> (endo_q * x, y) is the multiplication by endo_r
+    let (endo_q, endo_r) = try_endos::<VestaG>().unwrap();
+    assert_eq!((endo_q, endo_r), endos::<VestaG>());
+
+    let rng = &mut StdRng::from_seed([0u8; 32]);
+    let point = VestaG::prime_subgroup_generator()
+        .mul(Fp::rand(rng))
+        .into_affine();
+    let (x, y) = point.to_coordinates().unwrap();
+    assert_eq!(
+        point.mul(endo_r).into_affine(),
+        VestaG::of_coordinates(x * endo_q, y)
+    );
+}
+
+#[test]
+fn test_many_openings_proof() {
+    let srs = SRS::<VestaG>::create(16);
+    let rng = &mut StdRng::from_seed([0u8; 32]);
+    let group_map = <VestaG as CommitmentCurve>::Map::setup();
+    let sponge = DefaultFqSponge::<_, SC>::new(mina_poseidon::pasta::fq_kimchi::static_params());
+
+    // two polynomials opened at two points, and a chunked polynomial opened
+    // at one of these points and another one
+    let poly1 = DensePolynomial::<Fp>::rand(10, rng);
+    let poly2 = DensePolynomial::<Fp>::rand(5, rng);
+    let poly3 = DensePolynomial::<Fp>::rand(25, rng);
+    let commitment1 = srs.commit(&poly1, 1, rng);
+    let commitment2 = srs.commit(&poly2, 1, rng);
+    let commitment3 = srs.commit(&poly3, 2, rng);
+    let (zeta, omega, xi) = (Fp::rand(rng), Fp::rand(rng), Fp::rand(rng));
+    let points1 = vec![zeta, zeta * omega];
+    let points2 = vec![xi, zeta];
+
+    let polys1 = vec![
+        (
+            DensePolynomialOrEvaluations::<_, Radix2EvaluationDomain<_>>::DensePolynomial(&poly1),
+            commitment1.blinders,
+        ),
+        (
+            DensePolynomialOrEvaluations::DensePolynomial(&poly2),
+            commitment2.blinders,
+        ),
+    ];
+    let polys2 = vec![(
+        DensePolynomialOrEvaluations::DensePolynomial(&poly3),
+        commitment3.blinders,
+    )];
+    let proof = srs.open_many(
+        &group_map,
+        &[
+            OpeningGroup {
+                polys: &polys1,
+                points: &points1,
+            },
+            OpeningGroup {
+                polys: &polys2,
+                points: &points2,
+            },
+        ],
+        sponge.clone(),
+        rng,
+    );
+    // only the evaluations of each polynomial at the point it is not opened
+    // at are sent
+    assert_eq!(proof.cross_evaluations[0][0].len(), 1);
+    assert_eq!(proof.cross_evaluations[1][0].len(), 1);
+    assert_eq!(proof.cross_evaluations[1][0][0].len(), 2);
+
+    let evaluation = |poly: &DensePolynomial<Fp>, commitment, num_chunks, points: &[Fp]| {
+        let chunked = poly.to_chunked_polynomial(num_chunks, srs.g.len());
+        Evaluation {
+            commitment,
+            evaluations: points.iter().map(|x| chunked.evaluate_chunks(*x)).collect(),
+        }
+    };
+    let groups = |eval1: Fp| {
+        let mut evaluation1 = evaluation(&poly1, commitment1.commitment.clone(), 1, &points1);
+        evaluation1.evaluations[0][0] = eval1;
+        vec![
+            (
+                vec![
+                    evaluation1,
+                    evaluation(&poly2, commitment2.commitment.clone(), 1, &points1),
+                ],
+                points1.clone(),
+            ),
+            (
+                vec![evaluation(
+                    &poly3,
+                    commitment3.commitment.clone(),
+                    2,
+                    &points2,
+                )],
+                points2.clone(),
+            ),
+        ]
+    };
+
+    let batch = proof
+        .batch_evaluation_proof(groups(poly1.evaluate(&zeta)), sponge.clone())
+        .unwrap();
+    assert_eq!(batch.evaluation_points, vec![zeta, zeta * omega, xi]);
+    assert!(srs.verify(&group_map, &mut [batch], rng));
+
+    // a wrong evaluation is rejected
+    let batch = proof
+        .batch_evaluation_proof(groups(poly1.evaluate(&zeta) + Fp::one()), sponge.clone())
+        .unwrap();
+    assert!(!srs.verify(&group_map, &mut [batch], rng));
+
+    // so are evaluations that do not match the groups
+    let mut wrong_groups = groups(poly1.evaluate(&zeta));
+    wrong_groups[1].1.pop();
+    assert!(proof.batch_evaluation_proof(wrong_groups, sponge).is_err());
+}
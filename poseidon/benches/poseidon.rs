@@ -1,7 +1,7 @@
 use mina_poseidon::{
     constants::{PlonkSpongeConstantsKimchi, PlonkSpongeConstantsLegacy},
     pasta::{fp_kimchi as SpongeParametersKimchi, fp_legacy as SpongeParametersLegacy},
-    permutation::poseidon_block_cipher,
+    permutation::{poseidon_block_cipher, poseidon_block_cipher_batch},
 };
 use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
@@ -33,5 +33,33 @@ pub fn bench_poseidon_absorb_permutation_pasta_fp(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_poseidon_absorb_permutation_pasta_fp);
+pub fn bench_poseidon_permutation_batch_pasta_fp(c: &mut Criterion) {
+    let seed = thread_rng().gen();
+    eprintln!("Seed: {seed:?}");
+    let mut rng = StdRng::from_seed(seed);
+
+    let mut states: Vec<Vec<Fp>> = (0..1024)
+        .map(|_| (0..3).map(|_| Fp::rand(&mut rng)).collect())
+        .collect();
+
+    let params = SpongeParametersKimchi::static_params();
+    c.bench_function("poseidon_permutation 1024 states kimchi", |b| {
+        b.iter(|| {
+            for state in states.iter_mut() {
+                poseidon_block_cipher::<Fp, PlonkSpongeConstantsKimchi>(params, state);
+            }
+        })
+    });
+    c.bench_function("poseidon_permutation_batch 1024 states kimchi", |b| {
+        b.iter(|| {
+            poseidon_block_cipher_batch::<Fp, PlonkSpongeConstantsKimchi>(params, &mut states);
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_poseidon_absorb_permutation_pasta_fp,
+    bench_poseidon_permutation_batch_pasta_fp
+);
 criterion_main!(benches);
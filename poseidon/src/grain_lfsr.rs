@@ -0,0 +1,388 @@
+//! This module implements the generation of the parameters of Poseidon (round
+//! constants and MDS matrix) for any prime field and width, following the
+//! reference implementation of the [Poseidon paper](https://eprint.iacr.org/2019/458):
+//!
+//! - the parameters are derived from a Grain LFSR in self-shrinking mode,
+//!   seeded with the description of the instance (field size, width and number
+//!   of rounds), so that they can be reproduced by anyone;
+//! - the round constants are sampled by rejection, as integers of the size of
+//!   the field smaller than the modulus;
+//! - the MDS matrix is the Cauchy matrix `1 / (x_i + y_j)` of the next `2 *
+//!   width` elements.
+//!
+//! The numbers of rounds can be checked against the attacks considered by the
+//! reference implementation with [PoseidonConfig::check_security], or chosen
+//! with [PoseidonConfig::secure], which adds the security margin of the paper
+//! (two full rounds and 7.5% of partial rounds).
+//!
+//! NOTE: the MDS matrix is not checked against the infinitely long subspace
+//! trails of the partial rounds. Instances with partial rounds should check
+//! their MDS matrix with the reference implementation.
+//!
+//! The parameters of the pasta fields (see [crate::pasta]) were generated with
+//! a different procedure, described in `pasta/params.sage`.
+
+use crate::poseidon::ArithmeticSpongeParams;
+use ark_ff::PrimeField;
+use num_bigint::BigUint;
+use o1_utils::FieldHelpers;
+use thiserror::Error;
+
+/// The size of the state of the Grain LFSR
+const STATE_SIZE: usize = 80;
+
+/// The number of bits discarded after the seeding of the LFSR
+const WARMUP: usize = 160;
+
+/// Errors that can arise when generating Poseidon parameters
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParamsError {
+    #[error("the width of the sponge must be at least 2, got {0}")]
+    InvalidWidth(usize),
+    #[error("x^{0} is not a permutation of the field")]
+    InvalidSbox(u64),
+    #[error("the number of full rounds around partial rounds must be even, got {0}")]
+    OddFullRounds(usize),
+    #[error("{full} full rounds and {partial} partial rounds are not secure for {security} bits of security")]
+    InsecureRounds {
+        full: usize,
+        partial: usize,
+        security: usize,
+    },
+    #[error("no secure number of rounds found for {0} bits of security")]
+    NoSecureRounds(usize),
+}
+
+/// The Grain LFSR of the reference implementation of Poseidon, in
+/// self-shrinking mode
+#[derive(Clone, Debug)]
+pub struct GrainLfsr {
+    state: [bool; STATE_SIZE],
+    /// The position of the oldest bit of `state`
+    head: usize,
+}
+
+impl GrainLfsr {
+    /// Creates the LFSR of a Poseidon instance over a prime field of
+    /// `field_size` bits, with the S-box `x^alpha`
+    pub fn new(field_size: usize, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        // field type (1: prime field), S-box type (0: x^alpha), the sizes,
+        // and 30 bits set to one
+        let mut bits = Vec::with_capacity(STATE_SIZE);
+        for (value, len) in [
+            (1, 2),
+            (0, 4),
+            (field_size, 12),
+            (width, 12),
+            (full_rounds, 10),
+            (partial_rounds, 10),
+        ] {
+            bits.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        bits.resize(STATE_SIZE, true);
+
+        let mut lfsr = Self {
+            state: bits.try_into().unwrap(),
+            head: 0,
+        };
+        for _ in 0..WARMUP {
+            lfsr.update();
+        }
+        lfsr
+    }
+
+    /// Shifts the LFSR, and returns the new bit
+    fn update(&mut self) -> bool {
+        let bit = |i: usize| self.state[(self.head + i) % STATE_SIZE];
+        let new_bit = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.state[self.head] = new_bit;
+        self.head = (self.head + 1) % STATE_SIZE;
+        new_bit
+    }
+
+    /// Returns the next bit of the self-shrinking generator: the bits are
+    /// taken by pairs, and the second bit is kept if the first one is set
+    pub fn next_bit(&mut self) -> bool {
+        while !self.update() {
+            self.update();
+        }
+        self.update()
+    }
+
+    /// Returns the next integer of `num_bits` bits, most significant bit first
+    pub fn next_biguint(&mut self, num_bits: usize) -> BigUint {
+        let mut bits = vec![0u8; num_bits];
+        for bit in bits.iter_mut() {
+            *bit = self.next_bit() as u8;
+        }
+        BigUint::from_radix_be(&bits, 2).unwrap_or_default()
+    }
+
+    /// Returns the next field element, sampled by rejection
+    pub fn next_field_element<F: PrimeField>(&mut self) -> F {
+        let modulus = F::modulus_biguint();
+        loop {
+            let x = self.next_biguint(F::size_in_bits());
+            if x < modulus {
+                return F::from_biguint(&x).expect("the integer is smaller than the modulus");
+            }
+        }
+    }
+
+    /// Returns the next field element, reduced modulo the size of the field
+    pub fn next_field_element_reduced<F: PrimeField>(&mut self) -> F {
+        let x = self.next_biguint(F::size_in_bits()) % F::modulus_biguint();
+        F::from_biguint(&x).expect("the integer is reduced")
+    }
+}
+
+/// The configuration of a Poseidon instance with the S-box `x^alpha`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoseidonConfig {
+    /// The width of the state of the sponge
+    pub width: usize,
+    /// The number of full rounds
+    pub full_rounds: usize,
+    /// The number of partial rounds
+    pub partial_rounds: usize,
+    /// The exponent of the S-box
+    pub alpha: u64,
+    /// The targeted security, in bits
+    pub security_level: usize,
+}
+
+impl PoseidonConfig {
+    /// Creates a configuration targeting 128 bits of security
+    pub fn new(width: usize, full_rounds: usize, partial_rounds: usize, alpha: u64) -> Self {
+        Self {
+            width,
+            full_rounds,
+            partial_rounds,
+            alpha,
+            security_level: 128,
+        }
+    }
+
+    /// Sets the targeted security, in bits
+    pub fn security_level(mut self, security_level: usize) -> Self {
+        self.security_level = security_level;
+        self
+    }
+
+    /// Returns the configuration with the fewest S-boxes that resists the
+    /// attacks of [PoseidonConfig::check_security], with the security margin
+    /// of the paper
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the S-box is not a permutation of the field, or if
+    /// no secure number of rounds is found.
+    pub fn secure<F: PrimeField>(
+        width: usize,
+        alpha: u64,
+        security_level: usize,
+    ) -> Result<Self, ParamsError> {
+        let config = Self::new(width, 0, 0, alpha).security_level(security_level);
+        config.check_sbox::<F>()?;
+
+        let mut best: Option<(usize, usize, usize)> = None;
+        for partial_rounds in 1..500 {
+            let full_rounds = (4..100).step_by(2).find(|&full_rounds| {
+                Self::new(width, full_rounds, partial_rounds, alpha)
+                    .security_level(security_level)
+                    .is_secure::<F>()
+            });
+            if let Some(full_rounds) = full_rounds {
+                let cost = width * full_rounds + partial_rounds;
+                if best.map_or(true, |(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, full_rounds, partial_rounds));
+                }
+            }
+        }
+        let (_, full_rounds, partial_rounds) =
+            best.ok_or(ParamsError::NoSecureRounds(security_level))?;
+
+        Ok(Self {
+            full_rounds: full_rounds + 2,
+            partial_rounds: (partial_rounds as f64 * 1.075).ceil() as usize,
+            ..config
+        })
+    }
+
+    /// Checks that `x^alpha` is a permutation of the field, that is that
+    /// `alpha` is coprime with `p - 1`
+    fn check_sbox<F: PrimeField>(&self) -> Result<(), ParamsError> {
+        let order = F::modulus_biguint() - 1u8;
+        let rem = (order % self.alpha)
+            .to_u64_digits()
+            .first()
+            .copied()
+            .unwrap_or(0);
+        let (mut a, mut b) = (self.alpha, rem);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        if self.alpha < 3 || a != 1 {
+            return Err(ParamsError::InvalidSbox(self.alpha));
+        }
+        Ok(())
+    }
+
+    /// Whether the numbers of rounds resist the statistical, interpolation
+    /// and Gröbner basis attacks considered by the reference implementation,
+    /// including the attack of <https://eprint.iacr.org/2023/537>
+    fn is_secure<F: PrimeField>(&self) -> bool {
+        let t = self.width as f64;
+        let m = self.security_level as f64;
+        let alpha = self.alpha as f64;
+        let r_f = self.full_rounds as f64;
+        let r_p = self.partial_rounds as f64;
+        let n = F::size_in_bits() as f64;
+        let log2_p = log2(&F::modulus_biguint());
+        // the logarithm of 2 in base alpha
+        let log_alpha_2 = 2f64.ln() / alpha.ln();
+
+        let statistical = if m <= (log2_p - (alpha - 1.) / 2.).floor() * (t + 1.) {
+            6.
+        } else {
+            10.
+        };
+        let interpolation =
+            1. + (log_alpha_2 * m.min(n)).ceil() + (t.ln() / alpha.ln()).ceil() - r_p;
+        let groebner_1 = log_alpha_2 * m.min(log2_p) - r_p;
+        let groebner_2 = t - 1. + log_alpha_2 * (m / (t + 1.)).min(log2_p / 2.) - r_p;
+        let groebner_3 = (t - 2. + m / (2. * alpha.log2()) - r_p) / (t - 1.);
+        let min_full_rounds = [
+            statistical,
+            interpolation,
+            groebner_1,
+            groebner_2,
+            groebner_3,
+        ]
+        .into_iter()
+        .map(f64::ceil)
+        .fold(f64::MIN, f64::max);
+
+        let r = (t / 3.).floor();
+        let over = (r_f - 1.) * t + r_p + r + r * (r_f / 2.) + r_p + alpha;
+        let under = r * (r_f / 2.) + r_p + alpha;
+        let groebner_4 = (2. * log2_binomial(over, under)).ceil();
+
+        r_f >= min_full_rounds && groebner_4 >= m
+    }
+
+    /// Checks the configuration, see the [module documentation](self)
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the width is smaller than 2, if the S-box is not a
+    /// permutation of the field, if the number of full rounds is odd with
+    /// partial rounds, or if the numbers of rounds are not secure.
+    pub fn check_security<F: PrimeField>(&self) -> Result<(), ParamsError> {
+        if self.width < 2 {
+            return Err(ParamsError::InvalidWidth(self.width));
+        }
+        self.check_sbox::<F>()?;
+        // the full rounds are split around the partial rounds
+        if self.partial_rounds > 0 && self.full_rounds % 2 != 0 {
+            return Err(ParamsError::OddFullRounds(self.full_rounds));
+        }
+        if !self.is_secure::<F>() {
+            return Err(ParamsError::InsecureRounds {
+                full: self.full_rounds,
+                partial: self.partial_rounds,
+                security: self.security_level,
+            });
+        }
+        Ok(())
+    }
+
+    /// Generates the round constants, one row of `width` constants per round,
+    /// and the MDS matrix of the instance
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the configuration is not secure, see
+    /// [PoseidonConfig::check_security].
+    pub fn generate<F: PrimeField>(&self) -> Result<ArithmeticSpongeParams<F>, ParamsError> {
+        self.check_security::<F>()?;
+        let mut lfsr = GrainLfsr::new(
+            F::size_in_bits(),
+            self.width,
+            self.full_rounds,
+            self.partial_rounds,
+        );
+
+        let round_constants = (0..self.full_rounds + self.partial_rounds)
+            .map(|_| (0..self.width).map(|_| lfsr.next_field_element()).collect())
+            .collect();
+
+        // resample until the x_i and y_j are distinct and x_i + y_j != 0
+        let mds = loop {
+            let values: Vec<F> = (0..2 * self.width)
+                .map(|_| lfsr.next_field_element_reduced())
+                .collect();
+            let distinct = values
+                .iter()
+                .enumerate()
+                .all(|(i, x)| values[..i].iter().all(|y| x != y));
+            let (xs, ys) = values.split_at(self.width);
+            let sums: Option<Vec<Vec<F>>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| (*x + y).inverse()).collect())
+                .collect();
+            match sums {
+                Some(mds) if distinct => break mds,
+                _ => continue,
+            }
+        };
+
+        Ok(ArithmeticSpongeParams {
+            round_constants,
+            mds,
+        })
+    }
+}
+
+/// The logarithm in base 2 of a positive integer
+fn log2(x: &BigUint) -> f64 {
+    // the 53 most significant bits fit in the mantissa of a float
+    let shift = x.bits().saturating_sub(53);
+    let top = (x >> shift).to_u64_digits()[0];
+    (top as f64).log2() + shift as f64
+}
+
+/// The logarithm in base 2 of the binomial coefficient `n` choose `k`, for
+/// real `n` and `k`
+fn log2_binomial(n: f64, k: f64) -> f64 {
+    (ln_gamma(n + 1.) - ln_gamma(k + 1.) - ln_gamma(n - k + 1.)) / 2f64.ln()
+}
+
+/// The natural logarithm of the gamma function, for positive `x`, with the
+/// Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1. - x);
+    }
+    let x = x - 1.;
+    let mut sum = COEFFICIENTS[0];
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + G + 0.5;
+    0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
@@ -1,8 +1,11 @@
 pub mod constants;
 pub mod dummy_values;
+pub mod grain_lfsr;
+pub mod merkle;
 pub mod pasta;
 pub mod permutation;
 pub mod poseidon;
 pub mod sponge;
+pub mod transcript;
 
 pub use sponge::FqSponge; // Commonly used so reexported for convenience
@@ -0,0 +1,275 @@
+//! This module implements Merkle trees of configurable arity, hashed with the
+//! Kimchi instance of Poseidon.
+//!
+//! All the hashes are computed with the 2-to-1 compression function
+//! `compress(a, b) = P(a, b, 0)[0]`, `P` being the permutation, which is also
+//! the one exposed to circuits. The children of a node are absorbed one at a
+//! time, starting from a tag which depends on the arity of the tree and on the
+//! level of the node, so that the leaves, the nodes of different levels and
+//! the nodes of trees of different arities can never be confused:
+//!
+//! - a leaf `x` is hashed as `compress(tag(arity, 0), x)`;
+//! - a node of level `l > 0`, of children `c_0, ..., c_{arity - 1}`, is hashed
+//!   as `compress(...compress(compress(tag(arity, l), c_0), c_1)..., c_{arity - 1})`.
+//!
+//! A tree of depth `d` has `arity^d` leaves, the missing leaves being set to
+//! zero. The membership proof of a leaf is its [MerklePath], which contains,
+//! for each level from the leaves to the root, the position of the node among
+//! its siblings and the `arity - 1` siblings.
+
+use crate::{
+    constants::PlonkSpongeConstantsKimchi, permutation::poseidon_block_cipher,
+    poseidon::ArithmeticSpongeParams,
+};
+use ark_ff::Field;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use thiserror::Error;
+
+/// Errors that can arise when building or opening a Merkle tree
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleError {
+    #[error("the arity of a Merkle tree must be at least 2, got {0}")]
+    InvalidArity(usize),
+    #[error(
+        "the depth of a Merkle tree must be positive and its leaves must fit in memory, got {0}"
+    )]
+    InvalidDepth(usize),
+    #[error("the tree has {capacity} leaves, got {found}")]
+    TooManyLeaves { capacity: usize, found: usize },
+    #[error("the index {index} is out of the {capacity} leaves of the tree")]
+    IndexOutOfBounds { index: usize, capacity: usize },
+}
+
+/// The tag from which the hash of a node of level `level` (the leaves being at
+/// level 0) is computed, see the [module documentation](self)
+pub fn tag<F: Field>(arity: usize, level: usize) -> F {
+    F::from(((level as u64) << 32) | arity as u64)
+}
+
+/// The 2-to-1 compression function used to hash the tree
+pub fn compress<F: Field>(params: &ArithmeticSpongeParams<F>, left: F, right: F) -> F {
+    let mut state = vec![left, right, F::zero()];
+    poseidon_block_cipher::<F, PlonkSpongeConstantsKimchi>(params, &mut state);
+    state[0]
+}
+
+/// Returns the digest of the leaf `leaf`, in a tree of arity `arity`
+pub fn hash_leaf<F: Field>(params: &ArithmeticSpongeParams<F>, arity: usize, leaf: F) -> F {
+    compress(params, tag(arity, 0), leaf)
+}
+
+/// Returns the digest of the node of level `level` with the given `children`,
+/// the arity of the tree being the number of children
+pub fn hash_node<F: Field>(params: &ArithmeticSpongeParams<F>, level: usize, children: &[F]) -> F {
+    children
+        .iter()
+        .fold(tag(children.len(), level), |acc, child| {
+            compress(params, acc, *child)
+        })
+}
+
+/// A Merkle tree, see the [module documentation](self)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree<F: Field> {
+    arity: usize,
+    depth: usize,
+    // the digests of each level, from the leaves to the root
+    levels: Vec<Vec<F>>,
+}
+
+/// The membership proof of a leaf of a [MerkleTree]
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerklePath<F: Field> {
+    /// The index of the leaf
+    pub index: usize,
+    /// For each level, from the leaves to the root, the siblings of the node
+    /// on the path, in order and without the node itself
+    #[serde_as(as = "Vec<Vec<o1_utils::serialization::SerdeAs>>")]
+    pub siblings: Vec<Vec<F>>,
+}
+
+impl<F: Field> MerkleTree<F> {
+    /// Creates a tree of arity `arity` and depth `depth`, the first leaves of
+    /// which are `leaves` and the others zero
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the arity is smaller than 2, if the depth is zero or
+    /// too large, or if there are more leaves than the capacity of the tree.
+    pub fn new(
+        params: &ArithmeticSpongeParams<F>,
+        arity: usize,
+        depth: usize,
+        leaves: &[F],
+    ) -> Result<Self, MerkleError> {
+        if arity < 2 {
+            return Err(MerkleError::InvalidArity(arity));
+        }
+        let capacity = u32::try_from(depth)
+            .ok()
+            .filter(|depth| *depth > 0)
+            .and_then(|depth| arity.checked_pow(depth))
+            .ok_or(MerkleError::InvalidDepth(depth))?;
+        if leaves.len() > capacity {
+            return Err(MerkleError::TooManyLeaves {
+                capacity,
+                found: leaves.len(),
+            });
+        }
+
+        let empty_leaf = hash_leaf(params, arity, F::zero());
+        let mut digests: Vec<F> = leaves
+            .iter()
+            .map(|leaf| hash_leaf(params, arity, *leaf))
+            .collect();
+        digests.resize(capacity, empty_leaf);
+
+        let mut levels = vec![digests];
+        for level in 1..=depth {
+            let nodes = levels[level - 1]
+                .chunks(arity)
+                .map(|children| hash_node(params, level, children))
+                .collect();
+            levels.push(nodes);
+        }
+
+        Ok(Self {
+            arity,
+            depth,
+            levels,
+        })
+    }
+
+    /// Returns the arity of the tree
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    /// Returns the depth of the tree
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of leaves of the tree
+    pub fn capacity(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Returns the root of the tree
+    pub fn root(&self) -> F {
+        self.levels[self.depth][0]
+    }
+
+    /// Returns the membership proof of the leaf of index `index`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `index` is not smaller than the capacity of the tree.
+    pub fn path(&self, index: usize) -> Result<MerklePath<F>, MerkleError> {
+        self.check_index(index)?;
+        let mut node = index;
+        let siblings = self.levels[..self.depth]
+            .iter()
+            .map(|digests| {
+                let position = node % self.arity;
+                let first = node - position;
+                node /= self.arity;
+                (first..first + self.arity)
+                    .filter(|i| *i != first + position)
+                    .map(|i| digests[i])
+                    .collect()
+            })
+            .collect();
+        Ok(MerklePath { index, siblings })
+    }
+
+    /// Replaces the leaf of index `index` by `leaf`, and updates the nodes on
+    /// its path
+    ///
+    /// # Errors
+    ///
+    /// Will give error if `index` is not smaller than the capacity of the tree.
+    pub fn update(
+        &mut self,
+        params: &ArithmeticSpongeParams<F>,
+        index: usize,
+        leaf: F,
+    ) -> Result<(), MerkleError> {
+        self.check_index(index)?;
+        self.levels[0][index] = hash_leaf(params, self.arity, leaf);
+        let mut node = index;
+        for level in 1..=self.depth {
+            node /= self.arity;
+            let first = node * self.arity;
+            self.levels[level][node] = hash_node(
+                params,
+                level,
+                &self.levels[level - 1][first..first + self.arity],
+            );
+        }
+        Ok(())
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), MerkleError> {
+        if index < self.capacity() {
+            Ok(())
+        } else {
+            Err(MerkleError::IndexOutOfBounds {
+                index,
+                capacity: self.capacity(),
+            })
+        }
+    }
+}
+
+impl<F: Field> MerklePath<F> {
+    /// Returns the arity of the tree of the path
+    pub fn arity(&self) -> usize {
+        self.siblings
+            .first()
+            .map_or(0, |siblings| siblings.len() + 1)
+    }
+
+    /// Returns, for each level from the leaves to the root, the position of
+    /// the node on the path among its siblings
+    pub fn positions(&self) -> Vec<usize> {
+        let arity = self.arity();
+        let mut node = self.index;
+        self.siblings
+            .iter()
+            .map(|_| {
+                let position = node % arity;
+                node /= arity;
+                position
+            })
+            .collect()
+    }
+
+    /// Returns the root of the tree obtained when the leaf of the path is
+    /// `leaf`
+    pub fn root(&self, params: &ArithmeticSpongeParams<F>, leaf: F) -> F {
+        let arity = self.arity();
+        let mut digest = hash_leaf(params, arity, leaf);
+        for (level, (siblings, position)) in self.siblings.iter().zip(self.positions()).enumerate()
+        {
+            let mut children = siblings.clone();
+            children.insert(position, digest);
+            digest = hash_node(params, level + 1, &children);
+        }
+        digest
+    }
+
+    /// Returns true if `leaf` is the leaf of the path in the tree of root
+    /// `root`
+    pub fn verify(&self, params: &ArithmeticSpongeParams<F>, root: F, leaf: F) -> bool {
+        let arity = self.arity();
+        arity >= 2
+            && self
+                .siblings
+                .iter()
+                .all(|siblings| siblings.len() + 1 == arity)
+            && self.root(params, leaf) == root
+    }
+}
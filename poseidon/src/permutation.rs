@@ -6,6 +6,7 @@ use crate::{
     poseidon::{sbox, ArithmeticSpongeParams},
 };
 use ark_ff::Field;
+use rayon::prelude::*;
 
 fn apply_mds_matrix<F: Field, SC: SpongeConstants>(
     params: &ArithmeticSpongeParams<F>,
@@ -121,3 +122,129 @@ pub fn poseidon_block_cipher<F: Field, SC: SpongeConstants>(
         half_rounds::<F, SC>(params, state);
     }
 }
+
+/// The number of states permuted together by [poseidon_block_cipher_batch],
+/// in each of the parallel tasks.
+pub const BATCH_CHUNK_SIZE: usize = 64;
+
+/// Adds the round constants of the round `r` to the states stored by columns
+fn ark_columns<F: Field>(params: &ArithmeticSpongeParams<F>, columns: &mut [Vec<F>], r: usize) {
+    for (column, c) in columns.iter_mut().zip(params.round_constants[r].iter()) {
+        column.iter_mut().for_each(|x| x.add_assign(c));
+    }
+}
+
+/// Applies the MDS matrix to the states stored by columns
+fn apply_mds_matrix_columns<F: Field, SC: SpongeConstants>(
+    params: &ArithmeticSpongeParams<F>,
+    columns: &[Vec<F>],
+) -> Vec<Vec<F>> {
+    let len = columns.first().map_or(0, Vec::len);
+    if SC::PERM_FULL_MDS {
+        params
+            .mds
+            .iter()
+            .map(|m| {
+                let mut res = vec![F::zero(); len];
+                for (column, m) in columns.iter().zip(m.iter()) {
+                    res.iter_mut()
+                        .zip(column.iter())
+                        .for_each(|(r, x)| *r += *m * x);
+                }
+                res
+            })
+            .collect()
+    } else {
+        let add = |a: &[F], b: &[F]| a.iter().zip(b.iter()).map(|(a, b)| *a + b).collect();
+        vec![
+            add(&columns[0], &columns[2]),
+            add(&columns[0], &columns[1]),
+            add(&columns[1], &columns[2]),
+        ]
+    }
+}
+
+/// Applies a full round of the permutation to states stored by columns:
+/// `columns[i][k]` is the `i`th element of the `k`th state.
+/// This is equivalent to calling [full_round] on each of the states, but
+/// each step of the round is applied to all the states in a single pass.
+pub fn full_round_columns<F: Field, SC: SpongeConstants>(
+    params: &ArithmeticSpongeParams<F>,
+    columns: &mut Vec<Vec<F>>,
+    r: usize,
+) {
+    for column in columns.iter_mut() {
+        column.iter_mut().for_each(|x| *x = sbox::<F, SC>(*x));
+    }
+    *columns = apply_mds_matrix_columns::<F, SC>(params, columns);
+    ark_columns(params, columns, r);
+}
+
+/// Applies the permutation to states stored by columns, see [full_round_columns]
+pub fn poseidon_block_cipher_columns<F: Field, SC: SpongeConstants>(
+    params: &ArithmeticSpongeParams<F>,
+    columns: &mut Vec<Vec<F>>,
+) {
+    if SC::PERM_HALF_ROUNDS_FULL == 0 {
+        if SC::PERM_INITIAL_ARK {
+            ark_columns(params, columns, 0);
+            for r in 0..SC::PERM_ROUNDS_FULL {
+                full_round_columns::<F, SC>(params, columns, r + 1);
+            }
+        } else {
+            for r in 0..SC::PERM_ROUNDS_FULL {
+                full_round_columns::<F, SC>(params, columns, r);
+            }
+        }
+    } else {
+        let full_round = |columns: &mut Vec<Vec<F>>, r: usize| {
+            ark_columns(params, columns, r);
+            for column in columns.iter_mut() {
+                column.iter_mut().for_each(|x| *x = sbox::<F, SC>(*x));
+            }
+            *columns = apply_mds_matrix_columns::<F, SC>(params, columns);
+        };
+        for r in 0..SC::PERM_HALF_ROUNDS_FULL {
+            full_round(columns, r);
+        }
+        for r in 0..SC::PERM_ROUNDS_PARTIAL {
+            ark_columns(params, columns, SC::PERM_HALF_ROUNDS_FULL + r);
+            columns[0].iter_mut().for_each(|x| *x = sbox::<F, SC>(*x));
+            *columns = apply_mds_matrix_columns::<F, SC>(params, columns);
+        }
+        for r in 0..SC::PERM_HALF_ROUNDS_FULL {
+            full_round(
+                columns,
+                SC::PERM_HALF_ROUNDS_FULL + SC::PERM_ROUNDS_PARTIAL + r,
+            );
+        }
+    }
+}
+
+/// Transposes a batch of states into columns, see [full_round_columns]
+pub fn states_to_columns<F: Field>(states: &[Vec<F>], width: usize) -> Vec<Vec<F>> {
+    (0..width)
+        .map(|i| states.iter().map(|state| state[i]).collect())
+        .collect()
+}
+
+/// Applies the permutation to each of the `states`.
+///
+/// The states are split in chunks of [BATCH_CHUNK_SIZE] states, which are
+/// permuted in parallel. In each chunk, the states are stored by columns so
+/// that each step of the permutation runs over all of them at once.
+pub fn poseidon_block_cipher_batch<F: Field, SC: SpongeConstants>(
+    params: &ArithmeticSpongeParams<F>,
+    states: &mut [Vec<F>],
+) {
+    let width = SC::SPONGE_WIDTH;
+    states.par_chunks_mut(BATCH_CHUNK_SIZE).for_each(|chunk| {
+        let mut columns = states_to_columns(chunk, width);
+        poseidon_block_cipher_columns::<F, SC>(params, &mut columns);
+        for (k, state) in chunk.iter_mut().enumerate() {
+            for (i, column) in columns.iter().enumerate() {
+                state[i] = column[k];
+            }
+        }
+    });
+}
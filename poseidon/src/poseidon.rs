@@ -2,7 +2,7 @@
 
 use crate::{
     constants::SpongeConstants,
-    permutation::{full_round, poseidon_block_cipher},
+    permutation::{full_round, poseidon_block_cipher, poseidon_block_cipher_batch},
 };
 use ark_ff::Field;
 use serde::{Deserialize, Serialize};
@@ -72,6 +72,38 @@ impl<F: Field, SC: SpongeConstants> ArithmeticSponge<F, SC> {
     fn poseidon_block_cipher(&mut self) {
         poseidon_block_cipher::<F, SC>(self.params, &mut self.state);
     }
+
+    /// Hashes each of the `inputs`, returning the same digests as absorbing
+    /// each input in a fresh sponge and squeezing once. The permutations of all
+    /// the inputs are computed together (see [poseidon_block_cipher_batch]).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the inputs do not all have the same length.
+    pub fn hash_batch(params: &'static ArithmeticSpongeParams<F>, inputs: &[Vec<F>]) -> Vec<F> {
+        let len = inputs.first().map_or(0, Vec::len);
+        assert!(
+            inputs.iter().all(|input| input.len() == len),
+            "the inputs of a batch must have the same length"
+        );
+
+        let mut states = vec![vec![F::zero(); SC::SPONGE_WIDTH]; inputs.len()];
+        if len == 0 {
+            poseidon_block_cipher_batch::<F, SC>(params, &mut states);
+        }
+        for block in (0..len).step_by(SC::SPONGE_RATE) {
+            for (state, input) in states.iter_mut().zip(inputs.iter()) {
+                for (s, x) in state
+                    .iter_mut()
+                    .zip(input[block..].iter().take(SC::SPONGE_RATE))
+                {
+                    s.add_assign(x);
+                }
+            }
+            poseidon_block_cipher_batch::<F, SC>(params, &mut states);
+        }
+        states.into_iter().map(|state| state[0]).collect()
+    }
 }
 
 impl<F: Field, SC: SpongeConstants> Sponge<F, F> for ArithmeticSponge<F, SC> {
@@ -0,0 +1,205 @@
+//! This module implements labeled transcripts of the Fiat-Shamir transform.
+//!
+//! A [Transcript] wraps a [FqSponge]: every element absorbed and every
+//! challenge squeezed is given a label naming its role in the protocol, so
+//! that the sequence of operations can be recorded as a [TranscriptEntry]
+//! trace and compared between a prover and a verifier. A challenge label can
+//! only be squeezed once by a transcript, which prevents using the same
+//! challenge for two purposes: squeezing it again is a
+//! [TranscriptError::ChallengeReused].
+//!
+//! A transcript created by [SpongeTranscript::with_domain] absorbs the domain
+//! separator of its protocol first, and the label of every operation before
+//! the operation itself, so that the challenges of two protocols, or of two
+//! orderings of the same elements, can never be confused. A transcript created
+//! by [SpongeTranscript::new] absorbs neither, and produces the same challenges
+//! as the underlying sponge, for the protocols which must stay compatible with
+//! existing proofs.
+
+use crate::FqSponge;
+use ark_ff::{Field, PrimeField};
+use thiserror::Error;
+
+/// The number of bytes of a domain separator packed in a field element
+const DOMAIN_CHUNK_BYTES: usize = 31;
+
+/// An operation of a transcript, with the number of absorbed elements
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranscriptOp {
+    /// Absorption of base field elements
+    AbsorbFq(usize),
+    /// Absorption of curve points
+    AbsorbG(usize),
+    /// Absorption of scalar field elements
+    AbsorbFr(usize),
+    /// Challenge in the base field
+    ChallengeFq,
+    /// Challenge in the scalar field
+    Challenge,
+}
+
+/// Errors of the operations of a transcript
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptError {
+    #[error("the challenge {0} is squeezed twice")]
+    ChallengeReused(&'static str),
+}
+
+/// A labeled operation of a transcript
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// The label of the operation
+    pub label: &'static str,
+    /// The operation
+    pub op: TranscriptOp,
+}
+
+/// A labeled transcript over a base field `Fq` of the curve `G`, `Fr` being
+/// the scalar field of the curve
+pub trait Transcript<Fq: Field, G, Fr> {
+    /// Absorbs the base field elements `x`, labeled `label`
+    fn absorb_fq(&mut self, label: &'static str, x: &[Fq]);
+
+    /// Absorbs the curve points `g`, labeled `label`
+    fn absorb_g(&mut self, label: &'static str, g: &[G]);
+
+    /// Absorbs the scalar field elements `x`, labeled `label`
+    fn absorb_fr(&mut self, label: &'static str, x: &[Fr]);
+
+    /// Squeezes the base field challenge labeled `label`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a challenge labeled `label` was already squeezed.
+    fn challenge_fq(&mut self, label: &'static str) -> Result<Fq, TranscriptError>;
+
+    /// Squeezes the scalar field challenge labeled `label`
+    ///
+    /// # Errors
+    ///
+    /// Will give error if a challenge labeled `label` was already squeezed.
+    fn challenge(&mut self, label: &'static str) -> Result<Fr, TranscriptError>;
+}
+
+/// A [Transcript] absorbing into a [FqSponge]
+#[derive(Clone, Debug)]
+pub struct SpongeTranscript<S> {
+    sponge: S,
+    /// Whether the labels are absorbed, see [SpongeTranscript::with_domain]
+    absorb_labels: bool,
+    challenges: Vec<&'static str>,
+    trace: Option<Vec<TranscriptEntry>>,
+}
+
+/// Absorbs `s` in `sponge`, packed in base field elements after its length
+fn absorb_str<Fq: PrimeField, G, Fr, S: FqSponge<Fq, G, Fr>>(sponge: &mut S, s: &str) {
+    let bytes = s.as_bytes();
+    sponge.absorb_fq(&[Fq::from(bytes.len() as u64)]);
+    let chunks: Vec<Fq> = bytes
+        .chunks(DOMAIN_CHUNK_BYTES)
+        .map(Fq::from_le_bytes_mod_order)
+        .collect();
+    sponge.absorb_fq(&chunks);
+}
+
+impl<S> SpongeTranscript<S> {
+    /// Creates a transcript over `sponge`, without domain separator nor
+    /// labels: its challenges are the ones of the sponge
+    pub fn new(sponge: S) -> Self {
+        SpongeTranscript {
+            sponge,
+            absorb_labels: false,
+            challenges: vec![],
+            trace: None,
+        }
+    }
+
+    /// Creates a transcript over `sponge`, after absorbing the domain
+    /// separator `domain`. The label of every operation of the transcript is
+    /// absorbed before the operation, like the domain separator, packed in
+    /// base field elements after its length.
+    pub fn with_domain<Fq: PrimeField, G, Fr>(mut sponge: S, domain: &str) -> Self
+    where
+        S: FqSponge<Fq, G, Fr>,
+    {
+        absorb_str(&mut sponge, domain);
+        SpongeTranscript {
+            absorb_labels: true,
+            ..Self::new(sponge)
+        }
+    }
+
+    /// Records the operations of the transcript from now on, see
+    /// [SpongeTranscript::trace]
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    /// Returns the operations recorded since [SpongeTranscript::with_trace],
+    /// or `None` if they are not recorded
+    pub fn trace(&self) -> Option<&[TranscriptEntry]> {
+        self.trace.as_deref()
+    }
+
+    /// Returns the underlying sponge
+    pub fn sponge(&self) -> &S {
+        &self.sponge
+    }
+
+    /// Returns the underlying sponge, to continue the protocol with a
+    /// component absorbing directly into it
+    pub fn into_sponge(self) -> S {
+        self.sponge
+    }
+
+    /// Registers the challenge `label`, which must not have been squeezed yet
+    fn register_challenge(&mut self, label: &'static str) -> Result<(), TranscriptError> {
+        if self.challenges.contains(&label) {
+            return Err(TranscriptError::ChallengeReused(label));
+        }
+        self.challenges.push(label);
+        Ok(())
+    }
+
+    fn record<Fq: PrimeField, G, Fr>(&mut self, label: &'static str, op: TranscriptOp)
+    where
+        S: FqSponge<Fq, G, Fr>,
+    {
+        if self.absorb_labels {
+            absorb_str(&mut self.sponge, label);
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.push(TranscriptEntry { label, op });
+        }
+    }
+}
+
+impl<Fq: PrimeField, G, Fr, S: FqSponge<Fq, G, Fr>> Transcript<Fq, G, Fr> for SpongeTranscript<S> {
+    fn absorb_fq(&mut self, label: &'static str, x: &[Fq]) {
+        self.record(label, TranscriptOp::AbsorbFq(x.len()));
+        self.sponge.absorb_fq(x);
+    }
+
+    fn absorb_g(&mut self, label: &'static str, g: &[G]) {
+        self.record(label, TranscriptOp::AbsorbG(g.len()));
+        self.sponge.absorb_g(g);
+    }
+
+    fn absorb_fr(&mut self, label: &'static str, x: &[Fr]) {
+        self.record(label, TranscriptOp::AbsorbFr(x.len()));
+        self.sponge.absorb_fr(x);
+    }
+
+    fn challenge_fq(&mut self, label: &'static str) -> Result<Fq, TranscriptError> {
+        self.register_challenge(label)?;
+        self.record(label, TranscriptOp::ChallengeFq);
+        Ok(self.sponge.challenge_fq())
+    }
+
+    fn challenge(&mut self, label: &'static str) -> Result<Fr, TranscriptError> {
+        self.register_challenge(label)?;
+        self.record(label, TranscriptOp::Challenge);
+        Ok(self.sponge.challenge())
+    }
+}
@@ -0,0 +1,80 @@
+use ark_bn254::Fr;
+use mina_curves::pasta::Fp;
+use mina_poseidon::{
+    constants::{PlonkSpongeConstantsKimchi, SpongeConstants},
+    grain_lfsr::{ParamsError, PoseidonConfig},
+};
+use o1_utils::FieldHelpers;
+
+#[test]
+fn test_grain_lfsr_bn254() {
+    // The parameters of the reference implementation for BN254, x^5 and a
+    // width of 3, as used by circomlib
+    let params = PoseidonConfig::new(3, 8, 57, 5).generate::<Fr>().unwrap();
+    assert_eq!(params.round_constants.len(), 8 + 57);
+    assert!(params.round_constants.iter().all(|row| row.len() == 3));
+    let expected = [
+        "0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e",
+        "00f1445235f2148c5986587169fc1bcd887b08d4d00868df5696fff40956e864",
+        "08dff3487e8ac99e1f29a058d0fa80b930c728730b7ab36ce879f3890ecf73f5",
+    ];
+    for (constant, expected) in params.round_constants[0].iter().zip(expected) {
+        assert_eq!(
+            constant.to_biguint().to_str_radix(16),
+            expected.trim_start_matches('0')
+        );
+    }
+    assert_eq!(
+        params.mds[0][0].to_biguint().to_str_radix(16),
+        "109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b"
+    );
+    assert_eq!(
+        params.mds[0][1].to_biguint().to_str_radix(16),
+        "16ed41e13bb9c0c66ae119424fddbcbc9314dc9fdbdeea55d6c64543dc4903e0"
+    );
+
+    // the generation is deterministic
+    let again = PoseidonConfig::new(3, 8, 57, 5).generate::<Fr>().unwrap();
+    assert_eq!(params.round_constants, again.round_constants);
+    assert_eq!(params.mds, again.mds);
+}
+
+#[test]
+fn test_grain_lfsr_security() {
+    // the number of rounds of the kimchi instance is secure
+    PoseidonConfig::new(
+        PlonkSpongeConstantsKimchi::SPONGE_WIDTH,
+        PlonkSpongeConstantsKimchi::PERM_ROUNDS_FULL,
+        PlonkSpongeConstantsKimchi::PERM_ROUNDS_PARTIAL,
+        PlonkSpongeConstantsKimchi::PERM_SBOX as u64,
+    )
+    .check_security::<Fp>()
+    .unwrap();
+
+    // the secure number of rounds includes the security margin
+    let config = PoseidonConfig::secure::<Fr>(3, 5, 128).unwrap();
+    assert_eq!(config.full_rounds, 8);
+    config.check_security::<Fr>().unwrap();
+    let insecure = PoseidonConfig {
+        full_rounds: config.full_rounds - 4,
+        ..config
+    };
+    assert!(matches!(
+        insecure.check_security::<Fr>(),
+        Err(ParamsError::InsecureRounds { .. })
+    ));
+    assert!(matches!(
+        PoseidonConfig::new(3, 8, 20, 5).generate::<Fr>(),
+        Err(ParamsError::InsecureRounds { .. })
+    ));
+
+    // x^3 is not a permutation of the BN254 scalar field
+    assert_eq!(
+        PoseidonConfig::new(3, 8, 57, 3).check_security::<Fr>(),
+        Err(ParamsError::InvalidSbox(3))
+    );
+    assert_eq!(
+        PoseidonConfig::new(3, 7, 57, 5).check_security::<Fr>(),
+        Err(ParamsError::OddFullRounds(7))
+    );
+}
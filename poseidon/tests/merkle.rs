@@ -0,0 +1,103 @@
+use mina_curves::pasta::Fp;
+use mina_poseidon::{
+    merkle::{compress, hash_leaf, hash_node, tag, MerkleError, MerklePath, MerkleTree},
+    pasta::fp_kimchi,
+};
+
+#[test]
+fn test_merkle_tree() {
+    let params = fp_kimchi::static_params();
+    let leaves: Vec<Fp> = (0u64..5).map(Fp::from).collect();
+
+    for arity in [2, 3, 4] {
+        let mut tree = MerkleTree::new(params, arity, 3, &leaves).unwrap();
+        assert_eq!(tree.capacity(), arity.pow(3));
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(index).unwrap();
+            assert_eq!(path.arity(), arity);
+            assert_eq!(path.siblings.len(), 3);
+            assert!(path.verify(params, tree.root(), *leaf));
+            assert!(!path.verify(params, tree.root(), *leaf + Fp::from(1u8)));
+        }
+        // the missing leaves are zero
+        let last = tree.capacity() - 1;
+        assert!(tree
+            .path(last)
+            .unwrap()
+            .verify(params, tree.root(), Fp::from(0u8)));
+
+        // updating a leaf gives the tree of the updated leaves
+        let mut updated = leaves.clone();
+        updated[2] = Fp::from(42u8);
+        tree.update(params, 2, updated[2]).unwrap();
+        assert_eq!(tree, MerkleTree::new(params, arity, 3, &updated).unwrap());
+        assert!(tree
+            .path(2)
+            .unwrap()
+            .verify(params, tree.root(), Fp::from(42u8)));
+    }
+}
+
+#[test]
+fn test_merkle_tree_hashes() {
+    let params = fp_kimchi::static_params();
+    let (a, b) = (Fp::from(1u8), Fp::from(2u8));
+    let tree = MerkleTree::new(params, 2, 1, &[a, b]).unwrap();
+    let left = compress(params, tag(2, 0), a);
+    let right = compress(params, tag(2, 0), b);
+    assert_eq!(left, hash_leaf(params, 2, a));
+    assert_eq!(
+        tree.root(),
+        compress(params, compress(params, tag(2, 1), left), right)
+    );
+    assert_eq!(tree.root(), hash_node(params, 1, &[left, right]));
+
+    // the tags separate the leaves, the levels and the arities
+    assert_ne!(tag::<Fp>(2, 0), tag::<Fp>(2, 1));
+    assert_ne!(tag::<Fp>(2, 1), tag::<Fp>(3, 1));
+    assert_ne!(
+        MerkleTree::new(params, 2, 2, &[a, b]).unwrap().root(),
+        MerkleTree::new(params, 4, 1, &[a, b]).unwrap().root()
+    );
+}
+
+#[test]
+fn test_merkle_path_serialization() {
+    let params = fp_kimchi::static_params();
+    let tree = MerkleTree::new(params, 3, 2, &[Fp::from(7u8)]).unwrap();
+    let path = tree.path(4).unwrap();
+    let serialized = serde_json::to_string(&path).unwrap();
+    let deserialized: MerklePath<Fp> = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, path);
+    assert_eq!(deserialized.positions(), vec![1, 1]);
+}
+
+#[test]
+fn test_merkle_tree_errors() {
+    let params = fp_kimchi::static_params();
+    let leaves = vec![Fp::from(1u8); 5];
+    assert_eq!(
+        MerkleTree::new(params, 1, 3, &leaves),
+        Err(MerkleError::InvalidArity(1))
+    );
+    assert_eq!(
+        MerkleTree::new(params, 2, 0, &leaves),
+        Err(MerkleError::InvalidDepth(0))
+    );
+    assert_eq!(
+        MerkleTree::new(params, 2, 2, &leaves),
+        Err(MerkleError::TooManyLeaves {
+            capacity: 4,
+            found: 5
+        })
+    );
+    let tree = MerkleTree::new(params, 2, 3, &leaves).unwrap();
+    assert_eq!(
+        tree.path(8),
+        Err(MerkleError::IndexOutOfBounds {
+            index: 8,
+            capacity: 8
+        })
+    );
+}
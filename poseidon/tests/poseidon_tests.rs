@@ -1,10 +1,12 @@
+use ark_ff::UniformRand;
 use mina_curves::pasta::Fp;
 use mina_poseidon::{
-    constants::{PlonkSpongeConstantsKimchi, PlonkSpongeConstantsLegacy},
+    constants::{PlonkSpongeConstantsKimchi, PlonkSpongeConstantsLegacy, SpongeConstants},
     pasta::{fp_kimchi as SpongeParametersKimchi, fp_legacy as SpongeParametersLegacy},
-    poseidon::{ArithmeticSponge as Poseidon, Sponge as _},
+    poseidon::{ArithmeticSponge as Poseidon, ArithmeticSpongeParams, Sponge as _},
 };
 use o1_utils::FieldHelpers;
+use rand::{rngs::StdRng, SeedableRng};
 use serde::Deserialize;
 use std::{fs::File, path::PathBuf}; // needed for ::new() sponge
 
@@ -78,3 +80,32 @@ fn poseidon_test_vectors_kimchi() {
     }
     test_vectors("kimchi.json", hash);
 }
+
+fn test_hash_batch<SC: SpongeConstants>(params: &'static ArithmeticSpongeParams<Fp>) {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    for len in 0..6 {
+        // more inputs than a single chunk of the batch
+        let inputs: Vec<Vec<Fp>> = (0..100)
+            .map(|_| (0..len).map(|_| Fp::rand(&mut rng)).collect())
+            .collect();
+        let expected: Vec<Fp> = inputs
+            .iter()
+            .map(|input| {
+                let mut hash = Poseidon::<Fp, SC>::new(params);
+                hash.absorb(input);
+                hash.squeeze()
+            })
+            .collect();
+        assert_eq!(Poseidon::<Fp, SC>::hash_batch(params, &inputs), expected);
+    }
+}
+
+#[test]
+fn poseidon_hash_batch_legacy() {
+    test_hash_batch::<PlonkSpongeConstantsLegacy>(SpongeParametersLegacy::static_params());
+}
+
+#[test]
+fn poseidon_hash_batch_kimchi() {
+    test_hash_batch::<PlonkSpongeConstantsKimchi>(SpongeParametersKimchi::static_params());
+}
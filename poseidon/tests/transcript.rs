@@ -0,0 +1,89 @@
+use ark_ec::AffineCurve;
+use mina_curves::pasta::{Fq, Vesta, VestaParameters};
+use mina_poseidon::{
+    constants::PlonkSpongeConstantsKimchi,
+    pasta::fq_kimchi,
+    sponge::DefaultFqSponge,
+    transcript::{SpongeTranscript, Transcript, TranscriptEntry, TranscriptError, TranscriptOp},
+    FqSponge,
+};
+
+type Sponge = DefaultFqSponge<VestaParameters, PlonkSpongeConstantsKimchi>;
+
+fn sponge() -> Sponge {
+    Sponge::new(fq_kimchi::static_params())
+}
+
+#[test]
+fn test_transcript_without_domain_matches_sponge() {
+    let mut transcript = SpongeTranscript::new(sponge()).with_trace();
+    transcript.absorb_fq("digest", &[Fq::from(1u8), Fq::from(2u8)]);
+    transcript.absorb_g("comm", &[Vesta::prime_subgroup_generator()]);
+    let beta = transcript.challenge("beta").unwrap();
+
+    let mut expected = sponge();
+    expected.absorb_fq(&[Fq::from(1u8), Fq::from(2u8)]);
+    expected.absorb_g(&[Vesta::prime_subgroup_generator()]);
+    assert_eq!(beta, expected.challenge());
+
+    assert_eq!(
+        transcript.trace().unwrap(),
+        &[
+            TranscriptEntry {
+                label: "digest",
+                op: TranscriptOp::AbsorbFq(2)
+            },
+            TranscriptEntry {
+                label: "comm",
+                op: TranscriptOp::AbsorbG(1)
+            },
+            TranscriptEntry {
+                label: "beta",
+                op: TranscriptOp::Challenge
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_transcript_domain_separation() {
+    let mut first = SpongeTranscript::with_domain(sponge(), "first protocol");
+    let mut second = SpongeTranscript::with_domain(sponge(), "second protocol");
+    let mut none = SpongeTranscript::new(sponge());
+    assert!(first.trace().is_none());
+
+    let challenge: Fq = first.challenge_fq("challenge").unwrap();
+    assert_ne!(challenge, second.challenge_fq("challenge").unwrap());
+    assert_ne!(challenge, none.challenge_fq("challenge").unwrap());
+}
+
+#[test]
+fn test_transcript_labels() {
+    let transcript = |label| {
+        let mut transcript = SpongeTranscript::with_domain(sponge(), "protocol");
+        transcript.absorb_fq(label, &[Fq::from(1u8)]);
+        transcript.challenge("beta").unwrap()
+    };
+    // the labels are absorbed with a domain separator
+    assert_ne!(transcript("left"), transcript("right"));
+
+    // and not without
+    let mut left = SpongeTranscript::new(sponge());
+    left.absorb_fq("left", &[Fq::from(1u8)]);
+    let mut right = SpongeTranscript::new(sponge());
+    right.absorb_fq("right", &[Fq::from(1u8)]);
+    assert_eq!(
+        left.challenge("beta").unwrap(),
+        right.challenge("beta").unwrap()
+    );
+}
+
+#[test]
+fn test_transcript_challenge_reuse() {
+    let mut transcript = SpongeTranscript::new(sponge());
+    transcript.challenge("beta").unwrap();
+    assert_eq!(
+        transcript.challenge("beta"),
+        Err(TranscriptError::ChallengeReused("beta"))
+    );
+}
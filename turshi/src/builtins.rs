@@ -0,0 +1,164 @@
+//! This module implements the Cairo builtins supported by the runner.
+//!
+//! A builtin owns a memory segment starting at some base address. The segment
+//! is split into instances of a fixed number of cells: the first cells of an
+//! instance are inputs written by the program, and the remaining cells are
+//! outputs that the runner deduces from the inputs when they are read.
+//! The supported builtins are:
+//!
+//! - `pedersen`: instances `(x, y, hash(x, y))`
+//! - `range_check`: instances `(x)` with `0 <= x < 2^128`
+//! - `bitwise`: instances `(x, y, x & y, x ^ y, x | y)` with `0 <= x, y < 2^251`
+
+use crate::helper::*;
+use ark_ff::Field;
+use o1_utils::FieldHelpers;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Number of bits bounding the values of the range check builtin
+pub const RANGE_CHECK_BITS: usize = 128;
+
+/// Number of bits bounding the operands of the bitwise builtin
+pub const BITWISE_BITS: usize = 251;
+
+/// A Cairo builtin
+#[derive(Clone, Copy, Debug)]
+pub enum CairoBuiltin<F> {
+    /// The Pedersen hash builtin. The hash is defined over the STARK curve,
+    /// which is not available for every field `F`, so it is provided by the caller.
+    Pedersen {
+        /// The hash function of two field elements
+        hash: fn(F, F) -> F,
+    },
+    /// The range check builtin, for values in `[0, 2^128)`
+    RangeCheck,
+    /// The bitwise builtin, for operands in `[0, 2^251)`
+    Bitwise,
+}
+
+impl<F: Field> CairoBuiltin<F> {
+    /// Name of the builtin, as used in the `%builtins` directive of Cairo
+    pub fn name(&self) -> &'static str {
+        match self {
+            CairoBuiltin::Pedersen { .. } => "pedersen",
+            CairoBuiltin::RangeCheck => "range_check",
+            CairoBuiltin::Bitwise => "bitwise",
+        }
+    }
+
+    /// Number of memory cells of an instance of the builtin
+    pub fn cells_per_instance(&self) -> usize {
+        match self {
+            CairoBuiltin::Pedersen { .. } => 3,
+            CairoBuiltin::RangeCheck => 1,
+            CairoBuiltin::Bitwise => 5,
+        }
+    }
+
+    /// Number of input cells of an instance of the builtin, the remaining ones are outputs
+    pub fn n_inputs(&self) -> usize {
+        match self {
+            CairoBuiltin::Pedersen { .. } => 2,
+            CairoBuiltin::RangeCheck => 1,
+            CairoBuiltin::Bitwise => 2,
+        }
+    }
+
+    /// Computes the output cells of an instance from its input cells
+    pub fn outputs(&self, inputs: &[F]) -> Vec<F> {
+        match self {
+            CairoBuiltin::Pedersen { hash } => vec![hash(inputs[0], inputs[1])],
+            CairoBuiltin::RangeCheck => vec![],
+            CairoBuiltin::Bitwise => {
+                let x = inputs[0].to_bytes();
+                let y = inputs[1].to_bytes();
+                let apply = |op: fn(u8, u8) -> u8| {
+                    let bytes: Vec<u8> = x.iter().zip(y.iter()).map(|(a, b)| op(*a, *b)).collect();
+                    F::from_bytes(&bytes).expect("bitwise output is not a field element")
+                };
+                vec![
+                    apply(|a, b| a & b),
+                    apply(|a, b| a ^ b),
+                    apply(|a, b| a | b),
+                ]
+            }
+        }
+    }
+
+    /// Checks that the inputs of an instance are in the domain of the builtin
+    pub fn check_inputs(&self, inputs: &[F]) -> bool {
+        match self {
+            CairoBuiltin::Pedersen { .. } => true,
+            CairoBuiltin::RangeCheck => fits_in_bits(inputs[0], RANGE_CHECK_BITS),
+            CairoBuiltin::Bitwise => inputs.iter().all(|x| fits_in_bits(*x, BITWISE_BITS)),
+        }
+    }
+}
+
+/// Returns whether the field element, seen as an integer, is smaller than `2^bits`
+fn fits_in_bits<F: Field>(x: F, bits: usize) -> bool {
+    x.to_bytes()
+        .iter()
+        .enumerate()
+        .all(|(i, byte)| match (8 * i + 8).checked_sub(bits) {
+            None | Some(0) => true,
+            Some(excess) if excess >= 8 => *byte == 0,
+            Some(excess) => byte >> (8 - excess) == 0,
+        })
+}
+
+/// The memory segment of a builtin
+#[derive(Clone, Copy, Debug)]
+pub struct BuiltinSegment<F> {
+    /// the builtin owning the segment
+    pub builtin: CairoBuiltin<F>,
+    /// first address of the segment
+    pub base: F,
+}
+
+impl<F: Field> BuiltinSegment<F> {
+    /// Creates a segment for `builtin` starting at address `base`
+    pub fn new(builtin: CairoBuiltin<F>, base: F) -> Self {
+        BuiltinSegment { builtin, base }
+    }
+
+    /// Returns the index of the instance and the index of the cell within the
+    /// instance corresponding to address `addr`, if it belongs to the segment
+    pub fn locate(&self, addr: F) -> Option<(usize, usize)> {
+        let offset = addr.to_u64().checked_sub(self.base.to_u64())? as usize;
+        let cells = self.builtin.cells_per_instance();
+        Some((offset / cells, offset % cells))
+    }
+
+    /// Address of the `cell`-th cell of the `instance`-th instance of the segment
+    pub fn address(&self, instance: usize, cell: usize) -> F {
+        self.base + F::from((instance * self.builtin.cells_per_instance() + cell) as u64)
+    }
+}
+
+/// Errors of the builtins found when validating the memory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuiltinError {
+    /// An input of an instance is not written
+    MissingInput(&'static str, u64),
+    /// An input of an instance is out of the domain of the builtin
+    InvalidInput(&'static str, u64),
+    /// An output of an instance does not match the inputs
+    WrongOutput(&'static str, u64),
+}
+
+impl Display for BuiltinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            BuiltinError::MissingInput(name, addr) => {
+                write!(f, "missing input of the {name} builtin at address {addr}")
+            }
+            BuiltinError::InvalidInput(name, addr) => {
+                write!(f, "invalid input of the {name} builtin at address {addr}")
+            }
+            BuiltinError::WrongOutput(name, addr) => {
+                write!(f, "wrong output of the {name} builtin at address {addr}")
+            }
+        }
+    }
+}
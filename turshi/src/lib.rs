@@ -6,14 +6,18 @@
 //! logic which is represented as steps of computation making up the full
 //! program.
 
+pub mod builtins;
 pub mod flags;
 pub mod helper;
 pub mod memory;
+pub mod public_memory;
 pub mod runner;
 pub mod word;
 
 pub use self::{
+    builtins::CairoBuiltin,
     memory::CairoMemory,
+    public_memory::{MemoryArgument, PublicMemory},
     runner::{CairoInstruction, CairoProgram, Pointers},
     word::{FlagBits, Offsets},
 };
@@ -6,7 +6,11 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use crate::{helper::*, word::CairoWord};
+use crate::{
+    builtins::{BuiltinError, BuiltinSegment, CairoBuiltin},
+    helper::*,
+    word::CairoWord,
+};
 use ark_ff::Field;
 use core::iter::repeat;
 
@@ -16,6 +20,10 @@ pub struct CairoMemory<F> {
     codelen: usize,
     /// full memory vector, None if non initialized
     data: Vec<Option<CairoWord<F>>>,
+    /// segments of the builtins used by the program, sorted by base address
+    builtins: Vec<BuiltinSegment<F>>,
+    /// first address after the last builtin segment, if declared
+    builtins_end: Option<F>,
 }
 
 impl<F: Field> Index<F> for CairoMemory<F> {
@@ -61,6 +69,8 @@ impl<F: Field> CairoMemory<F> {
         CairoMemory {
             codelen: aux.len() - 1,
             data: aux.into_iter().map(|i| Some(CairoWord::new(i))).collect(),
+            builtins: Vec::new(),
+            builtins_end: None,
         }
     }
 
@@ -94,9 +104,140 @@ impl<F: Field> CairoMemory<F> {
         self[addr] = Some(CairoWord::new(elem));
     }
 
-    /// Read element in memory address
+    /// Read element in memory address, deducing it if it is the output of a builtin
     pub fn read(&mut self, addr: F) -> Option<F> {
         self.resize(addr.to_u64()); // Resize if necessary
+        if self[addr].is_none() {
+            if let Some(elem) = self.deduce(addr) {
+                self.write(addr, elem);
+            }
+        }
         self[addr].map(|x| x.word())
     }
+
+    /// Read element in memory address without resizing nor deducing it
+    pub fn peek(&self, addr: F) -> Option<F> {
+        if addr.to_u64() < self.len() {
+            self[addr].map(|x| x.word())
+        } else {
+            None
+        }
+    }
+
+    /// Declares the segment of a builtin starting at memory address `base`
+    /// Panics if another builtin segment starts at the same address
+    pub fn add_builtin(&mut self, builtin: CairoBuiltin<F>, base: F) {
+        let pos = self
+            .builtins
+            .partition_point(|seg| seg.base.to_u64() < base.to_u64());
+        assert!(
+            self.builtins.get(pos).map(|seg| seg.base) != Some(base),
+            "builtin segments must not start at the same address"
+        );
+        self.builtins
+            .insert(pos, BuiltinSegment::new(builtin, base));
+    }
+
+    /// Declares that the last builtin segment ends before memory address `end`,
+    /// e.g. where the execution segment starts. Otherwise, it spans up to the
+    /// end of the memory.
+    pub fn end_builtins(&mut self, end: F) {
+        self.builtins_end = Some(end);
+    }
+
+    /// Get the segments of the builtins, sorted by base address
+    pub fn builtins(&self) -> &[BuiltinSegment<F>] {
+        &self.builtins
+    }
+
+    /// Returns the segment containing memory address `addr`, if any. A segment
+    /// spans from its base address to the base address of the next segment, or
+    /// to the end of the builtins for the last one.
+    fn segment(&self, addr: F) -> Option<&BuiltinSegment<F>> {
+        if matches!(self.builtins_end, Some(end) if addr.to_u64() >= end.to_u64()) {
+            return None;
+        }
+        let pos = self
+            .builtins
+            .partition_point(|seg| seg.base.to_u64() <= addr.to_u64());
+        pos.checked_sub(1).map(|pos| &self.builtins[pos])
+    }
+
+    /// Deduces the content of memory address `addr` if it is an output cell
+    /// of a builtin instance whose inputs are written
+    fn deduce(&self, addr: F) -> Option<F> {
+        let seg = self.segment(addr)?;
+        let (instance, cell) = seg.locate(addr)?;
+        let n_inputs = seg.builtin.n_inputs();
+        if cell < n_inputs {
+            return None;
+        }
+        let inputs = (0..n_inputs)
+            .map(|i| self.peek(seg.address(instance, i)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(seg.builtin.outputs(&inputs)[cell - n_inputs])
+    }
+
+    /// Returns the number of instances used in the `i`-th builtin segment, that
+    /// is up to the last written cell of the segment
+    pub fn builtin_size(&self, i: usize) -> usize {
+        let seg = &self.builtins[i];
+        let end = match (self.builtins.get(i + 1), self.builtins_end) {
+            (Some(next), _) => next.base.to_u64(),
+            (None, Some(end)) => end.to_u64(),
+            (None, None) => self.len(),
+        }
+        .min(self.len());
+        let base = seg.base.to_u64();
+        let used = (base..end)
+            .rev()
+            .find(|addr| self.peek(F::from(*addr)).is_some())
+            .map_or(0, |addr| addr - base + 1) as usize;
+        let cells = seg.builtin.cells_per_instance();
+        (used + cells - 1) / cells
+    }
+
+    /// Returns the cells of the used instances of the `i`-th builtin segment
+    /// Fails if an input is missing or invalid, or if an output is wrong
+    pub fn builtin_instances(&self, i: usize) -> std::result::Result<Vec<Vec<F>>, BuiltinError> {
+        let seg = &self.builtins[i];
+        let name = seg.builtin.name();
+        let n_inputs = seg.builtin.n_inputs();
+        (0..self.builtin_size(i))
+            .map(|instance| {
+                let inputs = (0..n_inputs)
+                    .map(|cell| {
+                        let addr = seg.address(instance, cell);
+                        self.peek(addr)
+                            .ok_or(BuiltinError::MissingInput(name, addr.to_u64()))
+                    })
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                if !seg.builtin.check_inputs(&inputs) {
+                    return Err(BuiltinError::InvalidInput(
+                        name,
+                        seg.address(instance, 0).to_u64(),
+                    ));
+                }
+                let mut cells = inputs.clone();
+                for (j, output) in seg.builtin.outputs(&inputs).into_iter().enumerate() {
+                    let addr = seg.address(instance, n_inputs + j);
+                    match self.peek(addr) {
+                        Some(elem) if elem != output => {
+                            return Err(BuiltinError::WrongOutput(name, addr.to_u64()))
+                        }
+                        _ => cells.push(output),
+                    }
+                }
+                Ok(cells)
+            })
+            .collect()
+    }
+
+    /// Checks the memory cells of all the builtin segments
+    pub fn validate_builtins(&self) -> std::result::Result<(), BuiltinError> {
+        for i in 0..self.builtins.len() {
+            self.builtin_instances(i)?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,290 @@
+//! This module implements the public memory argument of Cairo, which binds the
+//! bytecode of the program and its public inputs and outputs to the memory
+//! accessed during the execution.
+//!
+//! The memory accesses `(a, v)` of the execution are permuted into a list
+//! `(a', v')` sorted by address, which must be continuous and single-valued:
+//!
+//! - `(a'_{i+1} - a'_i) * (a'_{i+1} - a'_i - 1) = 0`
+//! - `(v'_{i+1} - v'_i) * (a'_{i+1} - a'_i - 1) = 0`
+//!
+//! The `n` cells of the public memory are appended to the accesses, but they are
+//! replaced by dummy accesses `(0, 0)` in the unsorted list, so that the values
+//! claimed by the prover never enter it. For challenges `z` and `alpha`, the
+//! grand product of the permutation then satisfies
+//!
+//! `prod_i (z - (a_i + alpha * v_i)) / (z - (a'_i + alpha * v'_i))
+//!     = z^n / prod_{(a, v) public} (z - (a + alpha * v))`
+//!
+//! where the right hand side is computed by the verifier from the public memory
+//! only.
+
+use crate::{helper::*, memory::CairoMemory, runner::CairoProgram, Pointers};
+use ark_ff::{batch_inversion, Field};
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+/// An access to the memory, reading `value` at address `addr`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess<F> {
+    /// The address of the cell
+    pub addr: F,
+    /// The value of the cell
+    pub value: F,
+}
+
+impl<F: Field> MemoryAccess<F> {
+    /// Creates a memory access
+    pub fn new(addr: F, value: F) -> Self {
+        MemoryAccess { addr, value }
+    }
+
+    /// The dummy access replacing the public memory cells in the unsorted list
+    pub fn dummy() -> Self {
+        MemoryAccess::new(F::zero(), F::zero())
+    }
+
+    /// Returns the random linear combination `z - (addr + alpha * value)`
+    fn term(&self, z: F, alpha: F) -> F {
+        z - (self.addr + alpha * self.value)
+    }
+}
+
+/// Errors of the public memory argument
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// A cell of a public memory segment is not written
+    MissingCell(u64),
+    /// A public memory cell is declared with two different values
+    ConflictingCell(u64),
+    /// The sorted accesses skip an address after the given one
+    NotContinuous(u64),
+    /// The sorted accesses read two different values at the given address
+    NotSingleValued(u64),
+    /// The grand product does not match the public memory
+    WrongProduct,
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            MemoryError::MissingCell(addr) => {
+                write!(f, "missing public memory cell at address {addr}")
+            }
+            MemoryError::ConflictingCell(addr) => {
+                write!(f, "conflicting public memory cell at address {addr}")
+            }
+            MemoryError::NotContinuous(addr) => {
+                write!(f, "memory accesses are not continuous after address {addr}")
+            }
+            MemoryError::NotSingleValued(addr) => {
+                write!(f, "memory accesses are not single valued at address {addr}")
+            }
+            MemoryError::WrongProduct => {
+                write!(f, "grand product does not match the public memory")
+            }
+        }
+    }
+}
+
+/// The public memory of a Cairo program: its bytecode, and its public inputs
+/// and outputs, sorted by address
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PublicMemory<F> {
+    cells: Vec<MemoryAccess<F>>,
+}
+
+impl<F: Field> PublicMemory<F> {
+    /// Creates an empty public memory
+    pub fn new() -> Self {
+        PublicMemory { cells: Vec::new() }
+    }
+
+    /// Creates the public memory containing the bytecode of the program, that
+    /// is the first [CairoMemory::get_codelen] cells after the dummy entry
+    pub fn from_bytecode(mem: &CairoMemory<F>) -> Self {
+        let mut public = PublicMemory::new();
+        public
+            .add_segment(mem, 1, mem.get_codelen() as u64 + 1)
+            .expect("the bytecode is written");
+        public
+    }
+
+    /// Declares the cell at address `addr` with value `value` as public
+    /// Fails if the cell is already public with a different value
+    pub fn add(&mut self, addr: F, value: F) -> Result<(), MemoryError> {
+        let pos = self
+            .cells
+            .partition_point(|cell| cell.addr.to_u64() < addr.to_u64());
+        match self.cells.get(pos) {
+            Some(cell) if cell.addr == addr => {
+                if cell.value != value {
+                    return Err(MemoryError::ConflictingCell(addr.to_u64()));
+                }
+            }
+            _ => self.cells.insert(pos, MemoryAccess::new(addr, value)),
+        }
+        Ok(())
+    }
+
+    /// Declares the cells of the memory in `[start, end)` as public, for
+    /// instance the public inputs or the outputs of the program
+    /// Fails if one of them is not written
+    pub fn add_segment(
+        &mut self,
+        mem: &CairoMemory<F>,
+        start: u64,
+        end: u64,
+    ) -> Result<(), MemoryError> {
+        for addr in start..end {
+            let value = mem
+                .peek(F::from(addr))
+                .ok_or(MemoryError::MissingCell(addr))?;
+            self.add(F::from(addr), value)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the public cells, sorted by address
+    pub fn cells(&self) -> &[MemoryAccess<F>] {
+        &self.cells
+    }
+
+    /// Returns the number of public cells
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns whether there is no public cell
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the value `z^n / prod_{(a, v) public} (z - (a + alpha * v))`
+    /// expected for the grand product of the memory argument
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `z - (a + alpha * v)` is zero for a public cell.
+    pub fn product(&self, z: F, alpha: F) -> F {
+        let denominator: F = self.cells.iter().map(|cell| cell.term(z, alpha)).product();
+        z.pow([self.len() as u64])
+            * denominator
+                .inverse()
+                .expect("the challenges must not match a public cell")
+    }
+}
+
+/// The columns of the memory argument of an execution: the accesses, with
+/// the public cells replaced by dummy accesses, and the sorted accesses
+pub struct MemoryArgument<F> {
+    accesses: Vec<MemoryAccess<F>>,
+    sorted: Vec<MemoryAccess<F>>,
+}
+
+impl<F: Field> MemoryArgument<F> {
+    /// Builds the memory argument of the execution of `prog` with public
+    /// memory `public`.
+    /// The accesses of an instruction are the reads of its word at `pc`, and of
+    /// `dst`, `op0` and `op1` at their addresses. The addresses which are not
+    /// accessed between the lowest and the highest ones are also read, so that
+    /// the sorted accesses are continuous.
+    pub fn new(prog: &CairoProgram<F>, public: &PublicMemory<F>) -> Self {
+        let mut accesses: Vec<MemoryAccess<F>> = prog
+            .trace()
+            .iter()
+            .flat_map(|inst| {
+                [
+                    MemoryAccess::new(inst.pc(), inst.instr()),
+                    MemoryAccess::new(inst.adr_dst(), inst.dst()),
+                    MemoryAccess::new(inst.adr_op0(), inst.op0()),
+                    MemoryAccess::new(inst.adr_op1(), inst.op1()),
+                ]
+            })
+            .collect();
+
+        let used: BTreeSet<u64> = accesses
+            .iter()
+            .chain(public.cells())
+            .map(|access| access.addr.to_u64())
+            .collect();
+        if let (Some(&first), Some(&last)) = (used.first(), used.last()) {
+            for addr in (first..=last).filter(|addr| !used.contains(addr)) {
+                let value = prog.mem.peek(F::from(addr)).unwrap_or(F::zero());
+                accesses.push(MemoryAccess::new(F::from(addr), value));
+            }
+        }
+
+        let mut sorted: Vec<MemoryAccess<F>> =
+            accesses.iter().chain(public.cells()).copied().collect();
+        sorted.sort_by_key(|access| access.addr.to_u64());
+
+        accesses.extend(public.cells().iter().map(|_| MemoryAccess::dummy()));
+
+        MemoryArgument { accesses, sorted }
+    }
+
+    /// Returns the accesses, ending with a dummy access per public cell
+    pub fn accesses(&self) -> &[MemoryAccess<F>] {
+        &self.accesses
+    }
+
+    /// Returns the accesses sorted by address
+    pub fn sorted(&self) -> &[MemoryAccess<F>] {
+        &self.sorted
+    }
+
+    /// Checks that the sorted accesses are continuous and single-valued
+    pub fn check(&self) -> Result<(), MemoryError> {
+        for pair in self.sorted.windows(2) {
+            let (curr, next) = (pair[0], pair[1]);
+            let step = next.addr - curr.addr;
+            if step * (step - F::one()) != F::zero() {
+                return Err(MemoryError::NotContinuous(curr.addr.to_u64()));
+            }
+            if (next.value - curr.value) * (step - F::one()) != F::zero() {
+                return Err(MemoryError::NotSingleValued(curr.addr.to_u64()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the running grand product of the permutation for challenges `z`
+    /// and `alpha`, whose last entry is the full product
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `z - (a' + alpha * v')` is zero for a sorted access.
+    pub fn grand_product(&self, z: F, alpha: F) -> Vec<F> {
+        let mut denominators: Vec<F> = self
+            .sorted
+            .iter()
+            .map(|access| access.term(z, alpha))
+            .collect();
+        assert!(
+            denominators.iter().all(|d| !d.is_zero()),
+            "the challenges must not match a memory access"
+        );
+        batch_inversion(&mut denominators);
+        self.accesses
+            .iter()
+            .zip(denominators)
+            .scan(F::one(), |acc, (access, inv)| {
+                *acc *= access.term(z, alpha) * inv;
+                Some(*acc)
+            })
+            .collect()
+    }
+
+    /// Verifies the argument against the public memory `public`
+    pub fn verify(&self, public: &PublicMemory<F>, z: F, alpha: F) -> Result<(), MemoryError> {
+        self.check()?;
+        let product = self.grand_product(z, alpha).last().copied();
+        if product.unwrap_or(F::one()) != public.product(z, alpha) {
+            return Err(MemoryError::WrongProduct);
+        }
+        Ok(())
+    }
+}
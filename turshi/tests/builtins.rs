@@ -0,0 +1,118 @@
+use ark_ff::{Field, One, Zero};
+use mina_curves::pasta::Fp as F;
+use turshi::{
+    builtins::{BuiltinError, CairoBuiltin, BITWISE_BITS, RANGE_CHECK_BITS},
+    CairoMemory, CairoProgram,
+};
+
+// A toy hash, the Pedersen hash over the STARK curve is not available for Pasta fields
+fn toy_hash(x: F, y: F) -> F {
+    x * x + y
+}
+
+#[test]
+fn test_builtin_deduction() {
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(10u32));
+    mem.add_builtin(CairoBuiltin::Pedersen { hash: toy_hash }, F::from(20u32));
+    mem.write(F::from(10u32), F::from(12u32));
+    mem.write(F::from(11u32), F::from(10u32));
+    mem.write(F::from(20u32), F::from(3u32));
+    mem.write(F::from(21u32), F::from(4u32));
+
+    assert_eq!(mem.read(F::from(12u32)), Some(F::from(8u32))); // and
+    assert_eq!(mem.read(F::from(13u32)), Some(F::from(6u32))); // xor
+    assert_eq!(mem.read(F::from(14u32)), Some(F::from(14u32))); // or
+    assert_eq!(mem.read(F::from(22u32)), Some(F::from(13u32))); // hash
+
+    // the inputs of the next instance are not written
+    assert_eq!(mem.read(F::from(17u32)), None);
+    assert_eq!(mem.builtin_size(0), 1);
+    assert_eq!(mem.builtin_size(1), 1);
+    assert_eq!(mem.validate_builtins(), Ok(()));
+}
+
+#[test]
+fn test_range_check_builtin() {
+    let bound = (0..RANGE_CHECK_BITS).fold(F::one(), |acc, _| acc.double());
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::RangeCheck, F::from(10u32));
+    mem.write(F::from(10u32), F::zero());
+    mem.write(F::from(11u32), bound - F::one());
+    assert_eq!(
+        mem.builtin_instances(0),
+        Ok(vec![vec![F::zero()], vec![bound - F::one()]])
+    );
+
+    mem.write(F::from(12u32), bound);
+    assert_eq!(
+        mem.validate_builtins(),
+        Err(BuiltinError::InvalidInput("range_check", 12))
+    );
+}
+
+#[test]
+fn test_bitwise_builtin_errors() {
+    let bound = (0..BITWISE_BITS).fold(F::one(), |acc, _| acc.double());
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(10u32));
+    mem.write(F::from(10u32), F::from(12u32));
+    mem.write(F::from(11u32), F::from(10u32));
+    mem.write(F::from(13u32), F::from(7u32));
+    assert_eq!(
+        mem.validate_builtins(),
+        Err(BuiltinError::WrongOutput("bitwise", 13))
+    );
+
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(10u32));
+    mem.write(F::from(10u32), bound);
+    mem.write(F::from(11u32), F::one());
+    assert_eq!(
+        mem.validate_builtins(),
+        Err(BuiltinError::InvalidInput("bitwise", 10))
+    );
+
+    let mut mem = CairoMemory::<F>::new(vec![]);
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(10u32));
+    mem.write(F::from(11u32), F::one());
+    assert_eq!(
+        mem.validate_builtins(),
+        Err(BuiltinError::MissingInput("bitwise", 10))
+    );
+}
+
+#[test]
+fn test_cairo_program_with_builtin() {
+    // [ap] = [[fp-3] + 2]; ap++
+    // ret
+    let instrs = [0x480280027ffd8000, 0x208b7fff7fff7ffe]
+        .iter()
+        .map(|&i: &i64| F::from(i))
+        .collect();
+    let mut mem = CairoMemory::<F>::new(instrs);
+    // the bitwise segment starts right after the program
+    mem.add_builtin(CairoBuiltin::Bitwise, F::from(3u32));
+    mem.write(F::from(3u32), F::from(12u32));
+    mem.write(F::from(4u32), F::from(10u32));
+    // the execution segment starts right after the only bitwise instance
+    mem.end_builtins(F::from(8u32));
+    // pointer to the bitwise segment, followed by the return fp and pc
+    mem.write(F::from(8u32), F::from(3u32));
+    mem.write(F::from(9u32), F::from(12u32));
+    mem.write(F::from(10u32), F::from(12u32));
+    let prog = CairoProgram::new(&mut mem, 1);
+
+    assert_eq!(prog.trace().len(), 2);
+    assert_eq!(prog.trace()[0].dst(), F::from(8u32));
+    assert_eq!(
+        prog.mem.builtin_instances(0),
+        Ok(vec![vec![
+            F::from(12u32),
+            F::from(10u32),
+            F::from(8u32),
+            F::from(6u32),
+            F::from(14u32),
+        ]])
+    );
+}
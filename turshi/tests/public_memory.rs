@@ -0,0 +1,126 @@
+use ark_ff::One;
+use mina_curves::pasta::Fp as F;
+use turshi::{
+    public_memory::{MemoryAccess, MemoryError},
+    CairoMemory, CairoProgram, MemoryArgument, PublicMemory,
+};
+
+/// Returns the memory of the program serializing 3 words from
+/// `test_cairo_output`, with its outputs at `[41, 44)`
+fn output_memory() -> CairoMemory<F> {
+    let instrs = [
+        0x400380007ffc7ffd,
+        0x482680017ffc8000,
+        1,
+        0x208b7fff7fff7ffe,
+        0x480680017fff8000,
+        10,
+        0x48307fff7fff8000,
+        0x48507fff7fff8000,
+        0x48307ffd7fff8000,
+        0x480a7ffd7fff8000,
+        0x48127ffb7fff8000,
+        0x1104800180018000,
+        -11,
+        0x48127ff87fff8000,
+        0x1104800180018000,
+        -14,
+        0x48127ff67fff8000,
+        0x1104800180018000,
+        -17,
+        0x208b7fff7fff7ffe,
+    ]
+    .iter()
+    .map(|&i: &i64| F::from(i))
+    .collect();
+    let mut mem = CairoMemory::<F>::new(instrs);
+    mem.write(F::from(21u32), F::from(41u32)); // beginning of outputs
+    mem.write(F::from(22u32), F::from(44u32)); // end of outputs
+    mem.write(F::from(23u32), F::from(44u32)); // end of program
+    mem
+}
+
+/// Returns challenges `z` and `alpha` of the memory argument
+fn challenges() -> (F, F) {
+    (
+        F::from(0x5b1f_3a6c_0e2d_9487u64),
+        F::from(0x2c7e_91d4_b803_f65au64),
+    )
+}
+
+#[test]
+fn test_public_memory() {
+    let mem = output_memory();
+    let mut public = PublicMemory::from_bytecode(&mem);
+    assert_eq!(public.len(), 20);
+    assert_eq!(
+        public.cells()[2],
+        MemoryAccess::new(F::from(3u32), F::one())
+    );
+
+    // Declaring a cell twice is only allowed with the same value
+    assert_eq!(public.add(F::from(3u32), F::one()), Ok(()));
+    assert_eq!(
+        public.add(F::from(3u32), F::from(2u32)),
+        Err(MemoryError::ConflictingCell(3))
+    );
+    assert_eq!(
+        public.add_segment(&mem, 23, 25),
+        Err(MemoryError::MissingCell(24))
+    );
+}
+
+#[test]
+fn test_memory_argument() {
+    let mut mem = output_memory();
+    let mut public = PublicMemory::from_bytecode(&mem);
+    let prog = CairoProgram::new(&mut mem, 5);
+    public.add_segment(prog.mem, 21, 24).unwrap();
+    public.add_segment(prog.mem, 41, 44).unwrap();
+    assert_eq!(public.cells().last().unwrap().value, F::from(410u32));
+
+    let argument = MemoryArgument::new(&prog, &public);
+    assert_eq!(argument.accesses().len(), argument.sorted().len());
+    assert_eq!(
+        argument.accesses()[argument.accesses().len() - 1],
+        MemoryAccess::dummy()
+    );
+    argument.check().unwrap();
+
+    let (z, alpha) = challenges();
+    assert_eq!(
+        argument.grand_product(z, alpha).len(),
+        argument.accesses().len()
+    );
+    assert_eq!(argument.verify(&public, z, alpha), Ok(()));
+}
+
+#[test]
+fn test_memory_argument_rejects_wrong_public_memory() {
+    let mut mem = output_memory();
+    let mut public = PublicMemory::from_bytecode(&mem);
+    let prog = CairoProgram::new(&mut mem, 5);
+    public.add_segment(prog.mem, 41, 44).unwrap();
+
+    let (z, alpha) = challenges();
+
+    // A claimed output different from the executed one
+    let mut forged = PublicMemory::from_bytecode(prog.mem);
+    forged.add(F::from(41u32), F::from(10u32)).unwrap();
+    forged.add(F::from(42u32), F::from(20u32)).unwrap();
+    forged.add(F::from(43u32), F::from(411u32)).unwrap();
+
+    // is caught by the product if the accesses are honest
+    let argument = MemoryArgument::new(&prog, &public);
+    assert_eq!(
+        argument.verify(&forged, z, alpha),
+        Err(MemoryError::WrongProduct)
+    );
+
+    // and otherwise by the sorted accesses
+    let argument = MemoryArgument::new(&prog, &forged);
+    assert_eq!(
+        argument.verify(&forged, z, alpha),
+        Err(MemoryError::NotSingleValued(43))
+    );
+}
@@ -0,0 +1,49 @@
+use ark_ff::UniformRand;
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use mina_curves::pasta::Fp;
+use o1_utils::fft::{fft_in_place, ifft_in_place};
+
+pub fn bench_fft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft");
+    group.sample_size(10);
+    let mut rng = o1_utils::tests::make_test_rng(None);
+
+    for log_size in [14, 16, 18, 20] {
+        let domain = D::<Fp>::new(1 << log_size).unwrap();
+        let values: Vec<Fp> = (0..domain.size()).map(|_| Fp::rand(&mut rng)).collect();
+        let size = format!("2^{log_size}");
+
+        group.bench_with_input(BenchmarkId::new("ark-poly fft", &size), &values, |b, v| {
+            b.iter_batched(
+                || v.clone(),
+                |mut v| domain.fft_in_place(black_box(&mut v)),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("parallel fft", &size), &values, |b, v| {
+            b.iter_batched(
+                || v.clone(),
+                |mut v| fft_in_place(domain, black_box(&mut v)),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("ark-poly ifft", &size), &values, |b, v| {
+            b.iter_batched(
+                || v.clone(),
+                |mut v| domain.ifft_in_place(black_box(&mut v)),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_with_input(BenchmarkId::new("parallel ifft", &size), &values, |b, v| {
+            b.iter_batched(
+                || v.clone(),
+                |mut v| ifft_in_place(domain, black_box(&mut v)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, bench_fft);
+criterion_main!(benches);
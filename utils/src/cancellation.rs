@@ -0,0 +1,55 @@
+//! Cooperative cancellation of long-running computations, such as the creation
+//! of a proof or the generation of a witness.
+//!
+//! A [CancellationToken] is shared between the computation and the service
+//! driving it. The computation checks the token between its steps, and returns
+//! [Cancelled] once the token has been cancelled, dropping the buffers it
+//! allocated, instead of the process having to be killed.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use thiserror::Error;
+
+/// The error returned by a computation that has been cancelled
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("the computation was cancelled")]
+pub struct Cancelled;
+
+/// A token to cancel a computation, see the
+/// [module documentation](self).
+/// Clones of a token share its state, so that cancelling one of them cancels
+/// all of them.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token which is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the computations checking this token, or a clone of it
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Checks whether the computation can go on
+    ///
+    /// # Errors
+    ///
+    /// Will give error if the token has been cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
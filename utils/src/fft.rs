@@ -0,0 +1,279 @@
+//! This module implements a multi-threaded radix-2/4 FFT over the evaluation
+//! domains of arkworks.
+//!
+//! A transform of size `n = n1 * n2` is computed with the four-step
+//! algorithm: the values are transposed in a `n1 x n2` matrix, the `n1` rows
+//! are transformed in parallel with FFTs of size `n2`, multiplied by twiddle
+//! factors and transposed back, and the `n2` rows are transformed in parallel
+//! with FFTs of size `n1`. The rows fit in the cache of a core, and the
+//! transposes are cache-blocked like the ones of [crate::transpose].
+//! The small FFTs fuse the stages of the radix-2 FFT by pairs into radix-4
+//! butterflies, halving the number of passes over the values.
+//!
+//! The transforms below [MIN_PARALLEL_LOG_SIZE] run on the current thread.
+//! [FftEvaluate] and [FftInterpolate] use these transforms when the
+//! `parallel_fft` feature is enabled, and the ones of arkworks otherwise, so
+//! that the code paths of the provers can switch between them.
+//!
+//! ```
+//! use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+//! use mina_curves::pasta::Fp;
+//! use o1_utils::fft::{fft_in_place, ifft_in_place};
+//!
+//! let domain = Radix2EvaluationDomain::<Fp>::new(4).unwrap();
+//! let coeffs: Vec<Fp> = (0..4u64).map(Fp::from).collect();
+//!
+//! let mut values = coeffs.clone();
+//! fft_in_place(domain, &mut values);
+//! assert_eq!(values, domain.fft(&coeffs));
+//!
+//! ifft_in_place(domain, &mut values);
+//! assert_eq!(values, coeffs);
+//! ```
+
+use crate::transpose::BLOCK_SIZE;
+use ark_ff::FftField;
+#[cfg(feature = "parallel_fft")]
+use ark_poly::UVPolynomial;
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
+};
+use rayon::prelude::*;
+
+/// The log of the size from which the transforms are split in parallel
+/// transforms
+pub const MIN_PARALLEL_LOG_SIZE: u32 = 10;
+
+/// Evaluates the polynomial of coefficients `values` over `domain`, in place.
+/// Like [EvaluationDomain::fft_in_place], the coefficients are resized to the
+/// size of the domain.
+pub fn fft_in_place<F: FftField>(domain: D<F>, values: &mut Vec<F>) {
+    values.resize(domain.size(), F::zero());
+    transform(values, domain.group_gen);
+}
+
+/// Interpolates the evaluations `values` over `domain`, in place.
+/// Like [EvaluationDomain::ifft_in_place], the evaluations are resized to the
+/// size of the domain.
+pub fn ifft_in_place<F: FftField>(domain: D<F>, values: &mut Vec<F>) {
+    values.resize(domain.size(), F::zero());
+    transform(values, domain.group_gen_inv);
+    let size_inv = domain.size_inv;
+    values.par_iter_mut().for_each(|value| *value *= size_inv);
+}
+
+/// Evaluates the polynomial of coefficients `values` over the coset of
+/// `domain` by the multiplicative generator of the field, in place
+pub fn coset_fft_in_place<F: FftField>(domain: D<F>, values: &mut Vec<F>) {
+    distribute_powers(values, F::multiplicative_generator());
+    fft_in_place(domain, values);
+}
+
+/// Interpolates the evaluations `values` over the coset of `domain` by the
+/// multiplicative generator of the field, in place
+pub fn coset_ifft_in_place<F: FftField>(domain: D<F>, values: &mut Vec<F>) {
+    ifft_in_place(domain, values);
+    let generator_inv = F::multiplicative_generator()
+        .inverse()
+        .expect("the generator is not zero");
+    distribute_powers(values, generator_inv);
+}
+
+/// Multiplies the `i`-th value by `g^i`
+fn distribute_powers<F: FftField>(values: &mut [F], g: F) {
+    values
+        .par_chunks_mut(1 << MIN_PARALLEL_LOG_SIZE)
+        .enumerate()
+        .for_each(|(chunk, values)| {
+            let mut power = g.pow([(chunk << MIN_PARALLEL_LOG_SIZE) as u64]);
+            for value in values {
+                *value *= power;
+                power *= g;
+            }
+        });
+}
+
+/// Returns `[1, root, ..., root^(len - 1)]`
+fn powers<F: FftField>(root: F, len: usize) -> Vec<F> {
+    let mut powers = Vec::with_capacity(len);
+    let mut power = F::one();
+    for _ in 0..len {
+        powers.push(power);
+        power *= root;
+    }
+    powers
+}
+
+/// Computes the DFT of `values` for `root`, a primitive root of unity of order
+/// the length of `values`, which must be a power of 2
+fn transform<F: FftField>(values: &mut Vec<F>, root: F) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    if log_n < MIN_PARALLEL_LOG_SIZE {
+        serial_fft(values, &powers(root, n / 2));
+        return;
+    }
+
+    // With `j = j1 + n1 * j2` and `k = k2 + n2 * k1`, the DFT is
+    // `X[k] = sum_{j1} w1^{j1 k1} w^{j1 k2} sum_{j2} w2^{j2 k2} x[j1 + n1 j2]`
+    // for `w1 = w^n2` and `w2 = w^n1`
+    let n1 = 1 << (log_n / 2);
+    let n2 = n / n1;
+    let mut scratch = vec![F::zero(); n];
+
+    // the `n1` rows `x[j1 + n1 j2]`, transformed and multiplied by `w^{j1 k2}`
+    transpose_into(values, n2, n1, &mut scratch);
+    let twiddles = powers(root.pow([n1 as u64]), n2 / 2);
+    scratch
+        .par_chunks_mut(n2)
+        .enumerate()
+        .for_each(|(j1, row)| {
+            serial_fft(row, &twiddles);
+            let w = root.pow([j1 as u64]);
+            let mut power = F::one();
+            for value in row.iter_mut() {
+                *value *= power;
+                power *= w;
+            }
+        });
+
+    // the `n2` rows indexed by `k2`, transformed
+    transpose_into(&scratch, n1, n2, values);
+    let twiddles = powers(root.pow([n2 as u64]), n1 / 2);
+    values
+        .par_chunks_mut(n1)
+        .for_each(|row| serial_fft(row, &twiddles));
+
+    // `X[k2 + n2 k1]` is at row `k2` and column `k1`
+    transpose_into(values, n2, n1, &mut scratch);
+    std::mem::swap(values, &mut scratch);
+}
+
+/// Transposes the `rows x cols` matrix `src` into `dst`, blocks of
+/// [BLOCK_SIZE] rows and columns at a time
+fn transpose_into<F: Copy + Send + Sync>(src: &[F], rows: usize, cols: usize, dst: &mut [F]) {
+    dst.par_chunks_mut(BLOCK_SIZE * rows)
+        .enumerate()
+        .for_each(|(block, dst)| {
+            let first = block * BLOCK_SIZE;
+            let num_cols = dst.len() / rows;
+            for row_block in (0..rows).step_by(BLOCK_SIZE) {
+                for col in 0..num_cols {
+                    for row in row_block..rows.min(row_block + BLOCK_SIZE) {
+                        dst[col * rows + row] = src[row * cols + first + col];
+                    }
+                }
+            }
+        });
+}
+
+/// Computes the DFT of `values` on the current thread, with `twiddles` the
+/// first half of the powers of the root of unity
+fn serial_fft<F: FftField>(values: &mut [F], twiddles: &[F]) {
+    let n = values.len();
+    if n <= 1 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - log_n);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    // a radix-2 stage if the number of stages is odd
+    let mut m = 1;
+    if log_n % 2 == 1 {
+        for pair in values.chunks_mut(2) {
+            let (a0, a1) = (pair[0], pair[1]);
+            pair[0] = a0 + a1;
+            pair[1] = a0 - a1;
+        }
+        m = 2;
+    }
+
+    // the radix-2 stages of half sizes `m` and `2m` fused in radix-4 stages
+    while m < n {
+        let stride = n / (4 * m);
+        let i = twiddles[n / 4];
+        for block in values.chunks_mut(4 * m) {
+            for k in 0..m {
+                let w = twiddles[k * stride];
+                let w2 = twiddles[2 * k * stride];
+                let t1 = w2 * block[k + m];
+                let (b0, b1) = (block[k] + t1, block[k] - t1);
+                let t3 = w2 * block[k + 3 * m];
+                let (b2, b3) = (block[k + 2 * m] + t3, block[k + 2 * m] - t3);
+                let t2 = w * b2;
+                let u3 = w * b3 * i;
+                block[k] = b0 + t2;
+                block[k + 2 * m] = b0 - t2;
+                block[k + m] = b1 + u3;
+                block[k + 3 * m] = b1 - u3;
+            }
+        }
+        m *= 4;
+    }
+}
+
+/// An extension of [DensePolynomial] evaluating it over a domain with the FFT
+/// selected by the `parallel_fft` feature, see the
+/// [module documentation](self)
+pub trait FftEvaluate<F: FftField> {
+    /// Evaluates the polynomial over `domain`
+    fn fft_evaluate_over_domain_by_ref(&self, domain: D<F>) -> Evaluations<F, D<F>>;
+
+    /// Evaluates the polynomial over `domain`, reusing its coefficients
+    fn fft_evaluate_over_domain(self, domain: D<F>) -> Evaluations<F, D<F>>;
+}
+
+impl<F: FftField> FftEvaluate<F> for DensePolynomial<F> {
+    fn fft_evaluate_over_domain_by_ref(&self, domain: D<F>) -> Evaluations<F, D<F>> {
+        self.clone().fft_evaluate_over_domain(domain)
+    }
+
+    #[cfg(feature = "parallel_fft")]
+    fn fft_evaluate_over_domain(self, domain: D<F>) -> Evaluations<F, D<F>> {
+        let mut values = self.coeffs;
+        fft_in_place(domain, &mut values);
+        Evaluations::from_vec_and_domain(values, domain)
+    }
+
+    #[cfg(not(feature = "parallel_fft"))]
+    fn fft_evaluate_over_domain(self, domain: D<F>) -> Evaluations<F, D<F>> {
+        self.evaluate_over_domain(domain)
+    }
+}
+
+/// An extension of [Evaluations] interpolating them with the FFT selected by
+/// the `parallel_fft` feature, see the [module documentation](self)
+pub trait FftInterpolate<F: FftField> {
+    /// Interpolates the evaluations
+    fn fft_interpolate_by_ref(&self) -> DensePolynomial<F>;
+
+    /// Interpolates the evaluations, reusing their values
+    fn fft_interpolate(self) -> DensePolynomial<F>;
+}
+
+impl<F: FftField> FftInterpolate<F> for Evaluations<F, D<F>> {
+    fn fft_interpolate_by_ref(&self) -> DensePolynomial<F> {
+        self.clone().fft_interpolate()
+    }
+
+    #[cfg(feature = "parallel_fft")]
+    fn fft_interpolate(self) -> DensePolynomial<F> {
+        let domain = self.domain();
+        let mut values = self.evals;
+        ifft_in_place(domain, &mut values);
+        DensePolynomial::from_coefficients_vec(values)
+    }
+
+    #[cfg(not(feature = "parallel_fft"))]
+    fn fft_interpolate(self) -> DensePolynomial<F> {
+        self.interpolate()
+    }
+}
@@ -0,0 +1,141 @@
+//! Loader of known-answer test (KAT) files of hash functions, such as the
+//! `ShortMsgKAT_*.txt` files of the Keccak reference implementation or the
+//! `*.rsp` response files of the NIST CAVP for SHA-3.
+//!
+//! Both formats consist of blank-line separated records of the form
+//!
+//! ```text
+//! Len = 16
+//! Msg = 41FB
+//! MD = A8EACEDA4D47B3281A795AD9E1EA2122B407BAF9AABCB9E18B5717B7873537D2
+//! ```
+//!
+//! where `Len` is the length of the message in bits, interleaved with `#`
+//! comments and `[L = 256]` section headers giving the output length.
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Keccak-256 vectors in the format of the Keccak reference implementation
+pub const KECCAK_256_SHORT_MSG: &str = include_str!("../resources/kat/ShortMsgKAT_256.txt");
+
+/// SHA3-256 vectors in the format of the NIST CAVP response files
+pub const SHA3_256_SHORT_MSG: &str = include_str!("../resources/kat/SHA3_256ShortMsg.rsp");
+
+/// KAT loader error
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KatError {
+    /// A line that is neither a comment, a header nor a `key = value` pair
+    #[error("malformed line {0}")]
+    MalformedLine(usize),
+    /// A record without one of the `Len`, `Msg` or `MD` fields
+    #[error("missing field {1} in the record ending at line {0}")]
+    MissingField(usize, &'static str),
+    /// A field that could not be decoded
+    #[error("invalid value of field {1} at line {0}")]
+    InvalidField(usize, &'static str),
+    /// A message that is shorter than its declared length
+    #[error("message shorter than its length at line {0}")]
+    ShortMessage(usize),
+}
+
+/// Result alias using [KatError]
+pub type Result<T> = std::result::Result<T, KatError>;
+
+/// A known-answer test vector
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KatVector {
+    /// Length of the message in bits
+    pub len: usize,
+    /// Message, padded with zero bits to a whole number of bytes
+    pub msg: Vec<u8>,
+    /// Expected digest
+    pub md: Vec<u8>,
+}
+
+impl KatVector {
+    /// Returns the bytes of the message, or `None` if its length is not a
+    /// multiple of 8 bits
+    pub fn message(&self) -> Option<&[u8]> {
+        if self.len % 8 == 0 {
+            Some(&self.msg[..self.len / 8])
+        } else {
+            None
+        }
+    }
+}
+
+/// The vectors of a KAT file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KatFile {
+    /// Output length in bits declared by the last `[L = ..]` header, if any
+    pub output_bits: Option<usize>,
+    /// Vectors in the order of the file
+    pub vectors: Vec<KatVector>,
+}
+
+impl KatFile {
+    /// Parses the content of a KAT file
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut file = KatFile::default();
+        let mut record = HashMap::new();
+        // a trailing empty line terminates the last record
+        for (i, line) in content.lines().map(str::trim).chain([""]).enumerate() {
+            let line_number = i + 1;
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                if !record.is_empty() {
+                    file.vectors.push(Self::vector(line_number, &record)?);
+                    record.clear();
+                }
+                continue;
+            }
+            let (key, value) = line
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split_once('=')
+                .ok_or(KatError::MalformedLine(line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+            if line.starts_with('[') {
+                if key == "L" {
+                    file.output_bits = Some(
+                        value
+                            .parse()
+                            .map_err(|_| KatError::InvalidField(line_number, "L"))?,
+                    );
+                }
+                continue;
+            }
+            record.insert(key.to_string(), (line_number, value.to_string()));
+        }
+        Ok(file)
+    }
+
+    /// Returns the vectors whose message is a whole number of bytes, which are
+    /// the only ones a bytewise implementation can be tested against
+    pub fn byte_vectors(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.vectors
+            .iter()
+            .filter_map(|vector| Some((vector.message()?, vector.md.as_slice())))
+    }
+
+    fn vector(end: usize, record: &HashMap<String, (usize, String)>) -> Result<KatVector> {
+        let field = |name: &'static str| record.get(name).ok_or(KatError::MissingField(end, name));
+
+        let (line, len) = field("Len")?;
+        let len: usize = len
+            .parse()
+            .map_err(|_| KatError::InvalidField(*line, "Len"))?;
+        let (line, msg) = field("Msg")?;
+        let msg = hex::decode(msg).map_err(|_| KatError::InvalidField(*line, "Msg"))?;
+        if msg.len() * 8 < len {
+            return Err(KatError::ShortMessage(*line));
+        }
+        let (line, md) = field("MD")?;
+        let md = hex::decode(md).map_err(|_| KatError::InvalidField(*line, "MD"))?;
+
+        Ok(KatVector { len, msg, md })
+    }
+}
@@ -5,15 +5,19 @@ pub mod adjacent_pairs;
 pub mod array;
 pub mod biguint_helpers;
 pub mod bitwise_operations;
+pub mod cancellation;
 pub mod chunked_evaluations;
 pub mod chunked_polynomial;
 pub mod dense_polynomial;
 pub mod evaluations;
+pub mod fft;
 pub mod field_helpers;
 pub mod foreign_field;
 pub mod hasher;
+pub mod kat;
 pub mod math;
 pub mod serialization;
+pub mod transpose;
 
 pub use biguint_helpers::BigUintHelpers;
 pub use bitwise_operations::BitwiseOps;
@@ -0,0 +1,59 @@
+//! This module hosts helpers to transpose a witness produced row by row into
+//! the columns expected by the provers.
+//!
+//! The transposition is cache-blocked: the columns are split in blocks of
+//! [BLOCK_SIZE] columns, processed in parallel, and each block copies the rows
+//! [BLOCK_SIZE] at a time. The inner loops copy between contiguous slices, so
+//! that the compiler can vectorize them.
+//!
+//! ```
+//! use o1_utils::transpose::transpose;
+//!
+//! let rows = vec![[1, 2, 3], [4, 5, 6]];
+//! let columns = transpose(&rows);
+//!
+//! assert_eq!(columns, [vec![1, 4], vec![2, 5], vec![3, 6]]);
+//! ```
+
+use rayon::prelude::*;
+use std::array;
+
+/// The number of rows and columns processed together by the transposition
+pub const BLOCK_SIZE: usize = 64;
+
+/// Appends the rows to the first `N` columns.
+///
+/// # Panics
+///
+/// Will panic if there are less than `N` columns.
+pub fn append_transposed<T: Copy + Send + Sync, const N: usize>(
+    columns: &mut [Vec<T>],
+    rows: &[[T; N]],
+) {
+    assert!(
+        columns.len() >= N,
+        "append_transposed: expected at least {N} columns, got {}",
+        columns.len()
+    );
+    columns[..N]
+        .par_chunks_mut(BLOCK_SIZE)
+        .enumerate()
+        .for_each(|(block, columns)| {
+            let first = block * BLOCK_SIZE;
+            columns
+                .iter_mut()
+                .for_each(|column| column.reserve(rows.len()));
+            for rows in rows.chunks(BLOCK_SIZE) {
+                for (i, column) in columns.iter_mut().enumerate() {
+                    column.extend(rows.iter().map(|row| row[first + i]));
+                }
+            }
+        });
+}
+
+/// Transposes rows of `N` values into `N` columns
+pub fn transpose<T: Copy + Send + Sync, const N: usize>(rows: &[[T; N]]) -> [Vec<T>; N] {
+    let mut columns = array::from_fn(|_| Vec::with_capacity(rows.len()));
+    append_transposed(&mut columns, rows);
+    columns
+}
@@ -0,0 +1,16 @@
+use o1_utils::cancellation::{CancellationToken, Cancelled};
+
+#[test]
+fn test_cancellation_token() {
+    let token = CancellationToken::new();
+    let clone = token.clone();
+    assert!(!clone.is_cancelled());
+    assert_eq!(clone.check(), Ok(()));
+
+    token.cancel();
+    assert!(clone.is_cancelled());
+    assert_eq!(clone.check(), Err(Cancelled));
+
+    // new tokens are independent
+    assert_eq!(CancellationToken::new().check(), Ok(()));
+}
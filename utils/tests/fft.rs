@@ -0,0 +1,54 @@
+use ark_ff::UniformRand;
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, Evaluations, Radix2EvaluationDomain as D,
+    UVPolynomial,
+};
+use mina_curves::pasta::Fp;
+use o1_utils::fft::{
+    coset_fft_in_place, coset_ifft_in_place, fft_in_place, ifft_in_place, FftEvaluate,
+    FftInterpolate, MIN_PARALLEL_LOG_SIZE,
+};
+
+#[test]
+fn test_fft_matches_arkworks() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    // the serial transforms, and the parallel ones with an odd and an even
+    // number of stages
+    for log_size in (0..6).chain(MIN_PARALLEL_LOG_SIZE..MIN_PARALLEL_LOG_SIZE + 3) {
+        let domain = D::<Fp>::new(1 << log_size).unwrap();
+        let coeffs: Vec<Fp> = (0..domain.size()).map(|_| Fp::rand(&mut rng)).collect();
+
+        let mut values = coeffs.clone();
+        fft_in_place(domain, &mut values);
+        assert_eq!(values, domain.fft(&coeffs), "fft of size 2^{log_size}");
+        ifft_in_place(domain, &mut values);
+        assert_eq!(values, coeffs, "ifft of size 2^{log_size}");
+
+        let mut values = coeffs.clone();
+        coset_fft_in_place(domain, &mut values);
+        assert_eq!(
+            values,
+            domain.coset_fft(&coeffs),
+            "coset fft of 2^{log_size}"
+        );
+        coset_ifft_in_place(domain, &mut values);
+        assert_eq!(values, coeffs, "coset ifft of size 2^{log_size}");
+    }
+}
+
+#[test]
+fn test_fft_pads_coefficients() {
+    let mut rng = o1_utils::tests::make_test_rng(None);
+    let domain = D::<Fp>::new(1 << (MIN_PARALLEL_LOG_SIZE + 1)).unwrap();
+    let poly = DensePolynomial::<Fp>::rand(100, &mut rng);
+
+    let evals = poly.fft_evaluate_over_domain_by_ref(domain);
+    assert_eq!(evals, poly.evaluate_over_domain_by_ref(domain));
+    assert_eq!(evals.fft_interpolate_by_ref(), poly);
+    assert_eq!(evals.fft_interpolate(), poly);
+
+    // the zero polynomial has no coefficients
+    let zero = DensePolynomial::<Fp>::from_coefficients_vec(vec![]);
+    let evals: Evaluations<Fp, D<Fp>> = zero.fft_evaluate_over_domain(domain);
+    assert_eq!(evals.evals, vec![Fp::from(0u64); domain.size()]);
+}
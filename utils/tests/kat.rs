@@ -0,0 +1,51 @@
+use o1_utils::kat::{KatError, KatFile, KECCAK_256_SHORT_MSG, SHA3_256_SHORT_MSG};
+
+#[test]
+fn test_kat_parse() {
+    let content = "# comment\n\n[L = 256]\n\nLen = 0\nMsg = 00\nMD = 0102\n\nLen = 5\nMsg = 48\nMD = 03\n\nLen = 16\nMsg = 41fb\nMD = 04";
+    let file = KatFile::parse(content).unwrap();
+    assert_eq!(file.output_bits, Some(256));
+    assert_eq!(file.vectors.len(), 3);
+    assert_eq!(file.vectors[0].message(), Some(&[][..]));
+    assert_eq!(file.vectors[0].md, vec![1, 2]);
+    // messages that are not a whole number of bytes
+    assert_eq!(file.vectors[1].message(), None);
+    let vectors: Vec<_> = file.byte_vectors().collect();
+    assert_eq!(
+        vectors,
+        vec![(&[][..], &[1, 2][..]), (&[0x41, 0xfb][..], &[4][..])]
+    );
+}
+
+#[test]
+fn test_kat_parse_errors() {
+    assert_eq!(
+        KatFile::parse("Len = 8\nMsg"),
+        Err(KatError::MalformedLine(2))
+    );
+    assert_eq!(
+        KatFile::parse("Len = 8\nMsg = cc\n"),
+        Err(KatError::MissingField(3, "MD"))
+    );
+    assert_eq!(
+        KatFile::parse("Len = 8\nMsg = zz\nMD = 00"),
+        Err(KatError::InvalidField(2, "Msg"))
+    );
+    assert_eq!(
+        KatFile::parse("Len = 16\nMsg = cc\nMD = 00"),
+        Err(KatError::ShortMessage(2))
+    );
+}
+
+#[test]
+fn test_kat_bundled_files() {
+    for content in [KECCAK_256_SHORT_MSG, SHA3_256_SHORT_MSG] {
+        let file = KatFile::parse(content).unwrap();
+        assert!(!file.vectors.is_empty());
+        assert!(file.vectors.iter().all(|vector| vector.md.len() == 32));
+    }
+    assert_eq!(
+        KatFile::parse(SHA3_256_SHORT_MSG).unwrap().output_bits,
+        Some(256)
+    );
+}
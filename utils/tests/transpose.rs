@@ -0,0 +1,25 @@
+use o1_utils::transpose::{append_transposed, transpose, BLOCK_SIZE};
+use std::array;
+
+#[test]
+fn test_transpose() {
+    const N: usize = 2 * BLOCK_SIZE + 3;
+    let rows: Vec<[usize; N]> = (0..3 * BLOCK_SIZE + 5)
+        .map(|row| array::from_fn(|col| row * N + col))
+        .collect();
+    let columns = transpose(&rows);
+    for (col, column) in columns.iter().enumerate() {
+        assert_eq!(column.len(), rows.len());
+        for (row, value) in column.iter().enumerate() {
+            assert_eq!(*value, rows[row][col]);
+        }
+    }
+}
+
+#[test]
+fn test_append_transposed() {
+    // the columns can be longer than the rows, and already hold values
+    let mut columns = vec![vec![0], vec![1], vec![2]];
+    append_transposed(&mut columns, &[[3, 4], [5, 6]]);
+    assert_eq!(columns, vec![vec![0, 3, 5], vec![1, 4, 6], vec![2]]);
+}